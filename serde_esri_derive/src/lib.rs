@@ -0,0 +1,264 @@
+//! Derive macros for [`serde_esri`](https://docs.rs/serde_esri).
+//!
+//! Use them through the `derive` feature of `serde_esri` rather than depending on this crate directly.
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    meta::ParseNestedMeta, parse_macro_input, Data, DeriveInput, Error, Fields, GenericArgument,
+    Ident, LitStr, PathArguments, Result, Type,
+};
+
+/// Implements `serde_esri::features::EsriAttributes` for a struct with named fields
+///
+/// See the documentation of the trait for how the fields are described.
+#[proc_macro_derive(EsriAttributes, attributes(esri))]
+pub fn derive_esri_attributes(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "EsriAttributes can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "EsriAttributes can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut rename_all = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                let rule = meta.value()?.parse::<LitStr>()?;
+                rename_all = Some(RenameRule::from_lit(&rule)?);
+                Ok(())
+            } else {
+                skip_meta(meta)
+            }
+        })?;
+    }
+
+    let mut esri_fields = Vec::with_capacity(fields.len());
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let mut name = None;
+        let mut skip = false;
+        let mut field_type = None;
+        let mut alias = None;
+
+        for attr in &field.attrs {
+            if attr.path().is_ident("serde") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") && meta.input.peek(syn::Token![=]) {
+                        name = Some(meta.value()?.parse::<LitStr>()?.value());
+                        Ok(())
+                    } else if meta.path.is_ident("skip") {
+                        skip = true;
+                        Ok(())
+                    } else {
+                        skip_meta(meta)
+                    }
+                })?;
+            } else if attr.path().is_ident("esri") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("field_type") {
+                        let lit = meta.value()?.parse::<LitStr>()?;
+                        field_type =
+                            Some(field_type_variant(&lit.value()).ok_or_else(|| {
+                                Error::new_spanned(&lit, "unknown Esri field type")
+                            })?);
+                        Ok(())
+                    } else if meta.path.is_ident("alias") {
+                        alias = Some(meta.value()?.parse::<LitStr>()?.value());
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `field_type` or `alias`"))
+                    }
+                })?;
+            }
+        }
+
+        if skip {
+            continue;
+        }
+
+        let ident_name = ident.to_string();
+        let ident_name = ident_name.trim_start_matches("r#");
+        let name = name.unwrap_or_else(|| match &rename_all {
+            Some(rule) => rule.apply(ident_name),
+            None => ident_name.to_string(),
+        });
+
+        let field_type = match field_type.or_else(|| infer_field_type(&field.ty)) {
+            Some(x) => Ident::new(x, Span::call_site()),
+            None => {
+                return Err(Error::new_spanned(
+                    &field.ty,
+                    "cannot infer the Esri field type, add #[esri(field_type = \"...\")]",
+                ))
+            }
+        };
+
+        let alias = match alias {
+            Some(alias) => quote!(::core::option::Option::Some(#alias.to_string())),
+            None => quote!(::core::option::Option::None),
+        };
+
+        esri_fields.push(quote! {
+            ::serde_esri::features::Field {
+                name: #name.to_string(),
+                field_type: ::serde_esri::field_type::FieldType::#field_type,
+                alias: #alias,
+                ..::core::default::Default::default()
+            }
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::serde_esri::features::EsriAttributes for #ident #ty_generics #where_clause {
+            fn fields() -> ::std::vec::Vec<::serde_esri::features::Field> {
+                ::std::vec![#(#esri_fields),*]
+            }
+        }
+    })
+}
+
+// consumes the value of a serde attribute that is not used here
+fn skip_meta(meta: ParseNestedMeta) -> Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(skip_meta)?;
+    }
+    Ok(())
+}
+
+fn field_type_variant(x: &str) -> Option<&'static str> {
+    let variant = match x {
+        "esriFieldTypeSmallInteger" => "EsriFieldTypeSmallInteger",
+        "esriFieldTypeInteger" => "EsriFieldTypeInteger",
+        "esriFieldTypeSingle" => "EsriFieldTypeSingle",
+        "esriFieldTypeDouble" => "EsriFieldTypeDouble",
+        "esriFieldTypeString" => "EsriFieldTypeString",
+        "esriFieldTypeDate" => "EsriFieldTypeDate",
+        "esriFieldTypeOID" => "EsriFieldTypeOid",
+        "esriFieldTypeGeometry" => "EsriFieldTypeGeometry",
+        "esriFieldTypeBlob" => "EsriFieldTypeBlob",
+        "esriFieldTypeRaster" => "EsriFieldTypeRaster",
+        "esriFieldTypeGUID" => "EsriFieldTypeGuid",
+        "esriFieldTypeGlobalID" => "EsriFieldTypeGlobalId",
+        "esriFieldTypeXML" => "EsriFieldTypeXml",
+        "esriFieldTypeBigInteger" => "EsriFieldTypeBigInteger",
+        "esriFieldTypeDateOnly" => "EsriFieldTypeDateOnly",
+        "esriFieldTypeTimeOnly" => "EsriFieldTypeTimeOnly",
+        "esriFieldTypeTimestampOffset" => "EsriFieldTypeTimestampOffset",
+        _ => return None,
+    };
+    Some(variant)
+}
+
+fn infer_field_type(ty: &Type) -> Option<&'static str> {
+    let segment = match ty {
+        Type::Reference(x) => return infer_field_type(&x.elem),
+        Type::Path(x) => x.path.segments.last()?,
+        _ => return None,
+    };
+
+    let variant = match segment.ident.to_string().as_str() {
+        "Option" => {
+            let PathArguments::AngleBracketed(args) = &segment.arguments else {
+                return None;
+            };
+            return match args.args.first()? {
+                GenericArgument::Type(inner) => infer_field_type(inner),
+                _ => None,
+            };
+        }
+        "i8" | "i16" | "u8" | "bool" => "EsriFieldTypeSmallInteger",
+        "i32" | "u16" => "EsriFieldTypeInteger",
+        "f32" => "EsriFieldTypeSingle",
+        "f64" | "i64" | "u32" | "u64" | "isize" | "usize" => "EsriFieldTypeDouble",
+        "String" | "str" | "char" => "EsriFieldTypeString",
+        "EsriDate" | "DateTime" | "SystemTime" => "EsriFieldTypeDate",
+        _ => return None,
+    };
+    Some(variant)
+}
+
+// the `rename_all` rules of serde
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn from_lit(x: &LitStr) -> Result<Self> {
+        let rule = match x.value().as_str() {
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "PascalCase" => Self::Pascal,
+            "camelCase" => Self::Camel,
+            "snake_case" => Self::Snake,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            "kebab-case" => Self::Kebab,
+            "SCREAMING-KEBAB-CASE" => Self::ScreamingKebab,
+            _ => return Err(Error::new_spanned(x, "unknown rename rule")),
+        };
+        Ok(rule)
+    }
+
+    // field names are assumed to be snake_case as they are in serde
+    fn apply(&self, field: &str) -> String {
+        let pascal = || {
+            field
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                        .unwrap_or_default()
+                })
+                .collect::<String>()
+        };
+
+        match self {
+            Self::Lower | Self::Snake => field.to_string(),
+            Self::Upper | Self::ScreamingSnake => field.to_ascii_uppercase(),
+            Self::Pascal => pascal(),
+            Self::Camel => {
+                let pascal = pascal();
+                let mut chars = pascal.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_lowercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+            Self::Kebab => field.replace('_', "-"),
+            Self::ScreamingKebab => field.replace('_', "-").to_ascii_uppercase(),
+        }
+    }
+}
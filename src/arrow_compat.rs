@@ -1,10 +1,18 @@
 //! Compatibility with arrow-rs and geoarrow
 //!
-//! This module is enabled by the `geoarrow` feature. It provides a single function
-//! `featureset_to_arrow()` which returns a `RecordBatch` containing arrays for each
+//! This module is enabled by the `geoarrow` feature. The function
+//! `featureset_to_arrow()` returns a `RecordBatch` containing arrays for each
 //! field in the original `FeatureSet` struct and an additional field for geometry
 //! if present.
 //!
+//! `arrow_to_featureset()` goes the other way. It creates a `FeatureSet` from a
+//! `RecordBatch` whose geometry column is WKB or a geoarrow native encoding so that
+//! it can be sent to `applyEdits` or `addFeatures`.
+//!
+//! [`IpcWriter`] writes features to an Arrow IPC file (Feather v2) in batches
+//! of a given size so that large layers can be exported without holding every
+//! feature in memory.
+//!
 //! This feature implements the following geoarrow traits:
 //!
 //! - `EsriCoord<N>` implements `CoordTrait` and `PointTrait`
@@ -13,24 +21,31 @@
 //! - `EsriPolyline<N>` implements `MultiLineStringTrait`
 //! - `EsriPolygon<N>` implements `PolygonTrait`
 use crate::{
-    features::{Feature, FeatureSet, Field},
+    features::{infer_fields, Feature, FeatureSet, Field},
     field_type::FieldType,
-    geometry::EsriGeometry,
+    geometry::{dimension_flags, EsriGeometry},
+    wkb::{from_wkb, WkbError},
 };
 
-use std::{result::Result, sync::Arc};
+use std::{io::Write, result::Result, sync::Arc};
 
-use geoarrow::GeometryArrayTrait;
-use serde_json::Value;
+use geoarrow::{datatypes::GeoDataType, error::GeoArrowError, GeometryArrayTrait};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 
 use arrow::{
     array::{
-        make_builder, Array, ArrayBuilder, BooleanBuilder, Date32Builder, Date64Builder,
+        make_builder, Array, ArrayBuilder, AsArray, BooleanBuilder, Date32Builder, Date64Builder,
         Float32Builder, Float64Builder, Int16Builder, Int32Builder, Int64Builder, Int8Builder,
         NullBuilder, StringBuilder, UInt16Builder, UInt32Builder, UInt64Builder, UInt8Builder,
     },
-    datatypes::{DataType, Field as AField, Schema, SchemaBuilder},
+    compute::cast,
+    datatypes::{
+        DataType, Field as AField, Float64Type, Int64Type, Schema, SchemaBuilder, TimeUnit,
+        TimestampMillisecondType, UInt64Type,
+    },
+    error::ArrowError,
+    ipc::writer::FileWriter,
     record_batch::RecordBatch,
 };
 
@@ -51,9 +66,9 @@ pub fn featureset_to_arrow<const N: usize>(
         })
         .collect::<Vec<_>>();
 
-    if x.geometryType.is_some() {
+    if let Some(geometry_type) = x.geometryType {
         // process geometries
-        let (geo_field, geo_arr) = as_geoarrow_array(x.geometryType.unwrap().as_str(), geometries);
+        let (geo_field, geo_arr) = as_geoarrow_array(geometry_type.as_str(), geometries);
 
         // create a new schema builder
         let mut sb = SchemaBuilder::from(schema);
@@ -85,6 +100,417 @@ pub fn featureset_to_geoarrow<const N: usize>(
     GeoTable::try_new(schema_ref, vec![arrow_res], geometry_index)
 }
 
+/// Writes features to an Arrow IPC file (Feather v2) in batches
+///
+/// Features are buffered until `batch_size` of them have been passed to
+/// [`IpcWriter::write_feature()`] and are then written as a single `RecordBatch`,
+/// so that only one batch is held in memory at once. Combined with
+/// [`FeatureIter`](crate::features::FeatureIter), a layer can be exported without
+/// reading all of its features. The file is incomplete until [`IpcWriter::finish()`]
+/// is called.
+///
+/// ```
+/// use arrow::ipc::reader::FileReader;
+/// use serde_esri::{arrow_compat::IpcWriter, features::FeatureIter};
+///
+/// let json = r#"{
+///     "geometryType": "esriGeometryPoint",
+///     "fields": [{"name": "id", "type": "esriFieldTypeInteger"}],
+///     "features": [
+///         {"attributes": {"id": 1}, "geometry": {"x": 1, "y": 2}},
+///         {"attributes": {"id": 2}, "geometry": {"x": 3, "y": 4}},
+///         {"attributes": {"id": 3}, "geometry": {"x": 5, "y": 6}}
+///     ]
+/// }"#;
+///
+/// let mut features = FeatureIter::<_, 2>::from_reader(json.as_bytes()).unwrap();
+/// let mut writer = IpcWriter::new(Vec::new(), features.envelope().unwrap(), 2).unwrap();
+/// for feature in features {
+///     writer.write_feature(feature.unwrap()).unwrap();
+/// }
+/// let bytes = writer.finish().unwrap();
+///
+/// let reader = FileReader::try_new(std::io::Cursor::new(bytes), None).unwrap();
+/// let rows: Vec<usize> = reader.map(|batch| batch.unwrap().num_rows()).collect();
+/// assert_eq!(rows, [2, 1]);
+/// ```
+pub struct IpcWriter<W: Write, const N: usize> {
+    writer: FileWriter<W>,
+    // the envelope of the `FeatureSet` with the features of the current batch
+    batch: FeatureSet<N>,
+    batch_size: usize,
+    written: usize,
+}
+
+impl<W: Write, const N: usize> IpcWriter<W, N> {
+    /// Write the schema of `fset` to `writer`, followed by its features
+    ///
+    /// The schema is based on the `fields` and `geometryType` of `fset`. If it has
+    /// no `fields`, they are inferred from the attributes of its features.
+    pub fn new(writer: W, mut fset: FeatureSet<N>, batch_size: usize) -> Result<Self, ArrowError> {
+        let features = std::mem::take(&mut fset.features);
+        if fset.fields.is_none() {
+            fset.fields = Some(infer_fields(&features));
+        }
+
+        let schema = featureset_to_arrow(fset.clone())?.schema();
+        let mut res = Self {
+            writer: FileWriter::try_new(writer, &schema)?,
+            batch: fset,
+            batch_size: batch_size.max(1),
+            written: 0,
+        };
+        for feature in features {
+            res.write_feature(feature)?;
+        }
+        Ok(res)
+    }
+
+    /// Write a single feature, writing a `RecordBatch` once the batch is full
+    pub fn write_feature(&mut self, feature: Feature<N>) -> Result<(), ArrowError> {
+        self.batch.features.push(feature);
+        self.written += 1;
+        if self.batch.features.len() >= self.batch_size {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    /// The number of features written so far
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Write the remaining features, finish the file, and return the underlying writer
+    pub fn finish(mut self) -> Result<W, ArrowError> {
+        self.write_batch()?;
+        self.writer.into_inner()
+    }
+
+    fn write_batch(&mut self) -> Result<(), ArrowError> {
+        if self.batch.features.is_empty() {
+            return Ok(());
+        }
+        let features = std::mem::take(&mut self.batch.features);
+        let batch = featureset_to_arrow(FeatureSet {
+            features,
+            ..self.batch.clone()
+        })?;
+        self.writer.write(&batch)
+    }
+}
+
+/// Errors that can occur when converting a `RecordBatch` into a `FeatureSet`
+#[derive(Debug)]
+pub enum ArrowConversionError {
+    /// A column is not in the schema of the `RecordBatch`
+    MissingColumn(String),
+    /// The column's data type has no corresponding Esri field type
+    UnsupportedType {
+        column: String,
+        data_type: DataType,
+    },
+    /// The column's data type cannot be stored in the field it is mapped to
+    TypeMismatch {
+        column: String,
+        field_type: FieldType,
+        data_type: DataType,
+    },
+    /// The geometry column is neither WKB nor a geoarrow native encoding
+    UnsupportedGeometry(String),
+    /// The geometry of a row could not be converted
+    InvalidGeometry {
+        row: usize,
+        error: WkbError,
+    },
+    ArrowError(ArrowError),
+    GeoArrowError(GeoArrowError),
+}
+
+impl std::fmt::Display for ArrowConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingColumn(column) => write!(f, "column `{column}` does not exist"),
+            Self::UnsupportedType { column, data_type } => {
+                write!(f, "column `{column}` has unsupported type {data_type}")
+            }
+            Self::TypeMismatch {
+                column,
+                field_type,
+                data_type,
+            } => write!(
+                f,
+                "column `{column}` of type {data_type} cannot be stored in a {field_type} field"
+            ),
+            Self::UnsupportedGeometry(column) => {
+                write!(f, "column `{column}` is not a supported geometry encoding")
+            }
+            Self::InvalidGeometry { row, error } => {
+                write!(f, "invalid geometry in row {row}: {error}")
+            }
+            Self::ArrowError(e) => write!(f, "{e}"),
+            Self::GeoArrowError(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ArrowConversionError {}
+
+/// Maps a column of a `RecordBatch` to a field of a `FeatureSet`
+#[derive(Debug, Clone)]
+pub struct FieldMapping {
+    /// The name of the column
+    pub column: String,
+    /// The field the column's values are stored in
+    pub field: Field,
+}
+
+impl FieldMapping {
+    pub fn new(column: &str, field: Field) -> Self {
+        Self {
+            column: column.to_string(),
+            field,
+        }
+    }
+}
+
+/// Given a `RecordBatch`, create a `FeatureSet`
+///
+/// `geometry_column` is the name of a column containing WKB or a geoarrow native
+/// encoding. If `None`, the first column with geoarrow extension metadata is used, if any.
+///
+/// `fields` selects the columns to use as attributes and the fields they are stored in.
+/// If `None`, every other column is used with a field of the same name and a type based
+/// on its data type. Integers wider than 32 bits become `esriFieldTypeDouble` fields and
+/// booleans become `esriFieldTypeSmallInteger` fields.
+pub fn arrow_to_featureset<const N: usize>(
+    batch: &RecordBatch,
+    geometry_column: Option<&str>,
+    fields: Option<&[FieldMapping]>,
+) -> Result<FeatureSet<N>, ArrowConversionError> {
+    let schema = batch.schema();
+
+    let geometry_column = match geometry_column {
+        Some(name) => Some(
+            schema
+                .index_of(name)
+                .map_err(|_| ArrowConversionError::MissingColumn(name.to_string()))?,
+        ),
+        None => schema
+            .fields()
+            .iter()
+            .position(|f| f.metadata().contains_key("ARROW:extension:name")),
+    };
+
+    let mappings = match fields {
+        Some(fields) => fields.to_vec(),
+        None => schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != geometry_column)
+            .map(|(_, f)| {
+                let field_type = infer_field_type(f.data_type()).ok_or_else(|| {
+                    ArrowConversionError::UnsupportedType {
+                        column: f.name().to_string(),
+                        data_type: f.data_type().clone(),
+                    }
+                })?;
+                let field = Field {
+                    name: f.name().to_string(),
+                    field_type,
+                    ..Default::default()
+                };
+                Ok(FieldMapping::new(f.name(), field))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+
+    // the values of each column in the order of the mappings
+    let columns = mappings
+        .iter()
+        .map(|m| {
+            let array = batch
+                .column_by_name(&m.column)
+                .ok_or_else(|| ArrowConversionError::MissingColumn(m.column.clone()))?;
+
+            if !is_compatible(&m.field.field_type, array.data_type()) {
+                return Err(ArrowConversionError::TypeMismatch {
+                    column: m.column.clone(),
+                    field_type: m.field.field_type.clone(),
+                    data_type: array.data_type().clone(),
+                });
+            }
+
+            column_values(array.as_ref()).map_err(ArrowConversionError::ArrowError)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut geometries = match geometry_column {
+        Some(i) => geometry_values::<N>(batch.column(i).as_ref(), schema.field(i))?,
+        None => vec![None; batch.num_rows()],
+    }
+    .into_iter();
+
+    let features = (0..batch.num_rows())
+        .map(|row| {
+            let attributes = mappings
+                .iter()
+                .zip(&columns)
+                .map(|(m, values)| (m.field.name.clone(), values[row].clone()))
+                .collect::<Map<_, _>>();
+
+            Feature {
+                geometry: geometries.next().flatten(),
+                attributes: Some(attributes),
+                #[cfg(feature = "preserve-unknown")]
+                extra: Default::default(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let geometry_type = features
+        .iter()
+        .find_map(|f| f.geometry.as_ref())
+        .map(|g| g.geometry_type().to_string());
+
+    let (has_z, has_m) = dimension_flags::<N>();
+    Ok(FeatureSet {
+        geometryType: geometry_type,
+        hasZ: Some(has_z),
+        hasM: Some(has_m),
+        fields: Some(mappings.into_iter().map(|m| m.field).collect()),
+        features,
+        ..Default::default()
+    })
+}
+
+fn infer_field_type(data_type: &DataType) -> Option<FieldType> {
+    let field_type = match data_type {
+        DataType::Boolean | DataType::Int8 | DataType::Int16 | DataType::UInt8 => {
+            FieldType::EsriFieldTypeSmallInteger
+        }
+        DataType::Int32 | DataType::UInt16 => FieldType::EsriFieldTypeInteger,
+        DataType::Int64 | DataType::UInt32 | DataType::UInt64 => FieldType::EsriFieldTypeDouble,
+        DataType::Float32 => FieldType::EsriFieldTypeSingle,
+        DataType::Float64 => FieldType::EsriFieldTypeDouble,
+        DataType::Null | DataType::Utf8 | DataType::LargeUtf8 => FieldType::EsriFieldTypeString,
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) => {
+            FieldType::EsriFieldTypeDate
+        }
+        _ => return None,
+    };
+    Some(field_type)
+}
+
+fn is_compatible(field_type: &FieldType, data_type: &DataType) -> bool {
+    let is_int = data_type.is_integer() || *data_type == DataType::Boolean;
+    let is_date = matches!(
+        data_type,
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) | DataType::Int64
+    );
+    let is_string = matches!(data_type, DataType::Utf8 | DataType::LargeUtf8);
+
+    *data_type == DataType::Null
+        || match field_type {
+            FieldType::EsriFieldTypeSmallInteger
+            | FieldType::EsriFieldTypeInteger
+            | FieldType::EsriFieldTypeBigInteger
+            | FieldType::EsriFieldTypeOid => is_int,
+            FieldType::EsriFieldTypeSingle | FieldType::EsriFieldTypeDouble => {
+                is_int || data_type.is_floating()
+            }
+            FieldType::EsriFieldTypeDate => is_date,
+            FieldType::EsriFieldTypeString
+            | FieldType::EsriFieldTypeGuid
+            | FieldType::EsriFieldTypeGlobalId
+            | FieldType::EsriFieldTypeXml
+            | FieldType::EsriFieldTypeDateOnly
+            | FieldType::EsriFieldTypeTimeOnly
+            | FieldType::EsriFieldTypeTimestampOffset => is_string,
+            FieldType::EsriFieldTypeBlob
+            | FieldType::EsriFieldTypeRaster
+            | FieldType::EsriFieldTypeGeometry => false,
+        }
+}
+
+// the values of a column as Esri JSON attribute values.
+// Dates are milliseconds since the epoch.
+fn column_values(array: &dyn Array) -> Result<Vec<Value>, ArrowError> {
+    fn to_values<T: Into<Value>>(x: impl Iterator<Item = Option<T>>) -> Vec<Value> {
+        x.map(|v| v.map_or(Value::Null, Into::into)).collect()
+    }
+
+    let values = match array.data_type() {
+        DataType::Null => vec![Value::Null; array.len()],
+        DataType::Boolean => to_values(array.as_boolean().iter().map(|v| v.map(i64::from))),
+        DataType::UInt64 => to_values(array.as_primitive::<UInt64Type>().iter()),
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => to_values(
+            cast(array, &DataType::Float64)?
+                .as_primitive::<Float64Type>()
+                .iter(),
+        ),
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) => {
+            let ms = DataType::Timestamp(TimeUnit::Millisecond, None);
+            to_values(
+                cast(array, &ms)?
+                    .as_primitive::<TimestampMillisecondType>()
+                    .iter(),
+            )
+        }
+        DataType::Utf8 | DataType::LargeUtf8 => {
+            to_values(cast(array, &DataType::Utf8)?.as_string::<i32>().iter())
+        }
+        x if x.is_integer() => to_values(
+            cast(array, &DataType::Int64)?
+                .as_primitive::<Int64Type>()
+                .iter(),
+        ),
+        x => {
+            return Err(ArrowError::CastError(format!(
+                "cannot convert {x} to an attribute value"
+            )))
+        }
+    };
+    Ok(values)
+}
+
+// the geometry of each row of a WKB or geoarrow native array
+fn geometry_values<const N: usize>(
+    array: &dyn Array,
+    field: &AField,
+) -> Result<Vec<Option<EsriGeometry<N>>>, ArrowConversionError> {
+    let unsupported = || ArrowConversionError::UnsupportedGeometry(field.name().to_string());
+
+    let wkb = match field.data_type() {
+        DataType::Binary | DataType::LargeBinary => {
+            cast(array, &DataType::LargeBinary).map_err(ArrowConversionError::ArrowError)?
+        }
+        _ => {
+            // validates the metadata as `from_arrow_array()` panics on unexpected types
+            match GeoDataType::try_from(field).map_err(|_| unsupported())? {
+                GeoDataType::WKB | GeoDataType::LargeWKB | GeoDataType::Rect => {
+                    return Err(unsupported())
+                }
+                _ => {}
+            }
+            let native = geoarrow::array::from_arrow_array(array, field)
+                .map_err(ArrowConversionError::GeoArrowError)?;
+            Arc::new(geoarrow::io::wkb::to_wkb::<i64>(native.as_ref()).into_inner())
+        }
+    };
+
+    wkb.as_binary::<i64>()
+        .iter()
+        .enumerate()
+        .map(|(row, x)| {
+            x.map(from_wkb::<N>)
+                .transpose()
+                .map_err(|error| ArrowConversionError::InvalidGeometry { row, error })
+        })
+        .collect()
+}
+
 // convert an esri field to a new arrow field
 impl From<Field> for AField {
     fn from(value: Field) -> Self {
@@ -100,6 +526,11 @@ impl From<Field> for AField {
             FieldType::EsriFieldTypeGuid => DataType::Utf8,
             FieldType::EsriFieldTypeGlobalId => DataType::Utf8,
             FieldType::EsriFieldTypeXml => DataType::LargeUtf8,
+            FieldType::EsriFieldTypeBigInteger => DataType::Int64,
+            // sent as ISO 8601 strings
+            FieldType::EsriFieldTypeDateOnly
+            | FieldType::EsriFieldTypeTimeOnly
+            | FieldType::EsriFieldTypeTimestampOffset => DataType::Utf8,
             FieldType::EsriFieldTypeRaster => unimplemented!(),
             FieldType::EsriFieldTypeGeometry => unimplemented!(),
         };
@@ -125,23 +556,22 @@ fn field_to_schema(fields: Vec<Field>) -> Schema {
 // the first element is a hashmap containing the field name as keys
 // and an array builder as the value
 // the second element is a vectoor of geometry options
+type FieldBuilders<'a> = HashMap<&'a String, (&'a AField, Box<dyn ArrayBuilder>)>;
+
 fn create_array_vecs<const N: usize>(
     //fields: &Fields,
     schema: &Schema,
     feats: Vec<Feature<N>>,
-) -> (
-    HashMap<&String, (&AField, Box<dyn ArrayBuilder>)>,
-    Vec<Option<EsriGeometry<N>>>,
-) {
+) -> (FieldBuilders<'_>, Vec<Option<EsriGeometry<N>>>) {
     let n = feats.len();
 
-    let mut map: HashMap<&String, (&AField, Box<dyn ArrayBuilder>)> = HashMap::new();
+    let mut map: FieldBuilders = HashMap::new();
 
     let mut geometries = Vec::with_capacity(n);
 
     schema.fields.iter().for_each(|f| {
         let b = make_builder(f.data_type(), n);
-        map.insert(f.name(), (&f, b));
+        map.insert(f.name(), (f, b));
     });
 
     feats.into_iter().for_each(|m| {
@@ -217,7 +647,7 @@ fn as_geoarrow_array<const N: usize>(
 
 // take a field and a builder
 // then match on the field to use downcast mut
-fn append_value(v: Value, f: &AField, builder: &mut Box<dyn ArrayBuilder>) -> () {
+fn append_value(v: Value, f: &AField, builder: &mut Box<dyn ArrayBuilder>) {
     let bb = builder.as_any_mut();
     match f.data_type() {
         DataType::Null => {
@@ -355,3 +785,49 @@ fn append_value(v: Value, f: &AField, builder: &mut Box<dyn ArrayBuilder>) -> ()
         _ => todo!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arrow_to_featureset() {
+        let json = r#"{
+            "geometryType": "esriGeometryPolygon",
+            "fields": [
+                {"name": "OBJECTID", "type": "esriFieldTypeOID"},
+                {"name": "name", "type": "esriFieldTypeString"}
+            ],
+            "features": [{
+                "attributes": {"OBJECTID": 1, "name": "square"},
+                "geometry": {"rings": [[[0, 0], [0, 10], [10, 10], [10, 0], [0, 0]]]}
+            }]
+        }"#;
+        let fset: FeatureSet<2> = serde_json::from_str(json).unwrap();
+        let batch = featureset_to_arrow(fset).unwrap();
+
+        let fset = arrow_to_featureset::<2>(&batch, None, None).unwrap();
+        assert_eq!(fset.geometryType.as_deref(), Some("esriGeometryPolygon"));
+        let feature = &fset.features[0];
+        assert_eq!(feature.attributes.as_ref().unwrap()["name"], "square");
+        let polygon = feature.geometry.clone().unwrap().as_polygon().unwrap();
+        assert_eq!(polygon.rings[0].0.len(), 5);
+
+        let mapping = [FieldMapping::new(
+            "name",
+            Field {
+                name: "NAME".to_string(),
+                field_type: FieldType::EsriFieldTypeInteger,
+                ..Default::default()
+            },
+        )];
+        assert!(matches!(
+            arrow_to_featureset::<2>(&batch, None, Some(&mapping)),
+            Err(ArrowConversionError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            arrow_to_featureset::<2>(&batch, Some("shape"), None),
+            Err(ArrowConversionError::MissingColumn(_))
+        ));
+    }
+}
@@ -0,0 +1,428 @@
+//! Typed attribute values.
+//!
+//! Attributes are a JSON object by default, so an integer field can hold a float
+//! and a date field a string. [`AttributeValue`] gives each attribute a type, and an
+//! [`AttributeMap`] of them can be used as the attributes of a `Feature<N, AttributeMap>`.
+//!
+//! Deserialized directly, JSON integers become [`AttributeValue::Integer`], other
+//! numbers [`AttributeValue::Double`], and strings [`AttributeValue::String`].
+//! [`FeatureSet::into_typed()`] uses the `fields` of a `FeatureSet` instead, so
+//! that dates, GUIDs, and integers sent as floats are read as their field's type,
+//! and values that cannot be stored in their field are rejected.
+//!
+//! ```
+//! use serde_esri::features::FeatureSet;
+//!
+//! let fset: FeatureSet<2> = serde_json::from_str(r#"{
+//!     "fields": [
+//!         {"name": "count", "type": "esriFieldTypeInteger"},
+//!         {"name": "created", "type": "esriFieldTypeDate"}
+//!     ],
+//!     "features": [{"attributes": {"count": 3.0, "created": "1700000000000"}}]
+//! }"#).unwrap();
+//!
+//! let typed = fset.into_typed().unwrap();
+//! let feature = &typed.features[0];
+//! assert_eq!(feature.get_i64("count").unwrap(), Some(3));
+//! assert_eq!(feature.get_date("created").unwrap().unwrap().0, 1_700_000_000_000);
+//! assert!(feature.get_str("count").is_err());
+//! ```
+use crate::{
+    date::{AttributeError, EsriDate},
+    features::{Feature, FeatureSet, Field},
+    field_type::FieldType,
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Attributes keyed by field name
+pub type AttributeMap = BTreeMap<String, AttributeValue>;
+
+/// The value of an attribute
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum AttributeValue {
+    #[default]
+    Null,
+    String(String),
+    Integer(i64),
+    Double(f64),
+    Bool(bool),
+    /// Milliseconds since the Unix epoch
+    Date(EsriDate),
+    /// Base64 encoded bytes, as sent by a service
+    Blob(String),
+    Guid(String),
+}
+
+impl AttributeValue {
+    /// The value of an attribute stored in a field of `field_type`
+    ///
+    /// Integers sent as floats without a fractional part and numbers sent as
+    /// strings are converted. Dates are read with [`EsriDate::from_attribute()`].
+    /// Booleans are kept as they are. `name` is only used in the error.
+    pub fn from_json(
+        name: &str,
+        value: &Value,
+        field_type: &FieldType,
+    ) -> Result<Self, AttributeError> {
+        use FieldType::*;
+
+        let wrong_type = |expected| AttributeError::WrongType {
+            name: name.to_string(),
+            expected,
+            value: value.clone(),
+        };
+
+        let res = match (field_type, value) {
+            (_, Value::Null) => Self::Null,
+            (_, Value::Bool(b)) => Self::Bool(*b),
+            (
+                EsriFieldTypeSmallInteger
+                | EsriFieldTypeInteger
+                | EsriFieldTypeBigInteger
+                | EsriFieldTypeOid,
+                value,
+            ) => {
+                let integer = match value {
+                    Value::Number(n) => n.as_i64().or_else(|| {
+                        n.as_f64()
+                            .filter(|x| x.fract() == 0.0 && x.abs() < 9e15)
+                            .map(|x| x as i64)
+                    }),
+                    Value::String(s) => s.trim().parse().ok(),
+                    _ => None,
+                };
+                Self::Integer(integer.ok_or_else(|| wrong_type("an integer"))?)
+            }
+            (EsriFieldTypeSingle | EsriFieldTypeDouble, value) => {
+                let double = match value {
+                    Value::Number(n) => n.as_f64(),
+                    Value::String(s) => s.trim().parse().ok(),
+                    _ => None,
+                };
+                Self::Double(double.ok_or_else(|| wrong_type("a number"))?)
+            }
+            (EsriFieldTypeDate, value) => {
+                let date = EsriDate::from_attribute(value);
+                Self::Date(date.ok_or_else(|| AttributeError::NotADate {
+                    name: name.to_string(),
+                    value: value.clone(),
+                })?)
+            }
+            (EsriFieldTypeGuid | EsriFieldTypeGlobalId, Value::String(s)) => Self::Guid(s.clone()),
+            (EsriFieldTypeBlob, Value::String(s)) => Self::Blob(s.clone()),
+            (_, Value::String(s)) => Self::String(s.clone()),
+            (EsriFieldTypeString | EsriFieldTypeXml, _) => return Err(wrong_type("a string")),
+            (_, value) => Self::from_value(value).ok_or_else(|| wrong_type("a scalar"))?,
+        };
+
+        Ok(res)
+    }
+
+    // the value of a JSON scalar, without a field to describe it
+    fn from_value(value: &Value) -> Option<Self> {
+        let res = match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Bool(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => Self::Integer(i),
+                None => Self::Double(n.as_f64()?),
+            },
+            Value::String(s) => Self::String(s.clone()),
+            Value::Array(_) | Value::Object(_) => return None,
+        };
+        Some(res)
+    }
+
+    /// The value as JSON. Dates are milliseconds since the epoch.
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::Null => Value::Null,
+            Self::String(s) | Self::Blob(s) | Self::Guid(s) => Value::from(s.as_str()),
+            Self::Integer(i) => Value::from(*i),
+            Self::Double(x) => Value::from(*x),
+            Self::Bool(b) => Value::from(*b),
+            Self::Date(d) => Value::from(d.0),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// The text of a string, GUID, or blob
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) | Self::Guid(s) | Self::Blob(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The value of an integer or a double
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(i) => Some(*i as f64),
+            Self::Double(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_date(&self) -> Option<EsriDate> {
+        match self {
+            Self::Date(d) => Some(*d),
+            _ => None,
+        }
+    }
+}
+
+impl From<&AttributeValue> for Value {
+    fn from(value: &AttributeValue) -> Self {
+        value.to_json()
+    }
+}
+
+impl From<AttributeValue> for Value {
+    fn from(value: AttributeValue) -> Self {
+        match value {
+            AttributeValue::String(s) | AttributeValue::Blob(s) | AttributeValue::Guid(s) => {
+                Value::String(s)
+            }
+            value => value.to_json(),
+        }
+    }
+}
+
+impl Serialize for AttributeValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Null => serializer.serialize_none(),
+            Self::String(s) | Self::Blob(s) | Self::Guid(s) => serializer.serialize_str(s),
+            Self::Integer(i) => serializer.serialize_i64(*i),
+            Self::Double(x) => serializer.serialize_f64(*x),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Date(d) => serializer.serialize_i64(d.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AttributeValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Self::from_value(&value)
+            .ok_or_else(|| de::Error::custom("expected a null, boolean, number, or string"))
+    }
+}
+
+// the typed attributes of a map of JSON attributes, using the fields that describe them
+fn typed_attributes(
+    attributes: Map<String, Value>,
+    fields: &[Field],
+) -> Result<AttributeMap, AttributeError> {
+    attributes
+        .into_iter()
+        .map(|(name, value)| {
+            let typed = match fields.iter().find(|f| f.name == name) {
+                Some(field) => AttributeValue::from_json(&name, &value, &field.field_type)?,
+                None => {
+                    AttributeValue::from_value(&value).ok_or_else(|| AttributeError::WrongType {
+                        name: name.clone(),
+                        expected: "a scalar",
+                        value,
+                    })?
+                }
+            };
+            Ok((name, typed))
+        })
+        .collect()
+}
+
+impl<const N: usize> FeatureSet<N> {
+    /// Convert the attributes of every feature into [`AttributeValue`]s of the type of their field
+    ///
+    /// Attributes without a field are converted as if they were deserialized directly.
+    /// Fails on the first value that cannot be stored in its field.
+    pub fn into_typed(self) -> Result<FeatureSet<N, AttributeMap>, AttributeError> {
+        let fields = self.fields.as_deref().unwrap_or_default();
+        let features = self
+            .features
+            .into_iter()
+            .map(|f| {
+                Ok(Feature {
+                    geometry: f.geometry,
+                    attributes: f
+                        .attributes
+                        .map(|a| typed_attributes(a, fields))
+                        .transpose()?,
+                    #[cfg(feature = "preserve-unknown")]
+                    extra: f.extra,
+                })
+            })
+            .collect::<Result<Vec<_>, AttributeError>>()?;
+
+        Ok(FeatureSet {
+            objectIdFieldName: self.objectIdFieldName,
+            globalIdFieldName: self.globalIdFieldName,
+            displayFieldName: self.displayFieldName,
+            geometryType: self.geometryType,
+            spatialReference: self.spatialReference,
+            hasZ: self.hasZ,
+            hasM: self.hasM,
+            fields: self.fields,
+            features,
+            exceededTransferLimit: self.exceededTransferLimit,
+            #[cfg(feature = "preserve-unknown")]
+            extra: self.extra,
+        })
+    }
+}
+
+impl<const N: usize> FeatureSet<N, AttributeMap> {
+    /// Convert the attributes of every feature back into JSON
+    pub fn into_json(self) -> FeatureSet<N> {
+        let features = self
+            .features
+            .into_iter()
+            .map(|f| Feature {
+                geometry: f.geometry,
+                attributes: f
+                    .attributes
+                    .map(|a| a.into_iter().map(|(k, v)| (k, v.into())).collect()),
+                #[cfg(feature = "preserve-unknown")]
+                extra: f.extra,
+            })
+            .collect();
+
+        FeatureSet {
+            objectIdFieldName: self.objectIdFieldName,
+            globalIdFieldName: self.globalIdFieldName,
+            displayFieldName: self.displayFieldName,
+            geometryType: self.geometryType,
+            spatialReference: self.spatialReference,
+            hasZ: self.hasZ,
+            hasM: self.hasM,
+            fields: self.fields,
+            features,
+            exceededTransferLimit: self.exceededTransferLimit,
+            #[cfg(feature = "preserve-unknown")]
+            extra: self.extra,
+        }
+    }
+}
+
+impl<const N: usize> Feature<N, AttributeMap> {
+    /// The attribute `name`, or `None` if it is missing or null
+    pub fn get(&self, name: &str) -> Option<&AttributeValue> {
+        self.attributes.as_ref()?.get(name).filter(|v| !v.is_null())
+    }
+
+    // the attribute `name` read with `f`, failing when it has another type
+    fn get_as<'a, T>(
+        &'a self,
+        name: &str,
+        expected: &'static str,
+        f: impl FnOnce(&'a AttributeValue) -> Option<T>,
+    ) -> Result<Option<T>, AttributeError> {
+        match self.get(name) {
+            None => Ok(None),
+            Some(value) => f(value).map(Some).ok_or_else(|| AttributeError::WrongType {
+                name: name.to_string(),
+                expected,
+                value: value.to_json(),
+            }),
+        }
+    }
+
+    /// The attribute `name` as an integer, or `None` if it is missing or null
+    pub fn get_i64(&self, name: &str) -> Result<Option<i64>, AttributeError> {
+        self.get_as(name, "an integer", AttributeValue::as_i64)
+    }
+
+    /// The attribute `name` as a number, or `None` if it is missing or null
+    pub fn get_f64(&self, name: &str) -> Result<Option<f64>, AttributeError> {
+        self.get_as(name, "a number", AttributeValue::as_f64)
+    }
+
+    /// The attribute `name` as text, or `None` if it is missing or null
+    pub fn get_str(&self, name: &str) -> Result<Option<&str>, AttributeError> {
+        self.get_as(name, "a string", AttributeValue::as_str)
+    }
+
+    /// The attribute `name` as a boolean, or `None` if it is missing or null
+    pub fn get_bool(&self, name: &str) -> Result<Option<bool>, AttributeError> {
+        self.get_as(name, "a boolean", AttributeValue::as_bool)
+    }
+
+    /// The attribute `name` as a date, or `None` if it is missing or null
+    pub fn get_date(&self, name: &str) -> Result<Option<EsriDate>, AttributeError> {
+        match self.get(name) {
+            None => Ok(None),
+            Some(value) => value
+                .as_date()
+                .map(Some)
+                .ok_or_else(|| AttributeError::NotADate {
+                    name: name.to_string(),
+                    value: value.to_json(),
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_json() {
+        let int = FieldType::EsriFieldTypeInteger;
+        assert_eq!(
+            AttributeValue::from_json("a", &json!(2.0), &int).unwrap(),
+            AttributeValue::Integer(2)
+        );
+        assert_eq!(
+            AttributeValue::from_json("a", &json!("7"), &int).unwrap(),
+            AttributeValue::Integer(7)
+        );
+        assert!(matches!(
+            AttributeValue::from_json("a", &json!(2.5), &int),
+            Err(AttributeError::WrongType { .. })
+        ));
+        assert_eq!(
+            AttributeValue::from_json("a", &json!("{ABC}"), &FieldType::EsriFieldTypeGlobalId)
+                .unwrap(),
+            AttributeValue::Guid("{ABC}".into())
+        );
+        assert!(
+            AttributeValue::from_json("a", &json!(1), &FieldType::EsriFieldTypeString).is_err()
+        );
+
+        // deserialized without fields
+        let feature: Feature<2, AttributeMap> =
+            serde_json::from_value(json!({"attributes": {"a": 1, "b": 1.5, "c": "x", "d": null}}))
+                .unwrap();
+        assert_eq!(feature.get_i64("a").unwrap(), Some(1));
+        assert_eq!(feature.get_f64("a").unwrap(), Some(1.0));
+        assert_eq!(feature.get_f64("b").unwrap(), Some(1.5));
+        assert_eq!(feature.get_str("d").unwrap(), None);
+        assert!(feature.get_date("c").is_err());
+        assert_eq!(
+            serde_json::to_value(&feature).unwrap(),
+            json!({"attributes": {"a": 1, "b": 1.5, "c": "x", "d": null}})
+        );
+    }
+}
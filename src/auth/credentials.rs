@@ -0,0 +1,267 @@
+use crate::error::{ClassifyError, ErrorKind};
+use secrecy::SecretString;
+use std::path::{Path, PathBuf};
+
+/// The environment variable read by [`EnvCredentials::default()`]
+pub const DEFAULT_TOKEN_VAR: &str = "ARCGIS_TOKEN";
+
+/// The profile read by [`FileCredentials`] unless another is selected
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug)]
+pub enum CredentialError {
+    /// The environment variable is not set or is not valid unicode
+    MissingVar(String),
+    /// The credentials file could not be read
+    Io(std::io::Error),
+    /// The profile does not exist or has no `token`
+    MissingProfile(String),
+    /// The home directory, used to locate the default credentials file, is unknown
+    NoHomeDir,
+    #[cfg(feature = "keyring")]
+    Keyring(keyring::Error),
+    /// A token could not be requested
+    #[cfg(any(
+        feature = "places-client",
+        feature = "feature-service-client",
+        feature = "geometry-service-client",
+        feature = "geocode-client",
+        feature = "routing-client",
+        feature = "image-service-client",
+        feature = "tile-client"
+    ))]
+    Request(reqwest::Error),
+    /// The portal refused to issue a token
+    Api(crate::feature_service::ErrorResponse),
+}
+
+impl std::fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingVar(var) => write!(f, "environment variable `{var}` is not set"),
+            Self::Io(e) => write!(f, "failed to read credentials file: {e}"),
+            Self::MissingProfile(profile) => {
+                write!(
+                    f,
+                    "profile `{profile}` has no token in the credentials file"
+                )
+            }
+            Self::NoHomeDir => write!(f, "could not determine the home directory"),
+            #[cfg(feature = "keyring")]
+            Self::Keyring(e) => write!(f, "failed to read keyring: {e}"),
+            #[cfg(any(
+                feature = "places-client",
+                feature = "feature-service-client",
+                feature = "geometry-service-client",
+                feature = "geocode-client",
+                feature = "routing-client",
+                feature = "image-service-client",
+                feature = "tile-client"
+            ))]
+            Self::Request(e) => write!(f, "token request failed: {e}"),
+            Self::Api(e) => write!(f, "{} ({})", e.error.message, e.error.code),
+        }
+    }
+}
+
+impl std::error::Error for CredentialError {}
+
+impl ClassifyError for CredentialError {
+    fn kind(&self) -> ErrorKind {
+        #[cfg(any(
+            feature = "places-client",
+            feature = "feature-service-client",
+            feature = "geometry-service-client",
+            feature = "geocode-client",
+            feature = "routing-client",
+            feature = "image-service-client",
+            feature = "tile-client"
+        ))]
+        if let Self::Request(e) = self {
+            return ErrorKind::from_request_error(e);
+        }
+        ErrorKind::Auth
+    }
+}
+
+/// A source of the token used to authorize requests
+pub trait CredentialProvider {
+    fn token(&self) -> Result<SecretString, CredentialError>;
+
+    /// Discard the cached token, if any, after a server rejected it, so that the next
+    /// call of [`CredentialProvider::token()`] returns a new one
+    fn invalidate(&self) {}
+}
+
+/// A token provided directly
+impl CredentialProvider for SecretString {
+    fn token(&self) -> Result<SecretString, CredentialError> {
+        Ok(self.clone())
+    }
+}
+
+/// Reads a token from an environment variable
+#[derive(Debug, Clone)]
+pub struct EnvCredentials {
+    pub var: String,
+}
+
+impl EnvCredentials {
+    pub fn new(var: &str) -> Self {
+        Self {
+            var: var.to_string(),
+        }
+    }
+}
+
+impl Default for EnvCredentials {
+    fn default() -> Self {
+        Self::new(DEFAULT_TOKEN_VAR)
+    }
+}
+
+impl CredentialProvider for EnvCredentials {
+    fn token(&self) -> Result<SecretString, CredentialError> {
+        std::env::var(&self.var)
+            .map(SecretString::from)
+            .map_err(|_| CredentialError::MissingVar(self.var.clone()))
+    }
+}
+
+/// Reads a token from a credentials file
+///
+/// The file is made of `[profile]` sections containing `key = value` pairs.
+/// Lines starting with `#` or `;` are ignored.
+///
+/// ```text
+/// [default]
+/// token = your token
+///
+/// [enterprise]
+/// token = another token
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileCredentials {
+    pub path: PathBuf,
+    pub profile: String,
+}
+
+impl FileCredentials {
+    /// Read the `default` profile of the file at `path`
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            profile: DEFAULT_PROFILE.to_string(),
+        }
+    }
+
+    /// Read the `default` profile of `~/.arcgis/credentials`
+    pub fn from_home() -> Result<Self, CredentialError> {
+        let home = std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .ok_or(CredentialError::NoHomeDir)?;
+
+        Ok(Self::new(
+            PathBuf::from(home).join(".arcgis").join("credentials"),
+        ))
+    }
+
+    /// Select the profile to read
+    pub fn with_profile(mut self, profile: &str) -> Self {
+        self.profile = profile.to_string();
+        self
+    }
+}
+
+impl CredentialProvider for FileCredentials {
+    fn token(&self) -> Result<SecretString, CredentialError> {
+        let contents = std::fs::read_to_string(&self.path).map_err(CredentialError::Io)?;
+        parse_profile_token(&contents, &self.profile)
+            .map(SecretString::from)
+            .ok_or_else(|| CredentialError::MissingProfile(self.profile.clone()))
+    }
+}
+
+// finds the `token` of a profile in an ini style file
+fn parse_profile_token(contents: &str, profile: &str) -> Option<String> {
+    let mut in_profile = false;
+
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_profile = section.trim() == profile;
+            continue;
+        }
+
+        if !in_profile {
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            if key.trim() == "token" {
+                return Some(value.trim().to_string());
+            }
+        }
+    }
+
+    None
+}
+
+/// Reads a token from the operating system's keyring
+#[cfg(feature = "keyring")]
+#[derive(Debug, Clone)]
+pub struct KeyringCredentials {
+    pub service: String,
+    pub username: String,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringCredentials {
+    /// Read the token stored for `username` under `service`
+    pub fn new(service: &str, username: &str) -> Self {
+        Self {
+            service: service.to_string(),
+            username: username.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl CredentialProvider for KeyringCredentials {
+    fn token(&self) -> Result<SecretString, CredentialError> {
+        keyring::Entry::new(&self.service, &self.username)
+            .and_then(|entry| entry.get_password())
+            .map(SecretString::from)
+            .map_err(CredentialError::Keyring)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profile_token() {
+        let contents = "
+# comment
+[default]
+token = abc
+
+[enterprise]
+url = https://example.com
+token=def
+";
+        assert_eq!(
+            parse_profile_token(contents, "default").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            parse_profile_token(contents, "enterprise").as_deref(),
+            Some("def")
+        );
+        assert_eq!(parse_profile_token(contents, "missing"), None);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{
+    auth::{CachedToken, CredentialError, GeneratedToken, TokenProvider},
+    error::{ClassifyError, ErrorKind},
+    feature_service::{ErrorResponse, ExpectedResponse},
+};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Mutex, time::SystemTime};
+
+#[derive(Debug)]
+pub enum FederationError {
+    RequestError(reqwest::Error),
+    ApiError(ErrorResponse),
+    /// The URL does not contain `/rest/`, so the server cannot be determined
+    InvalidUrl(String),
+    /// The server is federated with a portal other than the one the credentials are for,
+    /// or with a portal that isn't served over https
+    UntrustedPortal(String),
+    CredentialError(CredentialError),
+}
+
+impl std::fmt::Display for FederationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RequestError(e) => write!(f, "request failed: {e}"),
+            Self::ApiError(e) => write!(f, "{} ({})", e.error.message, e.error.code),
+            Self::InvalidUrl(url) => write!(f, "not an ArcGIS Server REST URL: {url}"),
+            Self::UntrustedPortal(url) => {
+                write!(f, "the server is federated with an untrusted portal: {url}")
+            }
+            Self::CredentialError(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for FederationError {}
+
+impl ClassifyError for FederationError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::RequestError(e) => ErrorKind::from_request_error(e),
+            Self::ApiError(e) => e.kind(),
+            Self::InvalidUrl(_) | Self::UntrustedPortal(_) => ErrorKind::Fatal,
+            Self::CredentialError(e) => e.kind(),
+        }
+    }
+}
+
+/// The response from the `/rest/info` endpoint of an ArcGIS Server
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ServerInfo {
+    /// The portal the server is federated with
+    owningSystemUrl: Option<String>,
+}
+
+/// Exchanges a portal token for the server tokens required by ArcGIS Enterprise
+/// servers federated with the portal
+///
+/// The server of a URL is checked with its `/rest/info` endpoint. If it reports an
+/// `owningSystemUrl`, the portal token is exchanged at that portal's `/generateToken`
+/// endpoint. Otherwise, the portal token is used as is. The portal token is only sent to
+/// the portal the credentials are for, which must be served over https. Server tokens are
+/// cached per server until shortly before they expire.
+///
+/// The provider is asked for the portal token before every exchange, so a provider which
+/// refreshes its token, such as [`ClientCredentials`](crate::auth::ClientCredentials),
+/// keeps the exchanges working after the first portal token expires.
+///
+/// ```no_run
+/// use serde_esri::{auth::{EnvCredentials, FederatedCredentials}, feature_service::FeatureLayerClient};
+///
+/// let federated = FederatedCredentials::new("https://gis.example.com/portal", EnvCredentials::default());
+/// let client = FeatureLayerClient::new("https://gis.example.com/server/rest/services/Parcels/FeatureServer/0")
+///     .with_federated_credentials(&federated)
+///     .unwrap();
+/// ```
+pub struct FederatedCredentials {
+    /// The URL of the portal, e.g. `https://gis.example.com/portal`
+    pub portal_url: String,
+    provider: Box<dyn TokenProvider>,
+    client: reqwest::blocking::Client,
+    // the server tokens, or `None` for servers that aren't federated
+    cache: Mutex<HashMap<String, Option<CachedToken>>>,
+}
+
+impl std::fmt::Debug for FederatedCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FederatedCredentials")
+            .field("portal_url", &self.portal_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FederatedCredentials {
+    /// Create a token exchange for the tokens of the portal at `portal_url` supplied by `provider`
+    pub fn new<P: TokenProvider>(portal_url: &str, provider: P) -> Self {
+        Self {
+            portal_url: portal_url.trim_end_matches('/').to_string(),
+            provider: Box::new(provider),
+            client: reqwest::blocking::Client::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The token to use for requests to the server hosting `url`
+    pub fn server_token(&self, url: &str) -> Result<SecretString, FederationError> {
+        let server_url = server_root(url).ok_or_else(|| FederationError::InvalidUrl(url.into()))?;
+
+        let cached = self.cache.lock().unwrap().get(server_url).cloned();
+        match cached {
+            Some(Some(cached)) if cached.is_fresh() => return Ok(cached.token),
+            Some(None) => return self.portal_token(),
+            _ => {}
+        }
+
+        let info = self.send::<ServerInfo>(
+            self.client
+                .get(format!("{server_url}/rest/info"))
+                .query(&[("f", "json")]),
+        )?;
+
+        let cached = match info.owningSystemUrl {
+            Some(owner) => {
+                let portal_url = owner.trim_end_matches('/');
+                if !is_trusted(&self.portal_url, portal_url) {
+                    return Err(FederationError::UntrustedPortal(owner));
+                }
+
+                let portal_token = self.portal_token()?;
+                let form = [
+                    ("token", portal_token.expose_secret()),
+                    ("serverUrl", server_url),
+                    ("f", "json"),
+                ];
+                let generated = self.send::<GeneratedToken>(
+                    self.client
+                        .post(format!("{}/sharing/rest/generateToken", self.portal_url))
+                        .form(&form),
+                )?;
+
+                Some(CachedToken {
+                    token: SecretString::from(generated.token),
+                    expires: generated.expires.map(SystemTime::from),
+                })
+            }
+            None => None,
+        };
+
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(server_url.to_string(), cached.clone());
+        match cached {
+            Some(cached) => Ok(cached.token),
+            None => self.portal_token(),
+        }
+    }
+
+    fn portal_token(&self) -> Result<SecretString, FederationError> {
+        self.provider
+            .token()
+            .map_err(FederationError::CredentialError)
+    }
+
+    // sends the request and handles error responses
+    fn send<T: serde::de::DeserializeOwned>(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> Result<T, FederationError> {
+        let resp = request
+            .send()
+            .map_err(FederationError::RequestError)?
+            .json::<ExpectedResponse<T>>()
+            .map_err(FederationError::RequestError)?;
+
+        match resp {
+            ExpectedResponse::Ok(x) => Ok(x),
+            ExpectedResponse::Error(e) => Err(FederationError::ApiError(e)),
+        }
+    }
+}
+
+// the root of the ArcGIS Server hosting a REST URL, e.g. `https://gis.example.com/server`
+fn server_root(url: &str) -> Option<&str> {
+    url.find("/rest/")
+        .or_else(|| url.strip_suffix("/rest").map(str::len))
+        .map(|i| &url[..i])
+}
+
+// whether the portal reported by a server is the portal of the credentials, served over https
+fn is_trusted(portal_url: &str, owner: &str) -> bool {
+    let scheme = owner.get(..8);
+    scheme.is_some_and(|s| s.eq_ignore_ascii_case("https://"))
+        && owner.eq_ignore_ascii_case(portal_url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_root() {
+        assert_eq!(
+            server_root("https://gis.example.com/server/rest/services/Parcels/FeatureServer/0"),
+            Some("https://gis.example.com/server")
+        );
+        assert_eq!(
+            server_root("https://gis.example.com/server/rest"),
+            Some("https://gis.example.com/server")
+        );
+        assert_eq!(server_root("https://gis.example.com/server"), None);
+    }
+
+    #[test]
+    fn test_is_trusted() {
+        let portal = "https://gis.example.com/portal";
+        assert!(is_trusted(portal, "https://gis.example.com/portal"));
+        assert!(is_trusted(portal, "HTTPS://GIS.example.com/portal"));
+        assert!(!is_trusted(portal, "https://evil.example.com/portal"));
+        assert!(!is_trusted(
+            portal,
+            "https://gis.example.com/portal.evil.com"
+        ));
+        assert!(!is_trusted(
+            "http://gis.example.com/portal",
+            "http://gis.example.com/portal"
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+//! Authorization for the API clients.
+//!
+//! A [`CredentialProvider`] supplies the token used by a client so applications
+//! don't need to pass raw tokens around themselves.
+//!
+//! - [`EnvCredentials`] reads a token from an environment variable, `ARCGIS_TOKEN` by default.
+//! - [`FileCredentials`] reads a token from a profile of a credentials file, `~/.arcgis/credentials` by default.
+//! - `KeyringCredentials` reads a token from the operating system's keyring. Requires the `"keyring"` feature.
+//!
+//! ```no_run
+//! use serde_esri::auth::{CredentialProvider, EnvCredentials, FileCredentials};
+//!
+//! // read the token from `ARCGIS_TOKEN`, then from the `default` profile of `~/.arcgis/credentials`
+//! let token = EnvCredentials::default()
+//!     .token()
+//!     .or_else(|_| FileCredentials::from_home()?.token())
+//!     .unwrap();
+//! ```
+//!
+//! Clients accept any provider, e.g. `FeatureLayerClient::with_credentials()` and `PlacesClient::from_credentials()`.
+//! These read the token once. Tokens expire, so long-running applications should instead give the
+//! client a [`TokenProvider`], e.g. with `FeatureLayerClient::with_token_provider()` or
+//! `PlacesClient::from_token_provider()`, which is asked for the token before every request.
+//!
+//! - [`ClientCredentials`] requests tokens for an app with the OAuth 2.0 client credentials flow.
+//! - [`PasswordCredentials`] requests tokens for a user with a username and password.
+//!
+//! Both request a new token shortly before the previous one expires. They send blocking
+//! requests, so the async clients, e.g. with `AsyncPlacesClient::from_token_provider()`, ask
+//! their provider for a token on a blocking thread of the tokio runtime.
+//!
+//! Services hosted on an ArcGIS Enterprise server federated with a portal require a server token.
+//! With the `"feature-service-client"` feature, `FederatedCredentials` exchanges the tokens of a
+//! portal for server tokens and caches them per server. It only sends the portal token to that portal.
+mod credentials;
+pub use credentials::*;
+
+#[cfg(any(
+    feature = "places-client",
+    feature = "feature-service-client",
+    feature = "geometry-service-client",
+    feature = "geocode-client",
+    feature = "routing-client",
+    feature = "image-service-client",
+    feature = "tile-client"
+))]
+mod provider;
+#[cfg(any(
+    feature = "places-client",
+    feature = "feature-service-client",
+    feature = "geometry-service-client",
+    feature = "geocode-client",
+    feature = "routing-client",
+    feature = "image-service-client",
+    feature = "tile-client"
+))]
+pub use provider::*;
+
+// the providers that request tokens send blocking requests, which are not available in wasm builds
+#[cfg(all(
+    any(
+        feature = "places-client",
+        feature = "feature-service-client",
+        feature = "geometry-service-client",
+        feature = "geocode-client",
+        feature = "routing-client",
+        feature = "image-service-client",
+        feature = "tile-client"
+    ),
+    not(target_arch = "wasm32")
+))]
+mod token;
+#[cfg(all(
+    any(
+        feature = "places-client",
+        feature = "feature-service-client",
+        feature = "geometry-service-client",
+        feature = "geocode-client",
+        feature = "routing-client",
+        feature = "image-service-client",
+        feature = "tile-client"
+    ),
+    not(target_arch = "wasm32")
+))]
+pub use token::*;
+
+#[cfg(all(feature = "feature-service-client", not(target_arch = "wasm32")))]
+mod federation;
+#[cfg(all(feature = "feature-service-client", not(target_arch = "wasm32")))]
+pub use federation::*;
@@ -0,0 +1,104 @@
+use crate::auth::{CredentialError, CredentialProvider};
+use secrecy::{ExposeSecret, SecretString};
+use std::sync::Arc;
+
+/// A [`CredentialProvider`] that a client keeps and asks for a token before every request
+///
+/// Implemented for every provider that is `Send + Sync`. Providers which refresh their
+/// token, such as [`ClientCredentials`](crate::auth::ClientCredentials) and
+/// [`PasswordCredentials`](crate::auth::PasswordCredentials), keep long-running
+/// clients authorized after the first token expires.
+///
+/// The async clients ask a provider for a token on a blocking thread of the tokio runtime,
+/// as the providers which request tokens send blocking requests.
+pub trait TokenProvider: CredentialProvider + Send + Sync + 'static {}
+
+impl<C: CredentialProvider + Send + Sync + 'static> TokenProvider for C {}
+
+// the token of a client, shared by its clones
+#[derive(Clone)]
+pub(crate) enum TokenSource {
+    Static(SecretString),
+    Provider(Arc<dyn TokenProvider>),
+}
+
+impl TokenSource {
+    pub(crate) fn new<P: TokenProvider>(provider: P) -> Self {
+        Self::Provider(Arc::new(provider))
+    }
+
+    pub(crate) fn token(&self) -> Result<SecretString, CredentialError> {
+        match self {
+            Self::Static(token) => Ok(token.clone()),
+            Self::Provider(provider) => provider.token(),
+        }
+    }
+
+    // discards the token of a provider after a server rejected it. Returns `false` for a
+    // static token, which can't be replaced.
+    #[cfg(feature = "places-client")]
+    pub(crate) fn invalidate(&self) -> bool {
+        match self {
+            Self::Static(_) => false,
+            Self::Provider(provider) => {
+                provider.invalidate();
+                true
+            }
+        }
+    }
+
+    // the value of the `X-Esri-Authorization` header
+    pub(crate) fn bearer(&self) -> Result<String, CredentialError> {
+        Ok(format!("Bearer {}", self.token()?.expose_secret()))
+    }
+
+    // the value of the `X-Esri-Authorization` header, without blocking the async runtime
+    #[cfg(any(
+        feature = "places-client-async",
+        feature = "geometry-service-client-async"
+    ))]
+    pub(crate) async fn bearer_async(&self) -> Result<String, CredentialError> {
+        let token = match self {
+            Self::Static(token) => token.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Provider(provider) => {
+                let provider = provider.clone();
+                tokio::task::spawn_blocking(move || provider.token())
+                    .await
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))?
+            }
+            // the providers available in wasm builds don't send requests
+            #[cfg(target_arch = "wasm32")]
+            Self::Provider(provider) => provider.token()?,
+        };
+        Ok(format!("Bearer {}", token.expose_secret()))
+    }
+}
+
+impl From<SecretString> for TokenSource {
+    fn from(token: SecretString) -> Self {
+        Self::Static(token)
+    }
+}
+
+impl From<&str> for TokenSource {
+    fn from(token: &str) -> Self {
+        Self::Static(SecretString::from(token))
+    }
+}
+
+#[cfg(all(test, feature = "places-client-async", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bearer_async() {
+        let source = TokenSource::new(SecretString::from("secret"));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let bearer = runtime.block_on(source.bearer_async()).unwrap();
+        assert_eq!(bearer, "Bearer secret");
+    }
+}
@@ -0,0 +1,257 @@
+use crate::{
+    auth::{CredentialError, CredentialProvider},
+    date::EsriDate,
+    feature_service::ExpectedResponse,
+};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use std::{
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+// tokens are refreshed this long before they expire
+pub(crate) const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedToken {
+    pub(crate) token: SecretString,
+    pub(crate) expires: Option<SystemTime>,
+}
+
+impl CachedToken {
+    pub(crate) fn is_fresh(&self) -> bool {
+        self.expires
+            .is_none_or(|expires| SystemTime::now() + EXPIRY_MARGIN < expires)
+    }
+}
+
+/// The response from the `/generateToken` endpoint of a portal
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct GeneratedToken {
+    pub(crate) token: String,
+    pub(crate) expires: Option<EsriDate>,
+}
+
+/// The response from the `/oauth2/token` endpoint of a portal
+#[derive(Debug, Clone, Deserialize)]
+struct AccessToken {
+    access_token: String,
+    /// The lifetime of the token in seconds
+    expires_in: Option<u64>,
+}
+
+// returns the cached token or replaces it with a new one
+fn cached_or(
+    cache: &Mutex<Option<CachedToken>>,
+    request: impl FnOnce() -> Result<CachedToken, CredentialError>,
+) -> Result<SecretString, CredentialError> {
+    let mut cache = cache.lock().unwrap();
+    match cache.as_ref() {
+        Some(cached) if cached.is_fresh() => Ok(cached.token.clone()),
+        _ => {
+            let cached = request()?;
+            let token = cached.token.clone();
+            *cache = Some(cached);
+            Ok(token)
+        }
+    }
+}
+
+// sends the request and handles error responses
+fn send<T: serde::de::DeserializeOwned>(
+    request: reqwest::blocking::RequestBuilder,
+) -> Result<T, CredentialError> {
+    let resp = request
+        .send()
+        .map_err(CredentialError::Request)?
+        .json::<ExpectedResponse<T>>()
+        .map_err(CredentialError::Request)?;
+
+    match resp {
+        ExpectedResponse::Ok(x) => Ok(x),
+        ExpectedResponse::Error(e) => Err(CredentialError::Api(e)),
+    }
+}
+
+/// Requests tokens for an app with the OAuth 2.0 client credentials flow
+///
+/// A new token is requested shortly before the previous one expires or after a server
+/// rejected it.
+///
+/// ```no_run
+/// use serde_esri::auth::{ClientCredentials, CredentialProvider};
+///
+/// let credentials = ClientCredentials::new("https://www.arcgis.com", "client id", "client secret");
+/// let token = credentials.token().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ClientCredentials {
+    /// The URL of the portal, e.g. `https://www.arcgis.com`
+    pub portal_url: String,
+    pub client_id: String,
+    client_secret: SecretString,
+    /// The lifetime of each token in minutes. Defaults to two hours.
+    pub expiration: Option<u32>,
+    client: reqwest::blocking::Client,
+    cache: Mutex<Option<CachedToken>>,
+}
+
+impl ClientCredentials {
+    pub fn new(portal_url: &str, client_id: &str, client_secret: &str) -> Self {
+        Self {
+            portal_url: portal_url.trim_end_matches('/').to_string(),
+            client_id: client_id.to_string(),
+            client_secret: SecretString::from(client_secret),
+            expiration: None,
+            client: reqwest::blocking::Client::new(),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Set the lifetime of each token in minutes
+    pub fn with_expiration(mut self, minutes: u32) -> Self {
+        self.expiration = Some(minutes);
+        self
+    }
+
+    fn request_token(&self) -> Result<CachedToken, CredentialError> {
+        let mut form = vec![
+            ("client_id", self.client_id.clone()),
+            (
+                "client_secret",
+                self.client_secret.expose_secret().to_string(),
+            ),
+            ("grant_type", "client_credentials".into()),
+            ("f", "json".into()),
+        ];
+        if let Some(minutes) = self.expiration {
+            form.push(("expiration", minutes.to_string()));
+        }
+
+        let requested = SystemTime::now();
+        let resp = send::<AccessToken>(
+            self.client
+                .post(format!("{}/sharing/rest/oauth2/token", self.portal_url))
+                .form(&form),
+        )?;
+
+        Ok(CachedToken {
+            token: SecretString::from(resp.access_token),
+            expires: resp
+                .expires_in
+                .map(|secs| requested + Duration::from_secs(secs)),
+        })
+    }
+}
+
+impl CredentialProvider for ClientCredentials {
+    fn token(&self) -> Result<SecretString, CredentialError> {
+        cached_or(&self.cache, || self.request_token())
+    }
+
+    fn invalidate(&self) {
+        *self.cache.lock().unwrap() = None;
+    }
+}
+
+/// Requests tokens for a user from the `/generateToken` endpoint of a portal
+///
+/// Tokens are bound to the IP address of the requests. A new token is requested
+/// shortly before the previous one expires or after a server rejected it.
+///
+/// ```no_run
+/// use serde_esri::auth::{CredentialProvider, PasswordCredentials};
+///
+/// let credentials = PasswordCredentials::new("https://www.arcgis.com", "username", "password");
+/// let token = credentials.token().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct PasswordCredentials {
+    /// The URL of the portal, e.g. `https://www.arcgis.com`
+    pub portal_url: String,
+    pub username: String,
+    password: SecretString,
+    /// The lifetime of each token in minutes. Defaults to the portal's default.
+    pub expiration: Option<u32>,
+    client: reqwest::blocking::Client,
+    cache: Mutex<Option<CachedToken>>,
+}
+
+impl PasswordCredentials {
+    pub fn new(portal_url: &str, username: &str, password: &str) -> Self {
+        Self {
+            portal_url: portal_url.trim_end_matches('/').to_string(),
+            username: username.to_string(),
+            password: SecretString::from(password),
+            expiration: None,
+            client: reqwest::blocking::Client::new(),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Set the lifetime of each token in minutes
+    pub fn with_expiration(mut self, minutes: u32) -> Self {
+        self.expiration = Some(minutes);
+        self
+    }
+
+    fn request_token(&self) -> Result<CachedToken, CredentialError> {
+        let mut form = vec![
+            ("username", self.username.clone()),
+            ("password", self.password.expose_secret().to_string()),
+            ("client", "requestip".into()),
+            ("f", "json".into()),
+        ];
+        if let Some(minutes) = self.expiration {
+            form.push(("expiration", minutes.to_string()));
+        }
+
+        let resp = send::<GeneratedToken>(
+            self.client
+                .post(format!("{}/sharing/rest/generateToken", self.portal_url))
+                .form(&form),
+        )?;
+
+        Ok(CachedToken {
+            token: SecretString::from(resp.token),
+            expires: resp.expires.map(SystemTime::from),
+        })
+    }
+}
+
+impl CredentialProvider for PasswordCredentials {
+    fn token(&self) -> Result<SecretString, CredentialError> {
+        cached_or(&self.cache, || self.request_token())
+    }
+
+    fn invalidate(&self) {
+        *self.cache.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_or() {
+        let cache = Mutex::new(None);
+        let request = |token: &str, expires| {
+            let token = SecretString::from(token);
+            move || Ok(CachedToken { token, expires })
+        };
+
+        // expiring tokens are replaced
+        let soon = Some(SystemTime::now() + EXPIRY_MARGIN / 2);
+        let token = cached_or(&cache, request("first", soon)).unwrap();
+        assert_eq!(token.expose_secret(), "first");
+
+        let later = Some(SystemTime::now() + EXPIRY_MARGIN * 10);
+        let token = cached_or(&cache, request("second", later)).unwrap();
+        assert_eq!(token.expose_secret(), "second");
+
+        let token = cached_or(&cache, request("third", later)).unwrap();
+        assert_eq!(token.expose_secret(), "second");
+    }
+}
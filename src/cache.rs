@@ -0,0 +1,307 @@
+//! Caching of responses for clients that send requests with an [`HttpClient`].
+//!
+//! A [`CachingClient`] wraps another [`HttpClient`] and keeps the successful responses of
+//! `GET` requests in a [`CacheStore`] for a time to live. Esri errors, which services send
+//! with a 200 status, e.g. for an expired token, are not successful. The responses are keyed by the URL
+//! and the sorted query parameters of the request, not by its token. [`MemoryCache`] keeps
+//! the most recently used responses in memory and [`FileCache`] keeps them in a directory
+//! between runs. Implement [`CacheStore`] to keep them elsewhere.
+//!
+//! ```no_run
+//! use serde_esri::cache::{CachingClient, MemoryCache};
+//! use serde_esri::places::query::{PlacesClient, PLACES_API_URL};
+//! use std::time::Duration;
+//!
+//! let cache = CachingClient::new(reqwest::blocking::Client::new(), MemoryCache::new(1000))
+//!     .with_ttl(Duration::from_secs(24 * 60 * 60));
+//! let client = PlacesClient::new(PLACES_API_URL, "your token").with_http_client(cache);
+//! ```
+use crate::http::{HttpClient, HttpError, HttpRequest, HttpResponse, Method};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// A cached response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// When the entry is no longer used
+    pub expires: SystemTime,
+}
+
+impl CacheEntry {
+    pub fn is_fresh(&self) -> bool {
+        SystemTime::now() < self.expires
+    }
+}
+
+/// Stores the responses of a [`CachingClient`]
+pub trait CacheStore: Send + Sync {
+    /// The entry for `key`, if any. Expired entries are ignored by the client.
+    fn get(&self, key: &str) -> Option<CacheEntry>;
+    fn put(&self, key: &str, entry: CacheEntry);
+}
+
+/// Keeps up to `capacity` responses in memory, dropping the least recently used
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    entries: Mutex<Lru>,
+}
+
+// the entries with the tick of their last use
+#[derive(Debug, Default)]
+struct Lru {
+    entries: HashMap<String, (CacheEntry, u64)>,
+    tick: u64,
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::default(),
+        }
+    }
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let Lru { entries, tick } = &mut *self.entries.lock().unwrap();
+        let (entry, used) = entries.get_mut(key)?;
+        *tick += 1;
+        *used = *tick;
+        Some(entry.clone())
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        let Lru { entries, tick } = &mut *self.entries.lock().unwrap();
+        if self.capacity == 0 {
+            return;
+        }
+        if entries.len() >= self.capacity && !entries.contains_key(key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        *tick += 1;
+        entries.insert(key.to_string(), (entry, *tick));
+    }
+}
+
+/// Keeps responses as JSON files in a directory
+///
+/// Entries that cannot be read or written are treated as missing.
+#[derive(Debug, Clone)]
+pub struct FileCache {
+    pub dir: PathBuf,
+}
+
+// a file and the key it was written for, which guards against hash collisions
+#[derive(Serialize, Deserialize)]
+struct FileEntry {
+    key: String,
+    entry: CacheEntry,
+}
+
+impl FileCache {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    // a file name that is stable across runs and versions of Rust (64-bit FNV-1a)
+    fn path(&self, key: &str) -> PathBuf {
+        let hash = key.bytes().fold(0xcbf29ce484222325_u64, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+        self.dir.join(format!("{hash:016x}.json"))
+    }
+}
+
+impl CacheStore for FileCache {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let json = fs::read(self.path(key)).ok()?;
+        let file: FileEntry = serde_json::from_slice(&json).ok()?;
+        (file.key == key).then_some(file.entry)
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        let file = FileEntry {
+            key: key.to_string(),
+            entry,
+        };
+        if let Ok(json) = serde_json::to_vec(&file) {
+            let _ = fs::create_dir_all(&self.dir).and_then(|_| fs::write(self.path(key), json));
+        }
+    }
+}
+
+/// An [`HttpClient`] that answers `GET` requests from a [`CacheStore`]
+///
+/// Responses with a success status are kept for the time to live of the client, an hour
+/// unless set with [`CachingClient::with_ttl()`].
+#[derive(Debug)]
+pub struct CachingClient<H, S> {
+    pub client: H,
+    pub store: Arc<S>,
+    pub ttl: Duration,
+}
+
+impl<H: Clone, S> Clone for CachingClient<H, S> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            store: self.store.clone(),
+            ttl: self.ttl,
+        }
+    }
+}
+
+impl<H: HttpClient, S: CacheStore> CachingClient<H, S> {
+    pub fn new(client: H, store: S) -> Self {
+        Self {
+            client,
+            store: Arc::new(store),
+            ttl: Duration::from_secs(60 * 60),
+        }
+    }
+
+    /// Set how long responses are kept
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+impl<H: HttpClient, S: CacheStore> HttpClient for CachingClient<H, S> {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+        if request.method != Method::Get {
+            return self.client.execute(request);
+        }
+
+        let key = request.key();
+        if let Some(entry) = self.store.get(&key).filter(CacheEntry::is_fresh) {
+            return Ok(HttpResponse {
+                status: entry.status,
+                headers: entry.headers,
+                body: entry.body,
+            });
+        }
+
+        let resp = self.client.execute(request)?;
+        if (200..300).contains(&resp.status) && !is_esri_error(&resp.body) {
+            let entry = CacheEntry {
+                status: resp.status,
+                headers: resp.headers.clone(),
+                body: resp.body.clone(),
+                expires: SystemTime::now() + self.ttl,
+            };
+            self.store.put(&key, entry);
+        }
+        Ok(resp)
+    }
+}
+
+// whether `body` is an Esri error, e.g. `{"error": {"code": 498, ...}}`
+fn is_esri_error(body: &[u8]) -> bool {
+    #[derive(Deserialize)]
+    struct ErrorBody {
+        error: Option<serde::de::IgnoredAny>,
+    }
+
+    serde_json::from_slice::<ErrorBody>(body).is_ok_and(|b| b.error.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // counts the requests it receives and responds with its body
+    #[derive(Clone)]
+    struct Counter(Arc<AtomicUsize>, &'static str);
+
+    impl Default for Counter {
+        fn default() -> Self {
+            Self(Arc::default(), "{}")
+        }
+    }
+
+    impl HttpClient for Counter {
+        fn execute(&self, _: HttpRequest) -> Result<HttpResponse, HttpError> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(HttpResponse {
+                status: 200,
+                headers: vec![],
+                body: self.1.as_bytes().to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_caching_client() {
+        let counter = Counter::default();
+        let client = CachingClient::new(counter.clone(), MemoryCache::new(1));
+
+        let request = || HttpRequest::get("https://example.com/").param("a", "1");
+        client.execute(request().param("b", "2")).unwrap();
+        // the order of the parameters and the token don't matter
+        let reordered = HttpRequest::get("https://example.com").param("b", "2");
+        client
+            .execute(reordered.param("a", "1").param("token", "secret"))
+            .unwrap();
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+
+        // the least recently used response is dropped
+        client
+            .execute(HttpRequest::get("https://example.com/other"))
+            .unwrap();
+        client.execute(request().param("b", "2")).unwrap();
+        assert_eq!(counter.0.load(Ordering::Relaxed), 3);
+
+        let expired = client.with_ttl(Duration::ZERO);
+        expired
+            .execute(HttpRequest::get("https://example.com/other"))
+            .unwrap();
+        expired
+            .execute(HttpRequest::get("https://example.com/other"))
+            .unwrap();
+        assert_eq!(counter.0.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_errors_not_cached() {
+        let error = r#"{"error": {"code": 498, "message": "Invalid token.", "details": []}}"#;
+        let counter = Counter(Arc::default(), error);
+        let client = CachingClient::new(counter.clone(), MemoryCache::new(1));
+
+        for _ in 0..2 {
+            client
+                .execute(HttpRequest::get("https://example.com/"))
+                .unwrap();
+        }
+        assert_eq!(counter.0.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_keys() {
+        // parameters are encoded, so values can't pose as other parameters
+        let joined = HttpRequest::get("https://example.com").param("a", "1&b=2");
+        let split = HttpRequest::get("https://example.com")
+            .param("a", "1")
+            .param("b", "2");
+        assert_ne!(joined.key(), split.key());
+        assert_eq!(split.key(), "GET https://example.com?a=1&b=2");
+    }
+}
@@ -0,0 +1,120 @@
+//! Deterministic, canonical JSON serialization.
+//!
+//! `serde_json` writes object keys in the order of a type's fields, or in
+//! insertion order for attribute maps. The canonical form instead sorts the
+//! keys of every object, writes no whitespace, and formats floats the same way
+//! every time: `-0.0` is written as `0.0` and, optionally, every float is
+//! rounded to a fixed number of decimal places. Two equal geometries or
+//! `FeatureSet`s therefore always produce the same bytes, which can be hashed
+//! or diffed to detect changes.
+//!
+//! ```
+//! use serde_esri::{canonical, geometry::EsriPoint};
+//!
+//! let pt: EsriPoint = serde_json::from_str(r#"{"y": 2.000000001, "x": -0.0}"#).unwrap();
+//! assert_eq!(canonical::to_string(&pt).unwrap(), r#"{"x":0.0,"y":2.000000001}"#);
+//! assert_eq!(canonical::to_string_with_precision(&pt, 6).unwrap(), r#"{"x":0.0,"y":2.0}"#);
+//! ```
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+/// Serialize `value` to a canonical string of JSON
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    write_canonical(value, None)
+}
+
+/// Serialize `value` to a canonical string of JSON, rounding every float to
+/// `decimals` decimal places
+pub fn to_string_with_precision<T: Serialize + ?Sized>(
+    value: &T,
+    decimals: u32,
+) -> serde_json::Result<String> {
+    write_canonical(value, Some(decimals))
+}
+
+/// Serialize `value` to a canonical vector of bytes of JSON
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Vec<u8>> {
+    to_string(value).map(String::into_bytes)
+}
+
+fn write_canonical<T: Serialize + ?Sized>(
+    value: &T,
+    decimals: Option<u32>,
+) -> serde_json::Result<String> {
+    let value = serde_json::to_value(value)?;
+    let mut out = String::new();
+    write_value(&value, decimals, &mut out)?;
+    Ok(out)
+}
+
+fn write_value(value: &Value, decimals: Option<u32>, out: &mut String) -> serde_json::Result<()> {
+    match value {
+        Value::Number(n) => out.push_str(&format_number(n, decimals)),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, decimals, out)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::to_string(key)?);
+                out.push(':');
+                write_value(item, decimals, out)?;
+            }
+            out.push('}');
+        }
+        // null, booleans, and strings have a single representation
+        _ => out.push_str(&serde_json::to_string(value)?),
+    }
+    Ok(())
+}
+
+fn format_number(n: &Number, decimals: Option<u32>) -> String {
+    let x = match n.as_f64() {
+        Some(x) if n.is_f64() => x,
+        _ => return n.to_string(),
+    };
+
+    let x = match decimals {
+        Some(d) => {
+            let scale = 10_f64.powi(d as i32);
+            (x * scale).round() / scale
+        }
+        None => x,
+    };
+
+    // -0.0 == 0.0, so this also normalizes the sign of zero
+    let x = if x == 0.0 { 0.0 } else { x };
+    Number::from_f64(x).map_or_else(|| "null".to_string(), |n| n.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::FeatureSet;
+
+    #[test]
+    fn test_canonical_feature_set() {
+        let a = r#"{"features": [{"attributes": {"b": 1, "a": 0.1}, "geometry": {"y": 1.0, "x": -0.0}}]}"#;
+        let b =
+            r#"{"features": [{"geometry": {"x": 0.0, "y": 1}, "attributes": {"a": 0.1, "b": 1}}]}"#;
+        let a: FeatureSet<2> = serde_json::from_str(a).unwrap();
+        let b: FeatureSet<2> = serde_json::from_str(b).unwrap();
+
+        let expected =
+            r#"{"features":[{"attributes":{"a":0.1,"b":1},"geometry":{"x":0.0,"y":1.0}}]}"#;
+        assert_eq!(to_string(&a).unwrap(), expected);
+        assert_eq!(to_string(&b).unwrap(), expected);
+    }
+}
@@ -0,0 +1,422 @@
+//! Optional feature for writing a `FeatureSet` to CSV and reading one from CSV.
+//!
+//! [`FeatureSet::write_csv()`] writes a column for each field and encodes
+//! geometries according to a [`GeometryEncoding`]: as WKT in a `wkt` column, as
+//! the `x`, `y`, `z`, and `m` columns of points, or not at all. `z` and `m` columns
+//! are only written when a point has them. Dates are written as epoch milliseconds.
+//!
+//! [`FeatureSet::from_csv()`] reads geometries from the columns given by
+//! [`GeometryColumns`]. The type of every other column is inferred from its values:
+//! `esriFieldTypeInteger` if every value is a 32 bit integer, `esriFieldTypeDouble`
+//! if every value is a number, and `esriFieldTypeString` otherwise. Empty values are null.
+//!
+//! ```
+//! use serde_esri::{
+//!     csv::{GeometryColumns, GeometryEncoding},
+//!     features::FeatureSet,
+//!     spatial_reference::SpatialReference,
+//! };
+//!
+//! let json = r#"{
+//!     "geometryType": "esriGeometryPoint",
+//!     "fields": [{"name": "name", "type": "esriFieldTypeString"}],
+//!     "features": [{"attributes": {"name": "a, b"}, "geometry": {"x": 1.5, "y": 2}}]
+//! }"#;
+//! let fset: FeatureSet<2> = serde_json::from_str(json).unwrap();
+//!
+//! let mut csv = Vec::new();
+//! fset.write_csv(&mut csv, GeometryEncoding::XYColumns).unwrap();
+//! assert_eq!(String::from_utf8(csv.clone()).unwrap(), "name,x,y\n\"a, b\",1.5,2\n");
+//!
+//! let columns = GeometryColumns::XY { x: "x", y: "y", z: None, m: None };
+//! let fset = FeatureSet::<2>::from_csv(csv.as_slice(), columns, Some(SpatialReference::from_wkid(4326))).unwrap();
+//! let point = fset.features[0].geometry.clone().unwrap().as_point().unwrap();
+//! assert_eq!((point.x, point.y), (1.5, 2.0));
+//! ```
+use crate::{
+    features::{infer_fields, Feature, FeatureSet, Field},
+    field_type::FieldType,
+    geometry::{dimension_flags, EsriGeometry, EsriPoint},
+    spatial_reference::SpatialReference,
+    wkt::WktConversionError,
+};
+use serde_json::{Map, Value};
+use std::io::{Read, Write};
+use wkt::Wkt;
+
+/// How [`FeatureSet::write_csv()`] writes geometries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryEncoding {
+    /// A `wkt` column of Well-Known Text
+    Wkt,
+    /// `x` and `y` columns, and `z` and `m` columns if any point has them.
+    /// Only points can be written.
+    XYColumns,
+    /// No geometry columns
+    Skip,
+}
+
+/// The columns [`FeatureSet::from_csv()`] reads geometries from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryColumns<'a> {
+    /// A column of Well-Known Text
+    Wkt(&'a str),
+    /// The coordinate columns of points
+    XY {
+        x: &'a str,
+        y: &'a str,
+        z: Option<&'a str>,
+        m: Option<&'a str>,
+    },
+    /// The features have no geometry
+    None,
+}
+
+/// Errors that can occur when converting between CSV and a `FeatureSet`
+#[derive(Debug)]
+pub enum CsvError {
+    /// An error from the CSV reader or writer
+    Csv(csv::Error),
+    /// A column is not in the header of the CSV
+    MissingColumn(String),
+    /// The value of a coordinate column is not a number
+    InvalidCoordinate { row: usize, column: String },
+    /// The value of a WKT column could not be parsed
+    InvalidWkt { row: usize, message: String },
+    /// The geometry has no WKT representation or does not have the dimensions required by `N`
+    Wkt {
+        row: usize,
+        error: WktConversionError,
+    },
+    /// Only points can be written as coordinate columns
+    NotAPoint { row: usize },
+}
+
+impl std::fmt::Display for CsvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Csv(e) => write!(f, "{e}"),
+            Self::MissingColumn(column) => write!(f, "column `{column}` does not exist"),
+            Self::InvalidCoordinate { row, column } => {
+                write!(f, "column `{column}` of row {row} is not a number")
+            }
+            Self::InvalidWkt { row, message } => write!(f, "invalid WKT in row {row}: {message}"),
+            Self::Wkt { row, error } => write!(f, "invalid geometry in row {row}: {error}"),
+            Self::NotAPoint { row } => write!(
+                f,
+                "the geometry of row {row} is not a point and cannot be written as coordinates"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+impl From<csv::Error> for CsvError {
+    fn from(value: csv::Error) -> Self {
+        Self::Csv(value)
+    }
+}
+
+impl From<std::io::Error> for CsvError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Csv(value.into())
+    }
+}
+
+impl<const N: usize> FeatureSet<N> {
+    /// Write the features as CSV with a header row
+    ///
+    /// If the `FeatureSet` has no `fields`, they are inferred from the attributes.
+    pub fn write_csv<W: Write>(
+        &self,
+        writer: W,
+        geometry: GeometryEncoding,
+    ) -> Result<(), CsvError> {
+        let fields = match &self.fields {
+            Some(fields) => fields.clone(),
+            None => infer_fields(&self.features),
+        };
+
+        let points = self.features.iter().filter_map(|f| match &f.geometry {
+            Some(EsriGeometry::Point(pt)) => Some(pt),
+            _ => None,
+        });
+        let (has_z, has_m) = points.fold((false, false), |(z, m), pt| {
+            (z || pt.z.is_some(), m || pt.m.is_some())
+        });
+
+        let mut header: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        match geometry {
+            GeometryEncoding::Wkt => header.push("wkt"),
+            GeometryEncoding::XYColumns => {
+                header.extend(["x", "y"]);
+                header.extend(has_z.then_some("z"));
+                header.extend(has_m.then_some("m"));
+            }
+            GeometryEncoding::Skip => {}
+        }
+
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(&header)?;
+
+        for (row, feature) in self.features.iter().enumerate() {
+            let mut record: Vec<String> = fields
+                .iter()
+                .map(|field| {
+                    let value = feature.attributes.as_ref().and_then(|a| a.get(&field.name));
+                    value.map_or(String::new(), value_to_string)
+                })
+                .collect();
+
+            match (geometry, &feature.geometry) {
+                (GeometryEncoding::Wkt, Some(geometry)) => {
+                    let wkt =
+                        Wkt::try_from(geometry).map_err(|error| CsvError::Wkt { row, error })?;
+                    record.push(wkt.to_string());
+                }
+                (GeometryEncoding::XYColumns, Some(EsriGeometry::Point(pt))) => {
+                    record.extend([pt.x.to_string(), pt.y.to_string()]);
+                    let opt = |v: Option<f64>| v.map_or(String::new(), |v| v.to_string());
+                    if has_z {
+                        record.push(opt(pt.z));
+                    }
+                    if has_m {
+                        record.push(opt(pt.m));
+                    }
+                }
+                (GeometryEncoding::XYColumns, Some(_)) => return Err(CsvError::NotAPoint { row }),
+                (GeometryEncoding::Skip, _) => {}
+                // empty geometry columns
+                (_, None) => record.resize(header.len(), String::new()),
+            }
+
+            writer.write_record(&record)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read a `FeatureSet` from CSV with a header row
+    ///
+    /// Geometries are read from `geometry`. Rows with an empty geometry column have no
+    /// geometry. Every other column becomes an attribute with a field of the same name.
+    pub fn from_csv<R: Read>(
+        reader: R,
+        geometry: GeometryColumns<'_>,
+        spatial_reference: Option<SpatialReference>,
+    ) -> Result<Self, CsvError> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let header = reader.headers()?.clone();
+        let records = reader.records().collect::<Result<Vec<_>, _>>()?;
+
+        let index_of = |name: &str| {
+            header
+                .iter()
+                .position(|h| h == name)
+                .ok_or_else(|| CsvError::MissingColumn(name.to_string()))
+        };
+        let geometry_indices = match geometry {
+            GeometryColumns::Wkt(column) => vec![index_of(column)?],
+            GeometryColumns::XY { x, y, z, m } => {
+                let mut indices = vec![index_of(x)?, index_of(y)?];
+                for column in [z, m].into_iter().flatten() {
+                    indices.push(index_of(column)?);
+                }
+                indices
+            }
+            GeometryColumns::None => vec![],
+        };
+
+        let fields: Vec<(usize, Field)> = header
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !geometry_indices.contains(i))
+            .map(|(i, name)| {
+                let field = Field {
+                    name: name.to_string(),
+                    field_type: column_type(records.iter().map(|r| &r[i])),
+                    ..Default::default()
+                };
+                (i, field)
+            })
+            .collect();
+
+        let features = records
+            .iter()
+            .enumerate()
+            .map(|(row, record)| {
+                let attributes = fields
+                    .iter()
+                    .map(|(i, field)| (field.name.clone(), parse_value(&record[*i], field)))
+                    .collect::<Map<_, _>>();
+
+                Ok(Feature {
+                    geometry: read_geometry(row, record, &header, geometry)?,
+                    attributes: Some(attributes),
+                    #[cfg(feature = "preserve-unknown")]
+                    extra: Default::default(),
+                })
+            })
+            .collect::<Result<Vec<_>, CsvError>>()?;
+
+        let geometry_type = features
+            .iter()
+            .find_map(|f| f.geometry.as_ref())
+            .map(|g| g.geometry_type().to_string());
+
+        let (has_z, has_m) = dimension_flags::<N>();
+        Ok(FeatureSet {
+            geometryType: geometry_type,
+            spatialReference: spatial_reference,
+            hasZ: Some(has_z),
+            hasM: Some(has_m),
+            fields: Some(fields.into_iter().map(|(_, field)| field).collect()),
+            features,
+            ..Default::default()
+        })
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+// the narrowest field type that can store every non-empty value
+fn column_type<'a>(values: impl Iterator<Item = &'a str>) -> FieldType {
+    let mut field_type = None;
+    for value in values.filter(|v| !v.is_empty()) {
+        let is_integer = value.parse::<i32>().is_ok();
+        if !is_integer && !value.parse::<f64>().is_ok_and(f64::is_finite) {
+            return FieldType::EsriFieldTypeString;
+        }
+        field_type = match field_type {
+            None | Some(FieldType::EsriFieldTypeInteger) if is_integer => {
+                Some(FieldType::EsriFieldTypeInteger)
+            }
+            _ => Some(FieldType::EsriFieldTypeDouble),
+        };
+    }
+    field_type.unwrap_or(FieldType::EsriFieldTypeString)
+}
+
+fn parse_value(value: &str, field: &Field) -> Value {
+    if value.is_empty() {
+        return Value::Null;
+    }
+    match field.field_type {
+        FieldType::EsriFieldTypeInteger => value.parse::<i64>().map_or(Value::Null, Value::from),
+        FieldType::EsriFieldTypeDouble => value.parse::<f64>().map_or(Value::Null, Value::from),
+        _ => Value::String(value.to_string()),
+    }
+}
+
+fn read_geometry<const N: usize>(
+    row: usize,
+    record: &csv::StringRecord,
+    header: &csv::StringRecord,
+    geometry: GeometryColumns<'_>,
+) -> Result<Option<EsriGeometry<N>>, CsvError> {
+    let value = |column: &str| {
+        let i = header.iter().position(|h| h == column).unwrap_or_default();
+        &record[i]
+    };
+    let coordinate = |column: Option<&str>| -> Result<Option<f64>, CsvError> {
+        match column.map(value) {
+            None | Some("") => Ok(None),
+            Some(v) => v
+                .parse()
+                .map(Some)
+                .map_err(|_| CsvError::InvalidCoordinate {
+                    row,
+                    column: column.unwrap_or_default().to_string(),
+                }),
+        }
+    };
+
+    match geometry {
+        GeometryColumns::Wkt(column) if !value(column).is_empty() => {
+            let wkt =
+                value(column)
+                    .parse::<Wkt<f64>>()
+                    .map_err(|message| CsvError::InvalidWkt {
+                        row,
+                        message: message.to_string(),
+                    })?;
+            EsriGeometry::try_from(&wkt)
+                .map(Some)
+                .map_err(|error| CsvError::Wkt { row, error })
+        }
+        GeometryColumns::XY { x, y, z, m } => {
+            let (Some(x), Some(y)) = (coordinate(Some(x))?, coordinate(Some(y))?) else {
+                return Ok(None);
+            };
+            Ok(Some(EsriGeometry::Point(EsriPoint {
+                x,
+                y,
+                z: coordinate(z)?,
+                m: coordinate(m)?,
+                spatialReference: None,
+            })))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_round_trip() {
+        let json = r#"{
+            "geometryType": "esriGeometryPolyline",
+            "hasZ": true,
+            "features": [
+                {"attributes": {"id": 1, "area": 2.5, "name": "a"}, "geometry": {"paths": [[[0, 0, 1], [1, 1, 2]]]}},
+                {"attributes": {"id": 2, "area": 3, "name": null}}
+            ]
+        }"#;
+        let fset: FeatureSet<3> = serde_json::from_str(json).unwrap();
+
+        let mut csv = Vec::new();
+        fset.write_csv(&mut csv, GeometryEncoding::Wkt).unwrap();
+        let fset =
+            FeatureSet::<3>::from_csv(csv.as_slice(), GeometryColumns::Wkt("wkt"), None).unwrap();
+
+        let field_type = |name: &str| {
+            let fields = fset.fields.as_ref().unwrap();
+            fields
+                .iter()
+                .find(|f| f.name == name)
+                .unwrap()
+                .field_type
+                .clone()
+        };
+        assert!(matches!(field_type("id"), FieldType::EsriFieldTypeInteger));
+        assert!(matches!(field_type("area"), FieldType::EsriFieldTypeDouble));
+        assert!(matches!(field_type("name"), FieldType::EsriFieldTypeString));
+        assert_eq!(fset.geometryType.as_deref(), Some("esriGeometryPolyline"));
+        let polyline = fset.features[0].geometry.clone().unwrap().as_polyline();
+        assert_eq!(polyline.unwrap().paths[0].0[1].0, [1.0, 1.0, 2.0]);
+        assert!(fset.features[1].geometry.is_none());
+        assert_eq!(
+            fset.features[1].attributes.as_ref().unwrap()["name"],
+            Value::Null
+        );
+
+        assert!(matches!(
+            fset.write_csv(Vec::new(), GeometryEncoding::XYColumns),
+            Err(CsvError::NotAPoint { row: 0 })
+        ));
+        assert!(matches!(
+            FeatureSet::<3>::from_csv(csv.as_slice(), GeometryColumns::Wkt("shape"), None),
+            Err(CsvError::MissingColumn(_))
+        ));
+    }
+}
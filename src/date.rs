@@ -0,0 +1,160 @@
+//! Dates in Esri JSON.
+//!
+//! Esri JSON represents dates, such as the values of `esriFieldTypeDate` fields
+//! and the expiration of tokens, as milliseconds since the Unix epoch. Some
+//! services send them as strings. [`EsriDate`] accepts both.
+//!
+//! With the `chrono` feature, an `EsriDate` converts to and from a `DateTime<Utc>`.
+//! The date attributes of a feature are read with `Feature::attr_date()`, or
+//! `Feature::attr_datetime()` with the `chrono` feature, and written with
+//! `Feature::set_attr_date()`.
+//!
+//! ```
+//! use serde_esri::date::EsriDate;
+//! use serde_json::json;
+//!
+//! let attributes = json!({"created": "1700000000000"});
+//! let created = EsriDate::from_attribute(&attributes["created"]).unwrap();
+//! assert_eq!(created, EsriDate(1_700_000_000_000));
+//! ```
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Milliseconds since the Unix epoch
+///
+/// Deserializes from an integer or a string of an integer and serializes to an integer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(transparent)]
+pub struct EsriDate(pub i64);
+
+impl EsriDate {
+    /// The date of an attribute value, i.e. a number or a string of milliseconds
+    pub fn from_attribute(x: &Value) -> Option<Self> {
+        match x {
+            Value::Number(n) => n
+                .as_i64()
+                .or_else(|| n.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64)),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+        .map(Self)
+    }
+}
+
+impl<'de> Deserialize<'de> for EsriDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let x = Value::deserialize(deserializer)?;
+        Self::from_attribute(&x).ok_or_else(|| {
+            serde::de::Error::custom(format!("expected milliseconds since the epoch, found {x}"))
+        })
+    }
+}
+
+impl From<EsriDate> for Value {
+    fn from(x: EsriDate) -> Self {
+        Value::from(x.0)
+    }
+}
+
+impl From<SystemTime> for EsriDate {
+    fn from(x: SystemTime) -> Self {
+        match x.duration_since(UNIX_EPOCH) {
+            Ok(d) => Self(d.as_millis() as i64),
+            Err(e) => Self(-(e.duration().as_millis() as i64)),
+        }
+    }
+}
+
+impl From<EsriDate> for SystemTime {
+    fn from(x: EsriDate) -> Self {
+        let d = Duration::from_millis(x.0.unsigned_abs());
+        if x.0 < 0 {
+            UNIX_EPOCH - d
+        } else {
+            UNIX_EPOCH + d
+        }
+    }
+}
+
+/// An attribute that cannot be read as the requested type
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeError {
+    /// The value is neither milliseconds since the epoch nor a string of them
+    NotADate { name: String, value: Value },
+    /// The value cannot be read as the type of its field
+    WrongType {
+        name: String,
+        expected: &'static str,
+        value: Value,
+    },
+    /// The date is outside of the range of a `DateTime<Utc>`
+    #[cfg(feature = "chrono")]
+    OutOfRange(DateOutOfRange),
+}
+
+impl std::fmt::Display for AttributeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotADate { name, value } => {
+                write!(f, "attribute `{name}` is not a date: {value}")
+            }
+            Self::WrongType {
+                name,
+                expected,
+                value,
+            } => write!(f, "attribute `{name}` is not {expected}: {value}"),
+            #[cfg(feature = "chrono")]
+            Self::OutOfRange(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AttributeError {}
+
+/// An [`EsriDate`] outside of the range of a `DateTime<Utc>`
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateOutOfRange(pub EsriDate);
+
+#[cfg(feature = "chrono")]
+impl std::fmt::Display for DateOutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} milliseconds is out of range for a date", self.0 .0)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl std::error::Error for DateOutOfRange {}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for EsriDate {
+    fn from(x: chrono::DateTime<chrono::Utc>) -> Self {
+        Self(x.timestamp_millis())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<EsriDate> for chrono::DateTime<chrono::Utc> {
+    type Error = DateOutOfRange;
+
+    fn try_from(x: EsriDate) -> Result<Self, Self::Error> {
+        chrono::DateTime::from_timestamp_millis(x.0).ok_or(DateOutOfRange(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_esri_date() {
+        let dates: Vec<EsriDate> = serde_json::from_str(r#"[1700000000000, "-86400000"]"#).unwrap();
+        assert_eq!(dates, [EsriDate(1_700_000_000_000), EsriDate(-86_400_000)]);
+        assert!(serde_json::from_str::<EsriDate>(r#""yesterday""#).is_err());
+
+        for date in dates {
+            assert_eq!(EsriDate::from(SystemTime::from(date)), date);
+        }
+    }
+}
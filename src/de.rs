@@ -0,0 +1,968 @@
+//! Strict and lenient deserialization.
+//!
+//! Esri services are not always consistent: new properties appear as services
+//! are upgraded and some numbers are occasionally sent as strings, e.g. a
+//! `"wkid": "4326"`. The types of this crate are lenient by default, so plain
+//! `serde_json::from_str()` ignores unknown fields and coerces such values.
+//!
+//! The [`from_str()`], [`from_slice()`], and [`from_value()`] functions take a
+//! [`Mode`]. In [`Mode::Strict`], values must have their exact type and any field
+//! that is not represented by the target type is an error. This is useful in
+//! tests to catch changes to a service's schema. With the `preserve-unknown`
+//! feature, the types that keep unknown fields in their `extra` map accept them
+//! in strict mode too.
+//!
+//! Lenient mode also tolerates the differences between ArcGIS versions: the
+//! properties of Esri JSON objects, e.g. `spatialReference` or `latestWkid`, are
+//! matched regardless of their case, as are the values of `geometryType`.
+//!
+//! ```
+//! use serde_esri::{de::{self, Mode}, spatial_reference::SpatialReference};
+//!
+//! let json = r#"{"wkid": "4326"}"#;
+//! let sr: SpatialReference = de::from_str(json, Mode::Lenient).unwrap();
+//! assert_eq!(sr.wkid, Some(4326));
+//! assert!(de::from_str::<SpatialReference>(json, Mode::Strict).is_err());
+//!
+//! let json = r#"{"wkid": 4326, "unknown": true}"#;
+//! assert!(de::from_str::<SpatialReference>(json, Mode::Strict).is_err());
+//! ```
+//!
+//! Responses of older ArcGIS Server instances may not parse at all: numbers are
+//! sent as bare `NaN` tokens, numeric attributes as `"null"` strings, and
+//! geometries as empty strings. [`from_str_lossy()`] repairs a `FeatureSet`
+//! before deserializing it, setting such values to `null` and returning a
+//! [`Warning`] for each.
+//!
+//! ```
+//! use serde_esri::features::FeatureSet;
+//!
+//! let json = r#"{
+//!     "fields": [{"name": "POP", "type": "esriFieldTypeInteger"}],
+//!     "features": [
+//!         {"geometry": "", "attributes": {"POP": "null"}},
+//!         {"geometry": {"x": 1, "y": 2}, "attributes": {"POP": NaN}}
+//!     ]
+//! }"#;
+//! let (fset, warnings) = FeatureSet::<2>::from_str_lossy(json).unwrap();
+//! assert_eq!(fset.features.len(), 2);
+//! assert_eq!(warnings.len(), 3);
+//! assert!(warnings.iter().any(|w| w.path == "/features/0/geometry"));
+//! ```
+//!
+//! Payloads from untrusted sources can be checked against [`Limits`] on the
+//! number of vertices, rings, and features before they are deserialized, using
+//! [`from_str_with_limits()`] or [`from_slice_with_limits()`]. A
+//! [`FeatureIter`](crate::features::FeatureIter) checks each feature it reads
+//! against the limits given to its `with_limits()`.
+//!
+//! ```
+//! use serde_esri::{de::{self, DeError, Limits, Mode}, geometry::EsriPolygon};
+//!
+//! let limits = Limits { max_vertices: Some(3), ..Default::default() };
+//! let json = r#"{"rings": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}"#;
+//! let res = de::from_str_with_limits::<EsriPolygon<2>>(json, Mode::Lenient, &limits);
+//! assert!(matches!(res, Err(DeError::LimitExceeded(..))));
+//! ```
+use crate::{features::FeatureSet, geometry::EsriGeometry};
+use serde::{
+    de::{DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::Value;
+use std::{borrow::Cow, cell::Cell};
+
+/// How JSON is deserialized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Unknown fields are ignored and values are coerced where possible
+    #[default]
+    Lenient,
+    /// Unknown fields and values of the wrong type are errors
+    Strict,
+}
+
+#[derive(Debug)]
+pub enum DeError {
+    Json(serde_json::Error),
+    /// Fields that are not represented by the target type, as JSON pointers, e.g. `/features/0/geometry/foo`
+    UnknownFields(Vec<String>),
+    /// The payload has more vertices, rings, or features than the given maximum
+    LimitExceeded(Limit, usize),
+}
+
+impl std::fmt::Display for DeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "{e}"),
+            Self::UnknownFields(paths) => write!(f, "unknown fields: {}", paths.join(", ")),
+            Self::LimitExceeded(limit, max) => {
+                write!(f, "payload exceeds the limit of {max} {limit}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DeError {}
+
+thread_local! {
+    static MODE: Cell<Mode> = const { Cell::new(Mode::Lenient) };
+}
+
+// the mode of the deserialization running on this thread
+pub(crate) fn mode() -> Mode {
+    MODE.with(Cell::get)
+}
+
+// restores the previous mode when dropped
+struct ModeGuard(Mode);
+
+impl ModeGuard {
+    fn set(mode: Mode) -> Self {
+        Self(MODE.with(|m| m.replace(mode)))
+    }
+}
+
+impl Drop for ModeGuard {
+    fn drop(&mut self) {
+        MODE.with(|m| m.set(self.0));
+    }
+}
+
+/// Deserialize a `T` from a string of JSON
+pub fn from_str<T: DeserializeOwned + Serialize>(s: &str, mode: Mode) -> Result<T, DeError> {
+    from_value(serde_json::from_str(s).map_err(DeError::Json)?, mode)
+}
+
+/// Deserialize a `T` from bytes of JSON
+pub fn from_slice<T: DeserializeOwned + Serialize>(v: &[u8], mode: Mode) -> Result<T, DeError> {
+    from_value(serde_json::from_slice(v).map_err(DeError::Json)?, mode)
+}
+
+/// Deserialize a `T` from a `serde_json::Value`
+///
+/// In strict mode, unknown fields are found by serializing the result and
+/// comparing it with `value`. Fields whose value is `null` are not reported.
+pub fn from_value<T: DeserializeOwned + Serialize>(
+    mut value: Value,
+    mode: Mode,
+) -> Result<T, DeError> {
+    if mode == Mode::Lenient {
+        normalize_keys(&mut value);
+    }
+
+    let _guard = ModeGuard::set(mode);
+    let res = T::deserialize(&value).map_err(DeError::Json)?;
+
+    if mode == Mode::Strict {
+        let round_trip = serde_json::to_value(&res).map_err(DeError::Json)?;
+        let mut unknown = vec![];
+        unknown_fields(&value, &round_trip, String::new(), &mut unknown);
+        if !unknown.is_empty() {
+            return Err(DeError::UnknownFields(unknown));
+        }
+    }
+
+    Ok(res)
+}
+
+// the properties of Esri JSON objects, matched regardless of case in lenient mode
+const KEYS: &[&str] = &[
+    "alias",
+    "attributes",
+    "defaultValue",
+    "displayFieldName",
+    "domain",
+    "exceededTransferLimit",
+    "features",
+    "fields",
+    "geometry",
+    "geometryProperties",
+    "geometryType",
+    "globalIdFieldName",
+    "hasM",
+    "hasZ",
+    "isSystemMaintained",
+    "latestVcsWkid",
+    "latestWkid",
+    "mmax",
+    "mmin",
+    "name",
+    "objectIdFieldName",
+    "paths",
+    "points",
+    "rings",
+    "shapeAreaFieldName",
+    "shapeLengthFieldName",
+    "spatialReference",
+    "sqlType",
+    "type",
+    "uniqueIdField",
+    "units",
+    "vcsWkid",
+    "wkid",
+    "wkt",
+    "xmax",
+    "xmin",
+    "ymax",
+    "ymin",
+    "zmax",
+    "zmin",
+];
+
+// renames the keys that match one of `KEYS` in another case. Attributes are
+// user data and are left as is.
+fn normalize_keys(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let renames = map
+                .keys()
+                .filter_map(|key| {
+                    let canonical = KEYS.iter().find(|k| k.eq_ignore_ascii_case(key))?;
+                    (key != canonical && !map.contains_key(*canonical))
+                        .then(|| (key.clone(), *canonical))
+                })
+                .collect::<Vec<_>>();
+
+            for (key, canonical) in renames {
+                if let Some(v) = map.remove(&key) {
+                    map.insert(canonical.to_string(), v);
+                }
+            }
+
+            map.iter_mut()
+                .filter(|(key, _)| key.as_str() != "attributes")
+                .for_each(|(_, v)| normalize_keys(v));
+        }
+        Value::Array(items) => items.iter_mut().for_each(normalize_keys),
+        _ => {}
+    }
+}
+
+/// Deserialize a `T` from a string of JSON that is first checked against `limits`
+pub fn from_str_with_limits<T: DeserializeOwned + Serialize>(
+    s: &str,
+    mode: Mode,
+    limits: &Limits,
+) -> Result<T, DeError> {
+    limits.check(s.as_bytes())?;
+    from_str(s, mode)
+}
+
+/// Deserialize a `T` from bytes of JSON that are first checked against `limits`
+pub fn from_slice_with_limits<T: DeserializeOwned + Serialize>(
+    v: &[u8],
+    mode: Mode,
+    limits: &Limits,
+) -> Result<T, DeError> {
+    limits.check(v)?;
+    from_slice(v, mode)
+}
+
+/// A value that [`from_str_lossy()`] could not read and replaced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// The JSON pointer of the value, e.g. `/features/3/attributes/POP`
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Deserialize a `FeatureSet` from a string of JSON, repairing the values that would fail it
+///
+/// Also available as `FeatureSet::from_str_lossy()`.
+///
+/// Bare `NaN` and `Infinity` tokens are read as `null`. The attributes of numeric
+/// fields, as listed in `fields`, that are strings are parsed, or set to `null` if
+/// they are not numbers, e.g. `"null"` or `""`. Geometries that are not valid,
+/// such as empty strings, are set to `null`. Every repair is returned as a
+/// [`Warning`]. The result is then deserialized in [`Mode::Lenient`].
+pub fn from_str_lossy<const N: usize, A: DeserializeOwned + Serialize>(
+    s: &str,
+) -> Result<(FeatureSet<N, A>, Vec<Warning>), DeError> {
+    let mut value: Value = serde_json::from_str(&quote_non_finite(s)).map_err(DeError::Json)?;
+    normalize_keys(&mut value);
+
+    let mut warnings = vec![];
+    repair_non_finite(&mut value, String::new(), &mut warnings);
+    repair_features::<N>(&mut value, &mut warnings);
+
+    Ok((from_value(value, Mode::Lenient)?, warnings))
+}
+
+// the string that bare non-finite tokens are replaced with
+const NON_FINITE: &str = "\0non-finite";
+
+// quotes the `NaN`, `Infinity`, and `-Infinity` tokens that are outside of strings
+fn quote_non_finite(s: &str) -> Cow<'_, str> {
+    const TOKENS: [&str; 3] = ["NaN", "-Infinity", "Infinity"];
+    if !TOKENS.iter().any(|t| s.contains(t)) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut out = String::with_capacity(s.len());
+    let (mut in_string, mut escaped) = (false, false);
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        if !in_string {
+            if let Some(token) = TOKENS.iter().find(|t| rest.starts_with(**t)) {
+                out.push_str(r#""\u0000non-finite""#);
+                rest = &rest[token.len()..];
+                continue;
+            }
+        }
+
+        if c == '"' && !escaped {
+            in_string = !in_string;
+        }
+        escaped = in_string && c == '\\' && !escaped;
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    Cow::Owned(out)
+}
+
+// sets the values that were non-finite tokens to null
+fn repair_non_finite(value: &mut Value, path: String, warnings: &mut Vec<Warning>) {
+    match value {
+        Value::String(s) if s == NON_FINITE => {
+            *value = Value::Null;
+            warnings.push(Warning {
+                path,
+                message: "non-finite number replaced with null".into(),
+            });
+        }
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                repair_non_finite(v, format!("{path}/{key}"), warnings);
+            }
+        }
+        Value::Array(items) => {
+            for (n, v) in items.iter_mut().enumerate() {
+                repair_non_finite(v, format!("{path}/{n}"), warnings);
+            }
+        }
+        _ => {}
+    }
+}
+
+// the field types whose values are numbers
+const NUMERIC_FIELD_TYPES: [&str; 7] = [
+    "esriFieldTypeSmallInteger",
+    "esriFieldTypeInteger",
+    "esriFieldTypeBigInteger",
+    "esriFieldTypeSingle",
+    "esriFieldTypeDouble",
+    "esriFieldTypeDate",
+    "esriFieldTypeOID",
+];
+
+// parses the numeric attributes that are strings and drops invalid geometries
+fn repair_features<const N: usize>(value: &mut Value, warnings: &mut Vec<Warning>) {
+    let numeric: Vec<String> = value
+        .get("fields")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|f| {
+            let field_type = f.get("type").and_then(Value::as_str).unwrap_or_default();
+            NUMERIC_FIELD_TYPES
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(field_type))
+        })
+        .filter_map(|f| f.get("name")?.as_str().map(String::from))
+        .collect();
+
+    let Some(features) = value.get_mut("features").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    for (n, feature) in features.iter_mut().enumerate() {
+        let Some(feature) = feature.as_object_mut() else {
+            continue;
+        };
+
+        if let Some(geometry) = feature.get_mut("geometry").filter(|g| !g.is_null()) {
+            if let Err(e) = EsriGeometry::<N>::deserialize(&*geometry) {
+                *geometry = Value::Null;
+                warnings.push(Warning {
+                    path: format!("/features/{n}/geometry"),
+                    message: format!("invalid geometry replaced with null: {e}"),
+                });
+            }
+        }
+
+        let attributes = feature.get_mut("attributes").and_then(Value::as_object_mut);
+        for (name, v) in attributes.into_iter().flatten() {
+            let Some(s) = v.as_str().filter(|_| numeric.contains(name)) else {
+                continue;
+            };
+            let (repaired, message) = match s.trim().parse::<serde_json::Number>() {
+                Ok(x) => (Value::Number(x), format!("`{s}` parsed as a number")),
+                Err(_) => (Value::Null, format!("`{s}` replaced with null")),
+            };
+            *v = repaired;
+            warnings.push(Warning {
+                path: format!("/features/{n}/attributes/{name}"),
+                message,
+            });
+        }
+    }
+}
+
+/// Limits on the size of a payload. Every limit is `None`, i.e. unlimited, by default.
+///
+/// The limits apply to the whole payload. Vertices are counted in `points`,
+/// `paths`, `rings`, `curvePaths`, and `curveRings` arrays, rings in `paths`, `rings`,
+/// `curvePaths`, and `curveRings` arrays, and features in `features` arrays. Each point
+/// of a curve segment, i.e. its end point and its interior, center, or control points,
+/// counts as a vertex.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub max_vertices: Option<usize>,
+    pub max_rings: Option<usize>,
+    pub max_features: Option<usize>,
+}
+
+/// One of the [`Limits`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Vertices,
+    Rings,
+    Features,
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Vertices => write!(f, "vertices"),
+            Self::Rings => write!(f, "rings"),
+            Self::Features => write!(f, "features"),
+        }
+    }
+}
+
+impl Limits {
+    fn max(&self, limit: Limit) -> Option<usize> {
+        match limit {
+            Limit::Vertices => self.max_vertices,
+            Limit::Rings => self.max_rings,
+            Limit::Features => self.max_features,
+        }
+    }
+
+    /// Check that the JSON in `v` does not exceed the limits
+    ///
+    /// The JSON is scanned without allocating any of its values, so a payload
+    /// that exceeds the limits is rejected before it can exhaust memory.
+    pub fn check(&self, v: &[u8]) -> Result<(), DeError> {
+        if *self == Self::default() {
+            return Ok(());
+        }
+        self.scan(v, &Cell::new([0; 3]))
+    }
+
+    // checks a feature read from a stream, adding its features, rings, and
+    // vertices to those in `counts`
+    pub(crate) fn check_feature(&self, v: &[u8], counts: &Cell<[usize; 3]>) -> Result<(), DeError> {
+        if *self == Self::default() {
+            return Ok(());
+        }
+
+        let mut n = counts.get();
+        n[Limit::Features as usize] += 1;
+        counts.set(n);
+        match self.max_features {
+            Some(max) if n[Limit::Features as usize] > max => {
+                Err(DeError::LimitExceeded(Limit::Features, max))
+            }
+            _ => self.scan(v, counts),
+        }
+    }
+
+    fn scan(&self, v: &[u8], counts: &Cell<[usize; 3]>) -> Result<(), DeError> {
+        let scan = Scan {
+            limits: self,
+            counts,
+            exceeded: &Cell::new(None),
+            ctx: Ctx::Other,
+        };
+        let mut de = serde_json::Deserializer::from_slice(v);
+        match scan.deserialize(&mut de).and_then(|_| de.end()) {
+            Ok(()) => Ok(()),
+            Err(e) => match scan.exceeded.get() {
+                Some((limit, max)) => Err(DeError::LimitExceeded(limit, max)),
+                None => Err(DeError::Json(e)),
+            },
+        }
+    }
+}
+
+// what the elements of the array being scanned are
+#[derive(Clone, Copy)]
+enum Ctx {
+    Features,
+    Rings,
+    Vertices,
+    // a vertex, or a curve segment such as `{"c": [[x, y], [x, y]]}`
+    Vertex,
+    // the end point and interior, center, or control points of a curve segment
+    Segment,
+    Other,
+}
+
+// walks a JSON document, counting features, rings, and vertices
+#[derive(Clone, Copy)]
+struct Scan<'a> {
+    limits: &'a Limits,
+    counts: &'a Cell<[usize; 3]>,
+    exceeded: &'a Cell<Option<(Limit, usize)>>,
+    ctx: Ctx,
+}
+
+impl Scan<'_> {
+    fn count<E: serde::de::Error>(&self, limit: Limit) -> Result<(), E> {
+        let mut counts = self.counts.get();
+        counts[limit as usize] += 1;
+        self.counts.set(counts);
+
+        match self.limits.max(limit) {
+            Some(max) if counts[limit as usize] > max => {
+                self.exceeded.set(Some((limit, max)));
+                Err(E::custom(DeError::LimitExceeded(limit, max)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// the value of a scan is whether the JSON value is an array
+impl<'de> DeserializeSeed<'de> for Scan<'_> {
+    type Value = bool;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Scan<'_> {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_unit<E>(self) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<bool, A::Error> {
+        // the limit counting the elements, whether only arrays are counted, and the
+        // context of the elements
+        let (counted, arrays, ctx) = match self.ctx {
+            Ctx::Features => (Some(Limit::Features), false, Ctx::Other),
+            Ctx::Rings => (Some(Limit::Rings), false, Ctx::Vertices),
+            // curve segments count the points they contain
+            Ctx::Vertices => (Some(Limit::Vertices), true, Ctx::Vertex),
+            Ctx::Segment => (Some(Limit::Vertices), true, Ctx::Other),
+            Ctx::Vertex | Ctx::Other => (None, false, Ctx::Other),
+        };
+
+        while let Some(is_array) = seq.next_element_seed(Scan { ctx, ..self })? {
+            match counted {
+                Some(limit) if is_array || !arrays => self.count(limit)?,
+                _ => {}
+            }
+        }
+        Ok(true)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<bool, A::Error> {
+        let segment = matches!(self.ctx, Ctx::Vertex);
+        while let Some(ctx) = map.next_key_seed(Key { segment })? {
+            map.next_value_seed(Scan { ctx, ..self })?;
+        }
+        Ok(false)
+    }
+}
+
+// maps the key of an object to the context of its value. `segment` is whether the
+// object is a curve segment. Keys are matched regardless of case, as in lenient mode.
+struct Key {
+    segment: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for Key {
+    type Value = Ctx;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Ctx, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl Visitor<'_> for Key {
+    type Value = Ctx;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a key")
+    }
+
+    fn visit_str<E>(self, key: &str) -> Result<Ctx, E> {
+        let is = |keys: &[&str]| keys.iter().any(|k| k.eq_ignore_ascii_case(key));
+        Ok(if is(&["features"]) {
+            Ctx::Features
+        } else if is(&["paths", "rings", "curvePaths", "curveRings"]) {
+            Ctx::Rings
+        } else if is(&["points"]) {
+            Ctx::Vertices
+        } else if self.segment && is(&["a", "b", "c"]) {
+            Ctx::Segment
+        } else {
+            Ctx::Other
+        })
+    }
+}
+
+// collects the paths of the non-null fields of `input` missing from `output`
+fn unknown_fields(input: &Value, output: &Value, path: String, unknown: &mut Vec<String>) {
+    match (input, output) {
+        (Value::Object(i), Value::Object(o)) => {
+            for (key, value) in i.iter().filter(|(_, v)| !v.is_null()) {
+                let path = format!("{path}/{key}");
+                match o.get(key) {
+                    Some(out) => unknown_fields(value, out, path, unknown),
+                    None => unknown.push(path),
+                }
+            }
+        }
+        (Value::Array(i), Value::Array(o)) => {
+            for (n, (value, out)) in i.iter().zip(o).enumerate() {
+                unknown_fields(value, out, format!("{path}/{n}"), unknown);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `serialize_with` functions for the round trip of strict mode
+pub(crate) mod strict {
+    use super::{mode, Mode};
+    use serde::{Serialize, Serializer};
+    use serde_json::{Map, Value};
+
+    // the properties of a field that are kept in its `extra` map
+    const FIELD_PROPERTIES: &[&str] = &[
+        "description",
+        "editable",
+        "length",
+        "modelName",
+        "nullable",
+        "precision",
+        "required",
+        "scale",
+        "visible",
+    ];
+
+    /// The `extra` map of a `Field`. The round trip of strict mode drops unknown
+    /// properties so that they are reported, unless they are preserved.
+    pub fn field_properties<S: Serializer>(
+        extra: &Map<String, Value>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if cfg!(feature = "preserve-unknown") || mode() != Mode::Strict {
+            return extra.serialize(serializer);
+        }
+
+        let known: Map<String, Value> = extra
+            .iter()
+            .filter(|(k, _)| FIELD_PROPERTIES.contains(&k.as_str()))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        known.serialize(serializer)
+    }
+}
+
+/// `deserialize_with` functions that coerce strings in lenient mode
+pub(crate) mod lenient {
+    use super::{mode, Mode};
+    use serde::{Deserialize, Deserializer};
+    use std::str::FromStr;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ValueOrString<T> {
+        Value(T),
+        String(String),
+    }
+
+    fn coerce<T: FromStr, E: serde::de::Error>(x: ValueOrString<T>) -> Result<T, E> {
+        match x {
+            ValueOrString::Value(x) => Ok(x),
+            ValueOrString::String(s) if mode() == Mode::Lenient => s
+                .trim()
+                .parse()
+                .map_err(|_| E::custom(format!("cannot coerce `{s}`"))),
+            ValueOrString::String(s) => Err(E::custom(format!("expected a value, found `{s}`"))),
+        }
+    }
+
+    /// A value or, in lenient mode, a string that parses to the value
+    pub fn value<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + FromStr,
+    {
+        coerce(ValueOrString::deserialize(deserializer)?)
+    }
+
+    const GEOMETRY_TYPES: [&str; 6] = [
+        "esriGeometryPoint",
+        "esriGeometryMultipoint",
+        "esriGeometryPolyline",
+        "esriGeometryPolygon",
+        "esriGeometryEnvelope",
+        "esriGeometryMultiPatch",
+    ];
+
+    /// A `geometryType` whose case is normalized in lenient mode, e.g. `esriGeometryPolygon`
+    /// for `esrigeometrypolygon`. Use with `#[serde(default)]`.
+    pub fn geometry_type<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let geometry_type = Option::<String>::deserialize(deserializer)?;
+        Ok(geometry_type.map(|s| {
+            match GEOMETRY_TYPES.iter().find(|t| t.eq_ignore_ascii_case(&s)) {
+                Some(t) if mode() == Mode::Lenient => t.to_string(),
+                _ => s,
+            }
+        }))
+    }
+
+    /// Like [`value()`] for optional values. Use with `#[serde(default)]`.
+    pub fn option<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + FromStr,
+    {
+        Option::<ValueOrString<T>>::deserialize(deserializer)?
+            .map(coerce)
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::FeatureSet;
+
+    #[test]
+    fn test_strict_unknown_fields() {
+        let json = r#"{
+            "hasZ": "false",
+            "features": [{"geometry": {"x": 1.0, "y": 2.0, "foo": 1}, "attributes": {"a": null}}]
+        }"#;
+
+        assert!(from_str::<FeatureSet<2>>(json, Mode::Lenient).is_ok());
+        assert!(matches!(
+            from_str::<FeatureSet<2>>(json, Mode::Strict),
+            Err(DeError::Json(_))
+        ));
+
+        let json = json.replace("\"false\"", "false");
+        match from_str::<FeatureSet<2>>(&json, Mode::Strict) {
+            Err(DeError::UnknownFields(paths)) => assert_eq!(paths, ["/features/0/geometry/foo"]),
+            x => panic!("expected unknown fields, found {x:?}"),
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "preserve-unknown"))]
+    fn test_strict_unknown_field_properties() {
+        let json = r#"{
+            "fields": [{"name": "NAME", "type": "esriFieldTypeString", "length": 50, "foo": 1}],
+            "features": []
+        }"#;
+
+        let fset = from_str::<FeatureSet<2>>(json, Mode::Lenient).unwrap();
+        assert!(fset.fields.unwrap()[0].extra.contains_key("foo"));
+        match from_str::<FeatureSet<2>>(json, Mode::Strict) {
+            Err(DeError::UnknownFields(paths)) => assert_eq!(paths, ["/fields/0/foo"]),
+            x => panic!("expected unknown fields, found {x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_lenient_key_case() {
+        let json = r#"{
+            "geometrytype": "esrigeometrypoint",
+            "SpatialReference": {"WKID": 102100, "latestWkid": 3857},
+            "features": [{"attributes": {"WKID": 1}}]
+        }"#;
+
+        let fset = from_str::<FeatureSet<2>>(json, Mode::Lenient).unwrap();
+        assert_eq!(fset.geometryType.as_deref(), Some("esriGeometryPoint"));
+        let sr = fset.spatialReference.unwrap();
+        assert_eq!((sr.wkid, sr.latest_wkid), (Some(102100), Some(3857)));
+        let attributes = fset.features[0].attributes.as_ref().unwrap();
+        assert!(attributes.contains_key("WKID"));
+        #[cfg(not(feature = "preserve-unknown"))]
+        assert!(from_str::<FeatureSet<2>>(json, Mode::Strict).is_err());
+    }
+
+    #[test]
+    fn test_from_str_lossy() {
+        let json = r#"{
+            "fields": [
+                {"name": "POP", "type": "esriFieldTypeInteger"},
+                {"name": "NAME", "type": "esriFieldTypeString"}
+            ],
+            "features": [
+                {"geometry": {"x": NaN, "y": 2}, "attributes": {"POP": " 12 ", "NAME": "NaN \" NaN"}},
+                {"geometry": "", "attributes": {"POP": "null", "NAME": "null"}},
+                {"geometry": {"x": 1, "y": 2}, "attributes": {"POP": -Infinity, "NAME": null}}
+            ]
+        }"#;
+        assert!(serde_json::from_str::<FeatureSet<2>>(json).is_err());
+
+        let (fset, warnings) = FeatureSet::<2>::from_str_lossy(json).unwrap();
+        let paths: Vec<_> = warnings.iter().map(|w| w.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/features/0/geometry/x",
+                "/features/2/attributes/POP",
+                "/features/0/geometry",
+                "/features/0/attributes/POP",
+                "/features/1/geometry",
+                "/features/1/attributes/POP",
+            ]
+        );
+
+        let attributes = |i: usize| fset.features[i].attributes.as_ref().unwrap();
+        assert_eq!(attributes(0)["POP"], 12);
+        assert_eq!(attributes(0)["NAME"], "NaN \" NaN");
+        assert!(attributes(1)["POP"].is_null());
+        assert_eq!(attributes(1)["NAME"], "null");
+        assert!(fset.features[0].geometry.is_none());
+        assert!(fset.features[2].geometry.is_some());
+    }
+
+    #[test]
+    fn test_limits() {
+        let json = r#"{"features": [
+            {"geometry": {"paths": [[[0, 0], [1, 1]], [[2, 2], [3, 3]]]}},
+            {"geometry": {"points": [[0, 0], [1, 1]]}}
+        ]}"#;
+        let check = |limits: Limits| limits.check(json.as_bytes());
+
+        assert!(check(Limits::default()).is_ok());
+        assert!(check(Limits {
+            max_vertices: Some(6),
+            max_rings: Some(2),
+            max_features: Some(2)
+        })
+        .is_ok());
+        assert!(matches!(
+            check(Limits {
+                max_vertices: Some(5),
+                ..Default::default()
+            }),
+            Err(DeError::LimitExceeded(Limit::Vertices, 5))
+        ));
+        assert!(matches!(
+            check(Limits {
+                max_rings: Some(1),
+                ..Default::default()
+            }),
+            Err(DeError::LimitExceeded(Limit::Rings, 1))
+        ));
+        assert!(matches!(
+            check(Limits {
+                max_features: Some(1),
+                ..Default::default()
+            }),
+            Err(DeError::LimitExceeded(Limit::Features, 1))
+        ));
+    }
+
+    #[test]
+    fn test_limits_key_case() {
+        // lenient mode renames the keys, so they are limited regardless of case. 4 + 1 vertices.
+        let json = r#"{"Features": [
+            {"geometry": {"RINGS": [[[0, 0], [0, 1], [1, 1], [0, 0]]]}},
+            {"geometry": {"POINTS": [[0, 0]]}}
+        ]}"#;
+        let limits = |vertices, features| Limits {
+            max_vertices: Some(vertices),
+            max_features: Some(features),
+            ..Default::default()
+        };
+
+        assert!(from_str_with_limits::<FeatureSet<2>>(json, Mode::Lenient, &limits(5, 2)).is_ok());
+        assert!(matches!(
+            from_str_with_limits::<FeatureSet<2>>(json, Mode::Lenient, &limits(4, 2)),
+            Err(DeError::LimitExceeded(Limit::Vertices, 4))
+        ));
+        assert!(matches!(
+            limits(5, 1).check(json.as_bytes()),
+            Err(DeError::LimitExceeded(Limit::Features, 1))
+        ));
+    }
+
+    #[test]
+    fn test_limits_curves() {
+        // 1 + 2 + 3 + 2 vertices in 2 rings
+        let json = r#"{"geometry": {"curveRings": [
+            [[0, 0], {"c": [[2, 0], [1, 1]]}],
+            [{"b": [[3, 3], [4, 4], [5, 5]]}, {"a": [[0, 0], [1, 0], 0, 1]}]
+        ]}}"#;
+        let check = |limits: Limits| limits.check(json.as_bytes());
+
+        assert!(check(Limits {
+            max_vertices: Some(8),
+            max_rings: Some(2),
+            ..Default::default()
+        })
+        .is_ok());
+        assert!(matches!(
+            check(Limits {
+                max_vertices: Some(7),
+                ..Default::default()
+            }),
+            Err(DeError::LimitExceeded(Limit::Vertices, 7))
+        ));
+        assert!(matches!(
+            check(Limits {
+                max_rings: Some(1),
+                ..Default::default()
+            }),
+            Err(DeError::LimitExceeded(Limit::Rings, 1))
+        ));
+    }
+}
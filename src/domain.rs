@@ -0,0 +1,173 @@
+//! Attribute domains of fields.
+//!
+//! The fields of a layer may have a domain restricting their values. Coded value
+//! domains map codes, e.g. `1`, to names, e.g. `"Residential"`, and range domains
+//! restrict numbers to a range. [`FeatureSet::decode_domains()`] replaces the codes
+//! of the attributes of a `FeatureSet` with their names and
+//! [`FeatureSet::encode_domains()`] replaces names with codes before edits.
+//!
+//! ```
+//! use serde_esri::features::FeatureSet;
+//!
+//! let json = r#"{
+//!     "fields": [{
+//!         "name": "ZONE",
+//!         "type": "esriFieldTypeSmallInteger",
+//!         "domain": {
+//!             "type": "codedValue",
+//!             "name": "Zoning",
+//!             "codedValues": [{"name": "Residential", "code": 1}, {"name": "Commercial", "code": 2}]
+//!         }
+//!     }],
+//!     "features": [{"attributes": {"ZONE": 1}}]
+//! }"#;
+//! let mut fset: FeatureSet<2> = serde_json::from_str(json).unwrap();
+//!
+//! fset.decode_domains();
+//! assert_eq!(fset.features[0].attributes.as_ref().unwrap()["ZONE"], "Residential");
+//!
+//! fset.encode_domains().unwrap();
+//! assert_eq!(fset.features[0].attributes.as_ref().unwrap()["ZONE"], 1);
+//! ```
+use crate::features::{FeatureSet, Field};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+
+/// The domain of a field
+///
+/// `domain_type` is `codedValue`, `range`, or `inherited`. Properties without a field
+/// of their own, e.g. `mergePolicy`, are kept in `extra`.
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Domain {
+    #[serde(rename = "type")]
+    pub domain_type: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub codedValues: Option<Vec<CodedValue>>,
+    /// The minimum and maximum values of a range domain
+    pub range: Option<[Value; 2]>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A code and its name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodedValue {
+    pub name: String,
+    pub code: Value,
+}
+
+// codes are equal regardless of how numbers are written, e.g. `1` and `1.0`
+fn same_code(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => a == b,
+    }
+}
+
+impl Domain {
+    /// Whether the domain maps codes to names
+    pub fn is_coded_value(&self) -> bool {
+        self.domain_type == "codedValue"
+    }
+
+    /// The name of `code` in a coded value domain
+    pub fn name_of(&self, code: &Value) -> Option<&str> {
+        self.codedValues
+            .iter()
+            .flatten()
+            .find(|cv| same_code(&cv.code, code))
+            .map(|cv| cv.name.as_str())
+    }
+
+    /// The code named `name` in a coded value domain
+    pub fn code_of(&self, name: &str) -> Option<&Value> {
+        self.codedValues
+            .iter()
+            .flatten()
+            .find(|cv| cv.name == name)
+            .map(|cv| &cv.code)
+    }
+}
+
+/// A value that is neither a code nor the name of a code of the domain of its field
+#[derive(Debug, Clone, PartialEq)]
+pub struct DomainError {
+    pub field: String,
+    pub value: Value,
+}
+
+impl std::fmt::Display for DomainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is not a value of the domain of field `{}`",
+            self.value, self.field
+        )
+    }
+}
+
+impl std::error::Error for DomainError {}
+
+impl<const N: usize> FeatureSet<N> {
+    // the fields with coded value domains
+    fn coded_value_fields(&self) -> Vec<(String, Domain)> {
+        self.fields
+            .iter()
+            .flatten()
+            .filter_map(|Field { name, domain, .. }| {
+                let domain = domain.as_ref().filter(|d| d.is_coded_value())?;
+                Some((name.clone(), domain.clone()))
+            })
+            .collect()
+    }
+
+    /// Replace the codes of fields with coded value domains with their names
+    ///
+    /// Values that are not codes of the domain, such as null, are left unchanged.
+    pub fn decode_domains(&mut self) {
+        let fields = self.coded_value_fields();
+        let attributes = self.features.iter_mut().flat_map(|f| &mut f.attributes);
+        for attributes in attributes {
+            for (field, domain) in &fields {
+                if let Some(value) = attributes.get_mut(field) {
+                    if let Some(name) = domain.name_of(value) {
+                        *value = Value::from(name);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replace the names of codes of fields with coded value domains with their codes,
+    /// the inverse of [`FeatureSet::decode_domains()`]
+    ///
+    /// Codes and null values are left unchanged. Fails on the first value that is neither.
+    pub fn encode_domains(&mut self) -> Result<(), DomainError> {
+        let fields = self.coded_value_fields();
+        let attributes = self.features.iter_mut().flat_map(|f| &mut f.attributes);
+        for attributes in attributes {
+            for (field, domain) in &fields {
+                let Some(value) = attributes.get_mut(field) else {
+                    continue;
+                };
+                if value.is_null() || domain.name_of(value).is_some() {
+                    continue;
+                }
+                match value.as_str().and_then(|name| domain.code_of(name)) {
+                    Some(code) => *value = code.clone(),
+                    None => {
+                        return Err(DomainError {
+                            field: field.clone(),
+                            value: value.clone(),
+                        })
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,377 @@
+//! Classification of the errors returned by the API clients.
+//!
+//! Every client error implements [`ClassifyError`], which sorts it into an
+//! [`ErrorKind`]. A [`RetryPolicy`] retries an operation while its errors are
+//! [`ErrorKind::Retryable`], so the bulk uploader and paginated queries treat
+//! errors the same way.
+use std::time::Duration;
+
+/// How an error should be handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A transient failure, e.g. a timeout, rate limiting (429), or a server error (5xx). The request may succeed if repeated.
+    Retryable,
+    /// The token is missing, invalid, or expired (498, 499) or lacks permission (401, 403). The Places clients repeat the request once with a new token from their `TokenProvider`, if they have one. Otherwise the request fails again until it is sent with a new token.
+    Auth,
+    /// The request is invalid, e.g. a bad parameter (400), and will fail again if repeated.
+    Fatal,
+}
+
+impl ErrorKind {
+    /// Classify an HTTP status code or an Esri error code
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            429 | 500..=599 => Self::Retryable,
+            401 | 403 | 498 | 499 => Self::Auth,
+            _ => Self::Fatal,
+        }
+    }
+
+    /// Classify a transport error. Timeouts and connection failures are retryable.
+    #[cfg(any(
+        feature = "places-client",
+        feature = "feature-service-client",
+        feature = "geometry-service-client",
+        feature = "geocode-client",
+        feature = "routing-client",
+        feature = "image-service-client",
+        feature = "tile-client"
+    ))]
+    pub fn from_request_error(e: &reqwest::Error) -> Self {
+        // `fetch` doesn't tell connection failures apart from other errors
+        #[cfg(target_arch = "wasm32")]
+        let connect = false;
+        #[cfg(not(target_arch = "wasm32"))]
+        let connect = e.is_connect();
+
+        match e.status() {
+            Some(status) => Self::from_code(status.as_u16().into()),
+            None if e.is_timeout() || connect => Self::Retryable,
+            None => Self::Fatal,
+        }
+    }
+}
+
+// the length of the body snippets of errors, in characters
+#[cfg(any(feature = "places-client", feature = "geometry-service-client"))]
+const SNIPPET_LEN: usize = 200;
+
+// the start of a response body, for errors
+#[cfg(any(feature = "places-client", feature = "geometry-service-client"))]
+pub(crate) fn snippet(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+    match body.char_indices().nth(SNIPPET_LEN) {
+        Some((i, _)) => format!("{}...", &body[..i]),
+        None => body.into_owned(),
+    }
+}
+
+/// An error that can be classified into an [`ErrorKind`]
+pub trait ClassifyError {
+    fn kind(&self) -> ErrorKind;
+
+    /// `true` if repeating the request may succeed
+    fn is_retryable(&self) -> bool {
+        self.kind() == ErrorKind::Retryable
+    }
+
+    /// `true` if the request failed because of its token
+    fn is_auth(&self) -> bool {
+        self.kind() == ErrorKind::Auth
+    }
+}
+
+/// Retries operations that fail with a retryable error, with exponential backoff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The number of times an operation is retried
+    pub max_retries: u32,
+    /// The delay before the first retry. The delay doubles with each retry.
+    pub delay: Duration,
+    /// The longest delay before a retry. Longer backoffs, and longer delays requested
+    /// by the server with a `Retry-After` header, are shortened to it.
+    pub max_delay: Duration,
+    /// Whether each delay is shortened by a random amount of up to half, so that
+    /// clients which failed together don't retry together
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, delay: Duration) -> Self {
+        Self {
+            max_retries,
+            delay,
+            ..Self::default()
+        }
+    }
+
+    /// Set the longest delay before a retry
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set whether the delays are randomized
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The delay before retry number `retry`, starting at 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self
+            .delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        if !self.jitter {
+            return delay;
+        }
+
+        delay.mul_f64(0.5 + fastrand::f64() / 2.0)
+    }
+
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self::new(0, Duration::ZERO)
+    }
+
+    /// Run `f` until it succeeds, fails with an error that isn't retryable, or
+    /// runs out of retries. `f` is passed the attempt number, starting at 1.
+    pub fn run<T, E, F>(&self, mut f: F) -> Result<T, E>
+    where
+        E: ClassifyError,
+        F: FnMut(u32) -> Result<T, E>,
+    {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match f(attempt) {
+                Err(e) => match self.delay(attempt, &e, None) {
+                    Some(delay) => std::thread::sleep(delay),
+                    None => return Err(e),
+                },
+                Ok(x) => return Ok(x),
+            }
+        }
+    }
+
+    /// Run `f` like [`RetryPolicy::run()`]. Its errors come with the delay requested by
+    /// the server, if any, e.g. with a `Retry-After` header, which is waited instead of
+    /// the backoff of the policy.
+    #[cfg(all(feature = "places-client", not(target_arch = "wasm32")))]
+    pub fn run_after<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        E: ClassifyError + std::error::Error,
+        F: FnMut(u32) -> Result<T, (E, Option<Duration>)>,
+    {
+        self.run_refreshing(f, || false)
+    }
+
+    // runs `f` like `run_after()`. After the first auth error, `f` is repeated at once if
+    // `refresh` discarded the rejected token. The repeat doesn't count as a retry.
+    #[cfg(all(feature = "places-client", not(target_arch = "wasm32")))]
+    pub(crate) fn run_refreshing<T, E, F, R>(&self, mut f: F, mut refresh: R) -> Result<T, E>
+    where
+        E: ClassifyError + std::error::Error,
+        F: FnMut(u32) -> Result<T, (E, Option<Duration>)>,
+        R: FnMut() -> bool,
+    {
+        let mut attempt = 0;
+        let mut refreshed = false;
+        loop {
+            attempt += 1;
+            let (e, retry_after) = match f(attempt) {
+                Ok(x) => return Ok(x),
+                Err(e) => e,
+            };
+            if e.is_auth() && !refreshed && refresh() {
+                crate::trace::retry(attempt, Duration::ZERO, &e);
+                refreshed = true;
+                continue;
+            }
+            match self.delay(attempt - u32::from(refreshed), &e, retry_after) {
+                Some(delay) => {
+                    crate::trace::retry(attempt, delay, &e);
+                    std::thread::sleep(delay);
+                }
+                None => return Err(e),
+            }
+        }
+    }
+
+    /// The async counterpart of [`RetryPolicy::run_after()`], which waits without blocking
+    /// the tokio runtime
+    ///
+    /// Errors are not retried in wasm builds, which have no timer to wait with.
+    #[cfg(feature = "places-client-async")]
+    pub async fn run_async<T, E, F, Fut>(&self, f: F) -> Result<T, E>
+    where
+        E: ClassifyError + std::error::Error,
+        F: FnMut(u32) -> Fut,
+        Fut: std::future::Future<Output = Result<T, (E, Option<Duration>)>>,
+    {
+        self.run_async_refreshing(f, || false).await
+    }
+
+    // the async counterpart of `run_refreshing()`. The repeat after an auth error needs
+    // no timer, so it is made in wasm builds too.
+    #[cfg(feature = "places-client-async")]
+    pub(crate) async fn run_async_refreshing<T, E, F, Fut, R>(
+        &self,
+        mut f: F,
+        mut refresh: R,
+    ) -> Result<T, E>
+    where
+        E: ClassifyError + std::error::Error,
+        F: FnMut(u32) -> Fut,
+        Fut: std::future::Future<Output = Result<T, (E, Option<Duration>)>>,
+        R: FnMut() -> bool,
+    {
+        let mut attempt = 0;
+        let mut refreshed = false;
+        loop {
+            attempt += 1;
+            let (e, retry_after) = match f(attempt).await {
+                Ok(x) => return Ok(x),
+                Err(e) => e,
+            };
+            if e.is_auth() && !refreshed && refresh() {
+                crate::trace::retry(attempt, Duration::ZERO, &e);
+                refreshed = true;
+                continue;
+            }
+
+            #[cfg(target_arch = "wasm32")]
+            {
+                let _ = retry_after;
+                return Err(e);
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            match self.delay(attempt - u32::from(refreshed), &e, retry_after) {
+                Some(delay) => {
+                    crate::trace::retry(attempt, delay, &e);
+                    tokio::time::sleep(delay).await;
+                }
+                None => return Err(e),
+            }
+        }
+    }
+
+    // the delay before retry number `retry` after `error`, or `None` if it is not retried.
+    // A delay requested by the server takes precedence over the backoff, up to `max_delay`.
+    fn delay<E: ClassifyError>(
+        &self,
+        retry: u32,
+        error: &E,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        (retry <= self.max_retries && error.is_retryable()).then(|| match retry_after {
+            Some(delay) => delay.min(self.max_delay),
+            None => self.backoff(retry),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Code(i64);
+
+    impl ClassifyError for Code {
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::from_code(self.0)
+        }
+    }
+
+    impl std::fmt::Display for Code {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl std::error::Error for Code {}
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::new(2, Duration::ZERO);
+
+        let mut calls = 0;
+        let res: Result<(), Code> = policy.run(|_| {
+            calls += 1;
+            Err(Code(504))
+        });
+        assert!(res.is_err());
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let res: Result<(), Code> = policy.run(|_| {
+            calls += 1;
+            Err(Code(498))
+        });
+        assert!(res.unwrap_err().is_auth());
+        assert_eq!(calls, 1);
+
+        let res = policy.run(|attempt| {
+            if attempt < 2 {
+                Err(Code(429))
+            } else {
+                Ok(attempt)
+            }
+        });
+        assert_eq!(res.unwrap(), 2);
+    }
+
+    #[cfg(all(feature = "places-client-async", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_run_async() {
+        // the delay requested by the server overrides the long backoff
+        let policy = RetryPolicy::new(2, Duration::from_secs(60));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        let mut calls = 0;
+        let res: Result<(), Code> = runtime.block_on(policy.run_async(|_| {
+            calls += 1;
+            async { Err((Code(429), Some(Duration::ZERO))) }
+        }));
+        assert!(res.unwrap_err().is_retryable());
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new(3, Duration::from_secs(1)).with_jitter(false);
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+
+        let delay = policy.with_jitter(true).backoff(3);
+        assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(4));
+
+        // long backoffs and delays requested by the server are shortened
+        let policy = policy.with_max_delay(Duration::from_secs(3));
+        assert_eq!(policy.backoff(3), Duration::from_secs(3));
+        assert_eq!(policy.backoff(100), Duration::from_secs(3));
+        let retry_after = Some(Duration::from_secs(3600));
+        assert_eq!(
+            policy.delay(1, &Code(429), retry_after),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(policy.delay(1, &Code(400), retry_after), None);
+    }
+}
@@ -0,0 +1,1084 @@
+//! SQL-92 `where` clauses evaluated against attributes.
+//!
+//! Query `where` clauses and the `definitionExpression` of a layer use a subset of
+//! SQL-92. [`Expression::parse()`] parses one, and [`Expression::matches()`] tests
+//! the attributes of a feature against it, e.g. to filter a cached `FeatureSet` the
+//! way a service would. Parsing a clause and [`Expression::validate_fields()`]
+//! catch mistakes before a query is sent.
+//!
+//! The supported syntax is:
+//!
+//! - `AND`, `OR`, `NOT`, and parentheses
+//! - `=`, `<>`, `!=`, `<`, `<=`, `>`, `>=`, and `[NOT] BETWEEN ... AND ...`
+//! - `[NOT] IN (...)`, `[NOT] LIKE` with `%` and `_` and an optional `ESCAPE`
+//! - `IS [NOT] NULL`
+//! - `+`, `-`, `*`, `/`, and the `UPPER`, `LOWER`, and `TRIM` functions
+//! - strings in single quotes, numbers, `TRUE`, `FALSE`, `NULL`, and
+//!   `DATE 'YYYY-MM-DD'` and `TIMESTAMP 'YYYY-MM-DD HH:MI:SS'` literals
+//!
+//! Clauses may nest parentheses, `NOT`, and signs, and chain `AND`, `OR`, and arithmetic
+//! operators, up to [`MAX_DEPTH`] levels deep in total.
+//!
+//! Field names are case-insensitive and may be double quoted. Dates are compared as
+//! milliseconds since the epoch, as they are stored in attributes. Comparisons with
+//! `NULL` are unknown, and features are only matched when the clause is true.
+//!
+//! ```
+//! use serde_esri::expression::Expression;
+//! use serde_json::json;
+//!
+//! let expr = Expression::parse("STATUS = 'OPEN' AND pop > 1000 AND name LIKE 'San%'").unwrap();
+//!
+//! let attributes = json!({"status": "OPEN", "POP": 1500, "NAME": "San Diego"});
+//! assert!(expr.matches(attributes.as_object().unwrap()).unwrap());
+//!
+//! let attributes = json!({"status": "CLOSED", "POP": 1500, "NAME": "San Diego"});
+//! assert!(!expr.matches(attributes.as_object().unwrap()).unwrap());
+//! ```
+use crate::features::{Feature, FeatureSet, Field};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// Errors that can occur when parsing or evaluating an [`Expression`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    /// The clause is not valid SQL at `position`, a byte offset
+    Syntax { position: usize, message: String },
+    /// The clause refers to a field that is not in the attributes or fields
+    UnknownField(String),
+    /// An operator was applied to values it does not support
+    TypeMismatch(String),
+}
+
+impl std::fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Syntax { position, message } => {
+                write!(f, "syntax error at position {position}: {message}")
+            }
+            Self::UnknownField(name) => write!(f, "unknown field `{name}`"),
+            Self::TypeMismatch(message) => write!(f, "type mismatch: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+/// A comparison operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+/// An arithmetic operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithmeticOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+/// A scalar function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Upper,
+    Lower,
+    Trim,
+}
+
+/// A parsed `where` clause
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    /// A string, number, boolean, or `NULL`. Dates are milliseconds since the epoch.
+    Literal(Value),
+    Field(String),
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Compare {
+        left: Box<Expression>,
+        op: CompareOp,
+        right: Box<Expression>,
+    },
+    Arithmetic {
+        left: Box<Expression>,
+        op: ArithmeticOp,
+        right: Box<Expression>,
+    },
+    Negate(Box<Expression>),
+    Function(Function, Box<Expression>),
+    In {
+        expr: Box<Expression>,
+        list: Vec<Expression>,
+        negated: bool,
+    },
+    Like {
+        expr: Box<Expression>,
+        pattern: Box<Expression>,
+        escape: Option<char>,
+        negated: bool,
+    },
+    Between {
+        expr: Box<Expression>,
+        low: Box<Expression>,
+        high: Box<Expression>,
+        negated: bool,
+    },
+    IsNull {
+        expr: Box<Expression>,
+        negated: bool,
+    },
+}
+
+// Tokens
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    QuotedIdent(String),
+    Number(f64),
+    Str(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn syntax(position: usize, message: impl Into<String>) -> ExpressionError {
+    ExpressionError::Syntax {
+        position,
+        message: message.into(),
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>, ExpressionError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' | ')' | ',' | '+' | '-' | '*' | '/' | '=' => {
+                chars.next();
+                match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    ',' => Token::Comma,
+                    '+' => Token::Op("+"),
+                    '-' => Token::Op("-"),
+                    '*' => Token::Op("*"),
+                    '/' => Token::Op("/"),
+                    _ => Token::Op("="),
+                }
+            }
+            '<' | '>' | '!' => {
+                chars.next();
+                let next = chars.peek().map(|(_, c)| *c);
+                let op = match (c, next) {
+                    ('<', Some('=')) => "<=",
+                    ('<', Some('>')) => "<>",
+                    ('>', Some('=')) => ">=",
+                    ('!', Some('=')) => "<>",
+                    ('<', _) => "<",
+                    ('>', _) => ">",
+                    _ => return Err(syntax(start, "expected `!=`")),
+                };
+                if op.len() == 2 {
+                    chars.next();
+                }
+                Token::Op(op)
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        // quotes are escaped by doubling them
+                        Some((_, q)) if q == c => match chars.peek() {
+                            Some((_, q)) if *q == c => {
+                                text.push(c);
+                                chars.next();
+                            }
+                            _ => break,
+                        },
+                        Some((_, ch)) => text.push(ch),
+                        None => return Err(syntax(start, "unterminated quote")),
+                    }
+                }
+                match c {
+                    '\'' => Token::Str(text),
+                    _ => Token::QuotedIdent(text),
+                }
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut end = start;
+                let mut prev = c;
+                while let Some(&(i, ch)) = chars.peek() {
+                    let exponent_sign = (ch == '+' || ch == '-') && matches!(prev, 'e' | 'E');
+                    if !(ch.is_ascii_alphanumeric() || ch == '.' || exponent_sign) {
+                        break;
+                    }
+                    end = i + ch.len_utf8();
+                    prev = ch;
+                    chars.next();
+                }
+                let text = &s[start..end];
+                let number = text
+                    .parse()
+                    .map_err(|_| syntax(start, format!("invalid number `{text}`")))?;
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start;
+                while let Some(&(i, ch)) = chars.peek() {
+                    if !(ch.is_alphanumeric() || ch == '_' || ch == '.') {
+                        break;
+                    }
+                    end = i + ch.len_utf8();
+                    chars.next();
+                }
+                Token::Ident(s[start..end].to_string())
+            }
+            c => return Err(syntax(start, format!("unexpected character `{c}`"))),
+        };
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+// Parser
+
+/// The deepest a clause may be nested, so that untrusted clauses can't exhaust the
+/// stack when they are parsed or evaluated
+pub const MAX_DEPTH: usize = 100;
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    len: usize,
+    // the depth of the expression being parsed
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.len, |(i, _)| *i)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(_, t)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ExpressionError> {
+        Err(syntax(self.position(), message))
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(s)) if s.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ExpressionError> {
+        match self.eat_keyword(keyword) {
+            true => Ok(()),
+            false => self.error(format!("expected `{keyword}`")),
+        }
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), ExpressionError> {
+        match self.peek() == Some(&token) {
+            true => {
+                self.pos += 1;
+                Ok(())
+            }
+            false => self.error(format!("expected {what}")),
+        }
+    }
+
+    // descends a level into the expression, failing if the clause is nested too deeply
+    fn descend(&mut self) -> Result<(), ExpressionError> {
+        self.depth += 1;
+        match self.depth > MAX_DEPTH {
+            true => self.error("the clause is nested too deeply"),
+            false => Ok(()),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expression, ExpressionError> {
+        let depth = self.depth;
+        let mut left = self.and()?;
+        while self.eat_keyword("OR") {
+            self.descend()?;
+            left = Expression::Or(Box::new(left), Box::new(self.and()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expression, ExpressionError> {
+        let depth = self.depth;
+        let mut left = self.not()?;
+        while self.eat_keyword("AND") {
+            self.descend()?;
+            left = Expression::And(Box::new(left), Box::new(self.not()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expression, ExpressionError> {
+        match self.eat_keyword("NOT") {
+            true => {
+                self.descend()?;
+                let expr = Expression::Not(Box::new(self.not()?));
+                self.depth -= 1;
+                Ok(expr)
+            }
+            false => self.predicate(),
+        }
+    }
+
+    fn predicate(&mut self) -> Result<Expression, ExpressionError> {
+        let expr = Box::new(self.additive()?);
+
+        if let Some(Token::Op(op)) = self.peek() {
+            let op = match *op {
+                "=" => CompareOp::Eq,
+                "<>" => CompareOp::NotEq,
+                "<" => CompareOp::Lt,
+                "<=" => CompareOp::LtEq,
+                ">" => CompareOp::Gt,
+                ">=" => CompareOp::GtEq,
+                _ => return Ok(*expr),
+            };
+            self.pos += 1;
+            let right = Box::new(self.additive()?);
+            return Ok(Expression::Compare {
+                left: expr,
+                op,
+                right,
+            });
+        }
+
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expression::IsNull { expr, negated });
+        }
+
+        let negated = self.eat_keyword("NOT");
+        if self.eat_keyword("IN") {
+            self.expect(Token::LParen, "`(`")?;
+            let mut list = vec![self.additive()?];
+            while self.peek() == Some(&Token::Comma) {
+                self.pos += 1;
+                list.push(self.additive()?);
+            }
+            self.expect(Token::RParen, "`)`")?;
+            Ok(Expression::In {
+                expr,
+                list,
+                negated,
+            })
+        } else if self.eat_keyword("LIKE") {
+            let pattern = Box::new(self.additive()?);
+            let escape = match self.eat_keyword("ESCAPE") {
+                false => None,
+                true => match self.next() {
+                    Some(Token::Str(s)) if s.chars().count() == 1 => s.chars().next(),
+                    _ => {
+                        self.pos -= 1;
+                        return self.error("expected a single character escape");
+                    }
+                },
+            };
+            Ok(Expression::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+            })
+        } else if self.eat_keyword("BETWEEN") {
+            let low = Box::new(self.additive()?);
+            self.expect_keyword("AND")?;
+            let high = Box::new(self.additive()?);
+            Ok(Expression::Between {
+                expr,
+                low,
+                high,
+                negated,
+            })
+        } else if negated {
+            self.error("expected `IN`, `LIKE`, or `BETWEEN`")
+        } else {
+            Ok(*expr)
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expression, ExpressionError> {
+        let depth = self.depth;
+        let mut left = self.multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op("+")) => ArithmeticOp::Add,
+                Some(Token::Op("-")) => ArithmeticOp::Subtract,
+                _ => break,
+            };
+            self.pos += 1;
+            self.descend()?;
+            let right = Box::new(self.multiplicative()?);
+            left = Expression::Arithmetic {
+                left: Box::new(left),
+                op,
+                right,
+            };
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expression, ExpressionError> {
+        let depth = self.depth;
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op("*")) => ArithmeticOp::Multiply,
+                Some(Token::Op("/")) => ArithmeticOp::Divide,
+                _ => break,
+            };
+            self.pos += 1;
+            self.descend()?;
+            let right = Box::new(self.unary()?);
+            left = Expression::Arithmetic {
+                left: Box::new(left),
+                op,
+                right,
+            };
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expression, ExpressionError> {
+        match self.peek() {
+            Some(Token::Op("-")) => {
+                self.pos += 1;
+                self.descend()?;
+                let expr = Expression::Negate(Box::new(self.unary()?));
+                self.depth -= 1;
+                Ok(expr)
+            }
+            Some(Token::Op("+")) => {
+                self.pos += 1;
+                self.descend()?;
+                let expr = self.unary()?;
+                self.depth -= 1;
+                Ok(expr)
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression, ExpressionError> {
+        let position = self.position();
+        let expr = match self.next() {
+            Some(Token::Number(n)) => Expression::Literal(Value::from(n)),
+            Some(Token::Str(s)) => Expression::Literal(Value::from(s)),
+            Some(Token::QuotedIdent(name)) => Expression::Field(name),
+            Some(Token::LParen) => {
+                self.descend()?;
+                let expr = self.or()?;
+                self.expect(Token::RParen, "`)`")?;
+                self.depth -= 1;
+                expr
+            }
+            Some(Token::Ident(word)) => match word.to_ascii_uppercase().as_str() {
+                "NULL" => Expression::Literal(Value::Null),
+                "TRUE" => Expression::Literal(Value::Bool(true)),
+                "FALSE" => Expression::Literal(Value::Bool(false)),
+                kind @ ("DATE" | "TIMESTAMP") if matches!(self.peek(), Some(Token::Str(_))) => {
+                    let position = self.position();
+                    let Some(Token::Str(text)) = self.next() else {
+                        unreachable!()
+                    };
+                    let millis = parse_timestamp(&text).ok_or_else(|| {
+                        syntax(
+                            position,
+                            format!("invalid {} `{text}`", kind.to_lowercase()),
+                        )
+                    })?;
+                    Expression::Literal(Value::from(millis))
+                }
+                name @ ("UPPER" | "LOWER" | "TRIM") if self.peek() == Some(&Token::LParen) => {
+                    let function = match name {
+                        "UPPER" => Function::Upper,
+                        "LOWER" => Function::Lower,
+                        _ => Function::Trim,
+                    };
+                    self.pos += 1;
+                    self.descend()?;
+                    let arg = self.or()?;
+                    self.expect(Token::RParen, "`)`")?;
+                    self.depth -= 1;
+                    Expression::Function(function, Box::new(arg))
+                }
+                "AND" | "OR" | "NOT" | "IN" | "LIKE" | "IS" | "BETWEEN" | "ESCAPE" => {
+                    return Err(syntax(position, format!("unexpected `{word}`")))
+                }
+                _ => Expression::Field(word),
+            },
+            Some(_) => return Err(syntax(position, "expected a value")),
+            None => return Err(syntax(position, "unexpected end of the clause")),
+        };
+        Ok(expr)
+    }
+}
+
+// milliseconds since the epoch of `YYYY-MM-DD` followed by an optional `HH:MI:SS[.fff]`
+fn parse_timestamp(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (date, time) = match s.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time.trim())),
+        None => (s, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(0..=9999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // days from the civil date
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let seconds = match time {
+        None => 0.0,
+        Some(time) => {
+            let mut parts = time.splitn(3, ':');
+            let hours: f64 = parts.next()?.parse().ok()?;
+            let minutes: f64 = parts.next().unwrap_or("0").parse().ok()?;
+            let seconds: f64 = parts.next().unwrap_or("0").parse().ok()?;
+            hours * 3600.0 + minutes * 60.0 + seconds
+        }
+    };
+
+    // the time may be any number of hours
+    days.checked_mul(86_400_000)?
+        .checked_add((seconds * 1000.0).round() as i64)
+}
+
+// Evaluation
+
+// a character of a `LIKE` pattern
+#[derive(Clone, Copy, PartialEq)]
+enum Wildcard {
+    // `%`
+    Any,
+    // `_`
+    One,
+    Literal(char),
+}
+
+// `%` matches any number of characters and `_` a single one.
+// Matched in linear passes that backtrack only to the last `%`.
+fn like(text: &[char], pattern: &[char], escape: Option<char>) -> bool {
+    let mut tokens = Vec::with_capacity(pattern.len());
+    let mut chars = pattern.iter();
+    while let Some(&c) = chars.next() {
+        tokens.push(match c {
+            c if Some(c) == escape => match chars.next() {
+                Some(&literal) => Wildcard::Literal(literal),
+                None => return false,
+            },
+            '%' => Wildcard::Any,
+            '_' => Wildcard::One,
+            c => Wildcard::Literal(c),
+        });
+    }
+
+    let (mut t, mut p) = (0, 0);
+    // the token after the last `%` and the text it was matched from
+    let mut backtrack = None;
+    while t < text.len() {
+        match tokens.get(p) {
+            Some(Wildcard::Any) => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(Wildcard::One) => (t, p) = (t + 1, p + 1),
+            Some(Wildcard::Literal(c)) if *c == text[t] => (t, p) = (t + 1, p + 1),
+            _ => match backtrack {
+                Some((after, from)) => {
+                    (t, p) = (from + 1, after);
+                    backtrack = Some((after, from + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    tokens[p..].iter().all(|token| *token == Wildcard::Any)
+}
+
+fn as_number(x: &Value) -> Option<f64> {
+    match x {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(f64::from(*b as u8)),
+        _ => None,
+    }
+}
+
+// compares two non-null values, numerically unless both are strings
+fn compare(a: &Value, b: &Value) -> Result<Ordering, ExpressionError> {
+    if let (Value::String(a), Value::String(b)) = (a, b) {
+        return Ok(a.cmp(b));
+    }
+    match (as_number(a), as_number(b)) {
+        (Some(a), Some(b)) => a
+            .partial_cmp(&b)
+            .ok_or_else(|| ExpressionError::TypeMismatch("cannot compare NaN".into())),
+        _ => Err(ExpressionError::TypeMismatch(format!(
+            "cannot compare {a} and {b}"
+        ))),
+    }
+}
+
+// `None` is unknown
+fn and(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+fn or(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Expression {
+    /// Parse a `where` clause
+    pub fn parse(s: &str) -> Result<Self, ExpressionError> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            len: s.len(),
+            depth: 0,
+        };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(_) => parser.error("expected the end of the clause"),
+        }
+    }
+
+    /// The names of the fields the expression refers to, in order of appearance
+    pub fn fields(&self) -> Vec<&str> {
+        let mut fields = Vec::new();
+        self.visit_fields(&mut |name| fields.push(name));
+        fields
+    }
+
+    fn visit_fields<'a>(&'a self, f: &mut impl FnMut(&'a str)) {
+        match self {
+            Self::Literal(_) => {}
+            Self::Field(name) => f(name),
+            Self::Not(e) | Self::Negate(e) | Self::Function(_, e) => e.visit_fields(f),
+            Self::IsNull { expr, .. } => expr.visit_fields(f),
+            Self::And(a, b) | Self::Or(a, b) => {
+                a.visit_fields(f);
+                b.visit_fields(f);
+            }
+            Self::Compare { left, right, .. } | Self::Arithmetic { left, right, .. } => {
+                left.visit_fields(f);
+                right.visit_fields(f);
+            }
+            Self::In { expr, list, .. } => {
+                expr.visit_fields(f);
+                list.iter().for_each(|e| e.visit_fields(f));
+            }
+            Self::Like { expr, pattern, .. } => {
+                expr.visit_fields(f);
+                pattern.visit_fields(f);
+            }
+            Self::Between {
+                expr, low, high, ..
+            } => {
+                expr.visit_fields(f);
+                low.visit_fields(f);
+                high.visit_fields(f);
+            }
+        }
+    }
+
+    /// Check that every field the expression refers to is one of `fields`
+    ///
+    /// ```
+    /// use serde_esri::expression::{Expression, ExpressionError};
+    /// use serde_esri::features::Field;
+    ///
+    /// let fields: Vec<Field> = serde_json::from_str(r#"[{"name": "STATUS", "type": "esriFieldTypeString"}]"#).unwrap();
+    /// let expr = Expression::parse("status = 'OPEN' OR staus IS NULL").unwrap();
+    /// assert_eq!(expr.validate_fields(&fields), Err(ExpressionError::UnknownField("staus".into())));
+    /// ```
+    pub fn validate_fields(&self, fields: &[Field]) -> Result<(), ExpressionError> {
+        match self
+            .fields()
+            .into_iter()
+            .find(|name| !fields.iter().any(|f| f.name.eq_ignore_ascii_case(name)))
+        {
+            Some(name) => Err(ExpressionError::UnknownField(name.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Evaluate the expression to a value
+    ///
+    /// Predicates evaluate to a boolean, or `null` when they are unknown.
+    pub fn evaluate(&self, attributes: &Map<String, Value>) -> Result<Value, ExpressionError> {
+        let res = match self {
+            Self::Literal(value) => value.clone(),
+            Self::Field(name) => attributes
+                .get(name)
+                .or_else(|| {
+                    attributes
+                        .iter()
+                        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                        .map(|(_, v)| v)
+                })
+                .cloned()
+                .ok_or_else(|| ExpressionError::UnknownField(name.clone()))?,
+            Self::Negate(e) => match e.evaluate(attributes)? {
+                Value::Null => Value::Null,
+                value => Value::from(-as_number(&value).ok_or_else(|| {
+                    ExpressionError::TypeMismatch(format!("cannot negate {value}"))
+                })?),
+            },
+            Self::Function(function, e) => match e.evaluate(attributes)? {
+                Value::Null => Value::Null,
+                Value::String(s) => Value::from(match function {
+                    Function::Upper => s.to_uppercase(),
+                    Function::Lower => s.to_lowercase(),
+                    Function::Trim => s.trim().to_string(),
+                }),
+                value => {
+                    return Err(ExpressionError::TypeMismatch(format!(
+                        "{function:?} expects a string, found {value}"
+                    )))
+                }
+            },
+            Self::Arithmetic { left, op, right } => {
+                let (a, b) = (left.evaluate(attributes)?, right.evaluate(attributes)?);
+                if a.is_null() || b.is_null() {
+                    return Ok(Value::Null);
+                }
+                let (Some(x), Some(y)) = (as_number(&a), as_number(&b)) else {
+                    return Err(ExpressionError::TypeMismatch(format!(
+                        "cannot apply {op:?} to {a} and {b}"
+                    )));
+                };
+                let res = match op {
+                    ArithmeticOp::Add => x + y,
+                    ArithmeticOp::Subtract => x - y,
+                    ArithmeticOp::Multiply => x * y,
+                    ArithmeticOp::Divide if y == 0.0 => return Ok(Value::Null),
+                    ArithmeticOp::Divide => x / y,
+                };
+                Value::from(res)
+            }
+            predicate => match predicate.truth(attributes)? {
+                Some(b) => Value::Bool(b),
+                None => Value::Null,
+            },
+        };
+        Ok(res)
+    }
+
+    // the truth of a predicate, `None` when it is unknown
+    fn truth(&self, attributes: &Map<String, Value>) -> Result<Option<bool>, ExpressionError> {
+        let res = match self {
+            Self::Not(e) => e.truth(attributes)?.map(|b| !b),
+            Self::And(a, b) => {
+                let a = a.truth(attributes)?;
+                match a {
+                    Some(false) => Some(false),
+                    a => and(a, b.truth(attributes)?),
+                }
+            }
+            Self::Or(a, b) => {
+                let a = a.truth(attributes)?;
+                match a {
+                    Some(true) => Some(true),
+                    a => or(a, b.truth(attributes)?),
+                }
+            }
+            Self::Compare { left, op, right } => {
+                let (a, b) = (left.evaluate(attributes)?, right.evaluate(attributes)?);
+                if a.is_null() || b.is_null() {
+                    return Ok(None);
+                }
+                let ord = compare(&a, &b)?;
+                Some(match op {
+                    CompareOp::Eq => ord.is_eq(),
+                    CompareOp::NotEq => ord.is_ne(),
+                    CompareOp::Lt => ord.is_lt(),
+                    CompareOp::LtEq => ord.is_le(),
+                    CompareOp::Gt => ord.is_gt(),
+                    CompareOp::GtEq => ord.is_ge(),
+                })
+            }
+            Self::IsNull { expr, negated } => {
+                Some(expr.evaluate(attributes)?.is_null() != *negated)
+            }
+            Self::In {
+                expr,
+                list,
+                negated,
+            } => {
+                let value = expr.evaluate(attributes)?;
+                if value.is_null() {
+                    return Ok(None);
+                }
+                // unknown when there is no match and the list has a null
+                let mut res = Some(false);
+                for item in list {
+                    let item = item.evaluate(attributes)?;
+                    if item.is_null() {
+                        res = None;
+                    } else if compare(&value, &item)?.is_eq() {
+                        res = Some(true);
+                        break;
+                    }
+                }
+                res.map(|b| b != *negated)
+            }
+            Self::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+            } => {
+                let (value, pattern) = (expr.evaluate(attributes)?, pattern.evaluate(attributes)?);
+                match (value, pattern) {
+                    (Value::Null, _) | (_, Value::Null) => None,
+                    (Value::String(s), Value::String(p)) => {
+                        let text = s.chars().collect::<Vec<_>>();
+                        let pattern = p.chars().collect::<Vec<_>>();
+                        Some(like(&text, &pattern, *escape) != *negated)
+                    }
+                    (value, _) => {
+                        return Err(ExpressionError::TypeMismatch(format!(
+                            "LIKE expects a string, found {value}"
+                        )))
+                    }
+                }
+            }
+            Self::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let value = expr.evaluate(attributes)?;
+                let (low, high) = (low.evaluate(attributes)?, high.evaluate(attributes)?);
+                if value.is_null() || low.is_null() || high.is_null() {
+                    return Ok(None);
+                }
+                let inside = compare(&value, &low)?.is_ge() && compare(&value, &high)?.is_le();
+                Some(inside != *negated)
+            }
+            value => match value.evaluate(attributes)? {
+                Value::Null => None,
+                Value::Bool(b) => Some(b),
+                value => {
+                    return Err(ExpressionError::TypeMismatch(format!(
+                        "expected a condition, found {value}"
+                    )))
+                }
+            },
+        };
+        Ok(res)
+    }
+
+    /// `true` when the expression is true for `attributes`
+    ///
+    /// Fails when the expression refers to a field missing from `attributes`, or
+    /// compares values of different types.
+    pub fn matches(&self, attributes: &Map<String, Value>) -> Result<bool, ExpressionError> {
+        Ok(self.truth(attributes)? == Some(true))
+    }
+
+    /// `true` when the expression is true for the attributes of `feature`
+    pub fn matches_feature<const N: usize>(
+        &self,
+        feature: &Feature<N>,
+    ) -> Result<bool, ExpressionError> {
+        match &feature.attributes {
+            Some(attributes) => self.matches(attributes),
+            None => self.matches(&Map::new()),
+        }
+    }
+}
+
+impl<const N: usize> FeatureSet<N> {
+    /// Keep only the features matching a `where` clause
+    pub fn retain_where(&mut self, expr: &Expression) -> Result<(), ExpressionError> {
+        let mut res = Ok(());
+        self.features.retain(|feature| {
+            if res.is_err() {
+                return true;
+            }
+            expr.matches_feature(feature).unwrap_or_else(|e| {
+                res = Err(e);
+                true
+            })
+        });
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn eval(clause: &str, attributes: Value) -> Result<bool, ExpressionError> {
+        Expression::parse(clause)?.matches(attributes.as_object().unwrap())
+    }
+
+    #[test]
+    fn test_matches() {
+        let attrs = json!({"name": "O'Hare", "pop": 1200, "kind": null, "code": "A_1"});
+
+        assert!(eval("name = 'O''Hare'", attrs.clone()).unwrap());
+        assert!(eval(
+            "POP BETWEEN 1000 AND 2000 AND NOT pop IN (1, 2)",
+            attrs.clone()
+        )
+        .unwrap());
+        assert!(eval("pop * 2 - 400 >= 2000", attrs.clone()).unwrap());
+        assert!(eval("kind IS NULL AND name IS NOT NULL", attrs.clone()).unwrap());
+        assert!(eval("UPPER(name) LIKE 'O''H_RE%'", attrs.clone()).unwrap());
+        assert!(eval("code LIKE 'A\\_%' ESCAPE '\\'", attrs.clone()).unwrap());
+        assert!(!eval("code LIKE 'AB%' OR \"kind\" = 'x'", attrs.clone()).unwrap());
+        assert!(eval("name LIKE '%''%e'", attrs.clone()).unwrap());
+
+        // many `%` do not backtrack exponentially
+        let long = json!({"s": "a".repeat(200)});
+        let pattern = format!("s LIKE '{}b'", "%a".repeat(50));
+        assert!(!eval(&pattern, long.clone()).unwrap());
+        assert!(eval(&format!("s LIKE '{}%'", "%a".repeat(50)), long).unwrap());
+
+        // comparisons with null are unknown, even when negated
+        assert!(!eval("kind = 'x'", attrs.clone()).unwrap());
+        assert!(!eval("NOT kind = 'x'", attrs.clone()).unwrap());
+        assert!(eval("kind = 'x' OR 1 = 1", attrs.clone()).unwrap());
+
+        let attrs = json!({"created": 1_577_923_200_000_i64});
+        assert!(eval("created > DATE '2020-01-01'", attrs.clone()).unwrap());
+        assert!(eval("created = TIMESTAMP '2020-01-02 00:00:00'", attrs).unwrap());
+
+        assert_eq!(
+            eval("missing = 1", json!({})),
+            Err(ExpressionError::UnknownField("missing".into()))
+        );
+        assert!(matches!(
+            eval("name > 1", json!({"name": "a"})),
+            Err(ExpressionError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let position = |clause: &str| match Expression::parse(clause) {
+            Err(ExpressionError::Syntax { position, .. }) => position,
+            res => panic!("expected a syntax error, found {res:?}"),
+        };
+        assert_eq!(position("a = 'b"), 4);
+        assert_eq!(position("a = 1 AND"), 9);
+        assert_eq!(position("a IN (1, 2"), 10);
+        assert_eq!(position("a NOT = 1"), 6);
+        assert_eq!(position("a = 1 b"), 6);
+        assert!("1=1".parse::<Expression>().is_ok());
+
+        // dates out of range
+        assert_eq!(position("a > DATE '99999999999999-01-01'"), 9);
+        assert_eq!(position("a > TIMESTAMP '2020-01-01 1e300:00:00'"), 14);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let nested = |n: usize, open: &str, close: &str| {
+            format!("{}a = 1{}", open.repeat(n), close.repeat(n))
+        };
+        let chained = |n: usize, term: &str, op: &str| vec![term; n + 1].join(op);
+
+        for ok in [
+            nested(MAX_DEPTH, "(", ")"),
+            nested(MAX_DEPTH, "NOT ", ""),
+            chained(MAX_DEPTH, "a = 1", " OR "),
+            format!("a = {}", chained(MAX_DEPTH, "1", " * ")),
+        ] {
+            let expr = Expression::parse(&ok).unwrap();
+            assert!(expr.matches(json!({"a": 1}).as_object().unwrap()).is_ok());
+        }
+
+        for deep in [
+            nested(MAX_DEPTH + 1, "(", ")"),
+            nested(200_000, "(", ")"),
+            nested(MAX_DEPTH + 1, "-", ""),
+            chained(MAX_DEPTH + 1, "a = 1", " AND "),
+            format!("a = {}", chained(MAX_DEPTH + 1, "1", " + ")),
+        ] {
+            assert!(matches!(
+                Expression::parse(&deep),
+                Err(ExpressionError::Syntax { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+
+/// A change to the definition of a hosted feature service or layer, sent to the [`/updateDefinition`](https://developers.arcgis.com/rest/services-reference/online/update-definition-feature-layer/) admin endpoint
+///
+/// Only the provided properties are changed.
+///
+/// ```
+/// use serde_esri::feature_service::{DefinitionUpdateBuilder, FieldUpdate};
+///
+/// // enable editing and sync, and rename a field's alias
+/// let update = DefinitionUpdateBuilder::default()
+///     .capabilities("Query,Create,Update,Delete,Editing,Sync")
+///     .sync_enabled(true)
+///     .fields(vec![FieldUpdate::alias("POP2020", "Population (2020)")])
+///     .build()
+///     .unwrap();
+/// ```
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+#[serde(rename_all = "camelCase")]
+pub struct DefinitionUpdate {
+    /// The name of the layer.
+    #[builder(setter(into, strip_option), default)]
+    pub name: Option<String>,
+    /// The description of the service or layer.
+    #[builder(setter(into, strip_option), default)]
+    pub description: Option<String>,
+    /// Comma separated capabilities, e.g. `"Query,Create,Update,Delete,Editing,Sync"`.
+    #[builder(setter(into, strip_option), default)]
+    pub capabilities: Option<String>,
+    /// The maximum number of features returned by a query.
+    #[builder(setter(into, strip_option), default)]
+    pub max_record_count: Option<u64>,
+    /// Whether or not replicas can be created and synchronized. Service only.
+    #[builder(setter(into, strip_option), default)]
+    pub sync_enabled: Option<bool>,
+    /// Whether or not geometries can be updated.
+    #[builder(setter(into, strip_option), default)]
+    pub allow_geometry_updates: Option<bool>,
+    /// Whether or not the data is static. Must be `false` to allow editing.
+    #[builder(setter(into, strip_option), default)]
+    pub has_static_data: Option<bool>,
+    /// Changes to existing fields. Layer only.
+    #[builder(setter(into, strip_option), default)]
+    pub fields: Option<Vec<FieldUpdate>>,
+    /// Other properties of the definition, sent as is. Properties read from an
+    /// existing definition without a field of their own are kept here too.
+    #[serde(flatten)]
+    #[builder(setter(into), default)]
+    pub extra: Map<String, Value>,
+}
+
+/// A change to an existing field of a layer
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FieldUpdate {
+    /// The name of the field to change
+    pub name: String,
+    pub alias: Option<String>,
+    pub editable: Option<bool>,
+    pub nullable: Option<bool>,
+    /// Other properties of the field, sent as is
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl FieldUpdate {
+    /// Change the alias, i.e. the display name, of a field
+    pub fn alias(name: &str, alias: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            alias: Some(alias.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// The response of an admin operation such as `updateDefinition`, `refresh` or `truncate`
+///
+/// Asynchronous operations return a `statusURL` instead.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdminResponse {
+    #[serde(default)]
+    pub success: bool,
+    pub statusURL: Option<String>,
+}
@@ -0,0 +1,474 @@
+use crate::{
+    error::{ClassifyError, ErrorKind, RetryPolicy},
+    feature_service::{
+        ApplyEditsParams, ApplyEditsResponse, EditResult, FeatureLayerClient, FeatureServiceError,
+    },
+    features::Feature,
+    http::HttpClient,
+    progress::{Operation, Progress},
+};
+use std::time::Duration;
+
+/// The default maximum number of features sent in a single batch
+pub const DEFAULT_MAX_BATCH_FEATURES: usize = 1000;
+/// The default maximum size, in bytes of Esri JSON, of a single batch
+pub const DEFAULT_MAX_BATCH_BYTES: usize = 4 * 1024 * 1024;
+
+/// Adds a large number of features to a layer in batches
+///
+/// Features are split into [`/applyEdits`](https://developers.arcgis.com/rest/services-reference/enterprise/apply-edits-feature-service-layer/)
+/// requests that stay under both a feature count and a payload size limit.
+/// Each batch is applied atomically (`rollbackOnFailure=true`) and retried with
+/// exponential backoff when the request fails for a transient reason.
+///
+/// When a batch fails and `rollback` is enabled, the features added by the
+/// previously committed batches are deleted so the layer is left unchanged.
+///
+/// Progress is reported to the client's [`ProgressReporter`](crate::progress::ProgressReporter)
+/// after every committed batch.
+///
+/// ```no_run
+/// use serde_esri::feature_service::{BulkUploader, FeatureLayerClient};
+/// use serde_esri::features::FeatureSet;
+///
+/// let client = FeatureLayerClient::new("https://example.com/arcgis/rest/services/Trees/FeatureServer/0")
+///     .with_token("your token");
+/// let fset: FeatureSet<2> = serde_json::from_str("{\"features\": []}").unwrap();
+///
+/// let report = BulkUploader::new(&client)
+///     .with_max_batch_features(500)
+///     .with_rollback(true)
+///     .upload(fset.features)
+///     .unwrap();
+///
+/// println!("added {} features", report.object_ids().len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct BulkUploader<'a, H = reqwest::blocking::Client> {
+    client: &'a FeatureLayerClient<H>,
+    max_batch_features: usize,
+    max_batch_bytes: usize,
+    retry: RetryPolicy,
+    rollback: bool,
+}
+
+/// The outcome of a single committed batch
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    /// The position of the batch in the upload
+    pub batch: usize,
+    /// The number of requests made, including retries
+    pub attempts: u32,
+    /// The size of the batch in bytes of Esri JSON
+    pub bytes: usize,
+    /// The result of each added feature
+    pub results: Vec<EditResult>,
+}
+
+/// The batches committed by a [`BulkUploader`]
+#[derive(Debug, Clone, Default)]
+pub struct BulkUploadReport {
+    pub batches: Vec<BatchResult>,
+}
+
+impl BulkUploadReport {
+    /// The progress of the upload
+    pub fn progress(&self) -> Progress {
+        let mut progress = Progress::new(Operation::Upload);
+        for batch in &self.batches {
+            progress.add(batch.results.len(), batch.bytes);
+        }
+        progress
+    }
+
+    /// The object IDs of every added feature
+    pub fn object_ids(&self) -> Vec<i64> {
+        self.batches
+            .iter()
+            .flat_map(|b| &b.results)
+            .filter_map(|r| r.object_id)
+            .collect()
+    }
+}
+
+/// Why a batch could not be committed
+#[derive(Debug)]
+pub enum BatchFailure {
+    /// The request failed after all retries
+    Request(FeatureServiceError),
+    /// The service rejected one or more features of the batch
+    Rejected(Vec<EditResult>),
+}
+
+/// Returned when a [`BulkUploader`] fails to commit a batch
+#[derive(Debug)]
+pub struct BulkUploadError {
+    /// The position of the failed batch
+    pub batch: usize,
+    pub failure: BatchFailure,
+    /// The batches committed before the failure. These have been deleted if
+    /// `rollback` succeeded.
+    pub committed: BulkUploadReport,
+    /// The outcome of the rollback, `None` if rollback is disabled
+    pub rollback: Option<Result<(), Box<RollbackError>>>,
+}
+
+/// Returned when a rollback leaves committed features in the layer
+#[derive(Debug)]
+pub struct RollbackError {
+    /// The object IDs of the features that were not deleted
+    pub object_ids: Vec<i64>,
+    /// The request that failed after all retries, or `None` if the service rejected
+    /// the deletes
+    pub error: Option<Box<FeatureServiceError>>,
+}
+
+impl std::fmt::Display for RollbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let n = self.object_ids.len();
+        match &self.error {
+            Some(e) => write!(f, "{n} features were not deleted: {e}"),
+            None => write!(f, "{n} features were not deleted"),
+        }
+    }
+}
+
+impl std::error::Error for RollbackError {}
+
+impl std::fmt::Display for BulkUploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.failure {
+            BatchFailure::Request(e) => write!(f, "batch {} failed: {e}", self.batch)?,
+            BatchFailure::Rejected(r) => {
+                let n = r.iter().filter(|r| !r.success).count();
+                write!(f, "batch {} failed: {n} features were rejected", self.batch)?
+            }
+        }
+
+        match &self.rollback {
+            Some(Ok(())) => write!(f, "; previous batches were rolled back"),
+            Some(Err(e)) => write!(f, "; rollback failed: {e}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for BulkUploadError {}
+
+impl ClassifyError for BulkUploadError {
+    fn kind(&self) -> ErrorKind {
+        match &self.failure {
+            BatchFailure::Request(e) => e.kind(),
+            BatchFailure::Rejected(_) => ErrorKind::Fatal,
+        }
+    }
+}
+
+impl<'a, H: HttpClient> BulkUploader<'a, H> {
+    /// Create an uploader for the layer of `client`
+    pub fn new(client: &'a FeatureLayerClient<H>) -> Self {
+        Self {
+            client,
+            max_batch_features: DEFAULT_MAX_BATCH_FEATURES,
+            max_batch_bytes: DEFAULT_MAX_BATCH_BYTES,
+            retry: RetryPolicy::default(),
+            rollback: false,
+        }
+    }
+
+    /// Set the maximum number of features in a batch
+    pub fn with_max_batch_features(mut self, x: usize) -> Self {
+        self.max_batch_features = x.max(1);
+        self
+    }
+
+    /// Set the maximum size of a batch in bytes of Esri JSON
+    ///
+    /// A feature larger than this limit is sent in a batch of its own.
+    pub fn with_max_batch_bytes(mut self, x: usize) -> Self {
+        self.max_batch_bytes = x;
+        self
+    }
+
+    /// Set the number of times a failed request is retried and the delay
+    /// before the first retry. The delay doubles with each retry.
+    pub fn with_retries(mut self, max_retries: u32, retry_delay: Duration) -> Self {
+        self.retry = RetryPolicy::new(max_retries, retry_delay);
+        self
+    }
+
+    /// Delete the features of committed batches when a batch fails
+    pub fn with_rollback(mut self, rollback: bool) -> Self {
+        self.rollback = rollback;
+        self
+    }
+
+    /// Split `features` into batches and add them to the layer
+    ///
+    /// Batches are sent sequentially as they fill up so `features` can be a
+    /// lazy iterator.
+    pub fn upload<const N: usize, I>(
+        &self,
+        features: I,
+    ) -> Result<BulkUploadReport, BulkUploadError>
+    where
+        I: IntoIterator<Item = Feature<N>>,
+    {
+        let mut report = BulkUploadReport::default();
+        let mut batch = vec![];
+        let mut batch_bytes = 0;
+
+        for feature in features {
+            // +1 for the separating comma
+            let size = serde_json::to_vec(&feature).map_or(0, |x| x.len()) + 1;
+
+            let full = batch.len() >= self.max_batch_features
+                || (!batch.is_empty() && batch_bytes + size > self.max_batch_bytes);
+
+            if full {
+                self.commit(std::mem::take(&mut batch), batch_bytes, &mut report)?;
+                batch_bytes = 0;
+            }
+
+            batch.push(feature);
+            batch_bytes += size;
+        }
+
+        if !batch.is_empty() {
+            self.commit(batch, batch_bytes, &mut report)?;
+        }
+
+        Ok(report)
+    }
+
+    fn commit<const N: usize>(
+        &self,
+        features: Vec<Feature<N>>,
+        bytes: usize,
+        report: &mut BulkUploadReport,
+    ) -> Result<(), BulkUploadError> {
+        let batch = report.batches.len();
+        let params = ApplyEditsParams {
+            adds: features,
+            rollback_on_failure: Some(true),
+            ..Default::default()
+        };
+
+        let failure = match self.send(&params) {
+            Ok((resp, attempts)) if resp.success() => {
+                report.batches.push(BatchResult {
+                    batch,
+                    attempts,
+                    bytes,
+                    results: resp.add_results,
+                });
+                if let Some(reporter) = &self.client.progress {
+                    reporter.report(&report.progress());
+                }
+                return Ok(());
+            }
+            Ok((resp, _)) => BatchFailure::Rejected(resp.add_results),
+            Err(e) => BatchFailure::Request(e),
+        };
+
+        let committed = std::mem::take(report);
+        let rollback = self
+            .rollback
+            .then(|| self.delete(committed.object_ids()).map_err(Box::new));
+
+        Err(BulkUploadError {
+            batch,
+            failure,
+            committed,
+            rollback,
+        })
+    }
+
+    // sends the edits with retries, returning the response and number of attempts
+    fn send<const N: usize>(
+        &self,
+        params: &ApplyEditsParams<N>,
+    ) -> Result<(ApplyEditsResponse, u32), FeatureServiceError> {
+        self.retry
+            .run(|attempt| Ok((self.client.apply_edits(params)?, attempt)))
+    }
+
+    // deletes previously added features in batches. A failed request stops the rollback,
+    // leaving the features of it and the later batches.
+    fn delete(&self, object_ids: Vec<i64>) -> Result<(), RollbackError> {
+        let mut failed = vec![];
+        for (i, ids) in object_ids.chunks(self.max_batch_features).enumerate() {
+            let params = ApplyEditsParams::<2> {
+                deletes: ids.to_vec(),
+                ..Default::default()
+            };
+            let resp = match self.send(&params) {
+                Ok((resp, _)) => resp,
+                Err(e) => {
+                    failed.extend_from_slice(&object_ids[i * self.max_batch_features..]);
+                    return Err(RollbackError {
+                        object_ids: failed,
+                        error: Some(Box::new(e)),
+                    });
+                }
+            };
+
+            // the IDs without a successful result
+            let deleted: Vec<i64> = resp
+                .delete_results
+                .iter()
+                .filter(|r| r.success)
+                .filter_map(|r| r.object_id)
+                .collect();
+            failed.extend(ids.iter().filter(|id| !deleted.contains(id)));
+        }
+
+        match failed.is_empty() {
+            true => Ok(()),
+            false => Err(RollbackError {
+                object_ids: failed,
+                error: None,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{HttpError, HttpRequest, HttpResponse};
+    use std::sync::{Arc, Mutex};
+
+    // responds with each body in turn and records the form of each request
+    #[derive(Clone, Default)]
+    struct Responses {
+        bodies: Arc<Mutex<Vec<&'static str>>>,
+        forms: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl HttpClient for Responses {
+        fn execute(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+            let form = String::from_utf8(request.body.unwrap_or_default()).unwrap();
+            self.forms.lock().unwrap().push(form);
+            let body = self.bodies.lock().unwrap().remove(0);
+            Ok(HttpResponse {
+                status: 200,
+                headers: vec![],
+                body: body.as_bytes().to_vec(),
+            })
+        }
+    }
+
+    fn layer(bodies: Vec<&'static str>) -> (FeatureLayerClient<Responses>, Responses) {
+        let responses = Responses {
+            bodies: Arc::new(Mutex::new(bodies)),
+            ..Default::default()
+        };
+        let client = FeatureLayerClient::new("https://example.com/FeatureServer/0")
+            .with_http_client(responses.clone());
+        (client, responses)
+    }
+
+    fn features(n: usize) -> Vec<Feature<2>> {
+        (0..n)
+            .map(|i| serde_json::from_value(serde_json::json!({"attributes": {"n": i}})).unwrap())
+            .collect()
+    }
+
+    const ADDED_1_2: &str = r#"{"addResults": [
+        {"objectId": 1, "success": true}, {"objectId": 2, "success": true}
+    ]}"#;
+    const ADDED_3: &str = r#"{"addResults": [{"objectId": 3, "success": true}]}"#;
+    const REJECTED: &str = r#"{"addResults": [
+        {"success": false, "error": {"code": 1000, "description": "invalid"}}
+    ]}"#;
+    const BUSY: &str = r#"{"error": {"code": 503, "message": "busy", "details": []}}"#;
+
+    #[test]
+    fn test_batches() {
+        let (client, responses) = layer(vec![ADDED_1_2, ADDED_3]);
+        let report = BulkUploader::new(&client)
+            .with_max_batch_features(2)
+            .upload(features(3))
+            .unwrap();
+
+        assert_eq!(report.object_ids(), vec![1, 2, 3]);
+        assert_eq!(report.batches.len(), 2);
+        assert_eq!(report.progress().features, 3);
+        let forms = responses.forms.lock().unwrap();
+        assert_eq!(forms.len(), 2);
+        assert!(forms.iter().all(|f| f.contains("rollbackOnFailure=true")));
+    }
+
+    #[test]
+    fn test_retry() {
+        let (client, _) = layer(vec![BUSY, ADDED_1_2]);
+        let report = BulkUploader::new(&client)
+            .with_retries(1, Duration::ZERO)
+            .upload(features(2))
+            .unwrap();
+        assert_eq!(report.batches[0].attempts, 2);
+
+        let (client, _) = layer(vec![BUSY, BUSY]);
+        let e = BulkUploader::new(&client)
+            .with_retries(1, Duration::ZERO)
+            .upload(features(2))
+            .unwrap_err();
+        assert!(matches!(e.failure, BatchFailure::Request(_)));
+        assert!(e.is_retryable());
+    }
+
+    #[test]
+    fn test_rollback() {
+        let deleted = r#"{"deleteResults": [
+            {"objectId": 1, "success": true}, {"objectId": 2, "success": true}
+        ]}"#;
+        let (client, responses) = layer(vec![ADDED_1_2, REJECTED, deleted]);
+        let e = BulkUploader::new(&client)
+            .with_max_batch_features(2)
+            .with_rollback(true)
+            .upload(features(3))
+            .unwrap_err();
+
+        assert_eq!(e.batch, 1);
+        assert!(matches!(e.failure, BatchFailure::Rejected(_)));
+        assert!(matches!(e.rollback, Some(Ok(()))));
+        assert!(e.to_string().ends_with("previous batches were rolled back"));
+        assert!(responses.forms.lock().unwrap()[2].contains("deletes=1%2C2"));
+    }
+
+    #[test]
+    fn test_failed_rollback() {
+        // the service can't delete a feature
+        let deleted = r#"{"deleteResults": [
+            {"objectId": 1, "success": true},
+            {"objectId": 2, "success": false, "error": {"code": 1000, "description": "locked"}}
+        ]}"#;
+        let (client, _) = layer(vec![ADDED_1_2, REJECTED, deleted]);
+        let e = BulkUploader::new(&client)
+            .with_max_batch_features(2)
+            .with_rollback(true)
+            .upload(features(3))
+            .unwrap_err();
+
+        let rollback = e.rollback.as_ref().unwrap().as_ref().unwrap_err();
+        assert_eq!(rollback.object_ids, vec![2]);
+        assert!(rollback.error.is_none());
+        assert!(e
+            .to_string()
+            .ends_with("rollback failed: 1 features were not deleted"));
+
+        // a failed request leaves the features it would have deleted
+        let (client, _) = layer(vec![ADDED_1_2, REJECTED, BUSY]);
+        let e = BulkUploader::new(&client)
+            .with_max_batch_features(2)
+            .with_retries(0, Duration::ZERO)
+            .with_rollback(true)
+            .upload(features(3))
+            .unwrap_err();
+
+        let rollback = e.rollback.as_ref().unwrap().as_ref().unwrap_err();
+        assert_eq!(rollback.object_ids, vec![1, 2]);
+        assert!(rollback.error.is_some());
+    }
+}
@@ -0,0 +1,145 @@
+use crate::features::Feature;
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+
+/// The server generation of a layer, used to request the changes made since a previous extraction
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerServerGen {
+    pub id: i64,
+    pub server_gen: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_server_gen: Option<i64>,
+}
+
+/// Request parameters used to query the [`/extractChanges`](https://developers.arcgis.com/rest/services-reference/enterprise/extract-changes-feature-service/) endpoint of a change tracking enabled Feature Service
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractChangesParams {
+    /// The IDs of the layers to extract changes from. Defaults to the client's layer.
+    #[builder(setter(into), default)]
+    pub layers: Vec<i64>,
+    /// The server generation of each layer returned by a previous extraction.
+    /// Changes made after these generations are returned.
+    #[builder(setter(into), default)]
+    pub layer_server_gens: Vec<LayerServerGen>,
+    /// Whether or not to return added features.
+    #[builder(setter(into, strip_option), default)]
+    pub return_inserts: Option<bool>,
+    /// Whether or not to return updated features.
+    #[builder(setter(into, strip_option), default)]
+    pub return_updates: Option<bool>,
+    /// Whether or not to return deleted features.
+    #[builder(setter(into, strip_option), default)]
+    pub return_deletes: Option<bool>,
+    /// Only return the object IDs of changed features.
+    #[builder(setter(into, strip_option), default)]
+    pub return_ids_only: Option<bool>,
+    /// Run the extraction as an asynchronous job. Recommended for large change sets.
+    #[builder(setter(into, strip_option), default)]
+    pub run_async: Option<bool>,
+}
+
+impl ExtractChangesParams {
+    /// The parameters as key-value pairs to be used as the body of a form request.
+    ///
+    /// Layers and server generations are serialized as JSON, and `dataFormat=json` and `f=json` are always included.
+    pub fn form_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![(
+            "layers",
+            serde_json::to_string(&self.layers).unwrap_or_default(),
+        )];
+
+        if !self.layer_server_gens.is_empty() {
+            pairs.push((
+                "layerServerGens",
+                serde_json::to_string(&self.layer_server_gens).unwrap_or_default(),
+            ));
+        }
+
+        let optional = [
+            ("returnInserts", self.return_inserts),
+            ("returnUpdates", self.return_updates),
+            ("returnDeletes", self.return_deletes),
+            ("returnIdsOnly", self.return_ids_only),
+            ("async", self.run_async),
+        ];
+
+        pairs.extend(
+            optional
+                .into_iter()
+                .filter_map(|(k, v)| Some((k, v?.to_string()))),
+        );
+        pairs.push(("dataFormat", "json".into()));
+        pairs.push(("f", "json".into()));
+        pairs
+    }
+}
+
+/// The changes extracted from a Feature Service
+///
+/// Store `layerServerGens` and pass them to the next extraction to only receive newer changes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractChangesResponse<const N: usize> {
+    #[serde(default)]
+    pub layer_server_gens: Vec<LayerServerGen>,
+    #[serde(default)]
+    pub edits: Vec<LayerChanges<N>>,
+}
+
+/// The changes made to a single layer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerChanges<const N: usize> {
+    pub id: i64,
+    /// The changed features. `None` when `returnIdsOnly` is `true`.
+    pub features: Option<ChangedFeatures<N>>,
+    /// The object IDs of the changed features when `returnIdsOnly` is `true`.
+    pub object_ids: Option<ChangedObjectIds>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedFeatures<const N: usize> {
+    #[serde(default)]
+    pub adds: Vec<Feature<N>>,
+    #[serde(default)]
+    pub updates: Vec<Feature<N>>,
+    #[serde(default)]
+    pub delete_ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChangedObjectIds {
+    #[serde(default)]
+    pub adds: Vec<i64>,
+    #[serde(default)]
+    pub updates: Vec<i64>,
+    #[serde(default)]
+    pub deletes: Vec<i64>,
+}
+
+/// The status of an asynchronous Feature Service job such as `extractChanges` or `createReplica`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    #[serde(default)]
+    pub status: String,
+    pub status_url: Option<String>,
+    pub result_url: Option<String>,
+}
+
+impl JobStatus {
+    /// `true` once the job has completed successfully
+    pub fn is_completed(&self) -> bool {
+        self.status.eq_ignore_ascii_case("completed")
+    }
+
+    /// `true` if the job failed or was cancelled
+    pub fn is_failed(&self) -> bool {
+        ["failed", "cancelled", "canceled", "completedwitherrors"]
+            .iter()
+            .any(|s| self.status.eq_ignore_ascii_case(s))
+    }
+}
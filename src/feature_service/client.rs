@@ -0,0 +1,997 @@
+use crate::{
+    auth::{
+        CredentialError, CredentialProvider, FederatedCredentials, FederationError, TokenProvider,
+        TokenSource,
+    },
+    error::{ErrorKind, RetryPolicy},
+    feature_service::{
+        feature_global_id, feature_object_id, is_global_id, normalize_global_id,
+        AddAttachmentResponse, AdminResponse, ApplyEditsParams, ApplyEditsResponse, AttachmentInfo,
+        AttachmentInfos, CreateReplicaParams, DefinitionUpdate, EditResult, ExpectedResponse,
+        ExtractChangesParams, ExtractChangesResponse, FeatureQueryParams, FeatureServiceError,
+        JobStatus, LayerDefinition, QueryFeatures, QueryPages, QueryResult, QueryState,
+        ReplicaAttachment, ReplicaResponse, StatisticsResult, SynchronizeReplicaParams,
+        SynchronizeReplicaResponse, UpsertError, UpsertResults,
+    },
+    features::Feature,
+    geometry::{EsriEnvelope, EsriGeometry},
+    http::{HttpClient, HttpError, HttpRequest},
+    progress::{Operation, Progress, ProgressReporter},
+    trace,
+};
+use base64::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+// the number of GlobalIDs in a single `IN` clause
+const GLOBAL_ID_CHUNK_SIZE: usize = 250;
+// the time between the first checks of an asynchronous job's status, doubled after each check
+const JOB_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// the longest time between checks of an asynchronous job's status
+const MAX_JOB_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// The default time an asynchronous job is waited for
+pub const DEFAULT_JOB_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+// the response of `uploads/upload`
+#[derive(Deserialize)]
+struct UploadResponse {
+    item: UploadedItem,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+struct UploadedItem {
+    itemID: String,
+}
+
+/// A client for a single layer of a Feature Service
+///
+/// ```
+/// use serde_esri::feature_service::FeatureLayerClient;
+/// let client = FeatureLayerClient::new(
+///     "https://services.arcgis.com/P3ePLMYs2RVChkJx/ArcGIS/rest/services/USA_Counties_Generalized_Boundaries/FeatureServer/0",
+/// );
+/// ```
+///
+/// Use [`FeatureLayerClient::with_token()`] to access secured layers.
+///
+/// Requests are sent with a `reqwest::blocking::Client` unless another [`HttpClient`]
+/// is set with [`FeatureLayerClient::with_http_client()`].
+#[derive(Clone)]
+pub struct FeatureLayerClient<H = reqwest::blocking::Client> {
+    /// The URL of the layer, e.g. `.../FeatureServer/0`
+    pub layer_url: String,
+    pub(crate) client: H,
+    /// The token to use for authorization. Redacted from `Debug` and zeroized on drop.
+    pub(crate) token: Option<TokenSource>,
+    /// Retries the pages of paginated queries
+    pub(crate) retry: RetryPolicy,
+    /// Receives the progress of paginated queries, downloads, and bulk uploads
+    pub(crate) progress: Option<Arc<dyn ProgressReporter>>,
+    /// The time an asynchronous job is waited for
+    pub(crate) job_timeout: Duration,
+}
+
+impl<H> std::fmt::Debug for FeatureLayerClient<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FeatureLayerClient")
+            .field("layer_url", &self.layer_url)
+            .field("token", &self.token.as_ref().map(|_| "[REDACTED]"))
+            .field("retry", &self.retry)
+            .field("progress", &self.progress.is_some())
+            .field("job_timeout", &self.job_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FeatureLayerClient {
+    /// Create a new client for the layer at `layer_url`
+    pub fn new(layer_url: &str) -> Self {
+        Self {
+            layer_url: layer_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: None,
+            retry: RetryPolicy::default(),
+            progress: None,
+            job_timeout: DEFAULT_JOB_TIMEOUT,
+        }
+    }
+}
+
+impl<H: HttpClient> FeatureLayerClient<H> {
+    /// Send requests with `client` instead
+    pub fn with_http_client<C: HttpClient>(self, client: C) -> FeatureLayerClient<C> {
+        FeatureLayerClient {
+            layer_url: self.layer_url,
+            client,
+            token: self.token,
+            retry: self.retry,
+            progress: self.progress,
+            job_timeout: self.job_timeout,
+        }
+    }
+
+    /// Set the token used to authorize requests
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Set the provider asked for the token before every request
+    ///
+    /// Use a provider which refreshes its token, such as [`ClientCredentials`](crate::auth::ClientCredentials),
+    /// for clients that outlive a token.
+    pub fn with_token_provider<P: TokenProvider>(mut self, provider: P) -> Self {
+        self.token = Some(TokenSource::new(provider));
+        self
+    }
+
+    /// Set how the pages of paginated queries are retried when a request fails
+    /// with a retryable error
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the time asynchronous jobs, e.g. of replicas, are waited for. A job that hasn't
+    /// finished by then fails with [`FeatureServiceError::JobFailed`] and its last status.
+    /// Defaults to [`DEFAULT_JOB_TIMEOUT`].
+    pub fn with_job_timeout(mut self, timeout: Duration) -> Self {
+        self.job_timeout = timeout;
+        self
+    }
+
+    /// Report the progress of paginated queries, replica and attachment downloads, and bulk uploads to `reporter`
+    pub fn with_progress<R: ProgressReporter + 'static>(mut self, reporter: R) -> Self {
+        self.progress = Some(Arc::new(reporter));
+        self
+    }
+
+    /// Set the token used to authorize requests from `credentials`
+    pub fn with_credentials<C: CredentialProvider>(
+        mut self,
+        credentials: &C,
+    ) -> Result<Self, CredentialError> {
+        self.token = Some(credentials.token()?.into());
+        Ok(self)
+    }
+
+    /// Set the token used to authorize requests to the server token for the layer's server
+    ///
+    /// Portal tokens are exchanged for a server token when the server is federated with a portal.
+    pub fn with_federated_credentials(
+        mut self,
+        credentials: &FederatedCredentials,
+    ) -> Result<Self, FederationError> {
+        self.token = Some(credentials.server_token(&self.layer_url)?.into());
+        Ok(self)
+    }
+
+    /// The URL of the Feature Service containing the layer and the layer's ID
+    ///
+    /// Returns `None` if the layer URL does not end with a numeric layer ID.
+    pub fn service_url(&self) -> Option<(&str, i64)> {
+        let (service_url, id) = self.layer_url.rsplit_once('/')?;
+        Some((service_url, id.parse().ok()?))
+    }
+
+    /// Query the [`/query`](https://developers.arcgis.com/rest/services-reference/enterprise/query-feature-service-layer/) endpoint
+    ///
+    /// Returns a single page of results. See [`FeatureLayerClient::query_all()`].
+    /// Quantized geometries are returned with real-world coordinates.
+    pub fn query<const N: usize>(
+        &self,
+        params: &FeatureQueryParams,
+    ) -> Result<QueryResult<N>, FeatureServiceError> {
+        Ok(self.query_sized(params)?.0)
+    }
+
+    // queries a page, returning its size in bytes
+    pub(crate) fn query_sized<const N: usize>(
+        &self,
+        params: &FeatureQueryParams,
+    ) -> Result<(QueryResult<N>, usize), FeatureServiceError> {
+        let request =
+            HttpRequest::get(&format!("{}/query", self.layer_url)).params(&params.query_pairs());
+
+        self.send_sized(request)
+            .map(|(result, bytes): (QueryResult<N>, _)| (result.dequantize(), bytes))
+    }
+
+    /// Query the `/query` endpoint with `f=pbf`, which is smaller and faster to decode than JSON
+    ///
+    /// Returns a single page of results with real-world coordinates. See [`crate::pbf`].
+    #[cfg(feature = "pbf")]
+    pub fn query_pbf<const N: usize>(
+        &self,
+        params: &FeatureQueryParams,
+    ) -> Result<crate::features::FeatureSet<N>, FeatureServiceError> {
+        let mut pairs = params.query_pairs();
+        pairs.retain(|(k, _)| *k != "f");
+        pairs.push(("f", "pbf".into()));
+        let request = HttpRequest::get(&format!("{}/query", self.layer_url)).params(&pairs);
+
+        let body = trace::send(&self.client, self.authorize(request)?)
+            .map_err(FeatureServiceError::HttpError)?
+            .body;
+
+        // errors are returned as JSON
+        if let Ok(ExpectedResponse::Error(e)) =
+            serde_json::from_slice::<ExpectedResponse<serde_json::Value>>(&body)
+        {
+            return Err(FeatureServiceError::ApiError(e));
+        }
+        crate::pbf::decode(&body).map_err(FeatureServiceError::PbfError)
+    }
+
+    /// Query the statistics of the layer's features with the `outStatistics` of `params`
+    ///
+    /// The rows of the result are deserialized into `A`, a JSON object by default.
+    pub fn query_statistics<A: DeserializeOwned>(
+        &self,
+        params: &FeatureQueryParams,
+    ) -> Result<StatisticsResult<A>, FeatureServiceError> {
+        let request =
+            HttpRequest::get(&format!("{}/query", self.layer_url)).params(&params.query_pairs());
+
+        self.send(request)
+    }
+
+    /// Query the layer and fetch every page of results
+    ///
+    /// Pages are requested using `resultOffset` until the service no longer
+    /// reports `exceededTransferLimit`. The schema of the first page is returned
+    /// with the features of every page, and `exceededTransferLimit` is `false`.
+    /// Pages that fail with a retryable error are retried with the client's [`RetryPolicy`].
+    pub fn query_all<const N: usize>(
+        &self,
+        params: &FeatureQueryParams,
+    ) -> Result<QueryResult<N>, FeatureServiceError> {
+        let mut pages = self.query_pages::<N>(params);
+        let mut result = pages.next().unwrap_or_else(|| Ok(QueryResult::default()))?;
+
+        for page in pages {
+            result.features.extend(page?.features);
+        }
+
+        result.exceededTransferLimit = false;
+        Ok(result)
+    }
+
+    /// Query the layer one page at a time
+    ///
+    /// Unlike [`FeatureLayerClient::query_all()`], the state of the query can be
+    /// exported with [`QueryPages::state()`] to resume a long extract later.
+    pub fn query_pages<const N: usize>(&self, params: &FeatureQueryParams) -> QueryPages<'_, N, H> {
+        let state = QueryState {
+            layer_url: self.layer_url.clone(),
+            params: params.clone(),
+            result_offset: params.result_offset.unwrap_or(0),
+            done: false,
+        };
+        QueryPages::new(self, state)
+    }
+
+    /// Query the layer one feature at a time
+    ///
+    /// Pages are fetched as they are needed, like [`FeatureLayerClient::query_pages()`].
+    ///
+    /// ```no_run
+    /// use serde_esri::feature_service::{FeatureLayerClient, FeatureQueryParamsBuilder};
+    ///
+    /// let client = FeatureLayerClient::new("https://services.arcgis.com/P3ePLMYs2RVChkJx/ArcGIS/rest/services/USA_Counties_Generalized_Boundaries/FeatureServer/0");
+    /// let params = FeatureQueryParamsBuilder::default()
+    ///     .where_clause("STATE_NAME = 'California'")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// for feature in client.query_features::<2>(&params) {
+    ///     println!("{:?}", feature.unwrap().attributes);
+    /// }
+    /// ```
+    pub fn query_features<const N: usize>(
+        &self,
+        params: &FeatureQueryParams,
+    ) -> QueryFeatures<'_, N, H> {
+        QueryFeatures::new(self.query_pages(params))
+    }
+
+    /// Resume a paginated query from a state exported with [`QueryPages::state()`]
+    pub fn resume_pages<const N: usize>(&self, state: QueryState) -> QueryPages<'_, N, H> {
+        QueryPages::new(self, state)
+    }
+
+    /// Query the features within `extent` one tile at a time
+    ///
+    /// `extent` is subdivided with [`EsriEnvelope::tiles()`] and every page of each
+    /// tile is fetched with [`FeatureLayerClient::query_all()`]. Use tiles small
+    /// enough to keep the number of pages of a tile low on dense layers. Features
+    /// intersecting more than one tile are only returned once, based on their object ID.
+    ///
+    /// The `geometry` of `params` is replaced by each tile. `in_sr` defaults to
+    /// the WKID of the extent's spatial reference.
+    pub fn query_tiled<const N: usize>(
+        &self,
+        params: &FeatureQueryParams,
+        extent: &EsriEnvelope,
+        max_width: f64,
+        max_height: f64,
+    ) -> Result<QueryResult<N>, FeatureServiceError> {
+        let in_sr = params
+            .in_sr
+            .or_else(|| extent.spatialReference.as_ref().and_then(|sr| sr.wkid));
+
+        let mut result: Option<QueryResult<N>> = None;
+        let mut seen = HashSet::new();
+
+        for tile in extent.tiles(max_width, max_height) {
+            let params = FeatureQueryParams {
+                geometry: Some(EsriGeometry::Envelope(tile)),
+                in_sr,
+                ..params.clone()
+            };
+            let mut page = self.query_all::<N>(&params)?;
+
+            let oid_field = page.objectIdFieldName.clone();
+            let features = std::mem::take(&mut page.features);
+            let result = result.get_or_insert(page);
+
+            result.features.extend(features.into_iter().filter(|f| {
+                let oid = oid_field.as_ref().and_then(|oid| feature_object_id(f, oid));
+                oid.is_none_or(|oid| seen.insert(oid))
+            }));
+        }
+
+        Ok(result.unwrap_or_default())
+    }
+
+    /// Download every feature of the layer with the Feature Service's [`/createReplica`](https://developers.arcgis.com/rest/services-reference/enterprise/create-replica/) endpoint
+    ///
+    /// The replica is created synchronously without attachments or sync
+    /// capabilities (`syncModel=none`). Geometries are returned in the layer's
+    /// spatial reference. The service must have the `Sync` capability enabled.
+    pub fn create_replica<const N: usize>(&self) -> Result<Vec<Feature<N>>, FeatureServiceError> {
+        let layer_id = self.service_url().map_or(0, |(_, id)| id);
+        let params = CreateReplicaParams {
+            replica_name: format!("serde_esri_{layer_id}"),
+            return_attachments: Some(false),
+            ..Default::default()
+        };
+
+        Ok(self
+            .create_replica_with::<N>(&params)?
+            .layers
+            .into_iter()
+            .filter(|layer| layer.id == layer_id)
+            .flat_map(|layer| layer.features)
+            .collect())
+    }
+
+    /// Create a replica with the Feature Service's [`/createReplica`](https://developers.arcgis.com/rest/services-reference/enterprise/create-replica/) endpoint
+    ///
+    /// `params.layers` defaults to this layer. Asynchronous jobs are polled until
+    /// they complete and replicas returned by URL are downloaded. Use
+    /// [`FeatureLayerClient::download_attachment()`] to fetch the replica's attachments.
+    /// Use [`FeatureLayerClient::export_replica()`] for formats other than `json`.
+    pub fn create_replica_with<const N: usize>(
+        &self,
+        params: &CreateReplicaParams,
+    ) -> Result<ReplicaResponse<N>, FeatureServiceError> {
+        let (service_url, layer_id) = self.service_parts();
+
+        let mut params = params.clone();
+        if params.layers.is_empty() {
+            params.layers = vec![layer_id];
+        }
+
+        let request =
+            HttpRequest::post(&format!("{service_url}/createReplica")).form(&params.form_pairs());
+
+        let (replica, bytes) = if params.run_async == Some(true) {
+            let job = self.send::<JobStatus>(request)?;
+            let status_url = job
+                .status_url
+                .ok_or(FeatureServiceError::JobFailed(job.status))?;
+            let result_url = self.wait_for_job(&status_url)?;
+            self.send_sized::<ReplicaResponse<N>>(HttpRequest::get(&result_url))?
+        } else {
+            let (replica, bytes) = self.send_sized::<ReplicaResponse<N>>(request)?;
+
+            // the service may ignore the transport type and respond with a url
+            match &replica.responseUrl {
+                Some(url) if replica.layers.is_empty() => self.send_sized(HttpRequest::get(url))?,
+                _ => (replica, bytes),
+            }
+        };
+
+        let features = replica.layers.iter().map(|l| l.features.len()).sum();
+        self.report(Operation::Replica, features, bytes);
+        Ok(replica)
+    }
+
+    /// Export the layers of a replica to a file with the Feature Service's [`/createReplica`](https://developers.arcgis.com/rest/services-reference/enterprise/create-replica/) endpoint
+    ///
+    /// The replica is always created by an asynchronous job, which is polled until it
+    /// completes. The file, in the format of `params.data_format`, is written to `writer`
+    /// once it is downloaded. `params.layers` defaults to this layer.
+    ///
+    /// Returns the number of bytes written.
+    pub fn export_replica<W: std::io::Write + ?Sized>(
+        &self,
+        params: &CreateReplicaParams,
+        writer: &mut W,
+    ) -> Result<u64, FeatureServiceError> {
+        let (service_url, layer_id) = self.service_parts();
+
+        let mut params = params.clone();
+        if params.layers.is_empty() {
+            params.layers = vec![layer_id];
+        }
+        params.run_async = Some(true);
+
+        let request =
+            HttpRequest::post(&format!("{service_url}/createReplica")).form(&params.form_pairs());
+
+        let job = self.send::<JobStatus>(request)?;
+        let status_url = job
+            .status_url
+            .ok_or(FeatureServiceError::JobFailed(job.status))?;
+        let result_url = self.wait_for_job(&status_url)?;
+
+        let written = self.download(HttpRequest::get(&result_url), writer)?;
+
+        self.report(Operation::Replica, 0, written as usize);
+        Ok(written)
+    }
+
+    /// Download every feature of the layer as a GeoJSON replica
+    ///
+    /// Unlike [`FeatureLayerClient::create_replica()`], the replica is created by an
+    /// asynchronous job, which suits layers too large to return in a single response.
+    /// Geometries are in WGS84 as required by GeoJSON.
+    #[cfg(feature = "geojson")]
+    pub fn export_feature_set<const N: usize>(
+        &self,
+    ) -> Result<crate::features::FeatureSet<N>, FeatureServiceError> {
+        let layer_id = self.service_url().map_or(0, |(_, id)| id);
+        let params = CreateReplicaParams {
+            replica_name: format!("serde_esri_{layer_id}"),
+            return_attachments: Some(false),
+            data_format: Some(crate::feature_service::ReplicaFormat::GeoJson),
+            ..Default::default()
+        };
+
+        let mut data = Vec::new();
+        self.export_replica(&params, &mut data)?;
+
+        let collection: geojson::FeatureCollection =
+            serde_json::from_slice(&data).map_err(FeatureServiceError::DecodeError)?;
+        crate::features::FeatureSet::try_from(&collection)
+            .map_err(FeatureServiceError::GeoJsonError)
+    }
+
+    /// Upload local edits, including attachments, and download the server's
+    /// changes with the Feature Service's [`/synchronizeReplica`](https://developers.arcgis.com/rest/services-reference/enterprise/synchronize-replica/) endpoint
+    ///
+    /// Attachments can be uploaded ahead of time with
+    /// [`FeatureLayerClient::upload_attachment()`] and referenced by their `uploadId`.
+    pub fn synchronize_replica<const N: usize>(
+        &self,
+        params: &SynchronizeReplicaParams<N>,
+    ) -> Result<SynchronizeReplicaResponse<N>, FeatureServiceError> {
+        let (service_url, _) = self.service_parts();
+        let request = HttpRequest::post(&format!("{service_url}/synchronizeReplica"))
+            .form(&params.form_pairs());
+
+        self.send(request)
+    }
+
+    /// Upload a file with the Feature Service's [`/uploads/upload`](https://developers.arcgis.com/rest/services-reference/enterprise/upload-feature-service/) endpoint
+    ///
+    /// Returns the ID of the uploaded item to use as the `uploadId` of a [`ReplicaAttachment`].
+    pub fn upload_attachment(
+        &self,
+        name: &str,
+        content_type: &str,
+        data: Vec<u8>,
+    ) -> Result<String, FeatureServiceError> {
+        let (service_url, _) = self.service_parts();
+
+        let request = HttpRequest::post(&format!("{service_url}/uploads/upload")).multipart(
+            &[("f", "json")],
+            "file",
+            name,
+            content_type,
+            &data,
+        );
+
+        let resp = self.send::<UploadResponse>(request)?;
+        Ok(resp.item.itemID)
+    }
+
+    /// Download the content of an attachment returned in a replica
+    ///
+    /// Attachments are fetched from their `url` or decoded from their base64
+    /// `data`. The content is verified against the attachment's `size`.
+    pub fn download_attachment(
+        &self,
+        attachment: &ReplicaAttachment,
+    ) -> Result<Vec<u8>, FeatureServiceError> {
+        let data = match (&attachment.url, &attachment.data) {
+            (Some(url), _) => {
+                trace::send(&self.client, self.authorize(HttpRequest::get(url))?)
+                    .and_then(|r| r.error_for_status())
+                    .map_err(FeatureServiceError::HttpError)?
+                    .body
+            }
+            (None, Some(data)) => BASE64_STANDARD
+                .decode(data)
+                .map_err(|_| FeatureServiceError::MissingAttachmentData)?,
+            (None, None) => return Err(FeatureServiceError::MissingAttachmentData),
+        };
+
+        match attachment.size {
+            Some(expected) if expected != data.len() as u64 => {
+                Err(FeatureServiceError::AttachmentSizeMismatch {
+                    expected,
+                    found: data.len() as u64,
+                })
+            }
+            _ => {
+                self.report(Operation::Attachment, 0, data.len());
+                Ok(data)
+            }
+        }
+    }
+
+    /// The attachments of a feature with the [`/attachments`](https://developers.arcgis.com/rest/services-reference/enterprise/attachment-infos-feature-service/) endpoint
+    pub fn query_attachments(
+        &self,
+        object_id: i64,
+    ) -> Result<Vec<AttachmentInfo>, FeatureServiceError> {
+        let request = HttpRequest::get(&format!("{}/{object_id}/attachments", self.layer_url))
+            .param("f", "json");
+
+        Ok(self.send::<AttachmentInfos>(request)?.attachment_infos)
+    }
+
+    /// Download the content of an attachment of a feature
+    ///
+    /// See [`FeatureLayerClient::write_feature_attachment()`] to write it to a file instead.
+    pub fn download_feature_attachment(
+        &self,
+        object_id: i64,
+        attachment_id: i64,
+    ) -> Result<Vec<u8>, FeatureServiceError> {
+        let mut data = Vec::new();
+        self.write_feature_attachment(object_id, attachment_id, &mut data)?;
+        Ok(data)
+    }
+
+    /// Download the content of an attachment of a feature and write it to `writer`
+    ///
+    /// Returns the number of bytes written.
+    pub fn write_feature_attachment<W: std::io::Write + ?Sized>(
+        &self,
+        object_id: i64,
+        attachment_id: i64,
+        writer: &mut W,
+    ) -> Result<u64, FeatureServiceError> {
+        let url = format!("{}/{object_id}/attachments/{attachment_id}", self.layer_url);
+        let written = self.download(HttpRequest::get(&url), writer)?;
+
+        self.report(Operation::Attachment, 0, written as usize);
+        Ok(written)
+    }
+
+    /// Attach a file to a feature with the [`/addAttachment`](https://developers.arcgis.com/rest/services-reference/enterprise/add-attachment/) endpoint
+    ///
+    /// A failure to add the attachment is reported in the result rather than as an error.
+    pub fn add_attachment(
+        &self,
+        object_id: i64,
+        name: &str,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<EditResult, FeatureServiceError> {
+        let url = format!("{}/{object_id}/addAttachment", self.layer_url);
+        let request = HttpRequest::post(&url).multipart(
+            &[("f", "json")],
+            "attachment",
+            name,
+            content_type,
+            &data,
+        );
+
+        Ok(self
+            .send::<AddAttachmentResponse>(request)?
+            .add_attachment_result)
+    }
+
+    /// Add, update, and delete features with the [`/applyEdits`](https://developers.arcgis.com/rest/services-reference/enterprise/apply-edits-feature-service-layer/) endpoint
+    ///
+    /// Edits that fail are reported in the response rather than as an error.
+    /// See [`ApplyEditsResponse::success()`].
+    pub fn apply_edits<const N: usize>(
+        &self,
+        params: &ApplyEditsParams<N>,
+    ) -> Result<ApplyEditsResponse, FeatureServiceError> {
+        let request =
+            HttpRequest::post(&format!("{}/applyEdits", self.layer_url)).form(&params.form_pairs());
+
+        self.send(request)
+    }
+
+    /// Insert or update features based on their GlobalID
+    ///
+    /// The layer is queried for the GlobalIDs of `features` that already exist.
+    /// Those features are updated and the rest are added with their GlobalIDs
+    /// preserved, so syncing the same features again does not create duplicates.
+    /// All edits are rolled back if any fails. Every GlobalID must be a GUID, with or
+    /// without braces.
+    pub fn upsert<const N: usize>(
+        &self,
+        features: Vec<Feature<N>>,
+        global_id_field: &str,
+    ) -> Result<UpsertResults, UpsertError> {
+        let global_ids = features
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let id = feature_global_id(f, global_id_field)
+                    .map(normalize_global_id)
+                    .ok_or(UpsertError::MissingGlobalId(i))?;
+                // the IDs are quoted in a `where` clause
+                match is_global_id(&id) {
+                    true => Ok(id),
+                    false => Err(UpsertError::InvalidGlobalId(i)),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut existing = HashSet::new();
+        for chunk in global_ids.chunks(GLOBAL_ID_CHUNK_SIZE) {
+            let ids = chunk
+                .iter()
+                .map(|id| format!("'{{{id}}}'"))
+                .collect::<Vec<_>>();
+
+            let params = FeatureQueryParams {
+                where_clause: Some(format!("{global_id_field} IN ({})", ids.join(","))),
+                out_fields: Some(vec![global_id_field.to_string()]),
+                return_geometry: Some(false),
+                ..Default::default()
+            };
+
+            let fset = self
+                .query_all::<N>(&params)
+                .map_err(UpsertError::RequestError)?;
+
+            existing.extend(
+                fset.features
+                    .iter()
+                    .filter_map(|f| feature_global_id(f, global_id_field))
+                    .map(normalize_global_id),
+            );
+        }
+
+        let params = ApplyEditsParams::upsert(features, global_id_field, &existing)?;
+        let resp = self
+            .apply_edits(&params)
+            .map_err(UpsertError::RequestError)?;
+
+        Ok(resp.upsert_results())
+    }
+
+    /// Extract the changes made to the layer with the Feature Service's [`/extractChanges`](https://developers.arcgis.com/rest/services-reference/enterprise/extract-changes-feature-service/) endpoint
+    ///
+    /// `params.layers` defaults to this layer. When `params.run_async` is `true`
+    /// the job's status is polled until it completes and its result is downloaded.
+    pub fn extract_changes<const N: usize>(
+        &self,
+        params: &ExtractChangesParams,
+    ) -> Result<ExtractChangesResponse<N>, FeatureServiceError> {
+        let (service_url, layer_id) = self.service_parts();
+
+        let mut params = params.clone();
+        if params.layers.is_empty() {
+            params.layers = vec![layer_id];
+        }
+
+        let request =
+            HttpRequest::post(&format!("{service_url}/extractChanges")).form(&params.form_pairs());
+
+        if params.run_async != Some(true) {
+            return self.send(request);
+        }
+
+        let job = self.send::<JobStatus>(request)?;
+        let status_url = job
+            .status_url
+            .ok_or(FeatureServiceError::JobFailed(job.status))?;
+        let result_url = self.wait_for_job(&status_url)?;
+
+        self.send(HttpRequest::get(&result_url))
+    }
+
+    /// The definition of a layer of the Feature Service containing the layer
+    ///
+    /// Use the ID from [`FeatureLayerClient::service_url()`] for the client's own layer.
+    pub fn layer_definition(&self, layer_id: i64) -> Result<LayerDefinition, FeatureServiceError> {
+        let (service_url, _) = self
+            .service_url()
+            .ok_or_else(|| FeatureServiceError::InvalidUrl(self.layer_url.clone()))?;
+        let request = HttpRequest::get(&format!("{service_url}/{layer_id}")).param("f", "json");
+
+        self.send(request)
+    }
+
+    /// The admin URL of the layer for hosted feature services
+    ///
+    /// `.../rest/services/<name>/FeatureServer/0` becomes `.../rest/admin/services/<name>/FeatureServer/0`.
+    /// Returns `None` if the layer URL is not a hosted feature service URL.
+    pub fn admin_url(&self) -> Option<String> {
+        let (root, service) = self.layer_url.split_once("/rest/services/")?;
+        Some(format!("{root}/rest/admin/services/{service}"))
+    }
+
+    /// Change the layer's definition with the [`/updateDefinition`](https://developers.arcgis.com/rest/services-reference/online/update-definition-feature-layer/) admin endpoint
+    pub fn update_definition(
+        &self,
+        update: &DefinitionUpdate,
+    ) -> Result<AdminResponse, FeatureServiceError> {
+        let admin_url = self.require_admin_url()?;
+        self.admin_request(&admin_url, "updateDefinition", update)
+    }
+
+    /// Change the definition of the Feature Service containing the layer with the [`/updateDefinition`](https://developers.arcgis.com/rest/services-reference/online/update-definition-feature-service/) admin endpoint
+    pub fn update_service_definition(
+        &self,
+        update: &DefinitionUpdate,
+    ) -> Result<AdminResponse, FeatureServiceError> {
+        let admin_url = self.require_admin_url()?;
+        let service_url = admin_url
+            .rsplit_once('/')
+            .map_or(admin_url.as_str(), |(x, _)| x);
+        self.admin_request(service_url, "updateDefinition", update)
+    }
+
+    /// Add layers or tables to the Feature Service containing the layer with the [`/addToDefinition`](https://developers.arcgis.com/rest/services-reference/online/add-to-definition-feature-service/) admin endpoint
+    ///
+    /// `definition` is typically of the form `{"layers": [...], "tables": [...]}`.
+    pub fn add_to_service_definition(
+        &self,
+        definition: &serde_json::Value,
+    ) -> Result<AdminResponse, FeatureServiceError> {
+        let admin_url = self.require_admin_url()?;
+        let service_url = admin_url
+            .rsplit_once('/')
+            .map_or(admin_url.as_str(), |(x, _)| x);
+        self.admin_request(service_url, "addToDefinition", definition)
+    }
+
+    /// Refresh the layer's cached definition with the [`/refresh`](https://developers.arcgis.com/rest/services-reference/online/refresh-feature-layer/) admin endpoint
+    pub fn refresh(&self) -> Result<AdminResponse, FeatureServiceError> {
+        let admin_url = self.require_admin_url()?;
+        let request = HttpRequest::post(&format!("{admin_url}/refresh")).form(&[("f", "json")]);
+
+        self.send(request)
+    }
+
+    /// Delete every feature in the layer with the [`/truncate`](https://developers.arcgis.com/rest/services-reference/online/truncate-feature-layer/) admin endpoint
+    ///
+    /// When `attachment_only` is `true` only the attachments are deleted.
+    pub fn truncate(&self, attachment_only: bool) -> Result<AdminResponse, FeatureServiceError> {
+        let admin_url = self.require_admin_url()?;
+        let form = [
+            ("attachmentOnly", attachment_only.to_string()),
+            ("async", "false".into()),
+            ("f", "json".into()),
+        ];
+        let request = HttpRequest::post(&format!("{admin_url}/truncate")).form(&form);
+
+        self.send(request)
+    }
+
+    fn require_admin_url(&self) -> Result<String, FeatureServiceError> {
+        self.admin_url()
+            .ok_or_else(|| FeatureServiceError::InvalidUrl(self.layer_url.clone()))
+    }
+
+    // sends a definition to an admin endpoint
+    fn admin_request<T: Serialize>(
+        &self,
+        url: &str,
+        operation: &str,
+        update: &T,
+    ) -> Result<AdminResponse, FeatureServiceError> {
+        let form = [
+            (operation, serde_json::to_string(update).unwrap_or_default()),
+            ("async", "false".into()),
+            ("f", "json".into()),
+        ];
+        let request = HttpRequest::post(&format!("{url}/{operation}")).form(&form);
+
+        self.send(request)
+    }
+
+    // polls an asynchronous job until it finishes or times out and returns its result url
+    fn wait_for_job(&self, status_url: &str) -> Result<String, FeatureServiceError> {
+        let start = Instant::now();
+        let mut interval = JOB_POLL_INTERVAL;
+        loop {
+            let request = HttpRequest::get(status_url).param("f", "json");
+            let status = self.send::<JobStatus>(request)?;
+
+            if status.is_completed() {
+                return status
+                    .result_url
+                    .ok_or(FeatureServiceError::JobFailed(status.status));
+            }
+
+            // including jobs stuck in a status, known or not
+            let remaining = self.job_timeout.saturating_sub(start.elapsed());
+            if status.is_failed() || remaining.is_zero() {
+                return Err(FeatureServiceError::JobFailed(status.status));
+            }
+
+            std::thread::sleep(interval.min(remaining));
+            interval = (interval * 2).min(MAX_JOB_POLL_INTERVAL);
+        }
+    }
+
+    // the service url and layer id, treating the layer url as the service url if it has no id
+    fn service_parts(&self) -> (&str, i64) {
+        self.service_url().unwrap_or((self.layer_url.as_str(), 0))
+    }
+
+    fn authorize(&self, request: HttpRequest) -> Result<HttpRequest, FeatureServiceError> {
+        match &self.token {
+            Some(token) => {
+                let bearer = token
+                    .bearer()
+                    .map_err(FeatureServiceError::CredentialError)?;
+                Ok(request.header("X-Esri-Authorization", &bearer))
+            }
+            None => Ok(request),
+        }
+    }
+
+    // authorizes the request, sends it, and handles error responses
+    fn send<T: DeserializeOwned>(&self, request: HttpRequest) -> Result<T, FeatureServiceError> {
+        Ok(self.send_sized(request)?.0)
+    }
+
+    // sends the request, returning the response and its size in bytes
+    fn send_sized<T: DeserializeOwned>(
+        &self,
+        request: HttpRequest,
+    ) -> Result<(T, usize), FeatureServiceError> {
+        let resp = trace::send(&self.client, self.authorize(request)?)
+            .map_err(FeatureServiceError::HttpError)?;
+        let size = resp.body.len();
+
+        // an Esri error is more specific than the status, e.g. from a proxy, that came with it
+        let decoded = serde_json::from_slice::<ExpectedResponse<T>>(&resp.body);
+        match (decoded, resp.error_for_status()) {
+            (Ok(ExpectedResponse::Error(e)), _) => Err(FeatureServiceError::ApiError(e)),
+            (_, Err(e)) => Err(FeatureServiceError::HttpError(e)),
+            (Ok(ExpectedResponse::Ok(x)), Ok(_)) => Ok((x, size)),
+            (Err(e), Ok(_)) => Err(FeatureServiceError::DecodeError(e)),
+        }
+    }
+
+    // authorizes the request, sends it, and writes the body of a successful response to
+    // `writer`, returning its size in bytes
+    fn download<W: std::io::Write + ?Sized>(
+        &self,
+        request: HttpRequest,
+        writer: &mut W,
+    ) -> Result<u64, FeatureServiceError> {
+        let resp = trace::send(&self.client, self.authorize(request)?)
+            .and_then(|r| r.error_for_status())
+            .map_err(FeatureServiceError::HttpError)?;
+        writer
+            .write_all(&resp.body)
+            .map_err(|e| FeatureServiceError::HttpError(HttpError::new(ErrorKind::Fatal, e)))?;
+        Ok(resp.body.len() as u64)
+    }
+
+    // reports a single page or download
+    fn report(&self, operation: Operation, features: usize, bytes: usize) {
+        if let Some(reporter) = &self.progress {
+            let mut progress = Progress::new(operation);
+            progress.add(features, bytes);
+            reporter.report(&progress);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ClassifyError, http::HttpResponse};
+    use std::sync::Mutex;
+
+    // responds with each status and body in turn
+    struct Responses(Mutex<Vec<(u16, &'static str)>>);
+
+    impl HttpClient for Responses {
+        fn execute(&self, _: HttpRequest) -> Result<HttpResponse, HttpError> {
+            let (status, body) = self.0.lock().unwrap().remove(0);
+            Ok(HttpResponse {
+                status,
+                headers: vec![],
+                body: body.as_bytes().to_vec(),
+            })
+        }
+    }
+
+    fn layer(responses: Vec<(u16, &'static str)>) -> FeatureLayerClient<Responses> {
+        FeatureLayerClient::new("https://example.com/FeatureServer/0")
+            .with_http_client(Responses(Mutex::new(responses)))
+    }
+
+    #[test]
+    fn test_http_status() {
+        let edits = ApplyEditsParams::<2>::default();
+
+        // a proxy's error page
+        let e = layer(vec![(503, "<html>Service Unavailable</html>")])
+            .apply_edits(&edits)
+            .unwrap_err();
+        assert!(matches!(e, FeatureServiceError::HttpError(_)));
+        assert!(e.is_retryable());
+
+        // an Esri error sent with an error status
+        let error = r#"{"error": {"code": 498, "message": "Invalid token.", "details": []}}"#;
+        let e = layer(vec![(498, error)]).apply_edits(&edits).unwrap_err();
+        assert!(matches!(e, FeatureServiceError::ApiError(_)));
+        assert!(e.is_auth());
+
+        let resp = layer(vec![(200, r#"{"addResults": []}"#)]).apply_edits(&edits);
+        assert!(resp.unwrap().success());
+    }
+
+    #[test]
+    fn test_job_timeout() {
+        let submitted = r#"{"status": "Pending", "statusUrl": "https://example.com/jobs/1"}"#;
+        let executing = r#"{"status": "esriJobExecuting"}"#;
+        let params = ExtractChangesParams {
+            run_async: Some(true),
+            ..Default::default()
+        };
+
+        let e = layer(vec![(200, submitted), (200, executing)])
+            .with_job_timeout(Duration::ZERO)
+            .extract_changes::<2>(&params)
+            .unwrap_err();
+        assert!(
+            matches!(e, FeatureServiceError::JobFailed(status) if status == "esriJobExecuting")
+        );
+    }
+
+    #[test]
+    fn test_upsert_global_ids() {
+        let feature = |id: &str| -> Feature<2> {
+            serde_json::from_value(serde_json::json!({"attributes": {"GlobalID": id}})).unwrap()
+        };
+        let features = vec![
+            feature("{8a7e1a5c-8d2b-4f2a-9c3e-1b2d3c4e5f60}"),
+            feature("8A7E1A5C-8D2B-4F2A-9C3E-1B2D3C4E5F61') OR (1=1"),
+        ];
+
+        // nothing is sent
+        let e = layer(vec![]).upsert(features, "GlobalID").unwrap_err();
+        assert!(matches!(e, UpsertError::InvalidGlobalId(1)));
+
+        assert!(is_global_id("8A7E1A5C-8D2B-4F2A-9C3E-1B2D3C4E5F60"));
+        assert!(!is_global_id("8A7E1A5C-8D2B-4F2A-9C3E-1B2D3C4E5F6"));
+        assert!(!is_global_id("8A7E1A5C-8D2B-4F2A-9C3E1B2D-3C4E5F60"));
+        assert!(!is_global_id("'8A7E1A5C-8D2B-4F2A-9C3E-1B2D3C4E5F'"));
+    }
+}
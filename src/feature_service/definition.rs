@@ -0,0 +1,159 @@
+use crate::{
+    date::EsriDate,
+    feature_service::{GeometryProperties, UniqueIdField},
+    features::Field,
+    geometry::EsriEnvelope,
+    spatial_reference::SpatialReference,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+
+/// The definition of a layer or table, returned by the layer's URL with `f=json`
+///
+/// Properties without a field of their own, e.g. `advancedQueryCapabilities` or `types`,
+/// are kept in `extra` so that the definition can be re-serialized without losing them.
+///
+/// ```
+/// use serde_esri::feature_service::LayerDefinition;
+///
+/// let json = r#"{
+///     "id": 0,
+///     "name": "Parcels",
+///     "type": "Feature Layer",
+///     "geometryType": "esriGeometryPolygon",
+///     "objectIdField": "OBJECTID",
+///     "extent": {"xmin": 0, "ymin": 0, "xmax": 10, "ymax": 10, "spatialReference": {"wkid": 102100}},
+///     "fields": [{"name": "OBJECTID", "type": "esriFieldTypeOID"}],
+///     "capabilities": "Query,Editing",
+///     "maxRecordCount": 2000
+/// }"#;
+/// let definition: LayerDefinition = serde_json::from_str(json).unwrap();
+/// assert!(definition.supports("Query"));
+/// assert!(definition.field("OBJECTID").is_some());
+/// assert_eq!(definition.spatial_reference().unwrap().normalized_wkid(), Some(3857));
+/// ```
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayerDefinition {
+    pub id: Option<i64>,
+    pub name: Option<String>,
+    /// `Feature Layer` or `Table`
+    #[serde(rename = "type")]
+    pub layer_type: Option<String>,
+    pub description: Option<String>,
+    pub currentVersion: Option<f64>,
+    #[serde(default, deserialize_with = "crate::de::lenient::geometry_type")]
+    pub geometryType: Option<String>,
+    #[serde(default, deserialize_with = "crate::de::lenient::option")]
+    pub hasZ: Option<bool>,
+    #[serde(default, deserialize_with = "crate::de::lenient::option")]
+    pub hasM: Option<bool>,
+    pub objectIdField: Option<String>,
+    pub globalIdField: Option<String>,
+    pub displayField: Option<String>,
+    pub uniqueIdField: Option<UniqueIdField>,
+    pub geometryProperties: Option<GeometryProperties>,
+    pub extent: Option<EsriEnvelope>,
+    pub sourceSpatialReference: Option<SpatialReference>,
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    #[serde(default)]
+    pub indexes: Vec<Index>,
+    pub drawingInfo: Option<DrawingInfo>,
+    /// Comma separated capabilities, e.g. `"Query,Create,Update,Delete,Editing"`
+    pub capabilities: Option<String>,
+    pub maxRecordCount: Option<u64>,
+    #[serde(default, deserialize_with = "crate::de::lenient::option")]
+    pub hasAttachments: Option<bool>,
+    pub editingInfo: Option<EditingInfo>,
+    pub editFieldsInfo: Option<EditFieldsInfo>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl LayerDefinition {
+    pub fn is_table(&self) -> bool {
+        self.layer_type.as_deref() == Some("Table")
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The spatial reference of the layer's extent, or of its source data
+    pub fn spatial_reference(&self) -> Option<&SpatialReference> {
+        self.extent
+            .as_ref()
+            .and_then(|e| e.spatialReference.as_ref())
+            .or(self.sourceSpatialReference.as_ref())
+    }
+
+    pub fn capabilities(&self) -> impl Iterator<Item = &str> {
+        self.capabilities
+            .iter()
+            .flat_map(|c| c.split(','))
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+    }
+
+    /// Whether the layer has the capability, ignoring case
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities()
+            .any(|c| c.eq_ignore_ascii_case(capability))
+    }
+}
+
+/// An attribute index of a layer
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Index {
+    pub name: String,
+    /// Comma separated names of the indexed fields
+    pub fields: String,
+    pub isAscending: Option<bool>,
+    pub isUnique: Option<bool>,
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// How the features of a layer are drawn and labeled
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DrawingInfo {
+    pub renderer: Option<crate::symbology::Renderer>,
+    pub transparency: Option<f64>,
+    pub labelingInfo: Option<Vec<Value>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// When a layer was last edited
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EditingInfo {
+    pub lastEditDate: Option<EsriDate>,
+    pub schemaLastEditDate: Option<EsriDate>,
+    pub dataLastEditDate: Option<EsriDate>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The fields that track who created and edited features, and when
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EditFieldsInfo {
+    pub creationDateField: Option<String>,
+    pub creatorField: Option<String>,
+    pub editDateField: Option<String>,
+    pub editorField: Option<String>,
+    pub realm: Option<String>,
+}
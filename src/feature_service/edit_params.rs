@@ -0,0 +1,195 @@
+use crate::{
+    error::{ClassifyError, ErrorKind},
+    features::Feature,
+};
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Returned when features cannot be upserted
+#[derive(Debug)]
+pub enum UpsertError {
+    /// The feature at this position does not have a GlobalID attribute
+    MissingGlobalId(usize),
+    /// The GlobalID at this position appears more than once
+    DuplicateGlobalId(usize),
+    /// The GlobalID at this position is not a GUID, e.g. `{8A7E1A5C-8D2B-4F2A-9C3E-1B2D3C4E5F60}`
+    InvalidGlobalId(usize),
+    #[cfg(feature = "feature-service-client")]
+    RequestError(crate::feature_service::FeatureServiceError),
+}
+
+impl std::fmt::Display for UpsertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingGlobalId(i) => write!(f, "feature {i} is missing a GlobalID"),
+            Self::DuplicateGlobalId(i) => write!(f, "feature {i} has a duplicate GlobalID"),
+            Self::InvalidGlobalId(i) => write!(f, "the GlobalID of feature {i} is not a GUID"),
+            #[cfg(feature = "feature-service-client")]
+            Self::RequestError(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for UpsertError {}
+
+impl ClassifyError for UpsertError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::MissingGlobalId(_) | Self::DuplicateGlobalId(_) | Self::InvalidGlobalId(_) => {
+                ErrorKind::Fatal
+            }
+            #[cfg(feature = "feature-service-client")]
+            Self::RequestError(e) => e.kind(),
+        }
+    }
+}
+
+/// Normalizes a GlobalID for comparison by removing braces and uppercasing,
+/// e.g. `{a1b2...}` and `A1B2...` are equal
+pub fn normalize_global_id(x: &str) -> String {
+    x.trim()
+        .trim_start_matches('{')
+        .trim_end_matches('}')
+        .to_uppercase()
+}
+
+/// `true` if a normalized GlobalID is a GUID, i.e. hex digits in groups of 8, 4, 4, 4, and 12
+pub fn is_global_id(x: &str) -> bool {
+    let groups: Vec<&str> = x.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(g, len)| g.len() == len && g.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// The GlobalID attribute of a feature
+pub fn feature_global_id<'a, const N: usize>(
+    x: &'a Feature<N>,
+    global_id_field: &str,
+) -> Option<&'a str> {
+    x.attributes.as_ref()?.get(global_id_field)?.as_str()
+}
+
+/// The object ID attribute of a feature
+pub fn feature_object_id<const N: usize>(x: &Feature<N>, object_id_field: &str) -> Option<i64> {
+    x.attributes.as_ref()?.get(object_id_field)?.as_i64()
+}
+
+/// Request parameters used to edit features with the [`/applyEdits`](https://developers.arcgis.com/rest/services-reference/enterprise/apply-edits-feature-service-layer/) endpoint of a feature layer
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyEditsParams<const N: usize> {
+    /// Features to add to the layer.
+    #[builder(setter(into), default)]
+    pub adds: Vec<Feature<N>>,
+    /// Features to update. Each feature must include its object ID in its attributes.
+    #[builder(setter(into), default)]
+    pub updates: Vec<Feature<N>>,
+    /// The object IDs of the features to delete. Not supported with `use_global_ids`.
+    /// Use [`ApplyEditsParams::delete_features()`] to delete features by value.
+    #[builder(setter(into), default)]
+    pub deletes: Vec<i64>,
+    /// When `true`, no edits are applied if any edit fails. Defaults to `true`.
+    #[builder(setter(into, strip_option), default)]
+    pub rollback_on_failure: Option<bool>,
+    /// When `true`, features are identified by their GlobalID rather than their
+    /// object ID and the GlobalIDs of added features are preserved.
+    #[builder(setter(into, strip_option), default)]
+    pub use_global_ids: Option<bool>,
+}
+
+impl<const N: usize> ApplyEditsParams<N> {
+    /// Create the edits that insert or update `features` based on their GlobalID
+    ///
+    /// Features whose GlobalID is in `existing` become updates and all other
+    /// features become adds. GlobalIDs are compared with [`normalize_global_id()`].
+    /// Because added features keep their GlobalID, repeating an upsert with the
+    /// same features is idempotent.
+    ///
+    /// Every feature must have a unique string value for `global_id_field`.
+    pub fn upsert(
+        features: Vec<Feature<N>>,
+        global_id_field: &str,
+        existing: &HashSet<String>,
+    ) -> Result<Self, UpsertError> {
+        let mut seen = HashSet::with_capacity(features.len());
+        let mut params = Self {
+            use_global_ids: Some(true),
+            rollback_on_failure: Some(true),
+            ..Default::default()
+        };
+
+        for (i, feature) in features.into_iter().enumerate() {
+            let global_id = feature_global_id(&feature, global_id_field)
+                .map(normalize_global_id)
+                .ok_or(UpsertError::MissingGlobalId(i))?;
+
+            if existing.contains(&global_id) {
+                params.updates.push(feature);
+            } else {
+                params.adds.push(feature);
+            }
+
+            if !seen.insert(global_id) {
+                return Err(UpsertError::DuplicateGlobalId(i));
+            }
+        }
+
+        Ok(params)
+    }
+
+    /// Delete `features`, identified by their `object_id_field` attribute
+    ///
+    /// Features without an integer object ID are skipped.
+    pub fn delete_features(mut self, features: &[Feature<N>], object_id_field: &str) -> Self {
+        self.deletes.extend(
+            features
+                .iter()
+                .filter_map(|f| feature_object_id(f, object_id_field)),
+        );
+        self
+    }
+
+    /// The parameters as key-value pairs to be used as the body of a form request.
+    ///
+    /// Features are serialized as Esri JSON, deletes are comma separated, and `f=json` is always included.
+    pub fn form_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![];
+
+        if !self.adds.is_empty() {
+            pairs.push((
+                "adds",
+                serde_json::to_string(&self.adds).unwrap_or_default(),
+            ));
+        }
+
+        if !self.updates.is_empty() {
+            pairs.push((
+                "updates",
+                serde_json::to_string(&self.updates).unwrap_or_default(),
+            ));
+        }
+
+        if !self.deletes.is_empty() {
+            let ids = self
+                .deletes
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>();
+            pairs.push(("deletes", ids.join(",")));
+        }
+
+        if let Some(rollback) = self.rollback_on_failure {
+            pairs.push(("rollbackOnFailure", rollback.to_string()));
+        }
+
+        if let Some(use_global_ids) = self.use_global_ids {
+            pairs.push(("useGlobalIds", use_global_ids.to_string()));
+        }
+
+        pairs.push(("f", "json".into()));
+        pairs
+    }
+}
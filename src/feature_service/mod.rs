@@ -0,0 +1,45 @@
+//! Types for querying the layers of a [Feature Service](https://developers.arcgis.com/rest/services-reference/enterprise/feature-service.htm).
+//!
+//! - [`FeatureQueryParamsBuilder`] creates a [`FeatureQueryParams`] struct used to query the [`/query`](https://developers.arcgis.com/rest/services-reference/enterprise/query-feature-service-layer/) endpoint of a layer, which responds with a [`QueryResult`].
+//! - [`QuantizationParameters`] request integer coordinates from a query, which are converted back with [`QueryResult::dequantize()`] and its [`QuantizationTransform`].
+//! - [`ExtractChangesParamsBuilder`] creates an [`ExtractChangesParams`] struct used to query the [`/extractChanges`](https://developers.arcgis.com/rest/services-reference/enterprise/extract-changes-feature-service/) endpoint of a change tracking enabled service.
+//! - [`CreateReplicaParamsBuilder`] and [`SynchronizeReplicaParamsBuilder`] create the parameters used to create and synchronize replicas, including their attachments.
+//! - [`LayerDefinition`] is the definition of a layer, with its fields, extent, renderer, capabilities, indexes, and editing info.
+//! - [`DefinitionUpdateBuilder`] creates a [`DefinitionUpdate`] struct used to change the definition of a hosted service or layer with the `updateDefinition` admin endpoint.
+//! - [`ApplyEditsParamsBuilder`] creates an [`ApplyEditsParams`] struct used to add, update, and delete features with the [`/applyEdits`](https://developers.arcgis.com/rest/services-reference/enterprise/apply-edits-feature-service-layer/) endpoint of a layer.
+//!
+//! Activate the `"feature-service-client"` feature to enable the `FeatureLayerClient` struct which sends these requests and downloads replicas,
+//! and the `BulkUploader` struct which adds large numbers of features in batches.
+//! Queries are paginated with `resultOffset`: `query_all()` collects every page
+//! into one `QueryResult`, while `query_pages()` and `query_features()` fetch the
+//! pages lazily and iterate over pages or individual features.
+mod admin;
+mod changes;
+mod definition;
+mod edit_params;
+mod quantization;
+mod query_params;
+mod replica;
+mod responses;
+pub use admin::*;
+pub use changes::*;
+pub use definition::*;
+pub use edit_params::*;
+pub use quantization::*;
+pub use query_params::*;
+pub use replica::*;
+pub use responses::*;
+
+#[cfg(feature = "feature-service-client")]
+mod bulk;
+#[cfg(feature = "feature-service-client")]
+mod client;
+#[cfg(feature = "feature-service-client")]
+mod pages;
+
+#[cfg(feature = "feature-service-client")]
+pub use bulk::*;
+#[cfg(feature = "feature-service-client")]
+pub use client::*;
+#[cfg(feature = "feature-service-client")]
+pub use pages::*;
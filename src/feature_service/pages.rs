@@ -0,0 +1,133 @@
+use crate::{
+    feature_service::{FeatureLayerClient, FeatureQueryParams, FeatureServiceError, QueryResult},
+    features::Feature,
+    http::HttpClient,
+    progress::{Operation, Progress},
+    trace::PageSpan,
+};
+use serde::{Deserialize, Serialize};
+
+/// The state of a paginated query, used to resume it later
+///
+/// Serialize the state returned by [`QueryPages::state()`] to survive a
+/// restart, then pass it to [`FeatureLayerClient::resume_pages()`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryState {
+    /// The URL of the queried layer
+    pub layer_url: String,
+    pub params: FeatureQueryParams,
+    /// The `resultOffset` of the next page
+    pub result_offset: u64,
+    /// `true` once every page has been fetched
+    pub done: bool,
+}
+
+/// An iterator over the pages of a query
+///
+/// Created by [`FeatureLayerClient::query_pages()`]. Pages are requested using
+/// `resultOffset` until the service no longer reports `exceededTransferLimit`.
+/// A page that fails is not skipped, so the state can be exported and the query
+/// resumed from that page.
+///
+/// Progress is reported to the client's [`ProgressReporter`](crate::progress::ProgressReporter)
+/// after every page.
+#[derive(Debug, Clone)]
+pub struct QueryPages<'a, const N: usize, H = reqwest::blocking::Client> {
+    client: &'a FeatureLayerClient<H>,
+    state: QueryState,
+    progress: Progress,
+}
+
+impl<'a, const N: usize, H: HttpClient> QueryPages<'a, N, H> {
+    pub(crate) fn new(client: &'a FeatureLayerClient<H>, state: QueryState) -> Self {
+        Self {
+            client,
+            state,
+            progress: Progress::new(Operation::Query),
+        }
+    }
+
+    /// The state of the query, i.e. the next page to fetch
+    pub fn state(&self) -> &QueryState {
+        &self.state
+    }
+}
+
+impl<const N: usize, H: HttpClient> Iterator for QueryPages<'_, N, H> {
+    type Item = Result<QueryResult<N>, FeatureServiceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.state.done {
+            return None;
+        }
+
+        let params = FeatureQueryParams {
+            result_offset: Some(self.state.result_offset),
+            ..self.state.params.clone()
+        };
+
+        let span = PageSpan::new(self.progress.pages + 1);
+        let (page, bytes) = match span.in_scope(|| {
+            self.client
+                .retry
+                .run(|_| self.client.query_sized::<N>(&params))
+        }) {
+            Ok(x) => x,
+            Err(e) => return Some(Err(e)),
+        };
+        span.results(page.features.len());
+
+        let n_page = page.features.len() as u64;
+        self.state.result_offset += n_page;
+        self.state.done = !page.exceededTransferLimit || n_page == 0;
+
+        self.progress.add(page.features.len(), bytes);
+        if let Some(reporter) = &self.client.progress {
+            reporter.report(&self.progress);
+        }
+
+        Some(Ok(page))
+    }
+}
+
+/// An iterator over the features of every page of a query
+///
+/// Created by [`FeatureLayerClient::query_features()`]. The next page is
+/// requested once the features of the current page have been returned.
+#[derive(Debug, Clone)]
+pub struct QueryFeatures<'a, const N: usize, H = reqwest::blocking::Client> {
+    pages: QueryPages<'a, N, H>,
+    features: std::vec::IntoIter<Feature<N>>,
+}
+
+impl<'a, const N: usize, H: HttpClient> QueryFeatures<'a, N, H> {
+    pub(crate) fn new(pages: QueryPages<'a, N, H>) -> Self {
+        Self {
+            pages,
+            features: Vec::new().into_iter(),
+        }
+    }
+
+    /// The state of the query, i.e. the next page to fetch
+    pub fn state(&self) -> &QueryState {
+        self.pages.state()
+    }
+}
+
+impl<const N: usize, H: HttpClient> Iterator for QueryFeatures<'_, N, H> {
+    type Item = Result<Feature<N>, FeatureServiceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(feature) = self.features.next() {
+                return Some(Ok(feature));
+            }
+
+            match self.pages.next()? {
+                Ok(page) => self.features = page.features.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::feature_service::QueryResult;
+use crate::geometry::{EsriCoord, EsriEnvelope, EsriGeometry};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+/// Whether quantized geometries are generalized for display or kept for editing
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QuantizationMode {
+    #[default]
+    View,
+    Edit,
+}
+
+/// The corner of the extent that quantized coordinates are counted from
+///
+/// Y values increase downwards from the upper left corner.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OriginPosition {
+    #[default]
+    UpperLeft,
+    BottomLeft,
+}
+
+/// The `quantizationParameters` of a query, which request integer coordinates
+///
+/// The coordinates are snapped to a grid with cells of `tolerance` over `extent`,
+/// in the units of the output spatial reference.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuantizationParameters {
+    pub mode: QuantizationMode,
+    pub origin_position: OriginPosition,
+    pub tolerance: Option<f64>,
+    pub extent: Option<EsriEnvelope>,
+}
+
+/// The `transform` returned with quantized geometries
+///
+/// The coordinates of each path, ring, and multipoint are integers, the first of
+/// which is counted from the origin and each following one from the coordinate before it.
+/// A coordinate is `translate + scale * value` for each dimension with a non-zero scale,
+/// with y values subtracted from an upper left origin.
+///
+/// ```
+/// use serde_esri::feature_service::QuantizationTransform;
+/// use serde_esri::geometry::EsriGeometry;
+///
+/// let transform: QuantizationTransform = serde_json::from_str(
+///     r#"{"originPosition": "upperLeft", "scale": [0.5, 0.5], "translate": [100, 200]}"#,
+/// ).unwrap();
+/// let quantized: EsriGeometry<2> = serde_json::from_str(r#"{"paths": [[[2, 4], [2, -2]]]}"#).unwrap();
+///
+/// let line = transform.dequantize(quantized.clone()).as_polyline().unwrap();
+/// assert_eq!(line.paths[0].0[0].0, [101.0, 198.0]);
+/// assert_eq!(line.paths[0].0[1].0, [102.0, 199.0]);
+///
+/// let requantized = transform.quantize(EsriGeometry::Polyline(line));
+/// assert_eq!(requantized.as_polyline().unwrap().paths[0].0[1].0, [2.0, -2.0]);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuantizationTransform {
+    #[serde(default)]
+    pub origin_position: OriginPosition,
+    pub scale: Vec<f64>,
+    pub translate: Vec<f64>,
+}
+
+impl QuantizationTransform {
+    /// The transform of a grid with cells of `tolerance` over `extent`
+    pub fn new(extent: &EsriEnvelope, tolerance: f64, origin_position: OriginPosition) -> Self {
+        let y = match origin_position {
+            OriginPosition::UpperLeft => extent.ymax,
+            OriginPosition::BottomLeft => extent.ymin,
+        };
+        Self {
+            origin_position,
+            scale: vec![tolerance, tolerance],
+            translate: vec![extent.xmin, y],
+        }
+    }
+
+    // the scale and translation of a dimension, if it is quantized
+    fn dimension(&self, i: usize) -> Option<(f64, f64)> {
+        let scale = *self.scale.get(i).filter(|s| **s != 0.0)?;
+        let translate = self.translate.get(i).copied().unwrap_or_default();
+        match (i, self.origin_position) {
+            (1, OriginPosition::UpperLeft) => Some((-scale, translate)),
+            _ => Some((scale, translate)),
+        }
+    }
+
+    fn to_real(&self, i: usize, value: f64) -> f64 {
+        self.dimension(i)
+            .map_or(value, |(scale, translate)| translate + scale * value)
+    }
+
+    fn to_int(&self, i: usize, value: f64) -> f64 {
+        self.dimension(i).map_or(value, |(scale, translate)| {
+            ((value - translate) / scale).round()
+        })
+    }
+
+    // decodes the x and y deltas of a path and scales every dimension
+    fn dequantize_path<const N: usize>(&self, path: &mut [EsriCoord<N>]) {
+        let mut position = [0.0; 2];
+        for coord in path {
+            for (i, value) in coord.0.iter_mut().enumerate() {
+                if i < 2 {
+                    position[i] += *value;
+                    *value = position[i];
+                }
+                *value = self.to_real(i, *value);
+            }
+        }
+    }
+
+    fn quantize_path<const N: usize>(&self, path: &mut [EsriCoord<N>]) {
+        let mut previous = [0.0; 2];
+        for coord in path {
+            for (i, value) in coord.0.iter_mut().enumerate() {
+                *value = self.to_int(i, *value);
+                if i < 2 {
+                    (*value, previous[i]) = (*value - previous[i], *value);
+                }
+            }
+        }
+    }
+
+    /// The geometry with real-world coordinates
+    ///
+    /// Curves are not quantized and are returned unchanged.
+    pub fn dequantize<const N: usize>(&self, geometry: EsriGeometry<N>) -> EsriGeometry<N> {
+        self.map(geometry, Self::dequantize_path, Self::to_real)
+    }
+
+    /// The geometry with quantized coordinates, the inverse of [`QuantizationTransform::dequantize()`]
+    ///
+    /// Coordinates are rounded to the grid and no vertices are removed.
+    pub fn quantize<const N: usize>(&self, geometry: EsriGeometry<N>) -> EsriGeometry<N> {
+        self.map(geometry, Self::quantize_path, Self::to_int)
+    }
+
+    fn map<const N: usize>(
+        &self,
+        mut geometry: EsriGeometry<N>,
+        path: fn(&Self, &mut [EsriCoord<N>]),
+        value: fn(&Self, usize, f64) -> f64,
+    ) -> EsriGeometry<N> {
+        match &mut geometry {
+            EsriGeometry::Point(point) => {
+                point.x = value(self, 0, point.x);
+                point.y = value(self, 1, point.y);
+                point.z = point.z.map(|z| value(self, 2, z));
+                point.m = point.m.map(|m| value(self, 3, m));
+            }
+            EsriGeometry::MultiPoint(mp) => path(self, &mut mp.points),
+            EsriGeometry::Polyline(pl) => pl.paths.iter_mut().for_each(|p| path(self, &mut p.0)),
+            EsriGeometry::Polygon(ply) => ply.rings.iter_mut().for_each(|r| path(self, &mut r.0)),
+            EsriGeometry::Multipatch(mp) => mp.rings.iter_mut().for_each(|r| path(self, &mut r.0)),
+            EsriGeometry::Envelope(envelope) => {
+                let (x0, x1) = (value(self, 0, envelope.xmin), value(self, 0, envelope.xmax));
+                let (y0, y1) = (value(self, 1, envelope.ymin), value(self, 1, envelope.ymax));
+                (envelope.xmin, envelope.xmax) = (x0.min(x1), x0.max(x1));
+                (envelope.ymin, envelope.ymax) = (y0.min(y1), y0.max(y1));
+            }
+            EsriGeometry::CurvePolygon(_) | EsriGeometry::CurvePolyline(_) => {}
+        }
+        geometry
+    }
+}
+
+impl<const N: usize> QueryResult<N> {
+    /// Replace quantized geometries with real-world coordinates using the result's `transform`
+    ///
+    /// Results without a `transform` are returned unchanged. `FeatureLayerClient`
+    /// dequantizes the results of its queries.
+    pub fn dequantize(mut self) -> Self {
+        if let Some(transform) = self.transform.take() {
+            for feature in &mut self.features {
+                feature.geometry = feature.geometry.take().map(|g| transform.dequantize(g));
+            }
+        }
+        self
+    }
+
+    /// Quantize the geometries with `transform`, which is returned with the result
+    pub fn quantize(self, transform: QuantizationTransform) -> Self {
+        let mut result = self.dequantize();
+        for feature in &mut result.features {
+            feature.geometry = feature.geometry.take().map(|g| transform.quantize(g));
+        }
+        result.transform = Some(transform);
+        result
+    }
+}
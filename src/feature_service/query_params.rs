@@ -0,0 +1,265 @@
+use crate::feature_service::QuantizationParameters;
+use crate::geometry::EsriGeometry;
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+
+/// The spatial relationship used to compare the query `geometry` with the layer's features
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum SpatialRel {
+    #[default]
+    #[serde(rename = "esriSpatialRelIntersects")]
+    Intersects,
+    #[serde(rename = "esriSpatialRelContains")]
+    Contains,
+    #[serde(rename = "esriSpatialRelCrosses")]
+    Crosses,
+    #[serde(rename = "esriSpatialRelEnvelopeIntersects")]
+    EnvelopeIntersects,
+    #[serde(rename = "esriSpatialRelIndexIntersects")]
+    IndexIntersects,
+    #[serde(rename = "esriSpatialRelOverlaps")]
+    Overlaps,
+    #[serde(rename = "esriSpatialRelTouches")]
+    Touches,
+    #[serde(rename = "esriSpatialRelWithin")]
+    Within,
+}
+
+impl SpatialRel {
+    /// The value used by the REST API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpatialRel::Intersects => "esriSpatialRelIntersects",
+            SpatialRel::Contains => "esriSpatialRelContains",
+            SpatialRel::Crosses => "esriSpatialRelCrosses",
+            SpatialRel::EnvelopeIntersects => "esriSpatialRelEnvelopeIntersects",
+            SpatialRel::IndexIntersects => "esriSpatialRelIndexIntersects",
+            SpatialRel::Overlaps => "esriSpatialRelOverlaps",
+            SpatialRel::Touches => "esriSpatialRelTouches",
+            SpatialRel::Within => "esriSpatialRelWithin",
+        }
+    }
+}
+
+/// The statistic computed by a [`StatisticDefinition`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatisticType {
+    Count,
+    Sum,
+    Min,
+    Max,
+    Avg,
+    Stddev,
+    Var,
+}
+
+/// A statistic of a field, used as the `outStatistics` of a query
+///
+/// ```
+/// use serde_esri::feature_service::{FeatureQueryParamsBuilder, StatisticDefinition};
+///
+/// // the population and number of counties of each state
+/// let params = FeatureQueryParamsBuilder::default()
+///     .out_statistics(vec![
+///         StatisticDefinition::sum("POPULATION").named("total"),
+///         StatisticDefinition::count("OBJECTID").named("counties"),
+///     ])
+///     .group_by_fields_for_statistics(vec!["STATE_NAME".to_string()])
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticDefinition {
+    pub statistic_type: StatisticType,
+    pub on_statistic_field: String,
+    /// The name of the statistic in the results. Defaults to a name chosen by the service.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub out_statistic_field_name: Option<String>,
+}
+
+impl StatisticDefinition {
+    pub fn new(statistic_type: StatisticType, field: &str) -> Self {
+        Self {
+            statistic_type,
+            on_statistic_field: field.to_string(),
+            out_statistic_field_name: None,
+        }
+    }
+
+    pub fn count(field: &str) -> Self {
+        Self::new(StatisticType::Count, field)
+    }
+
+    pub fn sum(field: &str) -> Self {
+        Self::new(StatisticType::Sum, field)
+    }
+
+    pub fn min(field: &str) -> Self {
+        Self::new(StatisticType::Min, field)
+    }
+
+    pub fn max(field: &str) -> Self {
+        Self::new(StatisticType::Max, field)
+    }
+
+    pub fn avg(field: &str) -> Self {
+        Self::new(StatisticType::Avg, field)
+    }
+
+    pub fn stddev(field: &str) -> Self {
+        Self::new(StatisticType::Stddev, field)
+    }
+
+    pub fn var(field: &str) -> Self {
+        Self::new(StatisticType::Var, field)
+    }
+
+    /// Set the name of the statistic in the results
+    pub fn named(mut self, name: &str) -> Self {
+        self.out_statistic_field_name = Some(name.to_string());
+        self
+    }
+}
+
+/// Request parameters used to query the [`/query`](https://developers.arcgis.com/rest/services-reference/enterprise/query-feature-service-layer/) endpoint of a feature layer
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureQueryParams {
+    /// A SQL-92 where clause used to filter features. Defaults to `1=1` which returns all features.
+    #[serde(rename = "where")]
+    #[builder(setter(into, strip_option), default)]
+    pub where_clause: Option<String>,
+    /// Only return the features with these object IDs.
+    #[builder(setter(into, strip_option), default)]
+    pub object_ids: Option<Vec<i64>>,
+    /// The fields to include in the response. Defaults to all fields, `*`.
+    #[builder(setter(into, strip_option), default)]
+    pub out_fields: Option<Vec<String>>,
+    /// A geometry to apply as a spatial filter. The `geometryType` is determined from the geometry.
+    #[builder(setter(into, strip_option), default)]
+    pub geometry: Option<EsriGeometry<2>>,
+    /// The WKID of the spatial reference of `geometry`.
+    #[builder(setter(into, strip_option), default)]
+    pub in_sr: Option<u32>,
+    /// The spatial relationship used when `geometry` is provided.
+    #[builder(setter(into, strip_option), default)]
+    pub spatial_rel: Option<SpatialRel>,
+    /// A time instant or extent in milliseconds since the epoch, e.g. `"1199145600000, 1230768000000"`.
+    ///
+    /// Use `null` for an open ended extent. Only applies to time-aware layers.
+    #[builder(setter(into, strip_option), default)]
+    pub time: Option<String>,
+    /// Whether or not to include geometries in the response.
+    #[builder(setter(into, strip_option), default)]
+    pub return_geometry: Option<bool>,
+    /// The WKID of the spatial reference the returned geometries should be in.
+    #[builder(setter(into, strip_option), default)]
+    pub out_sr: Option<u32>,
+    /// Whether or not to include Z values in the returned geometries.
+    #[builder(setter(into, strip_option), default)]
+    pub return_z: Option<bool>,
+    /// Whether or not to include M values in the returned geometries.
+    #[builder(setter(into, strip_option), default)]
+    pub return_m: Option<bool>,
+    /// Fields used to order the features, e.g. `"STATE_NAME DESC"`.
+    #[builder(setter(into, strip_option), default)]
+    pub order_by_fields: Option<Vec<String>>,
+    /// The number of features to skip.
+    #[builder(setter(into, strip_option), default)]
+    pub result_offset: Option<u64>,
+    /// The maximum number of features to return. Cannot exceed the layer's `maxRecordCount`.
+    #[builder(setter(into, strip_option), default)]
+    pub result_record_count: Option<u64>,
+    /// Return geometries with integer coordinates on a grid, which are smaller to transfer.
+    ///
+    /// See [`QueryResult::dequantize()`](crate::feature_service::QueryResult::dequantize).
+    #[builder(setter(into, strip_option), default)]
+    pub quantization_parameters: Option<QuantizationParameters>,
+    /// Statistics to compute instead of returning features.
+    ///
+    /// See [`FeatureLayerClient::query_statistics()`](crate::feature_service::FeatureLayerClient::query_statistics).
+    #[builder(setter(into, strip_option), default)]
+    pub out_statistics: Option<Vec<StatisticDefinition>>,
+    /// Fields whose values group the features the statistics are computed for.
+    #[builder(setter(into, strip_option), default)]
+    pub group_by_fields_for_statistics: Option<Vec<String>>,
+    /// A SQL-92 clause used to filter the groups of statistics, e.g. `"COUNT(OBJECTID) > 10"`.
+    #[builder(setter(into, strip_option), default)]
+    pub having: Option<String>,
+}
+
+impl FeatureQueryParams {
+    /// The parameters as key-value pairs to be used as the query string of a request.
+    ///
+    /// Lists are comma separated, geometries are serialized as Esri JSON, and `f=json` is always included.
+    pub fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let join = |x: &Vec<String>| x.join(",");
+
+        let mut pairs = vec![
+            (
+                "where",
+                self.where_clause.clone().unwrap_or_else(|| "1=1".into()),
+            ),
+            (
+                "outFields",
+                self.out_fields.as_ref().map_or_else(|| "*".into(), join),
+            ),
+        ];
+
+        if let Some(ids) = &self.object_ids {
+            let ids = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+            pairs.push(("objectIds", ids.join(",")));
+        }
+
+        if let Some(geometry) = &self.geometry {
+            pairs.push(("geometryType", geometry.geometry_type().into()));
+            pairs.push((
+                "geometry",
+                serde_json::to_string(geometry).unwrap_or_default(),
+            ));
+            let spatial_rel = self.spatial_rel.unwrap_or_default();
+            pairs.push(("spatialRel", spatial_rel.as_str().into()));
+        }
+
+        let optional = [
+            ("inSR", self.in_sr.map(|x| x.to_string())),
+            ("time", self.time.clone()),
+            (
+                "returnGeometry",
+                self.return_geometry.map(|x| x.to_string()),
+            ),
+            ("outSR", self.out_sr.map(|x| x.to_string())),
+            ("returnZ", self.return_z.map(|x| x.to_string())),
+            ("returnM", self.return_m.map(|x| x.to_string())),
+            ("orderByFields", self.order_by_fields.as_ref().map(join)),
+            ("resultOffset", self.result_offset.map(|x| x.to_string())),
+            (
+                "resultRecordCount",
+                self.result_record_count.map(|x| x.to_string()),
+            ),
+            (
+                "outStatistics",
+                self.out_statistics
+                    .as_ref()
+                    .map(|x| serde_json::to_string(x).unwrap_or_default()),
+            ),
+            (
+                "groupByFieldsForStatistics",
+                self.group_by_fields_for_statistics.as_ref().map(join),
+            ),
+            ("having", self.having.clone()),
+            (
+                "quantizationParameters",
+                self.quantization_parameters
+                    .as_ref()
+                    .map(|x| serde_json::to_string(x).unwrap_or_default()),
+            ),
+        ];
+
+        pairs.extend(optional.into_iter().filter_map(|(k, v)| Some((k, v?))));
+        pairs.push(("f", "json".into()));
+        pairs
+    }
+}
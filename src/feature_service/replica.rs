@@ -0,0 +1,264 @@
+use crate::feature_service::ChangedFeatures;
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+/// The direction attachments are synchronized in a replica
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AttachmentsSyncDirection {
+    #[default]
+    None,
+    Upload,
+    Bidirectional,
+}
+
+impl AttachmentsSyncDirection {
+    /// The value used by the REST API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Upload => "upload",
+            Self::Bidirectional => "bidirectional",
+        }
+    }
+}
+
+/// The format of the data of a replica
+///
+/// Only `json` replicas are returned in the response. The others are written to a file that
+/// is downloaded with `FeatureLayerClient::export_replica()`. Not every service supports
+/// every format.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReplicaFormat {
+    #[default]
+    Json,
+    GeoJson,
+    Sqlite,
+    FileGdb,
+    Shapefile,
+}
+
+impl ReplicaFormat {
+    /// The value used by the REST API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::GeoJson => "geojson",
+            Self::Sqlite => "sqlite",
+            Self::FileGdb => "filegdb",
+            Self::Shapefile => "shapefile",
+        }
+    }
+}
+
+/// The direction of a replica synchronization
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncDirection {
+    #[default]
+    Bidirectional,
+    Download,
+    Upload,
+}
+
+impl SyncDirection {
+    /// The value used by the REST API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bidirectional => "bidirectional",
+            Self::Download => "download",
+            Self::Upload => "upload",
+        }
+    }
+}
+
+/// An attachment included in a replica or in the edits of a replica synchronization
+///
+/// Downloaded attachments have a `url` when `returnAttachmentsDataByUrl=true`.
+/// Attachments being uploaded reference either an `uploadId` returned by the
+/// service's `uploads/upload` endpoint or base64 encoded `data`.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicaAttachment {
+    pub id: Option<i64>,
+    pub global_id: Option<String>,
+    pub parent_global_id: Option<String>,
+    pub name: Option<String>,
+    pub content_type: Option<String>,
+    /// The size of the attachment in bytes
+    pub size: Option<u64>,
+    pub url: Option<String>,
+    pub upload_id: Option<String>,
+    pub data: Option<String>,
+}
+
+/// Attachment edits sent or received in a replica synchronization
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentEdits {
+    #[serde(default)]
+    pub adds: Vec<ReplicaAttachment>,
+    #[serde(default)]
+    pub updates: Vec<ReplicaAttachment>,
+    /// The GlobalIDs of deleted attachments
+    #[serde(default)]
+    pub delete_ids: Vec<String>,
+}
+
+/// The feature and attachment edits of a single layer in a replica synchronization
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplicaLayerEdits<const N: usize> {
+    pub id: i64,
+    pub features: Option<ChangedFeatures<N>>,
+    pub attachments: Option<AttachmentEdits>,
+}
+
+/// Request parameters used to create a replica with the [`/createReplica`](https://developers.arcgis.com/rest/services-reference/enterprise/create-replica/) endpoint of a Feature Service
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateReplicaParams {
+    /// The name of the replica.
+    #[builder(setter(into), default)]
+    pub replica_name: String,
+    /// The IDs of the layers to include. Defaults to the client's layer.
+    #[builder(setter(into), default)]
+    pub layers: Vec<i64>,
+    /// `none` for a snapshot, or `perReplica` / `perLayer` for a replica that can be synchronized. Defaults to `none`.
+    #[builder(setter(into, strip_option), default)]
+    pub sync_model: Option<String>,
+    /// Whether or not to include attachments.
+    #[builder(setter(into, strip_option), default)]
+    pub return_attachments: Option<bool>,
+    /// Return attachments as URLs rather than embedded data.
+    #[builder(setter(into, strip_option), default)]
+    pub return_attachments_data_by_url: Option<bool>,
+    /// The direction attachments will be synchronized in.
+    #[builder(setter(into, strip_option), default)]
+    pub attachments_sync_direction: Option<AttachmentsSyncDirection>,
+    /// Run the replica creation as an asynchronous job.
+    #[builder(setter(into, strip_option), default)]
+    pub run_async: Option<bool>,
+    /// The format of the replica. Defaults to `json`.
+    #[builder(setter(into, strip_option), default)]
+    pub data_format: Option<ReplicaFormat>,
+}
+
+impl CreateReplicaParams {
+    /// The parameters as key-value pairs to be used as the body of a form request.
+    ///
+    /// A `json` replica is embedded in the response when run synchronously. Replicas in
+    /// other formats are written to a file whose URL is returned instead.
+    pub fn form_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![
+            ("replicaName", self.replica_name.clone()),
+            (
+                "layers",
+                serde_json::to_string(&self.layers).unwrap_or_default(),
+            ),
+            (
+                "syncModel",
+                self.sync_model.clone().unwrap_or_else(|| "none".into()),
+            ),
+        ];
+
+        let optional = [
+            (
+                "returnAttachments",
+                self.return_attachments.map(|x| x.to_string()),
+            ),
+            (
+                "returnAttachmentsDataByUrl",
+                self.return_attachments_data_by_url.map(|x| x.to_string()),
+            ),
+            (
+                "attachmentsSyncDirection",
+                self.attachments_sync_direction.map(|x| x.as_str().into()),
+            ),
+            ("async", self.run_async.map(|x| x.to_string())),
+        ];
+
+        pairs.extend(optional.into_iter().filter_map(|(k, v)| Some((k, v?))));
+        let format = self.data_format.unwrap_or_default();
+        let transport = match format {
+            ReplicaFormat::Json => "esriTransportTypeEmbedded",
+            _ => "esriTransportTypeUrl",
+        };
+        pairs.push(("dataFormat", format.as_str().into()));
+        pairs.push(("transportType", transport.into()));
+        pairs.push(("f", "json".into()));
+        pairs
+    }
+}
+
+/// Request parameters used to synchronize a replica with the [`/synchronizeReplica`](https://developers.arcgis.com/rest/services-reference/enterprise/synchronize-replica/) endpoint of a Feature Service
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+#[serde(rename_all = "camelCase")]
+pub struct SynchronizeReplicaParams<const N: usize> {
+    /// The ID of the replica returned by `createReplica`.
+    #[builder(setter(into))]
+    pub replica_id: String,
+    /// The server generation returned by the previous synchronization.
+    #[builder(setter(into, strip_option), default)]
+    pub replica_server_gen: Option<i64>,
+    /// The local edits to upload, including attachments.
+    #[builder(setter(into), default)]
+    pub edits: Vec<ReplicaLayerEdits<N>>,
+    /// Whether edits are uploaded, downloaded or both.
+    #[builder(setter(into, strip_option), default)]
+    pub sync_direction: Option<SyncDirection>,
+    /// Return downloaded attachments as URLs rather than embedded data.
+    #[builder(setter(into, strip_option), default)]
+    pub return_attachments_data_by_url: Option<bool>,
+}
+
+impl<const N: usize> SynchronizeReplicaParams<N> {
+    /// The parameters as key-value pairs to be used as the body of a form request.
+    pub fn form_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![
+            ("replicaID", self.replica_id.clone()),
+            ("syncLayers", "all".into()),
+            (
+                "edits",
+                serde_json::to_string(&self.edits).unwrap_or_default(),
+            ),
+        ];
+
+        let optional = [
+            (
+                "replicaServerGen",
+                self.replica_server_gen.map(|x| x.to_string()),
+            ),
+            (
+                "syncDirection",
+                self.sync_direction.map(|x| x.as_str().into()),
+            ),
+            (
+                "returnAttachmentsDataByUrl",
+                self.return_attachments_data_by_url.map(|x| x.to_string()),
+            ),
+        ];
+
+        pairs.extend(optional.into_iter().filter_map(|(k, v)| Some((k, v?))));
+        pairs.push(("async", "false".into()));
+        pairs.push(("f", "json".into()));
+        pairs
+    }
+}
+
+/// The response from the [`/synchronizeReplica`](https://developers.arcgis.com/rest/services-reference/enterprise/synchronize-replica/) endpoint
+///
+/// `edits` contains the changes, including attachments, made on the server
+/// since the previous synchronization.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SynchronizeReplicaResponse<const N: usize> {
+    pub replicaName: Option<String>,
+    pub replicaID: Option<String>,
+    pub replicaServerGen: Option<i64>,
+    #[serde(default)]
+    pub edits: Vec<ReplicaLayerEdits<N>>,
+}
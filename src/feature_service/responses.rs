@@ -0,0 +1,360 @@
+use crate::{
+    error::{ClassifyError, ErrorKind},
+    feature_service::{QuantizationTransform, ReplicaAttachment},
+    features::{Feature, FeatureSet, Field},
+    spatial_reference::SpatialReference,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+
+/// The error object returned by a feature service when a request fails
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ErrorResponse {
+    pub error: ErrorDetails,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ErrorDetails {
+    pub code: i32,
+    pub message: String,
+    #[serde(default)]
+    pub details: Vec<String>,
+}
+
+impl ClassifyError for ErrorResponse {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::from_code(self.error.code.into())
+    }
+}
+
+/// Feature service endpoints return either the expected object or an [`ErrorResponse`]
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ExpectedResponse<T> {
+    Error(ErrorResponse),
+    Ok(T),
+}
+
+/// The response from the [`/query`](https://developers.arcgis.com/rest/services-reference/enterprise/query-feature-service-layer/) endpoint of a layer
+///
+/// Keeps the metadata returned alongside the features, such as the schema and
+/// spatial reference, so that downstream schemas can be built from it.
+/// Convert it into a [`FeatureSet`] with `.into()`.
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct QueryResult<const N: usize> {
+    pub objectIdFieldName: Option<String>,
+    pub uniqueIdField: Option<UniqueIdField>,
+    pub globalIdFieldName: Option<String>,
+    pub displayFieldName: Option<String>,
+    #[serde(default, deserialize_with = "crate::de::lenient::geometry_type")]
+    pub geometryType: Option<String>,
+    pub geometryProperties: Option<GeometryProperties>,
+    pub spatialReference: Option<SpatialReference>,
+    #[serde(default)]
+    pub hasZ: bool,
+    #[serde(default)]
+    pub hasM: bool,
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    #[serde(default)]
+    pub features: Vec<Feature<N>>,
+    /// `true` when the query matched more features than were returned
+    #[serde(default)]
+    pub exceededTransferLimit: bool,
+    /// The transform of quantized geometries, see [`QueryResult::dequantize()`]
+    pub transform: Option<QuantizationTransform>,
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The response from the `/query` endpoint of a layer to a query with `outStatistics`
+///
+/// Each row holds the statistics of a group, keyed by their `outStatisticFieldName`,
+/// and the values of the `groupByFieldsForStatistics` of the group. Rows can be
+/// deserialized into any type `A`.
+///
+/// ```
+/// use serde_esri::feature_service::StatisticsResult;
+///
+/// let json = r#"{
+///     "fields": [{"name": "STATE_NAME", "type": "esriFieldTypeString"}, {"name": "total", "type": "esriFieldTypeDouble"}],
+///     "features": [{"attributes": {"STATE_NAME": "Maine", "total": 1362359}}]
+/// }"#;
+/// let result: StatisticsResult = serde_json::from_str(json).unwrap();
+/// assert_eq!(result.rows().next().unwrap()["total"], 1362359);
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(bound(deserialize = "A: Deserialize<'de>", serialize = "A: Serialize"))]
+pub struct StatisticsResult<A = Map<String, Value>> {
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    #[serde(default)]
+    pub features: Vec<StatisticsRow<A>>,
+}
+
+/// A row of a [`StatisticsResult`]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct StatisticsRow<A> {
+    pub attributes: A,
+}
+
+impl<A> StatisticsResult<A> {
+    pub fn rows(&self) -> impl Iterator<Item = &A> {
+        self.features.iter().map(|row| &row.attributes)
+    }
+
+    pub fn into_rows(self) -> Vec<A> {
+        self.features
+            .into_iter()
+            .map(|row| row.attributes)
+            .collect()
+    }
+}
+
+/// The field, or fields, that uniquely identify a feature
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UniqueIdField {
+    pub name: String,
+    #[serde(default)]
+    pub isSystemMaintained: bool,
+}
+
+/// The fields storing the area and length of the geometries
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GeometryProperties {
+    pub shapeAreaFieldName: Option<String>,
+    pub shapeLengthFieldName: Option<String>,
+    /// The units of the area and length, e.g. `esriMeters`
+    pub units: Option<String>,
+}
+
+impl<const N: usize> From<QueryResult<N>> for FeatureSet<N> {
+    fn from(value: QueryResult<N>) -> Self {
+        let value = value.dequantize();
+        FeatureSet {
+            objectIdFieldName: value.objectIdFieldName,
+            globalIdFieldName: value.globalIdFieldName,
+            displayFieldName: value.displayFieldName,
+            geometryType: value.geometryType,
+            spatialReference: value.spatialReference,
+            hasZ: Some(value.hasZ),
+            hasM: Some(value.hasM),
+            fields: Some(value.fields),
+            features: value.features,
+            exceededTransferLimit: Some(value.exceededTransferLimit),
+            #[cfg(feature = "preserve-unknown")]
+            extra: value.extra,
+        }
+    }
+}
+
+/// The response from the [`/createReplica`](https://developers.arcgis.com/rest/services-reference/enterprise/create-replica/) endpoint
+///
+/// When the replica is not embedded in the response, `responseUrl` points to a
+/// JSON file containing the replica's `layers`.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReplicaResponse<const N: usize> {
+    pub replicaName: Option<String>,
+    pub replicaID: Option<String>,
+    pub responseUrl: Option<String>,
+    #[serde(default)]
+    pub layers: Vec<ReplicaLayer<N>>,
+}
+
+/// The features and attachments of a single layer in a replica
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReplicaLayer<const N: usize> {
+    pub id: i64,
+    #[serde(default)]
+    pub features: Vec<Feature<N>>,
+    #[serde(default)]
+    pub attachments: Vec<ReplicaAttachment>,
+}
+
+/// The response from the [`/applyEdits`](https://developers.arcgis.com/rest/services-reference/enterprise/apply-edits-feature-service-layer/) endpoint
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyEditsResponse {
+    #[serde(default)]
+    pub add_results: Vec<EditResult>,
+    #[serde(default)]
+    pub update_results: Vec<EditResult>,
+    #[serde(default)]
+    pub delete_results: Vec<EditResult>,
+}
+
+impl ApplyEditsResponse {
+    /// `true` if every edit succeeded
+    pub fn success(&self) -> bool {
+        self.add_results
+            .iter()
+            .chain(&self.update_results)
+            .chain(&self.delete_results)
+            .all(|r| r.success)
+    }
+
+    /// Interpret the response of an upsert made with [`ApplyEditsParams::upsert()`](crate::feature_service::ApplyEditsParams::upsert)
+    pub fn upsert_results(&self) -> UpsertResults {
+        let global_ids = |results: &[EditResult]| {
+            results
+                .iter()
+                .filter(|r| r.success)
+                .filter_map(|r| r.global_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        UpsertResults {
+            inserted: global_ids(&self.add_results),
+            updated: global_ids(&self.update_results),
+            failed: self
+                .add_results
+                .iter()
+                .chain(&self.update_results)
+                .filter(|r| !r.success)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// The outcome of an upsert
+#[derive(Debug, Clone, Default)]
+pub struct UpsertResults {
+    /// The GlobalIDs of the features that were added
+    pub inserted: Vec<String>,
+    /// The GlobalIDs of the features that were updated
+    pub updated: Vec<String>,
+    /// The results of the edits that failed
+    pub failed: Vec<EditResult>,
+}
+
+/// The result of a single add, update, or delete
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditResult {
+    pub object_id: Option<i64>,
+    pub global_id: Option<String>,
+    pub success: bool,
+    pub error: Option<EditError>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EditError {
+    pub code: i32,
+    pub description: String,
+}
+
+/// An attachment of a feature, returned by the `/{objectId}/attachments` endpoint of a layer
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentInfo {
+    pub id: i64,
+    pub name: Option<String>,
+    pub content_type: Option<String>,
+    /// The size of the attachment in bytes
+    pub size: Option<u64>,
+    pub global_id: Option<String>,
+    pub parent_global_id: Option<String>,
+    pub keywords: Option<String>,
+}
+
+/// The response from the `/{objectId}/attachments` endpoint of a layer
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentInfos {
+    #[serde(default)]
+    pub attachment_infos: Vec<AttachmentInfo>,
+}
+
+/// The response from the `/{objectId}/addAttachment` endpoint of a layer
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddAttachmentResponse {
+    pub add_attachment_result: EditResult,
+}
+
+#[cfg(feature = "feature-service-client")]
+#[derive(Debug)]
+pub enum FeatureServiceError {
+    /// The request failed
+    HttpError(crate::http::HttpError),
+    ApiError(ErrorResponse),
+    /// The response is not the expected JSON
+    DecodeError(serde_json::Error),
+    /// An asynchronous job failed or did not return a result. Contains the job's status.
+    JobFailed(String),
+    /// A downloaded attachment does not have the size reported by the service
+    AttachmentSizeMismatch {
+        expected: u64,
+        found: u64,
+    },
+    /// An attachment has neither a `url` nor valid base64 `data`
+    MissingAttachmentData,
+    /// The URL does not support the operation, e.g. it is not a hosted feature service
+    InvalidUrl(String),
+    /// The token provider failed to supply a token
+    CredentialError(crate::auth::CredentialError),
+    /// The response is not a valid `f=pbf` response
+    #[cfg(feature = "pbf")]
+    PbfError(crate::pbf::PbfError),
+    /// A GeoJSON export cannot be converted into a `FeatureSet`
+    #[cfg(feature = "geojson")]
+    GeoJsonError(crate::geojson::GeoJsonConversionError),
+}
+
+#[cfg(feature = "feature-service-client")]
+impl std::fmt::Display for FeatureServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HttpError(e) => write!(f, "{e}"),
+            Self::ApiError(e) => write!(f, "{} ({})", e.error.message, e.error.code),
+            Self::DecodeError(e) => write!(f, "failed to decode response: {e}"),
+            Self::JobFailed(status) => write!(f, "job failed with status `{status}`"),
+            Self::AttachmentSizeMismatch { expected, found } => write!(
+                f,
+                "attachment is {found} bytes but {expected} bytes were expected"
+            ),
+            Self::MissingAttachmentData => write!(f, "attachment has no url or data"),
+            Self::InvalidUrl(url) => write!(f, "unsupported url: {url}"),
+            Self::CredentialError(e) => write!(f, "failed to get token: {e}"),
+            #[cfg(feature = "pbf")]
+            Self::PbfError(e) => write!(f, "failed to decode pbf response: {e}"),
+            #[cfg(feature = "geojson")]
+            Self::GeoJsonError(e) => write!(f, "failed to convert GeoJSON export: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "feature-service-client")]
+impl std::error::Error for FeatureServiceError {}
+
+#[cfg(feature = "feature-service-client")]
+impl ClassifyError for FeatureServiceError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::HttpError(e) => e.kind(),
+            Self::ApiError(e) => e.kind(),
+            Self::CredentialError(e) => e.kind(),
+            // a truncated download
+            Self::AttachmentSizeMismatch { .. } => ErrorKind::Retryable,
+            Self::DecodeError(_)
+            | Self::JobFailed(_)
+            | Self::MissingAttachmentData
+            | Self::InvalidUrl(_) => ErrorKind::Fatal,
+            #[cfg(feature = "pbf")]
+            Self::PbfError(_) => ErrorKind::Fatal,
+            #[cfg(feature = "geojson")]
+            Self::GeoJsonError(_) => ErrorKind::Fatal,
+        }
+    }
+}
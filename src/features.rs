@@ -7,10 +7,35 @@
 //! The Esri [`FeatureSet`](https://developers.arcgis.com/documentation/common-data-types/featureset-object.htm)
 //! object represents a collection of individual features. This is the most common representation that is encountered
 //! when working with a Feature Service via its rest API.
-use crate::{field_type::FieldType, geometry::EsriGeometry, spatial_reference::SpatialReference};
-use serde::{Deserialize, Serialize};
+//!
+//! A [`FeatureSetBuilder`] builds a `FeatureSet` from features, inferring its fields.
+//!
+//! A [`FeatureWriter`] writes a `FeatureSet` to an [`io::Write`](std::io::Write)
+//! sink one feature at a time, so large exports never hold every feature in memory.
+//! A [`FeatureIter`] reads one from an [`io::Read`](std::io::Read) source in the same way.
+//!
+//! Attributes are a JSON object by default. Any type implementing `Serialize` and
+//! `Deserialize` can be used instead, e.g. `Feature<2, MyAttributes>`. Types implementing
+//! [`EsriAttributes`] also describe the fields of their layer. With the `derive`
+//! feature, `#[derive(EsriAttributes)]` implements it from the struct definition.
+//! An [`AttributeMap`](crate::attribute::AttributeMap) gives every attribute a
+//! type from the fields of the `FeatureSet`.
+use crate::{
+    date::{AttributeError, EsriDate},
+    de::{DeError, Limits, Warning},
+    domain::Domain,
+    field_type::FieldType,
+    geometry::{fill_dimension_flags, EsriGeometry},
+    spatial_reference::SpatialReference,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{Map, Value};
 use serde_with::{serde_as, skip_serializing_none, DisplayFromStr};
+use std::cell::Cell;
+use std::io::{BufRead, BufReader, Read, Write};
+
+mod builder;
+pub use builder::*;
 
 // handy reference
 // https://github.com/Esri/arcgis-rest-js/blob/0e410dc16e0dd2961affb09ff7efbfb9b6c4999a/packages/arcgis-rest-request/src/types/feature.ts#L24
@@ -19,28 +44,541 @@ use serde_with::{serde_as, skip_serializing_none, DisplayFromStr};
 ///
 /// Note that both geometry and attributes are optional. This is because
 /// we can anticipate receiving _only_ geometries, or _only_ attributes
-/// or both together.
+/// or both together. The coordinates of the geometry are `T`, `f64` by default.
 #[skip_serializing_none]
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
-pub struct Feature<const N: usize> {
-    pub geometry: Option<EsriGeometry<N>>,
-    pub attributes: Option<Map<String, Value>>,
+pub struct Feature<const N: usize, A = Map<String, Value>, T = f64> {
+    pub geometry: Option<EsriGeometry<N, T>>,
+    pub attributes: Option<A>,
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// A set of geometries and their attributes
+///
+/// The coordinates of the geometries are `T`, `f64` by default.
 #[skip_serializing_none]
 #[allow(non_snake_case)]
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
-pub struct FeatureSet<const N: usize> {
+pub struct FeatureSet<const N: usize, A = Map<String, Value>, T = f64> {
     pub objectIdFieldName: Option<String>,
     pub globalIdFieldName: Option<String>,
     pub displayFieldName: Option<String>,
+    #[serde(default, deserialize_with = "crate::de::lenient::geometry_type")]
     pub geometryType: Option<String>, // TODO should this be an enum?
     pub spatialReference: Option<SpatialReference>,
+    #[serde(default, deserialize_with = "crate::de::lenient::option")]
     pub hasZ: Option<bool>,
+    #[serde(default, deserialize_with = "crate::de::lenient::option")]
     pub hasM: Option<bool>,
     pub fields: Option<Vec<Field>>,
-    pub features: Vec<Feature<N>>,
+    pub features: Vec<Feature<N, A, T>>,
+    /// `true` when a query matched more features than were returned
+    #[serde(default, deserialize_with = "crate::de::lenient::option")]
+    pub exceededTransferLimit: Option<bool>,
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<const N: usize, A, T> Feature<N, A, T> {
+    /// Sets `hasZ` and `hasM` if they are missing on the feature's geometry.
+    ///
+    /// See [`EsriGeometry::with_dimension_flags()`].
+    pub fn with_dimension_flags(mut self) -> Self {
+        self.geometry = self.geometry.map(EsriGeometry::with_dimension_flags);
+        self
+    }
+}
+
+impl<const N: usize, T> Feature<N, Map<String, Value>, T> {
+    /// The attribute `name` as an [`EsriDate`], or `None` if it is missing or null
+    pub fn attr_date(&self, name: &str) -> Result<Option<EsriDate>, AttributeError> {
+        match self.attributes.as_ref().and_then(|a| a.get(name)) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => {
+                EsriDate::from_attribute(value)
+                    .map(Some)
+                    .ok_or_else(|| AttributeError::NotADate {
+                        name: name.to_string(),
+                        value: value.clone(),
+                    })
+            }
+        }
+    }
+
+    /// The attribute `name` as a `DateTime<Utc>`, or `None` if it is missing or null
+    ///
+    /// ```
+    /// use serde_esri::features::Feature;
+    ///
+    /// let feature: Feature<2> = serde_json::from_str(r#"{"attributes": {"created": 1700000000000}}"#).unwrap();
+    /// let created = feature.attr_datetime("created").unwrap().unwrap();
+    /// assert_eq!(created.to_rfc3339(), "2023-11-14T22:13:20+00:00");
+    /// ```
+    #[cfg(feature = "chrono")]
+    pub fn attr_datetime(
+        &self,
+        name: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>, AttributeError> {
+        self.attr_date(name)?
+            .map(|date| date.try_into().map_err(AttributeError::OutOfRange))
+            .transpose()
+    }
+
+    /// Set the attribute `name` to a date, which is serialized as milliseconds since the epoch
+    pub fn set_attr_date<D: Into<EsriDate>>(&mut self, name: &str, date: D) {
+        self.attributes
+            .get_or_insert_with(Map::new)
+            .insert(name.to_string(), date.into().into());
+    }
+}
+
+impl<const N: usize, A, T> FeatureSet<N, A, T> {
+    /// Sets `hasZ` and `hasM` if they are missing on the `FeatureSet` and on
+    /// every feature's geometry based on `N`.
+    pub fn with_dimension_flags(mut self) -> Self {
+        fill_dimension_flags::<N>(&mut self.hasZ, &mut self.hasM);
+        self.features = self
+            .features
+            .into_iter()
+            .map(Feature::with_dimension_flags)
+            .collect();
+        self
+    }
+}
+
+impl<const N: usize, A: DeserializeOwned + Serialize> FeatureSet<N, A> {
+    /// Deserialize a `FeatureSet` from a string of JSON, replacing the values that
+    /// cannot be read with `null` and returning a warning for each
+    ///
+    /// See [`de::from_str_lossy()`](crate::de::from_str_lossy).
+    pub fn from_str_lossy(s: &str) -> Result<(Self, Vec<Warning>), DeError> {
+        crate::de::from_str_lossy(s)
+    }
+}
+
+impl<const N: usize, T> FeatureSet<N, Map<String, Value>, T> {
+    /// The names of the `esriFieldTypeDate` fields
+    pub fn date_fields(&self) -> impl Iterator<Item = &str> {
+        self.fields
+            .iter()
+            .flatten()
+            .filter(|f| matches!(f.field_type, FieldType::EsriFieldTypeDate))
+            .map(|f| f.name.as_str())
+    }
+
+    /// The values of the date fields of `feature` that are not null
+    #[cfg(feature = "chrono")]
+    pub fn datetimes(
+        &self,
+        feature: &Feature<N, Map<String, Value>, T>,
+    ) -> Result<Vec<(&str, chrono::DateTime<chrono::Utc>)>, AttributeError> {
+        let mut dates = Vec::new();
+        for field in self.date_fields() {
+            if let Some(date) = feature.attr_datetime(field)? {
+                dates.push((field, date));
+            }
+        }
+        Ok(dates)
+    }
+
+    /// Rewrite the values of the date fields as milliseconds since the epoch
+    ///
+    /// Some services send dates as strings. Fails on the first value that is not a date.
+    pub fn normalize_dates(&mut self) -> Result<(), AttributeError> {
+        let fields: Vec<String> = self.date_fields().map(String::from).collect();
+        for feature in &mut self.features {
+            for field in &fields {
+                if let Some(date) = feature.attr_date(field)? {
+                    feature.set_attr_date(field, date);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, A: EsriAttributes, T> FeatureSet<N, A, T> {
+    /// Sets `fields` to the fields described by `A`
+    pub fn with_fields(mut self) -> Self {
+        self.fields = Some(A::fields());
+        self
+    }
+}
+
+/// Attributes of a `Feature` with a fixed set of fields
+///
+/// The fields describe the layer the attributes are stored in, e.g. when
+/// creating a layer or building a `FeatureSet` for `applyEdits`.
+///
+/// With the `derive` feature, `#[derive(EsriAttributes)]` implements this trait.
+/// Each field is named after the struct field or its `#[serde(rename)]`,
+/// following `#[serde(rename_all)]`. Field types are inferred from the Rust types:
+///
+/// - `i8`, `i16`, `u8`, and `bool` are `esriFieldTypeSmallInteger`
+/// - `i32` and `u16` are `esriFieldTypeInteger`
+/// - `f32` is `esriFieldTypeSingle`
+/// - `f64` and wider integers are `esriFieldTypeDouble`
+/// - `String`, `&str`, and `char` are `esriFieldTypeString`
+/// - `EsriDate`, `DateTime`, and `SystemTime` are `esriFieldTypeDate`
+/// - `Option<T>` is the type of `T`
+///
+/// Other types require `#[esri(field_type = "...")]`. `#[esri(alias = "...")]`
+/// sets the alias of a field. Fields with `#[serde(skip)]` are omitted.
+pub trait EsriAttributes: Serialize + DeserializeOwned {
+    /// The fields of a layer storing these attributes
+    fn fields() -> Vec<Field>;
+}
+
+#[cfg(feature = "derive")]
+pub use serde_esri_derive::EsriAttributes;
+
+/// Writes a `FeatureSet` to a sink one feature at a time
+///
+/// The envelope of the `FeatureSet`, i.e. every property but its features, is
+/// written first. Features are then written as they are passed to
+/// [`FeatureWriter::write_feature()`]. The `FeatureSet` is incomplete until
+/// [`FeatureWriter::finish()`] is called.
+///
+/// ```
+/// use serde_esri::features::{Feature, FeatureSet, FeatureWriter};
+///
+/// let header = FeatureSet::<2> {
+///     geometryType: Some("esriGeometryPoint".into()),
+///     ..Default::default()
+/// };
+///
+/// let mut writer = FeatureWriter::new(Vec::new(), header).unwrap();
+/// for _ in 0..3 {
+///     writer.write_feature(&Feature::default()).unwrap();
+/// }
+/// let json = String::from_utf8(writer.finish().unwrap()).unwrap();
+/// assert_eq!(json, r#"{"geometryType":"esriGeometryPoint","features":[{},{},{}]}"#);
+/// ```
+#[derive(Debug)]
+pub struct FeatureWriter<W: Write, const N: usize, A = Map<String, Value>, T = f64> {
+    writer: W,
+    written: usize,
+    _features: std::marker::PhantomData<(A, T)>,
+}
+
+impl<W: Write, const N: usize, A: Serialize, T: Serialize> FeatureWriter<W, N, A, T> {
+    /// Write the envelope of `fset` to `writer`, followed by its features
+    pub fn new(mut writer: W, mut fset: FeatureSet<N, A, T>) -> serde_json::Result<Self> {
+        let features = std::mem::take(&mut fset.features);
+
+        let mut envelope = match serde_json::to_value(&fset)? {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        envelope.remove("features");
+
+        writer.write_all(b"{").map_err(serde_json::Error::io)?;
+        for (key, value) in &envelope {
+            serde_json::to_writer(&mut writer, key)?;
+            writer.write_all(b":").map_err(serde_json::Error::io)?;
+            serde_json::to_writer(&mut writer, value)?;
+            writer.write_all(b",").map_err(serde_json::Error::io)?;
+        }
+        writer
+            .write_all(b"\"features\":[")
+            .map_err(serde_json::Error::io)?;
+
+        let mut res = Self {
+            writer,
+            written: 0,
+            _features: std::marker::PhantomData,
+        };
+        for feature in &features {
+            res.write_feature(feature)?;
+        }
+        Ok(res)
+    }
+
+    /// Write a single feature
+    pub fn write_feature(&mut self, feature: &Feature<N, A, T>) -> serde_json::Result<()> {
+        if self.written > 0 {
+            self.writer.write_all(b",").map_err(serde_json::Error::io)?;
+        }
+        serde_json::to_writer(&mut self.writer, feature)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// The number of features written so far
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Close the `FeatureSet`, flush, and return the underlying writer
+    pub fn finish(mut self) -> serde_json::Result<W> {
+        self.writer
+            .write_all(b"]}")
+            .map_err(serde_json::Error::io)?;
+        self.writer.flush().map_err(serde_json::Error::io)?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads the features of a `FeatureSet` one at a time
+///
+/// Only a single feature is held in memory at once. The other properties of the
+/// `FeatureSet` are available from [`FeatureIter::envelope()`]. Properties that
+/// follow the `features` array, such as `exceededTransferLimit` in some responses,
+/// are only read once every feature has been.
+///
+/// ```
+/// use serde_esri::features::FeatureIter;
+///
+/// let json = r#"{"geometryType": "esriGeometryPoint", "features": [
+///     {"geometry": {"x": 1, "y": 2}},
+///     {"geometry": {"x": 3, "y": 4}}
+/// ]}"#;
+///
+/// let mut features = FeatureIter::<_, 2>::from_reader(json.as_bytes()).unwrap();
+/// assert_eq!(features.envelope().unwrap().geometryType.unwrap(), "esriGeometryPoint");
+/// assert_eq!(features.by_ref().count(), 2);
+/// ```
+///
+/// Features from untrusted sources can be checked against [`Limits`], which
+/// apply to all of the features read, before each is deserialized.
+///
+/// ```
+/// use serde_esri::{de::Limits, features::FeatureIter};
+///
+/// let json = r#"{"features": [{"geometry": {"points": [[1, 2], [3, 4]]}}]}"#;
+/// let limits = Limits { max_vertices: Some(1), ..Default::default() };
+/// let mut features = FeatureIter::<_, 2>::from_reader(json.as_bytes())
+///     .unwrap()
+///     .with_limits(limits);
+/// assert!(features.next().unwrap().is_err());
+/// ```
+pub struct FeatureIter<R: Read, const N: usize, A = Map<String, Value>> {
+    reader: BufReader<R>,
+    envelope: Map<String, Value>,
+    state: FeatureIterState,
+    limits: Limits,
+    // the features, rings, and vertices read so far
+    counts: Cell<[usize; 3]>,
+    _attributes: std::marker::PhantomData<A>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FeatureIterState {
+    Features,
+    AfterFeatures,
+    Done,
+}
+
+impl<R: Read, const N: usize, A: DeserializeOwned> FeatureIter<R, N, A> {
+    /// Read the properties of the `FeatureSet` up to its first feature
+    pub fn from_reader(reader: R) -> serde_json::Result<Self> {
+        let mut res = Self {
+            reader: BufReader::new(reader),
+            envelope: Map::new(),
+            state: FeatureIterState::Done,
+            limits: Limits::default(),
+            counts: Cell::new([0; 3]),
+            _attributes: std::marker::PhantomData,
+        };
+
+        res.expect(b'{')?;
+        if res.peek()? == Some(b'}') {
+            res.reader.consume(1);
+        } else {
+            res.read_properties()?;
+        }
+        Ok(res)
+    }
+
+    /// Check the features that are read against `limits`
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The properties of the `FeatureSet` read so far, without its features
+    pub fn envelope(&self) -> serde_json::Result<FeatureSet<N, A>> {
+        let mut envelope = self.envelope.clone();
+        envelope.insert("features".to_string(), Value::Array(vec![]));
+        serde_json::from_value(Value::Object(envelope))
+    }
+
+    // reads properties until the start of the features or the end of the object
+    fn read_properties(&mut self) -> serde_json::Result<()> {
+        loop {
+            let key: String = serde_json::from_slice(&self.read_value()?)?;
+            self.expect(b':')?;
+
+            if key == "features" {
+                self.expect(b'[')?;
+                self.state = if self.peek()? == Some(b']') {
+                    self.reader.consume(1);
+                    FeatureIterState::AfterFeatures
+                } else {
+                    FeatureIterState::Features
+                };
+                return Ok(());
+            }
+
+            let value = serde_json::from_slice(&self.read_value()?)?;
+            self.envelope.insert(key, value);
+            if self.read_delimiter(b'}')? {
+                self.state = FeatureIterState::Done;
+                return Ok(());
+            }
+        }
+    }
+
+    // the next byte that is not whitespace, without consuming it
+    fn peek(&mut self) -> serde_json::Result<Option<u8>> {
+        loop {
+            let buf = self.reader.fill_buf().map_err(serde_json::Error::io)?;
+            match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+                Some(i) => {
+                    let b = buf[i];
+                    self.reader.consume(i);
+                    return Ok(Some(b));
+                }
+                None if buf.is_empty() => return Ok(None),
+                None => {
+                    let n = buf.len();
+                    self.reader.consume(n);
+                }
+            }
+        }
+    }
+
+    fn expect(&mut self, x: u8) -> serde_json::Result<()> {
+        match self.peek()? {
+            Some(b) if b == x => {
+                self.reader.consume(1);
+                Ok(())
+            }
+            found => Err(serde::de::Error::custom(format!(
+                "expected `{}`, found {}",
+                x as char,
+                found.map_or("the end of the input".to_string(), |b| format!(
+                    "`{}`",
+                    b as char
+                ))
+            ))),
+        }
+    }
+
+    // consumes a `,` or `end`, returning `true` for `end`
+    fn read_delimiter(&mut self, end: u8) -> serde_json::Result<bool> {
+        if self.peek()? == Some(end) {
+            self.reader.consume(1);
+            Ok(true)
+        } else {
+            self.expect(b',').map(|_| false)
+        }
+    }
+
+    // the bytes of the next JSON value
+    fn read_value(&mut self) -> serde_json::Result<Vec<u8>> {
+        let mut value = Vec::new();
+        self.peek()?;
+        let mut depth = 0_usize;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        loop {
+            let buf = self.reader.fill_buf().map_err(serde_json::Error::io)?;
+            if buf.is_empty() {
+                return Err(serde::de::Error::custom("unexpected end of the input"));
+            }
+
+            let mut end = None;
+            for (i, &b) in buf.iter().enumerate() {
+                if in_string {
+                    match b {
+                        _ if escaped => escaped = false,
+                        b'\\' => escaped = true,
+                        b'"' => {
+                            in_string = false;
+                            if depth == 0 {
+                                end = Some(i + 1);
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                match b {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' if depth > 0 => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(i + 1);
+                            break;
+                        }
+                    }
+                    // the end of a number, `true`, `false`, or `null`
+                    b',' | b'}' | b']' if depth == 0 => {
+                        end = Some(i);
+                        break;
+                    }
+                    b if b.is_ascii_whitespace() && depth == 0 => {
+                        end = Some(i);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+
+            let n = end.unwrap_or(buf.len());
+            value.extend_from_slice(&buf[..n]);
+            self.reader.consume(n);
+
+            if end.is_some() {
+                return Ok(value);
+            }
+        }
+    }
+}
+
+impl<R: Read, const N: usize, A: DeserializeOwned> Iterator for FeatureIter<R, N, A> {
+    type Item = serde_json::Result<Feature<N, A>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = match self.state {
+            FeatureIterState::Done => return None,
+            FeatureIterState::Features => self.read_value().and_then(|feature| {
+                if self.read_delimiter(b']')? {
+                    self.state = FeatureIterState::AfterFeatures;
+                }
+                self.limits
+                    .check_feature(&feature, &self.counts)
+                    .map_err(serde::de::Error::custom)?;
+                serde_json::from_slice(&feature)
+            }),
+            FeatureIterState::AfterFeatures => {
+                self.state = FeatureIterState::Done;
+                match self.read_delimiter(b'}') {
+                    Ok(true) => return None,
+                    Ok(false) => match self.read_properties() {
+                        Ok(()) => return None,
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e),
+                }
+            }
+        };
+
+        if res.is_err() {
+            self.state = FeatureIterState::Done;
+        }
+        Some(res)
+    }
 }
 
 /// Metadata about an attribute field
@@ -57,11 +595,221 @@ pub struct Field {
     pub field_type: FieldType,
     pub alias: Option<String>,
     pub sqlType: Option<String>,
-    // unsure what this should be
-    pub domain: Option<serde_json::Value>,
+    pub domain: Option<Domain>,
     // unsure what this should be
     pub defaultValue: Option<serde_json::Value>,
+    /// Properties without a field of their own, e.g. `length` or `nullable`.
+    /// These are preserved so a layer definition can be re-serialized without losing them.
+    /// Other properties are unknown in strict mode.
+    #[serde(flatten, serialize_with = "crate::de::strict::field_properties")]
+    pub extra: Map<String, Value>,
 }
 
 // using this query for reference
 // https://services.arcgis.com/P3ePLMYs2RVChkJx/ArcGIS/rest/services/USA_Counties_Generalized_Boundaries/FeatureServer/0/query?where=1%3D1&objectIds=&time=&geometry=&geometryType=esriGeometryEnvelope&inSR=&spatialRel=esriSpatialRelIntersects&resultType=none&distance=0.0&units=esriSRUnit_Meter&relationParam=&returnGeodetic=false&outFields=*&returnGeometry=true&returnCentroid=false&returnEnvelope=false&featureEncoding=esriDefault&multipatchOption=xyFootprint&maxAllowableOffset=&geometryPrecision=&outSR=&defaultSR=&datumTransformation=&applyVCSProjection=false&returnIdsOnly=false&returnUniqueIdsOnly=false&returnCountOnly=false&returnExtentOnly=false&returnQueryGeometry=false&returnDistinctValues=false&cacheHint=false&orderByFields=&groupByFieldsForStatistics=&outStatistics=&having=&resultOffset=&resultRecordCount=1&returnZ=false&returnM=false&returnExceededLimitFeatures=true&quantizationParameters=&sqlFormat=none&f=pjson&token=
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_iter() {
+        // returns a few bytes at a time so that values span several reads
+        struct Trickle<'a>(&'a [u8]);
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = self.0.len().min(buf.len()).min(3);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let json = r#"{"objectIdFieldName": "OID", "fields": [{"name": "OID", "type": "esriFieldTypeOID"}],
+            "features": [
+                {"attributes": {"OID": 1, "name": "a \"quoted\" ], name"}},
+                {"attributes": {"OID": 2, "name": null}, "geometry": {"x": -1.5e2, "y": 2}}
+            ],
+            "exceededTransferLimit": true}"#;
+        let mut features = FeatureIter::<_, 2>::from_reader(Trickle(json.as_bytes())).unwrap();
+        assert_eq!(
+            features.envelope().unwrap().objectIdFieldName.unwrap(),
+            "OID"
+        );
+
+        let feats = features.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(feats.len(), 2);
+        assert_eq!(
+            feats[0].attributes.as_ref().unwrap()["name"],
+            "a \"quoted\" ], name"
+        );
+        assert_eq!(
+            feats[1].geometry.clone().unwrap().as_point().unwrap().x,
+            -150.0
+        );
+        assert_eq!(
+            features.envelope().unwrap().exceededTransferLimit,
+            Some(true)
+        );
+
+        let truncated = r#"{"features": [{"attributes": {}}, {"attri"#;
+        let mut features = FeatureIter::<_, 2>::from_reader(truncated.as_bytes()).unwrap();
+        assert!(features.next().unwrap().is_ok());
+        assert!(features.next().unwrap().is_err());
+        assert!(features.next().is_none());
+    }
+
+    #[test]
+    fn test_f32_coordinates() {
+        let json = r#"{"geometryType": "esriGeometryPolyline", "features": [
+            {"geometry": {"paths": [[[1.5, 2.5], [3.5, 4.5]]]}, "attributes": {"id": 1}}
+        ]}"#;
+        let fset: FeatureSet<2, Map<String, Value>, f32> = serde_json::from_str(json).unwrap();
+        let geometry = fset.features[0].geometry.clone().unwrap();
+        match geometry {
+            EsriGeometry::Polyline(line) => assert_eq!(line.paths[0].0[1].0, [3.5_f32, 4.5]),
+            g => panic!("expected a polyline, found {g:?}"),
+        }
+    }
+
+    #[test]
+    fn test_feature_writer_typed() {
+        #[derive(Serialize, Deserialize)]
+        struct Attributes {
+            id: i32,
+        }
+
+        let json = r#"{"geometryType": "esriGeometryPoint", "features": [
+            {"geometry": {"x": 1.5, "y": 2.5}, "attributes": {"id": 1}}
+        ]}"#;
+        let fset: FeatureSet<2, Attributes, f32> = serde_json::from_str(json).unwrap();
+        let feature = Feature {
+            geometry: None,
+            attributes: Some(Attributes { id: 2 }),
+            #[cfg(feature = "preserve-unknown")]
+            extra: Default::default(),
+        };
+
+        let mut writer = FeatureWriter::new(Vec::new(), fset).unwrap();
+        writer.write_feature(&feature).unwrap();
+        let json = writer.finish().unwrap();
+
+        let fset: FeatureSet<2, Attributes, f32> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(fset.features.len(), 2);
+        assert_eq!(fset.features[1].attributes.as_ref().unwrap().id, 2);
+    }
+
+    #[cfg(feature = "preserve-unknown")]
+    #[test]
+    fn test_preserve_unknown() {
+        let json = serde_json::json!({
+            "geometryType": "esriGeometryPoint",
+            "uniqueIdField": {"name": "OBJECTID", "isSystemMaintained": true},
+            "features": [{
+                "geometry": {"x": 1.0, "y": 2.0},
+                "attributes": {"OBJECTID": 1},
+                "centroid": {"x": 1.0, "y": 2.0}
+            }]
+        });
+        let fset: FeatureSet<2> = serde_json::from_value(json.clone()).unwrap();
+        assert!(fset.extra.contains_key("uniqueIdField"));
+        assert!(fset.features[0].extra.contains_key("centroid"));
+        assert_eq!(serde_json::to_value(&fset).unwrap(), json);
+
+        let json = serde_json::json!({
+            "placeId": "abc",
+            "name": "Cafe",
+            "address": {"locality": "Portland", "countryName": "United States"},
+            "accessibility": {"wheelchair": true}
+        });
+        let place: crate::places::PlaceDetails = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&place).unwrap(), json);
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod derive_tests {
+    use super::*;
+    use crate::date::EsriDate;
+
+    #[derive(Serialize, Deserialize, EsriAttributes)]
+    #[serde(rename_all = "UPPERCASE")]
+    struct Parcel {
+        #[esri(field_type = "esriFieldTypeOID")]
+        objectid: i64,
+        #[esri(alias = "Owner name")]
+        owner: Option<String>,
+        #[serde(rename = "AcreageTotal")]
+        acres: f64,
+        assessed: Option<EsriDate>,
+        #[serde(skip)]
+        _cache: Vec<u8>,
+    }
+
+    #[test]
+    fn test_derive_esri_attributes() {
+        let fields = Parcel::fields()
+            .into_iter()
+            .map(|f| (f.name, f.field_type.as_str_name(), f.alias))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                ("OBJECTID".to_string(), "esriFieldTypeOID", None),
+                (
+                    "OWNER".to_string(),
+                    "esriFieldTypeString",
+                    Some("Owner name".to_string())
+                ),
+                ("AcreageTotal".to_string(), "esriFieldTypeDouble", None),
+                ("ASSESSED".to_string(), "esriFieldTypeDate", None),
+            ]
+        );
+
+        let json = r#"{"features": [{"attributes": {
+            "OBJECTID": 1, "OWNER": null, "AcreageTotal": 2.5, "ASSESSED": 1700000000000
+        }}]}"#;
+        let fset = serde_json::from_str::<FeatureSet<2, Parcel>>(json)
+            .unwrap()
+            .with_fields();
+        let parcel = fset.features[0].attributes.as_ref().unwrap();
+        assert_eq!(parcel.acres, 2.5);
+        assert_eq!(parcel.assessed, Some(EsriDate(1_700_000_000_000)));
+        assert_eq!(fset.fields.unwrap().len(), 4);
+    }
+}
+
+#[cfg(all(test, feature = "chrono"))]
+mod date_tests {
+    use super::*;
+    use crate::date::EsriDate;
+
+    #[test]
+    fn test_normalize_dates() {
+        let json = r#"{
+            "fields": [
+                {"name": "created", "type": "esriFieldTypeDate"},
+                {"name": "name", "type": "esriFieldTypeString"}
+            ],
+            "features": [
+                {"attributes": {"created": "1700000000000", "name": "a"}},
+                {"attributes": {"created": null, "name": "b"}}
+            ]
+        }"#;
+        let mut fset: FeatureSet<2> = serde_json::from_str(json).unwrap();
+        assert_eq!(fset.date_fields().collect::<Vec<_>>(), ["created"]);
+
+        fset.normalize_dates().unwrap();
+        let attributes = |i: usize| fset.features[i].attributes.as_ref().unwrap();
+        assert_eq!(attributes(0)["created"], 1_700_000_000_000_i64);
+        assert_eq!(attributes(0)["name"], "a");
+        assert!(attributes(1)["created"].is_null());
+
+        assert!(fset.features[0].attr_date("name").is_err());
+        fset.features[1].set_attr_date("created", EsriDate(0));
+        assert_eq!(fset.features[1].attr_date("created"), Ok(Some(EsriDate(0))));
+
+        let created = fset.features[0].attr_datetime("created").unwrap().unwrap();
+        assert_eq!(created.to_rfc3339(), "2023-11-14T22:13:20+00:00");
+    }
+}
@@ -0,0 +1,449 @@
+use crate::{
+    features::{EsriAttributes, Feature, FeatureSet, Field},
+    field_type::FieldType,
+    geometry::{dimension_flags, EsriGeometry},
+    spatial_reference::SpatialReference,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// An error building a [`FeatureSet`] with a [`FeatureSetBuilder`]
+#[derive(Debug)]
+pub enum FeatureSetBuildError {
+    /// A feature has a geometry of another type than the first geometry
+    MixedGeometryTypes { expected: String, found: String },
+    /// The value of an attribute cannot be stored in its declared field
+    InvalidValue { field: String, value: Value },
+    /// Attributes could not be serialized, or did not serialize to a JSON object
+    Attributes(serde_json::Error),
+}
+
+impl std::fmt::Display for FeatureSetBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MixedGeometryTypes { expected, found } => {
+                write!(f, "expected {expected} geometries, found {found}")
+            }
+            Self::InvalidValue { field, value } => {
+                write!(f, "`{value}` is not a valid value of field `{field}`")
+            }
+            Self::Attributes(e) => write!(f, "failed to serialize attributes: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for FeatureSetBuildError {}
+
+/// Builds a `FeatureSet` whose fields, object IDs, geometry type, and spatial reference
+/// are consistent with its features
+///
+/// Field types are inferred from the attribute values unless declared with
+/// [`FeatureSetBuilder::field()`] or [`FeatureSetBuilder::fields_of()`]:
+///
+/// - booleans are `esriFieldTypeSmallInteger`
+/// - integers are `esriFieldTypeInteger`, or `esriFieldTypeDouble` when mixed with other numbers
+/// - other numbers are `esriFieldTypeDouble`
+/// - strings, and values of mixed types, are `esriFieldTypeString`
+///
+/// Dates are epoch milliseconds in Esri JSON, so date fields must be declared.
+/// Features without an object ID are numbered after the largest existing ID, starting at 1.
+/// The spatial reference defaults to that of the first geometry, or WGS84.
+///
+/// ```
+/// use serde_esri::features::FeatureSetBuilder;
+/// use serde_esri::field_type::FieldType;
+/// use serde_esri::geometry::{EsriGeometry, EsriPoint};
+/// use serde_json::json;
+///
+/// let point = |x, y| Some(EsriGeometry::<2>::Point(EsriPoint { x, y, ..Default::default() }));
+/// let attributes = |v: serde_json::Value| v.as_object().unwrap().clone();
+///
+/// let fset = FeatureSetBuilder::<2>::new()
+///     .wkid(4326)
+///     .field("visited", FieldType::EsriFieldTypeDate)
+///     .push(point(1.0, 2.0), attributes(json!({"name": "a", "count": 1, "visited": 1700000000000_i64})))
+///     .push(point(3.0, 4.0), attributes(json!({"name": "b", "count": 2.5, "visited": null})))
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(fset.geometryType.as_deref(), Some("esriGeometryPoint"));
+/// let fields = fset.fields.unwrap();
+/// assert_eq!(fields[0].name, "OBJECTID");
+/// let count = fields.iter().find(|f| f.name == "count").unwrap();
+/// assert!(matches!(count.field_type, FieldType::EsriFieldTypeDouble));
+/// assert_eq!(fset.features[1].attributes.as_ref().unwrap()["OBJECTID"], 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FeatureSetBuilder<const N: usize> {
+    features: Vec<Feature<N>>,
+    fields: Vec<Field>,
+    object_id_field: String,
+    spatial_reference: Option<SpatialReference>,
+    // the first error of the attributes pushed so far, returned by `build()`
+    error: Option<String>,
+}
+
+impl<const N: usize> Default for FeatureSetBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FeatureSetBuilder<N> {
+    pub fn new() -> Self {
+        Self {
+            features: Vec::new(),
+            fields: Vec::new(),
+            object_id_field: "OBJECTID".into(),
+            spatial_reference: None,
+            error: None,
+        }
+    }
+
+    /// Add a feature
+    pub fn push_feature(&mut self, feature: Feature<N>) -> &mut Self {
+        self.features.push(feature);
+        self
+    }
+
+    /// Add a feature with `geometry` and `attributes`
+    pub fn push(
+        &mut self,
+        geometry: Option<EsriGeometry<N>>,
+        attributes: Map<String, Value>,
+    ) -> &mut Self {
+        self.push_feature(Feature {
+            geometry,
+            attributes: Some(attributes),
+            #[cfg(feature = "preserve-unknown")]
+            extra: Default::default(),
+        })
+    }
+
+    /// Add a feature whose attributes are serialized from `attributes`, e.g. a struct
+    ///
+    /// Attributes that do not serialize to a JSON object are reported by [`FeatureSetBuilder::build()`].
+    pub fn push_serialized<A: Serialize>(
+        &mut self,
+        geometry: Option<EsriGeometry<N>>,
+        attributes: &A,
+    ) -> &mut Self {
+        match serde_json::to_value(attributes) {
+            Ok(Value::Object(attributes)) => self.push(geometry, attributes),
+            Ok(value) => {
+                self.error
+                    .get_or_insert(format!("{value} is not an object"));
+                self
+            }
+            Err(e) => {
+                self.error.get_or_insert(e.to_string());
+                self
+            }
+        }
+    }
+
+    /// Add features
+    pub fn extend<I: IntoIterator<Item = Feature<N>>>(&mut self, features: I) -> &mut Self {
+        self.features.extend(features);
+        self
+    }
+
+    /// Declare the type of a field instead of inferring it
+    pub fn field(&mut self, name: &str, field_type: FieldType) -> &mut Self {
+        self.declare(Field {
+            name: name.to_string(),
+            field_type,
+            ..Default::default()
+        })
+    }
+
+    /// Declare the fields described by `A`
+    pub fn fields_of<A: EsriAttributes>(&mut self) -> &mut Self {
+        for field in A::fields() {
+            self.declare(field);
+        }
+        self
+    }
+
+    /// The name of the object ID field. Defaults to `OBJECTID`.
+    pub fn object_id_field(&mut self, name: &str) -> &mut Self {
+        self.object_id_field = name.to_string();
+        self
+    }
+
+    pub fn spatial_reference(&mut self, spatial_reference: SpatialReference) -> &mut Self {
+        self.spatial_reference = Some(spatial_reference);
+        self
+    }
+
+    pub fn wkid(&mut self, wkid: u32) -> &mut Self {
+        self.spatial_reference(SpatialReference::from_wkid(wkid))
+    }
+
+    /// Build the `FeatureSet`, checking the geometries and the values of declared fields
+    pub fn build(&self) -> Result<FeatureSet<N>, FeatureSetBuildError> {
+        if let Some(e) = &self.error {
+            return Err(FeatureSetBuildError::Attributes(serde::ser::Error::custom(
+                e,
+            )));
+        }
+
+        let geometry_type = self.geometry_type()?;
+        let mut features = self.features.clone();
+        self.assign_object_ids(&mut features);
+
+        let mut fields = vec![Field {
+            name: self.object_id_field.clone(),
+            field_type: FieldType::EsriFieldTypeOid,
+            alias: Some(self.object_id_field.clone()),
+            ..Default::default()
+        }];
+        for field in &self.fields {
+            if field.name != self.object_id_field {
+                fields.push(field.clone());
+            }
+        }
+        for field in infer_fields(&features) {
+            if !fields.iter().any(|f| f.name == field.name) {
+                fields.push(field);
+            }
+        }
+        check_values(&self.fields, &features)?;
+
+        let spatial_reference = geometry_type.as_ref().map(|_| {
+            self.spatial_reference
+                .clone()
+                .or_else(|| first_spatial_reference(&features))
+                .unwrap_or_else(|| SpatialReference::from_wkid(4326))
+        });
+        let (has_z, has_m) = dimension_flags::<N>();
+
+        Ok(FeatureSet {
+            objectIdFieldName: Some(self.object_id_field.clone()),
+            geometryType: geometry_type,
+            spatialReference: spatial_reference,
+            hasZ: Some(has_z),
+            hasM: Some(has_m),
+            fields: Some(fields),
+            features,
+            ..Default::default()
+        })
+    }
+
+    // adds the field, replacing an earlier declaration of it
+    fn declare(&mut self, field: Field) -> &mut Self {
+        match self.fields.iter_mut().find(|f| f.name == field.name) {
+            Some(existing) => *existing = field,
+            None => self.fields.push(field),
+        }
+        self
+    }
+
+    // the type shared by every geometry
+    fn geometry_type(&self) -> Result<Option<String>, FeatureSetBuildError> {
+        let mut types = self
+            .features
+            .iter()
+            .filter_map(|f| f.geometry.as_ref())
+            .map(EsriGeometry::geometry_type);
+
+        let Some(expected) = types.next() else {
+            return Ok(None);
+        };
+        match types.find(|t| *t != expected) {
+            Some(found) => Err(FeatureSetBuildError::MixedGeometryTypes {
+                expected: expected.to_string(),
+                found: found.to_string(),
+            }),
+            None => Ok(Some(expected.to_string())),
+        }
+    }
+
+    // numbers the features without an integer object ID after the largest one
+    fn assign_object_ids(&self, features: &mut [Feature<N>]) {
+        let name = &self.object_id_field;
+        let id = |f: &Feature<N>| f.attributes.as_ref()?.get(name)?.as_i64();
+        let mut next = features.iter().filter_map(id).max().unwrap_or(0) + 1;
+
+        for feature in features.iter_mut() {
+            if id(feature).is_none() {
+                let attributes = feature.attributes.get_or_insert_with(Map::new);
+                attributes.insert(name.clone(), next.into());
+                next += 1;
+            }
+        }
+    }
+}
+
+impl FeatureSetBuilder<2> {
+    /// Add a feature from a `geo_types` geometry
+    ///
+    /// Geometry collections of a single type are merged. Other geometry collections are
+    /// added without a geometry.
+    #[cfg(feature = "from-geo")]
+    pub fn push_geo(
+        &mut self,
+        geometry: &geo_types::Geometry,
+        attributes: Map<String, Value>,
+    ) -> &mut Self {
+        self.push(geometry.try_into().ok(), attributes)
+    }
+}
+
+fn first_spatial_reference<const N: usize>(features: &[Feature<N>]) -> Option<SpatialReference> {
+    features
+        .iter()
+        .filter_map(|f| f.geometry.as_ref())
+        .find_map(|g| g.spatial_reference().cloned())
+}
+
+// checks that every value of a declared field can be stored in it
+fn check_values<const N: usize>(
+    declared: &[Field],
+    features: &[Feature<N>],
+) -> Result<(), FeatureSetBuildError> {
+    use FieldType::*;
+
+    for attributes in features.iter().filter_map(|f| f.attributes.as_ref()) {
+        for field in declared {
+            let Some(value) = attributes.get(&field.name).filter(|v| !v.is_null()) else {
+                continue;
+            };
+            let valid = match field.field_type {
+                EsriFieldTypeSmallInteger
+                | EsriFieldTypeInteger
+                | EsriFieldTypeBigInteger
+                | EsriFieldTypeOid => value.is_i64() || value.is_u64() || value.is_boolean(),
+                EsriFieldTypeSingle | EsriFieldTypeDouble => value.is_number(),
+                EsriFieldTypeDate => value.is_i64() || value.is_string(),
+                EsriFieldTypeString
+                | EsriFieldTypeGuid
+                | EsriFieldTypeGlobalId
+                | EsriFieldTypeXml
+                | EsriFieldTypeDateOnly
+                | EsriFieldTypeTimeOnly
+                | EsriFieldTypeTimestampOffset => value.is_string(),
+                _ => true,
+            };
+            if !valid {
+                return Err(FeatureSetBuildError::InvalidValue {
+                    field: field.name.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// infers the type of each attribute from its non-null values,
+// widening integers to doubles and anything mixed to strings
+pub(crate) fn infer_fields<const N: usize>(features: &[Feature<N>]) -> Vec<Field> {
+    let mut fields: Vec<Field> = Vec::new();
+
+    for attributes in features.iter().filter_map(|f| f.attributes.as_ref()) {
+        for (name, value) in attributes {
+            let Some(field_type) = value_field_type(value) else {
+                continue;
+            };
+
+            match fields.iter_mut().find(|f| &f.name == name) {
+                Some(field) => {
+                    field.field_type = widen(&field.field_type, field_type);
+                }
+                None => fields.push(Field {
+                    name: name.clone(),
+                    field_type,
+                    ..Default::default()
+                }),
+            }
+        }
+    }
+
+    fields
+}
+
+fn widen(current: &FieldType, other: FieldType) -> FieldType {
+    use FieldType::*;
+    match (current, other) {
+        (EsriFieldTypeInteger, EsriFieldTypeInteger) => EsriFieldTypeInteger,
+        (EsriFieldTypeSmallInteger, EsriFieldTypeSmallInteger) => EsriFieldTypeSmallInteger,
+        (
+            EsriFieldTypeInteger | EsriFieldTypeDouble,
+            EsriFieldTypeInteger | EsriFieldTypeDouble,
+        ) => EsriFieldTypeDouble,
+        _ => EsriFieldTypeString,
+    }
+}
+
+fn value_field_type(value: &Value) -> Option<FieldType> {
+    match value {
+        Value::Null => None,
+        Value::Bool(_) => Some(FieldType::EsriFieldTypeSmallInteger),
+        Value::Number(n) if n.is_f64() => Some(FieldType::EsriFieldTypeDouble),
+        Value::Number(_) => Some(FieldType::EsriFieldTypeInteger),
+        _ => Some(FieldType::EsriFieldTypeString),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{EsriPoint, EsriPolyline};
+    use serde_json::json;
+
+    #[test]
+    fn test_feature_set_builder() {
+        let point = EsriGeometry::<2>::Point(EsriPoint::default());
+        let line = EsriGeometry::<2>::Polyline(EsriPolyline::default());
+        let attrs = |v: Value| v.as_object().unwrap().clone();
+
+        // existing object ids are kept and the others numbered after them
+        let fset = FeatureSetBuilder::<2>::new()
+            .push(None, attrs(json!({"OBJECTID": 5, "flag": true})))
+            .push(None, attrs(json!({"flag": "x"})))
+            .build()
+            .unwrap();
+        let ids: Vec<_> = fset
+            .features
+            .iter()
+            .map(|f| f.attributes.as_ref().unwrap()["OBJECTID"].clone())
+            .collect();
+        assert_eq!(ids, vec![json!(5), json!(6)]);
+        assert_eq!(fset.geometryType, None);
+        assert_eq!(fset.spatialReference, None);
+        let fields = fset.fields.unwrap();
+        assert_eq!(fields.len(), 2);
+        assert!(matches!(
+            fields[1].field_type,
+            FieldType::EsriFieldTypeString
+        ));
+
+        let mixed = FeatureSetBuilder::<2>::new()
+            .push(Some(point.clone()), Map::new())
+            .push(Some(line), Map::new())
+            .build();
+        assert!(matches!(
+            mixed,
+            Err(FeatureSetBuildError::MixedGeometryTypes { .. })
+        ));
+
+        let invalid = FeatureSetBuilder::<2>::new()
+            .field("n", FieldType::EsriFieldTypeInteger)
+            .push(Some(point), attrs(json!({"n": "one"})))
+            .build();
+        assert!(matches!(
+            invalid,
+            Err(FeatureSetBuildError::InvalidValue { .. })
+        ));
+
+        let not_an_object = FeatureSetBuilder::<2>::new()
+            .push_serialized(None, &[1, 2])
+            .build();
+        assert!(matches!(
+            not_an_object,
+            Err(FeatureSetBuildError::Attributes(_))
+        ));
+    }
+}
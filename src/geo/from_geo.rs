@@ -1,12 +1,15 @@
 // This module is for converting geo types into Esri geometry objects
 // requires geo for cw and ccw enforcement
+//
+// The `Into` conversions produce 2 dimensional geometries. `ToEsriZM`
+// adds Z and M values to them.
 
 use crate::geometry::*;
 use geo::orient::Direction;
 use geo::{BooleanOps, CoordsIter, Orient};
 use geo_types::{
-    Coord, Geometry, Line, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon,
-    Rect, Triangle,
+    Coord, Geometry, GeometryCollection, Line, LineString, MultiLineString, MultiPoint,
+    MultiPolygon, Point, Polygon, Rect, Triangle,
 };
 
 // macro to implement T from &T impl
@@ -123,7 +126,7 @@ impl Into<EsriPolygon<2>> for &Polygon {
         EsriPolygon {
             hasZ: None,
             hasM: None,
-            rings: rings,
+            rings,
             spatialReference: None,
         }
     }
@@ -141,7 +144,7 @@ impl Into<EsriPolygon<2>> for &MultiPolygon {
         EsriPolygon {
             hasZ: None,
             hasM: None,
-            rings: rings,
+            rings,
             spatialReference: None,
         }
     }
@@ -162,6 +165,20 @@ impl Into<EsriPolygon<2>> for &Rect {
 }
 impl_into!(Rect, EsriPolygon<2>);
 
+impl Into<EsriEnvelope> for &Rect {
+    fn into(self) -> EsriEnvelope {
+        let (min, max) = (self.min(), self.max());
+        EsriEnvelope {
+            xmin: min.x,
+            ymin: min.y,
+            xmax: max.x,
+            ymax: max.y,
+            ..Default::default()
+        }
+    }
+}
+impl_into!(Rect, EsriEnvelope);
+
 impl TryInto<EsriGeometry<2>> for &Geometry {
     type Error = Option<()>;
 
@@ -179,7 +196,8 @@ impl TryInto<EsriGeometry<2>> for &Geometry {
             Geometry::Rect(g) => Ok(EsriGeometry::Polygon(g.into())),
             Geometry::Triangle(g) => Ok(EsriGeometry::Polygon(g.into())),
 
-            Geometry::GeometryCollection(_g) => Err(None),
+            // only homogeneous collections can be represented by a single geometry
+            Geometry::GeometryCollection(g) => merge_geometry_collection(g).ok_or(None),
         }
     }
 }
@@ -190,6 +208,271 @@ impl TryInto<EsriGeometry<2>> for Geometry {
     }
 }
 
+// Z and M values
+//
+// geo-types coordinates only have x and y. The Z and M values are supplied by
+// a closure so that they can be looked up from elsewhere, e.g. an elevation model.
+
+/// Converts geo-types geometries into Esri geometries with Z and M values.
+///
+/// `zm` returns the `(z, m)` values of each coordinate. An `N` of `3` keeps
+/// the Z value and `4` keeps both. `hasZ` and `hasM` are set based on `N`.
+/// Polygons are rewound as they are by the `Into` conversions.
+///
+/// Panics if `N` is greater than `4`.
+///
+/// ```
+/// use geo_types::{line_string, Coord};
+/// use serde_esri::{geo::from_geo::ToEsriZM, geometry::EsriPolyline};
+///
+/// let line = line_string![(x: 0.0, y: 0.0), (x: 10.0, y: 0.0)];
+/// let polyline: EsriPolyline<4> = line.to_esri_zm(|c: &Coord| (100.0, c.x));
+///
+/// assert_eq!(polyline.paths[0].0[1].0, [10.0, 0.0, 100.0, 10.0]);
+/// assert_eq!(polyline.hasM, Some(true));
+/// ```
+pub trait ToEsriZM<const N: usize> {
+    type Output;
+
+    fn to_esri_zm<F: FnMut(&Coord) -> (f64, f64)>(&self, zm: F) -> Self::Output;
+}
+
+fn coord_zm<const N: usize>(c: &Coord, zm: &mut impl FnMut(&Coord) -> (f64, f64)) -> [f64; N] {
+    let (z, m) = zm(c);
+    let xyzm = [c.x, c.y, z, m];
+    std::array::from_fn(|i| xyzm[i])
+}
+
+fn line_zm<const N: usize>(
+    x: &LineString,
+    zm: &mut impl FnMut(&Coord) -> (f64, f64),
+) -> Vec<[f64; N]> {
+    x.coords().map(|c| coord_zm(c, zm)).collect()
+}
+
+impl<const N: usize> ToEsriZM<N> for Point {
+    type Output = EsriPoint;
+
+    fn to_esri_zm<F: FnMut(&Coord) -> (f64, f64)>(&self, mut zm: F) -> EsriPoint {
+        let (has_z, has_m) = dimension_flags::<N>();
+        let (z, m) = zm(&self.0);
+        EsriPoint {
+            x: self.x(),
+            y: self.y(),
+            z: has_z.then_some(z),
+            m: has_m.then_some(m),
+            spatialReference: None,
+        }
+    }
+}
+
+impl<const N: usize> ToEsriZM<N> for MultiPoint {
+    type Output = EsriMultiPoint<N>;
+
+    fn to_esri_zm<F: FnMut(&Coord) -> (f64, f64)>(&self, mut zm: F) -> EsriMultiPoint<N> {
+        EsriMultiPoint::from_coords_zm(self.iter().map(|p| coord_zm(&p.0, &mut zm)))
+    }
+}
+
+impl<const N: usize> ToEsriZM<N> for LineString {
+    type Output = EsriPolyline<N>;
+
+    fn to_esri_zm<F: FnMut(&Coord) -> (f64, f64)>(&self, mut zm: F) -> EsriPolyline<N> {
+        EsriPolyline::from_coords_zm([line_zm(self, &mut zm)])
+    }
+}
+
+impl<const N: usize> ToEsriZM<N> for MultiLineString {
+    type Output = EsriPolyline<N>;
+
+    fn to_esri_zm<F: FnMut(&Coord) -> (f64, f64)>(&self, mut zm: F) -> EsriPolyline<N> {
+        EsriPolyline::from_coords_zm(self.iter().map(|l| line_zm(l, &mut zm)))
+    }
+}
+
+impl<const N: usize> ToEsriZM<N> for Polygon {
+    type Output = EsriPolygon<N>;
+
+    fn to_esri_zm<F: FnMut(&Coord) -> (f64, f64)>(&self, mut zm: F) -> EsriPolygon<N> {
+        let rewound = self.orient(Direction::Reversed);
+        EsriPolygon::from_coords_zm(rewound.rings().map(|r| line_zm(r, &mut zm)))
+    }
+}
+
+impl<const N: usize> ToEsriZM<N> for MultiPolygon {
+    type Output = EsriPolygon<N>;
+
+    fn to_esri_zm<F: FnMut(&Coord) -> (f64, f64)>(&self, mut zm: F) -> EsriPolygon<N> {
+        let rewound = self.orient(Direction::Reversed);
+        EsriPolygon::from_coords_zm(rewound.rings().map(|r| line_zm(r, &mut zm)))
+    }
+}
+
+/// Returns `None` for geometry collections. See [`split_geometry_collection()`].
+impl<const N: usize> ToEsriZM<N> for Geometry {
+    type Output = Option<EsriGeometry<N>>;
+
+    fn to_esri_zm<F: FnMut(&Coord) -> (f64, f64)>(&self, zm: F) -> Option<EsriGeometry<N>> {
+        let geometry = match self {
+            Geometry::Point(g) => EsriGeometry::Point(ToEsriZM::<N>::to_esri_zm(g, zm)),
+            Geometry::MultiPoint(g) => EsriGeometry::MultiPoint(g.to_esri_zm(zm)),
+            Geometry::Line(g) => EsriGeometry::Polyline(LineString::from(*g).to_esri_zm(zm)),
+            Geometry::LineString(g) => EsriGeometry::Polyline(g.to_esri_zm(zm)),
+            Geometry::MultiLineString(g) => EsriGeometry::Polyline(g.to_esri_zm(zm)),
+            Geometry::Polygon(g) => EsriGeometry::Polygon(g.to_esri_zm(zm)),
+            Geometry::MultiPolygon(g) => EsriGeometry::Polygon(g.to_esri_zm(zm)),
+            Geometry::Rect(g) => EsriGeometry::Polygon(g.to_polygon().to_esri_zm(zm)),
+            Geometry::Triangle(g) => EsriGeometry::Polygon(g.to_polygon().to_esri_zm(zm)),
+            Geometry::GeometryCollection(_) => return None,
+        };
+        Some(geometry)
+    }
+}
+
+// Normalization
+//
+// Geographic data often crosses the antimeridian, which services reject or
+// draw wrapped around the world. These conversions normalize the result.
+
+/// Converts geo-types geometries into Esri geometries normalized by [`NormalizeOptions`].
+///
+/// Coordinates are assumed to be longitude and latitude. Polygons are rewound
+/// as they are by the `Into` conversions before being split.
+///
+/// ```
+/// use geo_types::polygon;
+/// use serde_esri::{
+///     geo::from_geo::ToEsriNormalized,
+///     geometry::{EsriPolygon, NormalizeOptions},
+/// };
+///
+/// let ply = polygon![
+///     (x: 170.0, y: -10.0),
+///     (x: 190.0, y: -10.0),
+///     (x: 190.0, y: 10.0),
+///     (x: 170.0, y: 10.0),
+/// ];
+/// let esri: EsriPolygon<2> = ply.to_esri_normalized(&NormalizeOptions::default());
+/// assert_eq!(esri.rings.len(), 2);
+/// ```
+pub trait ToEsriNormalized {
+    type Output;
+
+    fn to_esri_normalized(&self, options: &NormalizeOptions) -> Self::Output;
+}
+
+macro_rules! impl_normalized {
+    ($Source:ty, $Target:ty) => {
+        impl ToEsriNormalized for $Source {
+            type Output = $Target;
+
+            fn to_esri_normalized(&self, options: &NormalizeOptions) -> $Target {
+                Into::<$Target>::into(self).normalize(options)
+            }
+        }
+    };
+}
+
+impl_normalized!(Line, EsriPolyline<2>);
+impl_normalized!(LineString, EsriPolyline<2>);
+impl_normalized!(MultiLineString, EsriPolyline<2>);
+impl_normalized!(Polygon, EsriPolygon<2>);
+impl_normalized!(MultiPolygon, EsriPolygon<2>);
+impl_normalized!(Rect, EsriPolygon<2>);
+impl_normalized!(Triangle, EsriPolygon<2>);
+
+/// Only polylines and polygons are normalized. Returns `None` if the geometry
+/// cannot be converted.
+impl ToEsriNormalized for Geometry {
+    type Output = Option<EsriGeometry<2>>;
+
+    fn to_esri_normalized(&self, options: &NormalizeOptions) -> Option<EsriGeometry<2>> {
+        let geometry = match TryInto::<EsriGeometry<2>>::try_into(self).ok()? {
+            EsriGeometry::Polyline(g) => EsriGeometry::Polyline(g.normalize(options)),
+            EsriGeometry::Polygon(g) => EsriGeometry::Polygon(g.normalize(options)),
+            g => g,
+        };
+        Some(geometry)
+    }
+}
+
+// GeometryCollections
+//
+// Esri JSON has no geometry collection type. A collection is either split
+// into its individual geometries or, when every member is of the same
+// kind, merged into a single multipart geometry.
+
+/// Splits a `GeometryCollection` into a vector of Esri geometries.
+///
+/// Nested collections are flattened so that every element of the
+/// returned vector is a single Esri geometry.
+pub fn split_geometry_collection(x: &GeometryCollection) -> Vec<EsriGeometry<2>> {
+    flatten_collection(x)
+        .into_iter()
+        .filter_map(|g| g.try_into().ok())
+        .collect()
+}
+
+/// Merges a homogeneous `GeometryCollection` into a single multipart Esri geometry.
+///
+/// - points and multipoints are merged into an `EsriMultiPoint`
+/// - lines, linestrings, and multilinestrings are merged into an `EsriPolyline`
+/// - polygons, multipolygons, rects, and triangles are merged into an `EsriPolygon`
+///
+/// Returns `None` if the collection is empty or contains more than one kind of geometry.
+pub fn merge_geometry_collection(x: &GeometryCollection) -> Option<EsriGeometry<2>> {
+    let members = flatten_collection(x)
+        .into_iter()
+        .map(TryInto::<EsriGeometry<2>>::try_into)
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+
+    let mut members = members.into_iter();
+    let first = members.next()?;
+
+    members.try_fold(first, |acc, geom| match (acc, geom) {
+        (EsriGeometry::Point(a), EsriGeometry::Point(b)) => {
+            Some(EsriGeometry::MultiPoint(EsriMultiPoint {
+                hasZ: None,
+                hasM: None,
+                points: vec![EsriCoord([a.x, a.y]), EsriCoord([b.x, b.y])],
+                spatialReference: None,
+            }))
+        }
+        (EsriGeometry::Point(a), EsriGeometry::MultiPoint(mut b)) => {
+            b.points.insert(0, EsriCoord([a.x, a.y]));
+            Some(EsriGeometry::MultiPoint(b))
+        }
+        (EsriGeometry::MultiPoint(mut a), EsriGeometry::Point(b)) => {
+            a.points.push(EsriCoord([b.x, b.y]));
+            Some(EsriGeometry::MultiPoint(a))
+        }
+        (EsriGeometry::MultiPoint(mut a), EsriGeometry::MultiPoint(b)) => {
+            a.points.extend(b.points);
+            Some(EsriGeometry::MultiPoint(a))
+        }
+        (EsriGeometry::Polyline(mut a), EsriGeometry::Polyline(b)) => {
+            a.paths.extend(b.paths);
+            Some(EsriGeometry::Polyline(a))
+        }
+        (EsriGeometry::Polygon(mut a), EsriGeometry::Polygon(b)) => {
+            a.rings.extend(b.rings);
+            Some(EsriGeometry::Polygon(a))
+        }
+        _ => None,
+    })
+}
+
+// recursively collects the non-collection members of a GeometryCollection
+fn flatten_collection(x: &GeometryCollection) -> Vec<&Geometry> {
+    x.iter()
+        .flat_map(|g| match g {
+            Geometry::GeometryCollection(gc) => flatten_collection(gc),
+            _ => vec![g],
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::geometry::*;
@@ -219,4 +502,58 @@ mod tests {
 
         assert_eq!(serial1, serial2);
     }
+
+    #[test]
+    fn test_rect_envelope() {
+        let rect = Rect::new(coord! {x: 3.0, y: 0.0}, coord! {x: 1.0, y: 2.0});
+        let envelope: EsriEnvelope = rect.into();
+        assert_eq!(envelope.bbox(), [1.0, 0.0, 3.0, 2.0]);
+
+        let polygon: EsriPolygon<2> = rect.into();
+        assert_eq!(polygon.bbox(), Some(envelope.bbox()));
+    }
+
+    #[test]
+    fn test_to_esri_zm() {
+        use super::ToEsriZM;
+        use geo::{polygon, Coord};
+
+        let poly = polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.0, y: 0.0)];
+        let esri_poly: EsriPolygon<3> = poly.to_esri_zm(|c: &Coord| (c.x + c.y, 0.0));
+        let esri_poly2: EsriPolygon<2> = poly.into();
+
+        assert_eq!((esri_poly.hasZ, esri_poly.hasM), (Some(true), Some(false)));
+        // same winding as the 2 dimensional conversion
+        for (a, b) in esri_poly.rings[0].iter().zip(esri_poly2.rings[0].iter()) {
+            assert_eq!(a.0, [b.0[0], b.0[1], b.0[0] + b.0[1]]);
+        }
+    }
+
+    #[test]
+    fn test_geometry_collection() {
+        use super::{merge_geometry_collection, split_geometry_collection};
+        use geo::{point, Geometry, GeometryCollection, LineString};
+
+        let points = GeometryCollection::new_from(vec![
+            Geometry::Point(point! {x: 0.0, y: 0.0}),
+            Geometry::GeometryCollection(GeometryCollection::new_from(vec![Geometry::Point(
+                point! {x: 1.0, y: 1.0},
+            )])),
+        ]);
+
+        assert_eq!(split_geometry_collection(&points).len(), 2);
+
+        let merged = merge_geometry_collection(&points)
+            .and_then(|g| g.as_multipoint())
+            .unwrap();
+        assert_eq!(merged.points.len(), 2);
+
+        let mixed = GeometryCollection::new_from(vec![
+            Geometry::Point(point! {x: 0.0, y: 0.0}),
+            Geometry::LineString(LineString::from(vec![(0.0, 0.0), (1.0, 1.0)])),
+        ]);
+
+        assert!(merge_geometry_collection(&mixed).is_none());
+        assert_eq!(split_geometry_collection(&mixed).len(), 2);
+    }
 }
@@ -1 +1,4 @@
+#[cfg(feature = "from-geo")]
 pub mod from_geo;
+#[cfg(feature = "geo")]
+pub mod to_geo;
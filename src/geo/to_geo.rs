@@ -0,0 +1,167 @@
+//! Converts Esri geometries into `geo-types` geometries that preserve the
+//! structure of multi-part geometries.
+//!
+//! An `EsriPolygon` may contain many exterior rings, each with their own holes.
+//! Following the Esri specification, rings wound clockwise are exteriors and
+//! rings wound counter-clockwise are holes. Each hole is assigned to the
+//! smallest exterior ring containing it. Holes that are not contained by any
+//! exterior ring are treated as exteriors.
+//!
+//! Provides conversions for:
+//!
+//! - `EsriPolygon` -> `MultiPolygon` with [`EsriPolygon::into_multi_polygon()`]
+//! - `EsriEnvelope` -> `Rect`
+//! - `EsriMultipatch` -> `MultiPolygon` with a polygon for each face
+//! - `EsriGeometry` -> `Geometry`, densifying true curves
+//! - `FeatureSet` -> `GeometryCollection`
+//!
+//! `EsriPolyline` -> `MultiLineString` is provided by the `geo` feature.
+//! `MultiPolygon::from()` cannot be used for polygons as geo-types wraps
+//! anything convertible into a `Polygon` in a `MultiPolygon`.
+//!
+//! ```
+//! use geo_types::{Geometry, MultiPolygon};
+//! use serde_esri::geometry::EsriGeometry;
+//!
+//! // two squares, the first with a hole
+//! let json = r#"{"rings": [
+//!     [[0, 0], [0, 10], [10, 10], [10, 0], [0, 0]],
+//!     [[2, 2], [4, 2], [4, 4], [2, 4], [2, 2]],
+//!     [[20, 0], [20, 10], [30, 10], [30, 0], [20, 0]]
+//! ]}"#;
+//! let geometry: EsriGeometry<2> = serde_json::from_str(json).unwrap();
+//!
+//! let Geometry::MultiPolygon(MultiPolygon(polygons)) = Geometry::from(geometry) else {
+//!     panic!("expected a multipolygon");
+//! };
+//! assert_eq!(polygons[0].interiors().len(), 1);
+//! assert_eq!(polygons[1].interiors().len(), 0);
+//! ```
+use crate::{features::FeatureSet, geometry::*, rings::classify_rings};
+use geo_types::{
+    Coord, CoordNum, Geometry, GeometryCollection, LineString, MultiLineString, MultiPolygon,
+    Polygon, Rect,
+};
+
+fn line_string<const N: usize, T: CoordNum>(x: EsriLineString<N, T>) -> LineString<T> {
+    LineString::new(x.into_iter().map(Coord::from).collect())
+}
+
+// densified curves are computed as f64
+fn cast_line_string<const N: usize, T: CoordNum>(
+    x: EsriLineString<N, f64>,
+) -> EsriLineString<N, T> {
+    let cast = |c: EsriCoord<N, f64>| EsriCoord(c.0.map(|v| T::from(v).unwrap_or_else(T::zero)));
+    EsriLineString(x.0.into_iter().map(cast).collect())
+}
+
+impl<const N: usize, T: CoordNum + Into<f64>> EsriPolygon<N, T> {
+    /// Convert into a `MultiPolygon` with a polygon for each exterior ring
+    pub fn into_multi_polygon(self) -> MultiPolygon<T> {
+        let groups = classify_rings(&self.rings);
+        let mut rings = self.rings.into_iter().map(Some).collect::<Vec<_>>();
+
+        let polygons = groups
+            .into_iter()
+            .map(|group| {
+                let mut group = group
+                    .into_iter()
+                    .filter_map(|i| rings[i].take())
+                    .map(line_string);
+                let exterior = group.next().unwrap_or_else(|| LineString::new(vec![]));
+                Polygon::new(exterior, group.collect())
+            })
+            .collect();
+
+        MultiPolygon::new(polygons)
+    }
+}
+
+impl<const N: usize, T: CoordNum> From<EsriMultipatch<N, T>> for MultiPolygon<T> {
+    fn from(value: EsriMultipatch<N, T>) -> Self {
+        let polygons = value
+            .faces()
+            .into_iter()
+            .map(|face| {
+                let mut rings = face.into_iter().map(line_string);
+                let exterior = rings.next().unwrap_or_else(|| LineString::new(vec![]));
+                Polygon::new(exterior, rings.collect())
+            })
+            .collect();
+        MultiPolygon::new(polygons)
+    }
+}
+
+impl<T: CoordNum> From<EsriEnvelope<T>> for Rect<T> {
+    fn from(value: EsriEnvelope<T>) -> Self {
+        Rect::new(
+            Coord {
+                x: value.xmin,
+                y: value.ymin,
+            },
+            Coord {
+                x: value.xmax,
+                y: value.ymax,
+            },
+        )
+    }
+}
+
+/// Single part polylines and polygons become a `LineString` and a `Polygon`.
+/// Multi-part ones become a `MultiLineString` and a `MultiPolygon`.
+impl<const N: usize, T: CoordNum + Into<f64>> From<EsriGeometry<N, T>> for Geometry<T> {
+    fn from(value: EsriGeometry<N, T>) -> Self {
+        match value {
+            EsriGeometry::Point(x) => Geometry::Point(x.into()),
+            EsriGeometry::MultiPoint(x) => Geometry::MultiPoint(x.into()),
+            EsriGeometry::Polyline(x) => {
+                let MultiLineString(mut lines) = x.into();
+                if lines.len() == 1 {
+                    Geometry::LineString(lines.remove(0))
+                } else {
+                    Geometry::MultiLineString(MultiLineString(lines))
+                }
+            }
+            EsriGeometry::Polygon(x) => {
+                let MultiPolygon(mut polygons) = x.into_multi_polygon();
+                if polygons.len() == 1 {
+                    Geometry::Polygon(polygons.remove(0))
+                } else {
+                    Geometry::MultiPolygon(MultiPolygon(polygons))
+                }
+            }
+            EsriGeometry::Envelope(x) => Geometry::Rect(x.into()),
+            EsriGeometry::Multipatch(x) => Geometry::MultiPolygon(x.into()),
+            EsriGeometry::CurvePolyline(x) => {
+                let x = x.linearize();
+                Geometry::from(EsriGeometry::<N, T>::Polyline(EsriPolyline {
+                    hasZ: x.hasZ,
+                    hasM: x.hasM,
+                    paths: x.paths.into_iter().map(cast_line_string).collect(),
+                    spatialReference: x.spatialReference,
+                }))
+            }
+            EsriGeometry::CurvePolygon(x) => {
+                let x = x.linearize();
+                Geometry::from(EsriGeometry::<N, T>::Polygon(EsriPolygon {
+                    hasZ: x.hasZ,
+                    hasM: x.hasM,
+                    rings: x.rings.into_iter().map(cast_line_string).collect(),
+                    spatialReference: x.spatialReference,
+                }))
+            }
+        }
+    }
+}
+
+/// Features without a geometry are skipped
+impl<const N: usize> From<FeatureSet<N>> for GeometryCollection {
+    fn from(value: FeatureSet<N>) -> Self {
+        value
+            .features
+            .into_iter()
+            .filter_map(|f| f.geometry)
+            .map(Geometry::from)
+            .collect()
+    }
+}
@@ -0,0 +1,336 @@
+// Implements the `geo-traits` traits for Esri geometries so that the geoarrow ecosystem
+// can read them without copying them into geo-types first.
+//
+// A polygon is read as its first ring followed by the holes of that ring. Polygons
+// with several exterior rings are read as a single polygon.
+use crate::geometry::{
+    EsriCoord, EsriLineString, EsriMultiPoint, EsriPoint, EsriPolygon, EsriPolyline,
+};
+use geo_traits::{
+    CoordTrait, Dimensions, GeometryTrait, GeometryType, LineStringTrait, MultiLineStringTrait,
+    MultiPointTrait, PointTrait, PolygonTrait, UnimplementedGeometryCollection, UnimplementedLine,
+    UnimplementedLineString, UnimplementedMultiLineString, UnimplementedMultiPoint,
+    UnimplementedMultiPolygon, UnimplementedPoint, UnimplementedPolygon, UnimplementedRect,
+    UnimplementedTriangle,
+};
+
+// `EsriGeometry<3>` is XYZ unless `hasM` is set without `hasZ`
+fn dimensions<const N: usize>(has_z: Option<bool>, has_m: Option<bool>) -> Dimensions {
+    match (N, has_z.unwrap_or(false), has_m.unwrap_or(false)) {
+        (2, ..) => Dimensions::Xy,
+        (3, false, true) => Dimensions::Xym,
+        (3, ..) => Dimensions::Xyz,
+        (4, ..) => Dimensions::Xyzm,
+        (n, ..) => Dimensions::Unknown(n),
+    }
+}
+
+// `Self` when the geometry is of the variant, otherwise the unimplemented type
+macro_rules! geometry_slot {
+    (Point, Point, $_:ty) => {
+        Self
+    };
+    (LineString, LineString, $_:ty) => {
+        Self
+    };
+    (Polygon, Polygon, $_:ty) => {
+        Self
+    };
+    (MultiPoint, MultiPoint, $_:ty) => {
+        Self
+    };
+    (MultiLineString, MultiLineString, $_:ty) => {
+        Self
+    };
+    ($variant:ident, $slot:ident, $unimplemented:ty) => {
+        $unimplemented
+    };
+}
+
+macro_rules! impl_geometry_trait {
+    ([$($generics:tt)*] $ty:ty, $variant:ident, |$this:ident| $dim:expr) => {
+        impl<$($generics)*> GeometryTrait for $ty {
+            type T = T;
+            type PointType<'b> = geometry_slot!($variant, Point, UnimplementedPoint<T>)
+            where
+                Self: 'b;
+            type LineStringType<'b> =
+                geometry_slot!($variant, LineString, UnimplementedLineString<T>)
+            where
+                Self: 'b;
+            type PolygonType<'b> = geometry_slot!($variant, Polygon, UnimplementedPolygon<T>)
+            where
+                Self: 'b;
+            type MultiPointType<'b> =
+                geometry_slot!($variant, MultiPoint, UnimplementedMultiPoint<T>)
+            where
+                Self: 'b;
+            type MultiLineStringType<'b> =
+                geometry_slot!($variant, MultiLineString, UnimplementedMultiLineString<T>)
+            where
+                Self: 'b;
+            type MultiPolygonType<'b> = UnimplementedMultiPolygon<T>
+            where
+                Self: 'b;
+            type GeometryCollectionType<'b> = UnimplementedGeometryCollection<T>
+            where
+                Self: 'b;
+            type RectType<'b> = UnimplementedRect<T>
+            where
+                Self: 'b;
+            type TriangleType<'b> = UnimplementedTriangle<T>
+            where
+                Self: 'b;
+            type LineType<'b> = UnimplementedLine<T>
+            where
+                Self: 'b;
+
+            fn dim(&self) -> Dimensions {
+                let $this = self;
+                $dim
+            }
+
+            fn as_type(
+                &self,
+            ) -> GeometryType<
+                '_,
+                Self::PointType<'_>,
+                Self::LineStringType<'_>,
+                Self::PolygonType<'_>,
+                Self::MultiPointType<'_>,
+                Self::MultiLineStringType<'_>,
+                Self::MultiPolygonType<'_>,
+                Self::GeometryCollectionType<'_>,
+                Self::RectType<'_>,
+                Self::TriangleType<'_>,
+                Self::LineType<'_>,
+            > {
+                GeometryType::$variant(self)
+            }
+        }
+    };
+}
+
+// Coordinates
+impl<const N: usize, T: Copy> CoordTrait for &EsriCoord<N, T> {
+    type T = T;
+
+    fn dim(&self) -> Dimensions {
+        dimensions::<N>(None, None)
+    }
+
+    fn x(&self) -> Self::T {
+        self.0[0]
+    }
+
+    fn y(&self) -> Self::T {
+        self.0[1]
+    }
+
+    fn nth_or_panic(&self, n: usize) -> Self::T {
+        self.0[n]
+    }
+}
+
+// a point is its own coordinate, with z and m when they are present
+impl<T: Copy> CoordTrait for &EsriPoint<T> {
+    type T = T;
+
+    fn dim(&self) -> Dimensions {
+        match (self.z.is_some(), self.m.is_some()) {
+            (false, false) => Dimensions::Xy,
+            (true, false) => Dimensions::Xyz,
+            (false, true) => Dimensions::Xym,
+            (true, true) => Dimensions::Xyzm,
+        }
+    }
+
+    fn x(&self) -> Self::T {
+        self.x
+    }
+
+    fn y(&self) -> Self::T {
+        self.y
+    }
+
+    fn nth_or_panic(&self, n: usize) -> Self::T {
+        [Some(self.x), Some(self.y), self.z, self.m]
+            .into_iter()
+            .flatten()
+            .nth(n)
+            .expect("coordinate index out of bounds")
+    }
+}
+
+// Points
+impl_geometry_trait!([T: Copy] EsriPoint<T>, Point, |pt| CoordTrait::dim(&pt));
+
+impl<T: Copy> PointTrait for EsriPoint<T> {
+    type CoordType<'a>
+        = &'a EsriPoint<T>
+    where
+        Self: 'a;
+
+    fn coord(&self) -> Option<Self::CoordType<'_>> {
+        Some(self)
+    }
+}
+
+// the points of a multipoint
+impl_geometry_trait!(['a, const N: usize, T: Copy] &'a EsriCoord<N, T>, Point, |_coord| {
+    dimensions::<N>(None, None)
+});
+
+impl<'a, const N: usize, T: Copy> PointTrait for &'a EsriCoord<N, T> {
+    type CoordType<'b>
+        = &'a EsriCoord<N, T>
+    where
+        Self: 'b;
+
+    fn coord(&self) -> Option<Self::CoordType<'_>> {
+        Some(*self)
+    }
+}
+
+// MultiPoints
+impl_geometry_trait!([const N: usize, T: Copy] EsriMultiPoint<N, T>, MultiPoint, |mp| {
+    dimensions::<N>(mp.hasZ, mp.hasM)
+});
+
+impl<const N: usize, T: Copy> MultiPointTrait for EsriMultiPoint<N, T> {
+    type InnerPointType<'a>
+        = &'a EsriCoord<N, T>
+    where
+        Self: 'a;
+
+    fn num_points(&self) -> usize {
+        self.points.len()
+    }
+
+    unsafe fn point_unchecked(&self, i: usize) -> Self::InnerPointType<'_> {
+        self.points.get_unchecked(i)
+    }
+}
+
+// the paths of a polyline and the rings of a polygon
+impl_geometry_trait!(['a, const N: usize, T: Copy] &'a EsriLineString<N, T>, LineString, |_line| {
+    dimensions::<N>(None, None)
+});
+
+impl<'a, const N: usize, T: Copy> LineStringTrait for &'a EsriLineString<N, T> {
+    type CoordType<'b>
+        = &'a EsriCoord<N, T>
+    where
+        Self: 'b;
+
+    fn num_coords(&self) -> usize {
+        self.0.len()
+    }
+
+    unsafe fn coord_unchecked(&self, i: usize) -> Self::CoordType<'_> {
+        self.0.get_unchecked(i)
+    }
+}
+
+// Polylines
+impl_geometry_trait!([const N: usize, T: Copy] EsriPolyline<N, T>, MultiLineString, |pl| {
+    dimensions::<N>(pl.hasZ, pl.hasM)
+});
+
+impl<const N: usize, T: Copy> MultiLineStringTrait for EsriPolyline<N, T> {
+    type InnerLineStringType<'a>
+        = &'a EsriLineString<N, T>
+    where
+        Self: 'a;
+
+    fn num_line_strings(&self) -> usize {
+        self.paths.len()
+    }
+
+    unsafe fn line_string_unchecked(&self, i: usize) -> Self::InnerLineStringType<'_> {
+        self.paths.get_unchecked(i)
+    }
+}
+
+// Polygons
+impl_geometry_trait!([const N: usize, T: Copy] EsriPolygon<N, T>, Polygon, |ply| {
+    dimensions::<N>(ply.hasZ, ply.hasM)
+});
+
+impl<const N: usize, T: Copy> PolygonTrait for EsriPolygon<N, T> {
+    type RingType<'a>
+        = &'a EsriLineString<N, T>
+    where
+        Self: 'a;
+
+    fn exterior(&self) -> Option<Self::RingType<'_>> {
+        self.rings.first()
+    }
+
+    fn num_interiors(&self) -> usize {
+        self.rings.len().saturating_sub(1)
+    }
+
+    unsafe fn interior_unchecked(&self, i: usize) -> Self::RingType<'_> {
+        self.rings.get_unchecked(i + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geo_traits() {
+        let point = EsriPoint {
+            x: 1.0,
+            y: 2.0,
+            m: Some(3.0),
+            ..Default::default()
+        };
+        assert_eq!(GeometryTrait::dim(&point), Dimensions::Xym);
+        let coord = point.coord().unwrap();
+        assert_eq!(coord.x_y(), (1.0, 2.0));
+        assert_eq!(coord.nth(2), Some(3.0));
+        assert_eq!(coord.nth(3), None);
+
+        let polygon = EsriPolygon::<3>::from_coords_zm([
+            [
+                [0.0, 0.0, 1.0],
+                [0.0, 10.0, 1.0],
+                [10.0, 10.0, 1.0],
+                [0.0, 0.0, 1.0],
+            ],
+            [
+                [1.0, 1.0, 2.0],
+                [2.0, 1.0, 2.0],
+                [1.0, 2.0, 2.0],
+                [1.0, 1.0, 2.0],
+            ],
+        ]);
+        assert_eq!(GeometryTrait::dim(&polygon), Dimensions::Xyz);
+        assert!(matches!(polygon.as_type(), GeometryType::Polygon(_)));
+        assert_eq!(polygon.exterior().unwrap().num_coords(), 4);
+        assert_eq!(polygon.num_interiors(), 1);
+        let hole = polygon.interior(0).unwrap();
+        assert_eq!(hole.coord(1).unwrap().nth_or_panic(2), 2.0);
+        assert!(polygon.interior(1).is_none());
+
+        let polyline = EsriPolyline::<2>::from_coords_zm([
+            vec![[0.0, 0.0], [1.0, 1.0]],
+            vec![[2.0, 2.0], [3.0, 3.0], [4.0, 4.0]],
+        ]);
+        let lengths: Vec<usize> = polyline.line_strings().map(|l| l.num_coords()).collect();
+        assert_eq!(lengths, [2, 3]);
+
+        let multipoint = EsriMultiPoint::<2> {
+            points: vec![EsriCoord([0.0, 1.0]), EsriCoord([2.0, 3.0])],
+            ..Default::default()
+        };
+        let xs: Vec<f64> = multipoint
+            .points()
+            .map(|p| p.coord().unwrap().x())
+            .collect();
+        assert_eq!(xs, [0.0, 2.0]);
+    }
+}
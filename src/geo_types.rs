@@ -11,12 +11,15 @@
 //! - `EsriMultiPoint` -> `MultiPoint`
 //! - `EsriPolyline` -> `MultiLineString`
 //! - `EsriPolygon` -> `Polygon`
+//!
+//! `EsriPolygon` -> `Polygon` treats the first ring as the exterior. Use the
+//! [`to_geo`](crate::geo::to_geo) conversions for polygons with many exterior rings.
 use crate::geometry::*;
-use geo_types::{Coord, LineString, MultiLineString, MultiPoint, Point, Polygon};
+use geo_types::{Coord, CoordNum, LineString, MultiLineString, MultiPoint, Point, Polygon};
 
 /// Note that only x and y dimensions are captured
-impl<const N: usize> From<EsriCoord<N>> for Coord {
-    fn from(value: EsriCoord<N>) -> Self {
+impl<const N: usize, T: CoordNum> From<EsriCoord<N, T>> for Coord<T> {
+    fn from(value: EsriCoord<N, T>) -> Self {
         Coord {
             x: value.0[0],
             y: value.0[1],
@@ -24,57 +27,51 @@ impl<const N: usize> From<EsriCoord<N>> for Coord {
     }
 }
 
-impl From<EsriPoint> for Point {
-    fn from(value: EsriPoint) -> Self {
+impl<T: CoordNum> From<EsriPoint<T>> for Point<T> {
+    fn from(value: EsriPoint<T>) -> Self {
         Point::new(value.x, value.y)
     }
 }
 
-impl<const N: usize> From<EsriMultiPoint<N>> for MultiPoint {
-    fn from(value: EsriMultiPoint<N>) -> Self {
+impl<const N: usize, T: CoordNum> From<EsriMultiPoint<N, T>> for MultiPoint<T> {
+    fn from(value: EsriMultiPoint<N, T>) -> Self {
         let pnts = value
             .points
             .into_iter()
             .map(|xi| Point::from(Coord::from(xi)))
-            .collect::<Vec<Point>>();
+            .collect::<Vec<Point<T>>>();
 
         MultiPoint::new(pnts)
     }
 }
 
-impl<const N: usize> From<EsriPolyline<N>> for MultiLineString {
-    fn from(value: EsriPolyline<N>) -> Self {
+impl<const N: usize, T: CoordNum> From<EsriPolyline<N, T>> for MultiLineString<T> {
+    fn from(value: EsriPolyline<N, T>) -> Self {
         let lns = value
             .paths
             .into_iter()
             .map(|mli| {
-                let li_coords = mli
-                    .into_iter()
-                    .map(|ci| Coord::from(ci))
-                    .collect::<Vec<Coord>>();
+                let li_coords = mli.into_iter().map(Coord::from).collect::<Vec<Coord<T>>>();
 
                 LineString::new(li_coords)
             })
-            .collect::<Vec<LineString>>();
+            .collect::<Vec<LineString<T>>>();
 
         MultiLineString::new(lns)
     }
 }
 
-impl<const N: usize> From<EsriPolygon<N>> for Polygon {
-    fn from(value: EsriPolygon<N>) -> Self {
+impl<const N: usize, T: CoordNum> From<EsriPolygon<N, T>> for Polygon<T> {
+    fn from(value: EsriPolygon<N, T>) -> Self {
         let lns = value
             .rings
             .into_iter()
             .map(|mli| {
-                let li_coords = mli
-                    .into_iter()
-                    .map(|ci| Coord::from(ci))
-                    .collect::<Vec<Coord>>();
+                let li_coords = mli.into_iter().map(Coord::from).collect::<Vec<Coord<T>>>();
 
                 LineString::new(li_coords)
             })
-            .collect::<Vec<LineString>>();
+            .collect::<Vec<LineString<T>>>();
 
         let mut lns_iter = lns.into_iter();
         let ext = lns_iter.next().unwrap();
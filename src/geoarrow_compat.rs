@@ -1,11 +1,10 @@
 use crate::geometry::*;
 use geoarrow::geo_traits::{
-    CoordTrait, LineStringTrait, 
-    LineStringIterator, MultiLineStringIterator, MultiLineStringTrait, MultiPointIterator, MultiPointTrait, PointTrait, PolygonInteriorIterator, PolygonTrait
+    CoordTrait, LineStringIterator, LineStringTrait, MultiLineStringIterator, MultiLineStringTrait,
+    MultiPointIterator, MultiPointTrait, PointTrait, PolygonInteriorIterator, PolygonTrait,
 };
 
-
-impl<'a, const N: usize> CoordTrait for &'a EsriCoord<N> {
+impl<const N: usize> CoordTrait for &EsriCoord<N> {
     type T = f64;
 
     fn x(&self) -> Self::T {
@@ -40,7 +39,7 @@ impl<const N: usize> PointTrait for EsriCoord<N> {
 }
 
 // required lifetime for multipoint trait
-impl<'a, const N: usize> PointTrait for &'a EsriCoord<N> {
+impl<const N: usize> PointTrait for &EsriCoord<N> {
     type T = f64;
 
     fn x(&self) -> Self::T {
@@ -97,20 +96,23 @@ impl<const N: usize> LineStringTrait for EsriLineString<N> {
     }
 
     unsafe fn coord_unchecked(&self, i: usize) -> Self::ItemType<'_> {
-        self.iter().nth(i).unwrap()
+        self.0.get_unchecked(i)
     }
 }
 
 impl<const N: usize> LineStringTrait for &EsriLineString<N> {
     type T = f64;
-    type ItemType<'a> = &'a EsriCoord<N> where Self: 'a;
+    type ItemType<'a>
+        = &'a EsriCoord<N>
+    where
+        Self: 'a;
 
     fn coords(&self) -> LineStringIterator<'_, Self::T, Self::ItemType<'_>, Self> {
         LineStringIterator::new(self, 0, self.num_coords())
     }
 
     fn num_coords(&self) -> usize {
-        (*self).0.len()
+        self.0.len()
     }
 
     fn coord(&self, i: usize) -> Option<Self::ItemType<'_>> {
@@ -122,11 +124,10 @@ impl<const N: usize> LineStringTrait for &EsriLineString<N> {
     }
 
     unsafe fn coord_unchecked(&self, i: usize) -> Self::ItemType<'_> {
-        (*self).iter().nth(i).unwrap()
+        self.0.get_unchecked(i)
     }
 }
 
-
 // Polyline implementation
 impl<const N: usize> MultiLineStringTrait for EsriPolyline<N> {
     type T = f64;
@@ -147,21 +148,17 @@ impl<const N: usize> MultiLineStringTrait for EsriPolyline<N> {
     unsafe fn line_unchecked(&self, i: usize) -> Self::ItemType<'_> {
         self.paths.get_unchecked(i)
     }
-    
 }
 
-
 // // Polygon implementation
 impl<const N: usize> PolygonTrait for EsriPolygon<N> {
     type T = f64;
     type ItemType<'a> = &'a EsriLineString<N>;
 
-
     fn exterior(&self) -> Option<Self::ItemType<'_>> {
-        self.rings.iter().nth(0)
+        self.rings.first()
     }
 
-
     fn num_interiors(&self) -> usize {
         let n = self.rings.len();
         n - 1
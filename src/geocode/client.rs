@@ -0,0 +1,192 @@
+use crate::auth::{CredentialError, CredentialProvider, TokenProvider, TokenSource};
+use crate::error::{ClassifyError, ErrorKind};
+use crate::feature_service::{ErrorResponse, ExpectedResponse};
+use crate::geocode::{
+    FindAddressCandidatesParams, FindAddressCandidatesResponse, GeocodeAddressesParams,
+    GeocodeAddressesResponse, GeocodedAddress, ReverseGeocodeParams, ReverseGeocodeResponse,
+    SuggestParams, SuggestResponse,
+};
+use crate::http::{HttpClient, HttpError, HttpRequest};
+use crate::trace;
+use serde::de::DeserializeOwned;
+
+/// The URL of the ArcGIS World Geocoding Service
+pub const GEOCODE_SERVICE_URL: &str =
+    "https://geocode-api.arcgis.com/arcgis/rest/services/World/GeocodeServer";
+
+#[derive(Debug)]
+pub enum GeocodeError {
+    /// The request failed or the response is not the expected JSON
+    HttpError(HttpError),
+    ApiError(ErrorResponse),
+    /// The token provider failed to supply a token
+    CredentialError(CredentialError),
+}
+
+impl std::fmt::Display for GeocodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HttpError(e) => write!(f, "{e}"),
+            Self::ApiError(e) => write!(f, "{} ({})", e.error.message, e.error.code),
+            Self::CredentialError(e) => write!(f, "failed to get token: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for GeocodeError {}
+
+impl ClassifyError for GeocodeError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::HttpError(e) => e.kind(),
+            Self::ApiError(e) => e.kind(),
+            Self::CredentialError(e) => e.kind(),
+        }
+    }
+}
+
+/// A client for the ArcGIS World Geocoding Service or any other geocode service
+///
+/// ```
+/// use serde_esri::geocode::{GeocodeClient, GEOCODE_SERVICE_URL};
+/// let client = GeocodeClient::new(GEOCODE_SERVICE_URL, "your token");
+/// ```
+///
+/// ## Note
+///
+/// Replace `"your token"` with your actual token.
+///
+/// Requests are sent with a `reqwest::blocking::Client` unless another [`HttpClient`]
+/// is set with [`GeocodeClient::with_http_client()`].
+///
+#[derive(Clone)]
+pub struct GeocodeClient<H = reqwest::blocking::Client> {
+    pub service_url: String,
+    pub(crate) client: H,
+    /// The token to use for authorization. Redacted from `Debug` and zeroized on drop.
+    pub(crate) token: TokenSource,
+}
+
+impl<H> std::fmt::Debug for GeocodeClient<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GeocodeClient")
+            .field("service_url", &self.service_url)
+            .field("token", &"[REDACTED]")
+            .finish_non_exhaustive()
+    }
+}
+
+impl GeocodeClient {
+    /// Create a new client for the geocode service at `service_url`
+    pub fn new(service_url: &str, token: &str) -> Self {
+        Self {
+            service_url: service_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: token.into(),
+        }
+    }
+
+    /// Create a new client for the geocode service using a token from `credentials`
+    pub fn from_credentials<C: CredentialProvider>(
+        service_url: &str,
+        credentials: &C,
+    ) -> Result<Self, CredentialError> {
+        Ok(Self {
+            service_url: service_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: credentials.token()?.into(),
+        })
+    }
+
+    /// Create a new client for the geocode service which gets a token from `provider` before every request
+    pub fn from_token_provider<P: TokenProvider>(service_url: &str, provider: P) -> Self {
+        Self {
+            service_url: service_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: TokenSource::new(provider),
+        }
+    }
+}
+
+impl<H: HttpClient> GeocodeClient<H> {
+    /// Send requests with `client` instead
+    pub fn with_http_client<C: HttpClient>(self, client: C) -> GeocodeClient<C> {
+        GeocodeClient {
+            service_url: self.service_url,
+            client,
+            token: self.token,
+        }
+    }
+
+    /// Query the [`/findAddressCandidates`](https://developers.arcgis.com/rest/geocode/api-reference/geocoding-find-address-candidates.htm) endpoint
+    pub fn find_address_candidates(
+        &self,
+        params: FindAddressCandidatesParams,
+    ) -> Result<FindAddressCandidatesResponse, GeocodeError> {
+        let request = HttpRequest::get(&format!("{}/findAddressCandidates", self.service_url))
+            .query(&params.prepare());
+        self.send(request)
+    }
+
+    /// Query the [`/reverseGeocode`](https://developers.arcgis.com/rest/geocode/api-reference/geocoding-reverse-geocode.htm) endpoint
+    pub fn reverse_geocode(
+        &self,
+        params: ReverseGeocodeParams,
+    ) -> Result<ReverseGeocodeResponse, GeocodeError> {
+        let request = HttpRequest::get(&format!("{}/reverseGeocode", self.service_url))
+            .query(&params.prepare());
+        self.send(request)
+    }
+
+    /// Query the [`/geocodeAddresses`](https://developers.arcgis.com/rest/geocode/api-reference/geocoding-geocode-addresses.htm) endpoint
+    ///
+    /// The addresses are sent in batches of `params.batch_size`, which defaults to [`MAX_BATCH_SIZE`](crate::geocode::MAX_BATCH_SIZE).
+    /// The results are returned in the order of the addresses, and
+    /// [`GeocodedAddress::result_id()`] is the index of the address.
+    pub fn geocode_addresses(
+        &self,
+        params: GeocodeAddressesParams,
+    ) -> Result<GeocodeAddressesResponse, GeocodeError> {
+        let batch_size = params.batch_size();
+        let mut spatial_reference = None;
+        let mut locations: Vec<GeocodedAddress> = Vec::with_capacity(params.addresses.len());
+
+        for (i, batch) in params.addresses.chunks(batch_size).enumerate() {
+            let request = HttpRequest::post(&format!("{}/geocodeAddresses", self.service_url))
+                .form(&params.form(batch, i * batch_size));
+            let resp: GeocodeAddressesResponse = self.send(request)?;
+            spatial_reference = spatial_reference.or(resp.spatial_reference);
+            locations.extend(resp.locations);
+        }
+
+        locations.sort_by_key(|l| l.result_id());
+
+        Ok(GeocodeAddressesResponse {
+            spatial_reference,
+            locations,
+        })
+    }
+
+    /// Query the [`/suggest`](https://developers.arcgis.com/rest/geocode/api-reference/geocoding-suggest.htm) endpoint
+    pub fn suggest(&self, params: SuggestParams) -> Result<SuggestResponse, GeocodeError> {
+        let request =
+            HttpRequest::get(&format!("{}/suggest", self.service_url)).query(&params.prepare());
+        self.send(request)
+    }
+
+    // authorizes the request, sends it, and handles error responses
+    fn send<T: DeserializeOwned>(&self, request: HttpRequest) -> Result<T, GeocodeError> {
+        let bearer = self.token.bearer().map_err(GeocodeError::CredentialError)?;
+        let resp = trace::send(
+            &self.client,
+            request.header("X-Esri-Authorization", &bearer),
+        )
+        .and_then(|r| r.json::<ExpectedResponse<T>>())
+        .map_err(GeocodeError::HttpError)?;
+
+        match resp {
+            ExpectedResponse::Ok(x) => Ok(x),
+            ExpectedResponse::Error(e) => Err(GeocodeError::ApiError(e)),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! Types for the [ArcGIS World Geocoding Service](https://developers.arcgis.com/rest/geocode/api-reference/overview-world-geocoding-service.htm).
+//!
+//! Activate the `"geocode-client"` feature to enable the `GeocodeClient` struct which queries the service.
+//!
+//! Each endpoint has a params struct and a corresponding `Builder`:
+//!
+//! - [`FindAddressCandidatesParamsBuilder`] creates a [`FindAddressCandidatesParams`] struct used to geocode one address.
+//! - [`ReverseGeocodeParamsBuilder`] creates a [`ReverseGeocodeParams`] struct used to find the address of a point.
+//! - [`GeocodeAddressesParamsBuilder`] creates a [`GeocodeAddressesParams`] struct used to geocode many addresses. The client sends them in batches of at most [`MAX_BATCH_SIZE`].
+//! - [`SuggestParamsBuilder`] creates a [`SuggestParams`] struct used to autocomplete partial addresses. The `magic_key` of a [`Suggestion`] can then be used to find its candidates.
+mod params;
+mod responses;
+pub use params::*;
+pub use responses::*;
+
+#[cfg(feature = "geocode-client")]
+mod client;
+
+#[cfg(feature = "geocode-client")]
+pub use client::*;
@@ -0,0 +1,364 @@
+use crate::geometry::{EsriEnvelope, EsriPoint};
+use derive_builder::Builder;
+#[cfg(feature = "geocode-client")]
+use serde::Serialize;
+
+/// The largest number of addresses the World Geocoding Service accepts in one `/geocodeAddresses` request
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+/// Request parameters used to query the [`/findAddressCandidates`](https://developers.arcgis.com/rest/geocode/api-reference/geocoding-find-address-candidates.htm) endpoint
+///
+/// Either `single_line` or the individual address components should be set.
+#[derive(Debug, Clone, Default, Builder)]
+pub struct FindAddressCandidatesParams {
+    /// The complete address in a single string, e.g. `"380 New York St, Redlands, CA"`
+    #[builder(setter(into, strip_option), default)]
+    pub single_line: Option<String>,
+    /// The street address, e.g. `"380 New York St"`
+    #[builder(setter(into, strip_option), default)]
+    pub address: Option<String>,
+    #[builder(setter(into, strip_option), default)]
+    pub city: Option<String>,
+    /// The state, province, or other first level subdivision
+    #[builder(setter(into, strip_option), default)]
+    pub region: Option<String>,
+    /// The postal code
+    #[builder(setter(into, strip_option), default)]
+    pub postal: Option<String>,
+    /// A two or three character country code which limits the search to one country
+    #[builder(setter(into, strip_option), default)]
+    pub country_code: Option<String>,
+    /// Limits the results to one or more categories, e.g. `"Address"` or `"Coffee Shop"`
+    #[builder(setter(into, strip_option), default)]
+    pub category: Option<String>,
+    /// Candidates closer to this point are ranked higher
+    #[builder(setter(into, strip_option), default)]
+    pub location: Option<EsriPoint>,
+    /// Limits the results to this extent
+    #[builder(setter(into, strip_option), default)]
+    pub search_extent: Option<EsriEnvelope>,
+    /// The attributes to return for each candidate. Use `"*"` for all of them.
+    #[builder(setter(into, strip_option), default)]
+    pub out_fields: Option<Vec<String>>,
+    /// The maximum number of candidates to return
+    #[builder(setter(into, strip_option), default)]
+    pub max_locations: Option<u32>,
+    /// The WKID of the returned locations. Defaults to `4326`.
+    #[builder(setter(into, strip_option), default)]
+    pub out_sr: Option<u32>,
+    /// A `magicKey` from [`Suggestion`](crate::geocode::Suggestion) which identifies the suggested address
+    #[builder(setter(into, strip_option), default)]
+    pub magic_key: Option<String>,
+    /// Whether the results will be stored. Storing results requires a token.
+    #[builder(setter(into, strip_option), default)]
+    pub for_storage: Option<bool>,
+}
+
+/// Prepared version of FindAddressCandidatesParams which encodes the geometries and fields
+#[cfg(feature = "geocode-client")]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FindAddressCandidatesPreparedParams {
+    single_line: Option<String>,
+    address: Option<String>,
+    city: Option<String>,
+    region: Option<String>,
+    postal: Option<String>,
+    country_code: Option<String>,
+    category: Option<String>,
+    location: Option<String>,
+    search_extent: Option<String>,
+    out_fields: Option<String>,
+    max_locations: Option<u32>,
+    #[serde(rename = "outSR")]
+    out_sr: Option<u32>,
+    magic_key: Option<String>,
+    for_storage: Option<bool>,
+    f: &'static str,
+}
+
+#[cfg(feature = "geocode-client")]
+impl FindAddressCandidatesParams {
+    pub(crate) fn prepare(self) -> FindAddressCandidatesPreparedParams {
+        FindAddressCandidatesPreparedParams {
+            single_line: self.single_line,
+            address: self.address,
+            city: self.city,
+            region: self.region,
+            postal: self.postal,
+            country_code: self.country_code,
+            category: self.category,
+            location: self.location.map(|p| to_json(&p)),
+            search_extent: self.search_extent.map(|e| to_json(&e)),
+            out_fields: self.out_fields.map(|f| f.join(",")),
+            max_locations: self.max_locations,
+            out_sr: self.out_sr,
+            magic_key: self.magic_key,
+            for_storage: self.for_storage,
+            f: "json",
+        }
+    }
+}
+
+/// Request parameters used to query the [`/reverseGeocode`](https://developers.arcgis.com/rest/geocode/api-reference/geocoding-reverse-geocode.htm) endpoint
+#[derive(Debug, Clone, Default, Builder)]
+pub struct ReverseGeocodeParams {
+    /// The point to find the address of. Its spatial reference defaults to `4326`.
+    pub location: EsriPoint,
+    /// The types of features to match, e.g. `"StreetAddress"` or `"POI"`
+    #[builder(setter(into, strip_option), default)]
+    pub feature_types: Option<Vec<String>>,
+    /// Whether the returned location is the `"rooftop"` or `"street"` location of the address
+    #[builder(setter(into, strip_option), default)]
+    pub location_type: Option<String>,
+    /// The language of the returned address, e.g. `"fr"`
+    #[builder(setter(into, strip_option), default)]
+    pub lang_code: Option<String>,
+    /// The WKID of the returned location. Defaults to `4326`.
+    #[builder(setter(into, strip_option), default)]
+    pub out_sr: Option<u32>,
+    /// Whether the results will be stored. Storing results requires a token.
+    #[builder(setter(into, strip_option), default)]
+    pub for_storage: Option<bool>,
+}
+
+/// Prepared version of ReverseGeocodeParams which encodes the location
+#[cfg(feature = "geocode-client")]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReverseGeocodePreparedParams {
+    location: String,
+    feature_types: Option<String>,
+    location_type: Option<String>,
+    lang_code: Option<String>,
+    #[serde(rename = "outSR")]
+    out_sr: Option<u32>,
+    for_storage: Option<bool>,
+    f: &'static str,
+}
+
+#[cfg(feature = "geocode-client")]
+impl ReverseGeocodeParams {
+    pub(crate) fn prepare(self) -> ReverseGeocodePreparedParams {
+        ReverseGeocodePreparedParams {
+            location: to_json(&self.location),
+            feature_types: self.feature_types.map(|f| f.join(",")),
+            location_type: self.location_type,
+            lang_code: self.lang_code,
+            out_sr: self.out_sr,
+            for_storage: self.for_storage,
+            f: "json",
+        }
+    }
+}
+
+/// An address sent to the `/geocodeAddresses` endpoint
+///
+/// Either `single_line` or the individual address components should be set.
+#[derive(Debug, Clone, Default, Builder)]
+pub struct GeocodeAddress {
+    #[builder(setter(into, strip_option), default)]
+    pub single_line: Option<String>,
+    #[builder(setter(into, strip_option), default)]
+    pub address: Option<String>,
+    #[builder(setter(into, strip_option), default)]
+    pub city: Option<String>,
+    #[builder(setter(into, strip_option), default)]
+    pub region: Option<String>,
+    #[builder(setter(into, strip_option), default)]
+    pub postal: Option<String>,
+    #[builder(setter(into, strip_option), default)]
+    pub country_code: Option<String>,
+}
+
+impl From<&str> for GeocodeAddress {
+    fn from(single_line: &str) -> Self {
+        Self {
+            single_line: Some(single_line.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Request parameters used to query the [`/geocodeAddresses`](https://developers.arcgis.com/rest/geocode/api-reference/geocoding-geocode-addresses.htm) endpoint
+///
+/// The addresses are sent in batches of `batch_size`, which defaults to [`MAX_BATCH_SIZE`].
+#[derive(Debug, Clone, Default, Builder)]
+pub struct GeocodeAddressesParams {
+    #[builder(setter(into))]
+    pub addresses: Vec<GeocodeAddress>,
+    /// Limits the results to one or more categories, e.g. `"Address"`
+    #[builder(setter(into, strip_option), default)]
+    pub category: Option<String>,
+    /// A two or three character country code which limits the search to one country
+    #[builder(setter(into, strip_option), default)]
+    pub source_country: Option<String>,
+    /// The WKID of the returned locations. Defaults to `4326`.
+    #[builder(setter(into, strip_option), default)]
+    pub out_sr: Option<u32>,
+    /// Whether the results will be stored. Storing results requires a token.
+    #[builder(setter(into, strip_option), default)]
+    pub for_storage: Option<bool>,
+    /// The number of addresses sent in each request
+    #[builder(setter(into, strip_option), default)]
+    pub batch_size: Option<usize>,
+}
+
+#[cfg(feature = "geocode-client")]
+#[derive(Debug, Serialize)]
+struct AddressRecord<'a> {
+    attributes: AddressAttributes<'a>,
+}
+
+#[cfg(feature = "geocode-client")]
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Serialize)]
+struct AddressAttributes<'a> {
+    #[serde(rename = "OBJECTID")]
+    object_id: usize,
+    #[serde(rename = "SingleLine")]
+    single_line: Option<&'a str>,
+    #[serde(rename = "Address")]
+    address: Option<&'a str>,
+    #[serde(rename = "City")]
+    city: Option<&'a str>,
+    #[serde(rename = "Region")]
+    region: Option<&'a str>,
+    #[serde(rename = "Postal")]
+    postal: Option<&'a str>,
+    #[serde(rename = "CountryCode")]
+    country_code: Option<&'a str>,
+}
+
+#[cfg(feature = "geocode-client")]
+impl GeocodeAddressesParams {
+    pub(crate) fn batch_size(&self) -> usize {
+        self.batch_size.unwrap_or(MAX_BATCH_SIZE).max(1)
+    }
+
+    // the form of one batch of addresses whose OBJECTIDs start at `offset`
+    pub(crate) fn form(
+        &self,
+        addresses: &[GeocodeAddress],
+        offset: usize,
+    ) -> Vec<(&'static str, String)> {
+        let records = addresses
+            .iter()
+            .enumerate()
+            .map(|(i, a)| AddressRecord {
+                attributes: AddressAttributes {
+                    object_id: offset + i,
+                    single_line: a.single_line.as_deref(),
+                    address: a.address.as_deref(),
+                    city: a.city.as_deref(),
+                    region: a.region.as_deref(),
+                    postal: a.postal.as_deref(),
+                    country_code: a.country_code.as_deref(),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let mut form = vec![
+            (
+                "addresses",
+                to_json(&serde_json::json!({ "records": records })),
+            ),
+            ("f", "json".to_string()),
+        ];
+        let optional = [
+            ("category", self.category.clone()),
+            ("sourceCountry", self.source_country.clone()),
+            ("outSR", self.out_sr.map(|x| x.to_string())),
+            ("forStorage", self.for_storage.map(|x| x.to_string())),
+        ];
+        form.extend(optional.into_iter().filter_map(|(k, v)| Some((k, v?))));
+        form
+    }
+}
+
+/// Request parameters used to query the [`/suggest`](https://developers.arcgis.com/rest/geocode/api-reference/geocoding-suggest.htm) endpoint
+#[derive(Debug, Clone, Default, Builder)]
+pub struct SuggestParams {
+    /// The partial address or place name, e.g. `"380 New Yo"`
+    #[builder(setter(into))]
+    pub text: String,
+    /// Suggestions closer to this point are ranked higher
+    #[builder(setter(into, strip_option), default)]
+    pub location: Option<EsriPoint>,
+    /// Limits the suggestions to this extent
+    #[builder(setter(into, strip_option), default)]
+    pub search_extent: Option<EsriEnvelope>,
+    /// Limits the suggestions to one or more categories
+    #[builder(setter(into, strip_option), default)]
+    pub category: Option<String>,
+    /// A two or three character country code which limits the suggestions to one country
+    #[builder(setter(into, strip_option), default)]
+    pub country_code: Option<String>,
+    /// The maximum number of suggestions to return, up to 15
+    #[builder(setter(into, strip_option), default)]
+    pub max_suggestions: Option<u32>,
+}
+
+/// Prepared version of SuggestParams which encodes the geometries
+#[cfg(feature = "geocode-client")]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SuggestPreparedParams {
+    text: String,
+    location: Option<String>,
+    search_extent: Option<String>,
+    category: Option<String>,
+    country_code: Option<String>,
+    max_suggestions: Option<u32>,
+    f: &'static str,
+}
+
+#[cfg(feature = "geocode-client")]
+impl SuggestParams {
+    pub(crate) fn prepare(self) -> SuggestPreparedParams {
+        SuggestPreparedParams {
+            text: self.text,
+            location: self.location.map(|p| to_json(&p)),
+            search_extent: self.search_extent.map(|e| to_json(&e)),
+            category: self.category,
+            country_code: self.country_code,
+            max_suggestions: self.max_suggestions,
+            f: "json",
+        }
+    }
+}
+
+#[cfg(feature = "geocode-client")]
+fn to_json<S: Serialize>(value: &S) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+#[cfg(all(test, feature = "geocode-client"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_form() {
+        let params = GeocodeAddressesParamsBuilder::default()
+            .addresses(vec![
+                "380 New York St, Redlands, CA".into(),
+                "1 Main St".into(),
+            ])
+            .batch_size(1usize)
+            .build()
+            .unwrap();
+
+        let batches = params
+            .addresses
+            .chunks(params.batch_size())
+            .enumerate()
+            .map(|(i, chunk)| params.form(chunk, i * params.batch_size()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(
+            batches[1][0].1,
+            r#"{"records":[{"attributes":{"OBJECTID":1,"SingleLine":"1 Main St"}}]}"#
+        );
+    }
+}
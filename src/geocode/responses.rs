@@ -0,0 +1,111 @@
+use crate::geometry::{EsriEnvelope, EsriPoint};
+use crate::spatial_reference::SpatialReference;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+/// A candidate location of an address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressCandidate {
+    /// The matched address
+    pub address: String,
+    pub location: EsriPoint,
+    /// How well the candidate matches the address from `0` to `100`
+    pub score: f64,
+    /// The attributes requested with `out_fields`
+    #[serde(default)]
+    pub attributes: Map<String, Value>,
+    /// The extent suitable for displaying the candidate on a map
+    pub extent: Option<EsriEnvelope>,
+}
+
+/// Represents the response from the /findAddressCandidates endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindAddressCandidatesResponse {
+    pub spatial_reference: Option<SpatialReference>,
+    pub candidates: Vec<AddressCandidate>,
+}
+
+/// Represents the response from the /reverseGeocode endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReverseGeocodeResponse {
+    /// The components of the address, e.g. `Match_addr`, `City`, and `Postal`
+    pub address: Map<String, Value>,
+    pub location: EsriPoint,
+}
+
+/// The result of one address sent to the /geocodeAddresses endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeocodedAddress {
+    /// The matched address. Empty if the address was not matched.
+    #[serde(default)]
+    pub address: String,
+    /// The location of the address or `None` if it was not matched
+    #[serde(default, deserialize_with = "matched_location")]
+    pub location: Option<EsriPoint>,
+    #[serde(default)]
+    pub score: f64,
+    /// The attributes of the result, including `ResultID` which is the index of the address
+    #[serde(default)]
+    pub attributes: Map<String, Value>,
+}
+
+impl GeocodedAddress {
+    /// The index of the address this result belongs to
+    pub fn result_id(&self) -> Option<u64> {
+        self.attributes.get("ResultID")?.as_u64()
+    }
+}
+
+// unmatched addresses have a location of `{"x": "NaN", "y": "NaN"}` or no location at all
+fn matched_location<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<EsriPoint>, D::Error> {
+    let value = Option::<Value>::deserialize(deserializer)?;
+    Ok(value.and_then(|v| serde_json::from_value(v).ok()))
+}
+
+/// Represents the response from the /geocodeAddresses endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeocodeAddressesResponse {
+    pub spatial_reference: Option<SpatialReference>,
+    pub locations: Vec<GeocodedAddress>,
+}
+
+/// A suggested address or place
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Suggestion {
+    pub text: String,
+    /// Identifies the suggestion in [`FindAddressCandidatesParams::magic_key`](crate::geocode::FindAddressCandidatesParams::magic_key)
+    pub magic_key: String,
+    /// Whether the suggestion is a category of places rather than one place
+    pub is_collection: bool,
+}
+
+/// Represents the response from the /suggest endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestResponse {
+    pub suggestions: Vec<Suggestion>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unmatched_address() {
+        let json = r#"{
+            "spatialReference": {"wkid": 4326, "latestWkid": 4326},
+            "locations": [
+                {"address": "", "location": {"x": "NaN", "y": "NaN"}, "score": 0, "attributes": {"ResultID": 1}},
+                {"address": "380 New York St, Redlands, California, 92373", "location": {"x": -117.19, "y": 34.05}, "score": 100, "attributes": {"ResultID": 0}}
+            ]
+        }"#;
+        let resp: GeocodeAddressesResponse = serde_json::from_str(json).unwrap();
+        assert!(resp.locations[0].location.is_none());
+        assert_eq!(resp.locations[0].result_id(), Some(1));
+        assert_eq!(resp.locations[1].location.as_ref().unwrap().x, -117.19);
+    }
+}
@@ -0,0 +1,412 @@
+//! Optional feature for converting between Esri JSON objects and [`geojson`](https://docs.rs/geojson) types.
+//!
+//! Provides conversions for:
+//!
+//! - `EsriPoint` <-> `Point`
+//! - `EsriMultiPoint` <-> `MultiPoint`
+//! - `EsriPolyline` <-> `LineString` or `MultiLineString`
+//! - `EsriPolygon` <-> `Polygon` or `MultiPolygon`
+//! - `EsriEnvelope` -> `Polygon`
+//! - `EsriCurvePolyline` and `EsriCurvePolygon` are densified
+//! - `EsriMultipatch` -> `MultiPolygon` with a polygon for each face
+//! - `EsriGeometry` <-> `Geometry`
+//! - `Feature` <-> `Feature`, mapping attributes to and from `properties`
+//! - `FeatureSet` <-> `FeatureCollection`
+//!
+//! GeoJSON positions support at most an elevation so M values are dropped, and
+//! only `N` of `2` or `3` can be converted from GeoJSON.
+//! Polygon rings are grouped into polygons based on their winding and are
+//! rewound to follow the right-hand rule of [RFC 7946](https://tools.ietf.org/html/rfc7946#section-3.1.6).
+//! When converting from GeoJSON, exterior rings are rewound clockwise and holes
+//! counter-clockwise as required by Esri.
+//!
+//! Note that GeoJSON coordinates are expected to be WGS84 longitude and latitude.
+//! No reprojection occurs.
+use crate::{
+    features::{Feature, FeatureSet},
+    geometry::*,
+    rings::{classify_rings, is_clockwise},
+    spatial_reference::SpatialReference,
+};
+use geojson::{feature::Id, Geometry, GeometryValue, Position};
+
+/// Errors that can occur when converting `geojson` types into Esri JSON objects
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoJsonConversionError {
+    /// The position does not have the dimensions required by `N`. GeoJSON positions have no M value.
+    DimensionMismatch { expected: usize, found: usize },
+    /// Esri JSON has no geometry collection type
+    GeometryCollection,
+}
+
+impl std::fmt::Display for GeoJsonConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DimensionMismatch { expected, found } => write!(
+                f,
+                "expected a position with {expected} dimensions, found {found}"
+            ),
+            Self::GeometryCollection => write!(f, "geometry collections cannot be converted"),
+        }
+    }
+}
+
+impl std::error::Error for GeoJsonConversionError {}
+
+// Esri -> GeoJSON
+
+fn position<const N: usize, T: Copy + Into<f64>>(x: &EsriCoord<N, T>) -> Position {
+    let (has_z, _) = dimension_flags::<N>();
+    if has_z {
+        Position::from([x.0[0].into(), x.0[1].into(), x.0[2].into()])
+    } else {
+        Position::from([x.0[0].into(), x.0[1].into()])
+    }
+}
+
+fn positions<const N: usize, T: Copy + Into<f64>>(x: &EsriLineString<N, T>) -> Vec<Position> {
+    x.iter().map(position).collect()
+}
+
+impl<T: Copy + Into<f64>> From<&EsriPoint<T>> for Geometry {
+    fn from(value: &EsriPoint<T>) -> Self {
+        let position = match value.z {
+            Some(z) => Position::from([value.x.into(), value.y.into(), z.into()]),
+            None => Position::from([value.x.into(), value.y.into()]),
+        };
+        Geometry::new_point(position)
+    }
+}
+
+impl<const N: usize, T: Copy + Into<f64>> From<&EsriMultiPoint<N, T>> for Geometry {
+    fn from(value: &EsriMultiPoint<N, T>) -> Self {
+        Geometry::new_multi_point(value.iter().map(position))
+    }
+}
+
+/// Polylines with a single path are converted to a `LineString`
+impl<const N: usize, T: Copy + Into<f64>> From<&EsriPolyline<N, T>> for Geometry {
+    fn from(value: &EsriPolyline<N, T>) -> Self {
+        let mut lines = value.paths.iter().map(positions).collect::<Vec<_>>();
+        if lines.len() == 1 {
+            Geometry::new_line_string(lines.remove(0))
+        } else {
+            Geometry::new_multi_line_string(lines)
+        }
+    }
+}
+
+/// Polygons with a single exterior ring are converted to a `Polygon`
+impl<const N: usize, T: Copy + Into<f64>> From<&EsriPolygon<N, T>> for Geometry {
+    fn from(value: &EsriPolygon<N, T>) -> Self {
+        let mut polygons = classify_rings(&value.rings)
+            .into_iter()
+            .map(|rings| {
+                rings
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, ring)| {
+                        let ring = &value.rings[ring];
+                        let mut coords = positions(ring);
+                        // RFC 7946: exteriors are counter-clockwise, holes clockwise
+                        if is_clockwise(ring) == (i == 0) {
+                            coords.reverse();
+                        }
+                        coords
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        if polygons.len() == 1 {
+            Geometry::new_polygon(polygons.remove(0))
+        } else {
+            Geometry::new_multi_polygon(polygons)
+        }
+    }
+}
+
+impl<T: Copy + Into<f64>> From<&EsriEnvelope<T>> for Geometry {
+    fn from(value: &EsriEnvelope<T>) -> Self {
+        let (xmin, ymin) = (value.xmin.into(), value.ymin.into());
+        let (xmax, ymax) = (value.xmax.into(), value.ymax.into());
+        Geometry::new_polygon(vec![vec![
+            [xmin, ymin],
+            [xmax, ymin],
+            [xmax, ymax],
+            [xmin, ymax],
+            [xmin, ymin],
+        ]])
+    }
+}
+
+impl<const N: usize, T: Copy + Into<f64>> From<&EsriMultipatch<N, T>> for Geometry {
+    fn from(value: &EsriMultipatch<N, T>) -> Self {
+        let polygons: Vec<Vec<Vec<Position>>> = value
+            .faces()
+            .iter()
+            .map(|face| face.iter().map(positions).collect())
+            .collect();
+        Geometry::new_multi_polygon(polygons)
+    }
+}
+
+/// `geojson::Geometry` is also available through `geojson`'s blanket `From` implementation
+impl<const N: usize, T: Copy + Into<f64>> From<&EsriGeometry<N, T>> for GeometryValue {
+    fn from(value: &EsriGeometry<N, T>) -> Self {
+        let geometry = match value {
+            EsriGeometry::Point(g) => Geometry::from(g),
+            EsriGeometry::MultiPoint(g) => Geometry::from(g),
+            EsriGeometry::Polyline(g) => Geometry::from(g),
+            EsriGeometry::Polygon(g) => Geometry::from(g),
+            EsriGeometry::Envelope(g) => Geometry::from(g),
+            EsriGeometry::CurvePolyline(g) => Geometry::from(&g.linearize()),
+            EsriGeometry::CurvePolygon(g) => Geometry::from(&g.linearize()),
+            EsriGeometry::Multipatch(g) => Geometry::from(g),
+        };
+        geometry.value
+    }
+}
+
+/// Attributes are used as the GeoJSON `properties`
+impl<const N: usize> From<&Feature<N>> for geojson::Feature {
+    fn from(value: &Feature<N>) -> Self {
+        geojson::Feature {
+            bbox: None,
+            geometry: value.geometry.as_ref().map(Geometry::from),
+            id: None,
+            properties: value.attributes.clone(),
+            foreign_members: None,
+        }
+    }
+}
+
+/// If `objectIdFieldName` is set, its value is used as each feature's `id`
+impl<const N: usize> From<&FeatureSet<N>> for geojson::FeatureCollection {
+    fn from(value: &FeatureSet<N>) -> Self {
+        let features = value.features.iter().map(|feat| {
+            let mut feature = geojson::Feature::from(feat);
+            feature.id = value
+                .objectIdFieldName
+                .as_ref()
+                .and_then(|oid| feat.attributes.as_ref()?.get(oid))
+                .and_then(|id| match id {
+                    serde_json::Value::Number(n) => Some(Id::Number(n.clone())),
+                    serde_json::Value::String(s) => Some(Id::String(s.clone())),
+                    _ => None,
+                });
+            feature
+        });
+
+        geojson::FeatureCollection::new(features)
+    }
+}
+
+// GeoJSON -> Esri
+
+fn esri_coord<const N: usize>(x: &Position) -> Result<EsriCoord<N>, GeoJsonConversionError> {
+    let (_, has_m) = dimension_flags::<N>();
+    let found = x.as_slice();
+    if has_m || found.len() < N {
+        return Err(GeoJsonConversionError::DimensionMismatch {
+            expected: N,
+            found: found.len(),
+        });
+    }
+
+    let mut coord = [0.0; N];
+    coord.copy_from_slice(&found[..N]);
+    Ok(EsriCoord(coord))
+}
+
+fn esri_line<const N: usize>(x: &[Position]) -> Result<EsriLineString<N>, GeoJsonConversionError> {
+    Ok(EsriLineString(
+        x.iter().map(esri_coord).collect::<Result<_, _>>()?,
+    ))
+}
+
+// converts the rings of a polygon winding the exterior clockwise
+// and the interiors counter-clockwise
+fn esri_rings<const N: usize>(
+    x: &[Vec<Position>],
+) -> Result<Vec<EsriLineString<N>>, GeoJsonConversionError> {
+    x.iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            let mut ring = esri_line(ring)?;
+            if is_clockwise(&ring) != (i == 0) {
+                ring.0.reverse();
+            }
+            Ok(ring)
+        })
+        .collect()
+}
+
+impl<const N: usize> TryFrom<&GeometryValue> for EsriGeometry<N> {
+    type Error = GeoJsonConversionError;
+
+    fn try_from(value: &GeometryValue) -> Result<Self, Self::Error> {
+        let (has_z, has_m) = dimension_flags::<N>();
+        let (has_z, has_m) = (has_z.then_some(true), has_m.then_some(true));
+
+        let geometry = match value {
+            GeometryValue::Point { coordinates } => {
+                let found = coordinates.as_slice();
+                if found.len() < N.min(3) {
+                    return Err(GeoJsonConversionError::DimensionMismatch {
+                        expected: N,
+                        found: found.len(),
+                    });
+                }
+                EsriGeometry::Point(EsriPoint {
+                    x: found[0],
+                    y: found[1],
+                    z: has_z.and(found.get(2).copied()),
+                    m: None,
+                    spatialReference: None,
+                })
+            }
+            GeometryValue::MultiPoint { coordinates } => EsriGeometry::MultiPoint(EsriMultiPoint {
+                hasZ: has_z,
+                hasM: has_m,
+                points: coordinates
+                    .iter()
+                    .map(esri_coord)
+                    .collect::<Result<_, _>>()?,
+                spatialReference: None,
+            }),
+            GeometryValue::LineString { coordinates } => EsriGeometry::Polyline(EsriPolyline {
+                hasZ: has_z,
+                hasM: has_m,
+                paths: vec![esri_line(coordinates)?],
+                spatialReference: None,
+            }),
+            GeometryValue::MultiLineString { coordinates } => {
+                EsriGeometry::Polyline(EsriPolyline {
+                    hasZ: has_z,
+                    hasM: has_m,
+                    paths: coordinates
+                        .iter()
+                        .map(|line| esri_line(line))
+                        .collect::<Result<_, _>>()?,
+                    spatialReference: None,
+                })
+            }
+            GeometryValue::Polygon { coordinates } => EsriGeometry::Polygon(EsriPolygon {
+                hasZ: has_z,
+                hasM: has_m,
+                rings: esri_rings(coordinates)?,
+                spatialReference: None,
+            }),
+            GeometryValue::MultiPolygon { coordinates } => {
+                let mut rings = Vec::new();
+                for polygon in coordinates {
+                    rings.extend(esri_rings(polygon)?);
+                }
+                EsriGeometry::Polygon(EsriPolygon {
+                    hasZ: has_z,
+                    hasM: has_m,
+                    rings,
+                    spatialReference: None,
+                })
+            }
+            GeometryValue::GeometryCollection { .. } => {
+                return Err(GeoJsonConversionError::GeometryCollection)
+            }
+        };
+
+        Ok(geometry)
+    }
+}
+
+impl<const N: usize> TryFrom<&Geometry> for EsriGeometry<N> {
+    type Error = GeoJsonConversionError;
+
+    fn try_from(value: &Geometry) -> Result<Self, Self::Error> {
+        EsriGeometry::try_from(&value.value)
+    }
+}
+
+/// `properties` are used as the attributes. The `id` is dropped.
+impl<const N: usize> TryFrom<&geojson::Feature> for Feature<N> {
+    type Error = GeoJsonConversionError;
+
+    fn try_from(value: &geojson::Feature) -> Result<Self, Self::Error> {
+        Ok(Feature {
+            geometry: value
+                .geometry
+                .as_ref()
+                .map(EsriGeometry::try_from)
+                .transpose()?,
+            attributes: value.properties.clone(),
+            #[cfg(feature = "preserve-unknown")]
+            extra: Default::default(),
+        })
+    }
+}
+
+/// The `spatialReference` is WGS84 (4326) and the `geometryType` is that of the first geometry
+impl<const N: usize> TryFrom<&geojson::FeatureCollection> for FeatureSet<N> {
+    type Error = GeoJsonConversionError;
+
+    fn try_from(value: &geojson::FeatureCollection) -> Result<Self, Self::Error> {
+        let features = value
+            .features
+            .iter()
+            .map(Feature::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let geometry_type = features
+            .iter()
+            .find_map(|f| f.geometry.as_ref())
+            .map(|g| g.geometry_type().to_string());
+
+        let (has_z, has_m) = dimension_flags::<N>();
+        Ok(FeatureSet {
+            geometryType: geometry_type,
+            spatialReference: Some(SpatialReference {
+                wkid: Some(4326),
+                ..Default::default()
+            }),
+            hasZ: Some(has_z),
+            hasM: Some(has_m),
+            features,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geojson_round_trip() {
+        let json = r#"{"type": "FeatureCollection", "features": [{
+            "type": "Feature",
+            "properties": {"name": "square"},
+            "geometry": {"type": "Polygon", "coordinates": [
+                [[0, 0], [10, 0], [10, 10], [0, 10], [0, 0]],
+                [[2, 2], [2, 4], [4, 4], [4, 2], [2, 2]]
+            ]}
+        }]}"#;
+        let fc: geojson::FeatureCollection = json
+            .parse::<geojson::GeoJson>()
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let fset = FeatureSet::<2>::try_from(&fc).unwrap();
+        assert_eq!(fset.geometryType.as_deref(), Some("esriGeometryPolygon"));
+        let feature = &fset.features[0];
+        assert_eq!(feature.attributes.as_ref().unwrap()["name"], "square");
+
+        let polygon = feature.geometry.clone().unwrap().as_polygon().unwrap();
+        assert!(is_clockwise(&polygon.rings[0]));
+        assert!(!is_clockwise(&polygon.rings[1]));
+
+        let back = geojson::FeatureCollection::from(&fset);
+        assert_eq!(back.features[0].geometry, fc.features[0].geometry);
+        assert!(FeatureSet::<4>::try_from(&fc).is_err());
+    }
+}
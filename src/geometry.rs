@@ -3,31 +3,67 @@
 //! The spatial reference of a geometry object is not always provided.
 //! As such it's encoded as an `Option<SpatialReference>` to handle the cases where it may
 //! or may not be provided.
+//!
+//! Every geometry type is generic over its coordinate scalar `T` which defaults
+//! to `f64`. Use `f32` to halve the memory footprint of parsed coordinates,
+//! e.g. `EsriPolygon<2, f32>`. Both are read from and written to JSON numbers.
 use crate::de_array::arrays;
 use crate::spatial_reference::SpatialReference;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 use serde_with::skip_serializing_none;
 
+mod curves;
+mod measure;
+mod multipatch;
+mod normalize;
+pub use curves::*;
+pub use multipatch::*;
+pub use normalize::*;
+
 /// Represents a single coordinate used in the creation of `EsriPolygon` and `EsriPolyline`s.
 ///
 /// It requires a valid `N` of values per coordinate. Should always be one of
 /// `2`, `3`, or `4` in the case of XY, XYZ, or XYZM coordinates.
 #[skip_serializing_none]
 #[derive(Clone, Deserialize, Serialize, Debug)]
-pub struct EsriCoord<const N: usize>(#[serde(with = "arrays")] pub [f64; N]);
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+pub struct EsriCoord<const N: usize, T = f64>(#[serde(with = "arrays")] pub [T; N]);
 
 /// An `esriGeometryPoint` with fields x, y, z, and m. x and y are both required.
 #[skip_serializing_none]
 #[allow(non_snake_case)]
 #[derive(Clone, Deserialize, Serialize, Debug, Default)]
-pub struct EsriPoint {
-    pub x: f64,
-    pub y: f64,
-    pub z: Option<f64>,
-    pub m: Option<f64>,
+pub struct EsriPoint<T = f64> {
+    pub x: T,
+    pub y: T,
+    pub z: Option<T>,
+    pub m: Option<T>,
     pub spatialReference: Option<SpatialReference>,
 }
 
+impl<T: Copy> EsriPoint<T> {
+    /// The envelope of the point. Its minimum and maximum are the point itself.
+    pub fn extent(&self) -> EsriEnvelope<T> {
+        EsriEnvelope {
+            xmin: self.x,
+            ymin: self.y,
+            xmax: self.x,
+            ymax: self.y,
+            zmin: self.z,
+            zmax: self.z,
+            mmin: self.m,
+            mmax: self.m,
+            spatialReference: self.spatialReference.clone(),
+        }
+    }
+
+    /// The bounding box of the point as `[xmin, ymin, xmax, ymax]`
+    pub fn bbox(&self) -> [T; 4] {
+        self.extent().bbox()
+    }
+}
+
 /// An `esriGeometryMultipoint` defined by a vector of `EsriCoord`s.
 ///
 /// `<N>` parameter should be equal to `2 + hasZ + hasM`. There are no
@@ -36,46 +72,69 @@ pub struct EsriPoint {
 #[skip_serializing_none]
 #[allow(non_snake_case)]
 #[derive(Clone, Deserialize, Serialize, Debug, Default)]
-pub struct EsriMultiPoint<const N: usize> {
+pub struct EsriMultiPoint<const N: usize, T = f64> {
     pub hasZ: Option<bool>,
     pub hasM: Option<bool>,
-    pub points: Vec<EsriCoord<N>>,
+    pub points: Vec<EsriCoord<N, T>>,
     pub spatialReference: Option<SpatialReference>,
 }
 
 // Implement iterators for EsriMultiPoint struct
 // this is mostly copy pasta from ChatGPT. Not _Too_ sure
 // what is happening in here
-pub struct EsriMultiPointIterator<'a, const N: usize> {
-    points_iter: std::slice::Iter<'a, EsriCoord<N>>,
+pub struct EsriMultiPointIterator<'a, const N: usize, T = f64> {
+    points_iter: std::slice::Iter<'a, EsriCoord<N, T>>,
 }
 
-impl<const N: usize> EsriMultiPoint<N> {
-    pub fn iter(&self) -> EsriMultiPointIterator<N> {
+impl<const N: usize, T> EsriMultiPoint<N, T> {
+    /// Create a multipoint from coordinates with `hasZ` and `hasM` set based on `N`
+    pub fn from_coords_zm<I: IntoIterator<Item = [T; N]>>(points: I) -> Self {
+        Self {
+            hasZ: None,
+            hasM: None,
+            points: points.into_iter().map(EsriCoord).collect(),
+            spatialReference: None,
+        }
+        .with_dimension_flags()
+    }
+
+    pub fn iter(&self) -> EsriMultiPointIterator<'_, N, T> {
         EsriMultiPointIterator {
             points_iter: self.points.iter(),
         }
     }
 }
 
-impl<'a, const N: usize> Iterator for EsriMultiPointIterator<'a, N> {
-    type Item = &'a EsriCoord<N>; // Define the associated type 'Item'
+impl<const N: usize, T: Copy + PartialOrd> EsriMultiPoint<N, T> {
+    /// The envelope of all points or `None` if there are none
+    pub fn extent(&self) -> Option<EsriEnvelope<T>> {
+        coords_extent(&self.points, self.hasZ, self.hasM, &self.spatialReference)
+    }
+
+    /// The bounding box of all points as `[xmin, ymin, xmax, ymax]`
+    pub fn bbox(&self) -> Option<[T; 4]> {
+        self.extent().map(|e| e.bbox())
+    }
+}
+
+impl<'a, const N: usize, T> Iterator for EsriMultiPointIterator<'a, N, T> {
+    type Item = &'a EsriCoord<N, T>; // Define the associated type 'Item'
 
     fn next(&mut self) -> Option<Self::Item> {
         self.points_iter.next()
     }
 }
 
-impl<'a, const N: usize> IntoIterator for &'a EsriMultiPoint<N> {
-    type Item = &'a EsriCoord<N>; // Define the associated type 'Item' for IntoIterator
-    type IntoIter = EsriMultiPointIterator<'a, N>; // Define the associated type 'IntoIter'
+impl<'a, const N: usize, T> IntoIterator for &'a EsriMultiPoint<N, T> {
+    type Item = &'a EsriCoord<N, T>; // Define the associated type 'Item' for IntoIterator
+    type IntoIter = EsriMultiPointIterator<'a, N, T>; // Define the associated type 'IntoIter'
 
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
     }
 }
 
-impl<'a, const N: usize> ExactSizeIterator for EsriMultiPointIterator<'a, N> {
+impl<'a, const N: usize, T> ExactSizeIterator for EsriMultiPointIterator<'a, N, T> {
     fn len(&self) -> usize {
         self.points_iter.len()
     }
@@ -89,43 +148,105 @@ impl<'a, const N: usize> ExactSizeIterator for EsriMultiPointIterator<'a, N> {
 /// for the `EsriPolygon` and `EsriPolyline` structs. They do not represent
 /// any Esri JSON geometry objects.
 #[derive(Clone, Deserialize, Serialize, Debug, Default)]
-pub struct EsriLineString<const N: usize>(pub Vec<EsriCoord<N>>);
+pub struct EsriLineString<const N: usize, T = f64>(pub Vec<EsriCoord<N, T>>);
 
-pub struct EsriLineStringIterator<'a, const N: usize> {
-    iter: std::slice::Iter<'a, EsriCoord<N>>,
+pub struct EsriLineStringIterator<'a, const N: usize, T = f64> {
+    iter: std::slice::Iter<'a, EsriCoord<N, T>>,
 }
 
-impl<'a, const N: usize> Iterator for EsriLineStringIterator<'a, N> {
-    type Item = &'a EsriCoord<N>;
+impl<'a, const N: usize, T> Iterator for EsriLineStringIterator<'a, N, T> {
+    type Item = &'a EsriCoord<N, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next()
     }
 }
 
-impl<'a, const N: usize> ExactSizeIterator for EsriLineStringIterator<'a, N> {
+impl<'a, const N: usize, T> ExactSizeIterator for EsriLineStringIterator<'a, N, T> {
     fn len(&self) -> usize {
         self.iter.len()
     }
 }
 
-impl<const N: usize> IntoIterator for EsriLineString<N> {
-    type Item = EsriCoord<N>;
-    type IntoIter = std::vec::IntoIter<EsriCoord<N>>;
+impl<const N: usize, T> IntoIterator for EsriLineString<N, T> {
+    type Item = EsriCoord<N, T>;
+    type IntoIter = std::vec::IntoIter<EsriCoord<N, T>>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
     }
 }
 
-impl<const N: usize> EsriLineString<N> {
-    pub fn iter(&self) -> EsriLineStringIterator<N> {
+impl<const N: usize, T> EsriLineString<N, T> {
+    pub fn from_coords<I: IntoIterator<Item = [T; N]>>(coords: I) -> Self {
+        Self(coords.into_iter().map(EsriCoord).collect())
+    }
+
+    pub fn iter(&self) -> EsriLineStringIterator<'_, N, T> {
         EsriLineStringIterator {
             iter: self.0.iter(),
         }
     }
 }
-/// An `esriGeometryPolyline` defined by a vector of `Vec<EsriCoord<N>>`.
+/// Returns the `(hasZ, hasM)` flags implied by a coordinate dimension `N`.
+///
+/// `2` is XY, `3` is XYZ, and `4` is XYZM. Any other value
+/// is treated as XY.
+pub const fn dimension_flags<const N: usize>() -> (bool, bool) {
+    match N {
+        3 => (true, false),
+        4 => (true, true),
+        _ => (false, false),
+    }
+}
+
+// fills in the flags that are `None`. A missing flag is whatever dimension of `N`
+// the other flag leaves over so that e.g. `hasM` of an XYM geometry is kept.
+pub(crate) fn fill_dimension_flags<const N: usize>(
+    has_z: &mut Option<bool>,
+    has_m: &mut Option<bool>,
+) {
+    let extra = N.saturating_sub(2);
+    match (*has_z, *has_m) {
+        (None, None) => {
+            let (z, m) = dimension_flags::<N>();
+            *has_z = Some(z);
+            *has_m = Some(m);
+        }
+        (Some(z), None) => *has_m = Some(extra > z as usize),
+        (None, Some(m)) => *has_z = Some(extra > m as usize),
+        (Some(_), Some(_)) => {}
+    }
+}
+
+macro_rules! impl_dimension_flags {
+    ($($ty:ident),*) => {
+        $(
+            impl<const N: usize, T> $ty<N, T> {
+                /// Sets `hasZ` and `hasM` if they are missing so that they are always serialized.
+                ///
+                /// Flags that are already set are kept. A missing flag is derived from `N`
+                /// and the other flag, see [`dimension_flags()`]. Some endpoints, such as
+                /// `applyEdits` on Z-enabled layers, require these flags even when they are `false`.
+                pub fn with_dimension_flags(mut self) -> Self {
+                    fill_dimension_flags::<N>(&mut self.hasZ, &mut self.hasM);
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_dimension_flags!(
+    EsriMultiPoint,
+    EsriPolyline,
+    EsriPolygon,
+    EsriCurvePolyline,
+    EsriCurvePolygon,
+    EsriMultipatch
+);
+
+/// An `esriGeometryPolyline` defined by a vector of `Vec<EsriCoord<N, T>>`.
 ///
 /// Each inner vector should be a single linestring.
 ///
@@ -135,34 +256,64 @@ impl<const N: usize> EsriLineString<N> {
 #[skip_serializing_none]
 #[allow(non_snake_case)]
 #[derive(Clone, Deserialize, Serialize, Debug, Default)]
-pub struct EsriPolyline<const N: usize> {
+pub struct EsriPolyline<const N: usize, T = f64> {
     pub hasZ: Option<bool>,
     pub hasM: Option<bool>,
-    pub paths: Vec<EsriLineString<N>>,
+    pub paths: Vec<EsriLineString<N, T>>,
     pub spatialReference: Option<SpatialReference>,
 }
 
-pub struct EsriPolylineIterator<'a, const N: usize> {
-    pub paths_iter: std::slice::Iter<'a, EsriLineString<N>>,
+impl<const N: usize, T> EsriPolyline<N, T> {
+    /// Create a polyline from the coordinates of each path with `hasZ` and `hasM` set based on `N`
+    pub fn from_coords_zm<I, P>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: IntoIterator<Item = [T; N]>,
+    {
+        Self {
+            hasZ: None,
+            hasM: None,
+            paths: paths.into_iter().map(EsriLineString::from_coords).collect(),
+            spatialReference: None,
+        }
+        .with_dimension_flags()
+    }
 }
 
-impl<'a, const N: usize> Iterator for EsriPolylineIterator<'a, N> {
-    type Item = &'a EsriLineString<N>;
+impl<const N: usize, T: Copy + PartialOrd> EsriPolyline<N, T> {
+    /// The envelope of all paths or `None` if there are no coordinates
+    pub fn extent(&self) -> Option<EsriEnvelope<T>> {
+        let coords = self.paths.iter().flat_map(|p| &p.0);
+        coords_extent(coords, self.hasZ, self.hasM, &self.spatialReference)
+    }
+
+    /// The bounding box of all paths as `[xmin, ymin, xmax, ymax]`
+    pub fn bbox(&self) -> Option<[T; 4]> {
+        self.extent().map(|e| e.bbox())
+    }
+}
+
+pub struct EsriPolylineIterator<'a, const N: usize, T = f64> {
+    pub paths_iter: std::slice::Iter<'a, EsriLineString<N, T>>,
+}
+
+impl<'a, const N: usize, T> Iterator for EsriPolylineIterator<'a, N, T> {
+    type Item = &'a EsriLineString<N, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.paths_iter.next()
     }
 }
 
-impl<'a, const N: usize> ExactSizeIterator for EsriPolylineIterator<'a, N> {
+impl<'a, const N: usize, T> ExactSizeIterator for EsriPolylineIterator<'a, N, T> {
     fn len(&self) -> usize {
         self.paths_iter.len()
     }
 }
 
-/// An `esriGeometryPolygon` defined by a `Vec<Vec<EsriCoord<N>>>`
+/// An `esriGeometryPolygon` defined by a `Vec<Vec<EsriCoord<N, T>>>`
 ///
-/// Each inner vector should be a single linear ring. The first `Vec<EsriCoord<N>>`
+/// Each inner vector should be a single linear ring. The first `Vec<EsriCoord<N, T>>`
 /// represents the exterior ring. Subsequent ones are interior rings. No checking
 /// of widing occurs.
 ///
@@ -172,45 +323,216 @@ impl<'a, const N: usize> ExactSizeIterator for EsriPolylineIterator<'a, N> {
 #[skip_serializing_none]
 #[allow(non_snake_case)]
 #[derive(Clone, Deserialize, Serialize, Debug, Default)]
-pub struct EsriPolygon<const N: usize> {
+pub struct EsriPolygon<const N: usize, T = f64> {
     pub hasZ: Option<bool>,
     pub hasM: Option<bool>,
-    pub rings: Vec<EsriLineString<N>>,
+    pub rings: Vec<EsriLineString<N, T>>,
     pub spatialReference: Option<SpatialReference>,
 }
 
-pub struct EsriPolygonIterator<'a, const N: usize> {
-    pub paths_iter: std::slice::Iter<'a, EsriLineString<N>>,
+impl<const N: usize, T> EsriPolygon<N, T> {
+    /// Create a polygon from the coordinates of each ring with `hasZ` and `hasM` set based on `N`
+    ///
+    /// The rings are not rewound. Exterior rings must be clockwise and holes counter-clockwise.
+    pub fn from_coords_zm<I, R>(rings: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = [T; N]>,
+    {
+        Self {
+            hasZ: None,
+            hasM: None,
+            rings: rings.into_iter().map(EsriLineString::from_coords).collect(),
+            spatialReference: None,
+        }
+        .with_dimension_flags()
+    }
+}
+
+impl<const N: usize, T: Copy + PartialOrd> EsriPolygon<N, T> {
+    /// The envelope of all rings or `None` if there are no coordinates
+    pub fn extent(&self) -> Option<EsriEnvelope<T>> {
+        let coords = self.rings.iter().flat_map(|r| &r.0);
+        coords_extent(coords, self.hasZ, self.hasM, &self.spatialReference)
+    }
+
+    /// The bounding box of all rings as `[xmin, ymin, xmax, ymax]`
+    pub fn bbox(&self) -> Option<[T; 4]> {
+        self.extent().map(|e| e.bbox())
+    }
+}
+
+// the envelope of coordinates. Without `hasZ` and `hasM` the dimensions are based on `N`.
+fn coords_extent<'a, const N: usize, T, I>(
+    coords: I,
+    has_z: Option<bool>,
+    has_m: Option<bool>,
+    spatial_reference: &Option<SpatialReference>,
+) -> Option<EsriEnvelope<T>>
+where
+    T: Copy + PartialOrd + 'a,
+    I: IntoIterator<Item = &'a EsriCoord<N, T>>,
+{
+    let (has_z, has_m) = match (has_z, has_m) {
+        (None, None) => dimension_flags::<N>(),
+        (z, m) => (z.unwrap_or(false), m.unwrap_or(false)),
+    };
+    let z_idx = (has_z && N > 2).then_some(2);
+    let m_idx = (has_m && N > 2 + has_z as usize).then_some(2 + has_z as usize);
+
+    let range =
+        |(min, max): (T, T), v: T| (if v < min { v } else { min }, if v > max { v } else { max });
+    let range_opt = |r: Option<(T, T)>, v: Option<T>| match (r, v) {
+        (Some(r), Some(v)) => Some(range(r, v)),
+        (None, v) => v.map(|v| (v, v)),
+        (r, None) => r,
+    };
+
+    let mut coords = coords.into_iter();
+    let first = coords.next()?;
+    let init = (
+        (first.0[0], first.0[0]),
+        (first.0[1], first.0[1]),
+        z_idx.map(|i| (first.0[i], first.0[i])),
+        m_idx.map(|i| (first.0[i], first.0[i])),
+    );
+
+    let (x, y, z, m) = coords.fold(init, |(x, y, z, m), c| {
+        (
+            range(x, c.0[0]),
+            range(y, c.0[1]),
+            range_opt(z, z_idx.map(|i| c.0[i])),
+            range_opt(m, m_idx.map(|i| c.0[i])),
+        )
+    });
+
+    Some(EsriEnvelope {
+        xmin: x.0,
+        ymin: y.0,
+        xmax: x.1,
+        ymax: y.1,
+        zmin: z.map(|z| z.0),
+        zmax: z.map(|z| z.1),
+        mmin: m.map(|m| m.0),
+        mmax: m.map(|m| m.1),
+        spatialReference: spatial_reference.clone(),
+    })
 }
 
-impl<'a, const N: usize> Iterator for EsriPolygonIterator<'a, N> {
-    type Item = &'a EsriLineString<N>;
+pub struct EsriPolygonIterator<'a, const N: usize, T = f64> {
+    pub paths_iter: std::slice::Iter<'a, EsriLineString<N, T>>,
+}
+
+impl<'a, const N: usize, T> Iterator for EsriPolygonIterator<'a, N, T> {
+    type Item = &'a EsriLineString<N, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.paths_iter.next()
     }
 }
 
-impl<'a, const N: usize> ExactSizeIterator for EsriPolygonIterator<'a, N> {
+impl<'a, const N: usize, T> ExactSizeIterator for EsriPolygonIterator<'a, N, T> {
     fn len(&self) -> usize {
         self.paths_iter.len()
     }
 }
 
-/// An enum of all valid geometry types. At present this does not include `esriGeometryEnvelope`
+/// An enum of all valid geometry types
 #[derive(Clone, Deserialize, Serialize, Debug)]
 #[serde(untagged)]
-pub enum EsriGeometry<const N: usize> {
-    Point(EsriPoint),
-    MultiPoint(EsriMultiPoint<N>),
-    Polygon(EsriPolygon<N>),
-    Polyline(EsriPolyline<N>),
-    Envelope(EsriEnvelope),
+pub enum EsriGeometry<const N: usize, T = f64> {
+    Point(EsriPoint<T>),
+    MultiPoint(EsriMultiPoint<N, T>),
+    // before polygons as both have rings
+    Multipatch(EsriMultipatch<N, T>),
+    Polygon(EsriPolygon<N, T>),
+    Polyline(EsriPolyline<N, T>),
+    Envelope(EsriEnvelope<T>),
+    CurvePolygon(EsriCurvePolygon<N, T>),
+    CurvePolyline(EsriCurvePolyline<N, T>),
 }
 
-impl<const N: usize> EsriGeometry<N> {
+impl<const N: usize, T> EsriGeometry<N, T> {
+    /// Sets `hasZ` and `hasM` if they are missing so that they are always serialized.
+    ///
+    /// Points and envelopes do not carry these flags and are returned unchanged.
+    pub fn with_dimension_flags(self) -> Self {
+        match self {
+            EsriGeometry::MultiPoint(mp) => EsriGeometry::MultiPoint(mp.with_dimension_flags()),
+            EsriGeometry::Polyline(pl) => EsriGeometry::Polyline(pl.with_dimension_flags()),
+            EsriGeometry::Polygon(ply) => EsriGeometry::Polygon(ply.with_dimension_flags()),
+            EsriGeometry::CurvePolyline(pl) => {
+                EsriGeometry::CurvePolyline(pl.with_dimension_flags())
+            }
+            EsriGeometry::CurvePolygon(ply) => {
+                EsriGeometry::CurvePolygon(ply.with_dimension_flags())
+            }
+            EsriGeometry::Multipatch(mp) => EsriGeometry::Multipatch(mp.with_dimension_flags()),
+            g => g,
+        }
+    }
+
+    /// The Esri geometry type, e.g. `"esriGeometryPolygon"`
+    pub fn geometry_type(&self) -> &'static str {
+        match self {
+            EsriGeometry::Point(_) => "esriGeometryPoint",
+            EsriGeometry::MultiPoint(_) => "esriGeometryMultipoint",
+            EsriGeometry::Polyline(_) | EsriGeometry::CurvePolyline(_) => "esriGeometryPolyline",
+            EsriGeometry::Polygon(_) | EsriGeometry::CurvePolygon(_) => "esriGeometryPolygon",
+            EsriGeometry::Envelope(_) => "esriGeometryEnvelope",
+            EsriGeometry::Multipatch(_) => "esriGeometryMultiPatch",
+        }
+    }
+
+    /// The spatial reference of the geometry, if set
+    pub fn spatial_reference(&self) -> Option<&SpatialReference> {
+        match self {
+            EsriGeometry::Point(g) => g.spatialReference.as_ref(),
+            EsriGeometry::MultiPoint(g) => g.spatialReference.as_ref(),
+            EsriGeometry::Multipatch(g) => g.spatialReference.as_ref(),
+            EsriGeometry::Polygon(g) => g.spatialReference.as_ref(),
+            EsriGeometry::Polyline(g) => g.spatialReference.as_ref(),
+            EsriGeometry::Envelope(g) => g.spatialReference.as_ref(),
+            EsriGeometry::CurvePolygon(g) => g.spatialReference.as_ref(),
+            EsriGeometry::CurvePolyline(g) => g.spatialReference.as_ref(),
+        }
+    }
+
+    /// The envelope of the geometry or `None` if it has no coordinates
+    ///
+    /// ```
+    /// use serde_esri::geometry::EsriGeometry;
+    ///
+    /// let json = r#"{"paths": [[[0, 5], [2, 1]], [[-1, 3], [4, 4]]]}"#;
+    /// let geometry: EsriGeometry<2> = serde_json::from_str(json).unwrap();
+    /// assert_eq!(geometry.bbox(), Some([-1.0, 1.0, 4.0, 5.0]));
+    /// ```
+    pub fn extent(&self) -> Option<EsriEnvelope<T>>
+    where
+        T: Copy + PartialOrd,
+    {
+        match self {
+            EsriGeometry::Point(g) => Some(g.extent()),
+            EsriGeometry::MultiPoint(g) => g.extent(),
+            EsriGeometry::Polyline(g) => g.extent(),
+            EsriGeometry::Polygon(g) => g.extent(),
+            EsriGeometry::Envelope(g) => Some(g.clone()),
+            EsriGeometry::CurvePolyline(g) => g.extent(),
+            EsriGeometry::CurvePolygon(g) => g.extent(),
+            EsriGeometry::Multipatch(g) => g.extent(),
+        }
+    }
+
+    /// The bounding box of the geometry as `[xmin, ymin, xmax, ymax]`
+    pub fn bbox(&self) -> Option<[T; 4]>
+    where
+        T: Copy + PartialOrd,
+    {
+        self.extent().map(|e| e.bbox())
+    }
+
     /// Returns a point if possible
-    pub fn as_point(self) -> Option<EsriPoint> {
+    pub fn as_point(self) -> Option<EsriPoint<T>> {
         match self {
             EsriGeometry::Point(p) => Some(p),
             _ => None,
@@ -218,7 +540,7 @@ impl<const N: usize> EsriGeometry<N> {
     }
 
     /// Returns a multipoint if possible
-    pub fn as_multipoint(self) -> Option<EsriMultiPoint<N>> {
+    pub fn as_multipoint(self) -> Option<EsriMultiPoint<N, T>> {
         match self {
             EsriGeometry::MultiPoint(p) => Some(p),
             _ => None,
@@ -226,7 +548,7 @@ impl<const N: usize> EsriGeometry<N> {
     }
 
     /// Returns a polyline if possible
-    pub fn as_polyline(self) -> Option<EsriPolyline<N>> {
+    pub fn as_polyline(self) -> Option<EsriPolyline<N, T>> {
         match self {
             EsriGeometry::Polyline(pl) => Some(pl),
             _ => None,
@@ -234,7 +556,7 @@ impl<const N: usize> EsriGeometry<N> {
     }
 
     /// Returns a polygon if possible
-    pub fn as_polygon(self) -> Option<EsriPolygon<N>> {
+    pub fn as_polygon(self) -> Option<EsriPolygon<N, T>> {
         match self {
             EsriGeometry::Polygon(ply) => Some(ply),
             _ => None,
@@ -242,20 +564,403 @@ impl<const N: usize> EsriGeometry<N> {
     }
 }
 
-// Completed: esriGeometryPoint | esriGeometryMultipoint | esriGeometryPolyline | esriGeometryPolygon |
-// TODO: esriGeometryEnvelope.
+/// A geometry whose dimensions are determined when it is deserialized
+///
+/// The variant is chosen based on the `hasZ` and `hasM` flags. If neither is present,
+/// the `z` and `m` values of points, the `zmin` and `mmin` values of envelopes, or
+/// the length of the first coordinate are used instead. Convert to an
+/// `EsriGeometry<N>` with `try_into()`.
+///
+/// ```
+/// use serde_esri::geometry::{EsriGeometry, EsriGeometryDyn};
+///
+/// let json = r#"{"hasZ": true, "paths": [[[0, 0, 10], [1, 1, 20]]]}"#;
+/// let geometry: EsriGeometryDyn = serde_json::from_str(json).unwrap();
+/// assert!(geometry.has_z());
+///
+/// let polyline: EsriGeometry<3> = geometry.clone().try_into().unwrap();
+/// assert!(EsriGeometry::<2>::try_from(geometry).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub enum EsriGeometryDyn<T = f64> {
+    Xy(EsriGeometry<2, T>),
+    Xyz(EsriGeometry<3, T>),
+    /// Coordinates are `[x, y, m]`
+    Xym(EsriGeometry<3, T>),
+    Xyzm(EsriGeometry<4, T>),
+}
+
+impl<T> EsriGeometryDyn<T> {
+    pub fn has_z(&self) -> bool {
+        matches!(self, Self::Xyz(_) | Self::Xyzm(_))
+    }
+
+    pub fn has_m(&self) -> bool {
+        matches!(self, Self::Xym(_) | Self::Xyzm(_))
+    }
+
+    /// The Esri geometry type, e.g. `"esriGeometryPolygon"`
+    pub fn geometry_type(&self) -> &'static str {
+        match self {
+            Self::Xy(g) => g.geometry_type(),
+            Self::Xyz(g) | Self::Xym(g) => g.geometry_type(),
+            Self::Xyzm(g) => g.geometry_type(),
+        }
+    }
+}
+
+// the dimensions of a geometry without `hasZ` or `hasM`
+fn infer_dimensions(x: &Value) -> (bool, bool) {
+    let has_value = |key| x.get(key).is_some_and(|v| !v.is_null());
+    if x.get("x").is_some() {
+        return (has_value("z"), has_value("m"));
+    }
+    if x.get("xmin").is_some() {
+        return (has_value("zmin"), has_value("mmin"));
+    }
+
+    let coord = x
+        .get("points")
+        .and_then(|p| p.get(0))
+        .or_else(|| x.get("paths").and_then(|p| p.get(0)?.get(0)))
+        .or_else(|| x.get("rings").and_then(|r| r.get(0)?.get(0)))
+        .or_else(|| x.get("curvePaths").and_then(|p| p.get(0)?.get(0)))
+        .or_else(|| x.get("curveRings").and_then(|r| r.get(0)?.get(0)));
+
+    match coord.and_then(Value::as_array).map(Vec::len) {
+        Some(3) => (true, false),
+        Some(4) => (true, true),
+        _ => (false, false),
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for EsriGeometryDyn<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let flag = |key| value.get(key).and_then(Value::as_bool);
+
+        let (has_z, has_m) = match (flag("hasZ"), flag("hasM")) {
+            (None, None) => infer_dimensions(&value),
+            (z, m) => (z.unwrap_or(false), m.unwrap_or(false)),
+        };
+
+        match (has_z, has_m) {
+            (false, false) => EsriGeometry::deserialize(value).map(Self::Xy),
+            (true, false) => EsriGeometry::deserialize(value).map(Self::Xyz),
+            (false, true) => EsriGeometry::deserialize(value).map(Self::Xym),
+            (true, true) => EsriGeometry::deserialize(value).map(Self::Xyzm),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+impl<T: Serialize> Serialize for EsriGeometryDyn<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Xy(g) => g.serialize(serializer),
+            Self::Xyz(g) | Self::Xym(g) => g.serialize(serializer),
+            Self::Xyzm(g) => g.serialize(serializer),
+        }
+    }
+}
+
+/// An [`EsriGeometryDyn`] does not have the dimensions of the requested `EsriGeometry<N>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DimensionMismatch {
+    pub expected: usize,
+    pub has_z: bool,
+    pub has_m: bool,
+}
+
+impl std::fmt::Display for DimensionMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "expected {} dimensions, found hasZ: {} and hasM: {}",
+            self.expected, self.has_z, self.has_m
+        )
+    }
+}
+
+impl std::error::Error for DimensionMismatch {}
+
+// `EsriGeometry<3>` is XYZ. XYM geometries are only available from `EsriGeometryDyn::Xym`
+macro_rules! impl_dyn_conversions {
+    ($n:literal, $variant:ident) => {
+        impl<T> From<EsriGeometry<$n, T>> for EsriGeometryDyn<T> {
+            fn from(value: EsriGeometry<$n, T>) -> Self {
+                Self::$variant(value)
+            }
+        }
+
+        impl<T> TryFrom<EsriGeometryDyn<T>> for EsriGeometry<$n, T> {
+            type Error = DimensionMismatch;
+
+            fn try_from(value: EsriGeometryDyn<T>) -> Result<Self, Self::Error> {
+                match value {
+                    EsriGeometryDyn::$variant(g) => Ok(g),
+                    g => Err(DimensionMismatch {
+                        expected: $n,
+                        has_z: g.has_z(),
+                        has_m: g.has_m(),
+                    }),
+                }
+            }
+        }
+    };
+}
 
+impl_dyn_conversions!(2, Xy);
+impl_dyn_conversions!(3, Xyz);
+impl_dyn_conversions!(4, Xyzm);
+
+/// An `esriGeometryEnvelope` defined by its minimum and maximum x and y values
+///
+/// The z and m ranges are optional.
 #[allow(non_snake_case)]
 #[derive(Clone, Deserialize, Serialize, Debug, Default)]
 #[skip_serializing_none]
-pub struct EsriEnvelope {
-    xmin: f64,
-    ymin: f64,
-    xmax: f64,
-    ymax: f64,
-    zmin: Option<f64>,
-    zmax: Option<f64>,
-    mmin: Option<f64>,
-    mmax: Option<f64>,
-    spatialReference: Option<SpatialReference>,
+pub struct EsriEnvelope<T = f64> {
+    pub xmin: T,
+    pub ymin: T,
+    pub xmax: T,
+    pub ymax: T,
+    pub zmin: Option<T>,
+    pub zmax: Option<T>,
+    pub mmin: Option<T>,
+    pub mmax: Option<T>,
+    pub spatialReference: Option<SpatialReference>,
+}
+
+impl<T: Copy> EsriEnvelope<T> {
+    /// The bounding box of the envelope as `[xmin, ymin, xmax, ymax]`
+    pub fn bbox(&self) -> [T; 4] {
+        [self.xmin, self.ymin, self.xmax, self.ymax]
+    }
+}
+
+impl EsriEnvelope {
+    /// Subdivide the envelope into a grid of equally sized tiles that are no
+    /// wider than `max_width` and no taller than `max_height`
+    ///
+    /// Tiles are returned row by row from the bottom left and keep the
+    /// envelope's spatial reference. Neighbouring tiles share an edge.
+    /// An envelope with a coordinate that is not finite has no tiles.
+    ///
+    /// # Panics
+    ///
+    /// If `max_width` or `max_height` is not positive.
+    ///
+    /// ```
+    /// use serde_esri::geometry::EsriEnvelope;
+    ///
+    /// let extent = EsriEnvelope { xmin: 0.0, ymin: 0.0, xmax: 10.0, ymax: 5.0, ..Default::default() };
+    /// let tiles = extent.tiles(4.0, 5.0).collect::<Vec<_>>();
+    /// assert_eq!(tiles.len(), 3);
+    /// assert_eq!((tiles[2].xmin, tiles[2].xmax), (20.0 / 3.0, 10.0));
+    ///
+    /// let infinite = EsriEnvelope { xmax: f64::INFINITY, ..extent };
+    /// assert_eq!(infinite.tiles(4.0, 5.0).count(), 0);
+    /// ```
+    pub fn tiles(&self, max_width: f64, max_height: f64) -> EsriEnvelopeTiles {
+        assert!(
+            max_width > 0.0 && max_height > 0.0,
+            "the size of a tile must be positive"
+        );
+
+        let finite = [self.xmin, self.ymin, self.xmax, self.ymax]
+            .iter()
+            .all(|c| c.is_finite());
+        let count = |extent: f64, max: f64| ((extent / max).ceil() as usize).max(1);
+        let cols = count(self.xmax - self.xmin, max_width);
+        let rows = count(self.ymax - self.ymin, max_height);
+        EsriEnvelopeTiles {
+            extent: self.clone(),
+            cols,
+            rows,
+            // more tiles than can be counted are never all iterated
+            total: match finite {
+                true => cols.saturating_mul(rows),
+                false => 0,
+            },
+            next: 0,
+        }
+    }
+}
+
+/// An iterator over the tiles of an [`EsriEnvelope`], created by [`EsriEnvelope::tiles()`]
+#[derive(Debug, Clone)]
+pub struct EsriEnvelopeTiles {
+    extent: EsriEnvelope,
+    cols: usize,
+    rows: usize,
+    total: usize,
+    next: usize,
+}
+
+impl Iterator for EsriEnvelopeTiles {
+    type Item = EsriEnvelope;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.total {
+            return None;
+        }
+
+        let (row, col) = (self.next / self.cols, self.next % self.cols);
+        self.next += 1;
+
+        // the last row and column end exactly on the edge of the extent
+        let split = |min: f64, max: f64, i: usize, n: usize| {
+            let step = (max - min) / n as f64;
+            let end = if i + 1 == n {
+                max
+            } else {
+                min + step * (i + 1) as f64
+            };
+            (min + step * i as f64, end)
+        };
+        let (xmin, xmax) = split(self.extent.xmin, self.extent.xmax, col, self.cols);
+        let (ymin, ymax) = split(self.extent.ymin, self.extent.ymax, row, self.rows);
+
+        Some(EsriEnvelope {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+            ..self.extent.clone()
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.total - self.next;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for EsriEnvelopeTiles {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_dimension_flags() {
+        let xyz = EsriPolyline::from_coords_zm([[[0.0, 0.0, 1.0], [1.0, 1.0, 2.0]]]);
+        assert_eq!((xyz.hasZ, xyz.hasM), (Some(true), Some(false)));
+
+        // the M of XYM geometries is kept
+        let xym = EsriPolyline::<3> {
+            hasZ: None,
+            hasM: Some(true),
+            ..xyz
+        }
+        .with_dimension_flags();
+        assert_eq!((xym.hasZ, xym.hasM), (Some(false), Some(true)));
+        let json = serde_json::to_value(&xym).unwrap();
+        assert_eq!(json["hasZ"], false);
+        assert_eq!(json["hasM"], true);
+
+        let xym = EsriMultiPoint::<3> {
+            hasZ: Some(false),
+            points: vec![EsriCoord([0.0, 0.0, 5.0])],
+            ..Default::default()
+        }
+        .with_dimension_flags();
+        assert_eq!((xym.hasZ, xym.hasM), (Some(false), Some(true)));
+
+        // flags that are both set aren't changed
+        let ply = EsriPolygon::<4> {
+            hasZ: Some(true),
+            hasM: Some(true),
+            ..Default::default()
+        }
+        .with_dimension_flags();
+        assert_eq!((ply.hasZ, ply.hasM), (Some(true), Some(true)));
+
+        let xy = EsriGeometry::Polygon(EsriPolygon::<2>::default()).with_dimension_flags();
+        let EsriGeometry::Polygon(xy) = xy else {
+            panic!("expected a polygon");
+        };
+        assert_eq!((xy.hasZ, xy.hasM), (Some(false), Some(false)));
+    }
+
+    #[test]
+    fn test_dyn_round_trip() {
+        let cases = [
+            (
+                r#"{"hasZ": false, "hasM": false, "paths": [[[0.0, 0.0], [1.0, 1.0]]]}"#,
+                false,
+                false,
+            ),
+            (
+                r#"{"hasZ": true, "hasM": false, "paths": [[[0.0, 0.0, 5.0], [1.0, 1.0, 6.0]]]}"#,
+                true,
+                false,
+            ),
+            (
+                r#"{"hasZ": false, "hasM": true, "paths": [[[0.0, 0.0, 5.0], [1.0, 1.0, 6.0]]]}"#,
+                false,
+                true,
+            ),
+            (
+                r#"{"hasZ": true, "hasM": true, "paths": [[[0.0, 0.0, 5.0, 7.0], [1.0, 1.0, 6.0, 8.0]]]}"#,
+                true,
+                true,
+            ),
+        ];
+
+        for (json, has_z, has_m) in cases {
+            let geometry: EsriGeometryDyn = serde_json::from_str(json).unwrap();
+            assert_eq!(
+                (geometry.has_z(), geometry.has_m()),
+                (has_z, has_m),
+                "{json}"
+            );
+            assert_eq!(geometry.geometry_type(), "esriGeometryPolyline");
+
+            let value = serde_json::to_value(&geometry).unwrap();
+            assert_eq!(value, serde_json::from_str::<Value>(json).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_dyn_xym() {
+        // without flags three coordinates are XYZ
+        let json = r#"{"paths": [[[0.0, 0.0, 5.0], [1.0, 1.0, 6.0]]]}"#;
+        let geometry: EsriGeometryDyn = serde_json::from_str(json).unwrap();
+        assert!(matches!(geometry, EsriGeometryDyn::Xyz(_)));
+
+        let json = r#"{"hasM": true, "paths": [[[0.0, 0.0, 5.0], [1.0, 1.0, 6.0]]]}"#;
+        let EsriGeometryDyn::Xym(xym) = serde_json::from_str::<EsriGeometryDyn>(json).unwrap()
+        else {
+            panic!("expected an XYM geometry");
+        };
+
+        // filling in the flags keeps the M and the geometry stays XYM
+        let geometry = EsriGeometryDyn::Xym(xym.with_dimension_flags());
+        let value = serde_json::to_value(&geometry).unwrap();
+        assert_eq!(
+            (&value["hasZ"], &value["hasM"]),
+            (&false.into(), &true.into())
+        );
+        let geometry: EsriGeometryDyn = serde_json::from_value(value).unwrap();
+        assert!(matches!(geometry, EsriGeometryDyn::Xym(_)));
+
+        let err = EsriGeometry::<3>::try_from(geometry).unwrap_err();
+        assert_eq!(
+            err,
+            DimensionMismatch {
+                expected: 3,
+                has_z: false,
+                has_m: true
+            }
+        );
+
+        let point: EsriGeometryDyn =
+            serde_json::from_str(r#"{"x": 1.0, "y": 2.0, "m": 3.0}"#).unwrap();
+        assert!(matches!(
+            point,
+            EsriGeometryDyn::Xym(EsriGeometry::Point(_))
+        ));
+    }
 }
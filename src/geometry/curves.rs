@@ -0,0 +1,573 @@
+// True curves as returned by feature services with `returnTrueCurves=true`
+//
+// A curved path is a list of segments. The first is always the start point and
+// every following segment ends at its own end point. Curves are linearized with
+// `densify()` before being converted to other formats.
+use super::{EsriCoord, EsriEnvelope, EsriLineString, EsriPolygon, EsriPolyline};
+use crate::spatial_reference::SpatialReference;
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_with::skip_serializing_none;
+use std::f64::consts::TAU;
+
+// an upper limit on the number of vertices a single curve is densified into
+const MAX_SEGMENTS: usize = 4096;
+
+/// A segment of a curved path
+///
+/// Interior, center, and control points only have x and y values. The z and m
+/// values of densified curves are interpolated between the start and end points.
+#[derive(Clone, Debug)]
+pub enum CurveSegment<const N: usize, T = f64> {
+    /// A straight line to a coordinate. The first segment of a path is its start point.
+    Line(EsriCoord<N, T>),
+    /// A circular arc through an interior point, `{"c": [end, interior]}`
+    CircularArc {
+        end: EsriCoord<N, T>,
+        interior: [T; 2],
+    },
+    /// An elliptic arc, `{"a": [end, center, minor, clockwise, rotation, axis, ratio]}`
+    EllipticArc(EsriEllipticArc<N, T>),
+    /// A cubic Bézier curve, `{"b": [end, control1, control2]}`
+    Bezier {
+        end: EsriCoord<N, T>,
+        controls: [[T; 2]; 2],
+    },
+}
+
+impl<const N: usize, T> CurveSegment<N, T> {
+    /// The coordinate the segment ends at
+    pub fn end(&self) -> &EsriCoord<N, T> {
+        match self {
+            CurveSegment::Line(end)
+            | CurveSegment::CircularArc { end, .. }
+            | CurveSegment::Bezier { end, .. } => end,
+            CurveSegment::EllipticArc(arc) => &arc.end,
+        }
+    }
+}
+
+/// An elliptic arc segment
+///
+/// Arcs of a circle omit `rotation`, `axis`, and `ratio`. Their radius is the
+/// distance between the start point and the center.
+#[derive(Clone, Debug)]
+pub struct EsriEllipticArc<const N: usize, T = f64> {
+    pub end: EsriCoord<N, T>,
+    pub center: [T; 2],
+    pub minor: bool,
+    pub clockwise: bool,
+    /// The angle of the major axis in radians
+    pub rotation: Option<f64>,
+    /// The length of the semi-major axis
+    pub axis: Option<f64>,
+    /// The ratio of the minor axis to the major axis
+    pub ratio: Option<f64>,
+}
+
+// the JSON representation of a segment
+#[derive(Deserialize)]
+#[serde(untagged, bound(deserialize = "T: Deserialize<'de>"))]
+enum RawSegment<const N: usize, T> {
+    Line(EsriCoord<N, T>),
+    CircularArc {
+        c: (EsriCoord<N, T>, [T; 2]),
+    },
+    EllipticArc {
+        a: EsriEllipticArc<N, T>,
+    },
+    Bezier {
+        b: (EsriCoord<N, T>, [T; 2], [T; 2]),
+    },
+}
+
+impl<'de, const N: usize, T: Deserialize<'de>> Deserialize<'de> for CurveSegment<N, T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let segment = match RawSegment::deserialize(deserializer)? {
+            RawSegment::Line(x) => CurveSegment::Line(x),
+            RawSegment::CircularArc { c: (end, interior) } => {
+                CurveSegment::CircularArc { end, interior }
+            }
+            RawSegment::EllipticArc { a } => CurveSegment::EllipticArc(a),
+            RawSegment::Bezier { b: (end, c1, c2) } => CurveSegment::Bezier {
+                end,
+                controls: [c1, c2],
+            },
+        };
+        Ok(segment)
+    }
+}
+
+impl<const N: usize, T: Serialize> Serialize for CurveSegment<N, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = match self {
+            CurveSegment::Line(x) => return x.serialize(serializer),
+            _ => serializer.serialize_map(Some(1))?,
+        };
+        match self {
+            CurveSegment::CircularArc { end, interior } => {
+                map.serialize_entry("c", &(end, interior))?
+            }
+            CurveSegment::EllipticArc(arc) => map.serialize_entry("a", arc)?,
+            CurveSegment::Bezier { end, controls } => {
+                map.serialize_entry("b", &(end, &controls[0], &controls[1]))?
+            }
+            CurveSegment::Line(_) => unreachable!(),
+        }
+        map.end()
+    }
+}
+
+// `minor` and `clockwise` are written as 0 or 1 but booleans are accepted too
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Flag {
+    Bool(bool),
+    Int(i64),
+}
+
+impl From<Flag> for bool {
+    fn from(value: Flag) -> Self {
+        match value {
+            Flag::Bool(x) => x,
+            Flag::Int(x) => x != 0,
+        }
+    }
+}
+
+impl<'de, const N: usize, T: Deserialize<'de>> Deserialize<'de> for EsriEllipticArc<N, T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ArcVisitor<const N: usize, T>(std::marker::PhantomData<T>);
+
+        impl<'de, const N: usize, T: Deserialize<'de>> Visitor<'de> for ArcVisitor<N, T> {
+            type Value = EsriEllipticArc<N, T>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("an array of [end, center, minor, clockwise, rotation, axis, ratio]")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let missing = |i| de::Error::invalid_length(i, &self);
+                Ok(EsriEllipticArc {
+                    end: seq.next_element()?.ok_or_else(|| missing(0))?,
+                    center: seq.next_element()?.ok_or_else(|| missing(1))?,
+                    minor: seq
+                        .next_element::<Flag>()?
+                        .ok_or_else(|| missing(2))?
+                        .into(),
+                    clockwise: seq
+                        .next_element::<Flag>()?
+                        .ok_or_else(|| missing(3))?
+                        .into(),
+                    rotation: seq.next_element()?,
+                    axis: seq.next_element()?,
+                    ratio: seq.next_element()?,
+                })
+            }
+        }
+
+        deserializer.deserialize_seq(ArcVisitor(std::marker::PhantomData))
+    }
+}
+
+impl<const N: usize, T: Serialize> Serialize for EsriEllipticArc<N, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let ellipse = match (self.rotation, self.axis, self.ratio) {
+            (Some(rotation), Some(axis), Some(ratio)) => Some([rotation, axis, ratio]),
+            _ => None,
+        };
+
+        let mut seq = serializer.serialize_seq(Some(if ellipse.is_some() { 7 } else { 4 }))?;
+        seq.serialize_element(&self.end)?;
+        seq.serialize_element(&self.center)?;
+        seq.serialize_element(&(self.minor as u8))?;
+        seq.serialize_element(&(self.clockwise as u8))?;
+        for x in ellipse.iter().flatten() {
+            seq.serialize_element(x)?;
+        }
+        seq.end()
+    }
+}
+
+/// An `esriGeometryPolyline` with true curves, defined by `curvePaths`
+///
+/// ```
+/// use serde_esri::geometry::EsriCurvePolyline;
+///
+/// let json = r#"{"curvePaths": [[[0, 0], {"c": [[2, 0], [1, 1]]}]]}"#;
+/// let curve: EsriCurvePolyline<2> = serde_json::from_str(json).unwrap();
+///
+/// // a half circle with a radius of 1
+/// let polyline = curve.densify(0.01);
+/// assert!(polyline.paths[0].0.len() > 3);
+/// assert!(polyline.paths[0].iter().all(|c| ((c.0[0] - 1.0).hypot(c.0[1]) - 1.0).abs() < 1e-9));
+/// ```
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Clone, Deserialize, Serialize, Debug, Default)]
+pub struct EsriCurvePolyline<const N: usize, T = f64> {
+    pub hasZ: Option<bool>,
+    pub hasM: Option<bool>,
+    pub curvePaths: Vec<Vec<CurveSegment<N, T>>>,
+    pub spatialReference: Option<SpatialReference>,
+}
+
+/// An `esriGeometryPolygon` with true curves, defined by `curveRings`
+///
+/// Rings are wound the same way as those of an [`EsriPolygon`].
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Clone, Deserialize, Serialize, Debug, Default)]
+pub struct EsriCurvePolygon<const N: usize, T = f64> {
+    pub hasZ: Option<bool>,
+    pub hasM: Option<bool>,
+    pub curveRings: Vec<Vec<CurveSegment<N, T>>>,
+    pub spatialReference: Option<SpatialReference>,
+}
+
+impl<const N: usize, T: Copy + Into<f64>> EsriCurvePolyline<N, T> {
+    /// Linearize the curves into a polyline
+    ///
+    /// `tolerance` is the largest distance allowed between a curve and the
+    /// line segments that approximate it.
+    pub fn densify(&self, tolerance: f64) -> EsriPolyline<N, f64> {
+        EsriPolyline {
+            hasZ: self.hasZ,
+            hasM: self.hasM,
+            paths: densify_paths(&self.curvePaths, tolerance),
+            spatialReference: self.spatialReference.clone(),
+        }
+    }
+
+    /// Linearize the curves with a tolerance of 1/10000 of the size of the geometry
+    ///
+    /// Used when converting curves to other formats.
+    pub fn linearize(&self) -> EsriPolyline<N, f64> {
+        self.densify(default_tolerance(&self.curvePaths))
+    }
+}
+
+impl<const N: usize, T: Copy + Into<f64>> EsriCurvePolygon<N, T> {
+    /// Linearize the curves into a polygon
+    ///
+    /// `tolerance` is the largest distance allowed between a curve and the
+    /// line segments that approximate it.
+    pub fn densify(&self, tolerance: f64) -> EsriPolygon<N, f64> {
+        EsriPolygon {
+            hasZ: self.hasZ,
+            hasM: self.hasM,
+            rings: densify_paths(&self.curveRings, tolerance),
+            spatialReference: self.spatialReference.clone(),
+        }
+    }
+
+    /// Linearize the curves with a tolerance of 1/10000 of the size of the geometry
+    ///
+    /// Used when converting curves to other formats.
+    pub fn linearize(&self) -> EsriPolygon<N, f64> {
+        self.densify(default_tolerance(&self.curveRings))
+    }
+}
+
+impl<const N: usize, T: Copy + PartialOrd> EsriCurvePolyline<N, T> {
+    /// The envelope of the vertices, interior points, and control points of the curves
+    ///
+    /// Arcs may extend beyond it. Use the envelope of [`densify()`](Self::densify) if that matters.
+    pub fn extent(&self) -> Option<EsriEnvelope<T>> {
+        curves_extent(
+            &self.curvePaths,
+            self.hasZ,
+            self.hasM,
+            &self.spatialReference,
+        )
+    }
+}
+
+impl<const N: usize, T: Copy + PartialOrd> EsriCurvePolygon<N, T> {
+    /// The envelope of the vertices, interior points, and control points of the curves
+    ///
+    /// Arcs may extend beyond it. Use the envelope of [`densify()`](Self::densify) if that matters.
+    pub fn extent(&self) -> Option<EsriEnvelope<T>> {
+        curves_extent(
+            &self.curveRings,
+            self.hasZ,
+            self.hasM,
+            &self.spatialReference,
+        )
+    }
+}
+
+fn curves_extent<const N: usize, T: Copy + PartialOrd>(
+    paths: &[Vec<CurveSegment<N, T>>],
+    has_z: Option<bool>,
+    has_m: Option<bool>,
+    spatial_reference: &Option<SpatialReference>,
+) -> Option<EsriEnvelope<T>> {
+    let ends = paths.iter().flatten().map(CurveSegment::end);
+    let mut extent = super::coords_extent(ends, has_z, has_m, spatial_reference)?;
+
+    let xy = paths.iter().flatten().flat_map(|s| match s {
+        CurveSegment::Line(_) | CurveSegment::EllipticArc(_) => vec![],
+        CurveSegment::CircularArc { interior, .. } => vec![interior],
+        CurveSegment::Bezier { controls, .. } => controls.iter().collect(),
+    });
+    for [x, y] in xy {
+        let (x, y) = (*x, *y);
+        if x < extent.xmin {
+            extent.xmin = x;
+        }
+        if x > extent.xmax {
+            extent.xmax = x;
+        }
+        if y < extent.ymin {
+            extent.ymin = y;
+        }
+        if y > extent.ymax {
+            extent.ymax = y;
+        }
+    }
+    Some(extent)
+}
+
+// 1/10000 of the diagonal of the extent of the vertices
+fn default_tolerance<const N: usize, T: Copy + Into<f64>>(
+    paths: &[Vec<CurveSegment<N, T>>],
+) -> f64 {
+    let (mut min, mut max) = ([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]);
+    for end in paths.iter().flatten().map(CurveSegment::end) {
+        for i in 0..2 {
+            min[i] = min[i].min(end.0[i].into());
+            max[i] = max[i].max(end.0[i].into());
+        }
+    }
+    let diagonal = (max[0] - min[0]).hypot(max[1] - min[1]);
+    if diagonal.is_finite() && diagonal > 0.0 {
+        diagonal / 10_000.0
+    } else {
+        f64::EPSILON
+    }
+}
+
+fn densify_paths<const N: usize, T: Copy + Into<f64>>(
+    paths: &[Vec<CurveSegment<N, T>>],
+    tolerance: f64,
+) -> Vec<EsriLineString<N, f64>> {
+    paths
+        .iter()
+        .map(|path| {
+            let mut coords: Vec<[f64; N]> = Vec::with_capacity(path.len());
+            for segment in path {
+                let end = to_f64(segment.end());
+                match coords.last().copied() {
+                    Some(start) => densify_segment(&mut coords, start, end, segment, tolerance),
+                    // a path that does not begin with a point starts at the end of its first segment
+                    None => coords.push(end),
+                }
+            }
+            EsriLineString::from_coords(coords)
+        })
+        .collect()
+}
+
+fn to_f64<const N: usize, T: Copy + Into<f64>>(x: &EsriCoord<N, T>) -> [f64; N] {
+    x.0.map(Into::into)
+}
+
+// the number of line segments needed for an arc of `sweep` radians
+fn arc_segments(radius: f64, sweep: f64, tolerance: f64) -> usize {
+    let cos = (1.0 - tolerance / radius).clamp(-1.0, 1.0);
+    let step = 2.0 * cos.acos();
+    if step > 0.0 && step.is_finite() {
+        ((sweep.abs() / step).ceil() as usize).clamp(1, MAX_SEGMENTS)
+    } else {
+        MAX_SEGMENTS
+    }
+}
+
+// pushes the vertices after `start` up to and including `end`
+fn densify_segment<const N: usize, T: Copy + Into<f64>>(
+    coords: &mut Vec<[f64; N]>,
+    start: [f64; N],
+    end: [f64; N],
+    segment: &CurveSegment<N, T>,
+    tolerance: f64,
+) {
+    // z and m values are interpolated between the start and end points
+    let mut push = |t: f64, x: f64, y: f64| {
+        let mut coord = start;
+        coord[0] = x;
+        coord[1] = y;
+        for i in 2..N {
+            coord[i] = start[i] + (end[i] - start[i]) * t;
+        }
+        coords.push(coord);
+    };
+
+    match segment {
+        CurveSegment::Line(_) => {}
+        CurveSegment::CircularArc { interior, .. } => {
+            let interior = [interior[0].into(), interior[1].into()];
+            if let Some((center, radius)) = circumcircle(start, interior, end) {
+                let angle = |p: [f64; N]| (p[1] - center[1]).atan2(p[0] - center[0]);
+                let a0 = angle(start);
+                let ccw = |a: f64| (a - a0).rem_euclid(TAU);
+                let a1 = ccw((interior[1] - center[1]).atan2(interior[0] - center[0]));
+                let a2 = ccw(angle(end));
+                // the arc passes through the interior point
+                let sweep = if a1 < a2 { a2 } else { a2 - TAU };
+
+                let n = arc_segments(radius, sweep, tolerance);
+                for i in 1..n {
+                    let t = i as f64 / n as f64;
+                    let a = a0 + sweep * t;
+                    push(
+                        t,
+                        center[0] + radius * a.cos(),
+                        center[1] + radius * a.sin(),
+                    );
+                }
+            }
+        }
+        CurveSegment::EllipticArc(arc) => {
+            let center: [f64; 2] = [arc.center[0].into(), arc.center[1].into()];
+            let (dx, dy) = (start[0] - center[0], start[1] - center[1]);
+            let rotation = arc.rotation.unwrap_or(0.0);
+            let axis = arc.axis.unwrap_or_else(|| dx.hypot(dy));
+            let ratio = arc.ratio.unwrap_or(1.0);
+            let (sin, cos) = rotation.sin_cos();
+
+            // the angle of a point on the unrotated ellipse
+            let param = |x: f64, y: f64| {
+                let (x, y) = (x - center[0], y - center[1]);
+                let (lx, ly) = (x * cos + y * sin, -x * sin + y * cos);
+                (ly / ratio).atan2(lx)
+            };
+            let t0 = param(start[0], start[1]);
+            let t2 = param(end[0], end[1]);
+
+            let mut sweep = if arc.clockwise {
+                -(t0 - t2).rem_euclid(TAU)
+            } else {
+                (t2 - t0).rem_euclid(TAU)
+            };
+            // an arc that ends where it starts is the whole ellipse
+            if sweep == 0.0 && !arc.minor {
+                sweep = if arc.clockwise { -TAU } else { TAU };
+            }
+
+            let n = arc_segments(axis, sweep, tolerance);
+            for i in 1..n {
+                let t = i as f64 / n as f64;
+                let a = t0 + sweep * t;
+                let (lx, ly) = (axis * a.cos(), axis * ratio * a.sin());
+                push(
+                    t,
+                    center[0] + lx * cos - ly * sin,
+                    center[1] + lx * sin + ly * cos,
+                );
+            }
+        }
+        CurveSegment::Bezier { controls, .. } => {
+            let p = [
+                [start[0], start[1]],
+                [controls[0][0].into(), controls[0][1].into()],
+                [controls[1][0].into(), controls[1][1].into()],
+                [end[0], end[1]],
+            ];
+            // Wang's formula for the number of segments of a cubic
+            let second_difference = |i: usize| {
+                (p[i][0] - 2.0 * p[i + 1][0] + p[i + 2][0])
+                    .hypot(p[i][1] - 2.0 * p[i + 1][1] + p[i + 2][1])
+            };
+            let m = second_difference(0).max(second_difference(1));
+            let n = ((0.75 * m / tolerance).sqrt().ceil() as usize).clamp(1, MAX_SEGMENTS);
+
+            for i in 1..n {
+                let t = i as f64 / n as f64;
+                let u = 1.0 - t;
+                let w = [u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t];
+                let x = (0..4).map(|j| w[j] * p[j][0]).sum();
+                let y = (0..4).map(|j| w[j] * p[j][1]).sum();
+                push(t, x, y);
+            }
+        }
+    }
+
+    coords.push(end);
+}
+
+// the center and radius of the circle through three points
+fn circumcircle<const N: usize>(a: [f64; N], b: [f64; 2], c: [f64; N]) -> Option<([f64; 2], f64)> {
+    let d = 2.0 * (a[0] * (b[1] - c[1]) + b[0] * (c[1] - a[1]) + c[0] * (a[1] - b[1]));
+    if d.abs() < f64::EPSILON || !d.is_finite() {
+        return None;
+    }
+
+    let sq = |x: f64, y: f64| x * x + y * y;
+    let (a2, b2, c2) = (sq(a[0], a[1]), sq(b[0], b[1]), sq(c[0], c[1]));
+    let ux = (a2 * (b[1] - c[1]) + b2 * (c[1] - a[1]) + c2 * (a[1] - b[1])) / d;
+    let uy = (a2 * (c[0] - b[0]) + b2 * (a[0] - c[0]) + c2 * (b[0] - a[0])) / d;
+    let radius = (a[0] - ux).hypot(a[1] - uy);
+    Some(([ux, uy], radius))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_round_trip() {
+        let json = r#"{"hasZ":true,"curvePaths":[[[0.0,0.0,1.0],{"a":[[2.0,0.0,3.0],[1.0,0.0],0,1]},{"b":[[4.0,0.0,3.0],[2.5,1.0],[3.5,1.0]]},[5.0,0.0,3.0]]]}"#;
+        let curve: EsriCurvePolyline<3> = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            curve.curvePaths[0][1],
+            CurveSegment::EllipticArc(EsriEllipticArc {
+                clockwise: true,
+                rotation: None,
+                ..
+            })
+        ));
+        assert_eq!(serde_json::to_string(&curve).unwrap(), json);
+
+        let path = &curve.densify(0.001).paths[0];
+        let coords = path.iter().map(|c| c.0).collect::<Vec<_>>();
+        assert_eq!(coords.first(), Some(&[0.0, 0.0, 1.0]));
+        assert_eq!(coords.last(), Some(&[5.0, 0.0, 3.0]));
+
+        // the clockwise half circle passes above the center
+        let top = coords.iter().find(|c| (c[0] - 1.0).abs() < 1e-9).unwrap();
+        assert!((top[1] - 1.0).abs() < 1e-9);
+        assert!((top[2] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_circular_arc_direction() {
+        let arc = |interior: [f64; 2]| EsriCurvePolyline::<2> {
+            curvePaths: vec![vec![
+                CurveSegment::Line(EsriCoord([0.0, 0.0])),
+                CurveSegment::CircularArc {
+                    end: EsriCoord([2.0, 0.0]),
+                    interior,
+                },
+            ]],
+            ..Default::default()
+        };
+
+        for interior in [[1.0, 1.0], [1.0, -1.0]] {
+            let path = &arc(interior).densify(0.001).paths[0];
+            let side = path.0.iter().map(|c| c.0[1] * interior[1]);
+            assert!(side.clone().all(|y| y >= -1e-12));
+            assert!((side.fold(0.0, f64::max) - 1.0).abs() < 1e-6);
+        }
+
+        let circle = &arc([1.0, 1.0]).densify(0.001).paths[0];
+        let deviation = circle.0.windows(2).map(|w| {
+            let mid = [(w[0].0[0] + w[1].0[0]) / 2.0, (w[0].0[1] + w[1].0[1]) / 2.0];
+            1.0 - (mid[0] - 1.0).hypot(mid[1])
+        });
+        assert!(deviation.fold(0.0, f64::max) <= 0.001);
+    }
+}
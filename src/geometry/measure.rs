@@ -0,0 +1,320 @@
+use super::{EsriGeometry, EsriLineString, EsriPoint};
+#[cfg(feature = "geodesic")]
+use crate::spatial_reference::{ReprojectError, SpatialReference, Transform};
+
+// the x and y of the vertices of a geometry, by the kind of part they form
+enum Parts {
+    Points(Vec<[f64; 2]>),
+    Paths(Vec<Vec<[f64; 2]>>),
+    Rings(Vec<Vec<[f64; 2]>>),
+}
+
+fn xy<const N: usize, T: Copy + Into<f64>>(lines: &[EsriLineString<N, T>]) -> Vec<Vec<[f64; 2]>> {
+    lines
+        .iter()
+        .map(|line| {
+            line.0
+                .iter()
+                .map(|c| [c.0[0].into(), c.0[1].into()])
+                .collect()
+        })
+        .collect()
+}
+
+// the segments of a path, or of a ring including the segment closing it
+fn segments(line: &[[f64; 2]], closed: bool) -> impl Iterator<Item = ([f64; 2], [f64; 2])> + '_ {
+    let closing = match (line.first(), line.last()) {
+        (Some(first), Some(last)) if closed && first != last => Some((*last, *first)),
+        _ => None,
+    };
+    line.windows(2).map(|w| (w[0], w[1])).chain(closing)
+}
+
+fn segment_length((a, b): ([f64; 2], [f64; 2])) -> f64 {
+    (b[0] - a[0]).hypot(b[1] - a[1])
+}
+
+// the shoelace formula. Negative for clockwise rings.
+fn signed_area(ring: &[[f64; 2]]) -> f64 {
+    segments(ring, true)
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum::<f64>()
+        / 2.0
+}
+
+impl Parts {
+    #[cfg(feature = "geodesic")]
+    fn map(self, f: impl Fn([f64; 2]) -> [f64; 2]) -> Self {
+        let lines = |lines: Vec<Vec<[f64; 2]>>| {
+            lines
+                .into_iter()
+                .map(|line| line.into_iter().map(&f).collect())
+                .collect()
+        };
+        match self {
+            Parts::Points(points) => Parts::Points(points.into_iter().map(&f).collect()),
+            Parts::Paths(paths) => Parts::Paths(lines(paths)),
+            Parts::Rings(rings) => Parts::Rings(lines(rings)),
+        }
+    }
+
+    fn area(&self) -> f64 {
+        match self {
+            Parts::Rings(rings) => rings.iter().map(|r| signed_area(r)).sum::<f64>().abs(),
+            _ => 0.0,
+        }
+    }
+
+    fn length(&self) -> f64 {
+        match self {
+            Parts::Points(_) => 0.0,
+            Parts::Paths(lines) | Parts::Rings(lines) => {
+                let closed = matches!(self, Parts::Rings(_));
+                lines
+                    .iter()
+                    .flat_map(|line| segments(line, closed))
+                    .map(segment_length)
+                    .sum()
+            }
+        }
+    }
+
+    // area weighted for rings, length weighted for paths, and the mean of points.
+    // Rings without an area fall back to their length, and lines without a length to their vertices.
+    fn centroid(&self) -> Option<[f64; 2]> {
+        let (lines, closed) = match self {
+            Parts::Points(points) => return mean(points.iter()),
+            Parts::Paths(paths) => (paths, false),
+            Parts::Rings(rings) => (rings, true),
+        };
+
+        if closed {
+            let area: f64 = lines.iter().map(|r| signed_area(r)).sum();
+            if area != 0.0 {
+                let (mut x, mut y) = (0.0, 0.0);
+                for (a, b) in lines.iter().flat_map(|r| segments(r, true)) {
+                    let cross = a[0] * b[1] - b[0] * a[1];
+                    x += (a[0] + b[0]) * cross;
+                    y += (a[1] + b[1]) * cross;
+                }
+                return Some([x / (6.0 * area), y / (6.0 * area)]);
+            }
+        }
+
+        let length = self.length();
+        if length == 0.0 {
+            return mean(lines.iter().flatten());
+        }
+        let (mut x, mut y) = (0.0, 0.0);
+        for (a, b) in lines.iter().flat_map(|l| segments(l, closed)) {
+            let weight = segment_length((a, b));
+            x += (a[0] + b[0]) / 2.0 * weight;
+            y += (a[1] + b[1]) / 2.0 * weight;
+        }
+        Some([x / length, y / length])
+    }
+}
+
+fn mean<'a>(points: impl Iterator<Item = &'a [f64; 2]>) -> Option<[f64; 2]> {
+    let (n, x, y) = points.fold((0, 0.0, 0.0), |(n, x, y), p| (n + 1, x + p[0], y + p[1]));
+    (n > 0).then(|| [x / n as f64, y / n as f64])
+}
+
+impl<const N: usize, T: Copy + Into<f64>> EsriGeometry<N, T> {
+    /// The planar area, in the square units of the spatial reference
+    ///
+    /// Holes are subtracted from the area of polygons. Curves are linearized first.
+    /// Points, polylines, and multipatches have no area.
+    pub fn area(&self) -> f64 {
+        self.parts().area()
+    }
+
+    /// The planar length of the paths of a polyline or of the rings of a polygon,
+    /// in the units of the spatial reference
+    ///
+    /// Curves are linearized first. Points and multipatches have no length.
+    pub fn length(&self) -> f64 {
+        self.parts().length()
+    }
+
+    /// The planar centroid, or `None` if the geometry has no vertices
+    ///
+    /// Polygons are weighted by area, polylines by length, and the vertices of
+    /// points and multipatches equally. The centroid has the spatial reference of
+    /// the geometry but no z or m.
+    ///
+    /// ```
+    /// use serde_esri::geometry::{EsriGeometry, EsriPolygon};
+    ///
+    /// let square = [[0.0, 0.0], [0.0, 2.0], [2.0, 2.0], [2.0, 0.0], [0.0, 0.0]];
+    /// let polygon = EsriGeometry::Polygon(EsriPolygon::<2>::from_coords_zm([square]));
+    /// assert_eq!(polygon.area(), 4.0);
+    /// assert_eq!(polygon.length(), 8.0);
+    /// let centroid = polygon.centroid().unwrap();
+    /// assert_eq!((centroid.x, centroid.y), (1.0, 1.0));
+    /// ```
+    pub fn centroid(&self) -> Option<EsriPoint> {
+        let [x, y] = self.parts().centroid()?;
+        Some(EsriPoint {
+            x,
+            y,
+            z: None,
+            m: None,
+            spatialReference: self.spatial_reference().cloned(),
+        })
+    }
+
+    fn parts(&self) -> Parts {
+        match self {
+            EsriGeometry::Point(pt) => Parts::Points(vec![[pt.x.into(), pt.y.into()]]),
+            EsriGeometry::MultiPoint(mp) => Parts::Points(
+                mp.points
+                    .iter()
+                    .map(|c| [c.0[0].into(), c.0[1].into()])
+                    .collect(),
+            ),
+            EsriGeometry::Multipatch(mp) => Parts::Points(xy(&mp.rings).concat()),
+            EsriGeometry::Polyline(pl) => Parts::Paths(xy(&pl.paths)),
+            EsriGeometry::Polygon(ply) => Parts::Rings(xy(&ply.rings)),
+            EsriGeometry::CurvePolyline(pl) => Parts::Paths(xy(&pl.linearize().paths)),
+            EsriGeometry::CurvePolygon(ply) => Parts::Rings(xy(&ply.linearize().rings)),
+            EsriGeometry::Envelope(env) => {
+                let (xmin, ymin) = (env.xmin.into(), env.ymin.into());
+                let (xmax, ymax) = (env.xmax.into(), env.ymax.into());
+                Parts::Rings(vec![vec![
+                    [xmin, ymin],
+                    [xmin, ymax],
+                    [xmax, ymax],
+                    [xmax, ymin],
+                    [xmin, ymin],
+                ]])
+            }
+        }
+    }
+}
+
+#[cfg(feature = "geodesic")]
+impl<const N: usize, T: Copy + Into<f64>> EsriGeometry<N, T> {
+    /// The geodesic area on the WGS 84 ellipsoid, in square meters
+    ///
+    /// The geometry is first transformed from its spatial reference to WGS 84, see
+    /// [`Transform`] for the spatial references that are supported.
+    pub fn geodesic_area(&self) -> Result<f64, ReprojectError> {
+        use geo::GeodesicArea;
+
+        let Parts::Rings(rings) = self.lon_lat()? else {
+            return Ok(0.0);
+        };
+        let area: f64 = rings
+            .into_iter()
+            .map(|ring| geo::Polygon::new(line_string(ring, true), vec![]).geodesic_area_signed())
+            .sum();
+        Ok(area.abs())
+    }
+
+    /// The geodesic length on the WGS 84 ellipsoid, in meters
+    ///
+    /// See [`EsriGeometry::geodesic_area()`] for how the spatial reference is handled.
+    pub fn geodesic_length(&self) -> Result<f64, ReprojectError> {
+        use geo::{Geodesic, Length};
+
+        let (lines, closed) = match self.lon_lat()? {
+            Parts::Points(_) => return Ok(0.0),
+            Parts::Paths(paths) => (paths, false),
+            Parts::Rings(rings) => (rings, true),
+        };
+        Ok(lines
+            .into_iter()
+            .map(|line| Geodesic.length(&line_string(line, closed)))
+            .sum())
+    }
+
+    // the parts with longitudes and latitudes on WGS 84
+    fn lon_lat(&self) -> Result<Parts, ReprojectError> {
+        let sr = self
+            .spatial_reference()
+            .ok_or(ReprojectError::MissingSpatialReference)?;
+        let transform = Transform::new(sr, &SpatialReference::from_wkid(4326))?;
+        Ok(self.parts().map(|[x, y]| {
+            let (lon, lat) = transform.apply(x, y);
+            [lon, lat]
+        }))
+    }
+}
+
+#[cfg(feature = "geodesic")]
+fn line_string(mut line: Vec<[f64; 2]>, closed: bool) -> geo::LineString {
+    if closed && line.first() != line.last() {
+        line.push(line[0]);
+    }
+    line.into_iter()
+        .map(|[x, y]| geo::coord! { x: x, y: y })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{EsriPolygon, EsriPolyline};
+
+    #[test]
+    fn test_planar_measures() {
+        // a 4 by 4 square with a 2 by 2 hole, not closed
+        let polygon = EsriGeometry::Polygon(EsriPolygon::<2>::from_coords_zm([
+            vec![[0.0, 0.0], [0.0, 4.0], [4.0, 4.0], [4.0, 0.0]],
+            vec![[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0], [0.0, 0.0]],
+        ]));
+        assert_eq!(polygon.area(), 12.0);
+        assert_eq!(polygon.length(), 24.0);
+        let centroid = polygon.centroid().unwrap();
+        assert!((centroid.x - 7.0 / 3.0).abs() < 1e-12);
+        assert!((centroid.y - 7.0 / 3.0).abs() < 1e-12);
+
+        let polyline = EsriGeometry::Polyline(EsriPolyline::<2>::from_coords_zm([[
+            [0.0, 0.0],
+            [3.0, 4.0],
+            [3.0, 0.0],
+        ]]));
+        assert_eq!(polyline.area(), 0.0);
+        assert_eq!(polyline.length(), 9.0);
+        let centroid = polyline.centroid().unwrap();
+        assert_eq!((centroid.x, centroid.y), (19.5 / 9.0, 2.0));
+
+        let empty = EsriGeometry::<2>::Polyline(EsriPolyline::default());
+        assert!(empty.centroid().is_none());
+    }
+
+    #[cfg(feature = "geodesic")]
+    #[test]
+    fn test_geodesic_measures() {
+        use crate::spatial_reference::SpatialReference;
+
+        // a degree of longitude along the equator in WGS 84 and Web Mercator
+        let mut polyline = EsriPolyline::<2>::from_coords_zm([[[0.0, 0.0], [1.0, 0.0]]]);
+        assert!(matches!(
+            EsriGeometry::Polyline(polyline.clone()).geodesic_length(),
+            Err(ReprojectError::MissingSpatialReference)
+        ));
+        polyline.spatialReference = Some(SpatialReference::from_wkid(4326));
+        let wgs84 = EsriGeometry::Polyline(polyline);
+        assert!((wgs84.geodesic_length().unwrap() - 111_319.49).abs() < 0.01);
+
+        let mercator = wgs84
+            .clone()
+            .reproject(&SpatialReference::from_wkid(3857))
+            .unwrap();
+        assert!((mercator.geodesic_length().unwrap() - 111_319.49).abs() < 0.01);
+
+        // a 1 by 1 degree cell at the equator
+        let mut cell = EsriPolygon::<2>::from_coords_zm([[
+            [0.0, 0.0],
+            [0.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 0.0],
+            [0.0, 0.0],
+        ]]);
+        cell.spatialReference = Some(SpatialReference::from_wkid(4326));
+        let area = EsriGeometry::Polygon(cell).geodesic_area().unwrap();
+        assert!((area / 1e6 - 12_308.78).abs() < 1.0);
+    }
+}
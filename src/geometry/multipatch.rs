@@ -0,0 +1,161 @@
+// Multipatches describe 3D surfaces, e.g. the buildings of a scene layer
+//
+// Each ring is a patch whose type determines how its coordinates form faces.
+use super::{EsriCoord, EsriEnvelope, EsriLineString};
+use crate::spatial_reference::SpatialReference;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+
+/// How the coordinates of a multipatch ring form faces
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum EsriPatchType {
+    /// Every coordinate after the first two completes a triangle with its two predecessors
+    #[serde(rename = "esriPatchTypeTriangleStrip")]
+    TriangleStrip,
+    /// Every coordinate after the first two completes a triangle with its predecessor and the first coordinate
+    #[serde(rename = "esriPatchTypeTriangleFan")]
+    TriangleFan,
+    /// Every three coordinates are a triangle
+    #[serde(rename = "esriPatchTypeTriangles")]
+    Triangles,
+    /// The exterior ring of a face
+    #[serde(rename = "esriPatchTypeOuterRing")]
+    OuterRing,
+    /// A hole in the face of the preceding outer ring
+    #[serde(rename = "esriPatchTypeInnerRing")]
+    InnerRing,
+    /// The first ring of a face whose rings are of an unspecified type
+    #[serde(rename = "esriPatchTypeFirstRing")]
+    FirstRing,
+    /// A ring of the face of the preceding first ring
+    #[serde(rename = "esriPatchTypeRing")]
+    Ring,
+}
+
+/// An `esriGeometryMultiPatch` defined by rings and the type of each ring
+///
+/// Materials, textures, and any other properties are kept in `extra` so that
+/// the geometry can be re-serialized without losing them.
+///
+/// ```
+/// use serde_esri::geometry::{EsriMultipatch, EsriPatchType};
+///
+/// let json = r#"{
+///     "hasZ": true,
+///     "rings": [[[0, 0, 0], [1, 0, 0], [0, 1, 0], [1, 1, 1]]],
+///     "ringTypes": ["esriPatchTypeTriangleStrip"],
+///     "materials": [{"color": [255, 0, 0]}]
+/// }"#;
+/// let patch: EsriMultipatch<3> = serde_json::from_str(json).unwrap();
+/// assert_eq!(patch.ringTypes, vec![EsriPatchType::TriangleStrip]);
+/// assert!(patch.extra.contains_key("materials"));
+///
+/// // a strip of 4 coordinates is 2 triangles
+/// assert_eq!(patch.faces().len(), 2);
+/// ```
+#[skip_serializing_none]
+#[allow(non_snake_case)]
+#[derive(Clone, Deserialize, Serialize, Debug, Default)]
+pub struct EsriMultipatch<const N: usize, T = f64> {
+    pub hasZ: Option<bool>,
+    pub hasM: Option<bool>,
+    pub rings: Vec<EsriLineString<N, T>>,
+    pub ringTypes: Vec<EsriPatchType>,
+    pub spatialReference: Option<SpatialReference>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<const N: usize, T: Clone> EsriMultipatch<N, T> {
+    /// The faces of the multipatch as closed rings
+    ///
+    /// The first ring of each face is its exterior and the rest are holes.
+    /// Triangles are faces of their own. Rings without a type are treated as
+    /// outer rings.
+    pub fn faces(&self) -> Vec<Vec<EsriLineString<N, T>>> {
+        let triangle = |a: &EsriCoord<N, T>, b: &EsriCoord<N, T>, c: &EsriCoord<N, T>| {
+            vec![EsriLineString(vec![
+                a.clone(),
+                b.clone(),
+                c.clone(),
+                a.clone(),
+            ])]
+        };
+
+        let mut faces = Vec::new();
+        for (i, ring) in self.rings.iter().enumerate() {
+            let coords = &ring.0;
+            match self.ringTypes.get(i).unwrap_or(&EsriPatchType::OuterRing) {
+                EsriPatchType::TriangleStrip => {
+                    faces.extend(coords.windows(3).map(|w| triangle(&w[0], &w[1], &w[2])))
+                }
+                EsriPatchType::TriangleFan => faces.extend(
+                    coords
+                        .windows(2)
+                        .skip(1)
+                        .map(|w| triangle(&coords[0], &w[0], &w[1])),
+                ),
+                EsriPatchType::Triangles => faces.extend(
+                    coords
+                        .chunks_exact(3)
+                        .map(|w| triangle(&w[0], &w[1], &w[2])),
+                ),
+                EsriPatchType::OuterRing | EsriPatchType::FirstRing => {
+                    faces.push(vec![ring.clone()])
+                }
+                EsriPatchType::InnerRing | EsriPatchType::Ring => match faces.last_mut() {
+                    Some(face) => face.push(ring.clone()),
+                    None => faces.push(vec![ring.clone()]),
+                },
+            }
+        }
+        faces
+    }
+}
+
+impl<const N: usize, T: Copy + PartialOrd> EsriMultipatch<N, T> {
+    /// The envelope of all rings or `None` if there are no coordinates
+    pub fn extent(&self) -> Option<EsriEnvelope<T>> {
+        let coords = self.rings.iter().flat_map(|r| &r.0);
+        super::coords_extent(coords, self.hasZ, self.hasM, &self.spatialReference)
+    }
+
+    /// The bounding box of all rings as `[xmin, ymin, xmax, ymax]`
+    pub fn bbox(&self) -> Option<[T; 4]> {
+        self.extent().map(|e| e.bbox())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::EsriGeometry;
+
+    #[test]
+    fn test_multipatch_geometry() {
+        let json = r#"{
+            "hasZ": true,
+            "rings": [
+                [[0, 0, 0], [0, 4, 0], [4, 4, 0], [4, 0, 0], [0, 0, 0]],
+                [[1, 1, 0], [2, 1, 0], [2, 2, 0], [1, 2, 0], [1, 1, 0]],
+                [[0, 0, 0], [4, 0, 0], [4, 0, 3], [0, 0, 3]]
+            ],
+            "ringTypes": ["esriPatchTypeOuterRing", "esriPatchTypeInnerRing", "esriPatchTypeTriangleFan"]
+        }"#;
+        let geometry: EsriGeometry<3> = serde_json::from_str(json).unwrap();
+        assert_eq!(geometry.geometry_type(), "esriGeometryMultiPatch");
+        assert_eq!(geometry.bbox(), Some([0.0, 0.0, 4.0, 4.0]));
+
+        let EsriGeometry::Multipatch(patch) = geometry else {
+            unreachable!()
+        };
+        let faces = patch.faces();
+        assert_eq!(faces.iter().map(Vec::len).collect::<Vec<_>>(), [2, 1, 1]);
+        assert_eq!(faces[2][0].0.last().unwrap().0, [0.0, 0.0, 0.0]);
+
+        // rings without ring types are polygons
+        let json = r#"{"rings": [[[0, 0], [0, 1], [1, 1], [0, 0]]]}"#;
+        let geometry: EsriGeometry<2> = serde_json::from_str(json).unwrap();
+        assert!(matches!(geometry, EsriGeometry::Polygon(_)));
+    }
+}
@@ -0,0 +1,372 @@
+// Normalization of geographic polylines and polygons, so that services accept them
+// and draw them without wrapping around the world.
+//
+// Geometries crossing the antimeridian are split into parts on either side of it.
+// A segment crosses it when its longitudes are more than 180° apart, i.e. the
+// shortest way between its ends is across the antimeridian.
+use super::{EsriLineString, EsriPolygon, EsriPolyline};
+
+/// How [`EsriPolyline::normalize()`] and [`EsriPolygon::normalize()`] normalize a geometry
+///
+/// The steps are applied in the order of the fields.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalizeOptions {
+    /// The longest segment allowed, in degrees. Longer segments are densified.
+    pub max_segment_length: Option<f64>,
+    /// The largest absolute latitude. Use `85.0511287798066` for Web Mercator.
+    pub max_latitude: Option<f64>,
+    /// Split parts crossing the antimeridian
+    pub split_antimeridian: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            max_segment_length: None,
+            max_latitude: Some(90.0),
+            split_antimeridian: true,
+        }
+    }
+}
+
+// a longitude in [-180, 180]
+fn wrap(lon: f64) -> f64 {
+    if (-180.0..=180.0).contains(&lon) {
+        lon
+    } else {
+        (lon + 180.0).rem_euclid(360.0) - 180.0
+    }
+}
+
+fn lerp<const N: usize>(a: &[f64; N], b: &[f64; N], t: f64) -> [f64; N] {
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+// the point at longitude `x` on the segment from `a` to `b`
+fn at_x<const N: usize>(a: &[f64; N], b: &[f64; N], x: f64) -> [f64; N] {
+    let mut c = lerp(a, b, (x - a[0]) / (b[0] - a[0]));
+    c[0] = x;
+    c
+}
+
+fn densify_line<const N: usize>(line: &EsriLineString<N>, max: f64) -> EsriLineString<N> {
+    let mut coords = Vec::with_capacity(line.0.len());
+    for (i, c) in line.0.iter().enumerate() {
+        if let Some(prev) = i.checked_sub(1).map(|i| &line.0[i].0) {
+            let length = (c.0[0] - prev[0]).hypot(c.0[1] - prev[1]);
+            let n = (length / max).ceil() as usize;
+            for step in 1..n {
+                coords.push(lerp(prev, &c.0, step as f64 / n as f64));
+            }
+        }
+        coords.push(c.0);
+    }
+    EsriLineString::from_coords(coords)
+}
+
+fn clamp_line<const N: usize>(line: &EsriLineString<N>, max: f64) -> EsriLineString<N> {
+    EsriLineString::from_coords(line.0.iter().map(|c| {
+        let mut c = c.0;
+        c[1] = c[1].clamp(-max, max);
+        c
+    }))
+}
+
+// splits a path where it crosses the antimeridian
+fn split_path<const N: usize>(path: &EsriLineString<N>) -> Vec<EsriLineString<N>> {
+    let mut parts = Vec::new();
+    let mut part: Vec<[f64; N]> = Vec::new();
+
+    for c in &path.0 {
+        let mut c = c.0;
+        c[0] = wrap(c[0]);
+        if let Some(prev) = part.last().copied() {
+            if (c[0] - prev[0]).abs() > 180.0 {
+                // cross towards the side of the previous point
+                let side = prev[0].signum();
+                let mut unwrapped = c;
+                unwrapped[0] += 360.0 * side;
+                let crossing = at_x(&prev, &unwrapped, 180.0 * side);
+                if prev[0] != crossing[0] {
+                    part.push(crossing);
+                }
+                parts.push(EsriLineString::from_coords(std::mem::take(&mut part)));
+                let mut start = crossing;
+                start[0] = -start[0];
+                if c[0] != start[0] {
+                    part.push(start);
+                }
+            }
+        }
+        part.push(c);
+    }
+
+    if !part.is_empty() {
+        parts.push(EsriLineString::from_coords(part));
+    }
+    parts
+}
+
+// the part of a ring on one side of longitude `bound`
+fn clip_side<const N: usize>(points: &[[f64; N]], bound: f64, above: bool) -> Vec<[f64; N]> {
+    let inside = |c: &[f64; N]| if above { c[0] >= bound } else { c[0] <= bound };
+    let mut res = Vec::with_capacity(points.len());
+    for (i, b) in points.iter().enumerate() {
+        let a = &points[(i + points.len() - 1) % points.len()];
+        match (inside(a), inside(b)) {
+            (true, true) => res.push(*b),
+            (true, false) => res.push(at_x(a, b, bound)),
+            (false, true) => {
+                res.push(at_x(a, b, bound));
+                res.push(*b);
+            }
+            (false, false) => {}
+        }
+    }
+    res
+}
+
+// the part of a closed ring between longitudes `lo` and `hi`, by Sutherland-Hodgman clipping
+fn clip_ring<const N: usize>(ring: &[[f64; N]], lo: f64, hi: f64) -> Vec<[f64; N]> {
+    let clipped = clip_side(&ring[..ring.len() - 1], lo, true);
+    let mut clipped = clip_side(&clipped, hi, false);
+    if let Some(first) = clipped.first().copied() {
+        clipped.push(first);
+    }
+    clipped
+}
+
+// splits a ring into rings on either side of the antimeridian
+fn split_ring<const N: usize>(ring: &EsriLineString<N>) -> Vec<EsriLineString<N>> {
+    let Some(first) = ring.0.first() else {
+        return vec![];
+    };
+
+    // continuous longitudes, which may extend beyond ±180
+    let mut coords: Vec<[f64; N]> = Vec::with_capacity(ring.0.len());
+    let mut first = first.0;
+    first[0] = wrap(first[0]);
+    coords.push(first);
+    for c in &ring.0[1..] {
+        let prev = coords[coords.len() - 1][0];
+        let mut c = c.0;
+        c[0] = prev + (c[0] - prev + 180.0).rem_euclid(360.0) - 180.0;
+        coords.push(c);
+    }
+
+    let (min, max) = coords
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), c| {
+            (min.min(c[0]), max.max(c[0]))
+        });
+    let last = coords[coords.len() - 1];
+
+    // rings around a pole do not close once unwrapped, and rings within
+    // [-180, 180] do not cross the antimeridian
+    let encircles_pole = (last[0] - first[0]).abs() > 180.0;
+    if encircles_pole || coords.len() < 4 || (min >= -180.0 && max <= 180.0) {
+        return vec![EsriLineString::from_coords(ring.0.iter().map(|c| {
+            let mut c = c.0;
+            c[0] = wrap(c[0]);
+            c
+        }))];
+    }
+
+    let first_strip = ((min + 180.0) / 360.0).floor() as i64;
+    let last_strip = ((max + 180.0) / 360.0).ceil() as i64;
+    (first_strip..last_strip)
+        .filter_map(|k| {
+            let offset = 360.0 * k as f64;
+            let clipped = clip_ring(&coords, offset - 180.0, offset + 180.0);
+            (clipped.len() >= 4).then(|| {
+                EsriLineString::from_coords(clipped.into_iter().map(|mut c| {
+                    c[0] -= offset;
+                    c
+                }))
+            })
+        })
+        .collect()
+}
+
+impl<const N: usize> EsriPolyline<N> {
+    /// Split the paths crossing the antimeridian into paths on either side of it
+    ///
+    /// Longitudes are wrapped into [-180, 180]. Z and M values at the antimeridian
+    /// are interpolated.
+    ///
+    /// ```
+    /// use serde_esri::geometry::EsriPolyline;
+    ///
+    /// let line = EsriPolyline::<2>::from_coords_zm([[[170.0, 0.0], [-170.0, 10.0]]]);
+    /// let split = line.split_antimeridian();
+    /// assert_eq!(split.paths.len(), 2);
+    /// assert_eq!(split.paths[0].0[1].0, [180.0, 5.0]);
+    /// assert_eq!(split.paths[1].0[0].0, [-180.0, 5.0]);
+    /// ```
+    pub fn split_antimeridian(&self) -> Self {
+        Self {
+            paths: self.paths.iter().flat_map(split_path).collect(),
+            spatialReference: self.spatialReference.clone(),
+            ..*self
+        }
+    }
+
+    /// Clamp latitudes to [-`max`, `max`]
+    pub fn clamp_latitudes(&self, max: f64) -> Self {
+        Self {
+            paths: self.paths.iter().map(|p| clamp_line(p, max)).collect(),
+            spatialReference: self.spatialReference.clone(),
+            ..*self
+        }
+    }
+
+    /// Add vertices so that no segment is longer than `max`
+    ///
+    /// # Panics
+    ///
+    /// If `max` is not positive.
+    pub fn densify(&self, max: f64) -> Self {
+        assert!(max > 0.0, "the length of a segment must be positive");
+        Self {
+            paths: self.paths.iter().map(|p| densify_line(p, max)).collect(),
+            spatialReference: self.spatialReference.clone(),
+            ..*self
+        }
+    }
+
+    /// Densify, clamp, and split the polyline according to `options`
+    pub fn normalize(&self, options: &NormalizeOptions) -> Self {
+        let mut res = self.clone();
+        if let Some(max) = options.max_segment_length {
+            res = res.densify(max);
+        }
+        if let Some(max) = options.max_latitude {
+            res = res.clamp_latitudes(max);
+        }
+        if options.split_antimeridian {
+            res = res.split_antimeridian();
+        }
+        res
+    }
+}
+
+impl<const N: usize> EsriPolygon<N> {
+    /// Split the rings crossing the antimeridian into rings on either side of it
+    ///
+    /// Each ring is clipped to either side of the antimeridian, so exterior rings
+    /// and holes keep their orientation. Longitudes are wrapped into [-180, 180].
+    /// Rings around a pole cannot be split and are only wrapped.
+    ///
+    /// ```
+    /// use serde_esri::geometry::EsriPolygon;
+    ///
+    /// let ply = EsriPolygon::<2>::from_coords_zm([[
+    ///     [170.0, -10.0], [170.0, 10.0], [-170.0, 10.0], [-170.0, -10.0], [170.0, -10.0],
+    /// ]]);
+    /// let split = ply.split_antimeridian();
+    /// assert_eq!(split.rings.len(), 2);
+    /// assert!(split.rings.iter().all(|r| r.0.iter().all(|c| c.0[0].abs() >= 170.0)));
+    /// ```
+    pub fn split_antimeridian(&self) -> Self {
+        Self {
+            rings: self.rings.iter().flat_map(split_ring).collect(),
+            spatialReference: self.spatialReference.clone(),
+            ..*self
+        }
+    }
+
+    /// Clamp latitudes to [-`max`, `max`]
+    pub fn clamp_latitudes(&self, max: f64) -> Self {
+        Self {
+            rings: self.rings.iter().map(|r| clamp_line(r, max)).collect(),
+            spatialReference: self.spatialReference.clone(),
+            ..*self
+        }
+    }
+
+    /// Add vertices so that no segment is longer than `max`
+    ///
+    /// # Panics
+    ///
+    /// If `max` is not positive.
+    pub fn densify(&self, max: f64) -> Self {
+        assert!(max > 0.0, "the length of a segment must be positive");
+        Self {
+            rings: self.rings.iter().map(|r| densify_line(r, max)).collect(),
+            spatialReference: self.spatialReference.clone(),
+            ..*self
+        }
+    }
+
+    /// Densify, clamp, and split the polygon according to `options`
+    pub fn normalize(&self, options: &NormalizeOptions) -> Self {
+        let mut res = self.clone();
+        if let Some(max) = options.max_segment_length {
+            res = res.densify(max);
+        }
+        if let Some(max) = options.max_latitude {
+            res = res.clamp_latitudes(max);
+        }
+        if options.split_antimeridian {
+            res = res.split_antimeridian();
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rings::is_clockwise;
+
+    #[test]
+    fn test_split_ring() {
+        // a clockwise square with a hole, crossing the antimeridian
+        let ply = EsriPolygon::<3>::from_coords_zm([
+            vec![
+                [170.0, -10.0, 0.0],
+                [170.0, 10.0, 0.0],
+                [190.0, 10.0, 2.0],
+                [-170.0, -10.0, 2.0],
+                [170.0, -10.0, 0.0],
+            ],
+            vec![
+                [175.0, -5.0, 1.0],
+                [-175.0, -5.0, 1.0],
+                [-175.0, 5.0, 1.0],
+                [175.0, 5.0, 1.0],
+                [175.0, -5.0, 1.0],
+            ],
+        ]);
+        let split = ply.split_antimeridian();
+        assert_eq!(split.rings.len(), 4);
+        assert!(is_clockwise(&split.rings[0]) && is_clockwise(&split.rings[1]));
+        assert!(!is_clockwise(&split.rings[2]) && !is_clockwise(&split.rings[3]));
+        for ring in &split.rings {
+            assert_eq!(ring.0[0].0, ring.0[ring.0.len() - 1].0);
+            assert!(ring.0.iter().all(|c| (-180.0..=180.0).contains(&c.0[0])));
+        }
+        // z is interpolated at the antimeridian
+        assert!(split.rings[0].0.iter().any(|c| c.0 == [180.0, 10.0, 1.0]));
+
+        // a ring around the south pole is only wrapped
+        let polar = EsriPolygon::<2>::from_coords_zm([[
+            [0.0, -80.0],
+            [120.0, -80.0],
+            [240.0, -80.0],
+            [0.0, -80.0],
+        ]]);
+        let split = polar.split_antimeridian();
+        assert_eq!(split.rings.len(), 1);
+        assert_eq!(split.rings[0].0[2].0, [-120.0, -80.0]);
+
+        let normalized = EsriPolyline::<2>::from_coords_zm([[[0.0, 95.0], [10.0, 0.0]]]).normalize(
+            &NormalizeOptions {
+                max_segment_length: Some(5.0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(normalized.paths[0].0[0].0, [0.0, 90.0]);
+        assert!(normalized.paths[0].0.len() > 2);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{
+    auth::{CredentialError, CredentialProvider, TokenProvider, TokenSource},
+    geometry::{EsriGeometry, EsriPolygon},
+    geometry_service::{
+        client::decode_response, simplify_form, AreasAndLengths, AreasAndLengthsParams,
+        BufferParams, Form, GeometriesResponse, GeometryServiceError, ProjectParams,
+    },
+};
+use serde::de::DeserializeOwned;
+
+/// An async client for a Geometry Service
+///
+/// The async counterpart of [`GeometryServiceClient`](crate::geometry_service::GeometryServiceClient)
+/// for use inside an async runtime such as tokio.
+///
+/// ```no_run
+/// use serde_esri::geometry::{EsriGeometry, EsriPoint};
+/// use serde_esri::geometry_service::{AsyncGeometryServiceClient, ProjectParamsBuilder, GEOMETRY_SERVICE_URL};
+///
+/// # async fn run() {
+/// let client = AsyncGeometryServiceClient::new(GEOMETRY_SERVICE_URL);
+/// let point = EsriGeometry::<2>::Point(EsriPoint { x: -117.19, y: 34.05, ..Default::default() });
+/// let params = ProjectParamsBuilder::default().in_sr(4326).out_sr(3857).build().unwrap();
+///
+/// let projected = client.project(&[point], &params).await.unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AsyncGeometryServiceClient {
+    /// The URL of the service, e.g. `.../GeometryServer`
+    pub service_url: String,
+    pub(crate) client: reqwest::Client,
+    /// The token to use for authorization. Redacted from `Debug` and zeroized on drop.
+    pub(crate) token: Option<TokenSource>,
+}
+
+impl std::fmt::Debug for AsyncGeometryServiceClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncGeometryServiceClient")
+            .field("service_url", &self.service_url)
+            .field("token", &self.token.as_ref().map(|_| "[REDACTED]"))
+            .finish_non_exhaustive()
+    }
+}
+
+impl AsyncGeometryServiceClient {
+    /// Create a new async client for the Geometry Service at `service_url`
+    pub fn new(service_url: &str) -> Self {
+        Self {
+            service_url: service_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+            token: None,
+        }
+    }
+
+    /// Set the token used to authorize requests
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Set the provider asked for the token before every request
+    ///
+    /// The provider is called on a blocking thread of the tokio runtime.
+    pub fn with_token_provider<P: TokenProvider>(mut self, provider: P) -> Self {
+        self.token = Some(TokenSource::new(provider));
+        self
+    }
+
+    /// Set the token used to authorize requests from `credentials`
+    pub fn with_credentials<C: CredentialProvider>(
+        mut self,
+        credentials: &C,
+    ) -> Result<Self, CredentialError> {
+        self.token = Some(credentials.token()?.into());
+        Ok(self)
+    }
+
+    /// Reproject geometries with the [`/project`](https://developers.arcgis.com/rest/services-reference/enterprise/project/) endpoint
+    pub async fn project<const N: usize>(
+        &self,
+        geometries: &[EsriGeometry<N>],
+        params: &ProjectParams,
+    ) -> Result<Vec<EsriGeometry<N>>, GeometryServiceError> {
+        if geometries.is_empty() {
+            return Ok(vec![]);
+        }
+        let resp: GeometriesResponse<_> = self.send("project", &params.form(geometries)).await?;
+        Ok(resp.geometries)
+    }
+
+    /// Buffer geometries with the [`/buffer`](https://developers.arcgis.com/rest/services-reference/enterprise/buffer/) endpoint
+    pub async fn buffer<const N: usize>(
+        &self,
+        geometries: &[EsriGeometry<N>],
+        params: &BufferParams,
+    ) -> Result<Vec<EsriPolygon<N>>, GeometryServiceError> {
+        if geometries.is_empty() {
+            return Ok(vec![]);
+        }
+        let resp: GeometriesResponse<_> = self.send("buffer", &params.form(geometries)).await?;
+        Ok(resp.geometries)
+    }
+
+    /// Make geometries topologically consistent with the [`/simplify`](https://developers.arcgis.com/rest/services-reference/enterprise/simplify/) endpoint
+    ///
+    /// `sr` is the WKID of the geometries.
+    pub async fn simplify<const N: usize>(
+        &self,
+        geometries: &[EsriGeometry<N>],
+        sr: u32,
+    ) -> Result<Vec<EsriGeometry<N>>, GeometryServiceError> {
+        if geometries.is_empty() {
+            return Ok(vec![]);
+        }
+        let form = simplify_form(geometries, sr);
+        let resp: GeometriesResponse<_> = self.send("simplify", &form).await?;
+        Ok(resp.geometries)
+    }
+
+    /// Measure polygons with the [`/areasAndLengths`](https://developers.arcgis.com/rest/services-reference/enterprise/areas-and-lengths/) endpoint
+    pub async fn areas_and_lengths<const N: usize>(
+        &self,
+        polygons: &[EsriPolygon<N>],
+        params: &AreasAndLengthsParams,
+    ) -> Result<AreasAndLengths, GeometryServiceError> {
+        self.send("areasAndLengths", &params.form(polygons)).await
+    }
+
+    // authorizes the request, sends it, and handles error responses
+    async fn send<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        form: &Form,
+    ) -> Result<T, GeometryServiceError> {
+        let request = self
+            .client
+            .post(format!("{}/{endpoint}", self.service_url))
+            .form(form);
+
+        let request = match &self.token {
+            Some(token) => {
+                let bearer = token
+                    .bearer_async()
+                    .await
+                    .map_err(GeometryServiceError::CredentialError)?;
+                request.header("X-Esri-Authorization", bearer)
+            }
+            None => request,
+        };
+
+        let resp = request
+            .send()
+            .await
+            .map_err(GeometryServiceError::RequestError)?;
+        let status = resp.status().as_u16();
+        let body = resp
+            .bytes()
+            .await
+            .map_err(GeometryServiceError::RequestError)?;
+
+        decode_response(status, &body)
+    }
+}
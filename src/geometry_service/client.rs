@@ -0,0 +1,290 @@
+use crate::{
+    auth::{CredentialError, CredentialProvider, TokenProvider, TokenSource},
+    error::{snippet, ClassifyError, ErrorKind},
+    feature_service::{ErrorResponse, ExpectedResponse},
+    geometry::{EsriGeometry, EsriPolygon},
+    geometry_service::{
+        simplify_form, AreasAndLengths, AreasAndLengthsParams, BufferParams, Form,
+        GeometriesResponse, ProjectParams,
+    },
+    http::{HttpClient, HttpError, HttpRequest},
+    trace,
+};
+use serde::de::DeserializeOwned;
+
+/// The URL of the public Geometry Service hosted by Esri
+pub const GEOMETRY_SERVICE_URL: &str =
+    "https://utility.arcgisonline.com/arcgis/rest/services/Geometry/GeometryServer";
+
+#[derive(Debug)]
+pub enum GeometryServiceError {
+    /// The request of an `AsyncGeometryServiceClient` failed
+    RequestError(reqwest::Error),
+    /// The request of a [`GeometryServiceClient`] failed
+    HttpError(HttpError),
+    ApiError(ErrorResponse),
+    /// The token provider failed to supply a token
+    CredentialError(CredentialError),
+    /// The response is not the expected JSON
+    DecodeError {
+        source: serde_json::Error,
+        /// The start of the response body
+        body: String,
+    },
+    /// The response has an error status and no error details
+    HttpStatus {
+        status: u16,
+        /// The start of the response body
+        body: String,
+    },
+}
+
+impl std::fmt::Display for GeometryServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RequestError(e) => write!(f, "request failed: {e}"),
+            Self::HttpError(e) => write!(f, "{e}"),
+            Self::ApiError(e) => write!(f, "{} ({})", e.error.message, e.error.code),
+            Self::CredentialError(e) => write!(f, "failed to get token: {e}"),
+            Self::DecodeError { source, body } => {
+                write!(f, "failed to decode response: {source}: {body}")
+            }
+            Self::HttpStatus { status, body } => write!(f, "HTTP status {status}: {body}"),
+        }
+    }
+}
+
+impl std::error::Error for GeometryServiceError {}
+
+impl ClassifyError for GeometryServiceError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::RequestError(e) => ErrorKind::from_request_error(e),
+            Self::HttpError(e) => e.kind(),
+            Self::ApiError(e) => e.kind(),
+            Self::CredentialError(e) => e.kind(),
+            Self::DecodeError { .. } => ErrorKind::Fatal,
+            Self::HttpStatus { status, .. } => ErrorKind::from_code((*status).into()),
+        }
+    }
+}
+
+// the error of a response, or its body decoded as `T`
+pub(crate) fn decode_response<T: DeserializeOwned>(
+    status: u16,
+    body: &[u8],
+) -> Result<T, GeometryServiceError> {
+    let resp = serde_json::from_slice::<ExpectedResponse<T>>(body);
+    match resp {
+        Ok(ExpectedResponse::Error(e)) => Err(GeometryServiceError::ApiError(e)),
+        _ if status >= 400 => Err(GeometryServiceError::HttpStatus {
+            status,
+            body: snippet(body),
+        }),
+        Ok(ExpectedResponse::Ok(x)) => Ok(x),
+        Err(source) => Err(GeometryServiceError::DecodeError {
+            source,
+            body: snippet(body),
+        }),
+    }
+}
+
+/// A client for a Geometry Service
+///
+/// ```no_run
+/// use serde_esri::geometry::{EsriGeometry, EsriPoint};
+/// use serde_esri::geometry_service::{GeometryServiceClient, ProjectParamsBuilder, GEOMETRY_SERVICE_URL};
+///
+/// let client = GeometryServiceClient::new(GEOMETRY_SERVICE_URL);
+/// let point = EsriGeometry::<2>::Point(EsriPoint { x: -117.19, y: 34.05, ..Default::default() });
+/// let params = ProjectParamsBuilder::default().in_sr(4326).out_sr(3857).build().unwrap();
+///
+/// let projected = client.project(&[point], &params).unwrap();
+/// ```
+///
+/// Requests are sent with a `reqwest::blocking::Client` unless another [`HttpClient`]
+/// is set with [`GeometryServiceClient::with_http_client()`].
+#[derive(Clone)]
+pub struct GeometryServiceClient<H = reqwest::blocking::Client> {
+    /// The URL of the service, e.g. `.../GeometryServer`
+    pub service_url: String,
+    pub(crate) client: H,
+    /// The token to use for authorization. Redacted from `Debug` and zeroized on drop.
+    pub(crate) token: Option<TokenSource>,
+}
+
+impl<H> std::fmt::Debug for GeometryServiceClient<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GeometryServiceClient")
+            .field("service_url", &self.service_url)
+            .field("token", &self.token.as_ref().map(|_| "[REDACTED]"))
+            .finish_non_exhaustive()
+    }
+}
+
+impl GeometryServiceClient {
+    /// Create a new client for the Geometry Service at `service_url`
+    pub fn new(service_url: &str) -> Self {
+        Self {
+            service_url: service_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: None,
+        }
+    }
+}
+
+impl<H: HttpClient> GeometryServiceClient<H> {
+    /// Send requests with `client` instead
+    pub fn with_http_client<C: HttpClient>(self, client: C) -> GeometryServiceClient<C> {
+        GeometryServiceClient {
+            service_url: self.service_url,
+            client,
+            token: self.token,
+        }
+    }
+
+    /// Set the token used to authorize requests
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Set the provider asked for the token before every request
+    pub fn with_token_provider<P: TokenProvider>(mut self, provider: P) -> Self {
+        self.token = Some(TokenSource::new(provider));
+        self
+    }
+
+    /// Set the token used to authorize requests from `credentials`
+    pub fn with_credentials<C: CredentialProvider>(
+        mut self,
+        credentials: &C,
+    ) -> Result<Self, CredentialError> {
+        self.token = Some(credentials.token()?.into());
+        Ok(self)
+    }
+
+    /// Reproject geometries with the [`/project`](https://developers.arcgis.com/rest/services-reference/enterprise/project/) endpoint
+    pub fn project<const N: usize>(
+        &self,
+        geometries: &[EsriGeometry<N>],
+        params: &ProjectParams,
+    ) -> Result<Vec<EsriGeometry<N>>, GeometryServiceError> {
+        if geometries.is_empty() {
+            return Ok(vec![]);
+        }
+        let resp: GeometriesResponse<_> = self.send("project", &params.form(geometries))?;
+        Ok(resp.geometries)
+    }
+
+    /// Buffer geometries with the [`/buffer`](https://developers.arcgis.com/rest/services-reference/enterprise/buffer/) endpoint
+    pub fn buffer<const N: usize>(
+        &self,
+        geometries: &[EsriGeometry<N>],
+        params: &BufferParams,
+    ) -> Result<Vec<EsriPolygon<N>>, GeometryServiceError> {
+        if geometries.is_empty() {
+            return Ok(vec![]);
+        }
+        let resp: GeometriesResponse<_> = self.send("buffer", &params.form(geometries))?;
+        Ok(resp.geometries)
+    }
+
+    /// Make geometries topologically consistent with the [`/simplify`](https://developers.arcgis.com/rest/services-reference/enterprise/simplify/) endpoint
+    ///
+    /// `sr` is the WKID of the geometries.
+    pub fn simplify<const N: usize>(
+        &self,
+        geometries: &[EsriGeometry<N>],
+        sr: u32,
+    ) -> Result<Vec<EsriGeometry<N>>, GeometryServiceError> {
+        if geometries.is_empty() {
+            return Ok(vec![]);
+        }
+        let resp: GeometriesResponse<_> = self.send("simplify", &simplify_form(geometries, sr))?;
+        Ok(resp.geometries)
+    }
+
+    /// Measure polygons with the [`/areasAndLengths`](https://developers.arcgis.com/rest/services-reference/enterprise/areas-and-lengths/) endpoint
+    pub fn areas_and_lengths<const N: usize>(
+        &self,
+        polygons: &[EsriPolygon<N>],
+        params: &AreasAndLengthsParams,
+    ) -> Result<AreasAndLengths, GeometryServiceError> {
+        self.send("areasAndLengths", &params.form(polygons))
+    }
+
+    // authorizes the request, sends it, and handles error responses
+    fn send<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        form: &Form,
+    ) -> Result<T, GeometryServiceError> {
+        let request = HttpRequest::post(&format!("{}/{endpoint}", self.service_url)).form(form);
+
+        let request = match &self.token {
+            Some(token) => {
+                let bearer = token
+                    .bearer()
+                    .map_err(GeometryServiceError::CredentialError)?;
+                request.header("X-Esri-Authorization", &bearer)
+            }
+            None => request,
+        };
+
+        let resp = trace::send(&self.client, request).map_err(GeometryServiceError::HttpError)?;
+
+        decode_response(resp.status, &resp.body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpResponse;
+
+    // checks the form of a `/simplify` request and responds with no geometries
+    struct Simplify;
+
+    impl HttpClient for Simplify {
+        fn execute(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+            assert_eq!(request.url, "https://example.com/GeometryServer/simplify");
+            let body = String::from_utf8(request.body.unwrap()).unwrap();
+            assert!(body.contains("sr=4326"), "{body}");
+            assert!(body.contains("geometries=%7B"), "{body}");
+
+            let body = br#"{"geometryType": "esriGeometryPoint", "geometries": []}"#.to_vec();
+            Ok(HttpResponse {
+                status: 200,
+                headers: vec![],
+                body,
+            })
+        }
+    }
+
+    #[test]
+    fn test_http_client() {
+        let client = GeometryServiceClient::new("https://example.com/GeometryServer/")
+            .with_http_client(Simplify);
+        let point = EsriGeometry::<2>::Point(Default::default());
+
+        let simplified = client.simplify(&[point], 4326).unwrap();
+        assert!(simplified.is_empty());
+    }
+
+    #[test]
+    fn test_decode_response() {
+        let error = br#"{"error": {"code": 400, "message": "Invalid geometry"}}"#;
+        let res = decode_response::<AreasAndLengths>(200, error);
+        assert!(matches!(res, Err(GeometryServiceError::ApiError(e)) if e.error.code == 400));
+
+        let res = decode_response::<AreasAndLengths>(502, b"Bad Gateway");
+        assert!(matches!(
+            res,
+            Err(GeometryServiceError::HttpStatus { status: 502, body }) if body == "Bad Gateway"
+        ));
+
+        let res = decode_response::<AreasAndLengths>(200, b"<html>");
+        assert!(matches!(res, Err(GeometryServiceError::DecodeError { .. })));
+    }
+}
@@ -0,0 +1,23 @@
+//! Types for the [Geometry Service](https://developers.arcgis.com/rest/services-reference/enterprise/geometry-service/) of an ArcGIS server.
+//!
+//! - [`ProjectParamsBuilder`] creates a [`ProjectParams`] struct used to reproject geometries with the [`/project`](https://developers.arcgis.com/rest/services-reference/enterprise/project/) endpoint.
+//! - [`BufferParamsBuilder`] creates a [`BufferParams`] struct used to buffer geometries with the [`/buffer`](https://developers.arcgis.com/rest/services-reference/enterprise/buffer/) endpoint.
+//! - [`AreasAndLengthsParamsBuilder`] creates an [`AreasAndLengthsParams`] struct used to measure polygons with the [`/areasAndLengths`](https://developers.arcgis.com/rest/services-reference/enterprise/areas-and-lengths/) endpoint, which responds with [`AreasAndLengths`].
+//!
+//! Activate the `"geometry-service-client"` feature to enable the `GeometryServiceClient` struct which sends these requests,
+//! as well as requests to the [`/simplify`](https://developers.arcgis.com/rest/services-reference/enterprise/simplify/) endpoint.
+//! The `"geometry-service-client-async"` feature adds `AsyncGeometryServiceClient` for async runtimes.
+//!
+//! Each request sends geometries of a single type, e.g. only polygons.
+mod params;
+pub use params::*;
+
+#[cfg(feature = "geometry-service-client-async")]
+mod async_client;
+#[cfg(feature = "geometry-service-client")]
+mod client;
+
+#[cfg(feature = "geometry-service-client-async")]
+pub use async_client::*;
+#[cfg(feature = "geometry-service-client")]
+pub use client::*;
@@ -0,0 +1,221 @@
+#[cfg(feature = "geometry-service-client")]
+use crate::geometry::{EsriGeometry, EsriPolygon};
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+
+/// The parameters of the [`/project`](https://developers.arcgis.com/rest/services-reference/enterprise/project/) endpoint
+#[derive(Debug, Clone, Default, Builder)]
+pub struct ProjectParams {
+    /// The WKID of the input geometries
+    pub in_sr: u32,
+    /// The WKID to project the geometries to
+    pub out_sr: u32,
+    /// The WKID of the datum transformation
+    #[builder(setter(strip_option), default)]
+    pub transformation: Option<u32>,
+    /// Whether `transformation` is applied in its forward direction
+    #[builder(setter(strip_option), default)]
+    pub transform_forward: Option<bool>,
+}
+
+/// The parameters of the [`/buffer`](https://developers.arcgis.com/rest/services-reference/enterprise/buffer/) endpoint
+#[derive(Debug, Clone, Default, Builder)]
+pub struct BufferParams {
+    /// The WKID of the input geometries
+    pub in_sr: u32,
+    /// The WKID of the buffers. Defaults to `in_sr`.
+    #[builder(setter(strip_option), default)]
+    pub out_sr: Option<u32>,
+    /// The WKID in which the buffers are computed. Defaults to `in_sr`.
+    #[builder(setter(strip_option), default)]
+    pub buffer_sr: Option<u32>,
+    /// A buffer is created for each distance around each geometry
+    pub distances: Vec<f64>,
+    /// The WKID of the unit of `distances`, e.g. `9001` for meters. Defaults to the unit of `buffer_sr`.
+    #[builder(setter(strip_option), default)]
+    pub unit: Option<u32>,
+    /// Whether the buffers of each distance are unioned into a single polygon
+    #[builder(setter(strip_option), default)]
+    pub union_results: Option<bool>,
+    /// Whether the buffers are geodesic
+    #[builder(setter(strip_option), default)]
+    pub geodesic: Option<bool>,
+}
+
+/// How the areas and lengths are calculated by the [`/areasAndLengths`](https://developers.arcgis.com/rest/services-reference/enterprise/areas-and-lengths/) endpoint
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CalculationType {
+    Planar,
+    Geodesic,
+    PreserveShape,
+}
+
+impl CalculationType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Planar => "planar",
+            Self::Geodesic => "geodesic",
+            Self::PreserveShape => "preserveShape",
+        }
+    }
+}
+
+/// The parameters of the [`/areasAndLengths`](https://developers.arcgis.com/rest/services-reference/enterprise/areas-and-lengths/) endpoint
+#[derive(Debug, Clone, Default, Builder)]
+pub struct AreasAndLengthsParams {
+    /// The WKID of the polygons
+    pub sr: u32,
+    /// The WKID of the unit of the lengths, e.g. `9001` for meters
+    #[builder(setter(strip_option), default)]
+    pub length_unit: Option<u32>,
+    /// The unit of the areas, e.g. `esriSquareMeters` or `esriAcres`
+    #[builder(setter(into, strip_option), default)]
+    pub area_unit: Option<String>,
+    #[builder(setter(strip_option), default)]
+    pub calculation_type: Option<CalculationType>,
+}
+
+/// The response from the [`/areasAndLengths`](https://developers.arcgis.com/rest/services-reference/enterprise/areas-and-lengths/) endpoint
+///
+/// Contains the area and perimeter of each polygon in the order they were sent.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AreasAndLengths {
+    pub areas: Vec<f64>,
+    pub lengths: Vec<f64>,
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+// the response of the endpoints returning geometries
+#[cfg(feature = "geometry-service-client")]
+#[derive(Debug, Deserialize)]
+pub(crate) struct GeometriesResponse<G> {
+    pub(crate) geometries: Vec<G>,
+}
+
+// the form fields of each request
+
+#[cfg(feature = "geometry-service-client")]
+pub(crate) type Form = Vec<(&'static str, String)>;
+
+// geometries of a single type as `{"geometryType": ..., "geometries": [...]}`
+#[cfg(feature = "geometry-service-client")]
+fn geometries_json<const N: usize>(geometries: &[EsriGeometry<N>]) -> String {
+    let geometry_type = geometries.first().map(EsriGeometry::geometry_type);
+    serde_json::json!({
+        "geometryType": geometry_type,
+        "geometries": geometries,
+    })
+    .to_string()
+}
+
+#[cfg(feature = "geometry-service-client")]
+impl ProjectParams {
+    pub(crate) fn form<const N: usize>(&self, geometries: &[EsriGeometry<N>]) -> Form {
+        let mut form = vec![
+            ("geometries", geometries_json(geometries)),
+            ("inSR", self.in_sr.to_string()),
+            ("outSR", self.out_sr.to_string()),
+            ("f", "json".into()),
+        ];
+        if let Some(x) = self.transformation {
+            form.push(("transformation", x.to_string()));
+        }
+        if let Some(x) = self.transform_forward {
+            form.push(("transformForward", x.to_string()));
+        }
+        form
+    }
+}
+
+#[cfg(feature = "geometry-service-client")]
+impl BufferParams {
+    pub(crate) fn form<const N: usize>(&self, geometries: &[EsriGeometry<N>]) -> Form {
+        let distances = self
+            .distances
+            .iter()
+            .map(f64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut form = vec![
+            ("geometries", geometries_json(geometries)),
+            ("inSR", self.in_sr.to_string()),
+            ("distances", distances),
+            ("f", "json".into()),
+        ];
+        let optional = [
+            ("outSR", self.out_sr.map(|x| x.to_string())),
+            ("bufferSR", self.buffer_sr.map(|x| x.to_string())),
+            ("unit", self.unit.map(|x| x.to_string())),
+            ("unionResults", self.union_results.map(|x| x.to_string())),
+            ("geodesic", self.geodesic.map(|x| x.to_string())),
+        ];
+        form.extend(optional.into_iter().filter_map(|(k, v)| Some((k, v?))));
+        form
+    }
+}
+
+#[cfg(feature = "geometry-service-client")]
+pub(crate) fn simplify_form<const N: usize>(geometries: &[EsriGeometry<N>], sr: u32) -> Form {
+    vec![
+        ("geometries", geometries_json(geometries)),
+        ("sr", sr.to_string()),
+        ("f", "json".into()),
+    ]
+}
+
+#[cfg(feature = "geometry-service-client")]
+impl AreasAndLengthsParams {
+    pub(crate) fn form<const N: usize>(&self, polygons: &[EsriPolygon<N>]) -> Form {
+        let mut form = vec![
+            (
+                "polygons",
+                serde_json::to_string(polygons).unwrap_or_default(),
+            ),
+            ("sr", self.sr.to_string()),
+            ("f", "json".into()),
+        ];
+        if let Some(x) = self.length_unit {
+            form.push(("lengthUnit", x.to_string()));
+        }
+        if let Some(x) = &self.area_unit {
+            form.push(("areaUnit", serde_json::json!({ "areaUnit": x }).to_string()));
+        }
+        if let Some(x) = self.calculation_type {
+            form.push(("calculationType", x.as_str().to_string()));
+        }
+        form
+    }
+}
+
+#[cfg(all(test, feature = "geometry-service-client"))]
+mod tests {
+    use super::*;
+    use crate::geometry::EsriPoint;
+
+    #[test]
+    fn test_project_form() {
+        let point = EsriGeometry::<2>::Point(EsriPoint {
+            x: -117.19,
+            y: 34.05,
+            ..Default::default()
+        });
+        let params = ProjectParamsBuilder::default()
+            .in_sr(4326)
+            .out_sr(3857)
+            .build()
+            .unwrap();
+
+        let form = params.form(&[point]);
+        assert_eq!(form[0].0, "geometries");
+        assert_eq!(
+            form[0].1,
+            r#"{"geometries":[{"x":-117.19,"y":34.05}],"geometryType":"esriGeometryPoint"}"#
+        );
+        assert_eq!(form.len(), 4);
+    }
+}
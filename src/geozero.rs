@@ -0,0 +1,530 @@
+//! Optional feature for reading and writing Esri geometries with [`geozero`](https://docs.rs/geozero).
+//!
+//! [`EsriGeometry<N>`] implements [`GeozeroGeometry`], so it can be processed into any
+//! format with a geozero writer, e.g. FlatGeobuf, GeoPackage, MVT, or SVG.
+//! [`EsriWriter`] is a [`GeomProcessor`] that builds an Esri geometry from the
+//! geometry of any geozero reader, and [`ToEsri`] converts a [`GeozeroGeometry`] with it.
+//!
+//! The const `N` determines the dimensions that are processed: `2` is XY, `3` is XYZ,
+//! and `4` is XYZM. As with the `wkb` feature, polylines are processed as
+//! MultiLineStrings and polygons as MultiPolygons. Envelopes are processed as 2D Polygons.
+//! True curves are densified. Multipatches are processed as MultiPolygons with a polygon
+//! for each face.
+//!
+//! Rings are rewound when building an `EsriPolygon` so that exterior rings
+//! are clockwise and interior rings are counter-clockwise as required by Esri.
+//!
+//! ```
+//! use serde_esri::{geometry::EsriGeometry, geozero::ToEsri};
+//!
+//! let json = r#"{"hasZ": true, "paths": [[[0, 0, 10], [1, 1, 20]]]}"#;
+//! let geometry: EsriGeometry<3> = serde_json::from_str(json).unwrap();
+//!
+//! let polyline = geometry.to_esri::<3>().unwrap().as_polyline().unwrap();
+//! assert_eq!(polyline.paths[0].0[1].0, [1.0, 1.0, 20.0]);
+//! ```
+use crate::{
+    geometry::*,
+    rings::{classify_rings, is_clockwise},
+    spatial_reference::SpatialReference,
+};
+use geozero::{
+    error::{GeozeroError, Result},
+    CoordDimensions, GeomProcessor, GeozeroGeometry,
+};
+
+fn dimensions<const N: usize>() -> CoordDimensions {
+    match dimension_flags::<N>() {
+        (true, true) => CoordDimensions::xyzm(),
+        (true, false) => CoordDimensions::xyz(),
+        (false, true) => CoordDimensions::xym(),
+        (false, false) => CoordDimensions::xy(),
+    }
+}
+
+fn process_coord<const N: usize, T: Copy + Into<f64>, P: GeomProcessor>(
+    processor: &mut P,
+    x: &EsriCoord<N, T>,
+    idx: usize,
+) -> Result<()> {
+    let (has_z, has_m) = dimension_flags::<N>();
+    let v = |i: usize| x.0[i].into();
+    if processor.multi_dim() {
+        let (z, m) = (has_z.then(|| v(2)), has_m.then(|| v(N - 1)));
+        processor.coordinate(v(0), v(1), z, m, None, None, idx)
+    } else {
+        processor.xy(v(0), v(1), idx)
+    }
+}
+
+fn process_coords<const N: usize, T: Copy + Into<f64>, P: GeomProcessor>(
+    processor: &mut P,
+    x: &EsriLineString<N, T>,
+    idx: usize,
+) -> Result<()> {
+    processor.linestring_begin(false, x.0.len(), idx)?;
+    for (i, coord) in x.iter().enumerate() {
+        process_coord(processor, coord, i)?;
+    }
+    processor.linestring_end(false, idx)
+}
+
+fn process_point<const N: usize, T: Copy + Into<f64>, P: GeomProcessor>(
+    processor: &mut P,
+    x: &EsriPoint<T>,
+) -> Result<()> {
+    let (has_z, has_m) = dimension_flags::<N>();
+    let (px, py) = (x.x.into(), x.y.into());
+    processor.point_begin(0)?;
+    if processor.multi_dim() {
+        let z = has_z.then(|| x.z.map_or(f64::NAN, Into::into));
+        let m = has_m.then(|| x.m.map_or(f64::NAN, Into::into));
+        processor.coordinate(px, py, z, m, None, None, 0)?;
+    } else {
+        processor.xy(px, py, 0)?;
+    }
+    processor.point_end(0)
+}
+
+fn process_multipoint<const N: usize, T: Copy + Into<f64>, P: GeomProcessor>(
+    processor: &mut P,
+    x: &EsriMultiPoint<N, T>,
+) -> Result<()> {
+    processor.multipoint_begin(x.points.len(), 0)?;
+    for (i, coord) in x.iter().enumerate() {
+        process_coord(processor, coord, i)?;
+    }
+    processor.multipoint_end(0)
+}
+
+fn process_polyline<const N: usize, T: Copy + Into<f64>, P: GeomProcessor>(
+    processor: &mut P,
+    x: &EsriPolyline<N, T>,
+) -> Result<()> {
+    processor.multilinestring_begin(x.paths.len(), 0)?;
+    for (i, path) in x.paths.iter().enumerate() {
+        process_coords(processor, path, i)?;
+    }
+    processor.multilinestring_end(0)
+}
+
+// processes each polygon, a list of rings with the exterior ring first, as a member of
+// a MultiPolygon
+fn process_polygons<const N: usize, T: Copy + Into<f64>, P: GeomProcessor>(
+    processor: &mut P,
+    polygons: &[Vec<&EsriLineString<N, T>>],
+) -> Result<()> {
+    processor.multipolygon_begin(polygons.len(), 0)?;
+    for (i, rings) in polygons.iter().enumerate() {
+        processor.polygon_begin(false, rings.len(), i)?;
+        for (j, ring) in rings.iter().enumerate() {
+            process_coords(processor, ring, j)?;
+        }
+        processor.polygon_end(false, i)?;
+    }
+    processor.multipolygon_end(0)
+}
+
+fn process_polygon<const N: usize, T: Copy + Into<f64>, P: GeomProcessor>(
+    processor: &mut P,
+    x: &EsriPolygon<N, T>,
+) -> Result<()> {
+    let polygons: Vec<Vec<_>> = classify_rings(&x.rings)
+        .iter()
+        .map(|rings| rings.iter().map(|i| &x.rings[*i]).collect())
+        .collect();
+    process_polygons(processor, &polygons)
+}
+
+fn process_multipatch<const N: usize, T: Copy + Into<f64>, P: GeomProcessor>(
+    processor: &mut P,
+    x: &EsriMultipatch<N, T>,
+) -> Result<()> {
+    let faces = x.faces();
+    let polygons: Vec<Vec<_>> = faces.iter().map(|rings| rings.iter().collect()).collect();
+    process_polygons(processor, &polygons)
+}
+
+fn process_envelope<T: Copy + Into<f64>, P: GeomProcessor>(
+    processor: &mut P,
+    x: &EsriEnvelope<T>,
+) -> Result<()> {
+    let (xmin, ymin) = (x.xmin.into(), x.ymin.into());
+    let (xmax, ymax) = (x.xmax.into(), x.ymax.into());
+    processor.polygon_begin(true, 1, 0)?;
+    processor.linestring_begin(false, 5, 0)?;
+    for (i, (x, y)) in [
+        (xmin, ymin),
+        (xmin, ymax),
+        (xmax, ymax),
+        (xmax, ymin),
+        (xmin, ymin),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        if processor.multi_dim() {
+            processor.coordinate(x, y, None, None, None, None, i)?;
+        } else {
+            processor.xy(x, y, i)?;
+        }
+    }
+    processor.linestring_end(false, 0)?;
+    processor.polygon_end(true, 0)
+}
+
+impl<const N: usize, T: Copy + Into<f64>> GeozeroGeometry for EsriGeometry<N, T> {
+    fn process_geom<P: GeomProcessor>(&self, processor: &mut P) -> Result<()> {
+        processor.srid(GeozeroGeometry::srid(self))?;
+        match self {
+            EsriGeometry::Point(g) => process_point::<N, T, P>(processor, g),
+            EsriGeometry::MultiPoint(g) => process_multipoint(processor, g),
+            EsriGeometry::Polyline(g) => process_polyline(processor, g),
+            EsriGeometry::Polygon(g) => process_polygon(processor, g),
+            EsriGeometry::Envelope(g) => process_envelope(processor, g),
+            EsriGeometry::CurvePolyline(g) => process_polyline(processor, &g.linearize()),
+            EsriGeometry::CurvePolygon(g) => process_polygon(processor, &g.linearize()),
+            EsriGeometry::Multipatch(g) => process_multipatch(processor, g),
+        }
+    }
+
+    fn dims(&self) -> CoordDimensions {
+        dimensions::<N>()
+    }
+
+    /// The well-known ID of the spatial reference, if any
+    fn srid(&self) -> Option<i32> {
+        self.spatial_reference()?
+            .normalized_wkid()
+            .and_then(|wkid| wkid.try_into().ok())
+    }
+}
+
+/// A [`GeomProcessor`] that builds an [`EsriGeometry<N>`]
+///
+/// LineStrings and MultiLineStrings become an `EsriPolyline`, and Polygons and
+/// MultiPolygons become an `EsriPolygon`. The SRID, if any, becomes the spatial reference.
+///
+/// Every coordinate must have the dimensions of `N`. Empty points, geometry collections,
+/// curves, and surfaces other than polygons cannot be converted.
+#[derive(Debug, Default)]
+pub struct EsriWriter<const N: usize> {
+    geometry: Option<EsriGeometry<N>>,
+    spatial_reference: Option<SpatialReference>,
+    // the coordinates of the point, multipoint, or linestring being processed
+    coords: Option<Vec<EsriCoord<N>>>,
+    // the paths or rings of the polyline or polygon being processed
+    paths: Option<Vec<EsriLineString<N>>>,
+    // whether the linestrings being processed are rings
+    rings: bool,
+}
+
+impl<const N: usize> EsriWriter<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The geometry that was built, if any
+    pub fn take_geometry(&mut self) -> Option<EsriGeometry<N>> {
+        self.geometry.take()
+    }
+
+    fn push_coord(&mut self, coord: EsriCoord<N>) -> Result<()> {
+        self.coords
+            .as_mut()
+            .ok_or(GeozeroError::Geometry("not ready for coordinates".into()))?
+            .push(coord);
+        Ok(())
+    }
+
+    fn take_coords(&mut self) -> Result<Vec<EsriCoord<N>>> {
+        self.coords
+            .take()
+            .ok_or(GeozeroError::Geometry("no coordinates".into()))
+    }
+
+    fn take_paths(&mut self) -> Result<Vec<EsriLineString<N>>> {
+        self.paths
+            .take()
+            .ok_or(GeozeroError::Geometry("no paths or rings".into()))
+    }
+
+    fn finish(&mut self, geometry: EsriGeometry<N>) -> Result<()> {
+        match self.geometry {
+            Some(_) => Err(GeozeroError::Geometry(
+                "geometry collections cannot be converted".into(),
+            )),
+            None => {
+                self.geometry = Some(geometry);
+                Ok(())
+            }
+        }
+    }
+
+    fn finish_polyline(&mut self) -> Result<()> {
+        let (has_z, has_m) = dimension_flags::<N>();
+        let paths = self.take_paths()?;
+        self.finish(EsriGeometry::Polyline(EsriPolyline {
+            hasZ: has_z.then_some(true),
+            hasM: has_m.then_some(true),
+            paths,
+            spatialReference: self.spatial_reference.clone(),
+        }))
+    }
+
+    fn finish_polygon(&mut self) -> Result<()> {
+        let (has_z, has_m) = dimension_flags::<N>();
+        let rings = self.take_paths()?;
+        self.finish(EsriGeometry::Polygon(EsriPolygon {
+            hasZ: has_z.then_some(true),
+            hasM: has_m.then_some(true),
+            rings,
+            spatialReference: self.spatial_reference.clone(),
+        }))
+    }
+
+    fn unsupported(geometry_type: &str) -> Result<()> {
+        Err(GeozeroError::Geometry(format!(
+            "{geometry_type} cannot be converted"
+        )))
+    }
+}
+
+impl<const N: usize> GeomProcessor for EsriWriter<N> {
+    fn dimensions(&self) -> CoordDimensions {
+        dimensions::<N>()
+    }
+
+    fn multi_dim(&self) -> bool {
+        N > 2
+    }
+
+    fn srid(&mut self, srid: Option<i32>) -> Result<()> {
+        self.spatial_reference = srid
+            .and_then(|srid| srid.try_into().ok())
+            .map(SpatialReference::from_wkid);
+        Ok(())
+    }
+
+    // only called when the processor is 2D
+    fn xy(&mut self, x: f64, y: f64, idx: usize) -> Result<()> {
+        self.coordinate(x, y, None, None, None, None, idx)
+    }
+
+    fn coordinate(
+        &mut self,
+        x: f64,
+        y: f64,
+        z: Option<f64>,
+        m: Option<f64>,
+        _t: Option<f64>,
+        _tm: Option<u64>,
+        _idx: usize,
+    ) -> Result<()> {
+        let (has_z, has_m) = dimension_flags::<N>();
+        let mut coord = [0.0; N];
+        coord[0] = x;
+        coord[1] = y;
+        if has_z {
+            coord[2] = z.ok_or(GeozeroError::Coord)?;
+        }
+        if has_m {
+            coord[N - 1] = m.ok_or(GeozeroError::Coord)?;
+        }
+        self.push_coord(EsriCoord(coord))
+    }
+
+    fn empty_point(&mut self, _idx: usize) -> Result<()> {
+        Self::unsupported("empty points")
+    }
+
+    fn point_begin(&mut self, _idx: usize) -> Result<()> {
+        self.coords = Some(Vec::with_capacity(1));
+        Ok(())
+    }
+
+    fn point_end(&mut self, _idx: usize) -> Result<()> {
+        let (has_z, has_m) = dimension_flags::<N>();
+        let coords = self.take_coords()?;
+        let [coord] = coords.as_slice() else {
+            return Err(GeozeroError::Geometry("a point has one coordinate".into()));
+        };
+        self.finish(EsriGeometry::Point(EsriPoint {
+            x: coord.0[0],
+            y: coord.0[1],
+            z: has_z.then(|| coord.0[2]),
+            m: has_m.then(|| coord.0[N - 1]),
+            spatialReference: self.spatial_reference.clone(),
+        }))
+    }
+
+    fn multipoint_begin(&mut self, size: usize, _idx: usize) -> Result<()> {
+        self.coords = Some(Vec::with_capacity(size));
+        Ok(())
+    }
+
+    fn multipoint_end(&mut self, _idx: usize) -> Result<()> {
+        let (has_z, has_m) = dimension_flags::<N>();
+        let points = self.take_coords()?;
+        self.finish(EsriGeometry::MultiPoint(EsriMultiPoint {
+            hasZ: has_z.then_some(true),
+            hasM: has_m.then_some(true),
+            points,
+            spatialReference: self.spatial_reference.clone(),
+        }))
+    }
+
+    fn linestring_begin(&mut self, tagged: bool, size: usize, _idx: usize) -> Result<()> {
+        if tagged {
+            self.paths = Some(Vec::with_capacity(1));
+        }
+        self.coords = Some(Vec::with_capacity(size));
+        Ok(())
+    }
+
+    // exterior rings are wound clockwise and interior rings counter-clockwise
+    fn linestring_end(&mut self, tagged: bool, idx: usize) -> Result<()> {
+        let mut line = EsriLineString(self.take_coords()?);
+        let paths = self
+            .paths
+            .as_mut()
+            .ok_or(GeozeroError::Geometry("not ready for a linestring".into()))?;
+        if self.rings && is_clockwise(&line) != (idx == 0) {
+            line.0.reverse();
+        }
+        paths.push(line);
+
+        match tagged {
+            true => self.finish_polyline(),
+            false => Ok(()),
+        }
+    }
+
+    fn multilinestring_begin(&mut self, size: usize, _idx: usize) -> Result<()> {
+        self.paths = Some(Vec::with_capacity(size));
+        Ok(())
+    }
+
+    fn multilinestring_end(&mut self, _idx: usize) -> Result<()> {
+        self.finish_polyline()
+    }
+
+    fn polygon_begin(&mut self, tagged: bool, size: usize, _idx: usize) -> Result<()> {
+        if tagged {
+            self.paths = Some(Vec::with_capacity(size));
+        }
+        self.rings = true;
+        Ok(())
+    }
+
+    fn polygon_end(&mut self, tagged: bool, _idx: usize) -> Result<()> {
+        self.rings = false;
+        match tagged {
+            true => self.finish_polygon(),
+            false => Ok(()),
+        }
+    }
+
+    fn multipolygon_begin(&mut self, size: usize, _idx: usize) -> Result<()> {
+        self.paths = Some(Vec::with_capacity(size));
+        Ok(())
+    }
+
+    fn multipolygon_end(&mut self, _idx: usize) -> Result<()> {
+        self.finish_polygon()
+    }
+
+    fn geometrycollection_begin(&mut self, _size: usize, _idx: usize) -> Result<()> {
+        Self::unsupported("geometry collections")
+    }
+
+    fn circularstring_begin(&mut self, _size: usize, _idx: usize) -> Result<()> {
+        Self::unsupported("circular strings")
+    }
+
+    fn compoundcurve_begin(&mut self, _size: usize, _idx: usize) -> Result<()> {
+        Self::unsupported("compound curves")
+    }
+
+    fn curvepolygon_begin(&mut self, _size: usize, _idx: usize) -> Result<()> {
+        Self::unsupported("curve polygons")
+    }
+
+    fn multicurve_begin(&mut self, _size: usize, _idx: usize) -> Result<()> {
+        Self::unsupported("multi curves")
+    }
+
+    fn multisurface_begin(&mut self, _size: usize, _idx: usize) -> Result<()> {
+        Self::unsupported("multi surfaces")
+    }
+
+    fn triangle_begin(&mut self, _tagged: bool, _size: usize, _idx: usize) -> Result<()> {
+        Self::unsupported("triangles")
+    }
+
+    fn polyhedralsurface_begin(&mut self, _size: usize, _idx: usize) -> Result<()> {
+        Self::unsupported("polyhedral surfaces")
+    }
+
+    fn tin_begin(&mut self, _size: usize, _idx: usize) -> Result<()> {
+        Self::unsupported("TINs")
+    }
+}
+
+/// Convert a geozero geometry into an Esri geometry with an [`EsriWriter`]
+pub trait ToEsri {
+    fn to_esri<const N: usize>(&self) -> Result<EsriGeometry<N>>;
+}
+
+impl<G: GeozeroGeometry> ToEsri for G {
+    fn to_esri<const N: usize>(&self) -> Result<EsriGeometry<N>> {
+        let mut writer = EsriWriter::<N>::new();
+        self.process_geom(&mut writer)?;
+        writer
+            .take_geometry()
+            .ok_or(GeozeroError::Geometry("no geometry".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geozero_round_trip() {
+        let json = r#"{"hasZ": true, "rings": [
+            [[0, 0, 1], [0, 10, 1], [10, 10, 1], [10, 0, 1], [0, 0, 1]],
+            [[2, 2, 1], [4, 2, 1], [4, 4, 1], [2, 4, 1], [2, 2, 1]]
+        ], "spatialReference": {"wkid": 4326}}"#;
+        let geometry: EsriGeometry<3> = serde_json::from_str(json).unwrap();
+        assert_eq!(GeozeroGeometry::srid(&geometry), Some(4326));
+
+        let polygon = geometry.to_esri::<3>().unwrap().as_polygon().unwrap();
+        assert_eq!(polygon.rings.len(), 2);
+        assert_eq!(polygon.rings[1].0[1].0, [4.0, 2.0, 1.0]);
+        assert_eq!(polygon.spatialReference.unwrap().wkid, Some(4326));
+
+        // the geometry has no M values
+        assert!(matches!(geometry.to_esri::<4>(), Err(GeozeroError::Coord)));
+    }
+
+    #[test]
+    fn test_writer_rewinds_rings() {
+        // a counter-clockwise exterior ring
+        let mut writer = EsriWriter::<2>::new();
+        writer.polygon_begin(true, 1, 0).unwrap();
+        writer.linestring_begin(false, 4, 0).unwrap();
+        for (i, (x, y)) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (0.0, 0.0)]
+            .into_iter()
+            .enumerate()
+        {
+            writer.xy(x, y, i).unwrap();
+        }
+        writer.linestring_end(false, 0).unwrap();
+        writer.polygon_end(true, 0).unwrap();
+
+        let polygon = writer.take_geometry().unwrap().as_polygon().unwrap();
+        assert!(is_clockwise(&polygon.rings[0]));
+        assert!(writer.empty_point(0).is_err());
+    }
+}
@@ -0,0 +1,639 @@
+//! Optional feature for writing `FeatureSet`s to [GeoPackage](https://www.geopackage.org/) files.
+//!
+//! [`write_gpkg()`] creates a feature table (or an attribute table when there is
+//! no geometry type) from a `FeatureSet`. Esri field types are mapped to
+//! GeoPackage column types:
+//!
+//! - `SmallInteger`, `Integer`, `OID` -> `INTEGER`
+//! - `Single` -> `FLOAT`
+//! - `Double` -> `DOUBLE`
+//! - `String`, `GUID`, `GlobalID`, `XML` -> `TEXT`
+//! - `Date` -> `DATETIME` (epoch milliseconds are written as RFC 3339 strings)
+//! - `Blob` -> `BLOB`
+//!
+//! Polylines are written as `MULTILINESTRING`s and polygons as `MULTIPOLYGON`s.
+//! The spatial reference is registered using its (latest) WKID as an EPSG code.
+//!
+//! With the `feature-service-client` feature, [`export_layer_to_gpkg()`] downloads
+//! a feature layer and writes it to a GeoPackage in one call.
+use crate::{
+    features::{FeatureSet, Field},
+    field_type::FieldType,
+    geometry::*,
+    spatial_reference::SpatialReference,
+    wkb::write_geometry,
+};
+use chrono::{DateTime, SecondsFormat};
+use rusqlite::{params_from_iter, types::Value as SqlValue, Connection};
+use serde_json::Value;
+use std::path::Path;
+
+#[cfg(feature = "feature-service-client")]
+use crate::feature_service::{FeatureLayerClient, FeatureQueryParamsBuilder, FeatureServiceError};
+
+/// Errors that can occur when writing a GeoPackage
+#[derive(Debug)]
+pub enum GpkgError {
+    Sqlite(rusqlite::Error),
+    #[cfg(feature = "feature-service-client")]
+    FeatureService(FeatureServiceError),
+}
+
+impl std::fmt::Display for GpkgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sqlite(e) => write!(f, "{e}"),
+            #[cfg(feature = "feature-service-client")]
+            Self::FeatureService(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for GpkgError {}
+
+impl From<rusqlite::Error> for GpkgError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sqlite(value)
+    }
+}
+
+#[cfg(feature = "feature-service-client")]
+impl From<FeatureServiceError> for GpkgError {
+    fn from(value: FeatureServiceError) -> Self {
+        Self::FeatureService(value)
+    }
+}
+
+const GEOMETRY_COLUMN: &str = "geom";
+const DEFAULT_FID: &str = "fid";
+// srs_id used for spatial references that only have a WKT definition
+const CUSTOM_SRS_ID: i32 = 100000;
+
+const CREATE_METADATA_TABLES: &str = "
+CREATE TABLE IF NOT EXISTS gpkg_spatial_ref_sys (
+    srs_name TEXT NOT NULL,
+    srs_id INTEGER NOT NULL PRIMARY KEY,
+    organization TEXT NOT NULL,
+    organization_coordsys_id INTEGER NOT NULL,
+    definition TEXT NOT NULL,
+    description TEXT
+);
+CREATE TABLE IF NOT EXISTS gpkg_contents (
+    table_name TEXT NOT NULL PRIMARY KEY,
+    data_type TEXT NOT NULL,
+    identifier TEXT UNIQUE,
+    description TEXT DEFAULT '',
+    last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+    min_x DOUBLE,
+    min_y DOUBLE,
+    max_x DOUBLE,
+    max_y DOUBLE,
+    srs_id INTEGER,
+    CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+);
+CREATE TABLE IF NOT EXISTS gpkg_geometry_columns (
+    table_name TEXT NOT NULL,
+    column_name TEXT NOT NULL,
+    geometry_type_name TEXT NOT NULL,
+    srs_id INTEGER NOT NULL,
+    z TINYINT NOT NULL,
+    m TINYINT NOT NULL,
+    CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+    CONSTRAINT uk_gc_table_name UNIQUE (table_name),
+    CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+    CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+);
+INSERT OR IGNORE INTO gpkg_spatial_ref_sys VALUES
+    ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined', 'undefined cartesian coordinate reference system'),
+    ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', 'undefined geographic coordinate reference system'),
+    ('WGS 84 geodetic', 4326, 'EPSG', 4326, 'GEOGCS[\"WGS 84\",DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563,AUTHORITY[\"EPSG\",\"7030\"]],AUTHORITY[\"EPSG\",\"6326\"]],PRIMEM[\"Greenwich\",0,AUTHORITY[\"EPSG\",\"8901\"]],UNIT[\"degree\",0.0174532925199433,AUTHORITY[\"EPSG\",\"9122\"]],AUTHORITY[\"EPSG\",\"4326\"]]', 'longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid');
+";
+
+fn quote(x: &str) -> String {
+    format!("\"{}\"", x.replace('"', "\"\""))
+}
+
+/// The GeoPackage geometry type name for an Esri geometry type
+pub fn gpkg_geometry_type(x: &str) -> Option<&'static str> {
+    match x {
+        "esriGeometryPoint" => Some("POINT"),
+        "esriGeometryMultipoint" => Some("MULTIPOINT"),
+        "esriGeometryPolyline" => Some("MULTILINESTRING"),
+        "esriGeometryPolygon" => Some("MULTIPOLYGON"),
+        "esriGeometryEnvelope" => Some("POLYGON"),
+        "esriGeometryMultiPatch" => Some("MULTIPOLYGON"),
+        _ => None,
+    }
+}
+
+// the column type of a field or None if it is not stored as a column
+fn column_type(x: &FieldType) -> Option<&'static str> {
+    match x {
+        FieldType::EsriFieldTypeSmallInteger
+        | FieldType::EsriFieldTypeInteger
+        | FieldType::EsriFieldTypeBigInteger
+        | FieldType::EsriFieldTypeOid => Some("INTEGER"),
+        FieldType::EsriFieldTypeSingle => Some("FLOAT"),
+        FieldType::EsriFieldTypeDouble => Some("DOUBLE"),
+        FieldType::EsriFieldTypeString
+        | FieldType::EsriFieldTypeGuid
+        | FieldType::EsriFieldTypeGlobalId
+        | FieldType::EsriFieldTypeXml
+        | FieldType::EsriFieldTypeTimeOnly
+        | FieldType::EsriFieldTypeTimestampOffset => Some("TEXT"),
+        FieldType::EsriFieldTypeDateOnly => Some("DATE"),
+        FieldType::EsriFieldTypeDate => Some("DATETIME"),
+        FieldType::EsriFieldTypeBlob => Some("BLOB"),
+        FieldType::EsriFieldTypeGeometry | FieldType::EsriFieldTypeRaster => None,
+    }
+}
+
+fn sql_value(x: Option<&Value>, field_type: &FieldType) -> SqlValue {
+    match (x, field_type) {
+        (None | Some(Value::Null), _) => SqlValue::Null,
+        (Some(Value::Number(n)), FieldType::EsriFieldTypeDate) => n
+            .as_i64()
+            .and_then(DateTime::from_timestamp_millis)
+            .map_or(SqlValue::Null, |dt| {
+                SqlValue::Text(dt.to_rfc3339_opts(SecondsFormat::Millis, true))
+            }),
+        (Some(Value::Number(n)), _) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => n.as_f64().map_or(SqlValue::Null, SqlValue::Real),
+        },
+        (Some(Value::Bool(b)), _) => SqlValue::Integer(*b as i64),
+        (Some(Value::String(s)), _) => SqlValue::Text(s.clone()),
+        (Some(v), _) => SqlValue::Text(v.to_string()),
+    }
+}
+
+// [minx, maxx, miny, maxy] followed by [minz, maxz] and [minm, maxm] for the dimensions
+// of `N`, as used by GeoPackage envelopes
+fn bounds<const N: usize>(x: &EsriGeometry<N>) -> Option<Vec<f64>> {
+    let (has_z, has_m) = dimension_flags::<N>();
+    let z_idx = has_z.then_some(2);
+    let m_idx = has_m.then_some(2 + has_z as usize);
+    // [x, y, z, m] with NaN for the dimensions `N` doesn't have
+    let coord = |c: &EsriCoord<N>| {
+        [
+            c.0[0],
+            c.0[1],
+            z_idx.map_or(f64::NAN, |i| c.0[i]),
+            m_idx.map_or(f64::NAN, |i| c.0[i]),
+        ]
+    };
+    let coords = |rings: &[EsriLineString<N>]| -> Vec<[f64; 4]> {
+        rings.iter().flat_map(|l| &l.0).map(coord).collect()
+    };
+
+    let xyzm: Vec<[f64; 4]> = match x {
+        EsriGeometry::Point(p) => {
+            vec![[p.x, p.y, p.z.unwrap_or(f64::NAN), p.m.unwrap_or(f64::NAN)]]
+        }
+        EsriGeometry::MultiPoint(g) => g.iter().map(coord).collect(),
+        EsriGeometry::Polyline(g) => coords(&g.paths),
+        EsriGeometry::Polygon(g) => coords(&g.rings),
+        EsriGeometry::Envelope(e) => vec![
+            [
+                e.xmin,
+                e.ymin,
+                e.zmin.unwrap_or(f64::NAN),
+                e.mmin.unwrap_or(f64::NAN),
+            ],
+            [
+                e.xmax,
+                e.ymax,
+                e.zmax.unwrap_or(f64::NAN),
+                e.mmax.unwrap_or(f64::NAN),
+            ],
+        ],
+        EsriGeometry::Multipatch(g) => coords(&g.rings),
+        EsriGeometry::CurvePolyline(g) => return bounds(&EsriGeometry::Polyline(g.linearize())),
+        EsriGeometry::CurvePolygon(g) => return bounds(&EsriGeometry::Polygon(g.linearize())),
+    };
+
+    // the range of each dimension, ignoring NaN
+    let mut ranges: [Option<(f64, f64)>; 4] = [None; 4];
+    for c in xyzm.iter().filter(|c| !c[0].is_nan() && !c[1].is_nan()) {
+        for (range, v) in ranges.iter_mut().zip(c).filter(|(_, v)| !v.is_nan()) {
+            *range = Some(range.map_or((*v, *v), |(min, max)| (min.min(*v), max.max(*v))));
+        }
+    }
+
+    let [x, y, z, m] = ranges;
+    let (x, y) = (x?, y?);
+    let mut envelope = vec![x.0, x.1, y.0, y.1];
+    for (has, range) in [(has_z, z), (has_m, m)] {
+        if has {
+            let (min, max) = range.unwrap_or((f64::NAN, f64::NAN));
+            envelope.extend([min, max]);
+        }
+    }
+    Some(envelope)
+}
+
+fn union(a: Option<[f64; 4]>, b: Option<[f64; 4]>) -> Option<[f64; 4]> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some([
+            a[0].min(b[0]),
+            a[1].max(b[1]),
+            a[2].min(b[2]),
+            a[3].max(b[3]),
+        ]),
+        (a, b) => a.or(b),
+    }
+}
+
+// encodes a geometry as a GeoPackageBinary blob with an envelope of the dimensions of `N`
+fn gpkg_blob<const N: usize>(x: &EsriGeometry<N>, srs_id: i32) -> Vec<u8> {
+    let envelope = bounds(x);
+    let mut buf = vec![b'G', b'P', 0];
+
+    // the envelope contents indicator: xy, xyz, xym, or xyzm
+    let indicator = match dimension_flags::<N>() {
+        (false, false) => 1,
+        (true, false) => 2,
+        (false, true) => 3,
+        (true, true) => 4,
+    };
+
+    match envelope {
+        // little endian with an envelope
+        Some(env) => {
+            buf.push((indicator << 1) | 1);
+            buf.extend(srs_id.to_le_bytes());
+            env.iter().for_each(|v| buf.extend(v.to_le_bytes()));
+        }
+        // little endian empty geometry
+        None => {
+            buf.push(0b0001_0001);
+            buf.extend(srs_id.to_le_bytes());
+        }
+    }
+
+    write_geometry(&mut buf, x);
+    buf
+}
+
+// registers the spatial reference and returns its srs_id
+fn register_srs(conn: &Connection, x: Option<&SpatialReference>) -> Result<i32, GpkgError> {
+    let Some(sr) = x else {
+        return Ok(-1);
+    };
+
+    let wkid = sr.latest_wkid.or(sr.wkid).map(|wkid| match wkid {
+        // Esri's Web Mercator WKIDs
+        102100 | 102113 => 3857,
+        wkid => wkid,
+    });
+
+    let (srs_id, organization) = match wkid {
+        Some(wkid) => (wkid as i32, "EPSG"),
+        None if sr.wkt.is_some() => (CUSTOM_SRS_ID, "NONE"),
+        None => return Ok(-1),
+    };
+
+    conn.execute(
+        "INSERT OR IGNORE INTO gpkg_spatial_ref_sys VALUES (?1, ?2, ?3, ?4, ?5, NULL)",
+        rusqlite::params![
+            format!("{organization}:{srs_id}"),
+            srs_id,
+            organization,
+            srs_id,
+            sr.wkt.as_deref().unwrap_or("undefined"),
+        ],
+    )?;
+
+    Ok(srs_id)
+}
+
+/// Write a `FeatureSet` to a new table in a GeoPackage
+///
+/// The GeoPackage at `path` is created if it does not exist. Writing fails if
+/// `table_name` already exists. The `objectIdFieldName` (or `OID` field) is used
+/// as the table's primary key, otherwise an `fid` column is created.
+pub fn write_gpkg<const N: usize, P: AsRef<Path>>(
+    x: &FeatureSet<N>,
+    path: P,
+    table_name: &str,
+) -> Result<(), GpkgError> {
+    let mut conn = Connection::open(path)?;
+    conn.pragma_update(None, "application_id", 0x47504B47)?;
+    conn.pragma_update(None, "user_version", 10300)?;
+
+    let tx = conn.transaction()?;
+    tx.execute_batch(CREATE_METADATA_TABLES)?;
+
+    let srs_id = register_srs(&tx, x.spatialReference.as_ref())?;
+
+    let geometry_type = x
+        .geometryType
+        .as_deref()
+        .or_else(|| {
+            x.features
+                .iter()
+                .find_map(|f| f.geometry.as_ref().map(EsriGeometry::geometry_type))
+        })
+        .and_then(gpkg_geometry_type);
+
+    let fields = x.fields.clone().unwrap_or_default();
+    let fid = fields
+        .iter()
+        .find(|f| {
+            matches!(f.field_type, FieldType::EsriFieldTypeOid)
+                || x.objectIdFieldName.as_deref() == Some(f.name.as_str())
+        })
+        .map_or(DEFAULT_FID, |f| f.name.as_str());
+
+    let columns = fields
+        .iter()
+        .filter(|f| f.name != fid)
+        .filter_map(|f| Some((f, column_type(&f.field_type)?)))
+        .collect::<Vec<(&Field, &str)>>();
+
+    // create the table
+    let mut defs = vec![format!("{} INTEGER PRIMARY KEY AUTOINCREMENT", quote(fid))];
+    if let Some(geometry_type) = geometry_type {
+        defs.push(format!("{} {geometry_type}", quote(GEOMETRY_COLUMN)));
+    }
+    defs.extend(
+        columns
+            .iter()
+            .map(|(f, col_type)| format!("{} {col_type}", quote(&f.name))),
+    );
+    tx.execute_batch(&format!(
+        "CREATE TABLE {} ({});",
+        quote(table_name),
+        defs.join(", ")
+    ))?;
+
+    // insert the features
+    let mut names = vec![quote(fid)];
+    if geometry_type.is_some() {
+        names.push(quote(GEOMETRY_COLUMN));
+    }
+    names.extend(columns.iter().map(|(f, _)| quote(&f.name)));
+    let placeholders = vec!["?"; names.len()].join(", ");
+
+    let mut extent = None;
+    {
+        let mut stmt = tx.prepare(&format!(
+            "INSERT INTO {} ({}) VALUES ({placeholders})",
+            quote(table_name),
+            names.join(", ")
+        ))?;
+
+        for feature in &x.features {
+            let attrs = feature.attributes.as_ref();
+            let mut values = vec![sql_value(
+                attrs.and_then(|a| a.get(fid)),
+                &FieldType::EsriFieldTypeOid,
+            )];
+
+            if geometry_type.is_some() {
+                values.push(match &feature.geometry {
+                    Some(geom) => {
+                        let xy = bounds(geom).map(|e| [e[0], e[1], e[2], e[3]]);
+                        extent = union(extent, xy);
+                        SqlValue::Blob(gpkg_blob(geom, srs_id))
+                    }
+                    None => SqlValue::Null,
+                });
+            }
+
+            values.extend(
+                columns
+                    .iter()
+                    .map(|(f, _)| sql_value(attrs.and_then(|a| a.get(&f.name)), &f.field_type)),
+            );
+
+            stmt.execute(params_from_iter(values))?;
+        }
+    }
+
+    // register the table
+    let data_type = if geometry_type.is_some() {
+        "features"
+    } else {
+        "attributes"
+    };
+    let [min_x, max_x, min_y, max_y] = extent.map_or([None; 4], |e| e.map(Some));
+
+    tx.execute(
+        "INSERT INTO gpkg_contents (table_name, data_type, identifier, min_x, min_y, max_x, max_y, srs_id) VALUES (?1, ?2, ?1, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![table_name, data_type, min_x, min_y, max_x, max_y, srs_id],
+    )?;
+
+    if let Some(geometry_type) = geometry_type {
+        let (has_z, has_m) = dimension_flags::<N>();
+        tx.execute(
+            "INSERT INTO gpkg_geometry_columns VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                table_name,
+                GEOMETRY_COLUMN,
+                geometry_type,
+                srs_id,
+                has_z as i32,
+                has_m as i32
+            ],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Download a feature layer and write it to a GeoPackage
+///
+/// The table is named after the file stem of `path`. See [`export_to_gpkg()`].
+#[cfg(feature = "feature-service-client")]
+pub fn export_layer_to_gpkg<const N: usize, P: AsRef<Path>>(
+    layer_url: &str,
+    path: P,
+) -> Result<(), GpkgError> {
+    export_to_gpkg::<N, P>(&FeatureLayerClient::new(layer_url), path)
+}
+
+/// Download every feature of a layer and write it to a GeoPackage
+///
+/// The layer's schema and spatial reference are fetched with an empty query.
+/// Features are downloaded with a replica when the service supports it and
+/// with a paginated query when the service rejects the replica, e.g. because it
+/// lacks the `Sync` capability. Other errors, e.g. of the token, are returned.
+/// `N` must match the dimensions of the layer.
+#[cfg(feature = "feature-service-client")]
+pub fn export_to_gpkg<const N: usize, P: AsRef<Path>>(
+    client: &FeatureLayerClient<impl crate::http::HttpClient>,
+    path: P,
+) -> Result<(), GpkgError> {
+    let (has_z, has_m) = dimension_flags::<N>();
+    let mut params = FeatureQueryParamsBuilder::default()
+        .where_clause("1=0")
+        .return_z(has_z)
+        .return_m(has_m)
+        .build()
+        .expect("all fields have defaults");
+
+    let mut fset: FeatureSet<N> = client.query::<N>(&params)?.into();
+
+    fset.features = match client.create_replica::<N>() {
+        Ok(features) => features,
+        Err(e) if replicas_unsupported(&e) => {
+            params.where_clause = None;
+            client.query_all::<N>(&params)?.features
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let table_name = path
+        .as_ref()
+        .file_stem()
+        .and_then(|x| x.to_str())
+        .unwrap_or("features")
+        .to_string();
+
+    write_gpkg(&fset, path, &table_name)
+}
+
+// whether the service rejected a replica because it doesn't support them, rather than
+// failing for a reason that a query would fail for too
+#[cfg(feature = "feature-service-client")]
+fn replicas_unsupported(e: &FeatureServiceError) -> bool {
+    matches!(e, FeatureServiceError::ApiError(e) if matches!(e.error.code, 400 | 404 | 405))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn f64_at(blob: &[u8], i: usize) -> f64 {
+        f64::from_le_bytes(blob[i..i + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_write_gpkg() {
+        let json = r#"{
+            "geometryType": "esriGeometryPolygon",
+            "hasZ": true,
+            "spatialReference": {"wkid": 102100},
+            "objectIdFieldName": "OBJECTID",
+            "fields": [
+                {"name": "OBJECTID", "type": "esriFieldTypeOID"},
+                {"name": "name", "type": "esriFieldTypeString"},
+                {"name": "created", "type": "esriFieldTypeDate"}
+            ],
+            "features": [
+                {
+                    "attributes": {"OBJECTID": 7, "name": "square", "created": 0},
+                    "geometry": {"rings": [[[0, 0, 5], [0, 10, 6], [10, 10, 7], [10, 0, 8], [0, 0, 5]]]}
+                },
+                {
+                    "attributes": {"OBJECTID": 8, "name": null, "created": null},
+                    "geometry": {"rings": [[[-5, 2, 1], [-5, 3, 1], [-4, 3, 1], [-5, 2, 1]]]}
+                }
+            ]
+        }"#;
+        let fset: FeatureSet<3> = serde_json::from_str(json).unwrap();
+        let path = std::env::temp_dir().join("serde_esri_test_gpkg.gpkg");
+        let _ = std::fs::remove_file(&path);
+        write_gpkg(&fset, &path, "squares").unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        let contents = conn
+            .query_row(
+                "SELECT data_type, min_x, min_y, max_x, max_y, srs_id FROM gpkg_contents WHERE table_name = 'squares'",
+                [],
+                |r| {
+                    Ok((
+                        r.get::<_, String>(0)?,
+                        [r.get::<_, f64>(1)?, r.get(2)?, r.get(3)?, r.get(4)?],
+                        r.get::<_, i32>(5)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(contents, ("features".into(), [-5.0, 0.0, 10.0, 10.0], 3857));
+
+        let columns = conn
+            .query_row(
+                "SELECT column_name, geometry_type_name, srs_id, z, m FROM gpkg_geometry_columns",
+                [],
+                |r| {
+                    Ok((
+                        r.get::<_, String>(0)?,
+                        r.get::<_, String>(1)?,
+                        r.get::<_, i32>(2)?,
+                        r.get::<_, i32>(3)?,
+                        r.get::<_, i32>(4)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(columns, ("geom".into(), "MULTIPOLYGON".into(), 3857, 1, 0));
+
+        let (blob, name, created) = conn
+            .query_row(
+                "SELECT geom, name, created FROM squares WHERE OBJECTID = 7",
+                [],
+                |r| {
+                    Ok((
+                        r.get::<_, Vec<u8>>(0)?,
+                        r.get::<_, String>(1)?,
+                        r.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            (name.as_str(), created.as_str()),
+            ("square", "1970-01-01T00:00:00.000Z")
+        );
+
+        // the header: magic, version, little endian flags with an xyz envelope, and srs_id
+        assert_eq!(&blob[..4], &[b'G', b'P', 0, 0b0000_0101]);
+        assert_eq!(i32::from_le_bytes(blob[4..8].try_into().unwrap()), 3857);
+        let envelope: Vec<f64> = (0..6).map(|i| f64_at(&blob, 8 + i * 8)).collect();
+        assert_eq!(envelope, vec![0.0, 10.0, 0.0, 10.0, 5.0, 8.0]);
+        // followed by the WKB of a MultiPolygon Z
+        assert_eq!(blob[56], 1);
+        assert_eq!(u32::from_le_bytes(blob[57..61].try_into().unwrap()), 1006);
+    }
+
+    #[test]
+    fn test_attribute_table() {
+        let json = r#"{"fields": [{"name": "n", "type": "esriFieldTypeInteger"}],
+            "features": [{"attributes": {"n": 1}}, {"attributes": {"n": 2}}]}"#;
+        let fset: FeatureSet<2> = serde_json::from_str(json).unwrap();
+        let path = std::env::temp_dir().join("serde_esri_test_gpkg_attributes.gpkg");
+        let _ = std::fs::remove_file(&path);
+        write_gpkg(&fset, &path, "numbers").unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        let data_type: String = conn
+            .query_row("SELECT data_type FROM gpkg_contents", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(data_type, "attributes");
+        let sum: i64 = conn
+            .query_row("SELECT sum(n) FROM numbers", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(sum, 3);
+    }
+
+    #[cfg(feature = "feature-service-client")]
+    #[test]
+    fn test_replicas_unsupported() {
+        let api_error = |code: i32| {
+            FeatureServiceError::ApiError(crate::feature_service::ErrorResponse {
+                error: crate::feature_service::ErrorDetails {
+                    code,
+                    message: String::new(),
+                    details: vec![],
+                },
+            })
+        };
+        assert!(replicas_unsupported(&api_error(400)));
+        assert!(!replicas_unsupported(&api_error(498)));
+        assert!(!replicas_unsupported(&api_error(403)));
+        assert!(!replicas_unsupported(&api_error(500)));
+    }
+}
@@ -0,0 +1,484 @@
+//! A minimal HTTP abstraction so that clients can send requests with any HTTP stack.
+//!
+//! An [`HttpClient`] sends an [`HttpRequest`] and returns the status, headers, and bytes of the
+//! [`HttpResponse`]. It is implemented for `reqwest::blocking::Client`, the default backend of
+//! the blocking clients such as [`PlacesClient`](crate::places::query::PlacesClient). Implement it
+//! to use another client, e.g. one with tracing or proxy handling, or a test double.
+//!
+//! ```
+//! use serde_esri::http::{HttpClient, HttpError, HttpRequest, HttpResponse};
+//! use serde_esri::places::query::{CategoriesQueryParams, PlacesClient, PLACES_API_URL};
+//!
+//! #[derive(Clone)]
+//! struct Offline;
+//!
+//! impl HttpClient for Offline {
+//!     fn execute(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+//!         assert!(request.url.ends_with("/categories"));
+//!         let body = br#"{"categories": []}"#.to_vec();
+//!         Ok(HttpResponse { status: 200, headers: vec![], body })
+//!     }
+//! }
+//!
+//! let client = PlacesClient::new(PLACES_API_URL, "your token").with_http_client(Offline);
+//! let categories = client.categories(CategoriesQueryParams::default()).unwrap();
+//! assert!(categories.categories.is_empty());
+//! ```
+use crate::error::{ClassifyError, ErrorKind};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Post => "POST",
+        }
+    }
+}
+
+/// A request to send
+///
+/// The `body` of a `POST` request is `application/x-www-form-urlencoded` unless it has a
+/// `Content-Type` header, e.g. `multipart/form-data` for file uploads.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    /// The query parameters, which are not encoded in `url`
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    /// A `GET` request of `url`
+    pub fn get(url: &str) -> Self {
+        Self {
+            method: Method::Get,
+            url: url.to_string(),
+            query: Vec::new(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    /// A `POST` request of `url`
+    pub fn post(url: &str) -> Self {
+        Self {
+            method: Method::Post,
+            ..Self::get(url)
+        }
+    }
+
+    /// Add a header
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add a query parameter
+    pub fn param(mut self, name: &str, value: &str) -> Self {
+        self.query.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add the query parameters `pairs`
+    pub fn params<K: AsRef<str>, V: AsRef<str>>(mut self, pairs: &[(K, V)]) -> Self {
+        let pairs = pairs
+            .iter()
+            .map(|(k, v)| (k.as_ref().into(), v.as_ref().into()));
+        self.query.extend(pairs);
+        self
+    }
+
+    // sets the body to the form-urlencoded `pairs`
+    #[cfg(any(
+        feature = "feature-service-client",
+        feature = "geometry-service-client",
+        feature = "geocode-client",
+        feature = "routing-client"
+    ))]
+    pub(crate) fn form<K: AsRef<str>, V: AsRef<str>>(mut self, pairs: &[(K, V)]) -> Self {
+        let pairs: Vec<String> = pairs
+            .iter()
+            .map(|(k, v)| format!("{}={}", encode_form(k.as_ref()), encode_form(v.as_ref())))
+            .collect();
+        self.body = Some(pairs.join("&").into_bytes());
+        self
+    }
+
+    // sets the body to a `multipart/form-data` form of the text `fields` and a file
+    // named `file_name` in the field `file_field`
+    #[cfg(feature = "feature-service-client")]
+    pub(crate) fn multipart(
+        self,
+        fields: &[(&str, &str)],
+        file_field: &str,
+        file_name: &str,
+        content_type: &str,
+        data: &[u8],
+    ) -> Self {
+        // the boundary must not appear in the content
+        let boundary = (0..)
+            .map(|i| format!("serde-esri-boundary-{i}"))
+            .find(|b| !data.windows(b.len()).any(|w| w == b.as_bytes()))
+            .unwrap_or_default();
+        // quotes and line breaks would end the header
+        let quoted = |s: &str| s.replace(['"', '\r', '\n'], "_");
+
+        let mut body = Vec::with_capacity(data.len() + 256);
+        for (name, value) in fields {
+            body.extend(format!("--{boundary}\r\n").bytes());
+            let disposition = format!("form-data; name=\"{}\"", quoted(name));
+            body.extend(format!("Content-Disposition: {disposition}\r\n\r\n{value}\r\n").bytes());
+        }
+        body.extend(format!("--{boundary}\r\n").bytes());
+        let disposition = format!(
+            "form-data; name=\"{}\"; filename=\"{}\"",
+            quoted(file_field),
+            quoted(file_name)
+        );
+        body.extend(format!("Content-Disposition: {disposition}\r\n").bytes());
+        body.extend(format!("Content-Type: {}\r\n\r\n", quoted(content_type)).bytes());
+        body.extend_from_slice(data);
+        body.extend(format!("\r\n--{boundary}--\r\n").bytes());
+
+        let content_type = format!("multipart/form-data; boundary={boundary}");
+        Self {
+            body: Some(body),
+            ..self.header("Content-Type", &content_type)
+        }
+    }
+
+    // the method, URL, and sorted and encoded query parameters, without a token, which
+    // identify the responses of cached and recorded requests
+    #[cfg(any(feature = "http-cache", feature = "replay"))]
+    pub(crate) fn key(&self) -> String {
+        let mut query: Vec<_> = self.query.iter().filter(|(k, _)| k != "token").collect();
+        query.sort();
+
+        let params: Vec<String> = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", encode_form(k), encode_form(v)))
+            .collect();
+        format!(
+            "{} {}?{}",
+            self.method.as_str(),
+            self.url.trim_end_matches('/'),
+            params.join("&")
+        )
+    }
+
+    /// Add the fields of `params` as query parameters. Fields that are `None` are skipped.
+    #[cfg(any(
+        feature = "places-client",
+        feature = "places-client-async",
+        feature = "geocode-client"
+    ))]
+    pub(crate) fn query<S: serde::Serialize>(mut self, params: &S) -> Self {
+        use serde_json::Value;
+
+        if let Ok(Value::Object(fields)) = serde_json::to_value(params) {
+            let pairs = fields.into_iter().filter_map(|(k, v)| match v {
+                Value::Null => None,
+                Value::String(s) => Some((k, s)),
+                v => Some((k, v.to_string())),
+            });
+            self.query.extend(pairs);
+        }
+        self
+    }
+}
+
+// percent-encodes `s` to be a segment of the path of a URL
+#[cfg(any(feature = "places-client", feature = "places-client-async"))]
+pub(crate) fn encode_path_segment(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+// percent-encodes `s` as a name or value of a form-urlencoded body
+#[cfg(any(
+    feature = "feature-service-client",
+    feature = "geometry-service-client",
+    feature = "geocode-client",
+    feature = "routing-client",
+    feature = "http-cache",
+    feature = "replay"
+))]
+fn encode_form(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'*' => {
+                (b as char).to_string()
+            }
+            b' ' => "+".to_string(),
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// The status, headers, and body of a response
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// The value of the header `name`, which is case-insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The response, or an [`HttpError`] if its status is an error (4xx or 5xx)
+    pub fn error_for_status(self) -> Result<Self, HttpError> {
+        match self.status {
+            400.. => Err(HttpError::new(
+                ErrorKind::from_code(self.status.into()),
+                format!("HTTP status {}", self.status),
+            )),
+            _ => Ok(self),
+        }
+    }
+
+    /// The body decoded as JSON
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, HttpError> {
+        serde_json::from_slice(&self.body).map_err(|e| HttpError::new(ErrorKind::Fatal, e))
+    }
+
+    /// The delay requested by a `Retry-After` header
+    ///
+    /// The header is either a number of seconds or an HTTP date in the IMF-fixdate format,
+    /// e.g. `Wed, 21 Oct 2015 07:28:00 GMT`. A date in the past is no delay. The obsolete
+    /// date formats are not supported, nor are dates in wasm builds, which have no clock.
+    pub fn retry_after(&self) -> Option<Duration> {
+        parse_retry_after(self.header("Retry-After")?)
+    }
+}
+
+// the delay of a `Retry-After` header in seconds or until an HTTP date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    return None;
+    #[cfg(not(target_arch = "wasm32"))]
+    parse_http_date(value).map(|date| {
+        date.duration_since(std::time::SystemTime::now())
+            .unwrap_or_default()
+    })
+}
+
+// an HTTP date in the IMF-fixdate format, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_http_date(value: &str) -> Option<std::time::SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let (_, date) = value.split_once(", ")?;
+    let [day, month, year, time, "GMT"] = date.split(' ').collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let day: u64 = day.parse().ok().filter(|d| (1..=31).contains(d))?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u64 + 1;
+    // the year has four digits
+    let year: u64 = year.parse().ok().filter(|y| (1970..=9999).contains(y))?;
+    let [h, m, s] = time.split(':').collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let (h, m, s): (u64, u64, u64) = (h.parse().ok()?, m.parse().ok()?, s.parse().ok()?);
+    if h > 23 || m > 59 || s > 60 {
+        return None;
+    }
+
+    // the days since 1970-01-01, counting years from March so that leap days come last
+    let y = if month <= 2 {
+        year.checked_sub(1)?
+    } else {
+        year
+    };
+    let (era, year_of_era) = (y / 400, y % 400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era
+        .checked_mul(146_097)?
+        .checked_add(day_of_era)?
+        .checked_sub(719_468)?;
+
+    let secs = days
+        .checked_mul(86_400)?
+        .checked_add(h * 3_600 + m * 60 + s)?;
+    std::time::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+/// A request that failed before a response was received
+#[derive(Debug)]
+pub struct HttpError {
+    /// Whether the request should be retried. Use [`ErrorKind::Retryable`] for timeouts and connection failures.
+    pub kind: ErrorKind,
+    pub source: Box<dyn std::error::Error + Send + Sync>,
+}
+
+impl HttpError {
+    pub fn new<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+        kind: ErrorKind,
+        source: E,
+    ) -> Self {
+        Self {
+            kind,
+            source: source.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request failed: {}", self.source)
+    }
+}
+
+impl std::error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+impl ClassifyError for HttpError {
+    fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
+
+/// Sends requests for a client
+pub trait HttpClient: Send + Sync {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, HttpError>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HttpClient for reqwest::blocking::Client {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+        let content_type = request
+            .headers
+            .iter()
+            .any(|(k, _)| k.eq_ignore_ascii_case("Content-Type"));
+        let builder = match request.method {
+            Method::Get => self.get(&request.url),
+            Method::Post if content_type => self.post(&request.url),
+            Method::Post => self
+                .post(&request.url)
+                .header("Content-Type", "application/x-www-form-urlencoded"),
+        };
+
+        let builder = request
+            .headers
+            .iter()
+            .fold(builder.query(&request.query), |b, (k, v)| b.header(k, v));
+
+        let builder = match request.body {
+            Some(body) => builder.body(body),
+            None => builder,
+        };
+
+        let into_error = |e: reqwest::Error| HttpError::new(ErrorKind::from_request_error(&e), e);
+        let resp = builder.send().map_err(into_error)?;
+        let status = resp.status().as_u16();
+        let headers = resp
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_string())))
+            .collect();
+        let body = resp.bytes().map_err(into_error)?.to_vec();
+
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_after() {
+        assert_eq!(parse_retry_after(" 120 "), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(std::time::UNIX_EPOCH + Duration::from_secs(784_111_777))
+        );
+        assert_eq!(
+            parse_http_date("Thu, 29 Feb 2024 00:00:00 GMT"),
+            Some(std::time::UNIX_EPOCH + Duration::from_secs(1_709_164_800))
+        );
+        // a date in the past
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert!(parse_retry_after("Fri, 01 Jan 2100 00:00:00 GMT").unwrap() > Duration::ZERO);
+        assert_eq!(parse_retry_after("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        // years out of range are rejected rather than overflowing
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 500000000000 08:49:37 GMT"),
+            None
+        );
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 18446744073709551615 08:49:37 GMT"),
+            None
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[cfg(feature = "feature-service-client")]
+    #[test]
+    fn test_bodies() {
+        let request = HttpRequest::post("https://example.com").form(&[("where", "a = 'b & c'")]);
+        assert_eq!(request.body.unwrap(), b"where=a+%3D+%27b+%26+c%27");
+
+        let request = HttpRequest::post("https://example.com").multipart(
+            &[("f", "json")],
+            "file",
+            "a.txt",
+            "text/plain",
+            b"serde-esri-boundary-0",
+        );
+        let (_, content_type) = &request.headers[0];
+        assert_eq!(
+            content_type,
+            "multipart/form-data; boundary=serde-esri-boundary-1"
+        );
+
+        let body = String::from_utf8(request.body.unwrap()).unwrap();
+        assert!(body.starts_with(
+            "--serde-esri-boundary-1\r\nContent-Disposition: form-data; name=\"f\"\r\n\r\njson\r\n"
+        ));
+        assert!(body.contains(
+            "filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\nserde-esri-boundary-0\r\n"
+        ));
+        assert!(body.ends_with("--serde-esri-boundary-1--\r\n"));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    auth::{CredentialError, CredentialProvider, TokenProvider, TokenSource},
+    error::{ClassifyError, ErrorKind},
+    feature_service::{ErrorResponse, ExpectedResponse},
+    http::{HttpClient, HttpError, HttpRequest},
+    image_service::{ExportImageParams, ExportImageResponse, ExportedImage},
+    trace,
+};
+
+#[derive(Debug)]
+pub enum ImageServiceError {
+    /// The request failed or the response is not the expected JSON
+    HttpError(HttpError),
+    ApiError(ErrorResponse),
+    /// The token provider failed to supply a token
+    CredentialError(CredentialError),
+}
+
+impl std::fmt::Display for ImageServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HttpError(e) => write!(f, "{e}"),
+            Self::ApiError(e) => write!(f, "{} ({})", e.error.message, e.error.code),
+            Self::CredentialError(e) => write!(f, "failed to get token: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ImageServiceError {}
+
+impl ClassifyError for ImageServiceError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::HttpError(e) => e.kind(),
+            Self::ApiError(e) => e.kind(),
+            Self::CredentialError(e) => e.kind(),
+        }
+    }
+}
+
+/// A client for an Image Service
+///
+/// ```no_run
+/// use serde_esri::geometry::EsriEnvelope;
+/// use serde_esri::image_service::{ExportImageParamsBuilder, ImageFormat, ImageServiceClient};
+///
+/// let client = ImageServiceClient::new(
+///     "https://sampleserver6.arcgisonline.com/arcgis/rest/services/NLCDLandCover2001/ImageServer",
+/// );
+/// let bbox = EsriEnvelope { xmin: -1.3e7, ymin: 4.0e6, xmax: -1.29e7, ymax: 4.1e6, ..Default::default() };
+/// let params = ExportImageParamsBuilder::default()
+///     .bbox(bbox)
+///     .size((256, 256))
+///     .format(ImageFormat::Tiff)
+///     .build()
+///     .unwrap();
+///
+/// let image = client.export_image(&params).unwrap();
+/// let transform = image.metadata.geo_transform();
+/// ```
+///
+/// Requests are sent with a `reqwest::blocking::Client` unless another [`HttpClient`]
+/// is set with [`ImageServiceClient::with_http_client()`].
+#[derive(Clone)]
+pub struct ImageServiceClient<H = reqwest::blocking::Client> {
+    /// The URL of the service, e.g. `.../ImageServer`
+    pub service_url: String,
+    pub(crate) client: H,
+    /// The token to use for authorization. Redacted from `Debug` and zeroized on drop.
+    pub(crate) token: Option<TokenSource>,
+}
+
+impl<H> std::fmt::Debug for ImageServiceClient<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImageServiceClient")
+            .field("service_url", &self.service_url)
+            .field("token", &self.token.as_ref().map(|_| "[REDACTED]"))
+            .finish_non_exhaustive()
+    }
+}
+
+impl ImageServiceClient {
+    /// Create a new client for the Image Service at `service_url`
+    pub fn new(service_url: &str) -> Self {
+        Self {
+            service_url: service_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: None,
+        }
+    }
+}
+
+impl<H: HttpClient> ImageServiceClient<H> {
+    /// Send requests with `client` instead
+    pub fn with_http_client<C: HttpClient>(self, client: C) -> ImageServiceClient<C> {
+        ImageServiceClient {
+            service_url: self.service_url,
+            client,
+            token: self.token,
+        }
+    }
+
+    /// Set the token used to authorize requests
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Set the provider asked for the token before every request
+    pub fn with_token_provider<P: TokenProvider>(mut self, provider: P) -> Self {
+        self.token = Some(TokenSource::new(provider));
+        self
+    }
+
+    /// Set the token used to authorize requests from `credentials`
+    pub fn with_credentials<C: CredentialProvider>(
+        mut self,
+        credentials: &C,
+    ) -> Result<Self, CredentialError> {
+        self.token = Some(credentials.token()?.into());
+        Ok(self)
+    }
+
+    /// Export an image with the [`/exportImage`](https://developers.arcgis.com/rest/services-reference/enterprise/export-image/) endpoint
+    /// and download it
+    pub fn export_image(
+        &self,
+        params: &ExportImageParams,
+    ) -> Result<ExportedImage, ImageServiceError> {
+        let metadata = self.export_image_metadata(params)?;
+
+        let resp = trace::send(
+            &self.client,
+            self.authorize(HttpRequest::get(&metadata.href))?,
+        )
+        .and_then(|r| r.error_for_status())
+        .map_err(ImageServiceError::HttpError)?;
+        let content_type = resp.header("Content-Type").map(String::from);
+
+        Ok(ExportedImage {
+            data: resp.body,
+            content_type,
+            metadata,
+        })
+    }
+
+    /// Export an image without downloading it
+    ///
+    /// The response contains the URL of the image and its georeferencing.
+    pub fn export_image_metadata(
+        &self,
+        params: &ExportImageParams,
+    ) -> Result<ExportImageResponse, ImageServiceError> {
+        let request = HttpRequest::get(&format!("{}/exportImage", self.service_url))
+            .params(&params.query_pairs());
+
+        let resp = trace::send(&self.client, self.authorize(request)?)
+            .and_then(|r| r.json::<ExpectedResponse<ExportImageResponse>>())
+            .map_err(ImageServiceError::HttpError)?;
+
+        match resp {
+            ExpectedResponse::Ok(x) => Ok(x),
+            ExpectedResponse::Error(e) => Err(ImageServiceError::ApiError(e)),
+        }
+    }
+
+    fn authorize(&self, request: HttpRequest) -> Result<HttpRequest, ImageServiceError> {
+        match &self.token {
+            Some(token) => {
+                let bearer = token.bearer().map_err(ImageServiceError::CredentialError)?;
+                Ok(request.header("X-Esri-Authorization", &bearer))
+            }
+            None => Ok(request),
+        }
+    }
+}
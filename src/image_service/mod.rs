@@ -0,0 +1,17 @@
+//! Types for the [Image Service](https://developers.arcgis.com/rest/services-reference/enterprise/image-service/) of an ArcGIS server.
+//!
+//! - [`ExportImageParamsBuilder`] creates an [`ExportImageParams`] struct used to export an image of an extent with the [`/exportImage`](https://developers.arcgis.com/rest/services-reference/enterprise/export-image/) endpoint.
+//! - [`RenderingRule`] applies a raster function, e.g. a hillshade, to the exported image.
+//!
+//! Activate the `"image-service-client"` feature to enable the `ImageServiceClient` struct which sends these requests
+//! and downloads the image with its [`ExportImageResponse`] georeferencing.
+mod params;
+mod responses;
+pub use params::*;
+pub use responses::*;
+
+#[cfg(feature = "image-service-client")]
+mod client;
+
+#[cfg(feature = "image-service-client")]
+pub use client::*;
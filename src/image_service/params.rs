@@ -0,0 +1,232 @@
+use crate::geometry::EsriEnvelope;
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+
+/// The format of an exported image
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    /// PNG where the image has transparent pixels and JPEG otherwise
+    #[default]
+    Jpgpng,
+    Png,
+    Png8,
+    Png24,
+    Png32,
+    Jpg,
+    Bmp,
+    Gif,
+    Tiff,
+    /// Band interleaved by pixel
+    Bip,
+    /// Band sequential
+    Bsq,
+    Lerc,
+}
+
+impl ImageFormat {
+    /// The value used by the REST API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Jpgpng => "jpgpng",
+            Self::Png => "png",
+            Self::Png8 => "png8",
+            Self::Png24 => "png24",
+            Self::Png32 => "png32",
+            Self::Jpg => "jpg",
+            Self::Bmp => "bmp",
+            Self::Gif => "gif",
+            Self::Tiff => "tiff",
+            Self::Bip => "bip",
+            Self::Bsq => "bsq",
+            Self::Lerc => "lerc",
+        }
+    }
+}
+
+/// A [raster function](https://developers.arcgis.com/rest/services-reference/enterprise/raster-function-objects/) applied to an image
+///
+/// ```
+/// use serde_esri::image_service::RenderingRule;
+///
+/// let rule = RenderingRule::new("Hillshade").argument("ZFactor", 2.0);
+/// ```
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderingRule {
+    /// The name of the raster function, e.g. `Hillshade` or `NDVI`
+    pub raster_function: Option<String>,
+    pub raster_function_arguments: Option<Map<String, Value>>,
+    pub variable_name: Option<String>,
+    /// The raster function template of the service to use instead of `raster_function`
+    pub raster_function_template: Option<String>,
+}
+
+impl RenderingRule {
+    pub fn new(raster_function: &str) -> Self {
+        Self {
+            raster_function: Some(raster_function.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Add an argument of the raster function
+    pub fn argument<V: Into<Value>>(mut self, name: &str, value: V) -> Self {
+        self.raster_function_arguments
+            .get_or_insert_with(Map::new)
+            .insert(name.to_string(), value.into());
+        self
+    }
+}
+
+/// Request parameters used to export an image with the [`/exportImage`](https://developers.arcgis.com/rest/services-reference/enterprise/export-image/) endpoint
+///
+/// The spatial reference of `bbox` is used as the `bboxSR`.
+///
+/// ```
+/// use serde_esri::geometry::EsriEnvelope;
+/// use serde_esri::image_service::{ExportImageParamsBuilder, ImageFormat};
+///
+/// let bbox = EsriEnvelope { xmin: -117.2, ymin: 34.0, xmax: -117.1, ymax: 34.1, ..Default::default() };
+/// let params = ExportImageParamsBuilder::default()
+///     .bbox(bbox)
+///     .size((512, 512))
+///     .format(ImageFormat::Tiff)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default, Builder)]
+pub struct ExportImageParams {
+    /// The extent of the image
+    pub bbox: EsriEnvelope,
+    /// The width and height of the image in pixels. Defaults to 400 by 400.
+    #[builder(setter(strip_option), default)]
+    pub size: Option<(u32, u32)>,
+    /// The WKID of the image. Defaults to the spatial reference of the service.
+    #[builder(setter(strip_option), default)]
+    pub image_sr: Option<u32>,
+    #[builder(setter(strip_option), default)]
+    pub format: Option<ImageFormat>,
+    /// The type of the pixels, e.g. `U8` or `F32`. Defaults to the type of the service.
+    #[builder(setter(into, strip_option), default)]
+    pub pixel_type: Option<String>,
+    /// The value of pixels without data
+    #[builder(setter(into, strip_option), default)]
+    pub no_data: Option<String>,
+    /// The resampling method, e.g. `RSP_BilinearInterpolation` or `RSP_NearestNeighbor`
+    #[builder(setter(into, strip_option), default)]
+    pub interpolation: Option<String>,
+    /// The quality of lossy formats, from 0 to 100
+    #[builder(setter(strip_option), default)]
+    pub compression_quality: Option<u8>,
+    /// The bands to export, in order
+    #[builder(setter(into, strip_option), default)]
+    pub band_ids: Option<Vec<u32>>,
+    #[builder(setter(strip_option), default)]
+    pub rendering_rule: Option<RenderingRule>,
+    /// The time instant or extent of the image as milliseconds since the epoch, e.g. `1199145600000,1230768000000`
+    #[builder(setter(into, strip_option), default)]
+    pub time: Option<String>,
+}
+
+impl ExportImageParamsBuilder {
+    /// Set the extent of the image from a `geo_types::Rect` in `wkid`
+    #[cfg(feature = "geo")]
+    pub fn rect(&mut self, rect: geo_types::Rect, wkid: u32) -> &mut Self {
+        self.bbox(EsriEnvelope {
+            xmin: rect.min().x,
+            ymin: rect.min().y,
+            xmax: rect.max().x,
+            ymax: rect.max().y,
+            spatialReference: Some(crate::spatial_reference::SpatialReference::from_wkid(wkid)),
+            ..Default::default()
+        })
+    }
+}
+
+impl ExportImageParams {
+    /// The parameters as key-value pairs to be used as the query of a request
+    ///
+    /// `f=json` is always included, so the response contains the georeferencing of the image.
+    pub fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let [xmin, ymin, xmax, ymax] = self.bbox.bbox();
+        let mut pairs = vec![("bbox", format!("{xmin},{ymin},{xmax},{ymax}"))];
+
+        let bbox_sr = self.bbox.spatialReference.as_ref().and_then(|sr| sr.wkid);
+        let optional = [
+            ("bboxSR", bbox_sr.map(|x| x.to_string())),
+            ("size", self.size.map(|(w, h)| format!("{w},{h}"))),
+            ("imageSR", self.image_sr.map(|x| x.to_string())),
+            ("format", self.format.map(|x| x.as_str().into())),
+            ("pixelType", self.pixel_type.clone()),
+            ("noData", self.no_data.clone()),
+            ("interpolation", self.interpolation.clone()),
+            (
+                "compressionQuality",
+                self.compression_quality.map(|x| x.to_string()),
+            ),
+            (
+                "bandIds",
+                self.band_ids
+                    .as_ref()
+                    .map(|ids| ids.iter().map(u32::to_string).collect::<Vec<_>>().join(",")),
+            ),
+            (
+                "renderingRule",
+                self.rendering_rule
+                    .as_ref()
+                    .and_then(|x| serde_json::to_string(x).ok()),
+            ),
+            ("time", self.time.clone()),
+        ];
+
+        pairs.extend(optional.into_iter().filter_map(|(k, v)| Some((k, v?))));
+        pairs.push(("f", "json".into()));
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial_reference::SpatialReference;
+
+    #[test]
+    fn test_export_image_pairs() {
+        let bbox = EsriEnvelope {
+            xmin: 0.0,
+            ymin: 1.0,
+            xmax: 2.0,
+            ymax: 3.5,
+            spatialReference: Some(SpatialReference::from_wkid(3857)),
+            ..Default::default()
+        };
+        let params = ExportImageParamsBuilder::default()
+            .bbox(bbox)
+            .size((256, 128))
+            .band_ids(vec![3, 2, 1])
+            .rendering_rule(RenderingRule::new("Hillshade").argument("ZFactor", 2))
+            .build()
+            .unwrap();
+
+        let pairs = params.query_pairs();
+        let get = |key| {
+            pairs
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(get("bbox"), Some("0,1,2,3.5"));
+        assert_eq!(get("bboxSR"), Some("3857"));
+        assert_eq!(get("size"), Some("256,128"));
+        assert_eq!(get("bandIds"), Some("3,2,1"));
+        assert_eq!(
+            get("renderingRule"),
+            Some(r#"{"rasterFunction":"Hillshade","rasterFunctionArguments":{"ZFactor":2}}"#)
+        );
+        assert_eq!(get("format"), None);
+    }
+}
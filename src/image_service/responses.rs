@@ -0,0 +1,63 @@
+use crate::geometry::EsriEnvelope;
+use serde::{Deserialize, Serialize};
+
+/// The response from the [`/exportImage`](https://developers.arcgis.com/rest/services-reference/enterprise/export-image/) endpoint with `f=json`
+///
+/// `extent` is the extent of the image, which can differ from the requested extent
+/// to keep the pixels square.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportImageResponse {
+    /// The URL to download the image from
+    pub href: String,
+    pub width: u32,
+    pub height: u32,
+    pub extent: EsriEnvelope,
+    pub scale: Option<f64>,
+}
+
+impl ExportImageResponse {
+    /// The width and height of a pixel in the units of `extent`
+    pub fn pixel_size(&self) -> (f64, f64) {
+        let [xmin, ymin, xmax, ymax] = self.extent.bbox();
+        (
+            (xmax - xmin) / self.width.max(1) as f64,
+            (ymax - ymin) / self.height.max(1) as f64,
+        )
+    }
+
+    /// The affine transform from pixel to map coordinates in the order used by GDAL
+    ///
+    /// `[xmin, pixel width, 0, ymax, 0, -pixel height]`, for a north-up image whose first row is at the top.
+    pub fn geo_transform(&self) -> [f64; 6] {
+        let (width, height) = self.pixel_size();
+        [self.extent.xmin, width, 0.0, self.extent.ymax, 0.0, -height]
+    }
+}
+
+/// An image downloaded from an Image Service with its georeferencing
+#[derive(Debug, Clone, Default)]
+pub struct ExportedImage {
+    /// The encoded image in the requested format
+    pub data: Vec<u8>,
+    /// The `Content-Type` of the image, if returned by the server
+    pub content_type: Option<String>,
+    pub metadata: ExportImageResponse,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geo_transform() {
+        let resp: ExportImageResponse = serde_json::from_str(
+            r#"{"href": "https://example.com/image.tif", "width": 100, "height": 50,
+                "extent": {"xmin": 10.0, "ymin": 20.0, "xmax": 30.0, "ymax": 25.0,
+                           "spatialReference": {"wkid": 3857}}, "scale": 0}"#,
+        )
+        .unwrap();
+
+        assert_eq!(resp.pixel_size(), (0.2, 0.1));
+        assert_eq!(resp.geo_transform(), [10.0, 0.2, 0.0, 25.0, 0.0, -0.1]);
+    }
+}
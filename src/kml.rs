@@ -0,0 +1,443 @@
+//! Optional feature for exporting `FeatureSet`s to [KML](https://developers.google.com/kml/documentation/kmlreference) and KMZ.
+//!
+//! [`FeatureSet::to_kml()`] writes a `Placemark` for each feature with its geometry
+//! and its attributes as `ExtendedData`. The value of the `displayFieldName` is
+//! used as the name of each placemark.
+//!
+//! When a [`Renderer`] is given, the symbol of each feature is written as a shared
+//! `Style`. Marker, line, and fill colors, line widths, marker sizes, and the
+//! images of picture markers are kept. Other symbols are not styled.
+//!
+//! Geometries are written as is: KML expects longitude and latitude on WGS 84, so
+//! other spatial references should be reprojected first. Polylines with several
+//! paths, polygons with several exterior rings, and multipoints are written as a
+//! `MultiGeometry`, envelopes as polygons, and multipatches as their faces.
+//! True curves are densified.
+//!
+//! [`FeatureSet::write_kmz()`] zips the KML into a KMZ.
+//!
+//! ```
+//! use serde_esri::features::FeatureSet;
+//!
+//! let fset: FeatureSet<2> = serde_json::from_str(r#"{
+//!     "displayFieldName": "name",
+//!     "features": [{"geometry": {"x": -122.4, "y": 37.8}, "attributes": {"name": "San Francisco"}}]
+//! }"#).unwrap();
+//!
+//! let kml = fset.to_kml(None);
+//! assert!(kml.contains("<name>San Francisco</name>"));
+//! assert!(kml.contains("<coordinates>-122.4,37.8</coordinates>"));
+//! ```
+use crate::{
+    features::{Feature, FeatureSet},
+    geometry::*,
+    rings::classify_rings,
+    symbology::{Color, Renderer, SimpleFillStyle, SimpleLineStyle, Symbol},
+};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::io::{Seek, Write};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+/// Errors that can occur when writing a KMZ
+#[derive(Debug)]
+pub enum KmlError {
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+}
+
+impl std::fmt::Display for KmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Zip(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for KmlError {}
+
+impl From<std::io::Error> for KmlError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<zip::result::ZipError> for KmlError {
+    fn from(value: zip::result::ZipError) -> Self {
+        Self::Zip(value)
+    }
+}
+
+fn escape(x: &str) -> String {
+    let mut escaped = String::with_capacity(x.len());
+    for c in x.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn attribute_string(x: &Value) -> String {
+    match x {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        x => x.to_string(),
+    }
+}
+
+// Styles
+
+// KML colors are hex encoded as aabbggrr
+fn kml_color([r, g, b, a]: Color) -> String {
+    format!("{a:02x}{b:02x}{g:02x}{r:02x}")
+}
+
+// symbol sizes are in points and KML widths are in pixels
+fn pixels(points: f64) -> f64 {
+    points * 4.0 / 3.0
+}
+
+fn line_style(out: &mut String, color: Option<Color>, width: Option<f64>, style: SimpleLineStyle) {
+    // null lines are fully transparent
+    let color = match style {
+        SimpleLineStyle::Null => Some([0, 0, 0, 0]),
+        _ => color,
+    };
+    out.push_str("<LineStyle>");
+    if let Some(color) = color {
+        let _ = write!(out, "<color>{}</color>", kml_color(color));
+    }
+    if let Some(width) = width {
+        let _ = write!(out, "<width>{}</width>", pixels(width));
+    }
+    out.push_str("</LineStyle>");
+}
+
+fn write_style(out: &mut String, id: usize, symbol: &Symbol) {
+    let _ = write!(out, "<Style id=\"style{id}\">");
+    match symbol {
+        Symbol::SimpleMarker(s) => {
+            out.push_str("<IconStyle>");
+            if let Some(color) = s.color {
+                let _ = write!(out, "<color>{}</color>", kml_color(color));
+            }
+            // the default Google Earth icon is about 24 pixels wide
+            if let Some(size) = s.size {
+                let _ = write!(out, "<scale>{}</scale>", pixels(size) / 24.0);
+            }
+            if let Some(angle) = s.angle {
+                let _ = write!(out, "<heading>{angle}</heading>");
+            }
+            out.push_str("</IconStyle>");
+        }
+        Symbol::PictureMarker(s) => {
+            out.push_str("<IconStyle>");
+            if let Some(width) = s.width {
+                let _ = write!(out, "<scale>{}</scale>", pixels(width) / 24.0);
+            }
+            if let Some(angle) = s.angle {
+                let _ = write!(out, "<heading>{angle}</heading>");
+            }
+            if let Some(url) = &s.url {
+                let _ = write!(out, "<Icon><href>{}</href></Icon>", escape(url));
+            }
+            out.push_str("</IconStyle>");
+        }
+        Symbol::SimpleLine(s) => line_style(out, s.color, s.width, s.style),
+        Symbol::SimpleFill(s) => {
+            if let Some(outline) = &s.outline {
+                line_style(out, outline.color, outline.width, outline.style);
+            }
+            out.push_str("<PolyStyle>");
+            if let Some(color) = s.color {
+                let _ = write!(out, "<color>{}</color>", kml_color(color));
+            }
+            if s.style == SimpleFillStyle::Null {
+                out.push_str("<fill>0</fill>");
+            }
+            if s.outline.is_none() {
+                out.push_str("<outline>0</outline>");
+            }
+            out.push_str("</PolyStyle>");
+        }
+        Symbol::PictureFill(s) => {
+            if let Some(outline) = &s.outline {
+                line_style(out, outline.color, outline.width, outline.style);
+            }
+        }
+        Symbol::Other(_) => {}
+    }
+    out.push_str("</Style>");
+}
+
+// Geometries
+
+fn write_coords<const N: usize>(out: &mut String, coords: &[EsriCoord<N>], has_z: bool) {
+    out.push_str("<coordinates>");
+    for (i, c) in coords.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let _ = write!(out, "{},{}", c.0[0], c.0[1]);
+        if has_z && N > 2 {
+            let _ = write!(out, ",{}", c.0[2]);
+        }
+    }
+    out.push_str("</coordinates>");
+}
+
+fn write_linestring<const N: usize>(out: &mut String, line: &EsriLineString<N>, has_z: bool) {
+    out.push_str("<LineString>");
+    write_coords(out, &line.0, has_z);
+    out.push_str("</LineString>");
+}
+
+fn write_ring<const N: usize>(out: &mut String, ring: &EsriLineString<N>, has_z: bool) {
+    out.push_str("<LinearRing>");
+    write_coords(out, &ring.0, has_z);
+    out.push_str("</LinearRing>");
+}
+
+// the exterior ring followed by its holes
+fn write_polygon<'a, const N: usize, I>(out: &mut String, mut rings: I, has_z: bool)
+where
+    I: Iterator<Item = &'a EsriLineString<N>>,
+{
+    out.push_str("<Polygon>");
+    if let Some(exterior) = rings.next() {
+        out.push_str("<outerBoundaryIs>");
+        write_ring(out, exterior, has_z);
+        out.push_str("</outerBoundaryIs>");
+    }
+    for hole in rings {
+        out.push_str("<innerBoundaryIs>");
+        write_ring(out, hole, has_z);
+        out.push_str("</innerBoundaryIs>");
+    }
+    out.push_str("</Polygon>");
+}
+
+// a single part is written on its own, several in a MultiGeometry
+fn write_parts<T>(out: &mut String, parts: &[T], mut write_part: impl FnMut(&mut String, &T)) {
+    if let [part] = parts {
+        write_part(out, part);
+        return;
+    }
+    out.push_str("<MultiGeometry>");
+    for part in parts {
+        write_part(out, part);
+    }
+    out.push_str("</MultiGeometry>");
+}
+
+fn write_rings<const N: usize>(out: &mut String, rings: &[EsriLineString<N>], has_z: bool) {
+    let polygons = classify_rings(rings);
+    write_parts(out, &polygons, |out, polygon| {
+        write_polygon(out, polygon.iter().map(|i| &rings[*i]), has_z)
+    });
+}
+
+fn write_geometry<const N: usize>(out: &mut String, geometry: &EsriGeometry<N>, has_z: bool) {
+    match geometry {
+        EsriGeometry::Point(pt) => {
+            let _ = write!(out, "<Point><coordinates>{},{}", pt.x, pt.y);
+            if let Some(z) = pt.z {
+                let _ = write!(out, ",{z}");
+            }
+            out.push_str("</coordinates></Point>");
+        }
+        EsriGeometry::MultiPoint(mp) => {
+            out.push_str("<MultiGeometry>");
+            for c in &mp.points {
+                out.push_str("<Point>");
+                write_coords(out, std::slice::from_ref(c), has_z);
+                out.push_str("</Point>");
+            }
+            out.push_str("</MultiGeometry>");
+        }
+        EsriGeometry::Polyline(pl) => write_parts(out, &pl.paths, |out, path| {
+            write_linestring(out, path, has_z)
+        }),
+        EsriGeometry::CurvePolyline(pl) => write_parts(out, &pl.linearize().paths, |out, path| {
+            write_linestring(out, path, has_z)
+        }),
+        EsriGeometry::Polygon(ply) => write_rings(out, &ply.rings, has_z),
+        EsriGeometry::CurvePolygon(ply) => write_rings(out, &ply.linearize().rings, has_z),
+        EsriGeometry::Envelope(e) => {
+            let ring = EsriLineString::<2>(
+                [
+                    [e.xmin, e.ymin],
+                    [e.xmin, e.ymax],
+                    [e.xmax, e.ymax],
+                    [e.xmax, e.ymin],
+                    [e.xmin, e.ymin],
+                ]
+                .into_iter()
+                .map(EsriCoord)
+                .collect(),
+            );
+            write_polygon(out, std::iter::once(&ring), false);
+        }
+        EsriGeometry::Multipatch(mp) => write_parts(out, &mp.faces(), |out, face| {
+            write_polygon(out, face.iter(), has_z)
+        }),
+    }
+}
+
+fn write_placemark<const N: usize>(
+    out: &mut String,
+    feature: &Feature<N>,
+    name_field: Option<&str>,
+    style: Option<usize>,
+    has_z: bool,
+) {
+    out.push_str("<Placemark>");
+    let attributes = feature.attributes.as_ref();
+
+    let name = name_field.and_then(|field| attributes?.get(field));
+    if let Some(name) = name.filter(|v| !v.is_null()) {
+        let _ = write!(out, "<name>{}</name>", escape(&attribute_string(name)));
+    }
+    if let Some(id) = style {
+        let _ = write!(out, "<styleUrl>#style{id}</styleUrl>");
+    }
+    if let Some(attributes) = attributes.filter(|a| !a.is_empty()) {
+        out.push_str("<ExtendedData>");
+        for (key, value) in attributes {
+            let _ = write!(
+                out,
+                "<Data name=\"{}\"><value>{}</value></Data>",
+                escape(key),
+                escape(&attribute_string(value))
+            );
+        }
+        out.push_str("</ExtendedData>");
+    }
+    if let Some(geometry) = &feature.geometry {
+        write_geometry(out, geometry, has_z);
+    }
+    out.push_str("</Placemark>");
+}
+
+impl<const N: usize> FeatureSet<N> {
+    /// Serialize the `FeatureSet` to a KML document
+    ///
+    /// Features are styled with the symbols of `renderer`, if any.
+    pub fn to_kml(&self, renderer: Option<&Renderer>) -> String {
+        let has_z = self.hasZ.unwrap_or(dimension_flags::<N>().0);
+
+        // the distinct symbols of the features and the index of each feature's symbol
+        let mut symbols: Vec<&Symbol> = Vec::new();
+        let styles = self
+            .features
+            .iter()
+            .map(|feature| {
+                let symbol = renderer?.symbol_for_feature(feature)?;
+                let id = match symbols.iter().position(|s| *s == symbol) {
+                    Some(id) => id,
+                    None => {
+                        symbols.push(symbol);
+                        symbols.len() - 1
+                    }
+                };
+                Some(id)
+            })
+            .collect::<Vec<_>>();
+
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<kml xmlns=\"http://www.opengis.net/kml/2.2\"><Document>",
+        );
+        for (id, symbol) in symbols.iter().enumerate() {
+            write_style(&mut out, id, symbol);
+        }
+        for (feature, style) in self.features.iter().zip(styles) {
+            write_placemark(
+                &mut out,
+                feature,
+                self.displayFieldName.as_deref(),
+                style,
+                has_z,
+            );
+        }
+        out.push_str("</Document></kml>\n");
+        out
+    }
+
+    /// Write the `FeatureSet` as a KMZ, a zip archive containing the KML as `doc.kml`
+    ///
+    /// Returns the writer once the archive is finished.
+    pub fn write_kmz<W: Write + Seek>(
+        &self,
+        writer: W,
+        renderer: Option<&Renderer>,
+    ) -> Result<W, KmlError> {
+        let mut zip = ZipWriter::new(writer);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.start_file("doc.kml", options)?;
+        zip.write_all(self.to_kml(renderer).as_bytes())?;
+        Ok(zip.finish()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_kml() {
+        let fset: FeatureSet<2> = serde_json::from_value(json!({
+            "features": [
+                {
+                    "geometry": {"rings": [
+                        [[0, 0], [0, 10], [10, 10], [10, 0], [0, 0]],
+                        [[4, 4], [6, 4], [6, 6], [4, 6], [4, 4]],
+                        [[20, 20], [20, 30], [30, 30], [20, 20]]
+                    ]},
+                    "attributes": {"kind": "park", "note": "a < b & c"}
+                },
+                {"geometry": {"paths": [[[0, 0], [1, 1]]]}, "attributes": {"kind": "road"}}
+            ]
+        }))
+        .unwrap();
+
+        let renderer: Renderer = serde_json::from_value(json!({
+            "type": "uniqueValue",
+            "field1": "kind",
+            "uniqueValueInfos": [
+                {"value": "park", "symbol": {"type": "esriSFS", "color": [0, 128, 255, 255]}},
+                {"value": "road", "symbol": {"type": "esriSLS", "color": [255, 0, 0, 128], "width": 1.5}}
+            ]
+        }))
+        .unwrap();
+
+        let kml = fset.to_kml(Some(&renderer));
+        assert!(kml.contains(
+            "<Style id=\"style0\"><PolyStyle><color>ffff8000</color><outline>0</outline></PolyStyle></Style>"
+        ));
+        assert!(kml.contains(
+            "<Style id=\"style1\"><LineStyle><color>800000ff</color><width>2</width></LineStyle></Style>"
+        ));
+        assert!(kml.contains("<value>a &lt; b &amp; c</value>"));
+
+        // two polygons, the first with a hole
+        assert_eq!(kml.matches("<Polygon>").count(), 2);
+        assert_eq!(kml.matches("<innerBoundaryIs>").count(), 1);
+        assert!(kml.contains(
+            "<styleUrl>#style1</styleUrl><ExtendedData><Data name=\"kind\"><value>road</value></Data></ExtendedData><LineString><coordinates>0,0 1,1</coordinates></LineString>"
+        ));
+
+        let kmz = fset
+            .write_kmz(std::io::Cursor::new(Vec::new()), None)
+            .unwrap()
+            .into_inner();
+        assert_eq!(&kmz[..2], b"PK");
+    }
+}
@@ -1,11 +1,79 @@
 #![doc = include_str!("../README.md")]
 
+// lets the derive macros refer to `::serde_esri` inside this crate
+#[cfg(feature = "derive")]
+extern crate self as serde_esri;
+
+pub mod attribute;
+#[cfg(any(
+    feature = "places-client",
+    feature = "feature-service-client",
+    feature = "geometry-service-client",
+    feature = "geocode-client",
+    feature = "routing-client",
+    feature = "image-service-client",
+    feature = "tile-client",
+    feature = "keyring"
+))]
+pub mod auth;
+#[cfg(feature = "http-cache")]
+pub mod cache;
+pub mod canonical;
+pub mod date;
+pub mod de;
 mod de_array;
+pub mod domain;
+pub mod error;
+pub mod expression;
+pub mod feature_service;
 pub mod features;
 pub mod field_type;
+pub mod geocode;
 pub mod geometry;
+pub mod geometry_service;
+#[cfg(any(
+    feature = "places-client",
+    feature = "feature-service-client",
+    feature = "geometry-service-client",
+    feature = "geocode-client",
+    feature = "routing-client",
+    feature = "image-service-client",
+    feature = "tile-client"
+))]
+pub mod http;
+pub mod image_service;
+#[cfg(feature = "pbf")]
+pub mod pbf;
 pub mod places;
+pub mod portal;
+pub mod progress;
+#[cfg(feature = "replay")]
+pub mod replay;
+mod rings;
+pub mod routing;
 pub mod spatial_reference;
+pub mod symbology;
+pub mod tiles;
+#[cfg(all(
+    any(
+        feature = "places-client",
+        feature = "feature-service-client",
+        feature = "geometry-service-client",
+        feature = "geocode-client",
+        feature = "routing-client",
+        feature = "image-service-client",
+        feature = "tile-client"
+    ),
+    not(target_arch = "wasm32")
+))]
+mod trace;
+pub mod validate;
+pub mod webmap;
+#[cfg(feature = "wkb")]
+pub mod wkb;
+#[cfg(all(feature = "gpkg", not(feature = "wkb")))]
+#[allow(dead_code)]
+mod wkb;
 // feature flag: geo-types
 #[cfg(feature = "geo")]
 pub mod geo_types;
@@ -16,6 +84,45 @@ pub mod arrow_compat;
 #[cfg(feature = "geoarrow")]
 mod geoarrow_compat;
 
-#[cfg(feature = "from-geo")]
+#[cfg(feature = "parquet")]
+pub mod parquet;
+
+#[cfg(feature = "rstar")]
+mod rstar_compat;
+
+#[cfg(feature = "geo-traits")]
+mod geo_traits_compat;
+
+#[cfg(feature = "wkt")]
+pub mod wkt;
+
+#[cfg(feature = "shapefile")]
+pub mod shapefile;
+
+#[cfg(feature = "csv")]
+pub mod csv;
+
+#[cfg(feature = "kml")]
+pub mod kml;
+
+#[cfg(feature = "geojson")]
+pub mod geojson;
+
+#[cfg(feature = "geozero")]
+pub mod geozero;
+
+#[cfg(feature = "ogc")]
+pub mod ogc;
+
+#[cfg(feature = "gpkg")]
+pub mod gpkg;
+
+#[cfg(feature = "polars")]
+pub mod polars;
+
+#[cfg(any(feature = "geo", feature = "from-geo"))]
 #[allow(clippy::from_over_into)]
 pub mod geo;
+
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
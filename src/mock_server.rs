@@ -0,0 +1,421 @@
+//! An embedded mock ArcGIS server for end-to-end tests.
+//!
+//! [`MockServer`] listens on a local port and serves fixture data over plain
+//! HTTP, so the clients of this crate, or of a downstream application, can be
+//! tested without network access. It emulates:
+//!
+//! - `GET {layer}/query` of a feature layer, paginated with `resultOffset` and
+//!   `resultRecordCount` up to a `maxRecordCount`. `returnCountOnly` is supported.
+//! - `GET /places/near-point` and `GET /places/within-extent`, paginated with
+//!   `offset` and `pageSize` and linked with `pagination.nextUrl`.
+//! - Token errors. When a token is set, requests without it in the
+//!   `X-Esri-Authorization` header or the `token` parameter fail with a 498
+//!   `Invalid token.` error, or a 401 error from the places endpoints.
+//!
+//! The fixtures are returned as is: `where` clauses, geometries, and search
+//! locations are not evaluated.
+//!
+//! ```
+//! use serde_esri::mock_server::{Fixtures, MockServer};
+//! use serde_json::json;
+//!
+//! let fixtures = Fixtures::default()
+//!     .with_layer("/FeatureServer/0", json!({"features": [{"attributes": {"OBJECTID": 1}}]}))
+//!     .with_token("secret");
+//!
+//! let server = MockServer::start(fixtures).unwrap();
+//! let layer_url = format!("{}/FeatureServer/0", server.url());
+//! ```
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+/// The data served by a [`MockServer`]
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    /// `FeatureSet`s served by the `query` endpoint of each layer, keyed by the layer's path
+    pub layers: HashMap<String, Value>,
+    /// `PlaceResult`s served by the `/places/near-point` and `/places/within-extent` endpoints
+    pub places: Vec<Value>,
+    /// The token that requests must include. Requests are not authorized when `None`.
+    pub token: Option<String>,
+    /// The maximum number of features returned by a single query. Default value: 2000
+    pub max_record_count: usize,
+    /// The number of places returned per page when `pageSize` is omitted. Default value: 10
+    pub page_size: usize,
+}
+
+impl Default for Fixtures {
+    fn default() -> Self {
+        Self {
+            layers: HashMap::new(),
+            places: vec![],
+            token: None,
+            max_record_count: 2000,
+            page_size: 10,
+        }
+    }
+}
+
+impl Fixtures {
+    /// Serve `feature_set` from `{path}/query`, e.g. with a path of `/FeatureServer/0`
+    pub fn with_layer(mut self, path: &str, feature_set: Value) -> Self {
+        self.layers
+            .insert(path.trim_end_matches('/').to_string(), feature_set);
+        self
+    }
+
+    /// Serve `places` from the places endpoints
+    pub fn with_places(mut self, places: Vec<Value>) -> Self {
+        self.places = places;
+        self
+    }
+
+    /// Require `token` on every request
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Set the maximum number of features returned by a single query
+    pub fn with_max_record_count(mut self, max_record_count: usize) -> Self {
+        self.max_record_count = max_record_count;
+        self
+    }
+}
+
+/// A local HTTP server emulating ArcGIS endpoints
+///
+/// The server runs on a background thread until it is dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Start serving `fixtures` on a free port of `127.0.0.1`
+    pub fn start(fixtures: Fixtures) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let stop = shutdown.clone();
+        let base_url = format!("http://{addr}");
+        let handle = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                // a failed connection only affects the request that made it
+                if let Ok(stream) = stream {
+                    let _ = handle_connection(stream, &fixtures, &base_url);
+                }
+            }
+        });
+
+        Ok(Self {
+            addr,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    /// The base URL of the server, e.g. `http://127.0.0.1:50123`
+    ///
+    /// Use it as the base URL of a `PlacesClient` or as the prefix of a layer URL.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // wake the listener so it sees the shutdown flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// a parsed HTTP request
+struct Request {
+    path: String,
+    params: HashMap<String, String>,
+    authorization: Option<String>,
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    fixtures: &Fixtures,
+    base_url: &str,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let request = read_request(&mut reader)?;
+    let (status, body) = respond(&request, fixtures, base_url);
+
+    let body = body.to_string();
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        if status == 200 { "OK" } else { "Error" },
+        body.len(),
+    )?;
+    stream.flush()
+}
+
+fn read_request(reader: &mut impl BufRead) -> std::io::Result<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let target = line.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+    let mut authorization = None;
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "x-esri-authorization" => authorization = Some(value.to_string()),
+                "content-length" => content_length = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+    }
+
+    // form encoded bodies are parsed like query strings
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let mut params = parse_params(query);
+    params.extend(parse_params(&String::from_utf8_lossy(&body)));
+
+    Ok(Request {
+        path: path.trim_end_matches('/').to_string(),
+        params,
+        authorization,
+    })
+}
+
+fn parse_params(s: &str) -> HashMap<String, String> {
+    s.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(k), decode(v))
+        })
+        .collect()
+}
+
+// decodes `application/x-www-form-urlencoded` text
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    Err(_) => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn respond(request: &Request, fixtures: &Fixtures, base_url: &str) -> (u16, Value) {
+    let places = request.path.starts_with("/places/");
+
+    if let Some(token) = &fixtures.token {
+        let bearer = format!("Bearer {token}");
+        let authorized = request.authorization.as_deref() == Some(bearer.as_str())
+            || request.params.get("token") == Some(token);
+        if !authorized && places {
+            return (401, places_error(401, "Invalid token."));
+        } else if !authorized {
+            return (200, error(498, "Invalid token."));
+        }
+    }
+
+    if places {
+        return match request.path.as_str() {
+            "/places/near-point" | "/places/within-extent" => {
+                (200, place_page(request, fixtures, base_url))
+            }
+            _ => (404, places_error(404, "Not found.")),
+        };
+    }
+
+    let layer = request
+        .path
+        .strip_suffix("/query")
+        .and_then(|path| fixtures.layers.get(path));
+
+    match layer {
+        Some(fset) => (200, query_page(request, fixtures, fset)),
+        None => (200, error(400, "Invalid URL")),
+    }
+}
+
+fn query_page(request: &Request, fixtures: &Fixtures, fset: &Value) -> Value {
+    let features = fset["features"].as_array().cloned().unwrap_or_default();
+
+    if request.params.get("returnCountOnly").map(String::as_str) == Some("true") {
+        return json!({ "count": features.len() });
+    }
+
+    let param = |name: &str| {
+        request
+            .params
+            .get(name)
+            .and_then(|x| x.parse::<usize>().ok())
+    };
+    let offset = param("resultOffset").unwrap_or(0);
+    let count = param("resultRecordCount")
+        .unwrap_or(fixtures.max_record_count)
+        .min(fixtures.max_record_count);
+
+    let page = features
+        .iter()
+        .skip(offset)
+        .take(count)
+        .cloned()
+        .collect::<Vec<_>>();
+    let exceeded = offset + page.len() < features.len();
+
+    let mut res = fset.as_object().cloned().unwrap_or_else(Map::new);
+    res.insert("features".into(), Value::Array(page));
+    res.insert("exceededTransferLimit".into(), Value::Bool(exceeded));
+    Value::Object(res)
+}
+
+fn place_page(request: &Request, fixtures: &Fixtures, base_url: &str) -> Value {
+    let param = |name: &str| {
+        request
+            .params
+            .get(name)
+            .and_then(|x| x.parse::<usize>().ok())
+    };
+    let offset = param("offset").unwrap_or(0);
+    let page_size = param("pageSize").unwrap_or(fixtures.page_size).max(1);
+
+    let results = fixtures
+        .places
+        .iter()
+        .skip(offset)
+        .take(page_size)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let next_offset = offset + results.len();
+    let next_url = (next_offset < fixtures.places.len()).then(|| {
+        let mut query = request
+            .params
+            .iter()
+            .filter(|(k, _)| k.as_str() != "offset")
+            .map(|(k, v)| format!("{k}={}", encode(v)))
+            .collect::<Vec<_>>();
+        query.sort();
+        query.push(format!("offset={next_offset}"));
+        format!("{base_url}{}?{}", request.path, query.join("&"))
+    });
+
+    json!({
+        "results": results,
+        "pagination": { "nextUrl": next_url },
+    })
+}
+
+fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn error(code: u16, message: &str) -> Value {
+    json!({ "error": { "code": code, "message": message, "details": [] } })
+}
+
+fn places_error(code: u16, message: &str) -> Value {
+    json!({
+        "error": {
+            "code": code,
+            "message": message,
+            "details": [],
+            "restInfoUrl": "https://places-api.arcgis.com/arcgis/rest/info",
+        }
+    })
+}
+
+#[cfg(all(test, feature = "feature-service-client"))]
+mod tests {
+    use super::*;
+    use crate::{
+        feature_service::{FeatureLayerClient, FeatureQueryParams, FeatureServiceError},
+        geometry::EsriEnvelope,
+    };
+
+    #[test]
+    fn test_mock_query() {
+        let features = (1..=5)
+            .map(|id| json!({"attributes": {"OBJECTID": id}}))
+            .collect::<Vec<_>>();
+        let fixtures = Fixtures::default()
+            .with_layer(
+                "/FeatureServer/0",
+                json!({"objectIdFieldName": "OBJECTID", "features": features}),
+            )
+            .with_token("secret")
+            .with_max_record_count(2);
+        let server = MockServer::start(fixtures).unwrap();
+        let url = format!("{}/FeatureServer/0", server.url());
+
+        let params = FeatureQueryParams::default();
+        let res = FeatureLayerClient::new(&url).query::<2>(&params);
+        assert!(matches!(res, Err(FeatureServiceError::ApiError(e)) if e.error.code == 498));
+
+        let client = FeatureLayerClient::new(&url).with_token("secret");
+        assert_eq!(client.query_pages::<2>(&params).count(), 3);
+        assert_eq!(client.query_all::<2>(&params).unwrap().features.len(), 5);
+        assert_eq!(client.query_features::<2>(&params).count(), 5);
+
+        // every tile returns every feature, so they must be deduplicated
+        let extent = EsriEnvelope {
+            xmax: 2.0,
+            ymax: 2.0,
+            ..Default::default()
+        };
+        let res = client.query_tiled::<2>(&params, &extent, 1.0, 1.0);
+        assert_eq!(res.unwrap().features.len(), 5);
+    }
+}
@@ -0,0 +1,248 @@
+//! Translation layer between [OGC API - Features](https://ogcapi.ogc.org/features/) and feature layer queries.
+//!
+//! This module is enabled by the `ogc` feature. It lets a feature service layer be
+//! exposed through an OGC API - Features `/items` endpoint:
+//!
+//! 1. parse the request's query string with [`OgcItemsQuery::from_query_pairs()`]
+//! 2. translate it into [`FeatureQueryParams`] with [`OgcItemsQuery::to_query_params()`]
+//! 3. query the layer and convert the resulting `FeatureSet` with [`OgcItemsQuery::to_feature_collection()`]
+//!
+//! Coordinates are always requested in WGS84 (`outSR=4326`) as required by GeoJSON.
+//! Only `bbox` values in the default CRS84 are supported.
+use crate::{
+    feature_service::{FeatureQueryParams, SpatialRel},
+    features::FeatureSet,
+    geometry::{EsriEnvelope, EsriGeometry},
+    spatial_reference::SpatialReference,
+};
+use chrono::DateTime;
+use geojson::{FeatureCollection, JsonObject};
+use serde_json::json;
+
+/// Errors that can occur when parsing OGC API - Features parameters
+#[derive(Debug, Clone, PartialEq)]
+pub enum OgcError {
+    /// `bbox` must be 4 or 6 comma separated numbers
+    InvalidBbox(String),
+    /// `datetime` must be an RFC 3339 instant or an interval of two instants separated by `/`
+    InvalidDatetime(String),
+    /// `limit` and `offset` must be non-negative integers
+    InvalidInteger(String),
+}
+
+impl std::fmt::Display for OgcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidBbox(x) => write!(f, "invalid bbox: {x}"),
+            Self::InvalidDatetime(x) => write!(f, "invalid datetime: {x}"),
+            Self::InvalidInteger(x) => write!(f, "invalid limit or offset: {x}"),
+        }
+    }
+}
+
+impl std::error::Error for OgcError {}
+
+/// The default number of features returned when `limit` is not provided
+pub const DEFAULT_LIMIT: u64 = 10;
+
+/// The query parameters of an OGC API - Features [`/collections/{collectionId}/items`](https://docs.ogc.org/is/17-069r4/17-069r4.html#_items_) request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OgcItemsQuery {
+    /// The bounding box as `[minx, miny, maxx, maxy]` in CRS84
+    pub bbox: Option<[f64; 4]>,
+    /// An RFC 3339 instant, e.g. `2018-02-12T23:20:50Z`, or an interval such as
+    /// `2018-02-12T00:00:00Z/..`
+    pub datetime: Option<String>,
+    /// The maximum number of features to return
+    pub limit: Option<u64>,
+    /// The number of features to skip
+    pub offset: Option<u64>,
+}
+
+impl OgcItemsQuery {
+    /// Parse the query parameters of an `/items` request.
+    ///
+    /// Unknown parameters are ignored. Six value (3D) bounding boxes are
+    /// accepted but their elevation is ignored.
+    pub fn from_query_pairs<K, V>(pairs: impl IntoIterator<Item = (K, V)>) -> Result<Self, OgcError>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut query = Self::default();
+
+        for (k, v) in pairs {
+            let v = v.as_ref();
+            match k.as_ref() {
+                "bbox" => query.bbox = Some(parse_bbox(v)?),
+                "datetime" => {
+                    // validate eagerly so errors surface before the request is made
+                    parse_datetime(v)?;
+                    query.datetime = Some(v.to_string());
+                }
+                "limit" => query.limit = Some(parse_integer(v)?),
+                "offset" => query.offset = Some(parse_integer(v)?),
+                _ => {}
+            }
+        }
+
+        Ok(query)
+    }
+
+    /// Translate the OGC parameters into feature layer query parameters.
+    ///
+    /// - `bbox` becomes an envelope `geometry` with `inSR=4326`
+    /// - `datetime` becomes `time` in epoch milliseconds
+    /// - `limit` and `offset` become `resultRecordCount` and `resultOffset`
+    pub fn to_query_params(&self) -> Result<FeatureQueryParams, OgcError> {
+        let geometry = self.bbox.map(|[xmin, ymin, xmax, ymax]| {
+            EsriGeometry::Envelope(EsriEnvelope {
+                xmin,
+                ymin,
+                xmax,
+                ymax,
+                spatialReference: Some(wgs84()),
+                ..Default::default()
+            })
+        });
+
+        let time = self.datetime.as_deref().map(parse_datetime).transpose()?;
+
+        Ok(FeatureQueryParams {
+            in_sr: geometry.as_ref().map(|_| 4326),
+            spatial_rel: geometry.as_ref().map(|_| SpatialRel::Intersects),
+            geometry,
+            time,
+            out_sr: Some(4326),
+            return_geometry: Some(true),
+            result_offset: self.offset,
+            result_record_count: Some(self.limit.unwrap_or(DEFAULT_LIMIT)),
+            ..Default::default()
+        })
+    }
+
+    /// Convert a `FeatureSet` returned by the translated query into an OGC
+    /// API - Features response.
+    ///
+    /// `items_url` is the URL of the `/items` endpoint and is used to create
+    /// the `self` and `next` links. A `next` link is added whenever a full page
+    /// of features was returned.
+    pub fn to_feature_collection<const N: usize>(
+        &self,
+        x: &FeatureSet<N>,
+        items_url: &str,
+    ) -> FeatureCollection {
+        let mut fc = FeatureCollection::from(x);
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        let offset = self.offset.unwrap_or(0);
+
+        let mut links = vec![json!({
+            "href": self.items_href(items_url, offset),
+            "rel": "self",
+            "type": "application/geo+json",
+        })];
+
+        if fc.features.len() as u64 >= limit {
+            links.push(json!({
+                "href": self.items_href(items_url, offset + limit),
+                "rel": "next",
+                "type": "application/geo+json",
+            }));
+        }
+
+        let mut members = JsonObject::new();
+        members.insert("numberReturned".into(), json!(fc.features.len()));
+        members.insert("links".into(), json!(links));
+        fc.foreign_members = Some(members);
+        fc
+    }
+
+    // the url of the items endpoint with these parameters at a given offset
+    fn items_href(&self, items_url: &str, offset: u64) -> String {
+        let mut params = vec![
+            "f=json".to_string(),
+            format!("limit={}", self.limit.unwrap_or(DEFAULT_LIMIT)),
+            format!("offset={offset}"),
+        ];
+
+        if let Some([xmin, ymin, xmax, ymax]) = self.bbox {
+            params.push(format!("bbox={xmin},{ymin},{xmax},{ymax}"));
+        }
+        if let Some(datetime) = &self.datetime {
+            params.push(format!("datetime={datetime}"));
+        }
+
+        format!("{items_url}?{}", params.join("&"))
+    }
+}
+
+fn wgs84() -> SpatialReference {
+    SpatialReference {
+        wkid: Some(4326),
+        ..Default::default()
+    }
+}
+
+fn parse_bbox(x: &str) -> Result<[f64; 4], OgcError> {
+    let invalid = || OgcError::InvalidBbox(x.to_string());
+
+    let values = x
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+
+    match values.as_slice() {
+        [xmin, ymin, xmax, ymax] => Ok([*xmin, *ymin, *xmax, *ymax]),
+        [xmin, ymin, _, xmax, ymax, _] => Ok([*xmin, *ymin, *xmax, *ymax]),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_integer(x: &str) -> Result<u64, OgcError> {
+    x.parse::<u64>()
+        .map_err(|_| OgcError::InvalidInteger(x.to_string()))
+}
+
+// converts an OGC datetime into the Esri `time` parameter
+fn parse_datetime(x: &str) -> Result<String, OgcError> {
+    let millis = |instant: &str| -> Result<String, OgcError> {
+        match instant {
+            "" | ".." => Ok("null".to_string()),
+            _ => DateTime::parse_from_rfc3339(instant)
+                .map(|dt| dt.timestamp_millis().to_string())
+                .map_err(|_| OgcError::InvalidDatetime(x.to_string())),
+        }
+    };
+
+    match x.split_once('/') {
+        Some((start, end)) => Ok(format!("{},{}", millis(start)?, millis(end)?)),
+        None if x == ".." => Err(OgcError::InvalidDatetime(x.to_string())),
+        None => millis(x),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items_query_translation() {
+        let query = OgcItemsQuery::from_query_pairs([
+            ("bbox", "-180,-90,180,90"),
+            ("datetime", "2018-02-12T00:00:00Z/.."),
+            ("limit", "100"),
+            ("f", "json"),
+        ])
+        .unwrap();
+
+        let params = query.to_query_params().unwrap();
+        assert_eq!(params.time.as_deref(), Some("1518393600000,null"));
+        assert_eq!(params.result_record_count, Some(100));
+        assert_eq!(params.in_sr, Some(4326));
+        assert!(matches!(params.geometry, Some(EsriGeometry::Envelope(_))));
+
+        assert!(OgcItemsQuery::from_query_pairs([("bbox", "1,2,3")]).is_err());
+        assert!(OgcItemsQuery::from_query_pairs([("datetime", "yesterday")]).is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! Optional feature for writing and reading GeoParquet files
+//!
+//! This module is enabled by the `parquet` feature. [`write_geoparquet()`] writes a
+//! `FeatureSet` to a [GeoParquet](https://geoparquet.org) file with a column for each
+//! field and the geometries as WKB in a `geometry` column. [`read_geoparquet()`] reads
+//! the features of a GeoParquet file whose primary geometry column is WKB.
+//!
+//! Geometries are encoded as with the `wkb` feature, e.g. polygons as MultiPolygons.
+//! The spatial reference is written as the `crs` of the column using its (latest) WKID
+//! as an EPSG code. A column without a `crs` is read as WGS 84 (WKID 4326).
+//!
+//! ```
+//! use serde_esri::{features::FeatureSet, parquet::{read_geoparquet, write_geoparquet}};
+//!
+//! let json = r#"{
+//!     "geometryType": "esriGeometryPoint",
+//!     "spatialReference": {"wkid": 4326},
+//!     "fields": [{"name": "name", "type": "esriFieldTypeString"}],
+//!     "features": [{"attributes": {"name": "a"}, "geometry": {"x": 1, "y": 2}}]
+//! }"#;
+//! let fset: FeatureSet<2> = serde_json::from_str(json).unwrap();
+//!
+//! let path = std::env::temp_dir().join("serde_esri_example.parquet");
+//! write_geoparquet(std::fs::File::create(&path).unwrap(), fset).unwrap();
+//!
+//! let fset = read_geoparquet::<_, 2>(std::fs::File::open(&path).unwrap()).unwrap();
+//! assert_eq!(fset.features[0].attributes.as_ref().unwrap()["name"], "a");
+//! ```
+use crate::{
+    arrow_compat::{arrow_to_featureset, featureset_to_arrow, ArrowConversionError},
+    features::{infer_fields, FeatureSet},
+    geometry::dimension_flags,
+    spatial_reference::SpatialReference,
+    wkb::to_wkb,
+};
+use arrow::{
+    array::{ArrayRef, BinaryBuilder},
+    datatypes::{DataType, Field, SchemaBuilder},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use parquet::{
+    arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter},
+    errors::ParquetError,
+    file::reader::ChunkReader,
+};
+use serde_json::{json, Value};
+use std::{io::Write, sync::Arc};
+
+/// The name of the geometry column written by [`write_geoparquet()`]
+pub const GEOMETRY_COLUMN: &str = "geometry";
+
+/// Errors that can occur when writing or reading GeoParquet files
+#[derive(Debug)]
+pub enum GeoParquetError {
+    /// The file has no `geo` metadata or it is not valid
+    InvalidMetadata(String),
+    /// The primary geometry column is not WKB
+    UnsupportedEncoding(String),
+    ConversionError(ArrowConversionError),
+    ArrowError(ArrowError),
+    ParquetError(ParquetError),
+}
+
+impl std::fmt::Display for GeoParquetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidMetadata(e) => write!(f, "invalid GeoParquet metadata: {e}"),
+            Self::UnsupportedEncoding(encoding) => {
+                write!(f, "unsupported geometry encoding {encoding}")
+            }
+            Self::ConversionError(e) => write!(f, "{e}"),
+            Self::ArrowError(e) => write!(f, "{e}"),
+            Self::ParquetError(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for GeoParquetError {}
+
+impl From<ArrowError> for GeoParquetError {
+    fn from(e: ArrowError) -> Self {
+        Self::ArrowError(e)
+    }
+}
+
+impl From<ParquetError> for GeoParquetError {
+    fn from(e: ParquetError) -> Self {
+        Self::ParquetError(e)
+    }
+}
+
+// the GeoParquet geometry type of the WKB of an Esri geometry type
+fn wkb_geometry_type<const N: usize>(geometry_type: &str) -> Option<String> {
+    let base = match geometry_type {
+        "esriGeometryPoint" => "Point",
+        "esriGeometryMultipoint" => "MultiPoint",
+        "esriGeometryPolyline" => "MultiLineString",
+        "esriGeometryPolygon" | "esriGeometryMultiPatch" => "MultiPolygon",
+        // envelopes are 2D
+        "esriGeometryEnvelope" => return Some("Polygon".to_string()),
+        _ => return None,
+    };
+    let suffix = match dimension_flags::<N>() {
+        (true, true) => " ZM",
+        (true, false) => " Z",
+        (false, true) => " M",
+        (false, false) => "",
+    };
+    Some(format!("{base}{suffix}"))
+}
+
+// the `geo` metadata of the geometry column
+fn geo_metadata<const N: usize>(fset: &FeatureSet<N>) -> Value {
+    let geometry_types: Vec<String> = fset
+        .geometryType
+        .as_deref()
+        .and_then(wkb_geometry_type::<N>)
+        .into_iter()
+        .collect();
+
+    let mut column = json!({
+        "encoding": "WKB",
+        "geometry_types": geometry_types,
+    });
+    let crs = fset
+        .spatialReference
+        .as_ref()
+        .map(|sr| match sr.normalized_wkid() {
+            Some(wkid) => json!({"id": {"authority": "EPSG", "code": wkid}}),
+            None => Value::Null,
+        });
+    if let Some(crs) = crs {
+        column["crs"] = crs;
+    }
+
+    json!({
+        "version": "1.1.0",
+        "primary_column": GEOMETRY_COLUMN,
+        "columns": {GEOMETRY_COLUMN: column},
+    })
+}
+
+/// Write `fset` to a GeoParquet file and return the underlying writer
+///
+/// The attributes are written as columns based on the `fields` of `fset`. If it has
+/// no `fields`, they are inferred from the attributes of its features.
+pub fn write_geoparquet<W: Write + Send, const N: usize>(
+    writer: W,
+    mut fset: FeatureSet<N>,
+) -> Result<W, GeoParquetError> {
+    if fset.fields.is_none() {
+        fset.fields = Some(infer_fields(&fset.features));
+    }
+    let metadata = geo_metadata(&fset);
+
+    let mut geometries = BinaryBuilder::new();
+    for feature in &fset.features {
+        geometries.append_option(feature.geometry.as_ref().map(to_wkb));
+    }
+    let geometries: ArrayRef = Arc::new(geometries.finish());
+
+    // the attribute columns, without the geoarrow geometry column
+    let attributes = featureset_to_arrow(FeatureSet {
+        geometryType: None,
+        ..fset
+    })?;
+
+    let mut schema = SchemaBuilder::from(attributes.schema().fields());
+    schema.push(Field::new(GEOMETRY_COLUMN, DataType::Binary, true));
+    schema
+        .metadata_mut()
+        .insert("geo".to_string(), metadata.to_string());
+    let mut columns = attributes.columns().to_vec();
+    columns.push(geometries);
+    let batch = RecordBatch::try_new(schema.finish().into(), columns)?;
+
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+    writer.write(&batch)?;
+    Ok(writer.into_inner()?)
+}
+
+// the well-known ID of a PROJJSON `crs`
+fn crs_wkid(crs: &Value) -> Option<u32> {
+    crs.get("id")?.get("code")?.as_u64()?.try_into().ok()
+}
+
+/// Read the features of a GeoParquet file
+///
+/// The primary geometry column must be WKB. Every other column is read as an attribute
+/// as described in [`arrow_to_featureset()`].
+pub fn read_geoparquet<R: ChunkReader + 'static, const N: usize>(
+    reader: R,
+) -> Result<FeatureSet<N>, GeoParquetError> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(reader)?;
+
+    let metadata = builder
+        .schema()
+        .metadata()
+        .get("geo")
+        .ok_or_else(|| GeoParquetError::InvalidMetadata("no `geo` metadata".to_string()))?;
+    let metadata: Value = serde_json::from_str(metadata)
+        .map_err(|e| GeoParquetError::InvalidMetadata(e.to_string()))?;
+    let primary_column = metadata["primary_column"]
+        .as_str()
+        .ok_or_else(|| GeoParquetError::InvalidMetadata("no `primary_column`".to_string()))?
+        .to_string();
+    let column = &metadata["columns"][&primary_column];
+    match column["encoding"].as_str() {
+        Some("WKB") => {}
+        encoding => {
+            let encoding = encoding.unwrap_or_default().to_string();
+            return Err(GeoParquetError::UnsupportedEncoding(encoding));
+        }
+    }
+    let spatial_reference = match column.get("crs") {
+        None => Some(SpatialReference::from_wkid(4326)),
+        Some(crs) => crs_wkid(crs).map(SpatialReference::from_wkid),
+    };
+
+    let mut res: Option<FeatureSet<N>> = None;
+    for batch in builder.build()? {
+        let fset = arrow_to_featureset::<N>(&batch?, Some(&primary_column), None)
+            .map_err(GeoParquetError::ConversionError)?;
+        match res.as_mut() {
+            Some(res) => {
+                res.geometryType = res.geometryType.take().or(fset.geometryType);
+                res.features.extend(fset.features);
+            }
+            None => res = Some(fset),
+        }
+    }
+
+    let (has_z, has_m) = dimension_flags::<N>();
+    let res = res.unwrap_or_else(|| FeatureSet {
+        hasZ: Some(has_z),
+        hasM: Some(has_m),
+        ..Default::default()
+    });
+    Ok(FeatureSet {
+        spatialReference: spatial_reference,
+        ..res
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn test_geoparquet_round_trip() {
+        let json = r#"{
+            "geometryType": "esriGeometryPolygon",
+            "hasZ": true,
+            "spatialReference": {"wkid": 102100},
+            "fields": [
+                {"name": "OBJECTID", "type": "esriFieldTypeOID"},
+                {"name": "name", "type": "esriFieldTypeString"},
+                {"name": "area", "type": "esriFieldTypeDouble"}
+            ],
+            "features": [
+                {
+                    "attributes": {"OBJECTID": 1, "name": "square", "area": 100.0},
+                    "geometry": {"rings": [
+                        [[0, 0, 1], [0, 10, 1], [10, 10, 1], [10, 0, 1], [0, 0, 1]],
+                        [[2, 2, 1], [4, 2, 1], [4, 4, 1], [2, 4, 1], [2, 2, 1]]
+                    ]}
+                },
+                {"attributes": {"OBJECTID": 2, "name": null, "area": null}}
+            ]
+        }"#;
+        let fset: FeatureSet<3> = serde_json::from_str(json).unwrap();
+        let path = std::env::temp_dir().join("serde_esri_test_geoparquet.parquet");
+        write_geoparquet(File::create(&path).unwrap(), fset).unwrap();
+
+        // the file has GeoParquet metadata
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let metadata: Value = serde_json::from_str(&builder.schema().metadata()["geo"]).unwrap();
+        assert_eq!(metadata["primary_column"], "geometry");
+        let column = &metadata["columns"]["geometry"];
+        assert_eq!(column["encoding"], "WKB");
+        assert_eq!(column["geometry_types"][0], "MultiPolygon Z");
+        assert_eq!(column["crs"]["id"]["code"], 3857);
+
+        let fset = read_geoparquet::<_, 3>(File::open(&path).unwrap()).unwrap();
+        assert_eq!(fset.geometryType.as_deref(), Some("esriGeometryPolygon"));
+        assert_eq!(fset.spatialReference.unwrap().wkid, Some(3857));
+        assert_eq!(fset.features.len(), 2);
+
+        let attributes = fset.features[0].attributes.as_ref().unwrap();
+        assert_eq!(attributes["name"], "square");
+        assert_eq!(attributes["area"], 100.0);
+        let polygon = fset.features[0].geometry.clone().unwrap();
+        let polygon = polygon.as_polygon().unwrap();
+        assert_eq!(polygon.rings.len(), 2);
+        assert_eq!(polygon.rings[1].0[1].0, [4.0, 2.0, 1.0]);
+
+        assert!(fset.features[1].geometry.is_none());
+        assert!(fset.features[1].attributes.as_ref().unwrap()["name"].is_null());
+
+        // the geometries have no M values
+        assert!(matches!(
+            read_geoparquet::<_, 4>(File::open(&path).unwrap()),
+            Err(GeoParquetError::ConversionError(_))
+        ));
+    }
+}
@@ -0,0 +1,388 @@
+//! Decoding of the Protocol Buffers responses of feature services, requested with `f=pbf`.
+//!
+//! The messages of Esri's `FeatureCollection.proto` are decoded with
+//! [`FeatureCollectionPBuffer::decode()`], and [`decode()`] converts a response into a
+//! [`FeatureSet`] with real-world coordinates. The coordinates of every geometry are
+//! quantized with the `transform` of the result, and the x and y values of each vertex
+//! are the difference from the vertex before it.
+//!
+//! With the `feature-service-client` feature, `FeatureLayerClient::query_pbf()` queries a
+//! layer with `f=pbf`.
+use crate::{
+    feature_service::OriginPosition,
+    features::{Feature as EsriFeature, FeatureSet},
+    geometry::{
+        EsriCoord, EsriGeometry, EsriLineString, EsriMultiPoint, EsriPoint, EsriPolygon,
+        EsriPolyline,
+    },
+};
+use serde_json::Map;
+
+mod proto;
+pub use proto::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PbfError {
+    /// The message ends in the middle of a field
+    Truncated,
+    /// The message is not a valid `FeatureCollectionPBuffer`
+    Invalid(&'static str),
+    /// The message uses a part of the schema that is not decoded
+    Unsupported(&'static str),
+    /// The response is a count or a list of object IDs rather than features
+    NoFeatures,
+    /// The geometries have a different number of values per coordinate than `N`
+    Dimension { expected: usize, found: usize },
+}
+
+impl std::fmt::Display for PbfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "message is truncated"),
+            Self::Invalid(reason) => write!(f, "invalid message: {reason}"),
+            Self::Unsupported(what) => write!(f, "{what} are not supported"),
+            Self::NoFeatures => write!(f, "response does not contain features"),
+            Self::Dimension { expected, found } => write!(
+                f,
+                "geometries have {found} values per coordinate but {expected} were expected"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PbfError {}
+
+/// Decode a `f=pbf` query response into a [`FeatureSet`]
+pub fn decode<const N: usize>(buf: &[u8]) -> Result<FeatureSet<N>, PbfError> {
+    match FeatureCollectionPBuffer::decode(buf)?.query_result {
+        Some(QueryResult::FeatureResult(result)) => (*result).into_feature_set(),
+        _ => Err(PbfError::NoFeatures),
+    }
+}
+
+impl FeatureResult {
+    /// The features with their attributes keyed by field name and their geometries dequantized
+    pub fn into_feature_set<const N: usize>(self) -> Result<FeatureSet<N>, PbfError> {
+        let dims = 2 + self.has_z as usize + self.has_m as usize;
+        let features = self
+            .features
+            .iter()
+            .map(|feature| {
+                let attributes = self
+                    .fields
+                    .iter()
+                    .map(|field| field.name.clone())
+                    .zip(feature.attributes.iter().cloned())
+                    .collect::<Map<_, _>>();
+                let geometry = feature
+                    .geometry
+                    .as_ref()
+                    .map(|g| self.geometry::<N>(g, dims))
+                    .transpose()?
+                    .flatten();
+                Ok(EsriFeature {
+                    geometry,
+                    attributes: Some(attributes),
+                    #[cfg(feature = "preserve-unknown")]
+                    extra: Default::default(),
+                })
+            })
+            .collect::<Result<_, PbfError>>()?;
+
+        let name = |name: String| Some(name).filter(|n| !n.is_empty());
+        Ok(FeatureSet {
+            objectIdFieldName: name(self.object_id_field_name),
+            globalIdFieldName: name(self.global_id_field_name),
+            displayFieldName: None,
+            geometryType: self.geometry_type.as_str().map(String::from),
+            spatialReference: self.spatial_reference,
+            hasZ: Some(self.has_z),
+            hasM: Some(self.has_m),
+            fields: Some(self.fields),
+            features,
+            exceededTransferLimit: Some(self.exceeded_transfer_limit),
+            #[cfg(feature = "preserve-unknown")]
+            extra: Default::default(),
+        })
+    }
+
+    // the real-world value of the quantized value `v` of dimension `i`, in the order x, y, m, z
+    fn dequantize(&self, i: usize, v: f64) -> f64 {
+        let Some(transform) = &self.transform else {
+            return v;
+        };
+        let (scale, translate) = (transform.scale[i], transform.translate[i]);
+        match (i, transform.origin_position) {
+            _ if scale == 0.0 => v,
+            (1, OriginPosition::UpperLeft) => translate - scale * v,
+            _ => translate + scale * v,
+        }
+    }
+
+    // the coordinates of each part of the geometry
+    fn parts<const N: usize>(
+        &self,
+        geometry: &Geometry,
+        dims: usize,
+    ) -> Result<Vec<Vec<EsriCoord<N>>>, PbfError> {
+        if !geometry.coords.len().is_multiple_of(dims) {
+            return Err(PbfError::Invalid("coordinates do not match hasZ and hasM"));
+        }
+
+        let mut position = [0_i64; 2];
+        let mut coords = geometry.coords.chunks(dims).map(|vertex| {
+            let mut coord = [0.0; N];
+            for (i, value) in vertex.iter().enumerate() {
+                coord[i] = match i {
+                    0 | 1 => {
+                        position[i] += value;
+                        self.dequantize(i, position[i] as f64)
+                    }
+                    // z comes before m
+                    2 if self.has_z => self.dequantize(3, *value as f64),
+                    _ => self.dequantize(2, *value as f64),
+                };
+            }
+            EsriCoord(coord)
+        });
+
+        if geometry.lengths.is_empty() {
+            return Ok(vec![coords.collect()]);
+        }
+        Ok(geometry
+            .lengths
+            .iter()
+            .map(|len| coords.by_ref().take(*len as usize).collect())
+            .collect())
+    }
+
+    fn geometry<const N: usize>(
+        &self,
+        geometry: &Geometry,
+        dims: usize,
+    ) -> Result<Option<EsriGeometry<N>>, PbfError> {
+        if self.geometry_type == GeometryType::Point {
+            return Ok(self.point(geometry, dims)?.map(EsriGeometry::Point));
+        }
+        if dims != N {
+            return Err(PbfError::Dimension {
+                expected: N,
+                found: dims,
+            });
+        }
+
+        let parts = self.parts::<N>(geometry, dims)?;
+        let lines = || parts.iter().cloned().map(EsriLineString).collect();
+        Ok(Some(match self.geometry_type {
+            GeometryType::Multipoint => EsriGeometry::MultiPoint(EsriMultiPoint {
+                hasZ: None,
+                hasM: None,
+                points: parts.iter().flatten().cloned().collect(),
+                spatialReference: None,
+            }),
+            GeometryType::Polyline => EsriGeometry::Polyline(EsriPolyline {
+                hasZ: None,
+                hasM: None,
+                paths: lines(),
+                spatialReference: None,
+            }),
+            GeometryType::Polygon => EsriGeometry::Polygon(EsriPolygon {
+                hasZ: None,
+                hasM: None,
+                rings: lines(),
+                spatialReference: None,
+            }),
+            GeometryType::Multipatch => return Err(PbfError::Unsupported("multipatches")),
+            GeometryType::Point | GeometryType::None => return Ok(None),
+        }))
+    }
+
+    fn point(&self, geometry: &Geometry, dims: usize) -> Result<Option<EsriPoint>, PbfError> {
+        let coords = &geometry.coords;
+        if coords.len() < dims {
+            return Ok(None);
+        }
+        let value = |i: usize, dim: usize| self.dequantize(dim, coords[i] as f64);
+        Ok(Some(EsriPoint {
+            x: value(0, 0),
+            y: value(1, 1),
+            z: self.has_z.then(|| value(2, 3)),
+            m: self.has_m.then(|| value(2 + self.has_z as usize, 2)),
+            spatialReference: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut v: u64, out: &mut Vec<u8>) {
+        while v >= 0x80 {
+            out.push(v as u8 | 0x80);
+            v >>= 7;
+        }
+        out.push(v as u8);
+    }
+
+    fn message(number: u64, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint(number << 3 | 2, &mut out);
+        varint(payload.len() as u64, &mut out);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn double(number: u64, v: f64) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint(number << 3 | 1, &mut out);
+        out.extend_from_slice(&v.to_le_bytes());
+        out
+    }
+
+    fn packed(number: u64, values: impl IntoIterator<Item = u64>) -> Vec<u8> {
+        let mut payload = Vec::new();
+        values.into_iter().for_each(|v| varint(v, &mut payload));
+        message(number, &payload)
+    }
+
+    #[test]
+    fn test_decode() {
+        let zigzag = |v: i64| ((v << 1) ^ (v >> 63)) as u64;
+
+        let field = [message(1, b"name"), vec![2 << 3, 4]].concat();
+        let transform = [
+            vec![1 << 3, 0],
+            message(2, &[double(1, 0.5), double(2, 0.5)].concat()),
+            message(3, &[double(1, 100.0), double(2, 200.0)].concat()),
+        ]
+        .concat();
+        let geometry = [
+            packed(2, [4]),
+            packed(3, [0, 0, 2, 0, 0, 2, -2, 0].map(zigzag)),
+        ]
+        .concat();
+        let feature = [message(1, &message(1, b"a")), message(2, &geometry)].concat();
+        let result = [
+            message(1, b"OBJECTID"),
+            vec![7 << 3, 3],
+            message(8, &[8, 0xe6, 0x21]),
+            message(12, &transform),
+            message(13, &field),
+            message(15, &feature),
+        ]
+        .concat();
+        let buf = message(2, &message(1, &result));
+
+        let fset = decode::<2>(&buf).unwrap();
+        assert_eq!(fset.geometryType.as_deref(), Some("esriGeometryPolygon"));
+        assert_eq!(fset.spatialReference.unwrap().wkid, Some(4326));
+
+        let feature = &fset.features[0];
+        assert_eq!(feature.attributes.as_ref().unwrap()["name"], "a");
+        let polygon = feature.geometry.clone().unwrap().as_polygon().unwrap();
+        let coords: Vec<_> = polygon.rings[0].0.iter().map(|c| c.0).collect();
+        assert_eq!(
+            coords,
+            vec![
+                [100.0, 200.0],
+                [101.0, 200.0],
+                [101.0, 199.0],
+                [100.0, 199.0]
+            ]
+        );
+
+        assert_eq!(
+            decode::<3>(&buf).unwrap_err(),
+            PbfError::Dimension {
+                expected: 3,
+                found: 2
+            }
+        );
+        assert_eq!(
+            decode::<2>(&buf[..buf.len() - 1]).unwrap_err(),
+            PbfError::Truncated
+        );
+    }
+
+    #[test]
+    fn test_decode_points_zm() {
+        let zigzag = |v: i64| ((v << 1) ^ (v >> 63)) as u64;
+        let field =
+            |name: &[u8], field_type: u8| [message(1, name), vec![2 << 3, field_type]].concat();
+        let int64 = |v: u64| {
+            let mut out = vec![6 << 3];
+            varint(v, &mut out);
+            out
+        };
+
+        // upper left origin with the scales and translations in the order x, y, m, z
+        let transform = [
+            vec![1 << 3, 0],
+            message(
+                2,
+                &[
+                    double(1, 1.0),
+                    double(2, 2.0),
+                    double(3, 0.5),
+                    double(4, 0.5),
+                ]
+                .concat(),
+            ),
+            message(
+                3,
+                &[
+                    double(1, 100.0),
+                    double(2, 500.0),
+                    double(3, 0.0),
+                    double(4, 10.0),
+                ]
+                .concat(),
+            ),
+        ]
+        .concat();
+        let feature = |coords: [i64; 4], id: u64, date: &[u8]| {
+            [
+                message(1, &int64(id)),
+                message(1, &message(1, date)),
+                message(2, &packed(3, coords.map(zigzag))),
+            ]
+            .concat()
+        };
+        let result = [
+            vec![7 << 3, 0],
+            vec![10 << 3, 1],
+            vec![11 << 3, 1],
+            message(12, &transform),
+            // esriFieldTypeBigInteger and esriFieldTypeDateOnly
+            message(13, &field(b"id", 13)),
+            message(13, &field(b"day", 14)),
+            message(
+                15,
+                &feature([3, 4, 6, 20], 9_007_199_254_740_993, b"2024-05-01"),
+            ),
+            message(15, &feature([0, 0, 0, 0], 1, b"2024-05-02")),
+        ]
+        .concat();
+        let buf = message(2, &message(1, &result));
+
+        let fset = decode::<4>(&buf).unwrap();
+        assert_eq!(fset.geometryType.as_deref(), Some("esriGeometryPoint"));
+        assert_eq!((fset.hasZ, fset.hasM), (Some(true), Some(true)));
+        let fields = fset.fields.as_ref().unwrap();
+        assert_eq!(fields[0].field_type.to_string(), "esriFieldTypeBigInteger");
+        assert_eq!(fields[1].field_type.to_string(), "esriFieldTypeDateOnly");
+
+        let attributes = fset.features[0].attributes.as_ref().unwrap();
+        assert_eq!(attributes["id"], 9_007_199_254_740_993_i64);
+        assert_eq!(attributes["day"], "2024-05-01");
+
+        let point = |i: usize| match fset.features[i].geometry.clone() {
+            Some(EsriGeometry::Point(p)) => (p.x, p.y, p.z, p.m),
+            g => panic!("expected a point, found {g:?}"),
+        };
+        // y grows downwards from the origin
+        assert_eq!(point(0), (103.0, 492.0, Some(13.0), Some(10.0)));
+        assert_eq!(point(1), (100.0, 500.0, Some(10.0), Some(0.0)));
+    }
+}
@@ -0,0 +1,539 @@
+// The messages of Esri's FeatureCollection.proto (package esriPBuffer) and their decoding
+//
+// Only the messages and fields needed for query results are decoded. Unknown fields are
+// skipped so that additions to the schema do not break decoding.
+use super::PbfError;
+use crate::feature_service::{GeometryProperties, OriginPosition, UniqueIdField};
+use crate::features::Field;
+use crate::field_type::FieldType;
+use crate::spatial_reference::SpatialReference;
+use serde_json::{Map, Value};
+
+// a field value in the protobuf wire format
+enum Wire<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn varint(&mut self) -> Result<u64, PbfError> {
+        let mut value = 0_u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self.buf.split_first().ok_or(PbfError::Truncated)?;
+            self.buf = rest;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(PbfError::Invalid("varint is longer than 10 bytes"))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], PbfError> {
+        if self.buf.len() < n {
+            return Err(PbfError::Truncated);
+        }
+        let (bytes, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    // the number and value of the next field
+    fn field(&mut self) -> Result<Option<(u64, Wire<'a>)>, PbfError> {
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 7 {
+            0 => Wire::Varint(self.varint()?),
+            1 => Wire::Fixed64(u64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            2 => {
+                let len = self.varint()? as usize;
+                Wire::Bytes(self.take(len)?)
+            }
+            5 => Wire::Fixed32(u32::from_le_bytes(self.take(4)?.try_into().unwrap())),
+            _ => return Err(PbfError::Invalid("unsupported wire type")),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+
+    // calls `f` with every field of the message
+    fn fields(
+        buf: &'a [u8],
+        mut f: impl FnMut(u64, Wire<'a>) -> Result<(), PbfError>,
+    ) -> Result<(), PbfError> {
+        let mut reader = Reader::new(buf);
+        while let Some((number, value)) = reader.field()? {
+            f(number, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Wire<'a> {
+    fn varint(&self) -> Result<u64, PbfError> {
+        match self {
+            Wire::Varint(v) => Ok(*v),
+            _ => Err(PbfError::Invalid("expected a varint")),
+        }
+    }
+
+    fn bytes(&self) -> Result<&'a [u8], PbfError> {
+        match self {
+            Wire::Bytes(b) => Ok(b),
+            _ => Err(PbfError::Invalid("expected a length-delimited field")),
+        }
+    }
+
+    fn string(&self) -> Result<String, PbfError> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|_| PbfError::Invalid("invalid UTF-8"))
+    }
+
+    fn double(&self) -> Result<f64, PbfError> {
+        match self {
+            Wire::Fixed64(v) => Ok(f64::from_bits(*v)),
+            _ => Err(PbfError::Invalid("expected a double")),
+        }
+    }
+
+    fn bool(&self) -> Result<bool, PbfError> {
+        Ok(self.varint()? != 0)
+    }
+
+    // repeated varints, packed or not
+    fn varints(&self, out: &mut Vec<u64>) -> Result<(), PbfError> {
+        match self {
+            Wire::Bytes(b) => {
+                let mut reader = Reader::new(b);
+                while !reader.buf.is_empty() {
+                    out.push(reader.varint()?);
+                }
+                Ok(())
+            }
+            _ => {
+                out.push(self.varint()?);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn zigzag(v: u64) -> i64 {
+    (v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+/// The root message of a `f=pbf` response
+#[derive(Debug, Clone, Default)]
+pub struct FeatureCollectionPBuffer {
+    pub version: String,
+    pub query_result: Option<QueryResult>,
+}
+
+impl FeatureCollectionPBuffer {
+    pub fn decode(buf: &[u8]) -> Result<Self, PbfError> {
+        let mut message = Self::default();
+        Reader::fields(buf, |number, value| {
+            match number {
+                1 => message.version = value.string()?,
+                2 => message.query_result = QueryResult::decode(value.bytes()?)?,
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(message)
+    }
+}
+
+/// The result of a query, depending on whether features, a count, or IDs were requested
+#[derive(Debug, Clone)]
+pub enum QueryResult {
+    FeatureResult(Box<FeatureResult>),
+    CountResult(u64),
+    IdsResult(ObjectIdsResult),
+}
+
+impl QueryResult {
+    fn decode(buf: &[u8]) -> Result<Option<Self>, PbfError> {
+        let mut result = None;
+        Reader::fields(buf, |number, value| {
+            match number {
+                1 => {
+                    result = Some(Self::FeatureResult(Box::new(FeatureResult::decode(
+                        value.bytes()?,
+                    )?)))
+                }
+                2 => {
+                    let mut count = 0;
+                    Reader::fields(value.bytes()?, |number, value| {
+                        if number == 1 {
+                            count = value.varint()?;
+                        }
+                        Ok(())
+                    })?;
+                    result = Some(Self::CountResult(count));
+                }
+                3 => result = Some(Self::IdsResult(ObjectIdsResult::decode(value.bytes()?)?)),
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(result)
+    }
+}
+
+/// The object IDs returned by a query with `returnIdsOnly=true`
+#[derive(Debug, Clone, Default)]
+pub struct ObjectIdsResult {
+    pub object_id_field_name: String,
+    pub object_ids: Vec<u64>,
+}
+
+impl ObjectIdsResult {
+    fn decode(buf: &[u8]) -> Result<Self, PbfError> {
+        let mut message = Self::default();
+        Reader::fields(buf, |number, value| {
+            match number {
+                1 => message.object_id_field_name = value.string()?,
+                3 => value.varints(&mut message.object_ids)?,
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(message)
+    }
+}
+
+/// The geometry type of a [`FeatureResult`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GeometryType {
+    Point,
+    Multipoint,
+    Polyline,
+    Polygon,
+    Multipatch,
+    #[default]
+    None,
+}
+
+impl GeometryType {
+    fn from_i32(value: u64) -> Self {
+        match value {
+            0 => Self::Point,
+            1 => Self::Multipoint,
+            2 => Self::Polyline,
+            3 => Self::Polygon,
+            4 => Self::Multipatch,
+            _ => Self::None,
+        }
+    }
+
+    /// The name of the geometry type in Esri JSON, e.g. `esriGeometryPolygon`
+    pub fn as_str(&self) -> Option<&'static str> {
+        match self {
+            Self::Point => Some("esriGeometryPoint"),
+            Self::Multipoint => Some("esriGeometryMultipoint"),
+            Self::Polyline => Some("esriGeometryPolyline"),
+            Self::Polygon => Some("esriGeometryPolygon"),
+            Self::Multipatch => Some("esriGeometryMultiPatch"),
+            Self::None => None,
+        }
+    }
+}
+
+/// The features and metadata returned by a query
+#[derive(Debug, Clone, Default)]
+pub struct FeatureResult {
+    pub object_id_field_name: String,
+    pub unique_id_field: Option<UniqueIdField>,
+    pub global_id_field_name: String,
+    pub geometry_properties: Option<GeometryProperties>,
+    pub geometry_type: GeometryType,
+    pub spatial_reference: Option<SpatialReference>,
+    pub exceeded_transfer_limit: bool,
+    pub has_z: bool,
+    pub has_m: bool,
+    pub transform: Option<Transform>,
+    pub fields: Vec<Field>,
+    pub features: Vec<Feature>,
+}
+
+impl FeatureResult {
+    fn decode(buf: &[u8]) -> Result<Self, PbfError> {
+        let mut message = Self::default();
+        Reader::fields(buf, |number, value| {
+            match number {
+                1 => message.object_id_field_name = value.string()?,
+                2 => message.unique_id_field = Some(decode_unique_id_field(value.bytes()?)?),
+                3 => message.global_id_field_name = value.string()?,
+                5 => {
+                    message.geometry_properties = Some(decode_geometry_properties(value.bytes()?)?)
+                }
+                7 => message.geometry_type = GeometryType::from_i32(value.varint()?),
+                8 => message.spatial_reference = Some(decode_spatial_reference(value.bytes()?)?),
+                9 => message.exceeded_transfer_limit = value.bool()?,
+                10 => message.has_z = value.bool()?,
+                11 => message.has_m = value.bool()?,
+                12 => message.transform = Some(Transform::decode(value.bytes()?)?),
+                13 => message.fields.push(decode_field(value.bytes()?)?),
+                15 => message.features.push(Feature::decode(value.bytes()?)?),
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(message)
+    }
+}
+
+fn decode_unique_id_field(buf: &[u8]) -> Result<UniqueIdField, PbfError> {
+    let mut field = UniqueIdField::default();
+    Reader::fields(buf, |number, value| {
+        match number {
+            1 => field.name = value.string()?,
+            2 => field.isSystemMaintained = value.bool()?,
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(field)
+}
+
+fn decode_geometry_properties(buf: &[u8]) -> Result<GeometryProperties, PbfError> {
+    let mut properties = GeometryProperties::default();
+    Reader::fields(buf, |number, value| {
+        match number {
+            1 => properties.shapeAreaFieldName = Some(value.string()?),
+            2 => properties.shapeLengthFieldName = Some(value.string()?),
+            3 => properties.units = Some(value.string()?),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(properties)
+}
+
+fn decode_spatial_reference(buf: &[u8]) -> Result<SpatialReference, PbfError> {
+    let mut sr = SpatialReference {
+        wkid: None,
+        ..Default::default()
+    };
+    // zero is the default value of an unset field
+    let wkid = |value: &Wire| Ok::<_, PbfError>(Some(value.varint()? as u32).filter(|w| *w != 0));
+    Reader::fields(buf, |number, value| {
+        match number {
+            1 => sr.wkid = wkid(&value)?,
+            2 => sr.latest_wkid = wkid(&value)?,
+            3 => sr.vcs_wkid = wkid(&value)?,
+            4 => sr.latest_vcs_wkid = wkid(&value)?,
+            5 => sr.wkt = Some(value.string()?).filter(|wkt| !wkt.is_empty()),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(sr)
+}
+
+// the names of the SQLType enum
+const SQL_TYPES: [&str; 26] = [
+    "sqlTypeBigInt",
+    "sqlTypeBinary",
+    "sqlTypeBit",
+    "sqlTypeChar",
+    "sqlTypeDate",
+    "sqlTypeDecimal",
+    "sqlTypeDouble",
+    "sqlTypeFloat",
+    "sqlTypeGeometry",
+    "sqlTypeGUID",
+    "sqlTypeInteger",
+    "sqlTypeLongNVarchar",
+    "sqlTypeLongVarbinary",
+    "sqlTypeLongVarchar",
+    "sqlTypeNChar",
+    "sqlTypeNVarchar",
+    "sqlTypeOther",
+    "sqlTypeReal",
+    "sqlTypeSmallInt",
+    "sqlTypeSqlXml",
+    "sqlTypeTime",
+    "sqlTypeTimestamp",
+    "sqlTypeTimestamp2",
+    "sqlTypeTinyInt",
+    "sqlTypeVarbinary",
+    "sqlTypeVarchar",
+];
+
+fn field_type(value: u64) -> Result<FieldType, PbfError> {
+    Ok(match value {
+        0 => FieldType::EsriFieldTypeSmallInteger,
+        1 => FieldType::EsriFieldTypeInteger,
+        2 => FieldType::EsriFieldTypeSingle,
+        3 => FieldType::EsriFieldTypeDouble,
+        4 => FieldType::EsriFieldTypeString,
+        5 => FieldType::EsriFieldTypeDate,
+        6 => FieldType::EsriFieldTypeOid,
+        7 => FieldType::EsriFieldTypeGeometry,
+        8 => FieldType::EsriFieldTypeBlob,
+        9 => FieldType::EsriFieldTypeRaster,
+        10 => FieldType::EsriFieldTypeGuid,
+        11 => FieldType::EsriFieldTypeGlobalId,
+        12 => FieldType::EsriFieldTypeXml,
+        13 => FieldType::EsriFieldTypeBigInteger,
+        14 => FieldType::EsriFieldTypeDateOnly,
+        15 => FieldType::EsriFieldTypeTimeOnly,
+        16 => FieldType::EsriFieldTypeTimestampOffset,
+        _ => return Err(PbfError::Invalid("unknown field type")),
+    })
+}
+
+fn decode_field(buf: &[u8]) -> Result<Field, PbfError> {
+    let mut field = Field {
+        name: String::new(),
+        field_type: FieldType::default(),
+        alias: None,
+        sqlType: None,
+        domain: None,
+        defaultValue: None,
+        extra: Map::new(),
+    };
+    Reader::fields(buf, |number, value| {
+        match number {
+            1 => field.name = value.string()?,
+            2 => field.field_type = field_type(value.varint()?)?,
+            3 => field.alias = Some(value.string()?),
+            4 => {
+                field.sqlType = SQL_TYPES
+                    .get(value.varint()? as usize)
+                    .map(|s| s.to_string())
+            }
+            // the domain as JSON
+            5 => field.domain = serde_json::from_str(&value.string()?).ok(),
+            6 => field.defaultValue = Some(Value::String(value.string()?)),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(field)
+}
+
+/// The quantization of the coordinates of a [`FeatureResult`]
+///
+/// The scale and translation are in the order x, y, m, z, as in the schema.
+#[derive(Debug, Clone, Default)]
+pub struct Transform {
+    pub origin_position: OriginPosition,
+    pub scale: [f64; 4],
+    pub translate: [f64; 4],
+}
+
+impl Transform {
+    fn decode(buf: &[u8]) -> Result<Self, PbfError> {
+        let mut message = Self::default();
+        let doubles = |buf: &[u8], out: &mut [f64; 4]| {
+            Reader::fields(buf, |number, value| {
+                if let Some(out) = (number as usize)
+                    .checked_sub(1)
+                    .and_then(|i| out.get_mut(i))
+                {
+                    *out = value.double()?;
+                }
+                Ok(())
+            })
+        };
+        Reader::fields(buf, |number, value| {
+            match number {
+                1 => {
+                    message.origin_position = match value.varint()? {
+                        0 => OriginPosition::UpperLeft,
+                        _ => OriginPosition::BottomLeft,
+                    }
+                }
+                2 => doubles(value.bytes()?, &mut message.scale)?,
+                3 => doubles(value.bytes()?, &mut message.translate)?,
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(message)
+    }
+}
+
+/// A feature with its attributes in the order of the fields
+#[derive(Debug, Clone, Default)]
+pub struct Feature {
+    pub attributes: Vec<Value>,
+    pub geometry: Option<Geometry>,
+    pub centroid: Option<Geometry>,
+}
+
+impl Feature {
+    fn decode(buf: &[u8]) -> Result<Self, PbfError> {
+        let mut message = Self::default();
+        Reader::fields(buf, |number, value| {
+            match number {
+                1 => message.attributes.push(decode_value(value.bytes()?)?),
+                2 => message.geometry = Some(Geometry::decode(value.bytes()?)?),
+                3 => return Err(PbfError::Unsupported("shape buffer geometries")),
+                4 => message.centroid = Some(Geometry::decode(value.bytes()?)?),
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(message)
+    }
+}
+
+// a value, which is null when none of its fields are set
+fn decode_value(buf: &[u8]) -> Result<Value, PbfError> {
+    let mut result = Value::Null;
+    Reader::fields(buf, |number, value| {
+        result = match (number, value) {
+            (1, value) => Value::String(value.string()?),
+            (2, Wire::Fixed32(v)) => Value::from(f32::from_bits(v) as f64),
+            (3, value) => Value::from(value.double()?),
+            (4, value) => Value::from(zigzag(value.varint()?) as i32),
+            (5, value) => Value::from(value.varint()? as u32),
+            (6, value) => Value::from(value.varint()? as i64),
+            (7, value) => Value::from(value.varint()?),
+            (8, value) => Value::from(zigzag(value.varint()?)),
+            (9, value) => Value::from(value.bool()?),
+            _ => return Ok(()),
+        };
+        Ok(())
+    })?;
+    Ok(result)
+}
+
+/// The quantized coordinates of a geometry
+///
+/// `lengths` are the number of vertices of each part and `coords` are the values of
+/// every vertex, with the x and y values encoded as the difference from the previous vertex.
+#[derive(Debug, Clone, Default)]
+pub struct Geometry {
+    pub lengths: Vec<u32>,
+    pub coords: Vec<i64>,
+}
+
+impl Geometry {
+    fn decode(buf: &[u8]) -> Result<Self, PbfError> {
+        let (mut lengths, mut coords) = (Vec::new(), Vec::new());
+        Reader::fields(buf, |number, value| match number {
+            2 => value.varints(&mut lengths),
+            3 => value.varints(&mut coords),
+            _ => Ok(()),
+        })?;
+        Ok(Self {
+            lengths: lengths.into_iter().map(|l| l as u32).collect(),
+            coords: coords.into_iter().map(zigzag).collect(),
+        })
+    }
+}
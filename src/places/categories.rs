@@ -0,0 +1,169 @@
+use crate::places::CategoryDetails;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, io, path::Path};
+
+/// The category hierarchy of the Places API, indexed by category ID
+///
+/// Build it from the response of the `/categories` endpoint, or with
+/// `PlacesClient::category_index()` which keeps it for the lifetime of the client.
+/// Use [`CategoryIndex::save()`] and [`CategoryIndex::load()`] to keep it between runs.
+///
+/// ```
+/// use serde_esri::places::{CategoryDetails, CategoryIndex};
+///
+/// let category = |id: &str, label: &[&str], parent: Option<&str>| -> CategoryDetails {
+///     let json = serde_json::json!({"categoryId": id, "fullLabel": label, "parents": parent.map(|p| [p])});
+///     serde_json::from_value(json).unwrap()
+/// };
+/// let index = CategoryIndex::new(vec![
+///     category("10000", &["Dining and Drinking"], None),
+///     category("13032", &["Dining and Drinking", "Cafe, Coffee, and Tea House"], Some("10000")),
+///     category("13035", &["Dining and Drinking", "Cafe, Coffee, and Tea House", "Coffee Shop"], Some("13032")),
+/// ]);
+///
+/// assert_eq!(index.name_of("13035"), Some("Coffee Shop"));
+/// assert_eq!(index.ancestors_of("13035").len(), 2);
+/// assert_eq!(index.search("coffee")[0].category_id, "13035");
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<CategoryDetails>", into = "Vec<CategoryDetails>")]
+pub struct CategoryIndex {
+    categories: HashMap<String, CategoryDetails>,
+    children: HashMap<String, Vec<String>>,
+}
+
+impl CategoryIndex {
+    pub fn new(categories: Vec<CategoryDetails>) -> Self {
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+        for category in &categories {
+            for parent in category.parents.iter().flatten() {
+                children
+                    .entry(parent.clone())
+                    .or_default()
+                    .push(category.category_id.clone());
+            }
+        }
+
+        let categories = categories
+            .into_iter()
+            .map(|c| (c.category_id.clone(), c))
+            .collect();
+
+        Self {
+            categories,
+            children,
+        }
+    }
+
+    /// Read an index written by [`CategoryIndex::save()`]
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let json = fs::read(path)?;
+        serde_json::from_slice(&json).map_err(io::Error::other)
+    }
+
+    /// Write the index to `path` as JSON
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_vec(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    pub fn len(&self) -> usize {
+        self.categories.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CategoryDetails> {
+        self.categories.values()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CategoryDetails> {
+        self.categories.get(id)
+    }
+
+    /// The name of the category, which is the last of its labels
+    pub fn name_of(&self, id: &str) -> Option<&str> {
+        self.get(id)?.full_label.last().map(String::as_str)
+    }
+
+    /// The categories whose parents include `id`
+    pub fn children_of(&self, id: &str) -> Vec<&CategoryDetails> {
+        self.children
+            .get(id)
+            .into_iter()
+            .flatten()
+            .filter_map(|child| self.get(child))
+            .collect()
+    }
+
+    /// The parents of the category, their parents, and so on, nearest first
+    pub fn ancestors_of(&self, id: &str) -> Vec<&CategoryDetails> {
+        let mut ancestors: Vec<&CategoryDetails> = Vec::new();
+        let mut next = 0;
+        let mut current = self.get(id);
+
+        while let Some(category) = current {
+            for parent in category.parents.iter().flatten() {
+                let seen = parent == id || ancestors.iter().any(|a| &a.category_id == parent);
+                if let Some(parent) = self.get(parent).filter(|_| !seen) {
+                    ancestors.push(parent);
+                }
+            }
+            current = ancestors.get(next).copied();
+            next += 1;
+        }
+
+        ancestors
+    }
+
+    /// The categories whose labels contain every word of `query`, ignoring case
+    ///
+    /// Categories named `query` are listed first, followed by those whose name starts
+    /// with it, those whose name contains it, and those where only the labels of a parent match.
+    pub fn search(&self, query: &str) -> Vec<&CategoryDetails> {
+        let query = query.trim().to_lowercase();
+        let words: Vec<&str> = query.split_whitespace().collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+        let matches = |label: &str| words.iter().all(|w| label.contains(w));
+
+        let mut found: Vec<(u8, &CategoryDetails)> = self
+            .iter()
+            .filter_map(|c| {
+                let name = c.full_label.last()?.to_lowercase();
+                let rank = if name == query {
+                    0
+                } else if name.starts_with(&query) {
+                    1
+                } else if matches(&name) {
+                    2
+                } else if matches(&c.full_label.join(" ").to_lowercase()) {
+                    3
+                } else {
+                    return None;
+                };
+                Some((rank, c))
+            })
+            .collect();
+
+        found.sort_by(|(a, x), (b, y)| {
+            (a, x.full_label.len(), &x.category_id).cmp(&(b, y.full_label.len(), &y.category_id))
+        });
+        found.into_iter().map(|(_, c)| c).collect()
+    }
+}
+
+impl From<Vec<CategoryDetails>> for CategoryIndex {
+    fn from(categories: Vec<CategoryDetails>) -> Self {
+        Self::new(categories)
+    }
+}
+
+impl From<CategoryIndex> for Vec<CategoryDetails> {
+    fn from(index: CategoryIndex) -> Self {
+        index.categories.into_values().collect()
+    }
+}
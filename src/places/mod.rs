@@ -1,5 +1,9 @@
 //! Representation of the [Places Service REST API](https://developers.arcgis.com/rest/places/) types and responses. Activate the `"places-client"` feature to enable the `PlacesClient` struct and the ability to query the API.
+#[allow(clippy::module_inception)]
 mod places;
 pub use places::*;
 
+mod categories;
+pub use categories::*;
+
 pub mod query;
@@ -11,7 +11,12 @@
 //     let model: frf = serde_json::from_str(&json).unwrap();
 // }
 
+use crate::features::{Feature, FeatureSet, FeatureSetBuilder};
+use crate::field_type::FieldType;
+use crate::geometry::{EsriGeometry, EsriPoint};
+use crate::spatial_reference::SpatialReference;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 /// Determines whether icons are returned and the type of icon to use with a place or category.
 /// The SVG and CIM symbols default to 15 x 15 pixels but can be scaled smoothly for display in larger UI elements or to emphasize these features on a map. The PNG icons are provided as 48 x 48 pixels but for map display the recommended size is 16 x 16 pixels.
@@ -34,6 +39,7 @@ pub enum Icon {
 ///
 /// This object and child properties are part of the "Location"
 /// attribute group.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AdditionalLocations {
@@ -48,6 +54,11 @@ pub struct AdditionalLocations {
 
     /// A location in the roof centroid for a place.
     pub roof: Option<NullablePoint>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A location for drop-off/pick-up for a place.
@@ -70,6 +81,11 @@ pub struct NullablePoint {
 
     /// The y, or latitude, of this location in WGS84 decimal degrees.
     pub y: f64,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// The address of a place, or point of interest (POI).
@@ -79,6 +95,7 @@ pub struct NullablePoint {
 ///
 /// This object and child properties are part of the "Address" attribute
 /// group.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Address {
@@ -122,6 +139,11 @@ pub struct Address {
 
     /// The street address for a place, for example the street name and number.
     pub street_address: Option<String>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Represents the category of a place.
@@ -140,6 +162,11 @@ pub struct Category {
 
     /// The label that describes the category.
     pub label: String,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Provides details about a category or type of place.
@@ -149,6 +176,7 @@ pub struct Category {
 /// types such as "Restaurant". A category such as "Provençal Restaurant"
 /// includes the details of its more generic parent, such as "French
 /// Restaurant".
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CategoryDetails {
@@ -168,6 +196,11 @@ pub struct CategoryDetails {
 
     /// The list of parent category Ids for this category.
     pub parents: Option<Vec<String>>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Details of an icon, suitable for depicting this place.
@@ -176,17 +209,29 @@ pub struct CategoryDetails {
 ///
 ///
 /// Information about an icon for depicting a place or category.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IconDetails {
     /// Url for an icon for this place or category in either `svg`, `cim` or `png` format.
     pub url: Option<String>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Information about a chain that a place belongs to.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainInfo {
     /// The name of the chain.
     pub name: Option<String>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// The contact information for a place.
@@ -196,6 +241,7 @@ pub struct ChainInfo {
 ///
 /// This object and child properties are part of the "Details" attribute
 /// group.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContactInfo {
     /// Email address.
@@ -209,6 +255,11 @@ pub struct ContactInfo {
 
     /// The website address of the place.
     pub website: Option<String>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Error JSON response
@@ -251,6 +302,7 @@ pub struct ErrorClass {
 ///
 /// This object and child properties are part of the "Details" attribute
 /// group.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Hours {
@@ -276,6 +328,11 @@ pub struct Hours {
     /// and 14:00 with 15:00. Popular hours are shown in 24-hour time in the
     /// local timezone of the place or POI.
     pub popular: Option<HoursByDay>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// The opening hours for a place.
@@ -306,6 +363,7 @@ pub struct Hours {
 /// it would contain two pairs of from/to times: 09:00 paired with 10:00
 /// and 14:00 with 15:00. Popular hours are shown in 24-hour time in the
 /// local timezone of the place or POI.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HoursByDay {
     pub friday: Option<Vec<TimeRange>>,
@@ -321,6 +379,11 @@ pub struct HoursByDay {
     pub tuesday: Option<Vec<TimeRange>>,
 
     pub wednesday: Option<Vec<TimeRange>>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A pair of times defining the start and end of a time period.
@@ -337,12 +400,18 @@ pub struct TimeRange {
 
     /// The end of a time range in the format "HH:MM".
     pub to: String,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A result of searching for places using either `places/near-point` or `places/within-extent`.
 ///
 /// The result object includes a single place that satisfied the search. For `places/near-point`,
 /// the distance from the search point, in meters, is included.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlaceResult {
@@ -357,6 +426,7 @@ pub struct PlaceResult {
 
     /// The distance, in meters, from the place to the search point of a 'places/near-point`
     /// query.
+    #[serde(default, deserialize_with = "crate::de::lenient::option")]
     pub distance: Option<f64>,
 
     /// Details of an icon, suitable for depicting this place.
@@ -377,6 +447,62 @@ pub struct PlaceResult {
     /// This place Id can be passed to the `places/{placeId}` endpoint to
     /// retrieve additional details.
     pub place_id: String,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl PlaceResult {
+    /// The location of the place as a WGS84 point
+    pub fn to_point(&self) -> EsriPoint {
+        EsriPoint {
+            x: self.location.x,
+            y: self.location.y,
+            z: None,
+            m: None,
+            spatialReference: Some(SpatialReference::from_wkid(4326)),
+        }
+    }
+
+    /// The place as a point feature with its `placeId`, `name`, `categories`, and `distance`
+    ///
+    /// `categories` are the labels of the categories of the place separated by `", "`.
+    pub fn to_feature(&self) -> Feature<2> {
+        let categories: Vec<&str> = self.categories.iter().map(|c| c.label.as_str()).collect();
+        let attributes = json!({
+            "placeId": self.place_id,
+            "name": self.name,
+            "categories": categories.join(", "),
+            "distance": self.distance,
+        });
+
+        Feature {
+            geometry: Some(EsriGeometry::Point(self.to_point())),
+            attributes: attributes.as_object().cloned(),
+            #[cfg(feature = "preserve-unknown")]
+            extra: Default::default(),
+        }
+    }
+}
+
+/// Collects places into a point `FeatureSet` in WGS84 with the attributes of [`PlaceResult::to_feature()`]
+///
+/// Features are numbered by an `OBJECTID` field.
+impl FromIterator<PlaceResult> for FeatureSet<2> {
+    fn from_iter<I: IntoIterator<Item = PlaceResult>>(iter: I) -> Self {
+        FeatureSetBuilder::new()
+            .wkid(4326)
+            .field("placeId", FieldType::EsriFieldTypeString)
+            .field("name", FieldType::EsriFieldTypeString)
+            .field("categories", FieldType::EsriFieldTypeString)
+            .field("distance", FieldType::EsriFieldTypeDouble)
+            .extend(iter.into_iter().map(|place| place.to_feature()))
+            .build()
+            // every feature is a point with a value of each field
+            .expect("places are valid features")
+    }
 }
 
 /// The location of this place as a WGS84 point.
@@ -391,13 +517,21 @@ pub struct PlaceResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Point {
     /// The x, or longitude, of this location in WGS84 decimal degrees.
+    #[serde(deserialize_with = "crate::de::lenient::value")]
     pub x: f64,
 
     /// The y, or latitude, of this location in WGS84 decimal degrees.
+    #[serde(deserialize_with = "crate::de::lenient::value")]
     pub y: f64,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Provides pagination links for accessing more results for the current request.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Pagination {
@@ -416,6 +550,11 @@ pub struct Pagination {
     /// pages of results. You must also supply authentication details, such
     /// as a `token`, to make a previous page request.
     pub previous_url: Option<String>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// The additional details for a `Place`, including address, contact details, opening hours,
@@ -424,6 +563,7 @@ pub struct Pagination {
 /// You can request additional details for a place by using the `placeId` in
 /// a `places/{placeId}` request. Use the `requestedFields` query parameter
 /// to choose the fields or attributes that are included in the response.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlaceDetails {
@@ -503,6 +643,11 @@ pub struct PlaceDetails {
     /// This object and child properties are part of the "Details" attribute
     /// group.
     pub social_media: Option<SocialMedia>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Rating information supplied by users of the place.
@@ -512,6 +657,7 @@ pub struct PlaceDetails {
 ///
 ///
 /// Rating information about the price and user rating of the place.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rating {
     /// An indication of the overall price of a place based on user reviews.
@@ -519,6 +665,11 @@ pub struct Rating {
 
     /// A rating for the place based on user-reviews from 0 to 5, where 5 is the best rating.
     pub user: Option<f64>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// An indication of the overall price of a place based on user reviews.
@@ -542,6 +693,7 @@ pub enum Price {
 ///
 ///
 /// The social media details for a place.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SocialMedia {
@@ -553,11 +705,17 @@ pub struct SocialMedia {
 
     /// The twitter handle of the place.
     pub twitter: Option<String>,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A result of searching for places using a `places/within-extent` request.
 ///
 /// The result object includes a single place that satisfied the search.
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WithinExtentResult {
@@ -588,4 +746,9 @@ pub struct WithinExtentResult {
     /// This place Id can be passed to the `places/{placeId}` endpoint to
     /// retrieve additional details.
     pub place_id: String,
+
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
@@ -0,0 +1,378 @@
+use crate::auth::{CredentialError, CredentialProvider, TokenProvider, TokenSource};
+use crate::error::{ErrorKind, RetryPolicy};
+use crate::http::{encode_path_segment, HttpError, HttpRequest, HttpResponse};
+use crate::places::query::{
+    decode_response, read_page, sealed::Sealed, CategoriesQueryParams, CategoriesResponse,
+    CategoryQueryParams, ExpectedResponse, NearPoint, NearPointQueryParams, Page, PageTransport,
+    Pages, PaginatedEndpoint, PlaceQueryParams, PlaceResponse, PlacesError, Step, WithinExtent,
+    WithinExtentQueryParams,
+};
+use crate::places::{CategoryDetails, PlaceResult};
+use crate::trace::{self, PageSpan};
+use futures_util::future::FutureExt;
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::{collections::HashMap, time::Duration};
+
+/// A stream of the results of a paginated place search
+///
+/// The next page is requested once the results of the current page have been
+/// returned. The stream ends after the first error.
+#[cfg(not(target_arch = "wasm32"))]
+pub type PlaceStream = futures_util::stream::BoxStream<'static, Result<PlaceResult, PlacesError>>;
+
+/// A stream of the results of a paginated place search
+///
+/// The next page is requested once the results of the current page have been
+/// returned. The stream ends after the first error. The futures of `fetch` are not `Send`.
+#[cfg(target_arch = "wasm32")]
+pub type PlaceStream =
+    futures_util::stream::LocalBoxStream<'static, Result<PlaceResult, PlacesError>>;
+
+/// A page of a search being fetched by an [`AsyncPlacesClient`]
+#[cfg(not(target_arch = "wasm32"))]
+pub type PageFuture = futures_util::future::BoxFuture<'static, Result<Page, PlacesError>>;
+
+/// A page of a search being fetched by an [`AsyncPlacesClient`]
+#[cfg(target_arch = "wasm32")]
+pub type PageFuture = futures_util::future::LocalBoxFuture<'static, Result<Page, PlacesError>>;
+
+/// An async client for the ArcGIS Places API
+///
+/// The async counterpart of [`PlacesClient`](crate::places::query::PlacesClient)
+/// for use inside an async runtime such as tokio.
+///
+/// Requests are sent with a `reqwest::Client`. An [`HttpClient`](crate::http::HttpClient)
+/// cannot be used since it blocks the thread, and there is no async counterpart of the
+/// trait: its futures would have to be boxed, and be `Send` except in wasm builds.
+///
+/// Requests that are rate limited or fail with a transient error, including the requests
+/// for further pages, are retried according to the [`RetryPolicy`] of the client without
+/// blocking the runtime. A `Retry-After` header takes precedence over the backoff of the policy.
+/// A request rejected because of its token is repeated once if the client gets its token from a
+/// [`TokenProvider`], which is asked for a new token.
+///
+/// ```no_run
+/// use futures_util::StreamExt;
+/// use serde_esri::places::query::{AsyncPlacesClient, NearPointQueryParams, PLACES_API_URL};
+///
+/// # async fn run() {
+/// let client = AsyncPlacesClient::new(PLACES_API_URL, "your token");
+/// let params = NearPointQueryParams::builder().point(-117.19, 34.05).build().unwrap();
+///
+/// let mut places = client.near_point(params);
+/// while let Some(place) = places.next().await {
+///     println!("{}", place.unwrap().name);
+/// }
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AsyncPlacesClient {
+    pub base_url: String,
+    pub(crate) client: reqwest::Client,
+    /// The token to use for authorization. Redacted from `Debug` and zeroized on drop.
+    pub(crate) token: TokenSource,
+    pub(crate) retry: RetryPolicy,
+}
+
+impl std::fmt::Debug for AsyncPlacesClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncPlacesClient")
+            .field("base_url", &self.base_url)
+            .field("token", &"[REDACTED]")
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AsyncPlacesClient {
+    /// Create a new async client for the Places API
+    pub fn new(base_url: &str, token: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            client: reqwest::Client::new(),
+            token: token.into(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Create a new async client for the Places API using a token from `credentials`
+    pub fn from_credentials<C: CredentialProvider>(
+        base_url: &str,
+        credentials: &C,
+    ) -> Result<Self, CredentialError> {
+        Ok(Self {
+            base_url: base_url.to_string(),
+            client: reqwest::Client::new(),
+            token: credentials.token()?.into(),
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    /// Create a new async client for the Places API which gets a token from `provider` before every request
+    ///
+    /// Use a provider which refreshes its token, such as `ClientCredentials`, for clients that
+    /// outlive a token. The provider is called on a blocking thread of the tokio runtime.
+    pub fn from_token_provider<P: TokenProvider>(base_url: &str, provider: P) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            client: reqwest::Client::new(),
+            token: TokenSource::new(provider),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Set the policy for retrying rate limited and failed requests
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Query the [`/places/near-point`](https://developers.arcgis.com/rest/places/near-point-get/) endpoint
+    ///
+    /// No request is sent until the stream is polled.
+    pub fn near_point(&self, params: NearPointQueryParams) -> PlaceStream {
+        self.search::<NearPoint>(params)
+    }
+
+    /// Query the [`/places/within-extent`](https://developers.arcgis.com/rest/places/within-extent-get/) endpoint
+    ///
+    /// No request is sent until the stream is polled.
+    pub fn within_extent(&self, params: WithinExtentQueryParams) -> PlaceStream {
+        self.search::<WithinExtent>(params)
+    }
+
+    /// Query the [`/places/{place_id}`](https://developers.arcgis.com/rest/places/place-details-get/) endpoint
+    pub async fn place_details(
+        &self,
+        params: PlaceQueryParams,
+    ) -> Result<PlaceResponse, PlacesError> {
+        let fields = params.requested_fields.join(",");
+        let id = encode_path_segment(&params.place_id);
+        let request = HttpRequest::get(&format!("{}/places/{id}", self.base_url))
+            .param("requestedFields", &fields);
+
+        self.send(request).await
+    }
+
+    /// Query the [`/places/{place_id}`](https://developers.arcgis.com/rest/places/place-details-get/) endpoint
+    /// for each of `ids`, with at most `concurrency` requests at a time
+    ///
+    /// The results are keyed by place ID. A failed request doesn't stop the others.
+    pub async fn place_details_batch<I, S>(
+        &self,
+        ids: I,
+        requested_fields: &[String],
+        concurrency: usize,
+    ) -> HashMap<String, Result<PlaceResponse, PlacesError>>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        stream::iter(ids.into_iter().map(Into::into))
+            .map(|place_id: String| async move {
+                let params = PlaceQueryParams {
+                    place_id: place_id.clone(),
+                    requested_fields: requested_fields.to_vec(),
+                };
+                (place_id, self.place_details(params).await)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Query the [`/categories`](https://developers.arcgis.com/rest/places/categories-get/) endpoint
+    pub async fn categories(
+        &self,
+        params: CategoriesQueryParams,
+    ) -> Result<CategoriesResponse, PlacesError> {
+        let request = HttpRequest::get(&format!("{}/categories", self.base_url)).query(&params);
+
+        self.send(request).await
+    }
+
+    /// Query the [`/categories/{categoryId}`](https://developers.arcgis.com/rest/places/category-details-get/) endpoint
+    pub async fn category_details(
+        &self,
+        params: CategoryQueryParams,
+    ) -> Result<CategoryDetails, PlacesError> {
+        let id = encode_path_segment(&params.category_id);
+        let request =
+            HttpRequest::get(&format!("{}/categories/{id}", self.base_url)).query(&params);
+
+        self.send(request).await
+    }
+
+    // sends the request, retrying retryable errors and an auth error with a new token
+    async fn send<T: DeserializeOwned>(&self, request: HttpRequest) -> Result<T, PlacesError> {
+        self.retry
+            .run_async_refreshing(|_| self.send_once(&request), || self.token.invalidate())
+            .await
+            .map_err(|e| e.retried(&self.retry))
+    }
+
+    // authorizes the request, sends it, and parses the response. Errors come with the
+    // delay of the `Retry-After` header, if any.
+    async fn send_once<T: DeserializeOwned>(
+        &self,
+        request: &HttpRequest,
+    ) -> Result<T, (PlacesError, Option<Duration>)> {
+        let bearer = self
+            .token
+            .bearer_async()
+            .await
+            .map_err(|e| (PlacesError::CredentialError(e), None))?;
+
+        let resp = trace::send_async(request, self.execute(request, &bearer))
+            .await
+            .map_err(|e| (PlacesError::HttpError(e), None))?;
+
+        decode_response(resp.status, &resp.body).map_err(|e| (e, resp.retry_after()))
+    }
+
+    /// Search a paginated endpoint, streaming the results of every page
+    ///
+    /// No request is sent until the stream is polled.
+    pub fn search<E: PaginatedEndpoint + 'static>(&self, params: E::Params) -> PlaceStream {
+        let first = match E::first_request(&self.base_url, &params) {
+            Ok(request) => request,
+            Err(e) => return failed(e),
+        };
+        let state = (self.clone(), Pages::<E>::new(first));
+
+        stream::unfold(state, |(client, mut pages)| async move {
+            loop {
+                match pages.step(&client) {
+                    Step::Place(place) => return Some((Ok(place), (client, pages))),
+                    Step::Fetch(page) => {
+                        if let Err(e) = pages.receive(page.await) {
+                            return Some((Err(e), (client, pages)));
+                        }
+                    }
+                    Step::Done => return None,
+                }
+            }
+        })
+        .place_stream()
+    }
+
+    // sends the GET `request` with reqwest and receives the response
+    async fn execute(
+        &self,
+        request: &HttpRequest,
+        bearer: &str,
+    ) -> Result<HttpResponse, HttpError> {
+        let into_error = |e: reqwest::Error| HttpError::new(ErrorKind::from_request_error(&e), e);
+        let resp = request
+            .headers
+            .iter()
+            .fold(self.client.get(&request.url), |b, (k, v)| b.header(k, v))
+            .query(&request.query)
+            .header("X-Esri-Authorization", bearer)
+            .send()
+            .await
+            .map_err(into_error)?;
+        let status = resp.status().as_u16();
+        let headers = resp
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_string())))
+            .collect();
+        let body = resp.bytes().await.map_err(into_error)?.to_vec();
+
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+impl Sealed for AsyncPlacesClient {}
+
+impl PageTransport for AsyncPlacesClient {
+    type Fetch = PageFuture;
+
+    fn fetch<E: PaginatedEndpoint>(
+        &self,
+        request: HttpRequest,
+        url: Option<String>,
+        page: usize,
+    ) -> Self::Fetch {
+        let fetch = fetch_page(self.clone(), request, E::parse_page, url, page);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        return fetch.boxed();
+        #[cfg(target_arch = "wasm32")]
+        return fetch.boxed_local();
+    }
+}
+
+// sends the request of page number `page` of a search. Not generic over the endpoint,
+// so the future is `'static` for any endpoint.
+async fn fetch_page(
+    client: AsyncPlacesClient,
+    request: HttpRequest,
+    parse: fn(ExpectedResponse) -> Result<Page, PlacesError>,
+    url: Option<String>,
+    page: usize,
+) -> Result<Page, PlacesError> {
+    let span = PageSpan::new(page as u64);
+    let resp = span.instrument(client.send(request)).await;
+    let resp = read_page(resp, parse, url.as_deref(), page)?;
+
+    span.results(resp.results.len());
+    Ok(resp)
+}
+
+// a search that fails without sending a request
+fn failed(e: PlacesError) -> PlaceStream {
+    stream::once(async { Err(e) }).place_stream()
+}
+
+// boxes a stream of places as a `PlaceStream`, which is only `Send` outside of wasm
+trait IntoPlaceStream {
+    fn place_stream(self) -> PlaceStream;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<S: Stream<Item = Result<PlaceResult, PlacesError>> + Send + 'static> IntoPlaceStream for S {
+    fn place_stream(self) -> PlaceStream {
+        self.boxed()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl<S: Stream<Item = Result<PlaceResult, PlacesError>> + 'static> IntoPlaceStream for S {
+    fn place_stream(self) -> PlaceStream {
+        self.boxed_local()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::error::ClassifyError;
+
+    #[test]
+    fn test_connection_error() {
+        // nothing listens on the port of the dropped listener
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let client = AsyncPlacesClient::new(&format!("http://127.0.0.1:{port}"), "token")
+            .with_retry_policy(RetryPolicy::none());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let params = CategoriesQueryParams::default();
+        let err = runtime.block_on(client.categories(params)).unwrap_err();
+        assert!(matches!(err, PlacesError::HttpError(_)));
+        assert_eq!(err.kind(), ErrorKind::Retryable);
+    }
+}
@@ -1,14 +1,23 @@
+use crate::auth::{CredentialError, CredentialProvider, TokenProvider, TokenSource};
+use crate::error::RetryPolicy;
+use crate::http::{encode_path_segment, HttpClient, HttpRequest};
 use crate::places::query::{
-    CategoriesQueryParams, CategoriesResponse, CategoryQueryParams, NearPointQuery,
-    NearPointQueryParams, PlaceQueryParams, PlaceResponse, PlacesError, WithinExtentQuery,
-    WithinExtentQueryParams,
+    decode_response, CategoriesQueryParams, CategoriesResponse, CategoryQueryParams,
+    NearPointQuery, NearPointQueryParams, PaginatedEndpoint, PaginatedQuery, PlaceQueryParams,
+    PlaceResponse, PlacesError, TiledWithinExtentQuery, WithinExtentQuery, WithinExtentQueryParams,
+};
+use crate::places::{CategoryDetails, CategoryIndex};
+use crate::trace;
+use serde::de::DeserializeOwned;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
 };
-use crate::places::CategoryDetails;
-use std::sync::Arc;
-
-/// The base URL for the Places API
-pub const PLACES_API_URL: &str =
-    "https://places-api.arcgis.com/arcgis/rest/services/places-service/v1";
 
 /// A client for the ArcGIS Places API
 ///
@@ -21,12 +30,34 @@ pub const PLACES_API_URL: &str =
 ///
 /// Replace `"your token"` with your actual token.
 ///
-#[derive(Debug, Clone)]
-pub struct PlacesClient {
+/// Requests are sent with a `reqwest::blocking::Client` unless another [`HttpClient`]
+/// is set with [`PlacesClient::with_http_client()`].
+///
+/// Requests that are rate limited or fail with a transient error, including the requests
+/// for further pages, are retried according to the [`RetryPolicy`] of the client. A
+/// `Retry-After` header takes precedence over the backoff of the policy. A request rejected
+/// because of its token is repeated once if the client gets its token from a [`TokenProvider`],
+/// which is asked for a new token.
+///
+#[derive(Clone)]
+pub struct PlacesClient<H = reqwest::blocking::Client> {
     pub base_url: String,
-    pub(crate) client: reqwest::blocking::Client,
-    /// The token to use for authorization.
-    pub(crate) token: String,
+    pub(crate) client: H,
+    /// The token to use for authorization. Redacted from `Debug` and zeroized on drop.
+    pub(crate) token: TokenSource,
+    pub(crate) retry: RetryPolicy,
+    // the category indexes fetched so far, by language
+    pub(crate) category_indexes: Arc<Mutex<HashMap<Option<String>, Arc<CategoryIndex>>>>,
+}
+
+impl<H> std::fmt::Debug for PlacesClient<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PlacesClient")
+            .field("base_url", &self.base_url)
+            .field("token", &"[REDACTED]")
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
 }
 
 impl PlacesClient {
@@ -35,40 +66,202 @@ impl PlacesClient {
         Self {
             base_url: base_url.to_string(),
             client: reqwest::blocking::Client::new(),
-            token: token.to_string(),
+            token: token.into(),
+            retry: RetryPolicy::default(),
+            category_indexes: Default::default(),
+        }
+    }
+
+    /// Create a new client for the Places API using a token from `credentials`
+    pub fn from_credentials<C: CredentialProvider>(
+        base_url: &str,
+        credentials: &C,
+    ) -> Result<Self, CredentialError> {
+        Ok(Self {
+            base_url: base_url.to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: credentials.token()?.into(),
+            retry: RetryPolicy::default(),
+            category_indexes: Default::default(),
+        })
+    }
+
+    /// Create a new client for the Places API which gets a token from `provider` before every request
+    ///
+    /// Use a provider which refreshes its token, such as [`ClientCredentials`](crate::auth::ClientCredentials),
+    /// for clients that outlive a token.
+    pub fn from_token_provider<P: TokenProvider>(base_url: &str, provider: P) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: TokenSource::new(provider),
+            retry: RetryPolicy::default(),
+            category_indexes: Default::default(),
         }
     }
+}
+
+impl<H: HttpClient + Clone> PlacesClient<H> {
+    /// Send requests with `client` instead
+    pub fn with_http_client<C: HttpClient + Clone>(self, client: C) -> PlacesClient<C> {
+        PlacesClient {
+            base_url: self.base_url,
+            client,
+            token: self.token,
+            retry: self.retry,
+            category_indexes: self.category_indexes,
+        }
+    }
+
+    /// Set the policy for retrying rate limited and failed requests
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    // sends the request, retrying retryable errors and an auth error with a new token
+    pub(crate) fn send<T: DeserializeOwned>(&self, request: HttpRequest) -> Result<T, PlacesError> {
+        self.retry
+            .run_refreshing(
+                |_| self.send_once(request.clone()),
+                || self.token.invalidate(),
+            )
+            .map_err(|e| e.retried(&self.retry))
+    }
+
+    // authorizes the request, sends it, and parses the response. Errors come with the
+    // delay of the `Retry-After` header, if any.
+    fn send_once<T: DeserializeOwned>(
+        &self,
+        request: HttpRequest,
+    ) -> Result<T, (PlacesError, Option<Duration>)> {
+        let bearer = self
+            .token
+            .bearer()
+            .map_err(|e| (PlacesError::CredentialError(e), None))?;
+        let resp = trace::send(
+            &self.client,
+            request.header("X-Esri-Authorization", &bearer),
+        )
+        .map_err(|e| (PlacesError::HttpError(e), None))?;
+
+        decode_response(resp.status, &resp.body).map_err(|e| (e, resp.retry_after()))
+    }
 
     /// Query the [`/places/near-point`](https://developers.arcgis.com/rest/places/near-point-get/) endpoint
-    pub fn near_point(&self, params: NearPointQueryParams) -> Result<NearPointQuery, PlacesError> {
-        NearPointQuery::new(Arc::new(self.clone()), params)
+    pub fn near_point(
+        &self,
+        params: NearPointQueryParams,
+    ) -> Result<NearPointQuery<H>, PlacesError> {
+        self.search(params)
     }
 
     pub fn within_extent(
         &self,
         params: WithinExtentQueryParams,
-    ) -> Result<WithinExtentQuery, PlacesError> {
-        WithinExtentQuery::new(Arc::new(self.clone()), params)
+    ) -> Result<WithinExtentQuery<H>, PlacesError> {
+        self.search(params)
+    }
+
+    /// Search a paginated endpoint. The first page is requested immediately.
+    pub fn search<E: PaginatedEndpoint>(
+        &self,
+        params: E::Params,
+    ) -> Result<PaginatedQuery<E, H>, PlacesError> {
+        PaginatedQuery::new(Arc::new(self.clone()), params)
+    }
+
+    /// Query the [`/places/within-extent`](https://developers.arcgis.com/rest/places/within-extent-get/)
+    /// endpoint for an extent larger than the API allows
+    ///
+    /// See [`TiledWithinExtentQuery`]. No request is sent until the results are iterated.
+    pub fn within_extent_tiled(
+        &self,
+        params: WithinExtentQueryParams,
+    ) -> TiledWithinExtentQuery<H> {
+        TiledWithinExtentQuery::new(Arc::new(self.clone()), params)
+    }
+
+    /// Search within `rect`, which can be of any size
+    ///
+    /// The extent of `params` is replaced by `rect`, which is split into tiles if it is larger than
+    /// the API allows, as in [`PlacesClient::within_extent_tiled()`].
+    ///
+    /// ```no_run
+    /// use geo_types::{coord, Rect};
+    /// use serde_esri::places::query::{PlacesClient, WithinExtentQueryParams, PLACES_API_URL};
+    ///
+    /// let client = PlacesClient::new(PLACES_API_URL, "your token");
+    /// let rect = Rect::new(coord! { x: -118.5, y: 33.7 }, coord! { x: -117.6, y: 34.3 });
+    /// let params = WithinExtentQueryParams {
+    ///     search_text: Some("coffee".into()),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// for place in client.within_extent_rect(rect, params) {
+    ///     println!("{}", place.unwrap().name);
+    /// }
+    /// ```
+    #[cfg(feature = "geo")]
+    pub fn within_extent_rect(
+        &self,
+        rect: geo_types::Rect,
+        params: WithinExtentQueryParams,
+    ) -> TiledWithinExtentQuery<H> {
+        let params = WithinExtentQueryParams {
+            xmin: rect.min().x,
+            ymin: rect.min().y,
+            xmax: rect.max().x,
+            ymax: rect.max().y,
+            ..params
+        };
+        self.within_extent_tiled(params)
     }
 
     /// Query the [`/places/{place_id}`](https://developers.arcgis.com/rest/places/place-details-get/) endpoint
     pub fn place_details(&self, params: PlaceQueryParams) -> Result<PlaceResponse, PlacesError> {
         let fields = params.requested_fields.join(",");
+        let id = encode_path_segment(&params.place_id);
+        let request = HttpRequest::get(&format!("{}/places/{id}", self.base_url))
+            .param("requestedFields", &fields);
 
-        let response = self
-            .client
-            .get(format!("{}/places/{}", self.base_url, params.place_id))
-            .header(
-                "X-Esri-Authorization",
-                format!("Bearer {}", self.token.as_str()),
-            )
-            .query(&vec![("requestedFields", fields.as_str())])
-            .send()
-            .map_err(PlacesError::RequestError)?
-            .json::<PlaceResponse>()
-            .map_err(PlacesError::RequestError)?;
+        self.send(request)
+    }
 
-        Ok(response)
+    /// Query the [`/places/{place_id}`](https://developers.arcgis.com/rest/places/place-details-get/) endpoint
+    /// for each of `ids`, with at most `concurrency` requests at a time
+    ///
+    /// The results are keyed by place ID. A failed request doesn't stop the others.
+    pub fn place_details_batch<I, S>(
+        &self,
+        ids: I,
+        requested_fields: &[String],
+        concurrency: usize,
+    ) -> HashMap<String, Result<PlaceResponse, PlacesError>>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let ids: Vec<String> = ids.into_iter().map(Into::into).collect();
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(HashMap::with_capacity(ids.len()));
+
+        // each thread requests the next ID until none are left
+        thread::scope(|scope| {
+            for _ in 0..concurrency.clamp(1, ids.len().max(1)) {
+                scope.spawn(|| {
+                    while let Some(id) = ids.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let resp = self.place_details(PlaceQueryParams {
+                            place_id: id.clone(),
+                            requested_fields: requested_fields.to_vec(),
+                        });
+                        results.lock().unwrap().insert(id.clone(), resp);
+                    }
+                });
+            }
+        });
+
+        results.into_inner().unwrap()
     }
 
     /// Query the [`/categories`](https://developers.arcgis.com/rest/places/categories-get/) endpoint
@@ -76,20 +269,34 @@ impl PlacesClient {
         &self,
         params: CategoriesQueryParams,
     ) -> Result<CategoriesResponse, PlacesError> {
-        let response = self
-            .client
-            .get(format!("{}/categories", self.base_url))
-            .header(
-                "X-Esri-Authorization",
-                format!("Bearer {}", self.token.as_str()),
-            )
-            .query(&params)
-            .send()
-            .map_err(PlacesError::RequestError)?
-            .json::<CategoriesResponse>()
-            .map_err(PlacesError::RequestError)?;
+        let request = HttpRequest::get(&format!("{}/categories", self.base_url)).query(&params);
+
+        self.send(request)
+    }
+
+    /// The [`CategoryIndex`] of the categories in `language`, or English if `None`
+    ///
+    /// The categories are fetched from the [`/categories`](https://developers.arcgis.com/rest/places/categories-get/)
+    /// endpoint once per language and kept for the lifetime of the client and its clones.
+    pub fn category_index(
+        &self,
+        language: Option<&str>,
+    ) -> Result<Arc<CategoryIndex>, PlacesError> {
+        let language = language.map(str::to_string);
+        if let Some(index) = self.category_indexes.lock().unwrap().get(&language) {
+            return Ok(index.clone());
+        }
 
-        Ok(response)
+        let params = CategoriesQueryParams {
+            language: language.clone(),
+            ..Default::default()
+        };
+        let index = Arc::new(CategoryIndex::new(self.categories(params)?.categories));
+        self.category_indexes
+            .lock()
+            .unwrap()
+            .insert(language, index.clone());
+        Ok(index)
     }
 
     /// Query the [`/categories/{categoryId}`](https://developers.arcgis.com/rest/places/category-details-get/) endpoint
@@ -97,22 +304,260 @@ impl PlacesClient {
         &self,
         params: CategoryQueryParams,
     ) -> Result<CategoryDetails, PlacesError> {
-        let response = self
-            .client
-            .get(format!(
-                "{}/categories/{}",
-                self.base_url, params.category_id
-            ))
-            .header(
-                "X-Esri-Authorization",
-                format!("Bearer {}", self.token.as_str()),
-            )
-            .query(&params)
-            .send()
-            .map_err(PlacesError::RequestError)?
-            .json::<CategoryDetails>()
-            .map_err(PlacesError::RequestError)?;
+        let id = encode_path_segment(&params.category_id);
+        let request =
+            HttpRequest::get(&format!("{}/categories/{id}", self.base_url)).query(&params);
+
+        self.send(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{HttpError, HttpResponse};
+    use crate::places::query::{CollectPlaces, PLACES_API_URL};
+    use secrecy::SecretString;
+
+    const CATEGORIES: &str = r#"{"categories": []}"#;
+
+    // responds with each status and body in turn
+    #[derive(Clone)]
+    struct Responses(Arc<Mutex<Vec<(u16, String)>>>);
+
+    impl HttpClient for Responses {
+        fn execute(&self, _: HttpRequest) -> Result<HttpResponse, HttpError> {
+            let (status, body) = self.0.lock().unwrap().remove(0);
+            let headers = vec![("retry-after".to_string(), "0".to_string())];
+            Ok(HttpResponse {
+                status,
+                headers,
+                body: body.as_bytes().to_vec(),
+            })
+        }
+    }
+
+    fn client(responses: Vec<(u16, &str)>) -> PlacesClient<Responses> {
+        let responses = responses.into_iter().map(|(s, b)| (s, b.into())).collect();
+        // the Retry-After header overrides the long delay
+        let retry = RetryPolicy::new(2, Duration::from_secs(60));
+        PlacesClient::new(PLACES_API_URL, "token")
+            .with_http_client(Responses(Arc::new(Mutex::new(responses))))
+            .with_retry_policy(retry)
+    }
+
+    #[test]
+    fn test_retry() {
+        let busy = (429, "Too Many Requests");
+        let resp = client(vec![busy, (503, ""), (200, CATEGORIES)])
+            .categories(CategoriesQueryParams::default());
+        assert!(resp.unwrap().categories.is_empty());
+
+        let resp = client(vec![busy, busy, busy]).categories(CategoriesQueryParams::default());
+        match resp.unwrap_err() {
+            PlacesError::RetriesExhausted(e) => {
+                assert!(matches!(*e, PlacesError::HttpStatus { status: 429, .. }))
+            }
+            e => panic!("unexpected error: {e:?}"),
+        }
+
+        let resp = client(vec![(404, "")]).categories(CategoriesQueryParams::default());
+        assert!(matches!(
+            resp,
+            Err(PlacesError::HttpStatus { status: 404, .. })
+        ));
+    }
+
+    // a token that expires after the first request, until it is invalidated
+    struct Expiring(AtomicUsize);
+
+    impl CredentialProvider for Expiring {
+        fn token(&self) -> Result<SecretString, CredentialError> {
+            let token = if self.0.load(Ordering::SeqCst) > 0 {
+                "new"
+            } else {
+                "old"
+            };
+            Ok(SecretString::from(token))
+        }
+
+        fn invalidate(&self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    // rejects requests with the old token
+    #[derive(Clone)]
+    struct RejectsOld(Arc<AtomicUsize>);
+
+    impl HttpClient for RejectsOld {
+        fn execute(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let authorized = request
+                .headers
+                .iter()
+                .any(|(k, v)| k == "X-Esri-Authorization" && v == "Bearer new");
+            let (status, body) = match authorized {
+                true => (200, CATEGORIES),
+                false => (498, "Invalid Token"),
+            };
+            Ok(HttpResponse {
+                status,
+                headers: vec![],
+                body: body.as_bytes().to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_auth_retry() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client =
+            PlacesClient::from_token_provider(PLACES_API_URL, Expiring(AtomicUsize::new(0)))
+                .with_http_client(RejectsOld(requests.clone()))
+                .with_retry_policy(RetryPolicy::none());
+        let resp = client.categories(CategoriesQueryParams::default());
+        assert!(resp.unwrap().categories.is_empty());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // a static token can't be refreshed
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = PlacesClient::new(PLACES_API_URL, "old")
+            .with_http_client(RejectsOld(requests.clone()))
+            .with_retry_policy(RetryPolicy::none());
+        let resp = client.categories(CategoriesQueryParams::default());
+        assert!(matches!(
+            resp,
+            Err(PlacesError::HttpStatus { status: 498, .. })
+        ));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_error_context() {
+        let resp = client(vec![(200, "<html>")]).categories(CategoriesQueryParams::default());
+        let e = resp.unwrap_err();
+        assert!(matches!(&e, PlacesError::DecodeError { body, .. } if body == "<html>"));
+
+        let error =
+            r#"{"error": {"code": 400, "message": "Invalid", "details": [], "restInfoUrl": ""}}"#;
+        let resp = client(vec![(200, error)]).categories(CategoriesQueryParams::default());
+        assert!(matches!(resp, Err(PlacesError::ApiError(e)) if e.error.code == 400));
+
+        let first = r#"{"results": [], "pagination": {"nextUrl": "https://example.com/2"}}"#;
+        let client = client(vec![(200, first), (400, "Bad Request")]);
+        let params = NearPointQueryParams::default();
+        let e = client
+            .near_point(params)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "failed to fetch page 2 from https://example.com/2: HTTP status 400: Bad Request"
+        );
+    }
+
+    #[test]
+    fn test_page_error_ends_search() {
+        let first = r#"{"results": [], "pagination": {"nextUrl": "https://example.com/2"}}"#;
+        let client = client(vec![(200, first), (400, "Bad Request")]);
+        let mut query = client.near_point(NearPointQueryParams::default()).unwrap();
+
+        // the failed page is not requested again
+        assert!(matches!(
+            query.next(),
+            Some(Err(PlacesError::PageError { page: 2, .. }))
+        ));
+        assert!(query.next().is_none());
+        assert!(query.next().is_none());
+
+        // but can be resumed
+        let state = query.state();
+        assert_eq!(state.next_page.as_deref(), Some("https://example.com/2"));
+        assert_eq!(state.page, 1);
+    }
+
+    #[test]
+    fn test_prefetch() {
+        let page = |name: &str, next: &str| {
+            let place = format!(
+                r#"{{"categories": [], "location": {{"x": 0, "y": 0}}, "name": "{name}", "placeId": "{name}"}}"#
+            );
+            format!(r#"{{"results": [{place}], "pagination": {{"nextUrl": "{next}"}}}}"#)
+        };
+        let (first, second) = (
+            page("a", "https://example.com/2"),
+            page("b", "https://example.com/3"),
+        );
+        let client = client(vec![(200, &first), (200, &second), (404, "")]);
+
+        let results = client
+            .near_point(NearPointQueryParams::default())
+            .unwrap()
+            .with_prefetch(1)
+            .collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[1].as_ref().unwrap().name, "b");
+        assert!(matches!(
+            results[2],
+            Err(PlacesError::PageError { page: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_collect_featureset() {
+        let page = |id: &str, next: &str| {
+            let place = format!(
+                r#"{{"categories": [{{"categoryId": "13035", "label": "Coffee Shop"}}], "distance": 12.5,
+                    "location": {{"x": -117.19, "y": 34.05}}, "name": "Cafe {id}", "placeId": "{id}"}}"#
+            );
+            format!(r#"{{"results": [{place}], "pagination": {{"nextUrl": "{next}"}}}}"#)
+        };
+        let last = r#"{"results": []}"#;
+        let (first, second) = (
+            page("a", "https://example.com/2"),
+            page("b", "https://example.com/3"),
+        );
+        let client = client(vec![(200, &first), (200, &second), (200, last)]);
+
+        let fset = client
+            .near_point(NearPointQueryParams::default())
+            .unwrap()
+            .collect_featureset()
+            .unwrap();
+        assert_eq!(fset.features.len(), 2);
+        assert_eq!(fset.geometryType.as_deref(), Some("esriGeometryPoint"));
+        let attributes = fset.features[1].attributes.as_ref().unwrap();
+        assert_eq!(attributes["placeId"], "b");
+        assert_eq!(attributes["categories"], "Coffee Shop");
+        assert_eq!(attributes["distance"], 12.5);
+    }
+
+    #[test]
+    fn test_place_details_batch() {
+        let place = r#"{"placeDetails": {"placeId": "a"}}"#;
+        let client = client(vec![(200, place), (404, ""), (200, place)]);
+
+        let results = client.place_details_batch(["a", "b", "c"], &[], 2);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results.values().filter(|r| r.is_err()).count(), 1);
+
+        // an ID is a single segment of the path
+        assert_eq!(encode_path_segment("a/b c?"), "a%2Fb%20c%3F");
+    }
+
+    #[test]
+    fn test_category_index() {
+        let categories =
+            r#"{"categories": [{"categoryId": "10000", "fullLabel": ["Dining and Drinking"]}]}"#;
+        let client = client(vec![(200, categories)]);
 
-        Ok(response)
+        // the second index is cached rather than requested
+        let index = client.category_index(None).unwrap();
+        assert!(Arc::ptr_eq(&index, &client.category_index(None).unwrap()));
+        assert_eq!(index.name_of("10000"), Some("Dining and Drinking"));
     }
 }
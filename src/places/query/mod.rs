@@ -5,14 +5,25 @@
 //! Each endpoint is supported from the client, and you can query them with the appropriate method. For example, to query the [`/places/near-point`](https://developers.arcgis.com/rest/places/near-point-get/) endpoint, you can use the [`PlacesClient::near_point()`] method.
 //!
 //! The client is responsible for handling the authorization token and making the requests to the API.
+//! Requests are sent with `reqwest` by default. Use [`PlacesClient::with_http_client()`] to send them with any other [`HttpClient`](crate::http::HttpClient).
 //!
 //! For [`PlacesClient::near_point()`] and [`PlacesClient::within_extent()`] queries, the client will automatically handle pagination and return an iterator over the results. This iterator will fetch the next page when needed. It will not, however, allow you to modify the page size. The default will be used.
+//! Call `with_prefetch()` on the query to fetch the following pages on a background thread while the current page is consumed.
+//!
+//! The API limits the size of a `/places/within-extent` search. [`PlacesClient::within_extent_tiled()`] searches a larger extent one tile at a time and removes the duplicate results.
+//! With the `geo` feature, `PlacesClient::within_extent_rect()` does the same for a `geo_types::Rect`.
+//!
+//! Both clients share their pagination. A [`PaginatedEndpoint`] describes the first request of a search and how to read its pages.
+//! [`PlacesClient::search()`] searches it with a [`PaginatedQuery`], of which [`NearPointQuery`] and [`WithinExtentQuery`] are aliases, and `AsyncPlacesClient::search()` with a stream.
+//!
+//! With the `places-client-async` feature, [`AsyncPlacesClient`] provides the same endpoints for async runtimes. Its searches return a `futures::Stream` of results instead of an iterator.
+//! It is the only client in builds for `wasm32-unknown-unknown`, where its requests are sent with the browser's `fetch` and its streams are not `Send`.
 //!
 //! Each endpoint is supported by a query struct that contains the parameters for the query. These query structs each have a corresponding `Builder` ussed to build the request and prepare the parameters for the request.
 //!
 //! - [`PlaceQueryParamsBuilder`] creates a [`PlaceQueryParams`] struct which is used in the [`PlacesClient::place_details()`] method.
-//! - [`NearPointQueryParamsBuilder`] creates a [`NearPointQueryParams`] struct which is used in the [`PlacesClient::near_point()`] method.
-//! - [`WithinExtentQueryParamsBuilder`] creates a [`WithinExtentQueryParams`] struct which is used in the [`PlacesClient::within_extent()`] method.
+//! - [`NearPointQueryParamsBuilder`] creates and validates a [`NearPointQueryParams`] struct which is used in the [`PlacesClient::near_point()`] method.
+//! - [`WithinExtentQueryParamsBuilder`] creates and validates a [`WithinExtentQueryParams`] struct which is used in the [`PlacesClient::within_extent()`] method.
 //! - [`CategoriesQueryParamsBuilder`] creates a [`CategoriesQueryParams`] struct which is used in the [`PlacesClient::categories()`] method.
 //! - [`CategoryQueryParamsBuilder`] creates a [`CategoryQueryParams`] struct which is used in the [`PlacesClient::category_details()`] method.
 mod query_params;
@@ -20,12 +31,25 @@ mod responses;
 pub use query_params::*;
 pub use responses::*;
 
+#[cfg(feature = "places-client-async")]
+mod async_client;
 #[cfg(feature = "places-client")]
+mod paginated;
+// the blocking client is not available in wasm builds
+#[cfg(all(feature = "places-client", not(target_arch = "wasm32")))]
 mod client;
-#[cfg(feature = "places-client")]
+#[cfg(all(feature = "places-client", not(target_arch = "wasm32")))]
 mod place_search;
 
-#[cfg(feature = "places-client")]
+#[cfg(feature = "places-client-async")]
+pub use async_client::*;
+#[cfg(all(feature = "places-client", not(target_arch = "wasm32")))]
 pub use client::*;
 #[cfg(feature = "places-client")]
+pub use paginated::*;
+#[cfg(all(feature = "places-client", not(target_arch = "wasm32")))]
 pub use place_search::*;
+
+/// The base URL for the Places API
+pub const PLACES_API_URL: &str =
+    "https://places-api.arcgis.com/arcgis/rest/services/places-service/v1";
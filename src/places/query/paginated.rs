@@ -0,0 +1,228 @@
+// The pagination shared by the blocking and async clients
+//
+// An endpoint only describes its first request and how to read a page of its
+// response. `Pages` holds the state of a search and decides which page is requested
+// next, and a `PageTransport` fetches it, so the same endpoint is searched with
+// `PaginatedQuery` on a blocking client and as a `PlaceStream` on an async one.
+
+use crate::http::HttpRequest;
+use crate::places::query::{
+    ExpectedResponse, NearPointQueryParams, PlacesError, WithinExtentQueryParams,
+};
+use crate::places::PlaceResult;
+use std::marker::PhantomData;
+
+/// A page of the results of a paginated search
+#[derive(Debug, Clone, Default)]
+pub struct Page {
+    pub results: Vec<PlaceResult>,
+    /// The URL of the next page, or `None` if this is the last page
+    pub next_url: Option<String>,
+}
+
+/// An endpoint of the Places API whose results are split into pages
+///
+/// The following pages are requested from the `nextUrl` of the pagination of a response.
+///
+/// ```
+/// use serde_esri::http::HttpRequest;
+/// use serde_esri::places::query::{PaginatedEndpoint, PlacesError};
+///
+/// struct Nearby;
+///
+/// impl PaginatedEndpoint for Nearby {
+///     type Params = (f64, f64);
+///
+///     fn first_request(base_url: &str, (x, y): &(f64, f64)) -> Result<HttpRequest, PlacesError> {
+///         Ok(HttpRequest::get(&format!("{base_url}/places/near-point"))
+///             .param("x", &x.to_string())
+///             .param("y", &y.to_string()))
+///     }
+/// }
+/// ```
+pub trait PaginatedEndpoint {
+    /// The parameters of a search
+    type Params: Clone;
+
+    /// The request of the first page of a search, or an error if `params` are invalid
+    fn first_request(base_url: &str, params: &Self::Params) -> Result<HttpRequest, PlacesError>;
+
+    /// The results and next page of a response
+    fn parse_page(resp: ExpectedResponse) -> Result<Page, PlacesError> {
+        match resp {
+            ExpectedResponse::Point(resp) => Ok(Page {
+                results: resp.results,
+                next_url: resp.pagination.and_then(|p| p.next_url),
+            }),
+            ExpectedResponse::Error(e) => Err(PlacesError::ApiError(e)),
+        }
+    }
+}
+
+/// The [`/places/near-point`](https://developers.arcgis.com/rest/places/near-point-get/) endpoint
+#[derive(Debug, Clone, Copy)]
+pub struct NearPoint;
+
+impl PaginatedEndpoint for NearPoint {
+    type Params = NearPointQueryParams;
+
+    fn first_request(base_url: &str, params: &Self::Params) -> Result<HttpRequest, PlacesError> {
+        params.validate().map_err(PlacesError::InvalidParams)?;
+        let url = format!("{base_url}/places/near-point");
+        Ok(HttpRequest::get(&url).query(&params.clone().prepare()))
+    }
+}
+
+/// The [`/places/within-extent`](https://developers.arcgis.com/rest/places/within-extent-get/) endpoint
+#[derive(Debug, Clone, Copy)]
+pub struct WithinExtent;
+
+impl PaginatedEndpoint for WithinExtent {
+    type Params = WithinExtentQueryParams;
+
+    fn first_request(base_url: &str, params: &Self::Params) -> Result<HttpRequest, PlacesError> {
+        params.validate().map_err(PlacesError::InvalidParams)?;
+        let url = format!("{base_url}/places/within-extent");
+        Ok(HttpRequest::get(&url).query(&params.clone().prepare()))
+    }
+}
+
+// reads page number `page` of a search, requested from `url` unless it is the first.
+// Failures of further pages say which page failed.
+pub(crate) fn read_page(
+    resp: Result<ExpectedResponse, PlacesError>,
+    parse: fn(ExpectedResponse) -> Result<Page, PlacesError>,
+    url: Option<&str>,
+    page: usize,
+) -> Result<Page, PlacesError> {
+    resp.and_then(parse).map_err(|e| match url {
+        Some(url) => PlacesError::PageError {
+            url: url.to_string(),
+            page,
+            source: Box::new(e),
+        },
+        None => e,
+    })
+}
+
+pub(crate) mod sealed {
+    pub trait Sealed {}
+}
+
+/// Fetches the pages of a search, either blocking or async
+///
+/// Implemented by [`PlacesClient`](crate::places::query::PlacesClient) and
+/// `AsyncPlacesClient`, which search every [`PaginatedEndpoint`] in the same way.
+/// This trait is sealed.
+pub trait PageTransport: sealed::Sealed {
+    /// The page, or for an async client a future of the page
+    type Fetch;
+
+    /// Send `request` for page number `page` of a search, which was requested from
+    /// `url` unless it is the first
+    #[doc(hidden)]
+    fn fetch<E: PaginatedEndpoint>(
+        &self,
+        request: HttpRequest,
+        url: Option<String>,
+        page: usize,
+    ) -> Self::Fetch;
+}
+
+// what a search does next
+pub(crate) enum Step<F> {
+    // return a result of the current page
+    Place(PlaceResult),
+    // wait for the next page, then `receive()` it
+    Fetch(F),
+    Done,
+}
+
+// the state of a search of an endpoint `E`: the results of the current page and the
+// request of the next one
+#[derive(Debug)]
+pub(crate) struct Pages<E> {
+    pub(crate) results: std::vec::IntoIter<PlaceResult>,
+    pub(crate) next: Option<HttpRequest>,
+    // the number of pages fetched so far
+    pub(crate) page: usize,
+    // the request being fetched
+    pending: Option<HttpRequest>,
+    // the request of the page that failed, which ended the search
+    failed: Option<HttpRequest>,
+    endpoint: PhantomData<fn() -> E>,
+}
+
+impl<E> Clone for Pages<E> {
+    fn clone(&self) -> Self {
+        Self {
+            results: self.results.clone(),
+            next: self.next.clone(),
+            page: self.page,
+            pending: self.pending.clone(),
+            failed: self.failed.clone(),
+            endpoint: PhantomData,
+        }
+    }
+}
+
+impl<E: PaginatedEndpoint> Pages<E> {
+    // a search that starts with `first`
+    pub(crate) fn new(first: HttpRequest) -> Self {
+        Self::resume(Vec::new(), Some(first), 0)
+    }
+
+    pub(crate) fn resume(
+        results: Vec<PlaceResult>,
+        next: Option<HttpRequest>,
+        page: usize,
+    ) -> Self {
+        Self {
+            results: results.into_iter(),
+            next,
+            page,
+            pending: None,
+            failed: None,
+            endpoint: PhantomData,
+        }
+    }
+
+    // the URL of the next page, or of the page that failed
+    pub(crate) fn next_url(&self) -> Option<String> {
+        let next = self.next.as_ref().or(self.failed.as_ref());
+        next.map(|request| request.url.clone())
+    }
+
+    // the next result of the current page, or else the next page from `transport`
+    pub(crate) fn step<T: PageTransport>(&mut self, transport: &T) -> Step<T::Fetch> {
+        if let Some(place) = self.results.next() {
+            return Step::Place(place);
+        }
+        let Some(request) = self.next.take() else {
+            return Step::Done;
+        };
+
+        // further pages are requested from the URL of the previous page
+        let url = (self.page > 0).then(|| request.url.clone());
+        self.pending = Some(request.clone());
+        Step::Fetch(transport.fetch::<E>(request, url, self.page + 1))
+    }
+
+    // makes the page fetched by `step()` the current one. A page that failed ends the
+    // search, which can still be resumed from its state.
+    pub(crate) fn receive(&mut self, page: Result<Page, PlacesError>) -> Result<(), PlacesError> {
+        match page {
+            Ok(page) => {
+                self.page += 1;
+                self.pending = None;
+                self.results = page.results.into_iter();
+                self.next = page.next_url.map(|url| HttpRequest::get(&url));
+                Ok(())
+            }
+            Err(e) => {
+                self.failed = self.pending.take();
+                Err(e)
+            }
+        }
+    }
+}
@@ -4,110 +4,137 @@
 // move to serde_esri
 // feature gate Client and NearbyQuery as well as WithinQuery
 
+use crate::features::FeatureSet;
+use crate::geometry::{EsriEnvelope, EsriEnvelopeTiles};
+use crate::http::{HttpClient, HttpRequest};
 use crate::places::{
     query::{
-        ExpectedResponse, NearPointQueryParams, PlacesClient, PlacesError, PointResponse,
-        WithinExtentQueryParams,
+        read_page, sealed::Sealed, NearPoint, Page, PageTransport, Pages, PaginatedEndpoint,
+        PlacesClient, PlacesError, Step, WithinExtent, WithinExtentQueryParams,
     },
     PlaceResult,
 };
-use std::sync::Arc;
+use crate::trace::PageSpan;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    sync::{
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+};
 
-/// Struct used to query the /places/near-point endpoint
-#[derive(Debug, Clone)]
-pub struct NearPointQuery {
-    /// The client as created by [`PlacesClient::new()`]
-    pub client: Arc<PlacesClient>,
+/// The state of a paginated place search, used to resume it later
+///
+/// Serialize the state returned by `state()` to survive a restart, then pass
+/// it to `resume()` to continue without repeating the initial request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaceSearchState<P> {
     /// The parameters used to query the endpoint
-    pub params: NearPointQueryParams,
-    /// The results of the query as an iterator. This iterator will automatically fetch the next page when needed.
-    pub results: <Vec<PlaceResult> as IntoIterator>::IntoIter,
-    /// The next page to fetch. This is automatically updated when the iterator reaches the end of the current page.
+    pub params: P,
+    /// The results of the current page that have not been returned yet
+    pub results: Vec<PlaceResult>,
+    /// The next page to fetch, or the page that failed and ended the search
     pub next_page: Option<String>,
+    /// The number of pages fetched so far
+    #[serde(default)]
+    pub page: usize,
 }
 
-impl NearPointQuery {
-    /// Create a new [`NearPointQuery`] from a [`PlacesClient`] and a [`NearPointQueryParams`]
-    /// This will send the initial request and parse the response. The next page
-    /// is stored in the `next_page` field. Use  `.into_iter()` to iterate over the results
-    /// and the subsequent pages.
-    ///
-    /// Note that requests are paginated so these impls use a blocking reqwest client.
-    pub fn new(
-        client: Arc<PlacesClient>,
-        params: NearPointQueryParams,
-    ) -> Result<Self, PlacesError> {
-        // create the initial request
-        let c = client
-            .client
-            .get(format!("{}/places/near-point", client.base_url))
-            .query(&params.clone().prepare())
-            .header("X-Esri-Authorization", format!("Bearer {}", client.token));
-
-        // send the request and parse the response
-        let resp = c
-            .send()
-            .map_err(PlacesError::RequestError)?
-            .json::<ExpectedResponse>()
-            .map_err(PlacesError::RequestError)?;
-
-        // Handle the ExpectedResponse
-        let point_response = match resp {
-            ExpectedResponse::Point(point_response) => point_response,
-            ExpectedResponse::Error(error_response) => {
-                return Err(PlacesError::ApiError(error_response))
-            }
-        };
+/// A paginated search of an endpoint `E`, such as [`NearPoint`] or [`WithinExtent`]
+///
+/// The search is an iterator over its results, which fetches the next page when needed.
+/// It ends after the first error. Its progress is exported with [`PaginatedQuery::state()`],
+/// from which a failed page is requested again by [`PaginatedQuery::resume()`].
+#[derive(Debug)]
+pub struct PaginatedQuery<E: PaginatedEndpoint, H = reqwest::blocking::Client> {
+    /// The client as created by [`PlacesClient::new()`]
+    pub client: Arc<PlacesClient<H>>,
+    /// The parameters used to query the endpoint
+    pub params: E::Params,
+    pages: Pages<E>,
+}
 
-        // fetch the pagination
-        let next_page = match point_response.pagination {
-            Some(p) => p.next_url,
-            None => None,
-        };
+/// Struct used to query the /places/near-point endpoint
+pub type NearPointQuery<H = reqwest::blocking::Client> = PaginatedQuery<NearPoint, H>;
+
+/// Struct used to query the /places/within-extent endpoint
+pub type WithinExtentQuery<H = reqwest::blocking::Client> = PaginatedQuery<WithinExtent, H>;
+
+impl<E: PaginatedEndpoint, H: Clone> Clone for PaginatedQuery<E, H> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            params: self.params.clone(),
+            pages: self.pages.clone(),
+        }
+    }
+}
+
+impl<E: PaginatedEndpoint, H: HttpClient + Clone> PaginatedQuery<E, H> {
+    /// Create a new query from a [`PlacesClient`] and the parameters of the endpoint
+    /// This will send the initial request and parse the response. Use  `.into_iter()`
+    /// to iterate over the results and the subsequent pages.
+    ///
+    /// Note that requests are paginated so these impls use a blocking [`HttpClient`].
+    pub fn new(client: Arc<PlacesClient<H>>, params: E::Params) -> Result<Self, PlacesError> {
+        let mut pages = Pages::new(E::first_request(&client.base_url, &params)?);
+        if let Step::Fetch(page) = pages.step(&*client) {
+            pages.receive(page)?;
+        }
 
-        // return the results
         Ok(Self {
             client,
             params,
-            results: point_response.results.into_iter(),
-            next_page,
+            pages,
         })
     }
 
-    pub fn try_next(&mut self) -> Result<Option<PlaceResult>, PlacesError> {
-        if let Some(place_res) = self.results.next() {
-            return Ok(Some(place_res));
+    /// Export the state of the search so it can be resumed with [`PaginatedQuery::resume()`]
+    pub fn state(&self) -> PlaceSearchState<E::Params> {
+        PlaceSearchState {
+            params: self.params.clone(),
+            results: self.pages.results.as_slice().to_vec(),
+            next_page: self.pages.next_url(),
+            page: self.pages.page,
         }
+    }
 
-        if self.next_page.is_none() {
-            return Ok(None);
+    /// Resume a search from a state exported with [`PaginatedQuery::state()`]. No request is sent.
+    pub fn resume(client: Arc<PlacesClient<H>>, state: PlaceSearchState<E::Params>) -> Self {
+        let next = state.next_page.map(|url| HttpRequest::get(&url));
+        Self {
+            client,
+            params: state.params,
+            pages: Pages::resume(state.results, next, state.page),
         }
+    }
 
-        let next_page = self
-            .client
-            .client
-            .get(self.next_page.as_ref().unwrap())
-            .header(
-                "X-Esri-Authorization",
-                format!("Bearer {}", self.client.token),
-            )
-            .send()
-            .map_err(PlacesError::RequestError)?
-            .json::<PointResponse>()
-            .map_err(PlacesError::RequestError)?;
-
-        self.results = next_page.results.into_iter();
-        self.next_page = match next_page.pagination {
-            Some(p) => p.next_url,
-            None => None,
-        };
+    pub fn try_next(&mut self) -> Result<Option<PlaceResult>, PlacesError> {
+        loop {
+            match self.pages.step(&*self.client) {
+                Step::Place(place) => return Ok(Some(place)),
+                Step::Fetch(page) => self.pages.receive(page)?,
+                Step::Done => return Ok(None),
+            }
+        }
+    }
 
-        Ok(self.results.next())
+    /// Fetch the following pages on a background thread while the current page is consumed
+    ///
+    /// At most `pages` pages are fetched ahead of the page being consumed.
+    pub fn with_prefetch(self, pages: usize) -> PrefetchQuery
+    where
+        E: 'static,
+        H: 'static,
+    {
+        PrefetchQuery::new(self.client, self.pages, pages)
     }
 }
 
-/// This lets you paginate through the results of a NearbyQuery
-impl Iterator for NearPointQuery {
+/// This lets you paginate through the results of a search
+impl<E: PaginatedEndpoint, H: HttpClient + Clone> Iterator for PaginatedQuery<E, H> {
     type Item = Result<PlaceResult, PlacesError>;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -119,100 +146,245 @@ impl Iterator for NearPointQuery {
     }
 }
 
-/// Struct used to query the /places/near-point endpoint
-#[derive(Debug, Clone)]
-pub struct WithinExtentQuery {
-    /// The client as created by [`PlacesClient::new()`]
-    pub client: Arc<PlacesClient>,
-    /// The parameters used to query the endpoint
-    pub params: WithinExtentQueryParams,
-    /// The results of the query as an iterator. This iterator will automatically fetch the next page when needed.
-    pub results: <Vec<PlaceResult> as IntoIterator>::IntoIter,
-    /// The next page to fetch. This is automatically updated when the iterator reaches the end of the current page.
-    pub next_page: Option<String>,
+impl<H> Sealed for PlacesClient<H> {}
+
+impl<H: HttpClient + Clone> PageTransport for PlacesClient<H> {
+    type Fetch = Result<Page, PlacesError>;
+
+    fn fetch<E: PaginatedEndpoint>(
+        &self,
+        request: HttpRequest,
+        url: Option<String>,
+        page: usize,
+    ) -> Self::Fetch {
+        let span = PageSpan::new(page as u64);
+        let resp = span.in_scope(|| self.send(request));
+        let resp = read_page(resp, E::parse_page, url.as_deref(), page)?;
+
+        span.results(resp.results.len());
+        Ok(resp)
+    }
 }
-impl WithinExtentQuery {
-    pub fn new(
-        client: Arc<PlacesClient>,
-        params: WithinExtentQueryParams,
-    ) -> Result<Self, PlacesError> {
-        // create the initial request
-        let c = client
-            .client
-            .get(format!("{}/places/within-extent", client.base_url))
-            .query(&params.clone().prepare())
-            .header("X-Esri-Authorization", format!("Bearer {}", client.token));
-
-        // send the request and parse the response
-        let resp = c
-            .send()
-            .map_err(PlacesError::RequestError)?
-            .json::<ExpectedResponse>()
-            .map_err(PlacesError::RequestError)?;
-
-        // Handle the ExpectedResponse
-        let point_response = match resp {
-            ExpectedResponse::Point(point_response) => point_response,
-            ExpectedResponse::Error(error_response) => {
-                return Err(PlacesError::ApiError(error_response))
+
+/// A place search which fetches its pages on a background thread
+///
+/// Created by [`PaginatedQuery::with_prefetch()`].
+/// The thread stops after the last page, after an error, or when the query is dropped.
+#[derive(Debug)]
+pub struct PrefetchQuery {
+    results: <Vec<PlaceResult> as IntoIterator>::IntoIter,
+    pages: Receiver<Result<Vec<PlaceResult>, PlacesError>>,
+}
+
+impl PrefetchQuery {
+    fn new<E: PaginatedEndpoint + 'static, H: HttpClient + Clone + 'static>(
+        client: Arc<PlacesClient<H>>,
+        mut search: Pages<E>,
+        pages: usize,
+    ) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(pages);
+        let results = std::mem::take(&mut search.results);
+
+        thread::spawn(move || {
+            while let Step::Fetch(page) = search.step(&*client) {
+                let resp = search
+                    .receive(page)
+                    .map(|_| std::mem::take(&mut search.results).collect());
+                // stop when the query is dropped
+                if sender.send(resp).is_err() {
+                    break;
+                }
             }
+        });
+
+        Self {
+            results,
+            pages: receiver,
+        }
+    }
+}
+
+impl Iterator for PrefetchQuery {
+    type Item = Result<PlaceResult, PlacesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(place) = self.results.next() {
+                return Some(Ok(place));
+            }
+
+            // the channel is closed after the last page or an error
+            match self.pages.recv().ok()? {
+                Ok(results) => self.results = results.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Collects every result of a place search, fetching all of its pages
+///
+/// Implemented for the iterators of place searches such as [`NearPointQuery`],
+/// [`WithinExtentQuery`], [`PrefetchQuery`], and [`TiledWithinExtentQuery`]. The
+/// first error stops the search and is returned.
+///
+/// ```no_run
+/// use serde_esri::places::query::{
+///     CollectPlaces, NearPointQueryParams, PlacesClient, PLACES_API_URL,
+/// };
+///
+/// let client = PlacesClient::new(PLACES_API_URL, "your token");
+/// let params = NearPointQueryParams::builder().point(-117.19, 34.05).build().unwrap();
+///
+/// let fset = client.near_point(params).unwrap().collect_featureset().unwrap();
+/// println!("{}", fset.features.len());
+/// ```
+pub trait CollectPlaces: Iterator<Item = Result<PlaceResult, PlacesError>> + Sized {
+    /// Collect the places into a point `FeatureSet`. See [`PlaceResult::to_feature()`] for its attributes.
+    fn collect_featureset(self) -> Result<FeatureSet<2>, PlacesError> {
+        self.collect()
+    }
+
+    /// Collect the places into a GeoJSON `FeatureCollection` with the place IDs as feature IDs
+    #[cfg(feature = "geojson")]
+    fn collect_geojson(self) -> Result<geojson::FeatureCollection, PlacesError> {
+        let fset = self.collect_featureset()?;
+        let mut collection = geojson::FeatureCollection::from(&fset);
+        for feature in &mut collection.features {
+            let place_id = feature.property("placeId").and_then(|id| id.as_str());
+            feature.id = place_id.map(|id| geojson::feature::Id::String(id.to_string()));
+        }
+        Ok(collection)
+    }
+}
+
+impl<I: Iterator<Item = Result<PlaceResult, PlacesError>>> CollectPlaces for I {}
+
+/// The maximum width and height, in meters, of a `places/within-extent` search
+pub const MAX_EXTENT_METERS: f64 = 20_000.0;
+
+// the length of a degree of latitude, in meters
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// A `places/within-extent` search of an extent of any size
+///
+/// Created by [`PlacesClient::within_extent_tiled()`]. The extent is subdivided
+/// into tiles within the [`MAX_EXTENT_METERS`] limit of the API, which are
+/// searched one after another. A place found in more than one tile is only
+/// returned once. An extent that is not finite is an error.
+#[derive(Debug)]
+pub struct TiledWithinExtentQuery<H = reqwest::blocking::Client> {
+    client: Arc<PlacesClient<H>>,
+    params: WithinExtentQueryParams,
+    tiles: EsriEnvelopeTiles,
+    current: Option<WithinExtentQuery<H>>,
+    seen: HashSet<String>,
+    // the extent is not finite
+    invalid: bool,
+}
+
+impl<H: HttpClient + Clone> TiledWithinExtentQuery<H> {
+    pub(crate) fn new(client: Arc<PlacesClient<H>>, params: WithinExtentQueryParams) -> Self {
+        let extent = EsriEnvelope {
+            xmin: params.xmin,
+            ymin: params.ymin,
+            xmax: params.xmax,
+            ymax: params.ymax,
+            ..Default::default()
         };
 
-        // fetch the pagination
-        let next_page = match point_response.pagination {
-            Some(p) => p.next_url,
-            None => None,
+        // a degree of longitude is longest at the latitude closest to the equator
+        let min_lat = match params.ymin <= 0.0 && params.ymax >= 0.0 {
+            true => 0.0,
+            false => params.ymin.abs().min(params.ymax.abs()).min(89.0),
         };
+        let max_height = MAX_EXTENT_METERS / METERS_PER_DEGREE;
+        let max_width = max_height / min_lat.to_radians().cos();
 
-        // return the results
-        Ok(Self {
+        Self {
             client,
             params,
-            results: point_response.results.into_iter(),
-            next_page,
-        })
+            tiles: extent.tiles(max_width, max_height),
+            current: None,
+            seen: HashSet::new(),
+            invalid: ![extent.xmin, extent.ymin, extent.xmax, extent.ymax]
+                .iter()
+                .all(|c| c.is_finite()),
+        }
     }
 
-    pub fn try_next(&mut self) -> Result<Option<PlaceResult>, PlacesError> {
-        if let Some(place_res) = self.results.next() {
-            return Ok(Some(place_res));
-        }
+    /// The number of tiles that have not been searched yet
+    pub fn remaining_tiles(&self) -> usize {
+        self.tiles.len()
+    }
+}
 
-        if self.next_page.is_none() {
-            return Ok(None);
+impl<H: HttpClient + Clone> Iterator for TiledWithinExtentQuery<H> {
+    type Item = Result<PlaceResult, PlacesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if std::mem::take(&mut self.invalid) {
+            let e = "the extent must be finite".to_string();
+            return Some(Err(PlacesError::InvalidParams(e)));
         }
 
-        let next_page = self
-            .client
-            .client
-            .get(self.next_page.as_ref().unwrap())
-            .header(
-                "X-Esri-Authorization",
-                format!("Bearer {}", self.client.token),
-            )
-            .send()
-            .map_err(PlacesError::RequestError)?
-            .json::<PointResponse>()
-            .map_err(PlacesError::RequestError)?;
-
-        self.results = next_page.results.into_iter();
-        self.next_page = match next_page.pagination {
-            Some(p) => p.next_url,
-            None => None,
-        };
+        loop {
+            match self.current.as_mut().and_then(Iterator::next) {
+                Some(Ok(place)) if !self.seen.insert(place.place_id.clone()) => continue,
+                Some(res) => return Some(res),
+                None => {}
+            }
 
-        Ok(self.results.next())
+            let tile = self.tiles.next()?;
+            let params = WithinExtentQueryParams {
+                xmin: tile.xmin,
+                ymin: tile.ymin,
+                xmax: tile.xmax,
+                ymax: tile.ymax,
+                ..self.params.clone()
+            };
+
+            match WithinExtentQuery::new(self.client.clone(), params) {
+                Ok(query) => self.current = Some(query),
+                Err(e) => {
+                    self.current = None;
+                    return Some(Err(e));
+                }
+            }
+        }
     }
 }
 
-impl Iterator for WithinExtentQuery {
-    type Item = Result<PlaceResult, PlacesError>;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::places::query::PLACES_API_URL;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.try_next() {
-            Ok(Some(place_res)) => Some(Ok(place_res)),
-            Ok(None) => None,
-            Err(e) => Some(Err(e)),
+    #[test]
+    fn test_tile_width() {
+        let client = Arc::new(PlacesClient::new(PLACES_API_URL, "token"));
+        for (ymin, ymax) in [(0.0, 60.0), (-10.0, 60.0), (-70.0, -50.0), (40.0, 41.0)] {
+            let params = WithinExtentQueryParams {
+                xmin: 10.0,
+                ymin,
+                xmax: 11.0,
+                ymax,
+                ..Default::default()
+            };
+            let query = TiledWithinExtentQuery::new(client.clone(), params);
+
+            for tile in query.tiles.clone() {
+                // the edge of a tile closest to the equator is its widest
+                let lat = match tile.ymin <= 0.0 && tile.ymax >= 0.0 {
+                    true => 0.0,
+                    false => tile.ymin.abs().min(tile.ymax.abs()),
+                };
+                let width = (tile.xmax - tile.xmin) * METERS_PER_DEGREE * lat.to_radians().cos();
+                assert!(
+                    width <= MAX_EXTENT_METERS + 1e-6,
+                    "{tile:?} is {width} m wide"
+                );
+            }
         }
     }
 }
@@ -2,8 +2,58 @@ use crate::places::Icon;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
+/// The largest `radius` of a `/places/near-point` search, in meters
+pub const MAX_RADIUS_METERS: f64 = 10_000.0;
+
+/// The largest number of category IDs of a search
+pub const MAX_CATEGORY_IDS: usize = 10;
+
+/// The longest `search_text` of a search, in characters
+pub const MAX_SEARCH_TEXT_LEN: usize = 255;
+
+// checks the parameters shared by the search endpoints
+fn validate_filters(
+    category_ids: Option<&Vec<String>>,
+    search_text: Option<&String>,
+) -> Result<(), String> {
+    if category_ids.is_some_and(|ids| ids.len() > MAX_CATEGORY_IDS) {
+        return Err(format!(
+            "at most {MAX_CATEGORY_IDS} category IDs can be searched"
+        ));
+    }
+    if search_text.is_some_and(|text| text.chars().count() > MAX_SEARCH_TEXT_LEN) {
+        return Err(format!(
+            "search text is longer than {MAX_SEARCH_TEXT_LEN} characters"
+        ));
+    }
+    Ok(())
+}
+
+fn validate_coordinate(x: f64, y: f64) -> Result<(), String> {
+    if !(-180.0..=180.0).contains(&x) || !(-90.0..=90.0).contains(&y) {
+        return Err(format!("({x}, {y}) is not a WGS84 longitude and latitude"));
+    }
+    Ok(())
+}
+
 /// Request parameters used to query the [`/places/near-point`](https://developers.arcgis.com/rest/places/near-point-get/) endpoint
+///
+/// The builder checks the parameters against the limits of the API.
+///
+/// ```
+/// use serde_esri::places::query::NearPointQueryParams;
+///
+/// let params = NearPointQueryParams::builder()
+///     .point(-117.19, 34.05)
+///     .radius(500.0)
+///     .category_ids(["17119", "10051"])
+///     .build()
+///     .unwrap();
+///
+/// assert!(NearPointQueryParams::builder().point(0.0, 0.0).radius(50_000.0).build().is_err());
+/// ```
 #[derive(Debug, Clone, Deserialize, Serialize, Default, Builder)]
+#[builder(build_fn(private, name = "build_unvalidated"))]
 pub struct NearPointQueryParams {
     /// The x, or longitude, of this location in WGS84 decimal degrees.
     pub x: f64,
@@ -33,6 +83,7 @@ pub struct NearPointQueryParams {
 }
 
 /// Prepared version of NearPointQueryParams which concatenates the category_ids
+#[cfg(feature = "places-client")]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct NearPointPreparedParams {
@@ -44,8 +95,25 @@ pub(crate) struct NearPointPreparedParams {
     icon: Option<Icon>,
 }
 
-#[cfg(feature = "places-client")]
 impl NearPointQueryParams {
+    pub fn builder() -> NearPointQueryParamsBuilder {
+        NearPointQueryParamsBuilder::default()
+    }
+
+    /// Check the parameters against the limits of the API
+    pub fn validate(&self) -> Result<(), String> {
+        validate_coordinate(self.x, self.y)?;
+        if let Some(radius) = self.radius {
+            if !(radius > 0.0 && radius <= MAX_RADIUS_METERS) {
+                return Err(format!(
+                    "radius must be greater than 0 and at most {MAX_RADIUS_METERS} meters"
+                ));
+            }
+        }
+        validate_filters(self.category_id.as_ref(), self.search_text.as_ref())
+    }
+
+    #[cfg(feature = "places-client")]
     pub(crate) fn prepare(self) -> NearPointPreparedParams {
         NearPointPreparedParams {
             x: self.x,
@@ -59,7 +127,25 @@ impl NearPointQueryParams {
 }
 
 /// Request parameters used to query the [`/places/within-extent`](https://developers.arcgis.com/rest/places/within-extent-get/) endpoint
+///
+/// The builder checks the parameters against the limits of the API, except for the size
+/// of the extent, which [`PlacesClient::within_extent_tiled()`](crate::places::query::PlacesClient::within_extent_tiled)
+/// can exceed. An extent that crosses the antimeridian must be split in two.
+///
+/// ```
+/// use serde_esri::places::query::WithinExtentQueryParams;
+///
+/// let params = WithinExtentQueryParams::builder()
+///     .extent(-118.02, 33.90, -117.98, 33.94)
+///     .search_text("coffee")
+///     .build()
+///     .unwrap();
+///
+/// // crosses the antimeridian
+/// assert!(WithinExtentQueryParams::builder().extent(179.9, 0.0, -179.9, 0.1).build().is_err());
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize, Builder, Default)]
+#[builder(build_fn(private, name = "build_unvalidated"))]
 #[serde(rename_all = "camelCase")]
 pub struct WithinExtentQueryParams {
     /// The minimum x coordinate, or longitude, of the search extent.
@@ -82,7 +168,7 @@ pub struct WithinExtentQueryParams {
 
     /// You can obtain information on category Ids from the places/categories endpoint. For example, to filter for places where the category is "Bicycle Store", include the categoryId 17117 in this property.
     /// You can search up to a maximum of 10 category Ids.
-    #[builder(setter(into, strip_option), default)]
+    #[builder(setter(custom), default)]
     pub category_ids: Option<Vec<String>>,
     /// Free search text for places against names, categories etc. Maximum of 255 characters.
     #[builder(setter(into, strip_option), default)]
@@ -93,6 +179,7 @@ pub struct WithinExtentQueryParams {
 }
 
 /// Prepared version of NearPointQueryParams which concatenates the category_ids
+#[cfg(feature = "places-client")]
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct WithinExtentPreparedParams {
@@ -105,8 +192,25 @@ pub(crate) struct WithinExtentPreparedParams {
     icon: Option<Icon>,
 }
 
-#[cfg(feature = "places-client")]
 impl WithinExtentQueryParams {
+    pub fn builder() -> WithinExtentQueryParamsBuilder {
+        WithinExtentQueryParamsBuilder::default()
+    }
+
+    /// Check the parameters against the limits of the API
+    pub fn validate(&self) -> Result<(), String> {
+        validate_coordinate(self.xmin, self.ymin)?;
+        validate_coordinate(self.xmax, self.ymax)?;
+        if self.xmin > self.xmax {
+            return Err("the extent crosses the antimeridian; search each side of it".into());
+        }
+        if self.ymin > self.ymax {
+            return Err("ymin is greater than ymax".into());
+        }
+        validate_filters(self.category_ids.as_ref(), self.search_text.as_ref())
+    }
+
+    #[cfg(feature = "places-client")]
     pub(crate) fn prepare(self) -> WithinExtentPreparedParams {
         WithinExtentPreparedParams {
             xmin: self.xmin,
@@ -122,6 +226,64 @@ impl WithinExtentQueryParams {
     }
 }
 
+impl NearPointQueryParamsBuilder {
+    /// Set the `x` and `y` of the point to search near
+    pub fn point(&mut self, x: f64, y: f64) -> &mut Self {
+        self.x = Some(x);
+        self.y = Some(y);
+        self
+    }
+
+    /// Set the category IDs to filter by
+    pub fn category_ids<I: IntoIterator<Item = S>, S: Into<String>>(
+        &mut self,
+        ids: I,
+    ) -> &mut Self {
+        self.category_id = Some(Some(ids.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Build the parameters, checking them against the limits of the API
+    pub fn build(&self) -> Result<NearPointQueryParams, NearPointQueryParamsBuilderError> {
+        let params = self.build_unvalidated()?;
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+impl WithinExtentQueryParamsBuilder {
+    /// Set the extent to search within
+    pub fn extent(&mut self, xmin: f64, ymin: f64, xmax: f64, ymax: f64) -> &mut Self {
+        self.xmin = Some(xmin);
+        self.ymin = Some(ymin);
+        self.xmax = Some(xmax);
+        self.ymax = Some(ymax);
+        self
+    }
+
+    /// Set the extent to search within from a `geo_types::Rect`
+    #[cfg(feature = "geo")]
+    pub fn rect(&mut self, rect: geo_types::Rect) -> &mut Self {
+        self.extent(rect.min().x, rect.min().y, rect.max().x, rect.max().y)
+    }
+
+    /// Set the category IDs to filter by
+    pub fn category_ids<I: IntoIterator<Item = S>, S: Into<String>>(
+        &mut self,
+        ids: I,
+    ) -> &mut Self {
+        self.category_ids = Some(Some(ids.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Build the parameters, checking them against the limits of the API
+    pub fn build(&self) -> Result<WithinExtentQueryParams, WithinExtentQueryParamsBuilderError> {
+        let params = self.build_unvalidated()?;
+        params.validate()?;
+        Ok(params)
+    }
+}
+
 /// Request parameters used to query the [`/places/{placeId}`](https://developers.arcgis.com/rest/places/place-id-get/) endpoint
 #[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
 #[serde(rename_all = "camelCase")]
@@ -1,3 +1,6 @@
+#[cfg(feature = "places-client")]
+use crate::error::snippet;
+use crate::error::{ClassifyError, ErrorKind};
 use crate::places::Pagination;
 use crate::places::{CategoryDetails, PlaceDetails, PlaceResult};
 use serde::{Deserialize, Serialize};
@@ -7,19 +10,32 @@ use serde::{Deserialize, Serialize};
 pub struct PlaceResponse {
     #[serde(rename = "placeDetails")]
     pub place_details: PlaceDetails,
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Represents the response from the /categories endpoint
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CategoriesResponse {
     pub categories: Vec<CategoryDetails>,
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Represents the response from the /places/near-point and /places/within-extent endpoints
+#[cfg_attr(feature = "preserve-unknown", serde_with::skip_serializing_none)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PointResponse {
     pub results: Vec<PlaceResult>,
     pub pagination: Option<Pagination>,
+    /// Properties without a field of their own
+    #[cfg(feature = "preserve-unknown")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -36,6 +52,12 @@ pub struct ErrorDetails {
     pub rest_info_url: String,
 }
 
+impl ClassifyError for ErrorResponse {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::from_code(self.error.code.into())
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 pub enum ExpectedResponse {
@@ -46,6 +68,127 @@ pub enum ExpectedResponse {
 #[cfg(feature = "places-client")]
 #[derive(Debug)]
 pub enum PlacesError {
-    RequestError(reqwest::Error),
     ApiError(ErrorResponse),
+    /// The token provider failed to supply a token
+    CredentialError(crate::auth::CredentialError),
+    /// The request failed before a response was received
+    HttpError(crate::http::HttpError),
+    /// The parameters exceed the limits of the API. No request was sent.
+    InvalidParams(String),
+    /// The response is not the expected JSON
+    DecodeError {
+        source: serde_json::Error,
+        /// The start of the response body
+        body: String,
+    },
+    /// The response has an error status and no error details
+    HttpStatus {
+        status: u16,
+        /// The start of the response body
+        body: String,
+    },
+    /// The request still failed after the retries of the client's [`RetryPolicy`](crate::error::RetryPolicy)
+    RetriesExhausted(Box<PlacesError>),
+    /// Fetching a further page of a search failed
+    PageError {
+        /// The URL of the page
+        url: String,
+        /// The number of the page, starting at 1 for the initial request
+        page: usize,
+        source: Box<PlacesError>,
+    },
+}
+
+#[cfg(feature = "places-client")]
+impl PlacesError {
+    pub(crate) fn decode(source: serde_json::Error, body: &[u8]) -> Self {
+        Self::DecodeError {
+            source,
+            body: snippet(body),
+        }
+    }
+
+    pub(crate) fn status(status: u16, body: &[u8]) -> Self {
+        Self::HttpStatus {
+            status,
+            body: snippet(body),
+        }
+    }
+
+    // the error returned by `retry`, which is retryable only if it ran out of retries
+    pub(crate) fn retried(self, retry: &crate::error::RetryPolicy) -> Self {
+        match self.is_retryable() && retry.max_retries > 0 {
+            true => Self::RetriesExhausted(Box::new(self)),
+            false => self,
+        }
+    }
+}
+
+// the error of a response, or its body decoded as `T`. A successful response can also
+// carry an error.
+#[cfg(feature = "places-client")]
+pub(crate) fn decode_response<T: serde::de::DeserializeOwned>(
+    status: u16,
+    body: &[u8],
+) -> Result<T, PlacesError> {
+    if status >= 400 {
+        return Err(match serde_json::from_slice::<ErrorResponse>(body) {
+            Ok(e) => PlacesError::ApiError(e),
+            Err(_) => PlacesError::status(status, body),
+        });
+    }
+
+    serde_json::from_slice(body).map_err(|e| match serde_json::from_slice(body) {
+        Ok(error) => PlacesError::ApiError(error),
+        Err(_) => PlacesError::decode(e, body),
+    })
+}
+
+#[cfg(feature = "places-client")]
+impl std::fmt::Display for PlacesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ApiError(e) => write!(f, "{} ({})", e.error.message, e.error.code),
+            Self::CredentialError(e) => write!(f, "failed to get token: {e}"),
+            Self::HttpError(e) => write!(f, "{e}"),
+            Self::InvalidParams(e) => write!(f, "invalid parameters: {e}"),
+            Self::DecodeError { source, body } => {
+                write!(f, "failed to decode response: {source}: {body}")
+            }
+            Self::HttpStatus { status, body } => write!(f, "HTTP status {status}: {body}"),
+            Self::RetriesExhausted(e) => write!(f, "retries exhausted: {e}"),
+            Self::PageError { url, page, source } => {
+                write!(f, "failed to fetch page {page} from {url}: {source}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "places-client")]
+impl std::error::Error for PlacesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ApiError(_) | Self::InvalidParams(_) | Self::HttpStatus { .. } => None,
+            Self::CredentialError(e) => Some(e),
+            Self::HttpError(e) => Some(e),
+            Self::DecodeError { source, .. } => Some(source),
+            Self::RetriesExhausted(e) => Some(e.as_ref()),
+            Self::PageError { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+
+#[cfg(feature = "places-client")]
+impl ClassifyError for PlacesError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::ApiError(e) => e.kind(),
+            Self::CredentialError(e) => e.kind(),
+            Self::HttpError(e) => e.kind(),
+            Self::InvalidParams(_) | Self::DecodeError { .. } => ErrorKind::Fatal,
+            Self::HttpStatus { status, .. } => ErrorKind::from_code((*status).into()),
+            Self::RetriesExhausted(e) => e.kind(),
+            Self::PageError { source, .. } => source.kind(),
+        }
+    }
 }
@@ -0,0 +1,372 @@
+//! Optional feature for converting a `FeatureSet` to and from a [`polars`](https://docs.rs/polars) `DataFrame`.
+//!
+//! [`FeatureSet::to_dataframe()`] creates a column for each field and a `geometry`
+//! column of WKB if the `FeatureSet` has geometries. Esri field types are mapped to
+//! polars data types as follows:
+//!
+//! - `esriFieldTypeSmallInteger` -> `Int16`
+//! - `esriFieldTypeInteger` -> `Int32`
+//! - `esriFieldTypeOID`, `esriFieldTypeBigInteger` -> `Int64`
+//! - `esriFieldTypeSingle` -> `Float32`
+//! - `esriFieldTypeDouble` -> `Float64`
+//! - `esriFieldTypeDate` -> `Datetime(ms)`
+//! - `esriFieldTypeString`, `esriFieldTypeGUID`, `esriFieldTypeGlobalID`, `esriFieldTypeXML` -> `String`
+//! - `esriFieldTypeDateOnly`, `esriFieldTypeTimeOnly`, `esriFieldTypeTimestampOffset` -> `String`
+//!
+//! [`FeatureSet::from_dataframe()`] goes the other way, reading geometries from a
+//! WKB column. Integers wider than 32 bits become `esriFieldTypeDouble` fields and
+//! booleans become `esriFieldTypeSmallInteger` fields.
+//!
+//! ```
+//! use serde_esri::{features::FeatureSet, spatial_reference::SpatialReference};
+//!
+//! let json = r#"{
+//!     "geometryType": "esriGeometryPoint",
+//!     "fields": [{"name": "name", "type": "esriFieldTypeString"}],
+//!     "features": [{"attributes": {"name": "a"}, "geometry": {"x": 1, "y": 2}}]
+//! }"#;
+//! let fset: FeatureSet<2> = serde_json::from_str(json).unwrap();
+//!
+//! let df = fset.to_dataframe().unwrap();
+//! assert_eq!(df.shape(), (1, 2));
+//!
+//! let fset = FeatureSet::<2>::from_dataframe(&df, Some("geometry"), Some(SpatialReference::from_wkid(4326))).unwrap();
+//! let point = fset.features[0].geometry.clone().unwrap().as_point().unwrap();
+//! assert_eq!((point.x, point.y), (1.0, 2.0));
+//! ```
+use crate::{
+    features::{infer_fields, Feature, FeatureSet, Field},
+    field_type::FieldType,
+    geometry::dimension_flags,
+    spatial_reference::SpatialReference,
+    wkb::{from_wkb, to_wkb, WkbError},
+};
+use polars::prelude::{
+    AnyValue, Column, DataFrame, DataType, IntoColumn, NamedFrom, PolarsError, Series, TimeUnit,
+};
+use serde_json::{Map, Value};
+
+/// The name of the WKB column created by [`FeatureSet::to_dataframe()`]
+pub const GEOMETRY_COLUMN: &str = "geometry";
+
+/// Errors that can occur when converting between a `FeatureSet` and a `DataFrame`
+#[derive(Debug)]
+pub enum PolarsConversionError {
+    /// A column is not in the `DataFrame`
+    MissingColumn(String),
+    /// The column's data type has no corresponding Esri field type
+    UnsupportedType {
+        column: String,
+        data_type: DataType,
+    },
+    /// The field's type has no corresponding polars data type
+    UnsupportedField {
+        field: String,
+        field_type: FieldType,
+    },
+    /// The geometry column is not binary
+    UnsupportedGeometry(String),
+    /// The geometry of a row could not be converted
+    InvalidGeometry {
+        row: usize,
+        error: WkbError,
+    },
+    PolarsError(PolarsError),
+}
+
+impl std::fmt::Display for PolarsConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingColumn(column) => write!(f, "column `{column}` does not exist"),
+            Self::UnsupportedType { column, data_type } => {
+                write!(f, "column `{column}` has unsupported type {data_type}")
+            }
+            Self::UnsupportedField { field, field_type } => {
+                write!(f, "field `{field}` has unsupported type {field_type}")
+            }
+            Self::UnsupportedGeometry(column) => {
+                write!(f, "column `{column}` is not a WKB geometry column")
+            }
+            Self::InvalidGeometry { row, error } => {
+                write!(f, "invalid geometry in row {row}: {error}")
+            }
+            Self::PolarsError(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for PolarsConversionError {}
+
+impl From<PolarsError> for PolarsConversionError {
+    fn from(value: PolarsError) -> Self {
+        Self::PolarsError(value)
+    }
+}
+
+impl<const N: usize> FeatureSet<N> {
+    /// Create a `DataFrame` with a column for each field and a [`GEOMETRY_COLUMN`] of WKB
+    ///
+    /// If the `FeatureSet` has no `fields`, they are inferred from the attributes.
+    pub fn to_dataframe(&self) -> Result<DataFrame, PolarsConversionError> {
+        let fields = match &self.fields {
+            Some(fields) => fields.clone(),
+            None => infer_fields(&self.features),
+        };
+
+        let mut columns = fields
+            .iter()
+            .map(|field| field_column(field, &self.features))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if self.geometryType.is_some() || self.features.iter().any(|f| f.geometry.is_some()) {
+            let wkb = self
+                .features
+                .iter()
+                .map(|f| f.geometry.as_ref().map(to_wkb))
+                .collect::<Vec<_>>();
+            let wkb = wkb.iter().map(Option::as_deref).collect::<Vec<_>>();
+            columns.push(Series::new(GEOMETRY_COLUMN.into(), wkb).into_column());
+        }
+
+        Ok(DataFrame::new_with_height(self.features.len(), columns)?)
+    }
+
+    /// Create a `FeatureSet` from a `DataFrame`
+    ///
+    /// `geometry_column` is the name of a binary column of WKB. Every other column is
+    /// used as an attribute with a field of the same name and a type based on its data type.
+    pub fn from_dataframe(
+        df: &DataFrame,
+        geometry_column: Option<&str>,
+        spatial_reference: Option<SpatialReference>,
+    ) -> Result<Self, PolarsConversionError> {
+        let mut fields = Vec::new();
+        let mut columns = Vec::new();
+        for column in df.get_columns() {
+            if Some(column.name().as_str()) == geometry_column {
+                continue;
+            }
+            let field_type = infer_field_type(column.dtype()).ok_or_else(|| {
+                PolarsConversionError::UnsupportedType {
+                    column: column.name().to_string(),
+                    data_type: column.dtype().clone(),
+                }
+            })?;
+            fields.push(Field {
+                name: column.name().to_string(),
+                field_type,
+                ..Default::default()
+            });
+            columns.push(column_values(column)?);
+        }
+
+        let mut geometries = match geometry_column {
+            Some(name) => {
+                let column = df
+                    .column(name)
+                    .map_err(|_| PolarsConversionError::MissingColumn(name.to_string()))?;
+                let wkb = column
+                    .binary()
+                    .map_err(|_| PolarsConversionError::UnsupportedGeometry(name.to_string()))?;
+                wkb.iter()
+                    .enumerate()
+                    .map(|(row, x)| {
+                        x.map(from_wkb::<N>)
+                            .transpose()
+                            .map_err(|error| PolarsConversionError::InvalidGeometry { row, error })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            None => vec![None; df.height()],
+        }
+        .into_iter();
+
+        let features = (0..df.height())
+            .map(|row| {
+                let attributes = fields
+                    .iter()
+                    .zip(&columns)
+                    .map(|(field, values)| (field.name.clone(), values[row].clone()))
+                    .collect::<Map<_, _>>();
+
+                Feature {
+                    geometry: geometries.next().flatten(),
+                    attributes: Some(attributes),
+                    #[cfg(feature = "preserve-unknown")]
+                    extra: Default::default(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let geometry_type = features
+            .iter()
+            .find_map(|f| f.geometry.as_ref())
+            .map(|g| g.geometry_type().to_string());
+
+        let (has_z, has_m) = dimension_flags::<N>();
+        Ok(FeatureSet {
+            geometryType: geometry_type,
+            spatialReference: spatial_reference,
+            hasZ: Some(has_z),
+            hasM: Some(has_m),
+            fields: Some(fields),
+            features,
+            ..Default::default()
+        })
+    }
+}
+
+// the column of a field's attribute values
+fn field_column<const N: usize>(
+    field: &Field,
+    features: &[Feature<N>],
+) -> Result<Column, PolarsConversionError> {
+    let name = field.name.as_str().into();
+    let values = features
+        .iter()
+        .map(|f| f.attributes.as_ref().and_then(|a| a.get(&field.name)));
+    let ints = || values.clone().map(|v| v.and_then(Value::as_i64));
+    let floats = || values.clone().map(|v| v.and_then(Value::as_f64));
+
+    let series = match field.field_type {
+        FieldType::EsriFieldTypeSmallInteger => Series::new(
+            name,
+            ints().map(|v| v.map(|v| v as i16)).collect::<Vec<_>>(),
+        ),
+        FieldType::EsriFieldTypeInteger => Series::new(
+            name,
+            ints().map(|v| v.map(|v| v as i32)).collect::<Vec<_>>(),
+        ),
+        FieldType::EsriFieldTypeOid | FieldType::EsriFieldTypeBigInteger => {
+            Series::new(name, ints().collect::<Vec<_>>())
+        }
+        FieldType::EsriFieldTypeSingle => Series::new(
+            name,
+            floats().map(|v| v.map(|v| v as f32)).collect::<Vec<_>>(),
+        ),
+        FieldType::EsriFieldTypeDouble => Series::new(name, floats().collect::<Vec<_>>()),
+        FieldType::EsriFieldTypeDate => Series::new(name, ints().collect::<Vec<_>>())
+            .cast(&DataType::Datetime(TimeUnit::Milliseconds, None))?,
+        FieldType::EsriFieldTypeString
+        | FieldType::EsriFieldTypeGuid
+        | FieldType::EsriFieldTypeGlobalId
+        | FieldType::EsriFieldTypeXml
+        | FieldType::EsriFieldTypeDateOnly
+        | FieldType::EsriFieldTypeTimeOnly
+        | FieldType::EsriFieldTypeTimestampOffset => Series::new(
+            name,
+            values
+                .map(|v| v.and_then(Value::as_str))
+                .collect::<Vec<_>>(),
+        ),
+        FieldType::EsriFieldTypeBlob
+        | FieldType::EsriFieldTypeRaster
+        | FieldType::EsriFieldTypeGeometry => {
+            return Err(PolarsConversionError::UnsupportedField {
+                field: field.name.clone(),
+                field_type: field.field_type.clone(),
+            })
+        }
+    };
+    Ok(series.into_column())
+}
+
+fn infer_field_type(data_type: &DataType) -> Option<FieldType> {
+    let field_type = match data_type {
+        DataType::Boolean | DataType::Int8 | DataType::Int16 | DataType::UInt8 => {
+            FieldType::EsriFieldTypeSmallInteger
+        }
+        DataType::Int32 | DataType::UInt16 => FieldType::EsriFieldTypeInteger,
+        DataType::Int64 | DataType::UInt32 | DataType::UInt64 => FieldType::EsriFieldTypeDouble,
+        DataType::Float32 => FieldType::EsriFieldTypeSingle,
+        DataType::Float64 => FieldType::EsriFieldTypeDouble,
+        DataType::Null | DataType::String => FieldType::EsriFieldTypeString,
+        DataType::Date | DataType::Datetime(_, _) => FieldType::EsriFieldTypeDate,
+        _ => return None,
+    };
+    Some(field_type)
+}
+
+// the values of a column as Esri JSON attribute values.
+// Dates are milliseconds since the epoch.
+fn column_values(column: &Column) -> Result<Vec<Value>, PolarsError> {
+    let column = match column.dtype() {
+        DataType::Date | DataType::Datetime(_, _) => column
+            .cast(&DataType::Datetime(TimeUnit::Milliseconds, None))?
+            .cast(&DataType::Int64)?,
+        _ => column.clone(),
+    };
+
+    (0..column.len())
+        .map(|i| {
+            let value = match column.get(i)? {
+                AnyValue::Boolean(v) => i64::from(v).into(),
+                AnyValue::Int8(v) => v.into(),
+                AnyValue::Int16(v) => v.into(),
+                AnyValue::Int32(v) => v.into(),
+                AnyValue::Int64(v) => v.into(),
+                AnyValue::UInt8(v) => v.into(),
+                AnyValue::UInt16(v) => v.into(),
+                AnyValue::UInt32(v) => v.into(),
+                AnyValue::UInt64(v) => v.into(),
+                AnyValue::Float32(v) => f64::from(v).into(),
+                AnyValue::Float64(v) => v.into(),
+                AnyValue::String(v) => v.into(),
+                AnyValue::StringOwned(v) => v.as_str().into(),
+                _ => Value::Null,
+            };
+            Ok(value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_dataframe() {
+        let df = DataFrame::new(vec![
+            Series::new("count".into(), [Some(1_i64), None]).into_column(),
+            Series::new("flag".into(), [true, false]).into_column(),
+            Series::new("date".into(), [0_i64, 86_400_000])
+                .cast(&DataType::Datetime(TimeUnit::Microseconds, None))
+                .unwrap()
+                .into_column(),
+        ])
+        .unwrap();
+
+        let fset = FeatureSet::<2>::from_dataframe(&df, None, None).unwrap();
+        let types: Vec<_> = fset
+            .fields
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|f| f.field_type.clone())
+            .collect();
+        assert!(matches!(
+            types[..],
+            [
+                FieldType::EsriFieldTypeDouble,
+                FieldType::EsriFieldTypeSmallInteger,
+                FieldType::EsriFieldTypeDate
+            ]
+        ));
+
+        let attributes = fset.features[1].attributes.as_ref().unwrap();
+        assert_eq!(attributes["count"], Value::Null);
+        assert_eq!(attributes["flag"], 0);
+        // 86,400,000 microseconds
+        assert_eq!(attributes["date"], 86_400);
+        assert!(fset.geometryType.is_none());
+
+        assert!(matches!(
+            FeatureSet::<2>::from_dataframe(&df, Some("shape"), None),
+            Err(PolarsConversionError::MissingColumn(_))
+        ));
+        assert!(matches!(
+            FeatureSet::<2>::from_dataframe(&df, Some("count"), None),
+            Err(PolarsConversionError::UnsupportedGeometry(_))
+        ));
+    }
+}
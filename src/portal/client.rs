@@ -0,0 +1,399 @@
+use crate::{
+    auth::{CredentialError, CredentialProvider, TokenProvider, TokenSource},
+    error::{ClassifyError, ErrorKind},
+    feature_service::{ErrorResponse, ExpectedResponse},
+    http::{HttpClient, HttpError, HttpRequest},
+    portal::{
+        CreateServiceParams, CreateServiceResponse, ExportFormat, ExportResponse, Folder,
+        ItemStatus, PortalItem, SearchParams, SearchResponse, UserContent,
+    },
+    trace,
+};
+use serde::de::DeserializeOwned;
+use std::time::{Duration, Instant};
+
+// the number of items in each page of a listing
+const PAGE_SIZE: u32 = 100;
+// the time between the first checks of an export's status, doubled after each check
+const EXPORT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// the longest time between checks of an export's status
+const MAX_EXPORT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// The default time an export is waited for
+pub const DEFAULT_EXPORT_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// The URL of ArcGIS Online
+pub const ARCGIS_ONLINE_URL: &str = "https://www.arcgis.com";
+
+#[derive(Debug)]
+pub enum PortalError {
+    /// The request failed or the response is not the expected JSON
+    HttpError(HttpError),
+    ApiError(ErrorResponse),
+    /// The token provider failed to supply a token
+    CredentialError(CredentialError),
+    /// An export failed or did not return an item. Contains the job's status.
+    JobFailed(String),
+}
+
+impl std::fmt::Display for PortalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HttpError(e) => write!(f, "{e}"),
+            Self::ApiError(e) => write!(f, "{} ({})", e.error.message, e.error.code),
+            Self::CredentialError(e) => write!(f, "failed to get token: {e}"),
+            Self::JobFailed(status) => write!(f, "job failed with status `{status}`"),
+        }
+    }
+}
+
+impl std::error::Error for PortalError {}
+
+impl ClassifyError for PortalError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::HttpError(e) => e.kind(),
+            Self::ApiError(e) => e.kind(),
+            Self::CredentialError(e) => e.kind(),
+            Self::JobFailed(_) => ErrorKind::Fatal,
+        }
+    }
+}
+
+/// A client for the content of a user in an ArcGIS Portal
+///
+/// ```
+/// use serde_esri::portal::{PortalClient, ARCGIS_ONLINE_URL};
+/// let client = PortalClient::new(ARCGIS_ONLINE_URL, "your username", "your token");
+/// ```
+///
+/// Requests are sent with a `reqwest::blocking::Client` unless another [`HttpClient`]
+/// is set with [`PortalClient::with_http_client()`].
+#[derive(Clone)]
+pub struct PortalClient<H = reqwest::blocking::Client> {
+    /// The URL of the portal, e.g. `https://www.arcgis.com`
+    pub portal_url: String,
+    /// The user whose content is managed
+    pub username: String,
+    pub(crate) client: H,
+    /// The token to use for authorization. Redacted from `Debug` and zeroized on drop.
+    pub(crate) token: TokenSource,
+    /// The time an export is waited for
+    pub(crate) export_timeout: Duration,
+}
+
+impl<H> std::fmt::Debug for PortalClient<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PortalClient")
+            .field("portal_url", &self.portal_url)
+            .field("username", &self.username)
+            .field("token", &"[REDACTED]")
+            .field("export_timeout", &self.export_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PortalClient {
+    /// Create a new client for the portal at `portal_url`
+    pub fn new(portal_url: &str, username: &str, token: &str) -> Self {
+        Self {
+            portal_url: portal_url.trim_end_matches('/').to_string(),
+            username: username.to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: token.into(),
+            export_timeout: DEFAULT_EXPORT_TIMEOUT,
+        }
+    }
+
+    /// Create a new client for the portal at `portal_url` using a token from `credentials`
+    pub fn from_credentials<C: CredentialProvider>(
+        portal_url: &str,
+        username: &str,
+        credentials: &C,
+    ) -> Result<Self, CredentialError> {
+        Ok(Self {
+            token: credentials.token()?.into(),
+            ..Self::new(portal_url, username, "")
+        })
+    }
+
+    /// Create a new client for the portal at `portal_url` which gets a token from `provider` before every request
+    pub fn from_token_provider<P: TokenProvider>(
+        portal_url: &str,
+        username: &str,
+        provider: P,
+    ) -> Self {
+        Self {
+            token: TokenSource::new(provider),
+            ..Self::new(portal_url, username, "")
+        }
+    }
+}
+
+impl<H: HttpClient> PortalClient<H> {
+    /// Send requests with `client` instead
+    pub fn with_http_client<C: HttpClient>(self, client: C) -> PortalClient<C> {
+        PortalClient {
+            portal_url: self.portal_url,
+            username: self.username,
+            client,
+            token: self.token,
+            export_timeout: self.export_timeout,
+        }
+    }
+
+    /// Set the time an export is waited for. An export that hasn't completed by then fails
+    /// with [`PortalError::JobFailed`] and its last status. Defaults to [`DEFAULT_EXPORT_TIMEOUT`].
+    pub fn with_export_timeout(mut self, timeout: Duration) -> Self {
+        self.export_timeout = timeout;
+        self
+    }
+
+    /// The URL of the user's content
+    pub fn user_content_url(&self) -> String {
+        format!("{}/content/users/{}", self.rest_url(), self.username)
+    }
+
+    /// The URL of the sharing REST API, e.g. `https://www.arcgis.com/sharing/rest`
+    pub fn rest_url(&self) -> String {
+        format!("{}/sharing/rest", self.portal_url)
+    }
+
+    /// Search for items with the [`/search`](https://developers.arcgis.com/rest/users-groups-and-items/search/) endpoint
+    ///
+    /// Returns a single page of results. Use [`PortalClient::search_all()`] to fetch every page.
+    pub fn search(&self, params: &SearchParams) -> Result<SearchResponse, PortalError> {
+        let request =
+            HttpRequest::get(&format!("{}/search", self.rest_url())).params(&params.query_pairs());
+
+        self.send(request)
+    }
+
+    /// Search for items, fetching every page of results
+    pub fn search_all(&self, params: &SearchParams) -> Result<Vec<PortalItem>, PortalError> {
+        let mut params = params.clone();
+        params.num = params.num.or(Some(PAGE_SIZE));
+
+        let mut items = Vec::new();
+        loop {
+            let page = self.search(&params)?;
+            items.extend(page.results);
+            if page.nextStart < 1 {
+                return Ok(items);
+            }
+            params.start = Some(page.nextStart);
+        }
+    }
+
+    /// The items shared with a group
+    pub fn group_items(&self, group_id: &str) -> Result<Vec<PortalItem>, PortalError> {
+        let params = SearchParams {
+            q: format!("group:{group_id}"),
+            ..Default::default()
+        };
+        self.search_all(&params)
+    }
+
+    /// The metadata of an item
+    pub fn item(&self, item_id: &str) -> Result<PortalItem, PortalError> {
+        let request = HttpRequest::get(&format!("{}/content/items/{item_id}", self.rest_url()))
+            .param("f", "json");
+
+        self.send(request)
+    }
+
+    /// The JSON data of an item, such as the definition of a Web Map
+    ///
+    /// Use [`PortalClient::download_item()`] for items whose data is a file.
+    pub fn item_data<T: DeserializeOwned>(&self, item_id: &str) -> Result<T, PortalError> {
+        let request =
+            HttpRequest::get(&format!("{}/content/items/{item_id}/data", self.rest_url()))
+                .param("f", "json");
+
+        self.send(request)
+    }
+
+    /// The items of a user in a folder, or in their root folder if `folder_id` is `None`
+    pub fn user_items(
+        &self,
+        username: &str,
+        folder_id: Option<&str>,
+    ) -> Result<Vec<PortalItem>, PortalError> {
+        let mut url = format!("{}/content/users/{username}", self.rest_url());
+        if let Some(folder_id) = folder_id {
+            url = format!("{url}/{folder_id}");
+        }
+
+        let mut items = Vec::new();
+        let mut start = 1;
+        loop {
+            let query = [
+                ("start", start.to_string()),
+                ("num", PAGE_SIZE.to_string()),
+                ("f", "json".into()),
+            ];
+            let page = self.send::<UserContent>(HttpRequest::get(&url).params(&query))?;
+            items.extend(page.items);
+            if page.nextStart < 1 {
+                return Ok(items);
+            }
+            start = page.nextStart;
+        }
+    }
+
+    /// The folders of a user
+    pub fn user_folders(&self, username: &str) -> Result<Vec<Folder>, PortalError> {
+        let request = HttpRequest::get(&format!("{}/content/users/{username}", self.rest_url()))
+            .params(&[("num", "1"), ("f", "json")]);
+
+        Ok(self.send::<UserContent>(request)?.folders)
+    }
+
+    /// Create an empty hosted feature service with the [`/createService`](https://developers.arcgis.com/rest/users-groups-and-items/create-service/) endpoint
+    pub fn create_service(
+        &self,
+        params: &CreateServiceParams,
+    ) -> Result<CreateServiceResponse, PortalError> {
+        let form = [
+            (
+                "createParameters",
+                serde_json::to_string(params).unwrap_or_default(),
+            ),
+            ("outputType", "featureService".into()),
+            ("f", "json".into()),
+        ];
+
+        let request =
+            HttpRequest::post(&format!("{}/createService", self.user_content_url())).form(&form);
+
+        self.send(request)
+    }
+
+    /// Export a hosted feature layer item to a new item with the [`/export`](https://developers.arcgis.com/rest/users-groups-and-items/export-item/) endpoint
+    ///
+    /// The export job is polled until it completes and the exported file is written to
+    /// `writer` once it is downloaded. The exported item is left in the user's content.
+    ///
+    /// Returns the ID of the exported item and the number of bytes written.
+    pub fn export_item<W: std::io::Write + ?Sized>(
+        &self,
+        item_id: &str,
+        format: ExportFormat,
+        title: &str,
+        writer: &mut W,
+    ) -> Result<(String, u64), PortalError> {
+        let form = [
+            ("itemId", item_id),
+            ("exportFormat", format.as_str()),
+            ("title", title),
+            ("f", "json"),
+        ];
+        let request = HttpRequest::post(&format!("{}/export", self.user_content_url())).form(&form);
+        let export = self.send::<ExportResponse>(request)?;
+
+        let (Some(export_id), Some(job_id)) = (export.exportItemId, export.jobId) else {
+            return Err(PortalError::JobFailed("no export item".into()));
+        };
+        self.wait_for_export(&export_id, &job_id)?;
+
+        let written = self.download_item(&export_id, writer)?;
+        Ok((export_id, written))
+    }
+
+    /// Download the data of an item and write it to `writer`
+    ///
+    /// Returns the number of bytes written.
+    pub fn download_item<W: std::io::Write + ?Sized>(
+        &self,
+        item_id: &str,
+        writer: &mut W,
+    ) -> Result<u64, PortalError> {
+        let url = format!("{}/content/items/{item_id}/data", self.rest_url());
+        let bearer = self.token.bearer().map_err(PortalError::CredentialError)?;
+        let request = HttpRequest::get(&url).header("X-Esri-Authorization", &bearer);
+
+        let resp = trace::send(&self.client, request)
+            .and_then(|r| r.error_for_status())
+            .map_err(PortalError::HttpError)?;
+        writer
+            .write_all(&resp.body)
+            .map_err(|e| PortalError::HttpError(HttpError::new(ErrorKind::Fatal, e)))?;
+        Ok(resp.body.len() as u64)
+    }
+
+    // polls the status of an export job with an increasing interval until it completes or
+    // times out
+    fn wait_for_export(&self, item_id: &str, job_id: &str) -> Result<(), PortalError> {
+        let url = format!("{}/items/{item_id}/status", self.user_content_url());
+        let start = Instant::now();
+        let mut interval = EXPORT_POLL_INTERVAL;
+        loop {
+            let query = [("jobId", job_id), ("jobType", "export"), ("f", "json")];
+            let status = self.send::<ItemStatus>(HttpRequest::get(&url).params(&query))?;
+
+            if status.is_completed() {
+                return Ok(());
+            }
+
+            let remaining = self.export_timeout.saturating_sub(start.elapsed());
+            if status.is_failed() || remaining.is_zero() {
+                return Err(PortalError::JobFailed(
+                    status.statusMessage.unwrap_or(status.status),
+                ));
+            }
+
+            std::thread::sleep(interval.min(remaining));
+            interval = (interval * 2).min(MAX_EXPORT_POLL_INTERVAL);
+        }
+    }
+
+    // authorizes the request, sends it, and handles error responses
+    fn send<T: DeserializeOwned>(&self, request: HttpRequest) -> Result<T, PortalError> {
+        let bearer = self.token.bearer().map_err(PortalError::CredentialError)?;
+        let resp = trace::send(
+            &self.client,
+            request.header("X-Esri-Authorization", &bearer),
+        )
+        .and_then(|r| r.json::<ExpectedResponse<T>>())
+        .map_err(PortalError::HttpError)?;
+
+        match resp {
+            ExpectedResponse::Ok(x) => Ok(x),
+            ExpectedResponse::Error(e) => Err(PortalError::ApiError(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpResponse;
+    use std::sync::Mutex;
+
+    // responds with each body in turn
+    struct Responses(Mutex<Vec<&'static str>>);
+
+    impl HttpClient for Responses {
+        fn execute(&self, _: HttpRequest) -> Result<HttpResponse, HttpError> {
+            let body = self.0.lock().unwrap().remove(0);
+            Ok(HttpResponse {
+                status: 200,
+                headers: vec![],
+                body: body.as_bytes().to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_export_timeout() {
+        let export = r#"{"exportItemId": "export", "jobId": "job"}"#;
+        let processing = r#"{"status": "processing"}"#;
+        let client = PortalClient::new(ARCGIS_ONLINE_URL, "user", "token")
+            .with_http_client(Responses(Mutex::new(vec![export, processing])))
+            .with_export_timeout(Duration::ZERO);
+
+        let e = client
+            .export_item("item", ExportFormat::Csv, "title", &mut vec![])
+            .unwrap_err();
+        assert!(matches!(e, PortalError::JobFailed(status) if status == "processing"));
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+/// The format of an item exported with the [`/export`](https://developers.arcgis.com/rest/users-groups-and-items/export-item/) endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    GeoJson,
+    Csv,
+    Shapefile,
+    FileGeodatabase,
+    GeoPackage,
+    Sqlite,
+    FeatureCollection,
+}
+
+impl ExportFormat {
+    /// The value used by the REST API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GeoJson => "GeoJson",
+            Self::Csv => "CSV",
+            Self::Shapefile => "Shapefile",
+            Self::FileGeodatabase => "File Geodatabase",
+            Self::GeoPackage => "GeoPackage",
+            Self::Sqlite => "SQLite Geodatabase",
+            Self::FeatureCollection => "Feature Collection",
+        }
+    }
+}
+
+/// The response from the [`/export`](https://developers.arcgis.com/rest/users-groups-and-items/export-item/) endpoint
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportResponse {
+    /// The ID of the item the data is exported to
+    pub exportItemId: Option<String>,
+    pub jobId: Option<String>,
+    pub serviceItemId: Option<String>,
+    #[serde(rename = "type")]
+    pub item_type: Option<String>,
+}
+
+/// The status of an item's job from the [`/status`](https://developers.arcgis.com/rest/users-groups-and-items/item-status/) endpoint
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ItemStatus {
+    /// `partial`, `processing`, `failed`, or `completed`
+    pub status: String,
+    pub statusMessage: Option<String>,
+    pub itemId: Option<String>,
+}
+
+impl ItemStatus {
+    pub fn is_completed(&self) -> bool {
+        self.status.eq_ignore_ascii_case("completed")
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.status.eq_ignore_ascii_case("failed")
+    }
+}
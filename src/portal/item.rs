@@ -0,0 +1,165 @@
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+/// An item in a portal, such as a Feature Service or a Web Map
+///
+/// Dates are milliseconds since the Unix epoch.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortalItem {
+    pub id: String,
+    pub owner: Option<String>,
+    pub org_id: Option<String>,
+    pub title: Option<String>,
+    /// The type of the item, e.g. `Feature Service`
+    #[serde(rename = "type")]
+    pub item_type: Option<String>,
+    #[serde(default)]
+    pub type_keywords: Vec<String>,
+    pub name: Option<String>,
+    pub snippet: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The URL of the service of the item, if any
+    pub url: Option<String>,
+    pub created: Option<i64>,
+    pub modified: Option<i64>,
+    /// `private`, `shared`, `org`, or `public`
+    pub access: Option<String>,
+    /// The extent in WGS84 as `[[xmin, ymin], [xmax, ymax]]`
+    #[serde(default)]
+    pub extent: Vec<[f64; 2]>,
+    /// The size of the item's data in bytes
+    pub size: Option<i64>,
+    pub num_views: Option<i64>,
+    pub owner_folder: Option<String>,
+    pub thumbnail: Option<String>,
+    pub access_information: Option<String>,
+    pub license_info: Option<String>,
+}
+
+impl PortalItem {
+    /// Whether the item is a Feature Service whose `url` can be used by a `FeatureLayerClient`
+    pub fn is_feature_service(&self) -> bool {
+        self.item_type.as_deref() == Some("Feature Service")
+    }
+}
+
+/// A folder of a user's content
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Folder {
+    pub id: String,
+    pub title: String,
+    pub username: Option<String>,
+    pub created: Option<i64>,
+}
+
+/// Request parameters used to search for items with the [`/search`](https://developers.arcgis.com/rest/users-groups-and-items/search/) endpoint
+///
+/// ```
+/// use serde_esri::portal::SearchParamsBuilder;
+///
+/// let params = SearchParamsBuilder::default()
+///     .q(r#"type:"Feature Service" AND owner:esri"#)
+///     .sort_field("modified")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default, Builder)]
+pub struct SearchParams {
+    /// The [query](https://developers.arcgis.com/rest/users-groups-and-items/search-reference/), e.g. `type:"Feature Service"`
+    #[builder(setter(into))]
+    pub q: String,
+    /// A WGS84 extent as `xmin,ymin,xmax,ymax`
+    #[builder(setter(into, strip_option), default)]
+    pub bbox: Option<String>,
+    /// Comma separated fields to sort by, e.g. `title` or `modified`
+    #[builder(setter(into, strip_option), default)]
+    pub sort_field: Option<String>,
+    /// `asc` or `desc`
+    #[builder(setter(into, strip_option), default)]
+    pub sort_order: Option<String>,
+    /// The number of results per page, at most 100. Defaults to 10.
+    #[builder(setter(into, strip_option), default)]
+    pub num: Option<u32>,
+    /// The 1-based index of the first result. Defaults to 1.
+    #[builder(setter(into, strip_option), default)]
+    pub start: Option<i64>,
+}
+
+impl SearchParams {
+    /// The parameters as key-value pairs to be used as the query of a request
+    pub fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![("q", self.q.clone())];
+
+        let optional = [
+            ("bbox", self.bbox.clone()),
+            ("sortField", self.sort_field.clone()),
+            ("sortOrder", self.sort_order.clone()),
+            ("num", self.num.map(|x| x.to_string())),
+            ("start", self.start.map(|x| x.to_string())),
+        ];
+
+        pairs.extend(optional.into_iter().filter_map(|(k, v)| Some((k, v?))));
+        pairs.push(("f", "json".into()));
+        pairs
+    }
+}
+
+/// A page of the response from the [`/search`](https://developers.arcgis.com/rest/users-groups-and-items/search/) endpoint
+///
+/// `nextStart` is `-1` on the last page.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub total: i64,
+    pub start: i64,
+    pub num: i64,
+    pub nextStart: i64,
+    #[serde(default)]
+    pub results: Vec<PortalItem>,
+}
+
+/// A page of the response from the [`/content/users/{username}`](https://developers.arcgis.com/rest/users-groups-and-items/user-content/) endpoint
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserContent {
+    pub username: Option<String>,
+    pub currentFolder: Option<Folder>,
+    #[serde(default)]
+    pub total: i64,
+    #[serde(default)]
+    pub nextStart: i64,
+    #[serde(default)]
+    pub items: Vec<PortalItem>,
+    /// The folders of the user, only listed at the root folder
+    #[serde(default)]
+    pub folders: Vec<Folder>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_response() {
+        let resp: SearchResponse = serde_json::from_str(
+            r#"{"total": 11, "start": 1, "num": 10, "nextStart": 11, "results": [{
+                "id": "abc123", "owner": "esri", "title": "Counties", "type": "Feature Service",
+                "typeKeywords": ["Data", "Service"], "tags": [], "created": 1300000000000,
+                "url": "https://services.arcgis.com/x/arcgis/rest/services/Counties/FeatureServer",
+                "extent": [[-125.0, 24.0], [-66.0, 50.0]], "numViews": 4
+            }]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(resp.nextStart, 11);
+        let item = &resp.results[0];
+        assert!(item.is_feature_service());
+        assert_eq!(item.extent[1], [-66.0, 50.0]);
+        assert_eq!(item.num_views, Some(4));
+    }
+}
@@ -0,0 +1,24 @@
+//! Types for working with an [ArcGIS Portal](https://developers.arcgis.com/rest/users-groups-and-items/) such as ArcGIS Online.
+//!
+//! - [`CreateServiceParamsBuilder`] creates a [`CreateServiceParams`] struct used to create an empty hosted feature service with the [`/createService`](https://developers.arcgis.com/rest/users-groups-and-items/create-service/) endpoint.
+//! - [`SearchParamsBuilder`] creates the parameters used to [search](https://developers.arcgis.com/rest/users-groups-and-items/search/) for [`PortalItem`]s.
+//! - [`ExportFormat`] is the format of a hosted layer exported with the [`/export`](https://developers.arcgis.com/rest/users-groups-and-items/export-item/) endpoint.
+//!
+//! Activate the `"portal-client"` feature to enable the `PortalClient` struct, which searches for items and manages a user's content, and [`publish_feature_layer()`](crate::portal::publish_feature_layer)
+//! which creates a hosted feature layer from a `FeatureSet` in one call.
+mod export;
+mod item;
+mod service;
+pub use export::*;
+pub use item::*;
+pub use service::*;
+
+#[cfg(feature = "portal-client")]
+mod client;
+#[cfg(feature = "portal-client")]
+mod publish;
+
+#[cfg(feature = "portal-client")]
+pub use client::*;
+#[cfg(feature = "portal-client")]
+pub use publish::*;
@@ -0,0 +1,236 @@
+use crate::{
+    error::{ClassifyError, ErrorKind},
+    feature_service::{BulkUploadError, BulkUploader, FeatureLayerClient, FeatureServiceError},
+    features::{infer_fields, FeatureSet},
+    field_type::FieldType,
+    geometry::dimension_flags,
+    http::HttpClient,
+    portal::{CreateServiceParams, PortalClient, PortalError},
+    spatial_reference::SpatialReference,
+};
+use serde_json::{json, Value};
+
+const EDIT_CAPABILITIES: &str = "Query,Create,Update,Delete,Editing";
+const DEFAULT_STRING_LENGTH: u32 = 256;
+
+#[derive(Debug)]
+pub enum PublishError {
+    /// The service could not be created
+    Portal(PortalError),
+    /// The layer could not be added to the service
+    FeatureService(FeatureServiceError),
+    /// The features could not be uploaded to the new layer
+    Upload(Box<BulkUploadError>),
+    /// The portal did not return the URL of the new service
+    MissingServiceUrl,
+}
+
+impl std::fmt::Display for PublishError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Portal(e) => write!(f, "failed to create service: {e}"),
+            Self::FeatureService(e) => write!(f, "failed to add layer: {e}"),
+            Self::Upload(e) => write!(f, "failed to upload features: {e}"),
+            Self::MissingServiceUrl => write!(f, "the portal did not return a service URL"),
+        }
+    }
+}
+
+impl std::error::Error for PublishError {}
+
+impl ClassifyError for PublishError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Portal(e) => e.kind(),
+            Self::FeatureService(e) => e.kind(),
+            Self::Upload(e) => e.kind(),
+            Self::MissingServiceUrl => ErrorKind::Fatal,
+        }
+    }
+}
+
+/// Publish a `FeatureSet` as a new hosted feature layer
+///
+/// Creates an editable feature service named after `title`, adds a single
+/// layer whose schema is taken from the `FeatureSet`'s `fields` (or inferred
+/// from its attributes when absent) and uploads every feature in batches.
+///
+/// Returns the URL of the new layer, e.g. `.../FeatureServer/0`.
+///
+/// ```no_run
+/// use serde_esri::{features::FeatureSet, portal::{publish_feature_layer, PortalClient, ARCGIS_ONLINE_URL}};
+///
+/// let portal = PortalClient::new(ARCGIS_ONLINE_URL, "your username", "your token");
+/// let fset: FeatureSet<2> = serde_json::from_str(r#"{"features": []}"#).unwrap();
+/// let layer_url = publish_feature_layer(&portal, "My Layer", &fset).unwrap();
+/// ```
+pub fn publish_feature_layer<const N: usize>(
+    portal: &PortalClient<impl HttpClient + Clone>,
+    title: &str,
+    fset: &FeatureSet<N>,
+) -> Result<String, PublishError> {
+    let spatial_reference = fset.spatialReference.clone().unwrap_or(SpatialReference {
+        wkid: Some(4326),
+        ..Default::default()
+    });
+
+    let params = CreateServiceParams {
+        name: service_name(title),
+        has_static_data: false,
+        capabilities: Some(EDIT_CAPABILITIES.into()),
+        spatial_reference: Some(spatial_reference.clone()),
+        ..Default::default()
+    };
+
+    let service_url = portal
+        .create_service(&params)
+        .map_err(PublishError::Portal)?
+        .serviceurl
+        .ok_or(PublishError::MissingServiceUrl)?;
+
+    let layer_url = format!("{}/0", service_url.trim_end_matches('/'));
+    let mut layer = FeatureLayerClient::new(&layer_url).with_http_client(portal.client.clone());
+    layer.token = Some(portal.token.clone());
+
+    let definition = json!({ "layers": [layer_definition(title, fset, &spatial_reference)] });
+    layer
+        .add_to_service_definition(&definition)
+        .map_err(PublishError::FeatureService)?;
+
+    BulkUploader::new(&layer)
+        .upload(fset.features.iter().cloned())
+        .map_err(|e| PublishError::Upload(Box::new(e)))?;
+
+    Ok(layer_url)
+}
+
+// service names may only contain letters, numbers and underscores
+fn service_name(title: &str) -> String {
+    title
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+// the layer definition sent to addToDefinition
+fn layer_definition<const N: usize>(
+    title: &str,
+    fset: &FeatureSet<N>,
+    spatial_reference: &SpatialReference,
+) -> Value {
+    let fields = fset
+        .fields
+        .clone()
+        .unwrap_or_else(|| infer_fields(&fset.features));
+
+    let object_id_field = fset
+        .objectIdFieldName
+        .clone()
+        .or_else(|| {
+            fields
+                .iter()
+                .find(|f| matches!(f.field_type, FieldType::EsriFieldTypeOid))
+                .map(|f| f.name.clone())
+        })
+        .unwrap_or_else(|| "OBJECTID".into());
+
+    let mut field_defs = fields
+        .iter()
+        .filter(|f| !matches!(f.field_type, FieldType::EsriFieldTypeGeometry))
+        .map(|f| {
+            let is_oid = f.name == object_id_field;
+            let field_type = if is_oid {
+                FieldType::EsriFieldTypeOid
+            } else {
+                f.field_type.clone()
+            };
+            let mut def = json!({
+                "nullable": !is_oid,
+                "editable": !is_oid,
+            });
+            if matches!(field_type, FieldType::EsriFieldTypeString) {
+                def["length"] = json!(DEFAULT_STRING_LENGTH);
+            }
+            // keep properties such as the length of existing fields
+            def.as_object_mut()
+                .unwrap()
+                .extend(f.extra.iter().map(|(k, v)| (k.clone(), v.clone())));
+            def["name"] = json!(f.name);
+            def["type"] = json!(field_type.as_str_name());
+            def["alias"] = json!(f.alias.as_deref().unwrap_or(&f.name));
+            def
+        })
+        .collect::<Vec<_>>();
+
+    if !fields.iter().any(|f| f.name == object_id_field) {
+        field_defs.insert(
+            0,
+            json!({
+                "name": object_id_field,
+                "type": FieldType::EsriFieldTypeOid.as_str_name(),
+                "alias": object_id_field,
+                "nullable": false,
+                "editable": false,
+            }),
+        );
+    }
+
+    let geometry_type = fset.geometryType.clone().or_else(|| {
+        fset.features
+            .iter()
+            .find_map(|f| f.geometry.as_ref())
+            .map(|g| g.geometry_type().to_string())
+    });
+
+    let (has_z, has_m) = dimension_flags::<N>();
+    let mut def = json!({
+        "id": 0,
+        "name": title,
+        "objectIdField": object_id_field,
+        "fields": field_defs,
+        "capabilities": EDIT_CAPABILITIES,
+        "hasZ": fset.hasZ.unwrap_or(has_z),
+        "hasM": fset.hasM.unwrap_or(has_m),
+        "extent": { "spatialReference": spatial_reference },
+    });
+
+    match geometry_type {
+        Some(geometry_type) => {
+            def["type"] = json!("Feature Layer");
+            def["geometryType"] = json!(geometry_type);
+        }
+        None => def["type"] = json!("Table"),
+    }
+
+    def
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_definition_inference() {
+        let fset: FeatureSet<2> = serde_json::from_str(
+            r#"{"features": [
+                {"geometry": {"x": 1.0, "y": 2.0}, "attributes": {"pop": 1, "name": "a"}},
+                {"geometry": {"x": 3.0, "y": 4.0}, "attributes": {"pop": 2.5, "name": null}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let def = layer_definition("Test", &fset, &SpatialReference::default());
+        assert_eq!(def["type"], "Feature Layer");
+        assert_eq!(def["geometryType"], "esriGeometryPoint");
+        assert_eq!(def["objectIdField"], "OBJECTID");
+
+        let fields = def["fields"].as_array().unwrap();
+        assert_eq!(fields[0]["type"], "esriFieldTypeOID");
+        assert_eq!(fields[1]["name"], "name");
+        assert_eq!(fields[1]["type"], "esriFieldTypeString");
+        assert_eq!(fields[2]["name"], "pop");
+        assert_eq!(fields[2]["type"], "esriFieldTypeDouble");
+        assert_eq!(service_name("My Layer!"), "My_Layer_");
+    }
+}
@@ -0,0 +1,40 @@
+use crate::spatial_reference::SpatialReference;
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+/// The parameters of a new hosted feature service sent to the [`/createService`](https://developers.arcgis.com/rest/users-groups-and-items/create-service/) endpoint
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateServiceParams {
+    /// The name of the service. Must be unique within the organization.
+    #[builder(setter(into))]
+    pub name: String,
+    #[builder(setter(into, strip_option), default)]
+    pub service_description: Option<String>,
+    /// Must be `false` to allow editing. Defaults to `false`.
+    #[builder(setter(into), default)]
+    pub has_static_data: bool,
+    #[builder(setter(into, strip_option), default)]
+    pub max_record_count: Option<u64>,
+    /// Comma separated capabilities, e.g. `"Query,Create,Update,Delete,Editing"`.
+    #[builder(setter(into, strip_option), default)]
+    pub capabilities: Option<String>,
+    #[builder(setter(into, strip_option), default)]
+    pub spatial_reference: Option<SpatialReference>,
+}
+
+/// The response from the [`/createService`](https://developers.arcgis.com/rest/users-groups-and-items/create-service/) endpoint
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateServiceResponse {
+    #[serde(default)]
+    pub success: bool,
+    /// The ID of the portal item of the service
+    pub itemId: Option<String>,
+    pub name: Option<String>,
+    /// The URL of the Feature Service
+    pub serviceurl: Option<String>,
+    pub encodedServiceURL: Option<String>,
+}
@@ -0,0 +1,65 @@
+//! Progress reporting for long running operations.
+//!
+//! Paginated queries, replica downloads, and bulk uploads report their
+//! cumulative [`Progress`] to a [`ProgressReporter`] after every page or batch.
+//! Any `Fn(&Progress)` closure is a reporter.
+//!
+//! ```
+//! use serde_esri::progress::{Progress, ProgressReporter};
+//!
+//! let reporter = |p: &Progress| println!("{:?}: {} features, {} bytes", p.operation, p.features, p.bytes);
+//! reporter.report(&Progress::default());
+//! ```
+
+/// The operation reporting its progress
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Operation {
+    /// A paginated query
+    #[default]
+    Query,
+    /// A replica download
+    Replica,
+    /// An attachment download
+    Attachment,
+    /// A bulk upload
+    Upload,
+}
+
+/// The cumulative progress of an operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub operation: Operation,
+    /// The number of pages fetched or batches uploaded
+    pub pages: u64,
+    /// The number of features downloaded or uploaded
+    pub features: u64,
+    /// The number of bytes downloaded or uploaded
+    pub bytes: u64,
+}
+
+impl Progress {
+    pub fn new(operation: Operation) -> Self {
+        Self {
+            operation,
+            ..Default::default()
+        }
+    }
+
+    /// Count a page or batch of `features` made of `bytes`
+    pub fn add(&mut self, features: usize, bytes: usize) {
+        self.pages += 1;
+        self.features += features as u64;
+        self.bytes += bytes as u64;
+    }
+}
+
+/// Receives the progress of long running operations
+pub trait ProgressReporter: Send + Sync {
+    fn report(&self, progress: &Progress);
+}
+
+impl<F: Fn(&Progress) + Send + Sync> ProgressReporter for F {
+    fn report(&self, progress: &Progress) {
+        self(progress)
+    }
+}
@@ -0,0 +1,245 @@
+//! Recording and replaying the HTTP interactions of clients for tests.
+//!
+//! A [`RecordingTransport`] sends requests with another [`HttpClient`] and records each
+//! request and response. Save them as a JSON [`Cassette`] and replay them with a
+//! [`ReplayTransport`], which needs neither a token nor a network. Requests are matched by
+//! their method, URL, and query parameters. Tokens and authorization headers are not recorded.
+//! Bodies that aren't UTF-8, e.g. of tiles, are recorded as base64.
+//!
+//! ```no_run
+//! use serde_esri::places::query::{CategoriesQueryParams, PlacesClient, PLACES_API_URL};
+//! use serde_esri::replay::{RecordingTransport, ReplayTransport};
+//!
+//! // record the interactions once
+//! let recorder = RecordingTransport::new(reqwest::blocking::Client::new());
+//! let client = PlacesClient::new(PLACES_API_URL, "your token").with_http_client(recorder.clone());
+//! client.categories(CategoriesQueryParams::default()).unwrap();
+//! recorder.save("tests/fixtures/categories.json").unwrap();
+//!
+//! // and replay them in tests
+//! let replay = ReplayTransport::from_file("tests/fixtures/categories.json").unwrap();
+//! let client = PlacesClient::new(PLACES_API_URL, "unused").with_http_client(replay);
+//! let categories = client.categories(CategoriesQueryParams::default()).unwrap();
+//! ```
+use crate::error::ErrorKind;
+use crate::http::{HttpClient, HttpError, HttpRequest, HttpResponse};
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// A request and its response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// The method, URL, and sorted query parameters of the request
+    pub request: String,
+    pub status: u16,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// Whether `body` is base64, because the response body isn't UTF-8
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub base64: bool,
+}
+
+impl Interaction {
+    /// The body of the response
+    pub fn body_bytes(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        match self.base64 {
+            true => BASE64_STANDARD.decode(&self.body),
+            false => Ok(self.body.clone().into_bytes()),
+        }
+    }
+}
+
+/// The recorded interactions, in the order they happened
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let json = fs::read(path)?;
+        serde_json::from_slice(&json).map_err(io::Error::other)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+}
+
+/// An [`HttpClient`] which records the interactions of another
+///
+/// Clones share the recorded interactions.
+#[derive(Debug, Clone)]
+pub struct RecordingTransport<H> {
+    pub client: H,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl<H: HttpClient> RecordingTransport<H> {
+    pub fn new(client: H) -> Self {
+        Self {
+            client,
+            cassette: Arc::default(),
+        }
+    }
+
+    /// The interactions recorded so far
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+
+    /// Write the interactions recorded so far to `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.cassette().save(path)
+    }
+}
+
+impl<H: HttpClient> HttpClient for RecordingTransport<H> {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+        let key = request.key();
+        let resp = self.client.execute(request)?;
+
+        let headers = resp
+            .headers
+            .iter()
+            .filter(|(k, _)| !k.eq_ignore_ascii_case("set-cookie"))
+            .cloned()
+            .collect();
+        let (body, base64) = match String::from_utf8(resp.body.clone()) {
+            Ok(body) => (body, false),
+            Err(_) => (BASE64_STANDARD.encode(&resp.body), true),
+        };
+        self.cassette
+            .lock()
+            .unwrap()
+            .interactions
+            .push(Interaction {
+                request: key,
+                status: resp.status,
+                headers,
+                body,
+                base64,
+            });
+
+        Ok(resp)
+    }
+}
+
+/// An [`HttpClient`] which responds with recorded interactions
+///
+/// Each request receives the first recorded response to the same request that has not
+/// been replayed yet. A request without one fails with an [`ErrorKind::Fatal`] error.
+/// Clones share the interactions that are left.
+#[derive(Debug, Clone)]
+pub struct ReplayTransport {
+    interactions: Arc<Mutex<Vec<Option<Interaction>>>>,
+}
+
+impl ReplayTransport {
+    pub fn new(cassette: Cassette) -> Self {
+        let interactions = cassette.interactions.into_iter().map(Some).collect();
+        Self {
+            interactions: Arc::new(Mutex::new(interactions)),
+        }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Cassette::load(path).map(Self::new)
+    }
+
+    /// The number of interactions that have not been replayed
+    pub fn remaining(&self) -> usize {
+        self.interactions.lock().unwrap().iter().flatten().count()
+    }
+}
+
+impl HttpClient for ReplayTransport {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+        let key = request.key();
+        let mut interactions = self.interactions.lock().unwrap();
+
+        let interaction = interactions
+            .iter_mut()
+            .find(|i| i.as_ref().is_some_and(|i| i.request == key))
+            .and_then(Option::take)
+            .ok_or_else(|| {
+                HttpError::new(ErrorKind::Fatal, format!("no recorded response to {key}"))
+            })?;
+
+        let body = interaction
+            .body_bytes()
+            .map_err(|e| HttpError::new(ErrorKind::Fatal, e))?;
+        Ok(HttpResponse {
+            status: interaction.status,
+            headers: interaction.headers,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let cassette = Cassette {
+            interactions: vec![Interaction {
+                request: HttpRequest::get("https://example.com/a")
+                    .param("x", "1")
+                    .key(),
+                status: 200,
+                headers: vec![],
+                body: "{}".into(),
+                base64: false,
+            }],
+        };
+        let replay = ReplayTransport::new(cassette);
+
+        // the token is not part of the request
+        let request = HttpRequest::get("https://example.com/a").param("token", "secret");
+        let resp = replay.execute(request.param("x", "1")).unwrap();
+        assert_eq!(resp.body, b"{}");
+        assert_eq!(replay.remaining(), 0);
+
+        let request = HttpRequest::get("https://example.com/a").param("x", "1");
+        assert!(replay.execute(request).is_err());
+    }
+
+    // responds with a PNG signature, which isn't UTF-8
+    struct Png;
+
+    const PNG: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+    impl HttpClient for Png {
+        fn execute(&self, _: HttpRequest) -> Result<HttpResponse, HttpError> {
+            Ok(HttpResponse {
+                status: 200,
+                headers: vec![],
+                body: PNG.to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_binary_body() {
+        let recorder = RecordingTransport::new(Png);
+        let request = || HttpRequest::get("https://example.com/tile/0/0/0");
+        recorder.execute(request()).unwrap();
+
+        // the body survives a round trip through JSON
+        let json = serde_json::to_string(&recorder.cassette()).unwrap();
+        let cassette: Cassette = serde_json::from_str(&json).unwrap();
+        assert!(cassette.interactions[0].base64);
+
+        let resp = ReplayTransport::new(cassette).execute(request()).unwrap();
+        assert_eq!(resp.body, PNG);
+    }
+}
@@ -0,0 +1,85 @@
+// Internal helpers for working with the rings of an `EsriPolygon`.
+//
+// Esri JSON does not distinguish exterior and interior rings explicitly.
+// Instead, exterior rings are wound clockwise and interior rings (holes)
+// counter-clockwise. A single `EsriPolygon` may contain multiple exterior
+// rings, each with their own holes.
+//
+// Not every helper is used in every combination of features.
+#![allow(dead_code)]
+use crate::geometry::EsriLineString;
+
+// shoelace formula. Negative values indicate clockwise winding
+pub(crate) fn signed_area<const N: usize, T: Copy + Into<f64>>(ring: &EsriLineString<N, T>) -> f64 {
+    ring.0
+        .windows(2)
+        .map(|w| {
+            let (x1, y1) = (w[0].0[0].into(), w[0].0[1].into());
+            let (x2, y2) = (w[1].0[0].into(), w[1].0[1].into());
+            x1 * y2 - x2 * y1
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+pub(crate) fn is_clockwise<const N: usize, T: Copy + Into<f64>>(
+    ring: &EsriLineString<N, T>,
+) -> bool {
+    signed_area(ring) < 0.0
+}
+
+// even-odd ray casting
+pub(crate) fn contains_point<const N: usize, T: Copy + Into<f64>>(
+    ring: &EsriLineString<N, T>,
+    x: f64,
+    y: f64,
+) -> bool {
+    let mut inside = false;
+    for w in ring.0.windows(2) {
+        let (x1, y1): (f64, f64) = (w[0].0[0].into(), w[0].0[1].into());
+        let (x2, y2): (f64, f64) = (w[1].0[0].into(), w[1].0[1].into());
+        if (y1 > y) != (y2 > y) && x < (x2 - x1) * (y - y1) / (y2 - y1) + x1 {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// Groups rings into polygons. Each inner vector contains the index of an
+// exterior ring followed by the indices of its holes. Holes are assigned to
+// the smallest exterior ring containing them. Holes that are not contained
+// by any exterior ring are treated as exterior rings themselves.
+pub(crate) fn classify_rings<const N: usize, T: Copy + Into<f64>>(
+    rings: &[EsriLineString<N, T>],
+) -> Vec<Vec<usize>> {
+    let (exteriors, holes): (Vec<usize>, Vec<usize>) = rings
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| !r.0.is_empty())
+        .map(|(i, _)| i)
+        .partition(|&i| is_clockwise(&rings[i]));
+
+    let mut polygons = exteriors.iter().map(|&i| vec![i]).collect::<Vec<_>>();
+
+    for hole in holes {
+        let (x, y) = (rings[hole].0[0].0[0].into(), rings[hole].0[0].0[1].into());
+
+        let parent = polygons
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| contains_point(&rings[p[0]], x, y))
+            .min_by(|(_, a), (_, b)| {
+                let area_a = signed_area(&rings[a[0]]).abs();
+                let area_b = signed_area(&rings[b[0]]).abs();
+                area_a.total_cmp(&area_b)
+            })
+            .map(|(i, _)| i);
+
+        match parent {
+            Some(i) => polygons[i].push(hole),
+            None => polygons.push(vec![hole]),
+        }
+    }
+
+    polygons
+}
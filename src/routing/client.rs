@@ -0,0 +1,131 @@
+use crate::auth::{CredentialError, CredentialProvider, TokenProvider, TokenSource};
+use crate::error::{ClassifyError, ErrorKind};
+use crate::feature_service::{ErrorResponse, ExpectedResponse};
+use crate::http::{HttpClient, HttpError, HttpRequest};
+use crate::routing::{RouteParams, SolveResponse};
+use crate::trace;
+
+/// The URL of the ArcGIS World Route Service
+pub const ROUTE_SERVICE_URL: &str =
+    "https://route-api.arcgis.com/arcgis/rest/services/World/Route/NAServer/Route_World";
+
+#[derive(Debug)]
+pub enum RoutingError {
+    /// The request failed or the response is not the expected JSON
+    HttpError(HttpError),
+    ApiError(ErrorResponse),
+    /// The token provider failed to supply a token
+    CredentialError(CredentialError),
+}
+
+impl std::fmt::Display for RoutingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HttpError(e) => write!(f, "{e}"),
+            Self::ApiError(e) => write!(f, "{} ({})", e.error.message, e.error.code),
+            Self::CredentialError(e) => write!(f, "failed to get token: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RoutingError {}
+
+impl ClassifyError for RoutingError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::HttpError(e) => e.kind(),
+            Self::ApiError(e) => e.kind(),
+            Self::CredentialError(e) => e.kind(),
+        }
+    }
+}
+
+/// A client for the ArcGIS World Route Service or any other route service
+///
+/// ```
+/// use serde_esri::routing::{RouteClient, ROUTE_SERVICE_URL};
+/// let client = RouteClient::new(ROUTE_SERVICE_URL, "your token");
+/// ```
+///
+/// ## Note
+///
+/// Replace `"your token"` with your actual token.
+///
+/// Requests are sent with a `reqwest::blocking::Client` unless another [`HttpClient`]
+/// is set with [`RouteClient::with_http_client()`].
+///
+#[derive(Clone)]
+pub struct RouteClient<H = reqwest::blocking::Client> {
+    pub service_url: String,
+    pub(crate) client: H,
+    /// The token to use for authorization. Redacted from `Debug` and zeroized on drop.
+    pub(crate) token: TokenSource,
+}
+
+impl<H> std::fmt::Debug for RouteClient<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RouteClient")
+            .field("service_url", &self.service_url)
+            .field("token", &"[REDACTED]")
+            .finish_non_exhaustive()
+    }
+}
+
+impl RouteClient {
+    /// Create a new client for the route service at `service_url`
+    pub fn new(service_url: &str, token: &str) -> Self {
+        Self {
+            service_url: service_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: token.into(),
+        }
+    }
+
+    /// Create a new client for the route service using a token from `credentials`
+    pub fn from_credentials<C: CredentialProvider>(
+        service_url: &str,
+        credentials: &C,
+    ) -> Result<Self, CredentialError> {
+        Ok(Self {
+            service_url: service_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: credentials.token()?.into(),
+        })
+    }
+
+    /// Create a new client for the route service which gets a token from `provider` before every request
+    pub fn from_token_provider<P: TokenProvider>(service_url: &str, provider: P) -> Self {
+        Self {
+            service_url: service_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: TokenSource::new(provider),
+        }
+    }
+}
+
+impl<H: HttpClient> RouteClient<H> {
+    /// Send requests with `client` instead
+    pub fn with_http_client<C: HttpClient>(self, client: C) -> RouteClient<C> {
+        RouteClient {
+            service_url: self.service_url,
+            client,
+            token: self.token,
+        }
+    }
+
+    /// Query the [`/solve`](https://developers.arcgis.com/rest/network/api-reference/route-synchronous-service.htm) endpoint
+    pub fn solve(&self, params: &RouteParams) -> Result<SolveResponse, RoutingError> {
+        let bearer = self.token.bearer().map_err(RoutingError::CredentialError)?;
+        let request = HttpRequest::post(&format!("{}/solve", self.service_url))
+            .header("X-Esri-Authorization", &bearer)
+            .form(&params.form());
+        let resp = trace::send(&self.client, request)
+            .and_then(|r| r.json::<ExpectedResponse<SolveResponse>>())
+            .map_err(RoutingError::HttpError)?;
+
+        match resp {
+            ExpectedResponse::Ok(x) => Ok(x),
+            ExpectedResponse::Error(e) => Err(RoutingError::ApiError(e)),
+        }
+    }
+}
@@ -0,0 +1,16 @@
+//! Types for the [ArcGIS routing service](https://developers.arcgis.com/rest/network/api-reference/route-synchronous-service.htm).
+//!
+//! Activate the `"routing-client"` feature to enable the `RouteClient` struct which finds routes with the `/solve` endpoint.
+//!
+//! [`RouteParamsBuilder`] creates a [`RouteParams`] struct with the stops, barriers, and [`TravelMode`] of the route.
+//! The [`SolveResponse`] contains the geometry and totals of each [`Route`] as well as its [`RouteDirections`].
+mod params;
+mod responses;
+pub use params::*;
+pub use responses::*;
+
+#[cfg(feature = "routing-client")]
+mod client;
+
+#[cfg(feature = "routing-client")]
+pub use client::*;
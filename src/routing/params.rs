@@ -0,0 +1,150 @@
+use crate::geometry::{EsriPoint, EsriPolygon, EsriPolyline};
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+
+/// A travel mode, e.g. driving time or walking distance
+///
+/// Use a travel mode returned by the `retrieveTravelModes` endpoint of a route
+/// service. Properties that are not modelled here are kept in `extra`.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TravelMode {
+    pub name: Option<String>,
+    pub id: Option<String>,
+    /// `"AUTOMOBILE"`, `"TRUCK"`, `"WALK"`, or `"OTHER"`
+    #[serde(rename = "type")]
+    pub mode_type: Option<String>,
+    pub description: Option<String>,
+    pub impedance_attribute_name: Option<String>,
+    pub time_attribute_name: Option<String>,
+    pub distance_attribute_name: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Request parameters used to query the [`/solve`](https://developers.arcgis.com/rest/network/api-reference/route-synchronous-service.htm) endpoint
+#[derive(Debug, Clone, Default, Builder)]
+pub struct RouteParams {
+    /// The locations to visit in order, at least two
+    #[builder(setter(into))]
+    pub stops: Vec<EsriPoint>,
+    /// Locations that cannot be traversed
+    #[builder(setter(into, strip_option), default)]
+    pub point_barriers: Option<Vec<EsriPoint>>,
+    /// Lines that cannot be crossed
+    #[builder(setter(into, strip_option), default)]
+    pub polyline_barriers: Option<Vec<EsriPolyline<2>>>,
+    /// Areas that cannot be traversed
+    #[builder(setter(into, strip_option), default)]
+    pub polygon_barriers: Option<Vec<EsriPolygon<2>>>,
+    /// Defaults to the default travel mode of the service
+    #[builder(setter(into, strip_option), default)]
+    pub travel_mode: Option<TravelMode>,
+    /// Whether the stops are reordered to find the shortest route
+    #[builder(setter(into, strip_option), default)]
+    pub find_best_sequence: Option<bool>,
+    /// Whether turn-by-turn directions are returned. Defaults to `true`.
+    #[builder(setter(into, strip_option), default)]
+    pub return_directions: Option<bool>,
+    /// The language of the directions, e.g. `"fr"`
+    #[builder(setter(into, strip_option), default)]
+    pub directions_language: Option<String>,
+    /// The unit of the directions lengths, e.g. `"esriNAUKilometers"` or `"esriNAUMiles"`
+    #[builder(setter(into, strip_option), default)]
+    pub directions_length_units: Option<String>,
+    /// The time the route starts as milliseconds since the epoch
+    #[builder(setter(into, strip_option), default)]
+    pub start_time: Option<i64>,
+    /// The WKID of the returned geometries
+    #[builder(setter(into, strip_option), default)]
+    pub out_sr: Option<u32>,
+}
+
+#[cfg(feature = "routing-client")]
+impl RouteParams {
+    // the form of the request. Locations are sent as feature sets.
+    pub(crate) fn form(&self) -> Vec<(&'static str, String)> {
+        let mut form = vec![
+            ("stops", features_json(&self.stops)),
+            ("returnRoutes", "true".to_string()),
+            ("f", "json".to_string()),
+        ];
+        let optional = [
+            (
+                "barriers",
+                self.point_barriers.as_deref().map(features_json),
+            ),
+            (
+                "polylineBarriers",
+                self.polyline_barriers.as_deref().map(features_json),
+            ),
+            (
+                "polygonBarriers",
+                self.polygon_barriers.as_deref().map(features_json),
+            ),
+            (
+                "travelMode",
+                self.travel_mode
+                    .as_ref()
+                    .and_then(|t| serde_json::to_string(t).ok()),
+            ),
+            (
+                "findBestSequence",
+                self.find_best_sequence.map(|x| x.to_string()),
+            ),
+            (
+                "returnDirections",
+                self.return_directions.map(|x| x.to_string()),
+            ),
+            ("directionsLanguage", self.directions_language.clone()),
+            (
+                "directionsLengthUnits",
+                self.directions_length_units.clone(),
+            ),
+            ("startTime", self.start_time.map(|x| x.to_string())),
+            ("outSR", self.out_sr.map(|x| x.to_string())),
+        ];
+        form.extend(optional.into_iter().filter_map(|(k, v)| Some((k, v?))));
+        form
+    }
+}
+
+// geometries as `{"features": [{"geometry": ...}]}`
+#[cfg(feature = "routing-client")]
+fn features_json<G: Serialize>(geometries: &[G]) -> String {
+    let features = geometries
+        .iter()
+        .map(|g| serde_json::json!({ "geometry": g }))
+        .collect::<Vec<_>>();
+    serde_json::json!({ "features": features }).to_string()
+}
+
+#[cfg(all(test, feature = "routing-client"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_form() {
+        let stop = |x, y| EsriPoint {
+            x,
+            y,
+            ..Default::default()
+        };
+        let params = RouteParamsBuilder::default()
+            .stops(vec![stop(-117.19, 34.05), stop(-117.2, 34.06)])
+            .return_directions(false)
+            .build()
+            .unwrap();
+
+        let form = params.form();
+        assert_eq!(
+            form[0].1,
+            r#"{"features":[{"geometry":{"x":-117.19,"y":34.05}},{"geometry":{"x":-117.2,"y":34.06}}]}"#
+        );
+        assert!(form.contains(&("returnDirections", "false".to_string())));
+        assert!(!form.iter().any(|(k, _)| *k == "travelMode"));
+    }
+}
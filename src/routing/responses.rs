@@ -0,0 +1,152 @@
+use crate::geometry::{EsriEnvelope, EsriPolyline};
+use crate::spatial_reference::SpatialReference;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The attributes of a route
+///
+/// The total cost of each attribute of the travel mode is returned as
+/// `Total_<attribute>`. Those that are not modelled here are kept in `extra`.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouteAttributes {
+    pub Name: Option<String>,
+    /// The travel time in minutes
+    pub Total_TravelTime: Option<f64>,
+    pub Total_Kilometers: Option<f64>,
+    pub Total_Miles: Option<f64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A route between the stops
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Route {
+    pub attributes: RouteAttributes,
+    pub geometry: Option<EsriPolyline<2>>,
+}
+
+/// The routes returned by the /solve endpoint
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Routes {
+    pub spatialReference: Option<SpatialReference>,
+    #[serde(default)]
+    pub features: Vec<Route>,
+}
+
+/// The totals of a route's directions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectionsSummary {
+    /// The length in `directions_length_units`
+    pub total_length: f64,
+    /// The time in minutes
+    pub total_time: f64,
+    pub total_drive_time: Option<f64>,
+    pub envelope: Option<EsriEnvelope>,
+}
+
+/// One maneuver of the directions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Direction {
+    /// The instruction, e.g. `"Turn left on New York St"`
+    pub text: String,
+    pub length: f64,
+    pub time: f64,
+    /// The estimated time of arrival as milliseconds since the epoch
+    #[serde(rename = "ETA")]
+    pub eta: Option<i64>,
+    pub maneuver_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DirectionFeature {
+    pub(crate) attributes: Direction,
+}
+
+/// The turn-by-turn directions of a route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteDirections {
+    pub route_id: Option<i64>,
+    pub route_name: Option<String>,
+    pub summary: DirectionsSummary,
+    #[serde(with = "direction_features")]
+    pub features: Vec<Direction>,
+}
+
+// directions are wrapped in features as `{"attributes": {...}}`
+mod direction_features {
+    use super::{Direction, DirectionFeature};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(directions: &[Direction], s: S) -> Result<S::Ok, S::Error> {
+        directions
+            .iter()
+            .map(|d| DirectionFeature {
+                attributes: d.clone(),
+            })
+            .collect::<Vec<_>>()
+            .serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Direction>, D::Error> {
+        let features = Vec::<DirectionFeature>::deserialize(d)?;
+        Ok(features.into_iter().map(|f| f.attributes).collect())
+    }
+}
+
+/// Represents the response from the /solve endpoint
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SolveResponse {
+    #[serde(default)]
+    pub routes: Routes,
+    /// Empty unless `return_directions` is `true`
+    #[serde(default)]
+    pub directions: Vec<RouteDirections>,
+    /// Warnings raised while solving
+    #[serde(default)]
+    pub messages: Vec<Value>,
+}
+
+impl SolveResponse {
+    /// The geometry of the first route
+    pub fn route_geometry(&self) -> Option<&EsriPolyline<2>> {
+        self.routes.features.first()?.geometry.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_response() {
+        let json = r#"{
+            "routes": {
+                "spatialReference": {"wkid": 4326},
+                "features": [{
+                    "attributes": {"ObjectID": 1, "Name": "Stop 1 - Stop 2", "Total_TravelTime": 4.5, "Total_Kilometers": 2.1},
+                    "geometry": {"paths": [[[-117.19, 34.05], [-117.2, 34.06]]]}
+                }]
+            },
+            "directions": [{
+                "routeId": 1,
+                "routeName": "Stop 1 - Stop 2",
+                "summary": {"totalLength": 2.1, "totalTime": 4.5, "totalDriveTime": 4.5},
+                "features": [
+                    {"attributes": {"length": 0, "time": 0, "text": "Start at Stop 1", "ETA": -2209161600000, "maneuverType": "esriDMTDepart"}, "compressedGeometry": "+1+0+0"}
+                ]
+            }],
+            "messages": []
+        }"#;
+        let resp: SolveResponse = serde_json::from_str(json).unwrap();
+        let route = &resp.routes.features[0];
+        assert_eq!(route.attributes.Total_TravelTime, Some(4.5));
+        assert!(route.attributes.extra.contains_key("ObjectID"));
+        assert_eq!(resp.route_geometry().unwrap().paths.len(), 1);
+        assert_eq!(resp.directions[0].features[0].text, "Start at Stop 1");
+    }
+}
@@ -0,0 +1,159 @@
+// Implements `rstar` traits for `Feature` so that a `FeatureSet` can be indexed in an `RTree`.
+use crate::features::{Feature, FeatureSet};
+use crate::geometry::{EsriCoord, EsriEnvelope, EsriGeometry, EsriLineString};
+use crate::rings::contains_point;
+use rstar::{Envelope, PointDistance, RTree, RTreeObject, AABB};
+
+impl<const N: usize, A> RTreeObject for Feature<N, A> {
+    type Envelope = AABB<[f64; 2]>;
+
+    /// The envelope of the geometry. Features without a geometry have an empty envelope.
+    fn envelope(&self) -> Self::Envelope {
+        match self.geometry.as_ref().and_then(EsriGeometry::extent) {
+            Some(e) => AABB::from_corners([e.xmin, e.ymin], [e.xmax, e.ymax]),
+            None => AABB::new_empty(),
+        }
+    }
+}
+
+impl<const N: usize, A> PointDistance for Feature<N, A> {
+    /// The squared distance from `point` to the geometry, `0` inside polygons and envelopes
+    fn distance_2(&self, point: &[f64; 2]) -> f64 {
+        let Some(geometry) = &self.geometry else {
+            return f64::INFINITY;
+        };
+        match geometry {
+            EsriGeometry::Point(pt) => distance_2(point, [pt.x, pt.y]),
+            EsriGeometry::MultiPoint(mp) => mp
+                .points
+                .iter()
+                .map(|c| distance_2(point, [c.0[0], c.0[1]]))
+                .fold(f64::INFINITY, f64::min),
+            EsriGeometry::Polyline(pl) => lines_distance_2(&pl.paths, point),
+            EsriGeometry::Polygon(ply) => polygon_distance_2(&ply.rings, point),
+            EsriGeometry::CurvePolyline(pl) => lines_distance_2(&pl.linearize().paths, point),
+            EsriGeometry::CurvePolygon(ply) => polygon_distance_2(&ply.linearize().rings, point),
+            EsriGeometry::Envelope(e) => envelope_distance_2(e, point),
+            EsriGeometry::Multipatch(_) => self.envelope().distance_2(point),
+        }
+    }
+}
+
+fn distance_2(a: &[f64; 2], b: [f64; 2]) -> f64 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)
+}
+
+fn segment_distance_2(p: &[f64; 2], a: [f64; 2], b: [f64; 2]) -> f64 {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let len_2 = dx * dx + dy * dy;
+    let t = if len_2 > 0.0 {
+        (((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / len_2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    distance_2(p, [a[0] + t * dx, a[1] + t * dy])
+}
+
+// the distance to the nearest segment, or vertex of a line with a single vertex
+fn lines_distance_2<const N: usize>(lines: &[EsriLineString<N>], p: &[f64; 2]) -> f64 {
+    let xy = |c: &EsriCoord<N>| [c.0[0], c.0[1]];
+    lines
+        .iter()
+        .flat_map(|line| {
+            let first = line.0.first().map(|c| distance_2(p, xy(c)));
+            line.0
+                .windows(2)
+                .map(move |w| segment_distance_2(p, xy(&w[0]), xy(&w[1])))
+                .chain(first)
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+// points inside an odd number of rings are inside the polygon
+fn polygon_distance_2<const N: usize>(rings: &[EsriLineString<N>], p: &[f64; 2]) -> f64 {
+    let inside = rings
+        .iter()
+        .filter(|r| contains_point(r, p[0], p[1]))
+        .count();
+    match inside % 2 {
+        1 => 0.0,
+        _ => lines_distance_2(rings, p),
+    }
+}
+
+fn envelope_distance_2(e: &EsriEnvelope, p: &[f64; 2]) -> f64 {
+    AABB::from_corners([e.xmin, e.ymin], [e.xmax, e.ymax]).distance_2(p)
+}
+
+impl<const N: usize, A: Clone> FeatureSet<N, A> {
+    /// An [`RTree`] of the features that have a geometry, for spatial queries in
+    /// the coordinates of the `FeatureSet`
+    ///
+    /// ```
+    /// use serde_esri::features::FeatureSet;
+    ///
+    /// let fset: FeatureSet<2> = serde_json::from_str(r#"{"features": [
+    ///     {"geometry": {"rings": [[[0, 0], [0, 10], [10, 10], [10, 0], [0, 0]]]}, "attributes": {"id": 1}},
+    ///     {"geometry": {"x": 20, "y": 20}, "attributes": {"id": 2}},
+    ///     {"attributes": {"id": 3}}
+    /// ]}"#).unwrap();
+    ///
+    /// let tree = fset.to_rtree();
+    /// assert_eq!(tree.size(), 2);
+    ///
+    /// let containing: Vec<_> = tree.locate_all_at_point(&[5.0, 5.0]).collect();
+    /// assert_eq!(containing[0].attributes.as_ref().unwrap()["id"], 1);
+    ///
+    /// let nearest = tree.nearest_neighbor(&[18.0, 18.0]).unwrap();
+    /// assert_eq!(nearest.attributes.as_ref().unwrap()["id"], 2);
+    /// ```
+    pub fn to_rtree(&self) -> RTree<Feature<N, A>> {
+        let features = self
+            .features
+            .iter()
+            .filter(|f| f.geometry.is_some())
+            .cloned()
+            .collect();
+        RTree::bulk_load(features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{EsriPolygon, EsriPolyline};
+
+    #[test]
+    fn test_distance_2() {
+        let feature = |geometry| Feature::<2> {
+            geometry: Some(geometry),
+            ..Default::default()
+        };
+
+        // a square with a hole
+        let polygon = feature(EsriGeometry::Polygon(EsriPolygon::from_coords_zm([
+            [
+                [0.0, 0.0],
+                [0.0, 10.0],
+                [10.0, 10.0],
+                [10.0, 0.0],
+                [0.0, 0.0],
+            ],
+            [[4.0, 4.0], [6.0, 4.0], [6.0, 6.0], [4.0, 6.0], [4.0, 4.0]],
+        ])));
+        assert_eq!(polygon.distance_2(&[2.0, 2.0]), 0.0);
+        assert_eq!(polygon.distance_2(&[5.0, 5.0]), 1.0);
+        assert_eq!(polygon.distance_2(&[13.0, 14.0]), 25.0);
+        assert_eq!(
+            polygon.envelope(),
+            AABB::from_corners([0.0, 0.0], [10.0, 10.0])
+        );
+
+        let polyline = feature(EsriGeometry::Polyline(EsriPolyline::from_coords_zm([[
+            [0.0, 0.0],
+            [10.0, 0.0],
+        ]])));
+        assert_eq!(polyline.distance_2(&[5.0, 3.0]), 9.0);
+        assert_eq!(polyline.distance_2(&[-3.0, 4.0]), 25.0);
+    }
+}
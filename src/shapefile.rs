@@ -0,0 +1,1005 @@
+//! Optional feature for reading and writing shapefiles with the [`shapefile`](https://docs.rs/shapefile) crate.
+//!
+//! Shapes are converted into `EsriGeometry<N>` and dBase records into attribute
+//! maps so that a shapefile can be read directly into a `FeatureSet<N>` with
+//! [`read_shapefile()`] and written from one with [`write_shapefile()`].
+//!
+//! The const `N` determines which dimensions are read from each shape:
+//! `2` reads XY from any shape, `3` reads XYZ and `4` reads XYZM from `*Z` shapes.
+//!
+//! dBase fields are mapped to Esri field types as follows:
+//!
+//! - `Character`, `Memo` -> `esriFieldTypeString`
+//! - `Numeric` without decimal places -> `esriFieldTypeInteger`
+//! - `Numeric`, `Double`, `Currency` -> `esriFieldTypeDouble`
+//! - `Float` -> `esriFieldTypeSingle`
+//! - `Integer` -> `esriFieldTypeInteger`
+//! - `Logical` -> `esriFieldTypeSmallInteger`
+//! - `Date`, `DateTime` -> `esriFieldTypeDate` (epoch milliseconds)
+//!
+//! When writing, Esri fields are mapped back to dBase fields:
+//!
+//! - `String`, `GUID`, `GlobalID`, `XML` -> `Character` (254)
+//! - `SmallInteger`, `Integer`, `OID` -> `Numeric` (18, 0)
+//! - `Single`, `Double` -> `Numeric` (24, 15)
+//! - `Date` -> `Date`
+//!
+//! Other fields are skipped and field names are truncated to 10 characters.
+//! Geometries are written as `*Z` shapes when the layer has z values, as `*M`
+//! shapes when it only has m values, and as 2D shapes otherwise. Envelopes are
+//! written as polygons and true curves are densified. Features without a geometry
+//! are written as null shapes.
+use crate::{
+    features::{infer_fields, Feature, FeatureSet, Field},
+    field_type::FieldType,
+    geometry::*,
+    rings::is_clockwise,
+    spatial_reference::SpatialReference,
+};
+use serde_json::{Map, Value};
+use shapefile::dbase::{self, FieldInfo, FieldValue};
+use shapefile::record::{EsriShape, WritableShape};
+use shapefile::HasShapeType;
+use shapefile::{
+    Multipatch, Multipoint, MultipointM, MultipointZ, Patch, Point, PointM, PointZ, Polygon,
+    PolygonM, PolygonRing, PolygonZ, Polyline, PolylineM, PolylineZ, Shape, ShapeReader, ShapeType,
+    NO_DATA,
+};
+use std::collections::HashSet;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// Errors that can occur when converting between shapefiles and Esri types
+#[derive(Debug)]
+pub enum ShapefileError {
+    /// An error from the shapefile or dBase reader or writer
+    Shapefile(shapefile::Error),
+    /// Null shapes have no geometry
+    NullShape,
+    /// The shape does not have the dimensions required by `N`
+    DimensionMismatch { expected: usize, found: ShapeType },
+    /// The geometry has no coordinates or a path with fewer than two points
+    EmptyGeometry,
+    /// Two field names are the same once truncated to 10 characters
+    DuplicateFieldName(String),
+    /// The name cannot be used as a dBase field name
+    InvalidFieldName(String),
+    /// An error writing the `.prj` file
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for ShapefileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Shapefile(e) => write!(f, "{e}"),
+            Self::NullShape => write!(f, "null shapes cannot be converted"),
+            Self::DimensionMismatch { expected, found } => write!(
+                f,
+                "expected a shape with {expected} dimensions, found {found}"
+            ),
+            Self::EmptyGeometry => write!(f, "empty geometries cannot be converted"),
+            Self::DuplicateFieldName(name) => {
+                write!(f, "the dBase field name `{name}` is used more than once")
+            }
+            Self::InvalidFieldName(name) => write!(f, "`{name}` is not a valid dBase field name"),
+            Self::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ShapefileError {}
+
+impl From<shapefile::Error> for ShapefileError {
+    fn from(value: shapefile::Error) -> Self {
+        Self::Shapefile(value)
+    }
+}
+
+impl From<dbase::Error> for ShapefileError {
+    fn from(value: dbase::Error) -> Self {
+        Self::Shapefile(value.into())
+    }
+}
+
+impl From<std::io::Error> for ShapefileError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+// Shapes
+
+// shared access to the coordinates of the three shapefile point types
+trait ShapePoint {
+    const SHAPE_TYPE: ShapeType;
+    fn xyzm(&self) -> (f64, f64, Option<f64>, Option<f64>);
+}
+
+impl ShapePoint for Point {
+    const SHAPE_TYPE: ShapeType = ShapeType::Point;
+    fn xyzm(&self) -> (f64, f64, Option<f64>, Option<f64>) {
+        (self.x, self.y, None, None)
+    }
+}
+
+impl ShapePoint for PointM {
+    const SHAPE_TYPE: ShapeType = ShapeType::PointM;
+    fn xyzm(&self) -> (f64, f64, Option<f64>, Option<f64>) {
+        (self.x, self.y, None, Some(self.m))
+    }
+}
+
+impl ShapePoint for PointZ {
+    const SHAPE_TYPE: ShapeType = ShapeType::PointZ;
+    fn xyzm(&self) -> (f64, f64, Option<f64>, Option<f64>) {
+        (self.x, self.y, Some(self.z), Some(self.m))
+    }
+}
+
+fn to_coord<const N: usize, P: ShapePoint>(p: &P) -> Result<EsriCoord<N>, ShapefileError> {
+    let mismatch = ShapefileError::DimensionMismatch {
+        expected: N,
+        found: P::SHAPE_TYPE,
+    };
+
+    let (x, y, z, m) = p.xyzm();
+    let mut coord = [x; N];
+    coord[1] = y;
+
+    let (has_z, has_m) = dimension_flags::<N>();
+    if has_z {
+        coord[2] = z.ok_or(mismatch)?;
+    }
+    if has_m {
+        // PointZ always carries an m value
+        coord[3] = m.unwrap_or_default();
+    }
+
+    Ok(EsriCoord(coord))
+}
+
+fn to_linestring<const N: usize, P: ShapePoint>(
+    points: &[P],
+) -> Result<EsriLineString<N>, ShapefileError> {
+    let coords = points.iter().map(to_coord).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(EsriLineString(coords))
+}
+
+fn to_point<P: ShapePoint>(p: &P) -> EsriPoint {
+    let (x, y, z, m) = p.xyzm();
+    EsriPoint {
+        x,
+        y,
+        z,
+        m,
+        spatialReference: None,
+    }
+}
+
+fn to_multipoint<const N: usize, P: ShapePoint>(
+    points: &[P],
+) -> Result<EsriMultiPoint<N>, ShapefileError> {
+    let mp = EsriMultiPoint {
+        hasZ: None,
+        hasM: None,
+        points: to_linestring(points)?.0,
+        spatialReference: None,
+    };
+    Ok(mp.with_dimension_flags())
+}
+
+fn to_polyline<const N: usize, P: ShapePoint>(
+    parts: &[Vec<P>],
+) -> Result<EsriPolyline<N>, ShapefileError> {
+    let pl = EsriPolyline {
+        hasZ: None,
+        hasM: None,
+        paths: parts
+            .iter()
+            .map(|part| to_linestring(part))
+            .collect::<Result<Vec<_>, _>>()?,
+        spatialReference: None,
+    };
+    Ok(pl.with_dimension_flags())
+}
+
+// shapefile rings use the same winding as Esri JSON: outer rings
+// are clockwise and holes are counter-clockwise
+fn to_polygon<const N: usize, P: ShapePoint>(
+    rings: &[PolygonRing<P>],
+) -> Result<EsriPolygon<N>, ShapefileError> {
+    let ply = EsriPolygon {
+        hasZ: None,
+        hasM: None,
+        rings: rings
+            .iter()
+            .map(|ring| to_linestring(ring.points()))
+            .collect::<Result<Vec<_>, _>>()?,
+        spatialReference: None,
+    };
+    Ok(ply.with_dimension_flags())
+}
+
+fn to_multipatch<const N: usize>(patches: &[Patch]) -> Result<EsriMultipatch<N>, ShapefileError> {
+    let (rings, ring_types) = patches
+        .iter()
+        .map(|patch| {
+            let (points, ring_type) = match patch {
+                Patch::TriangleStrip(p) => (p, EsriPatchType::TriangleStrip),
+                Patch::TriangleFan(p) => (p, EsriPatchType::TriangleFan),
+                Patch::OuterRing(p) => (p, EsriPatchType::OuterRing),
+                Patch::InnerRing(p) => (p, EsriPatchType::InnerRing),
+                Patch::FirstRing(p) => (p, EsriPatchType::FirstRing),
+                Patch::Ring(p) => (p, EsriPatchType::Ring),
+            };
+            Ok((to_linestring(points)?, ring_type))
+        })
+        .collect::<Result<(Vec<_>, Vec<_>), ShapefileError>>()?;
+
+    let mp = EsriMultipatch {
+        rings,
+        ringTypes: ring_types,
+        ..Default::default()
+    };
+    Ok(mp.with_dimension_flags())
+}
+
+impl<const N: usize> TryFrom<&Shape> for EsriGeometry<N> {
+    type Error = ShapefileError;
+
+    fn try_from(value: &Shape) -> Result<Self, Self::Error> {
+        let geom = match value {
+            Shape::NullShape => return Err(ShapefileError::NullShape),
+            Shape::Multipatch(mp) => EsriGeometry::Multipatch(to_multipatch(mp.patches())?),
+            Shape::Point(p) => EsriGeometry::Point(to_point(p)),
+            Shape::PointM(p) => EsriGeometry::Point(to_point(p)),
+            Shape::PointZ(p) => EsriGeometry::Point(to_point(p)),
+            Shape::Multipoint(mp) => EsriGeometry::MultiPoint(to_multipoint(mp.points())?),
+            Shape::MultipointM(mp) => EsriGeometry::MultiPoint(to_multipoint(mp.points())?),
+            Shape::MultipointZ(mp) => EsriGeometry::MultiPoint(to_multipoint(mp.points())?),
+            Shape::Polyline(pl) => EsriGeometry::Polyline(to_polyline(pl.parts())?),
+            Shape::PolylineM(pl) => EsriGeometry::Polyline(to_polyline(pl.parts())?),
+            Shape::PolylineZ(pl) => EsriGeometry::Polyline(to_polyline(pl.parts())?),
+            Shape::Polygon(ply) => EsriGeometry::Polygon(to_polygon(ply.rings())?),
+            Shape::PolygonM(ply) => EsriGeometry::Polygon(to_polygon(ply.rings())?),
+            Shape::PolygonZ(ply) => EsriGeometry::Polygon(to_polygon(ply.rings())?),
+        };
+
+        Ok(geom)
+    }
+}
+
+// the x, y, z, and m of a coordinate with missing z as 0 and missing m as NO_DATA
+fn coord_xyzm<const N: usize>(c: &EsriCoord<N>, has_z: bool, has_m: bool) -> [f64; 4] {
+    let m_idx = if has_z { 3 } else { 2 };
+    let z = if has_z { c.0.get(2) } else { None };
+    let m = if has_m { c.0.get(m_idx) } else { None };
+    [
+        c.0[0],
+        c.0[1],
+        z.copied().unwrap_or(0.0),
+        m.copied().unwrap_or(NO_DATA),
+    ]
+}
+
+fn point_xyzm(pt: &EsriPoint) -> [f64; 4] {
+    [pt.x, pt.y, pt.z.unwrap_or(0.0), pt.m.unwrap_or(NO_DATA)]
+}
+
+// envelopes are written as clockwise rings
+fn envelope_ring<const N: usize>(e: &EsriEnvelope) -> EsriLineString<N> {
+    let coord = |x: f64, y: f64| {
+        let mut c = [0.0; N];
+        c[0] = x;
+        c[1] = y;
+        EsriCoord(c)
+    };
+    EsriLineString(vec![
+        coord(e.xmin, e.ymin),
+        coord(e.xmin, e.ymax),
+        coord(e.xmax, e.ymax),
+        coord(e.xmax, e.ymin),
+        coord(e.xmin, e.ymin),
+    ])
+}
+
+fn multipatch_shape<const N: usize>(
+    mp: &EsriMultipatch<N>,
+    has_m: bool,
+) -> Result<Shape, ShapefileError> {
+    let patches = mp
+        .rings
+        .iter()
+        .zip(&mp.ringTypes)
+        .flat_map(|(ring, ring_type)| {
+            let points = ring
+                .0
+                .iter()
+                .map(|c| {
+                    let [x, y, z, m] = coord_xyzm(c, true, has_m);
+                    PointZ::new(x, y, z, m)
+                })
+                .collect::<Vec<_>>();
+            match ring_type {
+                EsriPatchType::TriangleStrip => vec![Patch::TriangleStrip(points)],
+                EsriPatchType::TriangleFan => vec![Patch::TriangleFan(points)],
+                EsriPatchType::OuterRing => vec![Patch::OuterRing(points)],
+                EsriPatchType::InnerRing => vec![Patch::InnerRing(points)],
+                EsriPatchType::FirstRing => vec![Patch::FirstRing(points)],
+                EsriPatchType::Ring => vec![Patch::Ring(points)],
+                // shapefiles have no patch of separate triangles
+                EsriPatchType::Triangles => points
+                    .chunks_exact(3)
+                    .map(|t| Patch::TriangleStrip(t.to_vec()))
+                    .collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if patches.is_empty() {
+        return Err(ShapefileError::EmptyGeometry);
+    }
+    Ok(Shape::Multipatch(Multipatch::with_parts(patches)))
+}
+
+// builds the shape of a geometry from one of the three families of shape types
+macro_rules! esri_to_shape {
+    (
+        $geom:expr, $has_z:expr, $has_m:expr,
+        $multipoint:ident, $polyline:ident, $polygon:ident,
+        |$x:ident, $y:ident, $z:ident, $m:ident| $point:expr
+    ) => {{
+        let to_point = |[$x, $y, $z, $m]: [f64; 4]| $point;
+        let to_points = |line: &EsriLineString<N>| {
+            line.0
+                .iter()
+                .map(|c| to_point(coord_xyzm(c, $has_z, $has_m)))
+                .collect::<Vec<_>>()
+        };
+        let to_parts = |lines: &[EsriLineString<N>]| {
+            if lines.is_empty() || lines.iter().any(|l| l.0.len() < 2) {
+                return Err(ShapefileError::EmptyGeometry);
+            }
+            Ok(lines.iter().map(to_points).collect::<Vec<_>>())
+        };
+        let to_rings = |rings: &[EsriLineString<N>]| {
+            if rings.is_empty() || rings.iter().any(|r| r.0.len() < 3) {
+                return Err(ShapefileError::EmptyGeometry);
+            }
+            let rings = rings
+                .iter()
+                .map(|ring| match is_clockwise(ring) {
+                    true => PolygonRing::Outer(to_points(ring)),
+                    false => PolygonRing::Inner(to_points(ring)),
+                })
+                .collect::<Vec<_>>();
+            Ok($polygon::with_rings(rings))
+        };
+
+        let shape: Shape = match $geom {
+            EsriGeometry::Point(pt) => to_point(point_xyzm(pt)).into(),
+            EsriGeometry::MultiPoint(mp) if mp.points.is_empty() => {
+                return Err(ShapefileError::EmptyGeometry)
+            }
+            EsriGeometry::MultiPoint(mp) => $multipoint::new(to_points(&EsriLineString(
+                mp.points.clone(),
+            )))
+            .into(),
+            EsriGeometry::Polyline(pl) => $polyline::with_parts(to_parts(&pl.paths)?).into(),
+            EsriGeometry::CurvePolyline(pl) => {
+                $polyline::with_parts(to_parts(&pl.linearize().paths)?).into()
+            }
+            EsriGeometry::Polygon(ply) => to_rings(&ply.rings)?.into(),
+            EsriGeometry::CurvePolygon(ply) => to_rings(&ply.linearize().rings)?.into(),
+            EsriGeometry::Envelope(e) => to_rings(&[envelope_ring(e)])?.into(),
+            EsriGeometry::Multipatch(mp) => multipatch_shape(mp, $has_m)?,
+        };
+        shape
+    }};
+}
+
+// converts a geometry into the shape type for the given dimensions
+fn to_shape<const N: usize>(
+    geom: &EsriGeometry<N>,
+    has_z: bool,
+    has_m: bool,
+) -> Result<Shape, ShapefileError> {
+    let shape = match (has_z, has_m) {
+        (true, _) => esri_to_shape!(
+            geom,
+            has_z,
+            has_m,
+            MultipointZ,
+            PolylineZ,
+            PolygonZ,
+            |x, y, z, m| PointZ::new(x, y, z, m)
+        ),
+        (false, true) => esri_to_shape!(
+            geom,
+            has_z,
+            has_m,
+            MultipointM,
+            PolylineM,
+            PolygonM,
+            |x, y, _z, m| PointM::new(x, y, m)
+        ),
+        (false, false) => esri_to_shape!(
+            geom,
+            has_z,
+            has_m,
+            Multipoint,
+            Polyline,
+            Polygon,
+            |x, y, _z, _m| Point::new(x, y)
+        ),
+    };
+
+    Ok(shape)
+}
+
+// the dimensions of a geometry, from the presence of z and m for points
+fn geometry_dimensions<const N: usize>(geom: &EsriGeometry<N>) -> (bool, bool) {
+    let (has_z, has_m) = match geom {
+        EsriGeometry::Point(pt) => return (pt.z.is_some(), pt.m.is_some()),
+        EsriGeometry::MultiPoint(mp) => (mp.hasZ, mp.hasM),
+        EsriGeometry::Polyline(pl) => (pl.hasZ, pl.hasM),
+        EsriGeometry::Polygon(ply) => (ply.hasZ, ply.hasM),
+        EsriGeometry::CurvePolyline(pl) => (pl.hasZ, pl.hasM),
+        EsriGeometry::CurvePolygon(ply) => (ply.hasZ, ply.hasM),
+        EsriGeometry::Multipatch(mp) => (mp.hasZ, mp.hasM),
+        EsriGeometry::Envelope(_) => return (false, false),
+    };
+    let (n_z, n_m) = dimension_flags::<N>();
+    (has_z.unwrap_or(n_z), has_m.unwrap_or(n_m))
+}
+
+impl<const N: usize> TryFrom<&EsriGeometry<N>> for Shape {
+    type Error = ShapefileError;
+
+    /// Converts a geometry into a `*Z` shape if it has z values, a `*M` shape if
+    /// it only has m values, or a 2D shape otherwise.
+    fn try_from(value: &EsriGeometry<N>) -> Result<Self, Self::Error> {
+        let (has_z, has_m) = geometry_dimensions(value);
+        to_shape(value, has_z, has_m)
+    }
+}
+
+/// The Esri `geometryType` that corresponds to a shapefile `ShapeType`
+pub fn esri_geometry_type(x: ShapeType) -> Option<&'static str> {
+    match x {
+        ShapeType::Point | ShapeType::PointM | ShapeType::PointZ => Some("esriGeometryPoint"),
+        ShapeType::Multipoint | ShapeType::MultipointM | ShapeType::MultipointZ => {
+            Some("esriGeometryMultipoint")
+        }
+        ShapeType::Polyline | ShapeType::PolylineM | ShapeType::PolylineZ => {
+            Some("esriGeometryPolyline")
+        }
+        ShapeType::Polygon | ShapeType::PolygonM | ShapeType::PolygonZ => {
+            Some("esriGeometryPolygon")
+        }
+        ShapeType::Multipatch => Some("esriGeometryMultiPatch"),
+        ShapeType::NullShape => None,
+    }
+}
+
+// Attributes
+
+impl From<&FieldInfo> for Field {
+    fn from(value: &FieldInfo) -> Self {
+        let field_type = match value.field_type() {
+            dbase::FieldType::Character | dbase::FieldType::Memo => FieldType::EsriFieldTypeString,
+            dbase::FieldType::Numeric | dbase::FieldType::Double | dbase::FieldType::Currency => {
+                FieldType::EsriFieldTypeDouble
+            }
+            dbase::FieldType::Float => FieldType::EsriFieldTypeSingle,
+            dbase::FieldType::Integer => FieldType::EsriFieldTypeInteger,
+            dbase::FieldType::Logical => FieldType::EsriFieldTypeSmallInteger,
+            dbase::FieldType::Date | dbase::FieldType::DateTime => FieldType::EsriFieldTypeDate,
+        };
+
+        Field {
+            name: value.name().to_string(),
+            field_type,
+            alias: Some(value.name().to_string()),
+            sqlType: None,
+            domain: None,
+            defaultValue: None,
+            extra: Default::default(),
+        }
+    }
+}
+
+/// Converts a dBase value into an Esri JSON attribute value.
+///
+/// Dates are returned as milliseconds since the Unix epoch and logical values as `0` or `1`.
+pub fn field_value_to_json(x: FieldValue) -> Value {
+    match x {
+        FieldValue::Character(s) => s.map_or(Value::Null, Value::from),
+        FieldValue::Memo(s) => Value::from(s),
+        FieldValue::Numeric(n) => n.map_or(Value::Null, Value::from),
+        FieldValue::Float(n) => n.map_or(Value::Null, |n| Value::from(n as f64)),
+        FieldValue::Double(n) | FieldValue::Currency(n) => Value::from(n),
+        FieldValue::Integer(n) => Value::from(n),
+        FieldValue::Logical(b) => b.map_or(Value::Null, |b| Value::from(b as i16)),
+        FieldValue::Date(d) => d.map_or(Value::Null, |d| {
+            Value::from(d.to_unix_days() as i64 * 86_400_000)
+        }),
+        FieldValue::DateTime(dt) => Value::from(dt.to_unix_timestamp() * 1000),
+    }
+}
+
+/// Converts a dBase record into an attribute map
+pub fn record_to_attributes(x: dbase::Record) -> Map<String, Value> {
+    x.into_iter()
+        .map(|(k, v)| (k, field_value_to_json(v)))
+        .collect()
+}
+
+// adds the dBase field for an Esri field type, `None` for types that cannot be written
+type AddField = fn(dbase::TableWriterBuilder, dbase::FieldName) -> dbase::TableWriterBuilder;
+
+fn dbase_field(field_type: &FieldType) -> Option<AddField> {
+    let add: AddField = match field_type {
+        FieldType::EsriFieldTypeString
+        | FieldType::EsriFieldTypeGuid
+        | FieldType::EsriFieldTypeGlobalId
+        | FieldType::EsriFieldTypeXml
+        | FieldType::EsriFieldTypeDateOnly
+        | FieldType::EsriFieldTypeTimeOnly
+        | FieldType::EsriFieldTypeTimestampOffset => |b, name| b.add_character_field(name, 254),
+        FieldType::EsriFieldTypeSmallInteger
+        | FieldType::EsriFieldTypeInteger
+        | FieldType::EsriFieldTypeOid => |b, name| b.add_numeric_field(name, 18, 0),
+        FieldType::EsriFieldTypeBigInteger => |b, name| b.add_numeric_field(name, 20, 0),
+        FieldType::EsriFieldTypeSingle | FieldType::EsriFieldTypeDouble => {
+            |b, name| b.add_numeric_field(name, 24, 15)
+        }
+        FieldType::EsriFieldTypeDate => |b, name| b.add_date_field(name),
+        FieldType::EsriFieldTypeGeometry
+        | FieldType::EsriFieldTypeBlob
+        | FieldType::EsriFieldTypeRaster => return None,
+    };
+    Some(add)
+}
+
+// the civil date of a number of days since the Unix epoch
+fn date_from_unix_days(days: i64) -> Option<dbase::Date> {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    dbase::Date::new(day as u32, month as u32, u32::try_from(year).ok()?).ok()
+}
+
+/// Converts an Esri JSON attribute value into a dBase value for a field of `field_type`.
+///
+/// Dates are read as milliseconds since the Unix epoch. Values that cannot be
+/// represented by the field are written as null.
+pub fn json_to_field_value(x: &Value, field_type: &FieldType) -> FieldValue {
+    let number = match x {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        Value::Bool(b) => Some(f64::from(*b as u8)),
+        _ => None,
+    };
+
+    match field_type {
+        FieldType::EsriFieldTypeDate => FieldValue::Date(
+            crate::date::EsriDate::from_attribute(x)
+                .and_then(|d| date_from_unix_days(d.0.div_euclid(86_400_000))),
+        ),
+        FieldType::EsriFieldTypeSmallInteger
+        | FieldType::EsriFieldTypeInteger
+        | FieldType::EsriFieldTypeOid
+        | FieldType::EsriFieldTypeSingle
+        | FieldType::EsriFieldTypeDouble => FieldValue::Numeric(number),
+        _ => FieldValue::Character(match x {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            x => Some(x.to_string()),
+        }),
+    }
+}
+
+// the names of the `Numeric` fields without decimal places, which the dBase reader
+// does not expose, from the field descriptors of a dBase file
+fn integer_fields(path: &Path) -> std::io::Result<HashSet<String>> {
+    let mut file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut descriptor = [0u8; 32];
+    file.read_exact(&mut descriptor)?;
+
+    let mut names = HashSet::new();
+    loop {
+        // the descriptors end with 0x0D
+        file.read_exact(&mut descriptor[..1])?;
+        if descriptor[0] == 0x0D {
+            return Ok(names);
+        }
+        file.read_exact(&mut descriptor[1..])?;
+        if descriptor[11] == b'N' && descriptor[17] == 0 {
+            let name = descriptor[..11]
+                .split(|b| *b == 0)
+                .next()
+                .unwrap_or_default();
+            names.insert(String::from_utf8_lossy(name).into_owned());
+        }
+    }
+}
+
+/// Read a shapefile into a `FeatureSet`.
+///
+/// The `.dbf` file is required and its fields are used to populate `fields`.
+/// If a `.prj` file is present its WKT is used as the `spatialReference`.
+/// Null shapes result in a feature without a geometry.
+pub fn read_shapefile<const N: usize, P: AsRef<Path>>(
+    path: P,
+) -> Result<FeatureSet<N>, ShapefileError> {
+    let path = path.as_ref();
+    let shape_reader = ShapeReader::from_path(path)?;
+    let dbase_reader = dbase::Reader::from_path(path.with_extension("dbf"))?;
+    let integers = integer_fields(&path.with_extension("dbf"))?;
+
+    let fields = dbase_reader
+        .fields()
+        .iter()
+        .filter(|f| f.name() != "DeletionFlag")
+        .map(|f| match integers.contains(f.name()) {
+            true => Field {
+                field_type: FieldType::EsriFieldTypeInteger,
+                ..Field::from(f)
+            },
+            false => Field::from(f),
+        })
+        .collect::<Vec<_>>();
+
+    let mut reader = shapefile::Reader::new(shape_reader, dbase_reader);
+    let shape_type = reader.header().shape_type;
+
+    let features = reader
+        .iter_shapes_and_records()
+        .map(|res| {
+            let (shape, record) = res?;
+            let geometry = match shape {
+                Shape::NullShape => None,
+                shape => Some(EsriGeometry::try_from(&shape)?),
+            };
+
+            let mut attributes = record_to_attributes(record);
+            for name in &integers {
+                if let Some(value) = attributes.get_mut(name) {
+                    if let Some(n) = value.as_f64() {
+                        *value = Value::from(n as i64);
+                    }
+                }
+            }
+
+            Ok(Feature {
+                geometry,
+                attributes: Some(attributes),
+                #[cfg(feature = "preserve-unknown")]
+                extra: Default::default(),
+            })
+        })
+        .collect::<Result<Vec<_>, ShapefileError>>()?;
+
+    let spatial_reference = std::fs::read_to_string(path.with_extension("prj"))
+        .ok()
+        .map(|wkt| SpatialReference {
+            wkid: None,
+            latest_wkid: None,
+            vcs_wkid: None,
+            latest_vcs_wkid: None,
+            wkt: Some(wkt.trim().to_string()),
+        });
+
+    let (has_z, has_m) = dimension_flags::<N>();
+
+    Ok(FeatureSet {
+        objectIdFieldName: None,
+        globalIdFieldName: None,
+        displayFieldName: None,
+        geometryType: esri_geometry_type(shape_type).map(String::from),
+        spatialReference: spatial_reference,
+        hasZ: Some(has_z),
+        hasM: Some(has_m),
+        fields: Some(fields),
+        features,
+        exceededTransferLimit: None,
+        #[cfg(feature = "preserve-unknown")]
+        extra: Default::default(),
+    })
+}
+
+// A null record in a shapefile of shapes `S`.
+//
+// The shapefile writer only writes records of the shape type of the file, so
+// null records are written as records of `S` without content, and their shape
+// type is set to null once the file is written.
+struct NullRecord<S>(PhantomData<S>);
+
+// the shape type of a shapefile of null shapes
+struct NullShape;
+
+impl HasShapeType for NullShape {
+    fn shapetype() -> ShapeType {
+        ShapeType::NullShape
+    }
+}
+
+impl<S: HasShapeType> HasShapeType for NullRecord<S> {
+    fn shapetype() -> ShapeType {
+        S::shapetype()
+    }
+}
+
+impl<S> WritableShape for NullRecord<S> {
+    fn size_in_bytes(&self) -> usize {
+        0
+    }
+
+    fn write_to<T: Write>(&self, _: &mut T) -> Result<(), shapefile::Error> {
+        Ok(())
+    }
+}
+
+// empty ranges which leave the bounding box of the file unchanged
+impl<S: HasShapeType> EsriShape for NullRecord<S> {
+    fn x_range(&self) -> [f64; 2] {
+        [f64::MAX, f64::MIN]
+    }
+    fn y_range(&self) -> [f64; 2] {
+        [f64::MAX, f64::MIN]
+    }
+    fn z_range(&self) -> [f64; 2] {
+        [f64::MAX, f64::MIN]
+    }
+    fn m_range(&self) -> [f64; 2] {
+        [f64::MAX, f64::MIN]
+    }
+}
+
+fn write_null<S: HasShapeType>(
+    writer: &mut shapefile::Writer<std::io::BufWriter<std::fs::File>>,
+    record: &dbase::Record,
+) -> Result<(), shapefile::Error> {
+    writer.write_shape_and_record(&NullRecord::<S>(PhantomData), record)
+}
+
+// sets the shape type of the records `nulls` of the shapefile at `path` to null
+fn set_null_records(path: &Path, nulls: &[usize]) -> std::io::Result<()> {
+    if nulls.is_empty() {
+        return Ok(());
+    }
+
+    let index = std::fs::read(path.with_extension("shx"))?;
+    let mut shp = std::fs::OpenOptions::new().write(true).open(path)?;
+    for i in nulls {
+        // the offset of the record in 16-bit words, followed by its header
+        let entry = 100 + 8 * i;
+        let Some(offset) = index.get(entry..entry + 4) else {
+            continue;
+        };
+        let offset = u32::from_be_bytes([offset[0], offset[1], offset[2], offset[3]]);
+        shp.seek(SeekFrom::Start(u64::from(offset) * 2 + 8))?;
+        shp.write_all(&0_i32.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write a `FeatureSet` to a shapefile.
+///
+/// The `.shp`, `.shx`, and `.dbf` files are created next to `path`, and a `.prj`
+/// file when the `spatialReference` has a WKT. Fields are inferred from the
+/// attributes when `fields` is missing.
+///
+/// Every geometry is written with the dimensions of the `FeatureSet`, from
+/// `hasZ` and `hasM` or `N`. Shapefiles have a single shape type, so the
+/// geometries must all be of the same type. Features without a geometry are
+/// written as null shapes.
+///
+/// ```
+/// use serde_esri::{features::FeatureSet, shapefile::{read_shapefile, write_shapefile}};
+///
+/// let fset: FeatureSet<2> = serde_json::from_str(r#"{
+///     "geometryType": "esriGeometryPoint",
+///     "features": [{"geometry": {"x": 1, "y": 2}, "attributes": {"name": "a"}}]
+/// }"#).unwrap();
+///
+/// let dir = std::env::temp_dir().join(format!("serde_esri_doctest_{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let path = dir.join("points.shp");
+/// write_shapefile(&fset, &path).unwrap();
+///
+/// let read: FeatureSet<2> = read_shapefile(&path).unwrap();
+/// assert_eq!(read.features[0].attributes.as_ref().unwrap()["name"], "a");
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn write_shapefile<const N: usize, P: AsRef<Path>>(
+    x: &FeatureSet<N>,
+    path: P,
+) -> Result<(), ShapefileError> {
+    let path = path.as_ref();
+    let fields = match &x.fields {
+        Some(fields) => fields.clone(),
+        None => infer_fields(&x.features),
+    };
+
+    // dBase field names are at most 10 characters
+    let mut names = HashSet::new();
+    let mut columns = Vec::new();
+    let mut builder = dbase::TableWriterBuilder::new();
+    for field in &fields {
+        let Some(add_field) = dbase_field(&field.field_type) else {
+            continue;
+        };
+        // keep whole characters within the 10 bytes of a dBase field name
+        let mut name = String::new();
+        for c in field.name.chars() {
+            if name.len() + c.len_utf8() > 10 {
+                break;
+            }
+            name.push(c);
+        }
+        if !names.insert(name.clone()) {
+            return Err(ShapefileError::DuplicateFieldName(name));
+        }
+        let field_name = dbase::FieldName::try_from(name.as_str())
+            .map_err(|_| ShapefileError::InvalidFieldName(name.clone()))?;
+        builder = add_field(builder, field_name);
+        columns.push((field, name));
+    }
+
+    let (n_z, n_m) = dimension_flags::<N>();
+    let has_z = x.hasZ.unwrap_or(n_z);
+    let has_m = x.hasM.unwrap_or(n_m);
+
+    let shapes = x
+        .features
+        .iter()
+        .map(|f| f.geometry.as_ref().map(|g| to_shape(g, has_z, has_m)))
+        .map(Option::transpose)
+        .collect::<Result<Vec<_>, _>>()?;
+    let shape_type = shapes
+        .iter()
+        .flatten()
+        .next()
+        .map_or(ShapeType::NullShape, Shape::shapetype);
+
+    let mut writer = shapefile::Writer::from_path(path, builder)?;
+    let mut nulls = Vec::new();
+    for (i, (feature, shape)) in x.features.iter().zip(shapes).enumerate() {
+        let mut record = dbase::Record::default();
+        for (field, name) in &columns {
+            let value = feature
+                .attributes
+                .as_ref()
+                .and_then(|a| a.get(&field.name))
+                .unwrap_or(&Value::Null);
+            record.insert(name.clone(), json_to_field_value(value, &field.field_type));
+        }
+
+        match shape {
+            Some(Shape::Point(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::PointM(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::PointZ(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::Multipoint(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::MultipointM(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::MultipointZ(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::Polyline(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::PolylineM(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::PolylineZ(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::Polygon(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::PolygonM(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::PolygonZ(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::Multipatch(s)) => writer.write_shape_and_record(&s, &record)?,
+            Some(Shape::NullShape) | None => {
+                nulls.push(i);
+                match shape_type {
+                    ShapeType::Point => write_null::<Point>(&mut writer, &record)?,
+                    ShapeType::PointM => write_null::<PointM>(&mut writer, &record)?,
+                    ShapeType::PointZ => write_null::<PointZ>(&mut writer, &record)?,
+                    ShapeType::Multipoint => write_null::<Multipoint>(&mut writer, &record)?,
+                    ShapeType::MultipointM => write_null::<MultipointM>(&mut writer, &record)?,
+                    ShapeType::MultipointZ => write_null::<MultipointZ>(&mut writer, &record)?,
+                    ShapeType::Polyline => write_null::<Polyline>(&mut writer, &record)?,
+                    ShapeType::PolylineM => write_null::<PolylineM>(&mut writer, &record)?,
+                    ShapeType::PolylineZ => write_null::<PolylineZ>(&mut writer, &record)?,
+                    ShapeType::Polygon => write_null::<Polygon>(&mut writer, &record)?,
+                    ShapeType::PolygonM => write_null::<PolygonM>(&mut writer, &record)?,
+                    ShapeType::PolygonZ => write_null::<PolygonZ>(&mut writer, &record)?,
+                    ShapeType::Multipatch => write_null::<Multipatch>(&mut writer, &record)?,
+                    ShapeType::NullShape => write_null::<NullShape>(&mut writer, &record)?,
+                }
+            }
+        }
+    }
+
+    // the headers are written once the writer is dropped
+    drop(writer);
+    set_null_records(path, &nulls)?;
+
+    if let Some(wkt) = x.spatialReference.as_ref().and_then(|sr| sr.wkt.as_ref()) {
+        std::fs::write(path.with_extension("prj"), wkt)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shapefile_round_trip() {
+        let fset: FeatureSet<3> = serde_json::from_str(
+            r#"{
+                "geometryType": "esriGeometryPolygon",
+                "hasZ": true,
+                "spatialReference": {"wkt": "GEOGCS[\"WGS 84\"]"},
+                "fields": [
+                    {"name": "population", "type": "esriFieldTypeInteger"},
+                    {"name": "founded", "type": "esriFieldTypeDate"},
+                    {"name": "shape", "type": "esriFieldTypeGeometry"}
+                ],
+                "features": [{
+                    "geometry": {"rings": [
+                        [[0, 0, 1], [0, 10, 1], [10, 10, 1], [10, 0, 1], [0, 0, 1]],
+                        [[4, 4, 2], [6, 4, 2], [6, 6, 2], [4, 6, 2], [4, 4, 2]]
+                    ]},
+                    "attributes": {"population": 42, "founded": 951782400000}
+                }, {
+                    "attributes": {"population": 7}
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let dir = std::env::temp_dir().join(format!(
+            "serde_esri_shapefile_round_trip_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("round_trip.shp");
+        write_shapefile(&fset, &path).unwrap();
+        let read: FeatureSet<3> = read_shapefile(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(read.geometryType.as_deref(), Some("esriGeometryPolygon"));
+        assert_eq!(
+            read.spatialReference.unwrap().wkt.as_deref(),
+            Some("GEOGCS[\"WGS 84\"]")
+        );
+        assert_eq!(read.fields.as_ref().unwrap().len(), 2);
+
+        let attributes = read.features[0].attributes.as_ref().unwrap();
+        assert_eq!(attributes["population"], 42);
+        let population = &read.fields.as_ref().unwrap()[0];
+        assert!(matches!(
+            population.field_type,
+            FieldType::EsriFieldTypeInteger
+        ));
+        assert_eq!(attributes["founded"], 951782400000_i64);
+
+        let Some(EsriGeometry::Polygon(ply)) = &read.features[0].geometry else {
+            panic!("expected a polygon");
+        };
+        assert_eq!(ply.rings.len(), 2);
+        assert!(is_clockwise(&ply.rings[0]));
+        assert!(!is_clockwise(&ply.rings[1]));
+        assert_eq!(ply.rings[1].0[0].0[2], 2.0);
+
+        // features without a geometry are null shapes
+        assert!(read.features[1].geometry.is_none());
+        assert_eq!(
+            read.features[1].attributes.as_ref().unwrap()["population"],
+            7
+        );
+    }
+}
@@ -1,14 +1,50 @@
 //! Represents a spatial reference
+//!
+//! Common WKIDs are described by [`WKIDS`] with their WKT and units. Spatial references
+//! compare equal when they describe the same coordinate system, so that the Esri WKID
+//! 102100 is equal to 3857. With the `proj` feature, geometries and feature sets can be
+//! reprojected between them with `reproject()`.
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
+#[cfg(feature = "proj")]
+mod reproject;
+#[cfg(feature = "proj")]
+pub use reproject::*;
+
 /// Read more on [Esri docs site](https://developers.arcgis.com/documentation/common-data-types/geometry-objects.htm#GUID-DFF0E738-5A42-40BC-A811-ACCB5814BABC)
+///
+/// ```
+/// use serde_esri::spatial_reference::{SpatialReference, Unit};
+///
+/// let web_mercator: SpatialReference = serde_json::from_str(r#"{"wkid": 102100, "latestWkid": 3857}"#).unwrap();
+/// assert_eq!(web_mercator, SpatialReference::from_wkid(3857));
+/// assert!(web_mercator.is_projected());
+/// assert_eq!(web_mercator.unit(), Some(Unit::Meter));
+/// assert!(SpatialReference::from_wkid(4326).is_geographic());
+/// ```
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SpatialReference {
+    #[serde(default, deserialize_with = "crate::de::lenient::option")]
     pub wkid: Option<u32>,
+    #[serde(
+        default,
+        alias = "latestWkid",
+        deserialize_with = "crate::de::lenient::option"
+    )]
     pub latest_wkid: Option<u32>,
+    #[serde(
+        default,
+        alias = "vcsWkid",
+        deserialize_with = "crate::de::lenient::option"
+    )]
     pub vcs_wkid: Option<u32>,
+    #[serde(
+        default,
+        alias = "latestVcsWkid",
+        deserialize_with = "crate::de::lenient::option"
+    )]
     pub latest_vcs_wkid: Option<u32>,
     pub wkt: Option<String>,
 }
@@ -24,3 +60,207 @@ impl Default for SpatialReference {
         }
     }
 }
+
+/// The unit of the coordinates of a spatial reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Degree,
+    Meter,
+    Foot,
+    UsSurveyFoot,
+}
+
+impl Unit {
+    /// The length of the unit in meters, or `None` for angular units
+    pub fn meters(&self) -> Option<f64> {
+        match self {
+            Unit::Degree => None,
+            Unit::Meter => Some(1.0),
+            Unit::Foot => Some(0.3048),
+            Unit::UsSurveyFoot => Some(1200.0 / 3937.0),
+        }
+    }
+}
+
+/// A well-known spatial reference
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WkidInfo {
+    pub wkid: u32,
+    pub name: &'static str,
+    pub unit: Unit,
+    /// The WKT of the spatial reference as written by ArcGIS
+    pub wkt: &'static str,
+}
+
+impl WkidInfo {
+    /// Whether coordinates are longitudes and latitudes
+    pub fn is_geographic(&self) -> bool {
+        self.unit == Unit::Degree
+    }
+}
+
+const GCS_WGS_1984: &str = r#"GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]]"#;
+const GCS_NAD_1983: &str = r#"GEOGCS["GCS_North_American_1983",DATUM["D_North_American_1983",SPHEROID["GRS_1980",6378137.0,298.257222101]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]]"#;
+const WEB_MERCATOR: &str = r#"PROJCS["WGS_1984_Web_Mercator_Auxiliary_Sphere",GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Mercator_Auxiliary_Sphere"],PARAMETER["False_Easting",0.0],PARAMETER["False_Northing",0.0],PARAMETER["Central_Meridian",0.0],PARAMETER["Standard_Parallel_1",0.0],PARAMETER["Auxiliary_Sphere_Type",0.0],UNIT["Meter",1.0]]"#;
+
+/// Common spatial references
+///
+/// UTM zones on WGS 84 (326xx and 327xx) are not listed but are recognized as projected
+/// in meters by [`SpatialReference`].
+pub const WKIDS: &[WkidInfo] = &[
+    WkidInfo {
+        wkid: 4326,
+        name: "GCS_WGS_1984",
+        unit: Unit::Degree,
+        wkt: GCS_WGS_1984,
+    },
+    WkidInfo {
+        wkid: 4269,
+        name: "GCS_North_American_1983",
+        unit: Unit::Degree,
+        wkt: GCS_NAD_1983,
+    },
+    WkidInfo {
+        wkid: 4258,
+        name: "GCS_ETRS_1989",
+        unit: Unit::Degree,
+        wkt: r#"GEOGCS["GCS_ETRS_1989",DATUM["D_ETRS_1989",SPHEROID["GRS_1980",6378137.0,298.257222101]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]]"#,
+    },
+    WkidInfo {
+        wkid: 3857,
+        name: "WGS_1984_Web_Mercator_Auxiliary_Sphere",
+        unit: Unit::Meter,
+        wkt: WEB_MERCATOR,
+    },
+    WkidInfo {
+        wkid: 27700,
+        name: "British_National_Grid",
+        unit: Unit::Meter,
+        wkt: r#"PROJCS["British_National_Grid",GEOGCS["GCS_OSGB_1936",DATUM["D_OSGB_1936",SPHEROID["Airy_1830",6377563.396,299.3249646]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Transverse_Mercator"],PARAMETER["False_Easting",400000.0],PARAMETER["False_Northing",-100000.0],PARAMETER["Central_Meridian",-2.0],PARAMETER["Scale_Factor",0.9996012717],PARAMETER["Latitude_Of_Origin",49.0],UNIT["Meter",1.0]]"#,
+    },
+    WkidInfo {
+        wkid: 2263,
+        name: "NAD_1983_StatePlane_New_York_Long_Island_FIPS_3104_Feet",
+        unit: Unit::UsSurveyFoot,
+        wkt: r#"PROJCS["NAD_1983_StatePlane_New_York_Long_Island_FIPS_3104_Feet",GEOGCS["GCS_North_American_1983",DATUM["D_North_American_1983",SPHEROID["GRS_1980",6378137.0,298.257222101]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Lambert_Conformal_Conic"],PARAMETER["False_Easting",984250.0],PARAMETER["False_Northing",0.0],PARAMETER["Central_Meridian",-74.0],PARAMETER["Standard_Parallel_1",40.66666666666666],PARAMETER["Standard_Parallel_2",41.03333333333333],PARAMETER["Latitude_Of_Origin",40.16666666666666],UNIT["Foot_US",0.3048006096012192]]"#,
+    },
+];
+
+/// The information on a common WKID, see [`WKIDS`]
+///
+/// Esri's deprecated WKIDs for Web Mercator, such as 102100, are looked up as 3857.
+pub fn wkid_info(wkid: u32) -> Option<&'static WkidInfo> {
+    let wkid = normalize_wkid(wkid);
+    WKIDS.iter().find(|info| info.wkid == wkid)
+}
+
+// the WKID of Web Mercator for its aliases
+fn normalize_wkid(wkid: u32) -> u32 {
+    match wkid {
+        102100 | 102113 | 900913 | 3785 => 3857,
+        wkid => wkid,
+    }
+}
+
+// the zone of a UTM zone on WGS 84 and whether it is on the southern hemisphere
+pub(crate) fn utm_zone(wkid: u32) -> Option<(u32, bool)> {
+    match wkid {
+        32601..=32660 => Some((wkid - 32600, false)),
+        32701..=32760 => Some((wkid - 32700, true)),
+        _ => None,
+    }
+}
+
+impl SpatialReference {
+    pub fn from_wkid(wkid: u32) -> Self {
+        Self {
+            wkid: Some(wkid),
+            latest_wkid: None,
+            vcs_wkid: None,
+            latest_vcs_wkid: None,
+            wkt: None,
+        }
+    }
+
+    pub fn from_wkt<S: Into<String>>(wkt: S) -> Self {
+        Self {
+            wkid: None,
+            latest_wkid: None,
+            vcs_wkid: None,
+            latest_vcs_wkid: None,
+            wkt: Some(wkt.into()),
+        }
+    }
+
+    /// The latest WKID of the horizontal coordinate system, with the aliases of
+    /// Web Mercator as 3857
+    pub fn normalized_wkid(&self) -> Option<u32> {
+        self.latest_wkid.or(self.wkid).map(normalize_wkid)
+    }
+
+    /// The information on the spatial reference if it is one of [`WKIDS`]
+    pub fn info(&self) -> Option<&'static WkidInfo> {
+        self.normalized_wkid().and_then(wkid_info)
+    }
+
+    /// The WKT of the spatial reference, either its own or that of its WKID
+    pub fn wkt(&self) -> Option<&str> {
+        self.wkt.as_deref().or(self.info().map(|info| info.wkt))
+    }
+
+    /// Whether coordinates are longitudes and latitudes
+    ///
+    /// This is `false` when the spatial reference is neither a known WKID nor has a WKT.
+    pub fn is_geographic(&self) -> bool {
+        match self.info() {
+            Some(info) => info.is_geographic(),
+            None => self.wkt_starts_with("GEOGCS"),
+        }
+    }
+
+    /// Whether coordinates are projected
+    ///
+    /// This is `false` when the spatial reference is neither a known WKID nor has a WKT.
+    pub fn is_projected(&self) -> bool {
+        match self.info() {
+            Some(info) => !info.is_geographic(),
+            None => {
+                self.normalized_wkid().and_then(utm_zone).is_some()
+                    || self.wkt_starts_with("PROJCS")
+            }
+        }
+    }
+
+    /// The unit of the coordinates, if known
+    pub fn unit(&self) -> Option<Unit> {
+        match self.info() {
+            Some(info) => Some(info.unit),
+            None => self
+                .normalized_wkid()
+                .and_then(utm_zone)
+                .map(|_| Unit::Meter),
+        }
+    }
+
+    fn wkt_starts_with(&self, prefix: &str) -> bool {
+        self.wkt
+            .as_deref()
+            .is_some_and(|wkt| wkt.trim_start().starts_with(prefix))
+    }
+}
+
+/// Spatial references are equal when their normalized WKIDs are, or without WKIDs,
+/// when their WKTs are. Vertical coordinate systems are compared by their latest WKID.
+impl PartialEq for SpatialReference {
+    fn eq(&self, other: &Self) -> bool {
+        let horizontal = match (self.normalized_wkid(), other.normalized_wkid()) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => {
+                self.wkt.as_deref().map(str::trim) == other.wkt.as_deref().map(str::trim)
+            }
+            _ => false,
+        };
+        horizontal
+            && self.latest_vcs_wkid.or(self.vcs_wkid) == other.latest_vcs_wkid.or(other.vcs_wkid)
+    }
+}
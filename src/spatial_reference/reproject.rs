@@ -0,0 +1,367 @@
+// Reprojection of coordinates between common spatial references, without PROJ
+//
+// Geographic coordinates on WGS 84, NAD 83 and ETRS 89 are treated as the same as their
+// datums differ by less than 2 meters. They are projected to Web Mercator, and to the
+// UTM zones of WGS 84 with the Krüger series of the transverse Mercator projection.
+use super::{utm_zone, SpatialReference};
+use crate::features::FeatureSet;
+use crate::geometry::{EsriCoord, EsriEnvelope, EsriGeometry};
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+// the semi-major axis and flattening of WGS 84
+const A: f64 = 6378137.0;
+const F: f64 = 1.0 / 298.257223563;
+// the latitude at which Web Mercator is square
+const MAX_MERCATOR_LAT: f64 = 85.051_128_779_806_59;
+
+/// An error reprojecting coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReprojectError {
+    /// The geometry or feature set has no spatial reference to reproject from
+    MissingSpatialReference,
+    /// There is no transform from or to the spatial reference
+    Unsupported(SpatialReference),
+    /// Curves are not reprojected, densify them first
+    CurvedGeometry,
+}
+
+impl std::fmt::Display for ReprojectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingSpatialReference => write!(f, "missing spatial reference"),
+            Self::Unsupported(sr) => match sr.normalized_wkid() {
+                Some(wkid) => write!(f, "cannot reproject from or to WKID {wkid}"),
+                None => write!(
+                    f,
+                    "cannot reproject from or to a spatial reference without a WKID"
+                ),
+            },
+            Self::CurvedGeometry => write!(f, "curves must be densified before reprojecting"),
+        }
+    }
+}
+
+impl std::error::Error for ReprojectError {}
+
+// a coordinate system with a transform to and from longitudes and latitudes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Crs {
+    Geographic,
+    WebMercator,
+    Utm { zone: u32, south: bool },
+}
+
+impl Crs {
+    fn of(sr: &SpatialReference) -> Result<Self, ReprojectError> {
+        let wkid = sr.normalized_wkid();
+        match wkid {
+            Some(4326 | 4269 | 4258) => Ok(Crs::Geographic),
+            Some(3857) => Ok(Crs::WebMercator),
+            _ => wkid
+                .and_then(utm_zone)
+                .map(|(zone, south)| Crs::Utm { zone, south })
+                .ok_or_else(|| ReprojectError::Unsupported(sr.clone())),
+        }
+    }
+
+    fn unproject(self, x: f64, y: f64) -> (f64, f64) {
+        match self {
+            Crs::Geographic => (x, y),
+            Crs::WebMercator => (
+                (x / A).to_degrees(),
+                (2.0 * (y / A).exp().atan() - FRAC_PI_2).to_degrees(),
+            ),
+            Crs::Utm { zone, south } => {
+                let n0 = if south { 10_000_000.0 } else { 0.0 };
+                TransverseMercator::utm(zone).inverse(x - 500_000.0, y - n0)
+            }
+        }
+    }
+
+    fn project(self, lon: f64, lat: f64) -> (f64, f64) {
+        match self {
+            Crs::Geographic => (lon, lat),
+            Crs::WebMercator => {
+                let lat = lat.clamp(-MAX_MERCATOR_LAT, MAX_MERCATOR_LAT).to_radians();
+                (A * lon.to_radians(), A * (FRAC_PI_4 + lat / 2.0).tan().ln())
+            }
+            Crs::Utm { zone, south } => {
+                let n0 = if south { 10_000_000.0 } else { 0.0 };
+                let (x, y) = TransverseMercator::utm(zone).forward(lon, lat);
+                (x + 500_000.0, y + n0)
+            }
+        }
+    }
+}
+
+// the transverse Mercator projection on WGS 84 to the third order of the third flattening
+struct TransverseMercator {
+    central_meridian: f64,
+    scale: f64,
+}
+
+impl TransverseMercator {
+    fn utm(zone: u32) -> Self {
+        Self {
+            central_meridian: zone as f64 * 6.0 - 183.0,
+            scale: 0.9996,
+        }
+    }
+
+    fn n() -> f64 {
+        F / (2.0 - F)
+    }
+
+    // the radius of the rectifying sphere times the scale
+    fn radius(&self) -> f64 {
+        let n = Self::n();
+        self.scale * A / (1.0 + n) * (1.0 + n * n / 4.0 + n.powi(4) / 64.0)
+    }
+
+    fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let n = Self::n();
+        let alpha = [
+            n / 2.0 - 2.0 * n * n / 3.0 + 5.0 * n.powi(3) / 16.0,
+            13.0 * n * n / 48.0 - 3.0 * n.powi(3) / 5.0,
+            61.0 * n.powi(3) / 240.0,
+        ];
+        let e = (F * (2.0 - F)).sqrt();
+
+        let (phi, lambda) = (lat.to_radians(), (lon - self.central_meridian).to_radians());
+        let t = (phi.sin().atanh() - e * (e * phi.sin()).atanh()).sinh();
+        let xi = t.atan2(lambda.cos());
+        let eta = (lambda.sin() / (1.0 + t * t).sqrt()).atanh();
+
+        let (mut x, mut y) = (eta, xi);
+        for (j, a) in alpha.iter().enumerate() {
+            let k = 2.0 * (j + 1) as f64;
+            x += a * (k * xi).cos() * (k * eta).sinh();
+            y += a * (k * xi).sin() * (k * eta).cosh();
+        }
+        (self.radius() * x, self.radius() * y)
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let n = Self::n();
+        let beta = [
+            n / 2.0 - 2.0 * n * n / 3.0 + 37.0 * n.powi(3) / 96.0,
+            n * n / 48.0 + n.powi(3) / 15.0,
+            17.0 * n.powi(3) / 480.0,
+        ];
+        let delta = [
+            2.0 * n - 2.0 * n * n / 3.0 - 2.0 * n.powi(3),
+            7.0 * n * n / 3.0 - 8.0 * n.powi(3) / 5.0,
+            56.0 * n.powi(3) / 15.0,
+        ];
+
+        let (xi, eta) = (y / self.radius(), x / self.radius());
+        let (mut xi_, mut eta_) = (xi, eta);
+        for (j, b) in beta.iter().enumerate() {
+            let k = 2.0 * (j + 1) as f64;
+            xi_ -= b * (k * xi).sin() * (k * eta).cosh();
+            eta_ -= b * (k * xi).cos() * (k * eta).sinh();
+        }
+
+        let chi = (xi_.sin() / eta_.cosh()).asin();
+        let mut phi = chi;
+        for (j, d) in delta.iter().enumerate() {
+            phi += d * (2.0 * (j + 1) as f64 * chi).sin();
+        }
+        let lambda = eta_.sinh().atan2(xi_.cos());
+        (
+            self.central_meridian + lambda.to_degrees(),
+            phi.to_degrees(),
+        )
+    }
+}
+
+/// A transform of coordinates from one spatial reference to another
+///
+/// Transforms are available between WGS 84 (4326), NAD 83 (4269), ETRS 89 (4258),
+/// Web Mercator (3857 and its aliases), and the UTM zones of WGS 84 (326xx and 327xx).
+/// The geographic coordinate systems are treated as the same, without a datum shift.
+///
+/// ```
+/// use serde_esri::spatial_reference::{SpatialReference, Transform};
+///
+/// let transform = Transform::new(
+///     &SpatialReference::from_wkid(4326),
+///     &SpatialReference::from_wkid(102100),
+/// ).unwrap();
+/// let (x, y) = transform.apply(180.0, 0.0);
+/// assert!((x - 20037508.34).abs() < 0.01 && y.abs() < 1e-6);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    from: Crs,
+    to: Crs,
+}
+
+impl Transform {
+    pub fn new(from: &SpatialReference, to: &SpatialReference) -> Result<Self, ReprojectError> {
+        Ok(Self {
+            from: Crs::of(from)?,
+            to: Crs::of(to)?,
+        })
+    }
+
+    /// Transform an x and y coordinate, or a longitude and latitude
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        if self.from == self.to {
+            return (x, y);
+        }
+        let (lon, lat) = self.from.unproject(x, y);
+        self.to.project(lon, lat)
+    }
+
+    // z and m values are kept
+    fn coords<const N: usize>(&self, coords: &mut [EsriCoord<N>]) {
+        for coord in coords {
+            let (x, y) = self.apply(coord.0[0], coord.0[1]);
+            coord.0[0] = x;
+            coord.0[1] = y;
+        }
+    }
+
+    // the envelope of the transformed corners
+    fn envelope(&self, envelope: &mut EsriEnvelope) {
+        let corners = [
+            (envelope.xmin, envelope.ymin),
+            (envelope.xmin, envelope.ymax),
+            (envelope.xmax, envelope.ymin),
+            (envelope.xmax, envelope.ymax),
+        ]
+        .map(|(x, y)| self.apply(x, y));
+
+        envelope.xmin = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+        envelope.ymin = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+        envelope.xmax = corners
+            .iter()
+            .map(|c| c.0)
+            .fold(f64::NEG_INFINITY, f64::max);
+        envelope.ymax = corners
+            .iter()
+            .map(|c| c.1)
+            .fold(f64::NEG_INFINITY, f64::max);
+    }
+
+    // transforms the geometry, setting its spatial reference to `to` if it has one
+    fn geometry<const N: usize>(
+        &self,
+        mut geometry: EsriGeometry<N>,
+        to: &SpatialReference,
+    ) -> Result<EsriGeometry<N>, ReprojectError> {
+        let sr = match &mut geometry {
+            EsriGeometry::Point(point) => {
+                (point.x, point.y) = self.apply(point.x, point.y);
+                &mut point.spatialReference
+            }
+            EsriGeometry::MultiPoint(mp) => {
+                self.coords(&mut mp.points);
+                &mut mp.spatialReference
+            }
+            EsriGeometry::Polyline(pl) => {
+                pl.paths.iter_mut().for_each(|p| self.coords(&mut p.0));
+                &mut pl.spatialReference
+            }
+            EsriGeometry::Polygon(ply) => {
+                ply.rings.iter_mut().for_each(|r| self.coords(&mut r.0));
+                &mut ply.spatialReference
+            }
+            EsriGeometry::Multipatch(mp) => {
+                mp.rings.iter_mut().for_each(|r| self.coords(&mut r.0));
+                &mut mp.spatialReference
+            }
+            EsriGeometry::Envelope(envelope) => {
+                self.envelope(envelope);
+                &mut envelope.spatialReference
+            }
+            EsriGeometry::CurvePolygon(_) | EsriGeometry::CurvePolyline(_) => {
+                return Err(ReprojectError::CurvedGeometry)
+            }
+        };
+        if sr.is_some() {
+            *sr = Some(to.clone());
+        }
+        Ok(geometry)
+    }
+}
+
+impl<const N: usize> EsriGeometry<N> {
+    /// Transform the coordinates of the geometry from its spatial reference to `to`
+    ///
+    /// See [`Transform`] for the spatial references that are supported. Envelopes become
+    /// the envelope of their transformed corners and curves must be densified first.
+    pub fn reproject(self, to: &SpatialReference) -> Result<Self, ReprojectError> {
+        let from = self
+            .spatial_reference()
+            .ok_or(ReprojectError::MissingSpatialReference)?;
+        Transform::new(from, to)?.geometry(self, to)
+    }
+}
+
+impl<const N: usize, A> FeatureSet<N, A> {
+    /// Transform the geometries of the features from the spatial reference of the feature
+    /// set to `to`
+    ///
+    /// Geometries with their own spatial reference are transformed from it instead.
+    /// See [`EsriGeometry::reproject()`].
+    pub fn reproject(mut self, to: &SpatialReference) -> Result<Self, ReprojectError> {
+        let transform = self
+            .spatialReference
+            .as_ref()
+            .map(|from| Transform::new(from, to))
+            .transpose()?;
+
+        for feature in &mut self.features {
+            let Some(geometry) = feature.geometry.take() else {
+                continue;
+            };
+            let geometry = match (geometry.spatial_reference(), transform) {
+                (Some(_), _) => geometry.reproject(to)?,
+                (None, Some(transform)) => transform.geometry(geometry, to)?,
+                (None, None) => return Err(ReprojectError::MissingSpatialReference),
+            };
+            feature.geometry = Some(geometry);
+        }
+
+        self.spatialReference = Some(to.clone());
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::EsriPoint;
+
+    #[test]
+    fn test_reproject() {
+        let wgs84 = SpatialReference::from_wkid(4326);
+        let utm = SpatialReference::from_wkid(32633);
+
+        // the central meridian of the zone
+        let point = EsriGeometry::<2>::Point(EsriPoint {
+            x: 15.0,
+            y: 0.0,
+            spatialReference: Some(wgs84.clone()),
+            ..Default::default()
+        });
+        let point = point.reproject(&utm).unwrap().as_point().unwrap();
+        assert!((point.x - 500_000.0).abs() < 1e-6 && point.y.abs() < 1e-6);
+        assert_eq!(point.spatialReference, Some(utm.clone()));
+
+        // Berlin, and back to within a centimeter
+        let transform = Transform::new(&wgs84, &utm).unwrap();
+        let (x, y) = transform.apply(13.4050, 52.5200);
+        assert!((x - 391_779.0).abs() < 1.0 && (y - 5_820_072.0).abs() < 1.0);
+        let (lon, lat) = Transform::new(&utm, &wgs84).unwrap().apply(x, y);
+        assert!((lon - 13.4050).abs() < 1e-7 && (lat - 52.5200).abs() < 1e-7);
+
+        let mercator = Transform::new(&wgs84, &SpatialReference::from_wkid(3857)).unwrap();
+        let (_, y) = mercator.apply(0.0, 45.0);
+        assert!((y - 5_621_521.486).abs() < 0.01);
+
+        assert!(Transform::new(&wgs84, &SpatialReference::from_wkid(27700)).is_err());
+    }
+}
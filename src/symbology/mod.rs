@@ -0,0 +1,11 @@
+//! Renderers and symbols of the [`drawingInfo`](https://developers.arcgis.com/web-map-specification/objects/drawingInfo/) of a layer.
+//!
+//! - [`Symbol`] is a simple marker, line, or fill symbol, or a picture marker or fill symbol.
+//! - [`Renderer`] is a simple, unique value, or class breaks renderer.
+//!   [`Renderer::symbol_for()`] resolves the symbol used to draw a feature from its attributes.
+//!
+//! Renderers and symbols of other types are kept as JSON objects.
+mod renderers;
+mod symbols;
+pub use renderers::*;
+pub use symbols::*;
@@ -0,0 +1,290 @@
+use crate::features::Feature;
+use crate::symbology::Symbol;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+
+/// A renderer that draws every feature with the same symbol
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimpleRenderer {
+    pub symbol: Option<Symbol>,
+    pub label: Option<String>,
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The symbol of the features with a value
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UniqueValueInfo {
+    /// The values of the renderer's fields, separated by its `field_delimiter`
+    pub value: String,
+    pub label: Option<String>,
+    pub description: Option<String>,
+    pub symbol: Option<Symbol>,
+}
+
+/// A renderer that draws features with the symbol of the value of up to three fields
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UniqueValueRenderer {
+    pub field1: Option<String>,
+    pub field2: Option<String>,
+    pub field3: Option<String>,
+    /// Separates the values of the fields. Defaults to `,`.
+    pub field_delimiter: Option<String>,
+    pub default_symbol: Option<Symbol>,
+    pub default_label: Option<String>,
+    #[serde(default)]
+    pub unique_value_infos: Vec<UniqueValueInfo>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The symbol of the features with a value up to `class_max_value`
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassBreakInfo {
+    /// Defaults to the maximum of the previous class or the renderer's `min_value`
+    pub class_min_value: Option<f64>,
+    pub class_max_value: f64,
+    pub label: Option<String>,
+    pub description: Option<String>,
+    pub symbol: Option<Symbol>,
+}
+
+/// A renderer that draws features with the symbol of the range of a numeric field
+///
+/// Values can be normalized by another field (`esriNormalizeByField`), as a percentage of
+/// a total (`esriNormalizeByPercentOfTotal`), or by their logarithm (`esriNormalizeByLog`).
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassBreaksRenderer {
+    pub field: Option<String>,
+    pub classification_method: Option<String>,
+    pub normalization_type: Option<String>,
+    pub normalization_field: Option<String>,
+    pub normalization_total: Option<f64>,
+    pub min_value: Option<f64>,
+    pub default_symbol: Option<Symbol>,
+    pub default_label: Option<String>,
+    #[serde(default)]
+    pub class_break_infos: Vec<ClassBreakInfo>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The renderer of a layer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Renderer {
+    #[serde(rename = "simple")]
+    Simple(SimpleRenderer),
+    #[serde(rename = "uniqueValue")]
+    UniqueValue(UniqueValueRenderer),
+    #[serde(rename = "classBreaks")]
+    ClassBreaks(ClassBreaksRenderer),
+    /// A renderer of another type, e.g. a heatmap renderer
+    #[serde(untagged)]
+    Other(Map<String, Value>),
+}
+
+impl Renderer {
+    /// The symbol used to draw a feature with `attributes`
+    ///
+    /// Features without a matching value or class are drawn with the default symbol, if any.
+    /// Returns `None` for renderers of other types, whose values are given by expressions,
+    /// or that do not draw the feature.
+    ///
+    /// ```
+    /// use serde_esri::symbology::{Renderer, Symbol};
+    /// use serde_json::json;
+    ///
+    /// let renderer: Renderer = serde_json::from_value(json!({
+    ///     "type": "uniqueValue",
+    ///     "field1": "kind",
+    ///     "uniqueValueInfos": [{
+    ///         "value": "park",
+    ///         "symbol": {"type": "esriSFS", "style": "esriSFSSolid", "color": [0, 128, 0, 255]}
+    ///     }]
+    /// })).unwrap();
+    ///
+    /// let attributes = json!({"kind": "park"});
+    /// let symbol = renderer.symbol_for(attributes.as_object().unwrap()).unwrap();
+    /// assert_eq!(symbol.color(), Some([0, 128, 0, 255]));
+    /// ```
+    pub fn symbol_for(&self, attributes: &Map<String, Value>) -> Option<&Symbol> {
+        match self {
+            Self::Simple(r) => r.symbol.as_ref(),
+            Self::UniqueValue(r) => r.symbol_for(attributes),
+            Self::ClassBreaks(r) => r.symbol_for(attributes),
+            Self::Other(_) => None,
+        }
+    }
+
+    /// The symbol used to draw `feature`
+    pub fn symbol_for_feature<const N: usize>(&self, feature: &Feature<N>) -> Option<&Symbol> {
+        match &feature.attributes {
+            Some(attributes) => self.symbol_for(attributes),
+            None => self.symbol_for(&Map::new()),
+        }
+    }
+}
+
+impl UniqueValueRenderer {
+    /// The symbol of the value of the renderer's fields in `attributes`
+    pub fn symbol_for(&self, attributes: &Map<String, Value>) -> Option<&Symbol> {
+        let fields = [&self.field1, &self.field2, &self.field3];
+        let values: Vec<String> = fields
+            .into_iter()
+            .flatten()
+            .map(|field| value_string(attributes.get(field)))
+            .collect();
+        if values.is_empty() {
+            return self.default_symbol.as_ref();
+        }
+
+        let key = values.join(self.field_delimiter.as_deref().unwrap_or(","));
+        self.unique_value_infos
+            .iter()
+            .find(|info| info.value == key)
+            .map_or(self.default_symbol.as_ref(), |info| info.symbol.as_ref())
+    }
+}
+
+impl ClassBreaksRenderer {
+    /// The symbol of the class containing the value of the renderer's field in `attributes`
+    ///
+    /// The first class includes its minimum and every class includes its maximum.
+    pub fn symbol_for(&self, attributes: &Map<String, Value>) -> Option<&Symbol> {
+        let value = self.normalized_value(attributes).filter(|x| x.is_finite());
+        let Some(value) = value else {
+            return self.default_symbol.as_ref();
+        };
+
+        let mut min = self.min_value.unwrap_or(f64::NEG_INFINITY);
+        for (i, info) in self.class_break_infos.iter().enumerate() {
+            let min_value = info.class_min_value.unwrap_or(min);
+            let above_min = if i == 0 {
+                value >= min_value
+            } else {
+                value > min_value
+            };
+            if above_min && value <= info.class_max_value {
+                return info.symbol.as_ref();
+            }
+            min = info.class_max_value;
+        }
+
+        self.default_symbol.as_ref()
+    }
+
+    // the value of the field, normalized by the normalization type of the renderer
+    fn normalized_value(&self, attributes: &Map<String, Value>) -> Option<f64> {
+        let value = attributes.get(self.field.as_deref()?)?.as_f64()?;
+
+        match self.normalization_type.as_deref() {
+            Some("esriNormalizeByField") => {
+                let field = self.normalization_field.as_deref()?;
+                let by = attributes.get(field)?.as_f64()?;
+                (by != 0.0).then(|| value / by)
+            }
+            Some("esriNormalizeByPercentOfTotal") => {
+                let total = self.normalization_total?;
+                (total != 0.0).then(|| value / total * 100.0)
+            }
+            Some("esriNormalizeByLog") => Some(value.log10()),
+            _ => Some(value),
+        }
+    }
+}
+
+// an attribute value as written in the `value` of a unique value info
+fn value_string(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "<Null>".into(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => match n.as_f64() {
+            Some(x) if n.is_f64() && x.fract() == 0.0 && x.abs() < 1e15 => (x as i64).to_string(),
+            _ => n.to_string(),
+        },
+        Some(value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn color(symbol: Option<&Symbol>) -> Option<u8> {
+        symbol.and_then(Symbol::color).map(|c| c[0])
+    }
+
+    #[test]
+    fn test_class_breaks() {
+        let sfs = |red: u8| json!({"type": "esriSFS", "style": "esriSFSSolid", "color": [red, 0, 0, 255]});
+        let renderer: Renderer = serde_json::from_value(json!({
+            "type": "classBreaks",
+            "field": "pop",
+            "normalizationType": "esriNormalizeByField",
+            "normalizationField": "area",
+            "minValue": 0,
+            "defaultSymbol": sfs(9),
+            "classBreakInfos": [
+                {"classMaxValue": 10, "symbol": sfs(1)},
+                {"classMaxValue": 100, "symbol": sfs(2)}
+            ]
+        }))
+        .unwrap();
+
+        let attrs = |pop: f64, area: f64| {
+            json!({"pop": pop, "area": area})
+                .as_object()
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(color(renderer.symbol_for(&attrs(0.0, 1.0))), Some(1));
+        assert_eq!(color(renderer.symbol_for(&attrs(100.0, 10.0))), Some(1));
+        assert_eq!(color(renderer.symbol_for(&attrs(101.0, 10.0))), Some(2));
+        assert_eq!(color(renderer.symbol_for(&attrs(1001.0, 10.0))), Some(9));
+        assert_eq!(color(renderer.symbol_for(&attrs(1.0, 0.0))), Some(9));
+
+        // renderers of other types are kept
+        let heatmap = json!({"type": "heatmap", "blurRadius": 10});
+        let renderer: Renderer = serde_json::from_value(heatmap.clone()).unwrap();
+        assert!(matches!(renderer, Renderer::Other(_)));
+        assert_eq!(serde_json::to_value(&renderer).unwrap(), heatmap);
+    }
+
+    #[test]
+    fn test_unique_values() {
+        let renderer = UniqueValueRenderer {
+            field1: Some("code".into()),
+            field2: Some("kind".into()),
+            field_delimiter: Some("|".into()),
+            unique_value_infos: vec![UniqueValueInfo {
+                value: "3|<Null>".into(),
+                symbol: serde_json::from_value(json!({"type": "esriSLS", "color": [3, 0, 0, 255]}))
+                    .unwrap(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let attrs = json!({"code": 3.0, "kind": null});
+        assert_eq!(
+            color(renderer.symbol_for(attrs.as_object().unwrap())),
+            Some(3)
+        );
+        let attrs = json!({"code": 4, "kind": null});
+        assert_eq!(color(renderer.symbol_for(attrs.as_object().unwrap())), None);
+    }
+}
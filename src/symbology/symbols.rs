@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+
+/// A color as `[red, green, blue, alpha]`, each from 0 to 255
+pub type Color = [u8; 4];
+
+/// The shape of a [`SimpleMarkerSymbol`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum SimpleMarkerStyle {
+    #[default]
+    #[serde(rename = "esriSMSCircle")]
+    Circle,
+    #[serde(rename = "esriSMSCross")]
+    Cross,
+    #[serde(rename = "esriSMSDiamond")]
+    Diamond,
+    #[serde(rename = "esriSMSSquare")]
+    Square,
+    #[serde(rename = "esriSMSTriangle")]
+    Triangle,
+    #[serde(rename = "esriSMSX")]
+    X,
+}
+
+/// The dash pattern of a [`SimpleLineSymbol`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum SimpleLineStyle {
+    #[default]
+    #[serde(rename = "esriSLSSolid")]
+    Solid,
+    #[serde(rename = "esriSLSDash")]
+    Dash,
+    #[serde(rename = "esriSLSDashDot")]
+    DashDot,
+    #[serde(rename = "esriSLSDashDotDot")]
+    DashDotDot,
+    #[serde(rename = "esriSLSDot")]
+    Dot,
+    #[serde(rename = "esriSLSLongDash")]
+    LongDash,
+    #[serde(rename = "esriSLSLongDashDot")]
+    LongDashDot,
+    #[serde(rename = "esriSLSShortDash")]
+    ShortDash,
+    #[serde(rename = "esriSLSShortDashDot")]
+    ShortDashDot,
+    #[serde(rename = "esriSLSShortDashDotDot")]
+    ShortDashDotDot,
+    #[serde(rename = "esriSLSShortDot")]
+    ShortDot,
+    #[serde(rename = "esriSLSNull")]
+    Null,
+}
+
+/// The pattern of a [`SimpleFillSymbol`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum SimpleFillStyle {
+    #[default]
+    #[serde(rename = "esriSFSSolid")]
+    Solid,
+    #[serde(rename = "esriSFSBackwardDiagonal")]
+    BackwardDiagonal,
+    #[serde(rename = "esriSFSCross")]
+    Cross,
+    #[serde(rename = "esriSFSDiagonalCross")]
+    DiagonalCross,
+    #[serde(rename = "esriSFSForwardDiagonal")]
+    ForwardDiagonal,
+    #[serde(rename = "esriSFSHorizontal")]
+    Horizontal,
+    #[serde(rename = "esriSFSVertical")]
+    Vertical,
+    #[serde(rename = "esriSFSNull")]
+    Null,
+}
+
+/// A point drawn as a shape. Sizes and offsets are in points.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimpleMarkerSymbol {
+    #[serde(default)]
+    pub style: SimpleMarkerStyle,
+    pub color: Option<Color>,
+    pub size: Option<f64>,
+    pub angle: Option<f64>,
+    pub xoffset: Option<f64>,
+    pub yoffset: Option<f64>,
+    pub outline: Option<SimpleLineSymbol>,
+}
+
+/// A line. Its width is in points.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimpleLineSymbol {
+    #[serde(default)]
+    pub style: SimpleLineStyle,
+    pub color: Option<Color>,
+    pub width: Option<f64>,
+}
+
+/// A polygon filled with a color or a pattern
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimpleFillSymbol {
+    #[serde(default)]
+    pub style: SimpleFillStyle,
+    pub color: Option<Color>,
+    pub outline: Option<SimpleLineSymbol>,
+}
+
+/// A point or polygon drawn with an image
+///
+/// The image is either base64 encoded in `image_data` with its `content_type`, or at `url`.
+/// Sizes and offsets are in points.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PictureSymbol {
+    pub url: Option<String>,
+    pub image_data: Option<String>,
+    pub content_type: Option<String>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub angle: Option<f64>,
+    pub xoffset: Option<f64>,
+    pub yoffset: Option<f64>,
+    /// The outline of a picture fill symbol
+    pub outline: Option<SimpleLineSymbol>,
+}
+
+/// A symbol used to draw a feature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Symbol {
+    #[serde(rename = "esriSMS")]
+    SimpleMarker(SimpleMarkerSymbol),
+    #[serde(rename = "esriSLS")]
+    SimpleLine(SimpleLineSymbol),
+    #[serde(rename = "esriSFS")]
+    SimpleFill(SimpleFillSymbol),
+    #[serde(rename = "esriPMS")]
+    PictureMarker(PictureSymbol),
+    #[serde(rename = "esriPFS")]
+    PictureFill(PictureSymbol),
+    /// A symbol of another type, e.g. a text or CIM symbol
+    #[serde(untagged)]
+    Other(Map<String, Value>),
+}
+
+impl Symbol {
+    /// The fill or marker color of the symbol, or the color of a line
+    pub fn color(&self) -> Option<Color> {
+        match self {
+            Self::SimpleMarker(s) => s.color,
+            Self::SimpleLine(s) => s.color,
+            Self::SimpleFill(s) => s.color,
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{
+    auth::{CredentialError, CredentialProvider, TokenProvider, TokenSource},
+    error::{ClassifyError, ErrorKind},
+    feature_service::{ErrorResponse, ExpectedResponse},
+    geometry::EsriEnvelope,
+    http::{HttpClient, HttpError, HttpRequest},
+    tiles::{Tile, TileInfo},
+    trace,
+};
+use serde::Deserialize;
+
+#[derive(Debug)]
+pub enum TileError {
+    /// The request failed or the response is not the expected JSON
+    HttpError(HttpError),
+    ApiError(ErrorResponse),
+    /// The token provider failed to supply a token
+    CredentialError(CredentialError),
+    /// The service is not cached and has no tiling scheme
+    MissingTileInfo,
+}
+
+impl std::fmt::Display for TileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HttpError(e) => write!(f, "{e}"),
+            Self::ApiError(e) => write!(f, "{} ({})", e.error.message, e.error.code),
+            Self::CredentialError(e) => write!(f, "failed to get token: {e}"),
+            Self::MissingTileInfo => write!(f, "the service has no tiling scheme"),
+        }
+    }
+}
+
+impl std::error::Error for TileError {}
+
+impl ClassifyError for TileError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::HttpError(e) => e.kind(),
+            Self::ApiError(e) => e.kind(),
+            Self::CredentialError(e) => e.kind(),
+            Self::MissingTileInfo => ErrorKind::Fatal,
+        }
+    }
+}
+
+// the part of a service's metadata describing its tiles
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+struct ServiceTiles {
+    tileInfo: Option<TileInfo>,
+}
+
+/// A client for the tiles of a cached Map Service or a Vector Tile Service
+///
+/// The tiles of a `VectorTileServer` are requested as `.pbf` files.
+///
+/// ```no_run
+/// use serde_esri::geometry::EsriPoint;
+/// use serde_esri::tiles::TileClient;
+///
+/// let client = TileClient::new(
+///     "https://services.arcgisonline.com/arcgis/rest/services/World_Imagery/MapServer",
+/// );
+/// let scheme = client.tile_info().unwrap();
+/// let point = EsriPoint { x: -13046000.0, y: 4036000.0, ..Default::default() };
+/// let tile = scheme.tile_at(&point, 12).unwrap();
+/// let image = client.fetch_tile(&tile).unwrap();
+/// ```
+///
+/// Requests are sent with a `reqwest::blocking::Client` unless another [`HttpClient`]
+/// is set with [`TileClient::with_http_client()`].
+#[derive(Clone)]
+pub struct TileClient<H = reqwest::blocking::Client> {
+    /// The URL of the service, e.g. `.../MapServer` or `.../VectorTileServer`
+    pub service_url: String,
+    pub(crate) client: H,
+    /// The token to use for authorization. Redacted from `Debug` and zeroized on drop.
+    pub(crate) token: Option<TokenSource>,
+}
+
+impl<H> std::fmt::Debug for TileClient<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TileClient")
+            .field("service_url", &self.service_url)
+            .field("token", &self.token.as_ref().map(|_| "[REDACTED]"))
+            .finish_non_exhaustive()
+    }
+}
+
+impl TileClient {
+    /// Create a new client for the service at `service_url`
+    pub fn new(service_url: &str) -> Self {
+        Self {
+            service_url: service_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            token: None,
+        }
+    }
+}
+
+impl<H: HttpClient> TileClient<H> {
+    /// Send requests with `client` instead
+    pub fn with_http_client<C: HttpClient>(self, client: C) -> TileClient<C> {
+        TileClient {
+            service_url: self.service_url,
+            client,
+            token: self.token,
+        }
+    }
+
+    /// Set the token used to authorize requests
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Set the provider asked for the token before every request
+    pub fn with_token_provider<P: TokenProvider>(mut self, provider: P) -> Self {
+        self.token = Some(TokenSource::new(provider));
+        self
+    }
+
+    /// Set the token used to authorize requests from `credentials`
+    pub fn with_credentials<C: CredentialProvider>(
+        mut self,
+        credentials: &C,
+    ) -> Result<Self, CredentialError> {
+        self.token = Some(credentials.token()?.into());
+        Ok(self)
+    }
+
+    /// The tiling scheme from the service's metadata
+    pub fn tile_info(&self) -> Result<TileInfo, TileError> {
+        let request = HttpRequest::get(&self.service_url).param("f", "json");
+
+        let resp = trace::send(&self.client, self.authorize(request)?)
+            .and_then(|r| r.json::<ExpectedResponse<ServiceTiles>>())
+            .map_err(TileError::HttpError)?;
+
+        match resp {
+            ExpectedResponse::Ok(x) => x.tileInfo.ok_or(TileError::MissingTileInfo),
+            ExpectedResponse::Error(e) => Err(TileError::ApiError(e)),
+        }
+    }
+
+    /// The content of a tile, e.g. a PNG image or a protobuf vector tile
+    ///
+    /// Services may respond with an error status for tiles without data.
+    pub fn fetch_tile(&self, tile: &Tile) -> Result<Vec<u8>, TileError> {
+        let mut url = format!("{}/{}", self.service_url, tile.path());
+        if self.service_url.ends_with("VectorTileServer") {
+            url.push_str(".pbf");
+        }
+
+        let resp = trace::send(&self.client, self.authorize(HttpRequest::get(&url))?)
+            .and_then(|r| r.error_for_status())
+            .map_err(TileError::HttpError)?;
+
+        Ok(resp.body)
+    }
+
+    /// The tiles intersecting `extent` at `level` of `scheme`, with their content
+    pub fn fetch_tiles(
+        &self,
+        scheme: &TileInfo,
+        extent: &EsriEnvelope,
+        level: u32,
+    ) -> Result<Vec<(Tile, Vec<u8>)>, TileError> {
+        scheme
+            .tiles_in(extent, level)
+            .into_iter()
+            .map(|tile| Ok((tile, self.fetch_tile(&tile)?)))
+            .collect()
+    }
+
+    fn authorize(&self, request: HttpRequest) -> Result<HttpRequest, TileError> {
+        match &self.token {
+            Some(token) => {
+                let bearer = token.bearer().map_err(TileError::CredentialError)?;
+                Ok(request.header("X-Esri-Authorization", &bearer))
+            }
+            None => Ok(request),
+        }
+    }
+}
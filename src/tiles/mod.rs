@@ -0,0 +1,15 @@
+//! Tiling schemes of cached [Map Services](https://developers.arcgis.com/rest/services-reference/enterprise/map-tile/) and [Vector Tile Services](https://developers.arcgis.com/rest/services-reference/enterprise/vector-tile-service/).
+//!
+//! A [`TileInfo`] is the `tileInfo` of a service's metadata, with a [`Lod`] for each level of detail.
+//! It finds the [`Tile`] covering an `EsriPoint` or the tiles covering an `EsriEnvelope` at a level, and the extent of a tile.
+//! [`TileInfo::web_mercator()`] is the scheme used by ArcGIS Online basemaps.
+//!
+//! Activate the `"tile-client"` feature to enable the `TileClient` struct which fetches the tiling scheme and the tiles of a service.
+mod scheme;
+pub use scheme::*;
+
+#[cfg(feature = "tile-client")]
+mod client;
+
+#[cfg(feature = "tile-client")]
+pub use client::*;
@@ -0,0 +1,237 @@
+use crate::geometry::{EsriEnvelope, EsriPoint};
+use crate::spatial_reference::SpatialReference;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+// the resolution and scale of level 0 of the Web Mercator scheme of ArcGIS Online
+const WEB_MERCATOR_RESOLUTION: f64 = 156543.03392800014;
+const WEB_MERCATOR_SCALE: f64 = 591657527.591555;
+const WEB_MERCATOR_ORIGIN: f64 = 20037508.342787;
+
+/// A level of detail of a tiling scheme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lod {
+    pub level: u32,
+    /// The size of a pixel in the units of the spatial reference
+    pub resolution: f64,
+    pub scale: f64,
+}
+
+/// A tile of a tiling scheme. Rows increase downwards from the origin and columns to the right.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tile {
+    pub level: u32,
+    pub row: u32,
+    pub col: u32,
+}
+
+impl Tile {
+    /// The path of the tile relative to a service, `tile/{level}/{row}/{col}`
+    pub fn path(&self) -> String {
+        format!("tile/{}/{}/{}", self.level, self.row, self.col)
+    }
+}
+
+/// The tiling scheme of a cached service, from the `tileInfo` of its metadata
+///
+/// ```
+/// use serde_esri::geometry::EsriPoint;
+/// use serde_esri::tiles::{Tile, TileInfo};
+///
+/// let scheme = TileInfo::web_mercator();
+/// // the origin of Web Mercator is in the top left tile
+/// let point = EsriPoint { x: -20037500.0, y: 20037500.0, ..Default::default() };
+/// assert_eq!(scheme.tile_at(&point, 3), Some(Tile { level: 3, row: 0, col: 0 }));
+/// ```
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TileInfo {
+    /// The height of a tile in pixels
+    pub rows: u32,
+    /// The width of a tile in pixels
+    pub cols: u32,
+    pub dpi: Option<u32>,
+    /// The format of the tiles, e.g. `PNG32` or `pbf`
+    pub format: Option<String>,
+    /// The top left corner of the tiling scheme
+    pub origin: EsriPoint,
+    pub spatial_reference: Option<SpatialReference>,
+    #[serde(default)]
+    pub lods: Vec<Lod>,
+}
+
+impl TileInfo {
+    /// The 256 pixel Web Mercator scheme of ArcGIS Online with levels 0 to 23
+    pub fn web_mercator() -> Self {
+        let lods = (0..24)
+            .map(|level| Lod {
+                level,
+                resolution: WEB_MERCATOR_RESOLUTION / 2f64.powi(level as i32),
+                scale: WEB_MERCATOR_SCALE / 2f64.powi(level as i32),
+            })
+            .collect();
+
+        Self {
+            rows: 256,
+            cols: 256,
+            dpi: Some(96),
+            format: None,
+            origin: EsriPoint {
+                x: -WEB_MERCATOR_ORIGIN,
+                y: WEB_MERCATOR_ORIGIN,
+                ..Default::default()
+            },
+            spatial_reference: Some(SpatialReference::from_wkid(3857)),
+            lods,
+        }
+    }
+
+    pub fn lod(&self, level: u32) -> Option<&Lod> {
+        self.lods.iter().find(|lod| lod.level == level)
+    }
+
+    /// The level of detail whose resolution is closest to `resolution`
+    pub fn lod_for_resolution(&self, resolution: f64) -> Option<&Lod> {
+        self.closest_lod(|lod| (lod.resolution / resolution).ln().abs())
+    }
+
+    /// The level of detail whose scale is closest to `scale`
+    pub fn lod_for_scale(&self, scale: f64) -> Option<&Lod> {
+        self.closest_lod(|lod| (lod.scale / scale).ln().abs())
+    }
+
+    /// The width and height of a tile at `level` in the units of the spatial reference
+    pub fn tile_size(&self, level: u32) -> Option<(f64, f64)> {
+        let lod = self.lod(level)?;
+        Some((
+            self.cols as f64 * lod.resolution,
+            self.rows as f64 * lod.resolution,
+        ))
+    }
+
+    /// The tile containing `point` at `level`, if the point is right of and below the origin
+    pub fn tile_at(&self, point: &EsriPoint, level: u32) -> Option<Tile> {
+        let (width, height) = self.tile_size(level)?;
+        let col = ((point.x - self.origin.x) / width).floor();
+        let row = ((self.origin.y - point.y) / height).floor();
+        if col < 0.0 || row < 0.0 || !col.is_finite() || !row.is_finite() {
+            return None;
+        }
+
+        Some(Tile {
+            level,
+            row: row as u32,
+            col: col as u32,
+        })
+    }
+
+    /// The tiles intersecting `extent` at `level`, by row and then by column
+    ///
+    /// Parts of the extent above or left of the origin are ignored.
+    pub fn tiles_in(&self, extent: &EsriEnvelope, level: u32) -> Vec<Tile> {
+        let Some((width, height)) = self.tile_size(level) else {
+            return Vec::new();
+        };
+
+        // the first and last index of the tiles covering `min..max`, where `max` on a
+        // boundary does not include the next tile
+        let range = |min: f64, max: f64, size: f64| {
+            let first = (min / size).floor().max(0.0);
+            let last = ((max / size).ceil() - 1.0).max(first);
+            (max >= 0.0 && first.is_finite() && last.is_finite())
+                .then_some((first as u32, last as u32))
+        };
+
+        let cols = range(
+            extent.xmin - self.origin.x,
+            extent.xmax - self.origin.x,
+            width,
+        );
+        let rows = range(
+            self.origin.y - extent.ymax,
+            self.origin.y - extent.ymin,
+            height,
+        );
+        let (Some((col_min, col_max)), Some((row_min, row_max))) = (cols, rows) else {
+            return Vec::new();
+        };
+
+        (row_min..=row_max)
+            .flat_map(|row| (col_min..=col_max).map(move |col| Tile { level, row, col }))
+            .collect()
+    }
+
+    /// The extent covered by `tile`
+    pub fn tile_extent(&self, tile: &Tile) -> Option<EsriEnvelope> {
+        let (width, height) = self.tile_size(tile.level)?;
+        let xmin = self.origin.x + tile.col as f64 * width;
+        let ymax = self.origin.y - tile.row as f64 * height;
+
+        Some(EsriEnvelope {
+            xmin,
+            ymin: ymax - height,
+            xmax: xmin + width,
+            ymax,
+            spatialReference: self.spatial_reference.clone(),
+            ..Default::default()
+        })
+    }
+
+    fn closest_lod<F: Fn(&Lod) -> f64>(&self, distance: F) -> Option<&Lod> {
+        self.lods
+            .iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_math() {
+        let scheme: TileInfo = serde_json::from_str(
+            r#"{"rows": 256, "cols": 256, "dpi": 96, "format": "PNG32",
+                "origin": {"x": 0.0, "y": 1024.0},
+                "spatialReference": {"wkid": 3857},
+                "lods": [{"level": 0, "resolution": 2.0, "scale": 7559.0},
+                         {"level": 1, "resolution": 1.0, "scale": 3779.5}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(scheme.tile_size(0), Some((512.0, 512.0)));
+        assert_eq!(scheme.lod_for_resolution(1.2).unwrap().level, 1);
+        assert_eq!(scheme.lod_for_scale(8000.0).unwrap().level, 0);
+
+        let point = EsriPoint {
+            x: 300.0,
+            y: 700.0,
+            ..Default::default()
+        };
+        let tile = scheme.tile_at(&point, 1).unwrap();
+        assert_eq!((tile.row, tile.col), (1, 1));
+
+        let extent = scheme.tile_extent(&tile).unwrap();
+        assert_eq!(extent.bbox(), [256.0, 512.0, 512.0, 768.0]);
+        // an extent ending on a tile boundary does not include the next tile
+        assert_eq!(scheme.tiles_in(&extent, 1), vec![tile]);
+
+        let extent = EsriEnvelope {
+            xmin: -100.0,
+            ymin: 500.0,
+            xmax: 300.0,
+            ymax: 800.0,
+            ..Default::default()
+        };
+        let tiles: Vec<_> = scheme
+            .tiles_in(&extent, 1)
+            .iter()
+            .map(|t| (t.row, t.col))
+            .collect();
+        assert_eq!(tiles, vec![(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)]);
+
+        let outside = EsriPoint { x: -1.0, ..point };
+        assert_eq!(scheme.tile_at(&outside, 1), None);
+    }
+}
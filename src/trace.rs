@@ -0,0 +1,198 @@
+// Tracing of the requests of the clients, which does nothing without the `tracing` feature.
+//
+// Each request, blocking or async, is sent inside a `request` span with its method and URL,
+// with the token redacted. Its response is recorded as an event with the status and latency.
+// The requests of paginated queries are inside a `page` span with the number of the page.
+use crate::http::{HttpClient, HttpError, HttpRequest, HttpResponse};
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+// the span of a request. Its events and the events of the code run in its scope are
+// inside the span.
+pub(crate) struct RequestSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    start: Instant,
+}
+
+impl RequestSpan {
+    pub(crate) fn new(method: &str, url: &str) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = (method, url);
+
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!("request", method, url = %redact(url)),
+            #[cfg(feature = "tracing")]
+            start: Instant::now(),
+        }
+    }
+
+    // runs `f` inside the span
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+
+    // awaits `future` inside the span, which is entered whenever the future is polled
+    #[cfg(feature = "places-client-async")]
+    pub(crate) async fn instrument<F: std::future::Future>(&self, future: F) -> F::Output {
+        #[cfg(feature = "tracing")]
+        return tracing::Instrument::instrument(future, self.span.clone()).await;
+        #[cfg(not(feature = "tracing"))]
+        future.await
+    }
+
+    // records the response, and its size in bytes if known
+    pub(crate) fn response(&self, status: u16, bytes: Option<u64>) {
+        #[cfg(feature = "tracing")]
+        self.in_scope(|| {
+            tracing::debug!(
+                status,
+                bytes,
+                elapsed_ms = self.start.elapsed().as_millis() as u64,
+                "response"
+            )
+        });
+        #[cfg(not(feature = "tracing"))]
+        let _ = (status, bytes);
+    }
+
+    // records a request that failed before a response was received
+    pub(crate) fn failed(&self, error: &dyn std::error::Error) {
+        #[cfg(feature = "tracing")]
+        self.in_scope(|| {
+            tracing::warn!(
+                error = %error,
+                elapsed_ms = self.start.elapsed().as_millis() as u64,
+                "request failed"
+            )
+        });
+        #[cfg(not(feature = "tracing"))]
+        let _ = error;
+    }
+
+    // records the result of the request
+    fn record(&self, result: Result<HttpResponse, HttpError>) -> Result<HttpResponse, HttpError> {
+        match &result {
+            Ok(resp) => self.response(resp.status, Some(resp.body.len() as u64)),
+            Err(e) => self.failed(e),
+        }
+        result
+    }
+}
+
+// records that a request is retried after `delay`
+#[cfg(feature = "places-client")]
+pub(crate) fn retry(retry: u32, delay: std::time::Duration, error: &dyn std::error::Error) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        retry,
+        delay_ms = delay.as_millis() as u64,
+        error = %error,
+        "retrying request"
+    );
+    #[cfg(not(feature = "tracing"))]
+    let _ = (retry, delay, error);
+}
+
+// the span of a page of a paginated query, starting at 1, which contains its requests
+#[cfg(any(feature = "places-client", feature = "feature-service-client"))]
+pub(crate) struct PageSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+#[cfg(any(feature = "places-client", feature = "feature-service-client"))]
+impl PageSpan {
+    pub(crate) fn new(page: u64) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = page;
+
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!("page", page),
+        }
+    }
+
+    // runs `f` inside the span
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+
+    // awaits `future` inside the span, which is entered whenever the future is polled
+    #[cfg(feature = "places-client-async")]
+    pub(crate) async fn instrument<F: std::future::Future>(&self, future: F) -> F::Output {
+        #[cfg(feature = "tracing")]
+        return tracing::Instrument::instrument(future, self.span.clone()).await;
+        #[cfg(not(feature = "tracing"))]
+        future.await
+    }
+
+    // records the number of results of the page
+    pub(crate) fn results(&self, results: usize) {
+        #[cfg(feature = "tracing")]
+        self.in_scope(|| tracing::debug!(results, "page fetched"));
+        #[cfg(not(feature = "tracing"))]
+        let _ = results;
+    }
+}
+
+// sends a blocking request inside a request span
+pub(crate) fn send<H: HttpClient + ?Sized>(
+    client: &H,
+    request: HttpRequest,
+) -> Result<HttpResponse, HttpError> {
+    let span = RequestSpan::new(request.method.as_str(), &request.url);
+    let result = span.in_scope(|| client.execute(request));
+
+    span.record(result)
+}
+
+// awaits the response of an async request inside a request span
+#[cfg(feature = "places-client-async")]
+pub(crate) async fn send_async<F>(request: &HttpRequest, send: F) -> Result<HttpResponse, HttpError>
+where
+    F: std::future::Future<Output = Result<HttpResponse, HttpError>>,
+{
+    let span = RequestSpan::new(request.method.as_str(), &request.url);
+    let result = span.instrument(send).await;
+
+    span.record(result)
+}
+
+// the url with the value of its `token` parameter replaced
+#[cfg(feature = "tracing")]
+fn redact(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+
+    let params: Vec<&str> = query
+        .split('&')
+        .map(|param| match param.split_once('=') {
+            Some(("token", _)) => "token=[REDACTED]",
+            _ => param,
+        })
+        .collect();
+    format!("{base}?{}", params.join("&"))
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("https://example.com/query?f=json&token=secret"),
+            "https://example.com/query?f=json&token=[REDACTED]"
+        );
+    }
+}
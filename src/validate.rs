@@ -0,0 +1,282 @@
+//! Client-side validation of polygons and polylines against the Esri JSON spec.
+//!
+//! Services reject invalid geometries with errors that rarely say what is wrong
+//! with them. [`EsriPolygon::validate()`] and [`EsriPolyline::validate()`] list
+//! the [`ValidationIssue`]s of a geometry before it is sent, and
+//! [`EsriPolygon::fix()`] repairs the ones that can be repaired without changing
+//! its shape: unclosed rings and rings wound the wrong way.
+//!
+//! Exterior rings must be clockwise and holes counter-clockwise. A ring is a hole
+//! when it is inside an odd number of the other rings. Self-intersections are
+//! only checked with the `from-geo` feature, using the [`geo`](https://docs.rs/geo)
+//! crate.
+//!
+//! ```
+//! use serde_esri::{geometry::EsriPolygon, validate::ValidationIssue};
+//!
+//! // counter-clockwise and not closed
+//! let mut polygon = EsriPolygon::<2>::from_coords_zm([[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]]);
+//! assert_eq!(
+//!     polygon.validate(),
+//!     [ValidationIssue::UnclosedRing { ring: 0 }, ValidationIssue::WrongOrientation { ring: 0 }]
+//! );
+//!
+//! polygon.fix();
+//! assert!(polygon.validate().is_empty());
+//! ```
+use crate::geometry::{EsriLineString, EsriPolygon, EsriPolyline};
+use crate::rings::{contains_point, is_clockwise};
+use std::borrow::Cow;
+
+/// A reason that a polygon or polyline is not valid
+///
+/// Vertices are indexed within their ring or path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// A ring with fewer than 4 vertices, including the closing vertex
+    TooFewPointsInRing { ring: usize, count: usize },
+    /// A path with fewer than 2 vertices
+    TooFewPointsInPath { path: usize, count: usize },
+    /// A ring whose last vertex is not its first
+    UnclosedRing { ring: usize },
+    /// An exterior ring that is not clockwise, or a hole that is not counter-clockwise
+    WrongOrientation { ring: usize },
+    /// A ring that crosses or touches itself
+    SelfIntersection { ring: usize },
+    /// A vertex with the same x and y as the vertex before it
+    DuplicateVertex { part: usize, vertex: usize },
+    /// A vertex with an x or y that is NaN or infinite
+    NonFiniteCoordinate { part: usize, vertex: usize },
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooFewPointsInRing { ring, count } => {
+                write!(
+                    f,
+                    "ring {ring} has {count} vertices, at least 4 are required"
+                )
+            }
+            Self::TooFewPointsInPath { path, count } => {
+                write!(
+                    f,
+                    "path {path} has {count} vertices, at least 2 are required"
+                )
+            }
+            Self::UnclosedRing { ring } => write!(f, "ring {ring} is not closed"),
+            Self::WrongOrientation { ring } => write!(f, "ring {ring} is wound the wrong way"),
+            Self::SelfIntersection { ring } => write!(f, "ring {ring} intersects itself"),
+            Self::DuplicateVertex { part, vertex } => {
+                write!(
+                    f,
+                    "vertex {vertex} of part {part} repeats the vertex before it"
+                )
+            }
+            Self::NonFiniteCoordinate { part, vertex } => {
+                write!(f, "vertex {vertex} of part {part} is not finite")
+            }
+        }
+    }
+}
+
+impl<const N: usize, T: Copy + Into<f64>> EsriPolygon<N, T> {
+    /// The issues of the polygon, ring by ring. An empty list means it is valid.
+    ///
+    /// The orientation of a ring is only checked when it has enough finite vertices.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        for (i, ring) in self.rings.iter().enumerate() {
+            let count = ring.0.len();
+            if count < 4 {
+                issues.push(ValidationIssue::TooFewPointsInRing { ring: i, count });
+            }
+            if count > 0 && !is_closed(ring) {
+                issues.push(ValidationIssue::UnclosedRing { ring: i });
+            }
+            let vertex_issues = vertex_issues(i, ring);
+            let measurable = count >= 3 && vertex_issues.is_empty();
+            issues.extend(vertex_issues);
+            if !measurable {
+                continue;
+            }
+
+            if self.is_wrongly_wound(i) {
+                issues.push(ValidationIssue::WrongOrientation { ring: i });
+            }
+            #[cfg(feature = "from-geo")]
+            if self_intersects(ring) {
+                issues.push(ValidationIssue::SelfIntersection { ring: i });
+            }
+        }
+        issues
+    }
+
+    /// Close every ring and reverse the rings that are wound the wrong way
+    ///
+    /// The other issues found by [`EsriPolygon::validate()`] are left as is.
+    pub fn fix(&mut self) {
+        for ring in &mut self.rings {
+            if !ring.0.is_empty() && !is_closed(ring) {
+                ring.0.push(ring.0[0].clone());
+            }
+        }
+
+        let wrong: Vec<usize> = self
+            .validate()
+            .into_iter()
+            .filter_map(|issue| match issue {
+                ValidationIssue::WrongOrientation { ring } => Some(ring),
+                _ => None,
+            })
+            .collect();
+        for i in wrong {
+            self.rings[i].0.reverse();
+        }
+    }
+
+    // whether ring `i` is clockwise when it is a hole, or counter-clockwise when it is not
+    fn is_wrongly_wound(&self, i: usize) -> bool {
+        let ring = closed(&self.rings[i]);
+        let (x, y) = (ring.0[0].0[0].into(), ring.0[0].0[1].into());
+        let depth = self
+            .rings
+            .iter()
+            .enumerate()
+            .filter(|(j, other)| *j != i && other.0.len() >= 3)
+            .filter(|(_, other)| contains_point(&closed(other), x, y))
+            .count();
+        is_clockwise(&ring) == (depth % 2 == 1)
+    }
+}
+
+impl<const N: usize, T: Copy + Into<f64>> EsriPolyline<N, T> {
+    /// The issues of the polyline, path by path. An empty list means it is valid.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        for (i, path) in self.paths.iter().enumerate() {
+            let count = path.0.len();
+            if count < 2 {
+                issues.push(ValidationIssue::TooFewPointsInPath { path: i, count });
+            }
+            issues.extend(vertex_issues(i, path));
+        }
+        issues
+    }
+}
+
+// the x and y of a vertex
+fn xy<const N: usize, T: Copy + Into<f64>>(line: &EsriLineString<N, T>, i: usize) -> (f64, f64) {
+    (line.0[i].0[0].into(), line.0[i].0[1].into())
+}
+
+fn is_closed<const N: usize, T: Copy + Into<f64>>(line: &EsriLineString<N, T>) -> bool {
+    xy(line, 0) == xy(line, line.0.len() - 1)
+}
+
+// the ring with its first vertex repeated at the end if it is not closed
+fn closed<const N: usize, T: Copy + Into<f64>>(
+    ring: &EsriLineString<N, T>,
+) -> Cow<'_, EsriLineString<N, T>> {
+    if is_closed(ring) {
+        Cow::Borrowed(ring)
+    } else {
+        let mut ring = ring.clone();
+        ring.0.push(ring.0[0].clone());
+        Cow::Owned(ring)
+    }
+}
+
+// the non-finite and duplicate vertices of a ring or path
+fn vertex_issues<const N: usize, T: Copy + Into<f64>>(
+    part: usize,
+    line: &EsriLineString<N, T>,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for vertex in 0..line.0.len() {
+        let (x, y) = xy(line, vertex);
+        if !x.is_finite() || !y.is_finite() {
+            issues.push(ValidationIssue::NonFiniteCoordinate { part, vertex });
+        } else if vertex > 0 && xy(line, vertex - 1) == (x, y) {
+            issues.push(ValidationIssue::DuplicateVertex { part, vertex });
+        }
+    }
+    issues
+}
+
+#[cfg(feature = "from-geo")]
+fn self_intersects<const N: usize, T: Copy + Into<f64>>(ring: &EsriLineString<N, T>) -> bool {
+    use geo::algorithm::validation::{InvalidPolygon, Validation};
+
+    let ring = closed(ring);
+    let coords: Vec<(f64, f64)> = (0..ring.0.len()).map(|i| xy(&ring, i)).collect();
+    let polygon = geo::Polygon::new(coords.into(), vec![]);
+    polygon
+        .validation_errors()
+        .iter()
+        .any(|e| matches!(e, InvalidPolygon::SelfIntersection(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_polygon() {
+        // a clockwise exterior, a clockwise hole, and a ring with too few and duplicate vertices
+        let mut polygon = EsriPolygon::<2>::from_coords_zm([
+            vec![
+                [0.0, 0.0],
+                [0.0, 10.0],
+                [10.0, 10.0],
+                [10.0, 0.0],
+                [0.0, 0.0],
+            ],
+            vec![[2.0, 2.0], [2.0, 4.0], [4.0, 4.0], [4.0, 2.0], [2.0, 2.0]],
+            vec![[20.0, 20.0], [20.0, 20.0], [21.0, 20.0]],
+        ]);
+        assert_eq!(
+            polygon.validate(),
+            [
+                ValidationIssue::WrongOrientation { ring: 1 },
+                ValidationIssue::TooFewPointsInRing { ring: 2, count: 3 },
+                ValidationIssue::UnclosedRing { ring: 2 },
+                ValidationIssue::DuplicateVertex { part: 2, vertex: 1 },
+            ]
+        );
+
+        polygon.fix();
+        assert_eq!(
+            polygon.validate(),
+            [ValidationIssue::DuplicateVertex { part: 2, vertex: 1 }]
+        );
+        assert_eq!(polygon.rings[1].0[1].0, [4.0, 2.0]);
+        assert_eq!(polygon.rings[2].0.len(), 4);
+
+        let polyline = EsriPolyline::<2>::from_coords_zm([vec![[0.0, 0.0]], vec![[0.0, f64::NAN]]]);
+        assert_eq!(
+            polyline.validate(),
+            [
+                ValidationIssue::TooFewPointsInPath { path: 0, count: 1 },
+                ValidationIssue::TooFewPointsInPath { path: 1, count: 1 },
+                ValidationIssue::NonFiniteCoordinate { part: 1, vertex: 0 },
+            ]
+        );
+    }
+
+    #[cfg(feature = "from-geo")]
+    #[test]
+    fn test_self_intersection() {
+        // a bowtie
+        let polygon = EsriPolygon::<2>::from_coords_zm([[
+            [0.0, 0.0],
+            [0.0, 1.0],
+            [1.0, 0.0],
+            [1.0, 1.0],
+            [0.0, 0.0],
+        ]]);
+        assert!(polygon
+            .validate()
+            .contains(&ValidationIssue::SelfIntersection { ring: 0 }));
+    }
+}
@@ -0,0 +1,311 @@
+//! Types for the [Web Map specification](https://developers.arcgis.com/web-map-specification/), the JSON data of a Web Map item.
+//!
+//! A [`WebMap`] has operational layers and tables, a [`Basemap`], and [`Bookmark`]s. Each
+//! [`OperationalLayer`] may have a [`PopupInfo`] with its [`FieldInfo`]s and Arcade
+//! [`ExpressionInfo`]s. Properties that are not modelled are kept in `extra`.
+//!
+//! With the `"portal-client"` feature, fetch a Web Map with `PortalClient::item_data::<WebMap>()`.
+//!
+//! ```
+//! use serde_esri::webmap::WebMap;
+//!
+//! let webmap: WebMap = serde_json::from_str(r#"{
+//!     "operationalLayers": [{
+//!         "id": "parcels",
+//!         "title": "Parcels",
+//!         "layerType": "ArcGISFeatureLayer",
+//!         "url": "https://example.com/arcgis/rest/services/Parcels/FeatureServer/0",
+//!         "popupInfo": {"title": "Parcel {APN}", "fieldInfos": [{"fieldName": "OWNER", "visible": true}]}
+//!     }],
+//!     "baseMap": {"title": "Topographic", "baseMapLayers": []},
+//!     "version": "2.28"
+//! }"#).unwrap();
+//!
+//! assert_eq!(webmap.layer_urls().len(), 1);
+//! let popup = webmap.operational_layers[0].popup_info.as_ref().unwrap();
+//! assert_eq!(popup.field_names(), vec!["APN", "OWNER"]);
+//! ```
+use crate::feature_service::DrawingInfo;
+use crate::geometry::EsriEnvelope;
+use crate::spatial_reference::SpatialReference;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+
+/// The JSON data of a Web Map item
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebMap {
+    #[serde(default)]
+    pub operational_layers: Vec<OperationalLayer>,
+    #[serde(default)]
+    pub tables: Vec<OperationalLayer>,
+    #[serde(rename = "baseMap")]
+    pub basemap: Option<Basemap>,
+    pub spatial_reference: Option<SpatialReference>,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    /// The version of the specification, e.g. `2.28`
+    pub version: Option<String>,
+    pub authoring_app: Option<String>,
+    pub authoring_app_version: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl WebMap {
+    /// The operational layers and tables, including the layers of group layers, depth first
+    ///
+    /// Basemap layers are not included.
+    pub fn layers(&self) -> Vec<&OperationalLayer> {
+        let mut layers = Vec::new();
+        for layer in self.operational_layers.iter().chain(&self.tables) {
+            layer.collect(&mut layers);
+        }
+        layers
+    }
+
+    /// The URLs of the layers, tables, and basemap layers, in that order
+    pub fn layer_urls(&self) -> Vec<&str> {
+        let basemap = self.basemap.iter().flat_map(|b| &b.base_map_layers);
+        self.layers()
+            .into_iter()
+            .chain(basemap)
+            .filter_map(|layer| layer.url.as_deref())
+            .collect()
+    }
+}
+
+/// The ID of a layer, which is a string for operational layers and a number for the sublayers of a service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LayerId {
+    Number(i64),
+    String(String),
+}
+
+/// A layer or table of a Web Map, or a sublayer of one
+///
+/// Group layers and Map Service layers have their sublayers in `layers`.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationalLayer {
+    pub id: Option<LayerId>,
+    pub title: Option<String>,
+    /// e.g. `ArcGISFeatureLayer`, `ArcGISMapServiceLayer`, `VectorTileLayer`, or `GroupLayer`
+    pub layer_type: Option<String>,
+    pub url: Option<String>,
+    /// The ID of the portal item of the layer
+    pub item_id: Option<String>,
+    pub visibility: Option<bool>,
+    pub opacity: Option<f64>,
+    pub min_scale: Option<f64>,
+    pub max_scale: Option<f64>,
+    pub disable_popup: Option<bool>,
+    pub popup_info: Option<PopupInfo>,
+    pub layer_definition: Option<WebMapLayerDefinition>,
+    #[serde(default)]
+    pub layers: Vec<OperationalLayer>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl OperationalLayer {
+    pub fn is_group(&self) -> bool {
+        self.layer_type.as_deref() == Some("GroupLayer")
+    }
+
+    // the layer followed by its group's layers
+    fn collect<'a>(&'a self, layers: &mut Vec<&'a OperationalLayer>) {
+        layers.push(self);
+        if self.is_group() {
+            for layer in &self.layers {
+                layer.collect(layers);
+            }
+        }
+    }
+}
+
+/// The properties of a layer overridden by a Web Map
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebMapLayerDefinition {
+    /// A SQL `WHERE` clause filtering the features of the layer
+    pub definition_expression: Option<String>,
+    pub drawing_info: Option<DrawingInfo>,
+    pub min_scale: Option<f64>,
+    pub max_scale: Option<f64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The basemap of a Web Map
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Basemap {
+    pub title: Option<String>,
+    #[serde(default)]
+    pub base_map_layers: Vec<OperationalLayer>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A named extent of a Web Map
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    pub name: Option<String>,
+    pub extent: Option<EsriEnvelope>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The popup of a layer
+///
+/// `title` and `description` may reference fields as `{FIELD}` and expressions as `{expression/NAME}`.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PopupInfo {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub field_infos: Vec<FieldInfo>,
+    #[serde(default)]
+    pub expression_infos: Vec<ExpressionInfo>,
+    pub show_attachments: Option<bool>,
+    #[serde(default)]
+    pub media_infos: Vec<Value>,
+    #[serde(default)]
+    pub popup_elements: Vec<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl PopupInfo {
+    /// The fields referenced by the title, the description, and the field infos, in that
+    /// order and without duplicates
+    ///
+    /// Expressions, e.g. `expression/NAME`, are not fields and are left out.
+    pub fn field_names(&self) -> Vec<&str> {
+        let templates = [&self.title, &self.description];
+        let placeholders = templates
+            .into_iter()
+            .flatten()
+            .flat_map(|text| placeholders(text));
+        let infos = self.field_infos.iter().map(|f| f.field_name.as_str());
+
+        let mut names: Vec<&str> = Vec::new();
+        for name in placeholders.chain(infos) {
+            if !name.starts_with("expression/") && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// The expression with `name`, e.g. `expr0` for `{expression/expr0}`
+    pub fn expression(&self, name: &str) -> Option<&ExpressionInfo> {
+        self.expression_infos.iter().find(|e| e.name == name)
+    }
+}
+
+// the `NAME` of each `{NAME}` in `text`
+fn placeholders(text: &str) -> impl Iterator<Item = &str> {
+    text.split('{')
+        .skip(1)
+        .filter_map(|s| s.split_once('}'))
+        .map(|(name, _)| name.trim())
+        .filter(|name| !name.is_empty())
+}
+
+/// How a field is shown in a popup
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldInfo {
+    pub field_name: String,
+    pub label: Option<String>,
+    pub tooltip: Option<String>,
+    pub visible: Option<bool>,
+    pub is_editable: Option<bool>,
+    pub string_field_option: Option<String>,
+    pub format: Option<FieldFormat>,
+}
+
+/// The format of a numeric or date field in a popup
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldFormat {
+    /// The number of decimal places
+    pub places: Option<u32>,
+    pub digit_separator: Option<bool>,
+    /// e.g. `shortDate` or `longMonthDayYear`
+    pub date_format: Option<String>,
+}
+
+/// An [Arcade](https://developers.arcgis.com/arcade/) expression shown in a popup
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpressionInfo {
+    pub name: String,
+    pub title: Option<String>,
+    pub expression: String,
+    /// `string` or `number`
+    pub return_type: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webmap_layers() {
+        let webmap: WebMap = serde_json::from_str(
+            r#"{
+                "operationalLayers": [
+                    {"id": "group", "layerType": "GroupLayer", "layers": [
+                        {"id": "roads", "layerType": "ArcGISFeatureLayer", "url": "https://example.com/0"}
+                    ]},
+                    {"id": "dyn", "layerType": "ArcGISMapServiceLayer", "url": "https://example.com/MapServer",
+                     "layers": [{"id": 3, "popupInfo": {"description": "{expression/expr0} {NAME}",
+                                 "expressionInfos": [{"name": "expr0", "expression": "$feature.POP * 2"}]}}]}
+                ],
+                "tables": [{"id": "t", "url": "https://example.com/1"}],
+                "baseMap": {"baseMapLayers": [{"id": "b", "url": "https://example.com/tiles", "layerType": "ArcGISTiledMapServiceLayer"}]},
+                "bookmarks": [{"name": "Home", "extent": {"xmin": 0, "ymin": 0, "xmax": 1, "ymax": 1}}],
+                "applicationProperties": {"viewing": {}}
+            }"#,
+        )
+        .unwrap();
+
+        let ids: Vec<_> = webmap.layers().iter().map(|l| l.id.clone()).collect();
+        assert_eq!(
+            ids,
+            ["group", "roads", "dyn", "t"].map(|id| Some(LayerId::String(id.into())))
+        );
+        assert_eq!(
+            webmap.layer_urls(),
+            vec![
+                "https://example.com/0",
+                "https://example.com/MapServer",
+                "https://example.com/1",
+                "https://example.com/tiles"
+            ]
+        );
+
+        let sublayer = &webmap.operational_layers[1].layers[0];
+        assert_eq!(sublayer.id, Some(LayerId::Number(3)));
+        let popup = sublayer.popup_info.as_ref().unwrap();
+        assert_eq!(popup.field_names(), vec!["NAME"]);
+        assert!(popup.expression("expr0").is_some());
+        assert!(webmap.extra.contains_key("applicationProperties"));
+    }
+}
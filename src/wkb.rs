@@ -0,0 +1,466 @@
+//! Optional feature for converting Esri geometries to and from Well-Known Binary.
+//!
+//! Z and M values are preserved. The const `N` determines the dimensions that
+//! are written and that are expected when reading: `2` is XY, `3` is XYZ, and `4` is XYZM.
+//!
+//! [`to_wkb()`] writes little-endian ISO WKB. Polylines are always encoded as
+//! MultiLineStrings and polygons as MultiPolygons so that every feature of a
+//! layer shares a geometry type. Envelopes are encoded as 2D Polygons. True
+//! curves are densified. Multipatches are encoded as MultiPolygons with a
+//! polygon for each face.
+//!
+//! [`from_wkb()`] reads ISO WKB and PostGIS EWKB in either byte order. Rings
+//! are rewound so that exterior rings are clockwise and interior rings are
+//! counter-clockwise as required by Esri.
+//!
+//! ```
+//! use serde_esri::{geometry::EsriGeometry, wkb::{from_wkb, to_wkb}};
+//!
+//! let json = r#"{"hasZ": true, "paths": [[[0, 0, 10], [1, 1, 20]]]}"#;
+//! let geometry: EsriGeometry<3> = serde_json::from_str(json).unwrap();
+//!
+//! let bytes = to_wkb(&geometry);
+//! let polyline = from_wkb::<3>(&bytes).unwrap().as_polyline().unwrap();
+//! assert_eq!(polyline.paths[0].0[1].0, [1.0, 1.0, 20.0]);
+//! ```
+use crate::{
+    geometry::*,
+    rings::{classify_rings, is_clockwise},
+};
+
+/// Errors that can occur when reading WKB into Esri geometries
+#[derive(Debug, Clone, PartialEq)]
+pub enum WkbError {
+    /// The input ended before the geometry was complete
+    UnexpectedEof,
+    /// The byte order marker is neither `0` nor `1`
+    InvalidByteOrder(u8),
+    /// The geometry type code is not supported
+    InvalidType(u32),
+    /// The geometry does not have the dimensions required by `N`
+    DimensionMismatch {
+        expected: usize,
+        has_z: bool,
+        has_m: bool,
+    },
+    /// Esri JSON has no representation of an empty point
+    EmptyPoint,
+    /// Esri JSON has no geometry collection type
+    GeometryCollection,
+    /// Bytes remain after the geometry
+    TrailingBytes(usize),
+}
+
+impl std::fmt::Display for WkbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "unexpected end of WKB"),
+            Self::InvalidByteOrder(x) => write!(f, "invalid byte order {x}"),
+            Self::InvalidType(x) => write!(f, "unsupported geometry type {x}"),
+            Self::DimensionMismatch {
+                expected,
+                has_z,
+                has_m,
+            } => write!(
+                f,
+                "expected {expected} dimensions, found hasZ: {has_z} and hasM: {has_m}"
+            ),
+            Self::EmptyPoint => write!(f, "empty points cannot be converted"),
+            Self::GeometryCollection => write!(f, "geometry collections cannot be converted"),
+            Self::TrailingBytes(n) => write!(f, "{n} bytes remain after the geometry"),
+        }
+    }
+}
+
+impl std::error::Error for WkbError {}
+
+const POINT: u32 = 1;
+const LINESTRING: u32 = 2;
+const POLYGON: u32 = 3;
+const MULTIPOINT: u32 = 4;
+const MULTILINESTRING: u32 = 5;
+const MULTIPOLYGON: u32 = 6;
+const GEOMETRYCOLLECTION: u32 = 7;
+
+// EWKB flags
+const EWKB_Z: u32 = 0x8000_0000;
+const EWKB_M: u32 = 0x4000_0000;
+const EWKB_SRID: u32 = 0x2000_0000;
+
+// ISO WKB adds 1000 for Z, 2000 for M and 3000 for ZM
+fn type_code<const N: usize>(base: u32) -> u32 {
+    match dimension_flags::<N>() {
+        (true, true) => base + 3000,
+        (true, false) => base + 1000,
+        (false, true) => base + 2000,
+        (false, false) => base,
+    }
+}
+
+fn write_header(buf: &mut Vec<u8>, type_code: u32) {
+    buf.push(1);
+    buf.extend(type_code.to_le_bytes());
+}
+
+fn write_f64(buf: &mut Vec<u8>, x: f64) {
+    buf.extend(x.to_le_bytes());
+}
+
+fn write_u32(buf: &mut Vec<u8>, x: usize) {
+    buf.extend((x as u32).to_le_bytes());
+}
+
+fn write_coord<const N: usize, T: Copy + Into<f64>>(buf: &mut Vec<u8>, x: &EsriCoord<N, T>) {
+    x.0.iter().for_each(|v| write_f64(buf, (*v).into()));
+}
+
+fn write_coords<const N: usize, T: Copy + Into<f64>>(buf: &mut Vec<u8>, x: &EsriLineString<N, T>) {
+    write_u32(buf, x.0.len());
+    x.iter().for_each(|c| write_coord(buf, c));
+}
+
+fn write_point<const N: usize, T: Copy + Into<f64>>(buf: &mut Vec<u8>, x: &EsriPoint<T>) {
+    let (has_z, has_m) = dimension_flags::<N>();
+    write_header(buf, type_code::<N>(POINT));
+    write_f64(buf, x.x.into());
+    write_f64(buf, x.y.into());
+    if has_z {
+        write_f64(buf, x.z.map_or(f64::NAN, Into::into));
+    }
+    if has_m {
+        write_f64(buf, x.m.map_or(f64::NAN, Into::into));
+    }
+}
+
+fn write_multipoint<const N: usize, T: Copy + Into<f64>>(
+    buf: &mut Vec<u8>,
+    x: &EsriMultiPoint<N, T>,
+) {
+    write_header(buf, type_code::<N>(MULTIPOINT));
+    write_u32(buf, x.points.len());
+    x.iter().for_each(|c| {
+        write_header(buf, type_code::<N>(POINT));
+        write_coord(buf, c);
+    });
+}
+
+fn write_polyline<const N: usize, T: Copy + Into<f64>>(buf: &mut Vec<u8>, x: &EsriPolyline<N, T>) {
+    write_header(buf, type_code::<N>(MULTILINESTRING));
+    write_u32(buf, x.paths.len());
+    x.paths.iter().for_each(|path| {
+        write_header(buf, type_code::<N>(LINESTRING));
+        write_coords(buf, path);
+    });
+}
+
+fn write_polygon<const N: usize, T: Copy + Into<f64>>(buf: &mut Vec<u8>, x: &EsriPolygon<N, T>) {
+    let polygons = classify_rings(&x.rings);
+    write_header(buf, type_code::<N>(MULTIPOLYGON));
+    write_u32(buf, polygons.len());
+    polygons.iter().for_each(|rings| {
+        write_header(buf, type_code::<N>(POLYGON));
+        write_u32(buf, rings.len());
+        rings.iter().for_each(|i| write_coords(buf, &x.rings[*i]));
+    });
+}
+
+fn write_multipatch<const N: usize, T: Copy + Into<f64>>(
+    buf: &mut Vec<u8>,
+    x: &EsriMultipatch<N, T>,
+) {
+    let faces = x.faces();
+    write_header(buf, type_code::<N>(MULTIPOLYGON));
+    write_u32(buf, faces.len());
+    faces.iter().for_each(|rings| {
+        write_header(buf, type_code::<N>(POLYGON));
+        write_u32(buf, rings.len());
+        rings.iter().for_each(|ring| write_coords(buf, ring));
+    });
+}
+
+fn write_envelope<T: Copy + Into<f64>>(buf: &mut Vec<u8>, x: &EsriEnvelope<T>) {
+    let (xmin, ymin) = (x.xmin.into(), x.ymin.into());
+    let (xmax, ymax) = (x.xmax.into(), x.ymax.into());
+    write_header(buf, POLYGON);
+    write_u32(buf, 1);
+    write_u32(buf, 5);
+    for (x, y) in [
+        (xmin, ymin),
+        (xmin, ymax),
+        (xmax, ymax),
+        (xmax, ymin),
+        (xmin, ymin),
+    ] {
+        write_f64(buf, x);
+        write_f64(buf, y);
+    }
+}
+
+/// Appends the WKB encoding of `x` to `buf`
+pub(crate) fn write_geometry<const N: usize, T: Copy + Into<f64>>(
+    buf: &mut Vec<u8>,
+    x: &EsriGeometry<N, T>,
+) {
+    match x {
+        EsriGeometry::Point(g) => write_point::<N, T>(buf, g),
+        EsriGeometry::MultiPoint(g) => write_multipoint(buf, g),
+        EsriGeometry::Polyline(g) => write_polyline(buf, g),
+        EsriGeometry::Polygon(g) => write_polygon(buf, g),
+        EsriGeometry::Envelope(g) => write_envelope(buf, g),
+        EsriGeometry::CurvePolyline(g) => write_polyline(buf, &g.linearize()),
+        EsriGeometry::CurvePolygon(g) => write_polygon(buf, &g.linearize()),
+        EsriGeometry::Multipatch(g) => write_multipatch(buf, g),
+    }
+}
+
+/// Encode `x` as little-endian ISO WKB
+pub fn to_wkb<const N: usize, T: Copy + Into<f64>>(x: &EsriGeometry<N, T>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_geometry(&mut buf, x);
+    buf
+}
+
+/// Decode ISO WKB or EWKB into an Esri geometry
+///
+/// LineStrings and MultiLineStrings become an `EsriPolyline`, and Polygons and
+/// MultiPolygons become an `EsriPolygon`. The SRID of EWKB is ignored.
+pub fn from_wkb<const N: usize>(bytes: &[u8]) -> Result<EsriGeometry<N>, WkbError> {
+    let mut reader = Reader {
+        bytes,
+        little_endian: true,
+    };
+    let geometry = reader.read_geometry::<N>()?;
+    match reader.bytes.len() {
+        0 => Ok(geometry),
+        n => Err(WkbError::TrailingBytes(n)),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl Reader<'_> {
+    fn take<const K: usize>(&mut self) -> Result<[u8; K], WkbError> {
+        if self.bytes.len() < K {
+            return Err(WkbError::UnexpectedEof);
+        }
+        let (head, rest) = self.bytes.split_at(K);
+        self.bytes = rest;
+        Ok(head.try_into().unwrap())
+    }
+
+    fn read_u32(&mut self) -> Result<u32, WkbError> {
+        let x = self.take::<4>()?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(x)
+        } else {
+            u32::from_be_bytes(x)
+        })
+    }
+
+    // the length of a list of items each at least `min_size` bytes long
+    fn read_len(&mut self, min_size: usize) -> Result<usize, WkbError> {
+        let n = self.read_u32()? as usize;
+        // reject lengths that cannot fit in the input before allocating
+        if n.saturating_mul(min_size) > self.bytes.len() {
+            return Err(WkbError::UnexpectedEof);
+        }
+        Ok(n)
+    }
+
+    fn read_f64(&mut self) -> Result<f64, WkbError> {
+        let x = self.take::<8>()?;
+        Ok(if self.little_endian {
+            f64::from_le_bytes(x)
+        } else {
+            f64::from_be_bytes(x)
+        })
+    }
+
+    // reads the byte order and type code, returning the 2D geometry type
+    fn read_header<const N: usize>(&mut self) -> Result<u32, WkbError> {
+        self.little_endian = match self.take::<1>()?[0] {
+            0 => false,
+            1 => true,
+            x => return Err(WkbError::InvalidByteOrder(x)),
+        };
+
+        let code = self.read_u32()?;
+        if code & EWKB_SRID != 0 {
+            self.read_u32()?;
+        }
+
+        let base = code & 0x0fff_ffff;
+        let (has_z, has_m) = match base / 1000 {
+            0 => (code & EWKB_Z != 0, code & EWKB_M != 0),
+            1 => (true, false),
+            2 => (false, true),
+            3 => (true, true),
+            _ => return Err(WkbError::InvalidType(code)),
+        };
+
+        if (has_z, has_m) != dimension_flags::<N>() {
+            return Err(WkbError::DimensionMismatch {
+                expected: N,
+                has_z,
+                has_m,
+            });
+        }
+
+        Ok(base % 1000)
+    }
+
+    fn read_coord<const N: usize>(&mut self) -> Result<EsriCoord<N>, WkbError> {
+        let mut coord = [0.0; N];
+        for x in coord.iter_mut() {
+            *x = self.read_f64()?;
+        }
+        Ok(EsriCoord(coord))
+    }
+
+    fn read_coords<const N: usize>(&mut self) -> Result<EsriLineString<N>, WkbError> {
+        let n = self.read_len(N * 8)?;
+        (0..n)
+            .map(|_| self.read_coord())
+            .collect::<Result<_, _>>()
+            .map(EsriLineString)
+    }
+
+    // reads the header of a member of a multi-part geometry
+    fn expect_header<const N: usize>(&mut self, geometry_type: u32) -> Result<(), WkbError> {
+        match self.read_header::<N>()? {
+            x if x == geometry_type => Ok(()),
+            x => Err(WkbError::InvalidType(x)),
+        }
+    }
+
+    // exterior rings are wound clockwise and interior rings counter-clockwise
+    fn read_rings<const N: usize>(
+        &mut self,
+        rings: &mut Vec<EsriLineString<N>>,
+    ) -> Result<(), WkbError> {
+        let n = self.read_len(4)?;
+        for i in 0..n {
+            let mut ring = self.read_coords()?;
+            if is_clockwise(&ring) != (i == 0) {
+                ring.0.reverse();
+            }
+            rings.push(ring);
+        }
+        Ok(())
+    }
+
+    fn read_geometry<const N: usize>(&mut self) -> Result<EsriGeometry<N>, WkbError> {
+        let (has_z, has_m) = dimension_flags::<N>();
+        let (has_z_flag, has_m_flag) = (has_z.then_some(true), has_m.then_some(true));
+        let mut paths = Vec::new();
+
+        match self.read_header::<N>()? {
+            POINT => {
+                let coord = self.read_coord::<N>()?;
+                if coord.0[0].is_nan() {
+                    return Err(WkbError::EmptyPoint);
+                }
+                return Ok(EsriGeometry::Point(EsriPoint {
+                    x: coord.0[0],
+                    y: coord.0[1],
+                    z: has_z.then(|| coord.0[2]),
+                    m: has_m.then(|| coord.0[N - 1]),
+                    spatialReference: None,
+                }));
+            }
+            MULTIPOINT => {
+                let n = self.read_len(5 + N * 8)?;
+                let mut points = Vec::with_capacity(n);
+                for _ in 0..n {
+                    self.expect_header::<N>(POINT)?;
+                    points.push(self.read_coord()?);
+                }
+                return Ok(EsriGeometry::MultiPoint(EsriMultiPoint {
+                    hasZ: has_z_flag,
+                    hasM: has_m_flag,
+                    points,
+                    spatialReference: None,
+                }));
+            }
+            LINESTRING => paths.push(self.read_coords()?),
+            MULTILINESTRING => {
+                let n = self.read_len(9)?;
+                for _ in 0..n {
+                    self.expect_header::<N>(LINESTRING)?;
+                    paths.push(self.read_coords()?);
+                }
+            }
+            POLYGON => {
+                self.read_rings(&mut paths)?;
+                return Ok(polygon(paths));
+            }
+            MULTIPOLYGON => {
+                let n = self.read_len(9)?;
+                for _ in 0..n {
+                    self.expect_header::<N>(POLYGON)?;
+                    self.read_rings(&mut paths)?;
+                }
+                return Ok(polygon(paths));
+            }
+            GEOMETRYCOLLECTION => return Err(WkbError::GeometryCollection),
+            x => return Err(WkbError::InvalidType(x)),
+        }
+
+        Ok(EsriGeometry::Polyline(EsriPolyline {
+            hasZ: has_z_flag,
+            hasM: has_m_flag,
+            paths,
+            spatialReference: None,
+        }))
+    }
+}
+
+fn polygon<const N: usize>(rings: Vec<EsriLineString<N>>) -> EsriGeometry<N> {
+    let (has_z, has_m) = dimension_flags::<N>();
+    EsriGeometry::Polygon(EsriPolygon {
+        hasZ: has_z.then_some(true),
+        hasM: has_m.then_some(true),
+        rings,
+        spatialReference: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wkb_round_trip() {
+        let json = r#"{"hasZ": true, "hasM": true, "rings": [
+            [[0, 0, 1, 2], [0, 10, 1, 2], [10, 10, 1, 2], [10, 0, 1, 2], [0, 0, 1, 2]],
+            [[2, 2, 1, 2], [4, 2, 1, 2], [4, 4, 1, 2], [2, 4, 1, 2], [2, 2, 1, 2]]
+        ]}"#;
+        let geometry: EsriGeometry<4> = serde_json::from_str(json).unwrap();
+        let bytes = to_wkb(&geometry);
+
+        let polygon = from_wkb::<4>(&bytes).unwrap().as_polygon().unwrap();
+        assert_eq!(polygon.rings.len(), 2);
+        assert_eq!(polygon.rings[1].0[1].0, [4.0, 2.0, 1.0, 2.0]);
+        assert!(matches!(
+            from_wkb::<3>(&bytes),
+            Err(WkbError::DimensionMismatch { expected: 3, .. })
+        ));
+        assert_eq!(
+            from_wkb::<4>(&bytes[..bytes.len() - 1]).unwrap_err(),
+            WkbError::UnexpectedEof
+        );
+
+        // big-endian EWKB POINT Z with an SRID of 4326
+        let mut ewkb = vec![0];
+        ewkb.extend((POINT | EWKB_Z | EWKB_SRID).to_be_bytes());
+        ewkb.extend(4326_u32.to_be_bytes());
+        for x in [1.0_f64, 2.0, 3.0] {
+            ewkb.extend(x.to_be_bytes());
+        }
+        let point = from_wkb::<3>(&ewkb).unwrap().as_point().unwrap();
+        assert_eq!((point.x, point.y, point.z), (1.0, 2.0, Some(3.0)));
+    }
+}
@@ -0,0 +1,353 @@
+//! Optional feature for converting between Esri JSON geometries and the
+//! [`wkt`](https://docs.rs/wkt) crate's typed geometry model.
+//!
+//! Unlike the `geo` feature, Z and M values are preserved. The const `N`
+//! determines which dimensions are read from and written to a `wkt::types::Coord`:
+//! `2` is XY, `3` is XYZ, and `4` is XYZM.
+//!
+//! Provides conversions for:
+//!
+//! - `EsriCoord` <-> `Coord`
+//! - `EsriPoint` <-> `Point`
+//! - `EsriMultiPoint` <-> `MultiPoint`
+//! - `EsriPolyline` <-> `MultiLineString` (and from `LineString`)
+//! - `EsriPolygon` <-> `Polygon` (and from `MultiPolygon`)
+//! - `EsriMultipatch` -> `MultiPolygon` with a polygon for each face
+//! - `EsriGeometry` <-> `Wkt`
+//!
+//! Rings are rewound when converting into an `EsriPolygon` so that exterior rings
+//! are clockwise and interior rings are counter-clockwise as required by Esri.
+//!
+//! WKT strings are written and parsed with the `Display` and `FromStr` implementations of `Wkt`.
+//!
+//! ```
+//! use serde_esri::geometry::EsriGeometry;
+//! use wkt::Wkt;
+//!
+//! let json = r#"{"x": 1, "y": 2, "z": 3}"#;
+//! let point: EsriGeometry<3> = serde_json::from_str(json).unwrap();
+//!
+//! let wkt = Wkt::try_from(&point).unwrap().to_string();
+//! assert_eq!(wkt, "POINT Z(1 2 3)");
+//!
+//! let parsed: Wkt<f64> = wkt.parse().unwrap();
+//! let point = EsriGeometry::<3>::try_from(&parsed).unwrap().as_point().unwrap();
+//! assert_eq!(point.z, Some(3.0));
+//! ```
+use crate::geometry::*;
+use crate::rings::is_clockwise;
+use wkt::types::{
+    Coord, Dimension, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon,
+};
+use wkt::{Wkt, WktNum};
+
+/// Errors that can occur when converting between `wkt` types and Esri geometries
+#[derive(Debug, Clone, PartialEq)]
+pub enum WktConversionError {
+    /// The coordinate does not have the dimensions required by `N`
+    DimensionMismatch { expected: usize, found: Dimension },
+    /// Empty points cannot be represented in Esri JSON
+    EmptyPoint,
+    /// Esri JSON has no geometry collection type
+    GeometryCollection,
+    /// Envelopes have no WKT representation
+    Envelope,
+    /// True curves must be densified first
+    Curve,
+}
+
+impl std::fmt::Display for WktConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DimensionMismatch { expected, found } => write!(
+                f,
+                "expected a coordinate with {expected} dimensions, found {found:?}"
+            ),
+            Self::EmptyPoint => write!(f, "empty points cannot be converted"),
+            Self::GeometryCollection => write!(f, "geometry collections cannot be converted"),
+            Self::Envelope => write!(f, "envelopes cannot be converted"),
+            Self::Curve => write!(f, "curves must be densified before they can be converted"),
+        }
+    }
+}
+
+impl std::error::Error for WktConversionError {}
+
+// the wkt Dimension that corresponds to N
+fn dimension<const N: usize>() -> Dimension {
+    match dimension_flags::<N>() {
+        (true, true) => Dimension::XYZM,
+        (true, false) => Dimension::XYZ,
+        _ => Dimension::XY,
+    }
+}
+
+// Esri -> wkt
+
+impl<const N: usize, T: WktNum> From<&EsriCoord<N, T>> for Coord<T> {
+    fn from(value: &EsriCoord<N, T>) -> Self {
+        let (has_z, has_m) = dimension_flags::<N>();
+        Coord {
+            x: value.0[0],
+            y: value.0[1],
+            z: has_z.then(|| value.0[2]),
+            m: has_m.then(|| value.0[3]),
+        }
+    }
+}
+
+impl<T: WktNum> From<&EsriPoint<T>> for Point<T> {
+    fn from(value: &EsriPoint<T>) -> Self {
+        Point::from_coord(Coord {
+            x: value.x,
+            y: value.y,
+            z: value.z,
+            m: value.m,
+        })
+    }
+}
+
+impl<const N: usize, T: WktNum> From<&EsriLineString<N, T>> for LineString<T> {
+    fn from(value: &EsriLineString<N, T>) -> Self {
+        LineString::new(value.iter().map(Coord::from).collect(), dimension::<N>())
+    }
+}
+
+impl<const N: usize, T: WktNum> From<&EsriMultiPoint<N, T>> for MultiPoint<T> {
+    fn from(value: &EsriMultiPoint<N, T>) -> Self {
+        let points = value
+            .iter()
+            .map(|c| Point::new(Some(c.into()), dimension::<N>()))
+            .collect();
+
+        MultiPoint::new(points, dimension::<N>())
+    }
+}
+
+impl<const N: usize, T: WktNum> From<&EsriPolyline<N, T>> for MultiLineString<T> {
+    fn from(value: &EsriPolyline<N, T>) -> Self {
+        let lines = value.paths.iter().map(LineString::from).collect();
+        MultiLineString::new(lines, dimension::<N>())
+    }
+}
+
+/// Note that rings are mapped 1:1. The first ring is the exterior ring
+/// and all subsequent rings are treated as interior rings.
+impl<const N: usize, T: WktNum> From<&EsriPolygon<N, T>> for Polygon<T> {
+    fn from(value: &EsriPolygon<N, T>) -> Self {
+        let rings = value.rings.iter().map(LineString::from).collect();
+        Polygon::new(rings, dimension::<N>())
+    }
+}
+
+impl<const N: usize, T: WktNum> From<&EsriMultipatch<N, T>> for MultiPolygon<T> {
+    fn from(value: &EsriMultipatch<N, T>) -> Self {
+        let polygons = value
+            .faces()
+            .iter()
+            .map(|face| {
+                Polygon::new(
+                    face.iter().map(LineString::from).collect(),
+                    dimension::<N>(),
+                )
+            })
+            .collect();
+        MultiPolygon::new(polygons, dimension::<N>())
+    }
+}
+
+impl<const N: usize, T: WktNum> TryFrom<&EsriGeometry<N, T>> for Wkt<T> {
+    type Error = WktConversionError;
+
+    fn try_from(value: &EsriGeometry<N, T>) -> Result<Self, Self::Error> {
+        match value {
+            EsriGeometry::Point(g) => Ok(Wkt::Point(g.into())),
+            EsriGeometry::MultiPoint(g) => Ok(Wkt::MultiPoint(g.into())),
+            EsriGeometry::Polyline(g) => Ok(Wkt::MultiLineString(g.into())),
+            EsriGeometry::Polygon(g) => Ok(Wkt::Polygon(g.into())),
+            EsriGeometry::Envelope(_) => Err(WktConversionError::Envelope),
+            EsriGeometry::Multipatch(g) => Ok(Wkt::MultiPolygon(g.into())),
+            EsriGeometry::CurvePolyline(_) | EsriGeometry::CurvePolygon(_) => {
+                Err(WktConversionError::Curve)
+            }
+        }
+    }
+}
+
+// wkt -> Esri
+
+impl<const N: usize, T: WktNum> TryFrom<&Coord<T>> for EsriCoord<N, T> {
+    type Error = WktConversionError;
+
+    fn try_from(value: &Coord<T>) -> Result<Self, Self::Error> {
+        let mismatch = WktConversionError::DimensionMismatch {
+            expected: N,
+            found: value.dimension(),
+        };
+
+        let mut coord = [value.x; N];
+        coord[1] = value.y;
+
+        let (has_z, has_m) = dimension_flags::<N>();
+        if has_z {
+            coord[2] = value.z.ok_or(mismatch.clone())?;
+        }
+        if has_m {
+            coord[3] = value.m.ok_or(mismatch)?;
+        }
+
+        Ok(EsriCoord(coord))
+    }
+}
+
+impl<T: WktNum> TryFrom<&Point<T>> for EsriPoint<T> {
+    type Error = WktConversionError;
+
+    fn try_from(value: &Point<T>) -> Result<Self, Self::Error> {
+        let coord = value.coord().ok_or(WktConversionError::EmptyPoint)?;
+        Ok(EsriPoint {
+            x: coord.x,
+            y: coord.y,
+            z: coord.z,
+            m: coord.m,
+            spatialReference: None,
+        })
+    }
+}
+
+impl<const N: usize, T: WktNum> TryFrom<&LineString<T>> for EsriLineString<N, T> {
+    type Error = WktConversionError;
+
+    fn try_from(value: &LineString<T>) -> Result<Self, Self::Error> {
+        let coords = value
+            .coords()
+            .iter()
+            .map(EsriCoord::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(EsriLineString(coords))
+    }
+}
+
+impl<const N: usize, T: WktNum> TryFrom<&MultiPoint<T>> for EsriMultiPoint<N, T> {
+    type Error = WktConversionError;
+
+    fn try_from(value: &MultiPoint<T>) -> Result<Self, Self::Error> {
+        let points = value
+            .points()
+            .iter()
+            .map(|p| {
+                p.coord()
+                    .ok_or(WktConversionError::EmptyPoint)
+                    .and_then(EsriCoord::try_from)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (has_z, has_m) = dimension_flags::<N>();
+        Ok(EsriMultiPoint {
+            hasZ: has_z.then_some(true),
+            hasM: has_m.then_some(true),
+            points,
+            spatialReference: None,
+        })
+    }
+}
+
+impl<const N: usize, T: WktNum> TryFrom<&LineString<T>> for EsriPolyline<N, T> {
+    type Error = WktConversionError;
+
+    fn try_from(value: &LineString<T>) -> Result<Self, Self::Error> {
+        Ok(polyline_from_paths(vec![value.try_into()?]))
+    }
+}
+
+impl<const N: usize, T: WktNum> TryFrom<&MultiLineString<T>> for EsriPolyline<N, T> {
+    type Error = WktConversionError;
+
+    fn try_from(value: &MultiLineString<T>) -> Result<Self, Self::Error> {
+        let paths = value
+            .line_strings()
+            .iter()
+            .map(EsriLineString::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(polyline_from_paths(paths))
+    }
+}
+
+impl<const N: usize, T: WktNum + Into<f64>> TryFrom<&Polygon<T>> for EsriPolygon<N, T> {
+    type Error = WktConversionError;
+
+    fn try_from(value: &Polygon<T>) -> Result<Self, Self::Error> {
+        Ok(polygon_from_rings(esri_rings(value)?))
+    }
+}
+
+impl<const N: usize, T: WktNum + Into<f64>> TryFrom<&MultiPolygon<T>> for EsriPolygon<N, T> {
+    type Error = WktConversionError;
+
+    fn try_from(value: &MultiPolygon<T>) -> Result<Self, Self::Error> {
+        let mut rings = Vec::new();
+        for polygon in value.polygons() {
+            rings.extend(esri_rings(polygon)?);
+        }
+
+        Ok(polygon_from_rings(rings))
+    }
+}
+
+impl<const N: usize, T: WktNum + Into<f64>> TryFrom<&Wkt<T>> for EsriGeometry<N, T> {
+    type Error = WktConversionError;
+
+    fn try_from(value: &Wkt<T>) -> Result<Self, Self::Error> {
+        match value {
+            Wkt::Point(g) => Ok(EsriGeometry::Point(g.try_into()?)),
+            Wkt::MultiPoint(g) => Ok(EsriGeometry::MultiPoint(g.try_into()?)),
+            Wkt::LineString(g) => Ok(EsriGeometry::Polyline(g.try_into()?)),
+            Wkt::MultiLineString(g) => Ok(EsriGeometry::Polyline(g.try_into()?)),
+            Wkt::Polygon(g) => Ok(EsriGeometry::Polygon(g.try_into()?)),
+            Wkt::MultiPolygon(g) => Ok(EsriGeometry::Polygon(g.try_into()?)),
+            Wkt::GeometryCollection(_) => Err(WktConversionError::GeometryCollection),
+        }
+    }
+}
+
+fn polyline_from_paths<const N: usize, T>(paths: Vec<EsriLineString<N, T>>) -> EsriPolyline<N, T> {
+    let (has_z, has_m) = dimension_flags::<N>();
+    EsriPolyline {
+        hasZ: has_z.then_some(true),
+        hasM: has_m.then_some(true),
+        paths,
+        spatialReference: None,
+    }
+}
+
+fn polygon_from_rings<const N: usize, T>(rings: Vec<EsriLineString<N, T>>) -> EsriPolygon<N, T> {
+    let (has_z, has_m) = dimension_flags::<N>();
+    EsriPolygon {
+        hasZ: has_z.then_some(true),
+        hasM: has_m.then_some(true),
+        rings,
+        spatialReference: None,
+    }
+}
+
+// converts the rings of a polygon winding the exterior clockwise
+// and the interiors counter-clockwise
+fn esri_rings<const N: usize, T: WktNum + Into<f64>>(
+    polygon: &Polygon<T>,
+) -> Result<Vec<EsriLineString<N, T>>, WktConversionError> {
+    polygon
+        .rings()
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            let mut ring = EsriLineString::<N, T>::try_from(ring)?;
+            // exterior rings must be clockwise, interior rings counter-clockwise
+            if is_clockwise(&ring) != (i == 0) {
+                ring.0.reverse();
+            }
+            Ok(ring)
+        })
+        .collect()
+}